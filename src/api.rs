@@ -0,0 +1,196 @@
+//! Wrangler as a library, for programs that publish workers and manage KV namespaces without
+//! running `wrangler`.
+//!
+//! What wrangler would print goes to the terminal unless it's sent to a [`Sink`] with
+//! [`set_sink`]. Its HTTP clients can be set up with [`http::set_client_config`],
+//! [`http::set_api_base`] and [`http::set_timeouts`]. All four are settings of the whole
+//! process, and only the first call of each has an effect: later calls are ignored without
+//! an error, so set them up once, before the first operation.
+//!
+//! Some of wrangler doesn't follow them yet:
+//! - the cloudflare-rs clients, which the KV namespace operations use, ignore
+//!   `set_client_config`
+//! - prompts, and the output still printed with `println!`, go to the terminal rather than
+//!   to the sink
+//!
+//! `publish` and `preview` change the working directory of the process to the one of the
+//! configuration file while they run, so don't run them alongside anything else that
+//! depends on it.
+//! [`http::set_client_config`]: crate::http::set_client_config
+//! [`http::set_api_base`]: crate::http::set_api_base
+//! [`http::set_timeouts`]: crate::http::set_timeouts
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use url::Url;
+
+pub use crate::commands::publish::PublishOutput;
+pub use crate::kv::bulk::KeyValuePair;
+pub use crate::settings::global_user::GlobalUser;
+pub use crate::terminal::message::{set_sink, Sink, Stream};
+pub use cloudflare::endpoints::workerskv::WorkersKvNamespace;
+
+use crate::build_target;
+use crate::cli::publish::{check_freeze, record_deployment};
+use crate::commands::{self, kv};
+use crate::http;
+use crate::kv::{bulk, namespace};
+use crate::preview::{self, HttpMethod, RequestPayload};
+use crate::settings::toml::{Manifest, Target};
+use crate::terminal::message::Output;
+
+/// How `Wrangler::publish` publishes, like the flags of `wrangler publish` of the same names
+#[derive(Clone, Debug, Default)]
+pub struct PublishOptions {
+    pub environment: Option<String>,
+    /// A note kept with the deployment in its history
+    pub message: Option<String>,
+    /// Why to publish although publishing is frozen
+    pub override_freeze: Option<String>,
+    pub no_cache: bool,
+    pub if_changed: bool,
+    /// Publish only the routes, schedules and bindings, not the script
+    pub config_only: bool,
+    /// Publish to an environment of `protected_environments`
+    pub yes_i_know: bool,
+}
+
+/// The operations of wrangler, with the credentials of one user
+pub struct Wrangler {
+    user: GlobalUser,
+}
+
+impl Wrangler {
+    pub fn new(user: GlobalUser) -> Self {
+        Wrangler { user }
+    }
+
+    /// With the credentials `wrangler` would use: the ones in the environment, or else the
+    /// ones `wrangler login` or `wrangler config` saved
+    pub fn with_saved_user() -> Result<Self> {
+        Ok(Wrangler::new(GlobalUser::new()?))
+    }
+
+    /// Builds and publishes the worker of the configuration file at `config_path`
+    pub fn publish(&self, config_path: &Path, options: &PublishOptions) -> Result<PublishOutput> {
+        let environment = options.environment.as_deref();
+        in_project_dir(config_path, |manifest| {
+            let freeze_override =
+                check_freeze(manifest, environment, options.override_freeze.as_deref())?;
+            if manifest.is_protected(environment) && !options.yes_i_know {
+                anyhow::bail!(
+                    "{} is a protected environment, set yes_i_know to publish to it",
+                    environment.unwrap_or("the top level")
+                )
+            }
+            let mut target = manifest.get_target(environment, false)?;
+            let deployments = manifest.get_deployments(environment)?;
+            let output = if options.config_only {
+                commands::publish_config(&self.user, &target, deployments, Output::PlainText)?
+            } else {
+                commands::publish(
+                    &self.user,
+                    &mut target,
+                    deployments,
+                    Output::PlainText,
+                    false,
+                    options.no_cache,
+                    options.if_changed,
+                )?
+            };
+            record_deployment(
+                &self.user,
+                &target,
+                options.message.clone(),
+                freeze_override,
+            );
+            Ok(output)
+        })
+    }
+
+    /// Builds the worker of the configuration file at `config_path` and uploads it to the
+    /// preview service. Returns the link to its preview of `url`.
+    pub fn preview(
+        &self,
+        config_path: &Path,
+        environment: Option<&str>,
+        url: Url,
+    ) -> Result<String> {
+        in_project_dir(config_path, |manifest| {
+            let mut target = manifest.get_target(environment, true)?;
+            build_target(&target)?;
+            let sites_preview = target.site.is_some();
+            let script_id = preview::upload(&mut target, Some(&self.user), sites_preview, false)?;
+            let payload = RequestPayload::create(HttpMethod::Get, url, None, Vec::new());
+            Ok(preview::share_url(&script_id, &payload.browser_url))
+        })
+    }
+
+    pub fn namespaces(&self, account_id: &str) -> Result<Vec<WorkersKvNamespace>> {
+        namespace::list(&http::cf_v4_client(&self.user)?, account_id)
+    }
+
+    pub fn create_namespace(&self, account_id: &str, title: &str) -> Result<WorkersKvNamespace> {
+        match namespace::create(&http::cf_v4_client(&self.user)?, account_id, title) {
+            Ok(success) => Ok(success.result),
+            Err(e) => Err(kv::api_error(e)),
+        }
+    }
+
+    pub fn delete_namespace(&self, account_id: &str, namespace_id: &str) -> Result<()> {
+        match namespace::delete(http::cf_v4_client(&self.user)?, account_id, namespace_id) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(kv::api_error(e)),
+        }
+    }
+
+    /// Writes `pairs` to the namespace in batches, like `wrangler kv:bulk put`
+    pub fn put(
+        &self,
+        account_id: &str,
+        namespace_id: &str,
+        pairs: Vec<KeyValuePair>,
+    ) -> Result<()> {
+        bulk::put(&account(account_id), &self.user, namespace_id, pairs, &None)
+    }
+
+    /// Deletes `keys` from the namespace in batches, like `wrangler kv:bulk delete`
+    pub fn delete(&self, account_id: &str, namespace_id: &str, keys: Vec<String>) -> Result<()> {
+        bulk::delete(&account(account_id), &self.user, namespace_id, keys, &None)
+    }
+}
+
+// The bulk operations take a target only for its account
+fn account(account_id: &str) -> Target {
+    Target {
+        account_id: Some(account_id.to_string()).into(),
+        ..Default::default()
+    }
+}
+
+// Runs `f` in the directory of the configuration file at `config_path`, which the paths in
+// it are relative to, like `wrangler` does
+fn in_project_dir<T>(config_path: &Path, f: impl FnOnce(&Manifest) -> Result<T>) -> Result<T> {
+    let manifest = Manifest::new(config_path)?;
+    let _restore = RestoreDir(env::current_dir()?);
+    if let Some(dir) = config_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        env::set_current_dir(dir)?;
+    }
+    f(&manifest)
+}
+
+// Changes back to the directory the program was in, even when `f` fails or panics
+struct RestoreDir(PathBuf);
+
+impl Drop for RestoreDir {
+    fn drop(&mut self) {
+        if let Err(e) = env::set_current_dir(&self.0) {
+            log::warn!("could not change back to {}: {}", self.0.display(), e);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use ignore::WalkBuilder;
+use sha2::{Digest, Sha256};
+
+use crate::commands::build::RustArtifacts;
+use crate::settings::toml::{self, Target, TargetType, UploadFormat};
+
+/// Where what publishing builds and uploads is cached, relative to the project
+pub const CACHE_DIR: &str = ".wrangler/cache";
+
+// Directories that are never read by a build, or are too large to hash on every publish.
+// The cache itself is in `.wrangler`.
+const SKIPPED_DIRS: &[&str] = &[".git", ".wrangler", "node_modules", "target"];
+
+/// A copy of what a build of the project wrote, kept in `.wrangler/cache/build/<key>`
+/// where the key is a hash of the configuration and of every file the build could read.
+/// Publishing restores it instead of building again when none of them changed.
+pub struct BuildCache {
+    key: String,
+    root: PathBuf,
+    /// The files and directories the build writes, relative to `root`
+    outputs: Vec<PathBuf>,
+}
+
+impl BuildCache {
+    /// The cache of the build of `target`, or `None` when there's nothing to build or it's
+    /// not known where the build writes to.
+    pub fn new(target: &Target) -> Result<Option<Self>> {
+        let root = env::current_dir()?;
+        let outputs = match outputs(target)? {
+            Some(outputs) => outputs,
+            None => return Ok(None),
+        };
+        // builds writing outside of the project can't be restored from its cache
+        let outputs = match outputs
+            .iter()
+            .map(|output| relative_to(&root, output))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(outputs) => outputs,
+            None => return Ok(None),
+        };
+
+        let mut hasher = Sha256::new();
+        hasher.update(target.name.as_bytes());
+        hasher.update(format!("{:?}", target.target_type).as_bytes());
+        if let Some(config_path) = toml::config_path() {
+            hasher.update(fs::read(config_path).unwrap_or_default());
+        }
+        let mut skipped: Vec<PathBuf> = outputs.iter().map(|output| root.join(output)).collect();
+        if let Some(site) = &target.site {
+            // the assets of a site are uploaded as they are
+            skipped.push(root.join(&site.bucket));
+        }
+        hash_files(&mut hasher, &root, &skipped)?;
+
+        Ok(Some(BuildCache {
+            key: hex::encode(hasher.finalize()),
+            root,
+            outputs,
+        }))
+    }
+
+    /// Puts the outputs of a build of the same inputs back in place, returning whether
+    /// there was one.
+    pub fn restore(&self) -> Result<bool> {
+        let entry = self.entry();
+        if !entry.is_dir() {
+            return Ok(false);
+        }
+        for output in &self.outputs {
+            let cached = entry.join(output);
+            if !cached.exists() {
+                return Ok(false);
+            }
+            copy(&cached, &self.root.join(output))?;
+        }
+        log::info!("restored the build from {}", entry.display());
+        Ok(true)
+    }
+
+    /// Keeps a copy of the outputs of the build that just ran. Only the latest build is kept.
+    pub fn store(&self) -> Result<()> {
+        let builds = self.root.join(CACHE_DIR).join("build");
+        if builds.exists() {
+            fs::remove_dir_all(&builds)?;
+        }
+        let entry = self.entry();
+        for output in &self.outputs {
+            let built = self.root.join(output);
+            if built.exists() {
+                copy(&built, &entry.join(output))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn entry(&self) -> PathBuf {
+        self.root.join(CACHE_DIR).join("build").join(&self.key)
+    }
+}
+
+// What the build of `target` writes, or `None` when it isn't known
+fn outputs(target: &Target) -> Result<Option<Vec<PathBuf>>> {
+    let outputs = match &target.target_type {
+        TargetType::JavaScript => match &target.build {
+            Some(config) if config.command.is_some() => match &config.upload {
+                UploadFormat::ServiceWorker { main: Some(main) } => vec![main.clone()],
+                UploadFormat::ServiceWorker { main: None } => return Ok(None),
+                UploadFormat::Modules { dir, .. } => vec![dir.clone()],
+            },
+            _ => return Ok(None),
+        },
+        TargetType::TypeScript => match target.build.as_ref().map(|config| &config.upload) {
+            Some(UploadFormat::ServiceWorker { main: Some(main) }) => vec![main.clone()],
+            Some(UploadFormat::Modules { dir, .. }) => vec![dir.clone()],
+            _ => return Ok(None),
+        },
+        TargetType::Rust => {
+            let artifacts = RustArtifacts::new("./")?;
+            let pkg = artifacts.wasm_path.parent().map(Path::to_path_buf);
+            let generated = artifacts.script_path.parent().map(Path::to_path_buf);
+            match (pkg, generated) {
+                (Some(pkg), Some(generated)) => vec![pkg, generated],
+                _ => return Ok(None),
+            }
+        }
+        TargetType::Webpack => vec![target.package_dir()?.join("worker")],
+    };
+    Ok(Some(outputs))
+}
+
+fn relative_to(root: &Path, path: &Path) -> Option<PathBuf> {
+    let path = path.strip_prefix("./").unwrap_or(path);
+    if path.is_absolute() {
+        path.strip_prefix(root).ok().map(Path::to_path_buf)
+    } else if path.components().any(|c| c == Component::ParentDir) {
+        None
+    } else {
+        Some(path.to_path_buf())
+    }
+}
+
+// Hashes the path and contents of every file under `root` in a stable order, leaving out
+// git-ignored files and everything under `skipped`. Hidden files like `.babelrc` are
+// hashed, since builds read them.
+pub(crate) fn hash_files(hasher: &mut Sha256, root: &Path, skipped: &[PathBuf]) -> Result<()> {
+    let skipped = skipped.to_vec();
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            let is_skipped_dir = entry.file_type().map_or(false, |t| t.is_dir())
+                && entry
+                    .file_name()
+                    .to_str()
+                    .map_or(false, |name| SKIPPED_DIRS.contains(&name));
+            !is_skipped_dir && !skipped.iter().any(|path| entry.path() == path)
+        })
+        .build();
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().map_or(false, |t| t.is_file()) {
+            continue;
+        }
+        let path = entry.path();
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update(b"\0");
+        hasher.update(fs::read(path)?);
+        hasher.update(b"\0");
+    }
+    Ok(())
+}
+
+pub(crate) fn copy(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        if to.exists() {
+            fs::remove_dir_all(to)?;
+        }
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(root: &Path, skipped: &[PathBuf]) -> String {
+        let mut hasher = Sha256::new();
+        hash_files(&mut hasher, root, skipped).unwrap();
+        hex::encode(hasher.finalize())
+    }
+
+    #[test]
+    fn it_hashes_inputs_but_not_outputs() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let root = tmpdir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("dist")).unwrap();
+        fs::write(root.join("src/index.js"), "export default {}").unwrap();
+        let skipped = vec![root.join("dist")];
+
+        let before = hash(root, &skipped);
+        fs::write(root.join("dist/index.js"), "built").unwrap();
+        assert_eq!(hash(root, &skipped), before);
+
+        fs::write(root.join("src/index.js"), "export default { fetch }").unwrap();
+        assert_ne!(hash(root, &skipped), before);
+    }
+
+    #[test]
+    fn it_hashes_hidden_files_but_not_the_cache() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let root = tmpdir.path();
+        fs::create_dir_all(root.join(".wrangler/cache")).unwrap();
+        fs::write(root.join(".babelrc"), "{}").unwrap();
+
+        let before = hash(root, &[]);
+        fs::write(root.join(".wrangler/cache/key"), "built").unwrap();
+        assert_eq!(hash(root, &[]), before);
+
+        fs::write(root.join(".babelrc"), "{ \"presets\": [] }").unwrap();
+        assert_ne!(hash(root, &[]), before);
+    }
+
+    #[test]
+    fn it_only_caches_outputs_inside_of_the_project() {
+        let root = Path::new("/project");
+        assert_eq!(
+            relative_to(root, Path::new("/project/dist")),
+            Some(PathBuf::from("dist"))
+        );
+        assert_eq!(
+            relative_to(root, Path::new("./dist/worker.js")),
+            Some(PathBuf::from("dist/worker.js"))
+        );
+        assert_eq!(relative_to(root, Path::new("/elsewhere/dist")), None);
+        assert_eq!(relative_to(root, Path::new("../dist")), None);
+    }
+
+    #[test]
+    fn it_copies_directories() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let from = tmpdir.path().join("dist");
+        fs::create_dir_all(from.join("nested")).unwrap();
+        fs::write(from.join("nested/module.wasm"), "\0asm").unwrap();
+
+        let to = tmpdir.path().join("cache/dist");
+        copy(&from, &to).unwrap();
+        assert_eq!(fs::read(to.join("nested/module.wasm")).unwrap(), b"\0asm");
+    }
+}
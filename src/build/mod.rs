@@ -1,17 +1,128 @@
+pub mod cache;
+mod unused;
+
+pub use unused::warn_about_unused_config;
+
+use crate::commands;
+use crate::error::WranglerError;
 use crate::settings::toml::{Target, TargetType};
-use crate::terminal::message::{Message, StdErr};
-use crate::terminal::styles;
+use crate::terminal::message::{self, Message, StdErr};
 use crate::wranglerjs;
-use crate::{commands, install};
 
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::mpsc::Sender;
 
 use anyhow::{anyhow, Result};
 
-// Internal build logic, called by both `build` and `publish`
-// TODO: return a struct containing optional build info and construct output at command layer
+/// What a `Build` reports while it runs
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildEvent {
+    Started,
+    /// Something about the built script that may not work as configured
+    Warning(String),
+    Finished {
+        message: String,
+        /// The built script, when the build produces one
+        artifact: Option<Artifact>,
+    },
+    Failed(String),
+}
+
+/// The script a build produced
+#[derive(Clone, Debug, PartialEq)]
+pub struct Artifact {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+impl Artifact {
+    fn of(target: &Target) -> Option<Self> {
+        let path = unused::script_path(target).ok()??;
+        let size = fs::metadata(&path).ok()?.len();
+        Some(Artifact { path, size })
+    }
+}
+
+/// Builds a target for `build`, `publish`, `preview` and `dev`. Its events go to the
+/// channel given to `with_events`; without one, warnings are printed.
+pub struct Build<'a> {
+    target: &'a Target,
+    routes: Option<&'a [String]>,
+    events: Option<Sender<BuildEvent>>,
+    cache: bool,
+}
+
+impl<'a> Build<'a> {
+    pub fn new(target: &'a Target) -> Self {
+        Build {
+            target,
+            routes: None,
+            events: None,
+            cache: true,
+        }
+    }
+
+    /// Reports the configuration the built script doesn't use, `routes` being where it
+    /// will be published
+    pub fn with_routes(mut self, routes: &'a [String]) -> Self {
+        self.routes = Some(routes);
+        self
+    }
+
+    pub fn with_events(mut self, events: Sender<BuildEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Always compiles, instead of reusing what was compiled from the same sources, like the
+    /// WebAssembly of a `type = "rust"` project
+    pub fn without_cache(mut self) -> Self {
+        self.cache = false;
+        self
+    }
+
+    /// Runs the build, returning the message to report it with
+    pub fn run(&self) -> Result<String> {
+        self.emit(BuildEvent::Started);
+        let message = match run_build(self.target, self.cache) {
+            Ok(message) => message,
+            Err(e) => {
+                self.emit(BuildEvent::Failed(e.to_string()));
+                return Err(WranglerError::tag(e, WranglerError::Build));
+            }
+        };
+
+        if let Some(routes) = self.routes {
+            for warning in unused::unused_config_warnings(self.target, routes) {
+                match self.events {
+                    Some(_) => self.emit(BuildEvent::Warning(warning)),
+                    None => StdErr::warn(&warning),
+                }
+            }
+        }
+        self.emit(BuildEvent::Finished {
+            message: message.clone(),
+            artifact: Artifact::of(self.target),
+        });
+        Ok(message)
+    }
+
+    // nobody listening anymore doesn't stop the build
+    fn emit(&self, event: BuildEvent) {
+        if let Some(events) = &self.events {
+            let _ = events.send(event);
+        }
+    }
+}
+
+/// Builds `target`, returning the message to report the build with
 pub fn build_target(target: &Target) -> Result<String> {
+    Build::new(target).run()
+}
+
+fn run_build(target: &Target, cache: bool) -> Result<String> {
     let target_type = &target.target_type;
     match target_type {
         TargetType::JavaScript => match &target.build {
@@ -19,39 +130,24 @@ pub fn build_target(target: &Target) -> Result<String> {
                 let msg = "Basic JavaScript project found. Skipping unnecessary build!".to_string();
                 Ok(msg)
             }
-            Some(config) => {
-                if let Some((cmd_str, mut cmd)) = config.build_command() {
-                    StdErr::working(format!("Running {}", cmd_str).as_ref());
-                    let build_result = cmd.spawn()?.wait()?;
-                    if build_result.success() {
-                        Ok(String::from("Build completed successfully!"))
-                    } else if let Some(code) = build_result.code() {
-                        Err(anyhow!("Build failed! Status Code: {}", code))
-                    } else {
-                        Err(anyhow!("Build failed."))
-                    }
-                } else {
-                    Ok(String::from("No build command specified, skipping build."))
+            Some(config) => match config.build_command() {
+                Some((cmd_str, cmd)) => {
+                    run_build_command(cmd_str, cmd)?;
+                    Ok(String::from("Build completed successfully!"))
                 }
-            }
+                None => Ok(String::from("No build command specified, skipping build.")),
+            },
         },
+        TargetType::TypeScript => {
+            commands::build::typescript(target)?;
+            // a build command of its own bundles what was compiled
+            if let Some((cmd_str, cmd)) = target.build.as_ref().and_then(|c| c.build_command()) {
+                run_build_command(cmd_str, cmd)?;
+            }
+            Ok(String::from("Compiled TypeScript successfully!"))
+        }
         TargetType::Rust => {
-            let _ = which::which("rustc").map_err(|e| {
-                anyhow!(
-                    "'rustc' not found: {}. Installation documentation can be found here: {}",
-                    e,
-                    styles::url("https://www.rust-lang.org/tools/install")
-                )
-            })?;
-
-            let binary_path = install::install_wasm_pack()?;
-            let args = ["build", "--target", "no-modules"];
-
-            let command = command(&args, &binary_path);
-            let command_name = format!("{:?}", command);
-
-            StdErr::working("Compiling your project to WebAssembly...");
-            commands::run(command, &command_name)?;
+            commands::build::rust(cache)?;
             let msg = "Build succeeded".to_string();
             Ok(msg)
         }
@@ -68,6 +164,33 @@ pub fn build_target(target: &Target) -> Result<String> {
     }
 }
 
+fn run_build_command(cmd_str: &str, mut cmd: Command) -> Result<()> {
+    StdErr::working(format!("Running {}", cmd_str).as_ref());
+    // when quiet, stdout is only for results, so the build's output is kept for when it fails
+    let (build_result, build_output) = if message::is_quiet() {
+        let output = cmd.output()?;
+        let text = format!(
+            "\n{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        (output.status, text.trim_end().to_string())
+    } else {
+        (cmd.spawn()?.wait()?, String::new())
+    };
+    if build_result.success() {
+        Ok(())
+    } else if let Some(code) = build_result.code() {
+        Err(anyhow!(
+            "Build failed! Status Code: {}{}",
+            code,
+            build_output
+        ))
+    } else {
+        Err(anyhow!("Build failed.{}", build_output))
+    }
+}
+
 pub fn command(args: &[&str], binary_path: &Path) -> Command {
     let mut c = if cfg!(target_os = "windows") {
         let mut c = Command::new("cmd");
@@ -81,3 +204,35 @@ pub fn command(args: &[&str], binary_path: &Path) -> Command {
     c.args(args);
     c
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::Manifest;
+    use std::sync::mpsc;
+
+    #[test]
+    fn it_reports_the_build_as_events() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+"#,
+        )
+        .unwrap();
+        let target = manifest.get_target(None, false).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let message = Build::new(&target).with_events(tx).run().unwrap();
+        let events: Vec<BuildEvent> = rx.try_iter().collect();
+        assert_eq!(events.first(), Some(&BuildEvent::Started));
+        match events.last() {
+            Some(BuildEvent::Finished {
+                message: finished, ..
+            }) => assert_eq!(finished, &message),
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use regex::Regex;
+
+use crate::settings::toml::{Target, TargetType, UploadFormat};
+use crate::terminal::message::{Message, StdErr};
+use crate::upload::Package;
+use crate::wranglerjs;
+
+// Strings a bundle contains when it routes requests with one of the router libraries we
+// know the API of: itty-router, Hono and worktop all register handlers with `.get("/path")`
+const ROUTER_MARKERS: &[&str] = &["itty-router", "hono", "worktop", "Router("];
+
+/// Warns about the route patterns of the configuration file that the router of the built
+/// script never handles, and the `env.NAME` reads of the script that no binding provides.
+/// Both are best guesses from the text of the script, so nothing here fails the build.
+pub fn warn_about_unused_config(target: &Target, routes: &[String]) {
+    for warning in unused_config_warnings(target, routes) {
+        StdErr::warn(&warning);
+    }
+}
+
+pub(super) fn unused_config_warnings(target: &Target, routes: &[String]) -> Vec<String> {
+    let source = match script_source(target) {
+        Ok(Some(source)) => source,
+        Ok(None) => return Vec::new(),
+        Err(e) => {
+            log::info!("Skipping the unused configuration report: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut warnings = Vec::new();
+    for route in unreferenced_routes(&source, routes) {
+        warnings.push(format!(
+            "No handler of your router matches the route {}, requests to it may fall through to a 404",
+            route
+        ));
+    }
+    for name in undeclared_env_reads(&source, target) {
+        warnings.push(format!(
+            "Your script reads env.{} but it isn't in [vars] or any binding of your configuration file. Make sure it is set with `wrangler secret put {}`",
+            name, name
+        ));
+    }
+    warnings
+}
+
+fn script_source(target: &Target) -> Result<Option<String>> {
+    match script_path(target)? {
+        Some(script_path) => Ok(Some(fs::read_to_string(script_path)?)),
+        None => Ok(None),
+    }
+}
+
+/// The script a build of `target` produces, `None` for Rust builds, whose script is
+/// generated glue around the wasm module
+pub(super) fn script_path(target: &Target) -> Result<Option<PathBuf>> {
+    Ok(Some(match (&target.target_type, &target.build) {
+        (TargetType::Rust, _) => return Ok(None),
+        (TargetType::Webpack, _) => wranglerjs::Bundle::new(&target.package_dir()?).script_path(),
+        (TargetType::JavaScript | TargetType::TypeScript, Some(build)) => match &build.upload {
+            UploadFormat::ServiceWorker { main: Some(main) } => main.clone(),
+            UploadFormat::ServiceWorker { main: None } => package_main(target)?,
+            UploadFormat::Modules { main, dir, .. } => dir.join(main),
+        },
+        (TargetType::JavaScript | TargetType::TypeScript, None) => package_main(target)?,
+    }))
+}
+
+fn package_main(target: &Target) -> Result<PathBuf> {
+    let package_dir = target.package_dir()?;
+    let package = Package::new(&package_dir)?;
+    Ok(package_dir.join(package.main(&package_dir)?))
+}
+
+fn unreferenced_routes<'a>(source: &str, routes: &'a [String]) -> Vec<&'a str> {
+    if !ROUTER_MARKERS.iter().any(|marker| source.contains(marker)) {
+        return Vec::new();
+    }
+
+    let handler =
+        Regex::new(r#"\.(?:get|post|put|patch|delete|head|options|all)\(\s*["'`](/[^"'`]*)["'`]"#)
+            .unwrap();
+    // the part of a handler's path before its first parameter or wildcard
+    let handled: Vec<&str> = handler
+        .captures_iter(source)
+        .filter_map(|c| c.get(1))
+        .map(|path| {
+            path.as_str()
+                .split(&[':', '*'][..])
+                .next()
+                .unwrap_or_default()
+        })
+        .collect();
+    if handled.is_empty() {
+        return Vec::new();
+    }
+
+    routes
+        .iter()
+        .filter(|route| {
+            let route_path = route_path_prefix(route);
+            // a route on the whole hostname reaches every handler
+            route_path != "/"
+                && !handled
+                    .iter()
+                    .any(|path| path.starts_with(route_path) || route_path.starts_with(path))
+        })
+        .map(String::as_str)
+        .collect()
+}
+
+// "https://example.com/api/*" => "/api/"
+fn route_path_prefix(route: &str) -> &str {
+    let route = route
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    let path = match route.find('/') {
+        Some(start) => &route[start..],
+        None => "/",
+    };
+    path.split('*').next().unwrap_or_default()
+}
+
+// Only upper case names are considered, which is how bindings are conventionally named,
+// to leave out properties that are read off of other objects named `env`.
+fn undeclared_env_reads(source: &str, target: &Target) -> Vec<String> {
+    let mut declared: HashSet<&str> = target
+        .kv_namespaces
+        .iter()
+        .map(|kv| kv.binding.as_str())
+        .collect();
+    declared.extend(
+        target
+            .vars
+            .iter()
+            .flat_map(|v| v.keys())
+            .map(String::as_str),
+    );
+    declared.extend(
+        target
+            .text_blobs
+            .iter()
+            .flat_map(|b| b.keys())
+            .map(String::as_str),
+    );
+    declared.extend(
+        target
+            .wasm_modules
+            .iter()
+            .flat_map(|m| m.keys())
+            .map(String::as_str),
+    );
+    let classes = target
+        .durable_objects
+        .as_ref()
+        .and_then(|d| d.classes.as_ref());
+    declared.extend(classes.into_iter().flatten().map(|c| c.binding.as_str()));
+    if target.site.is_some() {
+        declared.insert("__STATIC_CONTENT");
+        declared.insert("__STATIC_CONTENT_MANIFEST");
+        declared.insert("__STATIC_CONTENT_OPTIONS");
+    }
+
+    let env_read =
+        Regex::new(r#"\benv(?:\.([A-Z_][A-Z0-9_]*)\b|\[["']([A-Z_][A-Z0-9_]*)["']\])"#).unwrap();
+    let mut undeclared = Vec::new();
+    for captures in env_read.captures_iter(source) {
+        let name = match captures.get(1).or_else(|| captures.get(2)) {
+            Some(name) => name.as_str(),
+            None => continue,
+        };
+        if !declared.contains(name) && !undeclared.iter().any(|n| n == name) {
+            undeclared.push(name.to_string());
+        }
+    }
+    undeclared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::Manifest;
+
+    const ROUTER_SCRIPT: &str = r#"
+import { Router } from "itty-router";
+const router = Router();
+router.get("/api/users/:id", (request, env) => fetch(env.API_ORIGIN + request.url));
+router.post("/api/login", (request, env) => env.SESSIONS.put(env["SESSION_KEY"], "1"));
+export default { fetch: (request, env) => router.handle(request, env, env.DEBUG) };
+"#;
+
+    #[test]
+    fn it_finds_routes_no_handler_matches() {
+        let routes: Vec<String> = vec![
+            "example.com/*".to_string(),
+            "example.com/api/*".to_string(),
+            "https://example.com/api/users/*".to_string(),
+            "example.com/admin*".to_string(),
+        ];
+
+        assert_eq!(
+            unreferenced_routes(ROUTER_SCRIPT, &routes),
+            vec!["example.com/admin*"]
+        );
+        // without a router, there's no telling which paths the script handles
+        assert!(unreferenced_routes("addEventListener('fetch', f)", &routes).is_empty());
+    }
+
+    #[test]
+    fn it_finds_env_reads_without_a_binding() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+name = "router"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+kv_namespaces = [{ binding = "SESSIONS", id = "abc" }]
+vars = { API_ORIGIN = "https://api.example.com" }
+"#,
+        )
+        .unwrap();
+        let target = manifest.get_target(None, false).unwrap();
+
+        assert_eq!(
+            undeclared_env_reads(ROUTER_SCRIPT, &target),
+            vec!["SESSION_KEY", "DEBUG"]
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use super::preview::read_body;
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use reqwest::Method;
+
+pub fn api(
+    method: &str,
+    path: &str,
+    headers: &[(String, String)],
+    data: Option<String>,
+    cli_params: &Cli,
+) -> Result<()> {
+    let method = Method::from_bytes(method.to_uppercase().as_bytes())
+        .map_err(|_| anyhow::anyhow!("Invalid HTTP method \"{}\"", method))?;
+    let env = cli_params.environment.as_deref();
+
+    // the configuration file is only needed to fill in placeholders
+    let mut manifest = None;
+    let path = commands::api::substitute_placeholders(path, |placeholder| {
+        if manifest.is_none() {
+            manifest = Some(Manifest::new(&cli_params.config)?);
+        }
+        let manifest = manifest.as_ref().unwrap();
+        match placeholder {
+            "account" => manifest.get_account_id(env),
+            _ => zone_id(manifest, env),
+        }
+    })?;
+
+    let body = match data {
+        Some(data) => Some(read_body(data)?),
+        None => None,
+    };
+    let user = GlobalUser::new()?;
+    commands::api::request(&user, method, &path, headers, body)
+}
+
+fn zone_id(manifest: &Manifest, env: Option<&str>) -> Result<String> {
+    let environment = manifest.get_environment(env)?;
+    match environment
+        .and_then(|environment| environment.zone_id.clone())
+        .or_else(|| manifest.zone_id.clone())
+    {
+        Some(zone_id) => Ok(zone_id),
+        None => anyhow::bail!("The :zone placeholder needs zone_id in your configuration file"),
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::Duration;
+
+use crate::commands;
+use crate::commands::auth::Shell;
+use crate::settings::global_user::GlobalUser;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Auth {
+    /// Print the commands that set a short-lived token in the current shell, so publishing
+    /// from shared machines doesn't need long-lived credentials on disk. Run it as
+    /// `eval "$(wrangler auth session)"`
+    Session {
+        /// How long the token lasts, e.g. 30m, 1h or 1d
+        #[structopt(long, default_value = "1h", parse(try_from_str = parse_ttl))]
+        ttl: Duration,
+
+        /// The shell to print the commands for
+        #[structopt(long, default_value = "sh", possible_values = &["sh", "fish", "powershell"])]
+        shell: Shell,
+    },
+}
+
+pub fn auth(auth: Auth) -> Result<()> {
+    match auth {
+        Auth::Session { ttl, shell } => {
+            let user = GlobalUser::new()?;
+            commands::auth::session(&user, ttl, shell)
+        }
+    }
+}
+
+fn parse_ttl(input: &str) -> Result<Duration> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+    let amount: i64 = match amount.parse() {
+        Ok(amount) if amount > 0 => amount,
+        _ => anyhow::bail!("Invalid TTL \"{}\" (use e.g. 30m, 1h or 1d)", input),
+    };
+
+    match unit {
+        "m" => Ok(Duration::minutes(amount)),
+        "h" | "" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        _ => anyhow::bail!("Invalid TTL \"{}\" (use e.g. 30m, 1h or 1d)", input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_ttls() {
+        assert_eq!(parse_ttl("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_ttl("1h").unwrap(), Duration::hours(1));
+        assert_eq!(parse_ttl("2").unwrap(), Duration::hours(2));
+        assert_eq!(parse_ttl("1d").unwrap(), Duration::days(1));
+        assert!(parse_ttl("0h").is_err());
+        assert!(parse_ttl("1 hour").is_err());
+    }
+}
@@ -1,13 +1,27 @@
 use super::Cli;
-use crate::build_target;
+use crate::build::Build;
 use crate::settings::toml::Manifest;
 use crate::terminal::message::{Message, StdOut};
+use crate::watch::{self, WatchOutput};
 
 use anyhow::Result;
 
-pub fn build(cli_params: &Cli) -> Result<()> {
+pub fn build(watch: bool, output: WatchOutput, cli_params: &Cli) -> Result<()> {
+    if watch {
+        let env = cli_params.environment.as_deref();
+        return watch::watch_and_check(&cli_params.config, env, output);
+    }
+
     log::info!("Getting project settings");
     let manifest = Manifest::new(&cli_params.config)?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
-    build_target(&target).map(|msg| StdOut::success(&msg))
+    let env = cli_params.environment.as_deref();
+    let target = manifest.get_target(env, false)?;
+
+    // a build doesn't need anywhere to publish to, so the routes are taken as they are
+    // configured rather than looking up the zones they belong to
+    let routes = manifest.route_patterns(env).unwrap_or_default();
+    Build::new(&target)
+        .with_routes(&routes)
+        .run()
+        .map(|msg| StdOut::success(&msg))
 }
@@ -0,0 +1,20 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+
+pub fn check(offline: bool, cli_params: &Cli) -> Result<()> {
+    let manifest = Manifest::new(&cli_params.config)?;
+    let env = cli_params.environment.as_deref();
+    let target = manifest.get_target(env, false)?;
+    let deployments = manifest.get_deployments(env)?;
+
+    let user = if offline {
+        None
+    } else {
+        Some(GlobalUser::new()?)
+    };
+
+    commands::check::check(&target, &deployments, user.as_ref())
+}
@@ -0,0 +1,33 @@
+use super::Cli;
+use crate::build_target;
+use crate::commands;
+use crate::settings::toml::Manifest;
+use crate::terminal::message::{Message, StdErr};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Compat {
+    /// Build your worker and report the minimum compatibility date and flags the features
+    /// its scripts use need, checking them against your configuration file
+    Check {
+        /// Scan the output of the last build instead of building again
+        #[structopt(name = "no-build", long)]
+        no_build: bool,
+    },
+}
+
+pub fn compat(compat: Compat, cli_params: &Cli) -> Result<()> {
+    match compat {
+        Compat::Check { no_build } => {
+            let manifest = Manifest::new(&cli_params.config)?;
+            let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+            if !no_build {
+                build_target(&target).map(|msg| StdErr::success(&msg))?;
+            }
+            commands::compat::check(&target)
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use super::Cli;
+use crate::commands::completions::{self, CompletionKind};
+use crate::settings::toml::Manifest;
+
+use anyhow::Result;
+use clap::Shell;
+
+pub fn completions(shell: Shell) -> Result<()> {
+    completions::completions(shell, &mut std::io::stdout())
+}
+
+pub fn complete(kind: CompletionKind, cli_params: &Cli) -> Result<()> {
+    // outside of a project there's nothing to complete, which isn't worth an error
+    if let Ok(manifest) = Manifest::new(&cli_params.config) {
+        for value in completions::complete(&manifest, kind) {
+            println!("{}", value);
+        }
+    }
+    Ok(())
+}
@@ -1,9 +1,27 @@
+use super::Cli;
 use crate::commands;
 use crate::settings::global_user::GlobalUser;
 use crate::terminal::message::{Message, StdOut};
 use crate::terminal::{interactive, styles};
 
 use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum ConfigCommand {
+    /// Rewrite the settings of your configuration file that older versions of wrangler
+    /// took, like `private` and a single `route`, into the ones that replaced them.
+    /// Comments are kept, except those of the settings replaced. With --yes, the changes
+    /// are written without asking
+    Migrate,
+}
+
+pub fn config(command: ConfigCommand, cli_params: &Cli) -> Result<()> {
+    match command {
+        ConfigCommand::Migrate => commands::config_migrate::migrate(&cli_params.config),
+    }
+}
 
 pub fn configure(api_key: bool, no_verify: bool) -> Result<()> {
     let user: GlobalUser = if !api_key {
@@ -23,7 +41,8 @@ pub fn configure(api_key: bool, no_verify: bool) -> Result<()> {
             styles::warning("(Not Recommended)"),
             styles::highlight("`wrangler config --api-key`")
         ));
-        let api_token_value: String = interactive::get_user_input("Enter API Token: ");
+        let api_token_value: String =
+            interactive::get_user_input("Enter API Token: ", "set CF_API_TOKEN")?;
         GlobalUser::ApiTokenAuth {
             api_token: api_token_value,
         }
@@ -37,8 +56,9 @@ pub fn configure(api_key: bool, no_verify: bool) -> Result<()> {
                 "https://support.cloudflare.com/hc/en-us/articles/200167836-Managing-API-Tokens-and-Keys",
             )
         ));
-        let email: String = interactive::get_user_input("Enter Email: ");
-        let api_key: String = interactive::get_user_input("Enter Global API Key: ");
+        let instead = "set CF_EMAIL and CF_API_KEY";
+        let email: String = interactive::get_user_input("Enter Email: ", instead)?;
+        let api_key: String = interactive::get_user_input("Enter Global API Key: ", instead)?;
 
         GlobalUser::GlobalKeyAuth { email, api_key }
     };
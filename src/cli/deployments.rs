@@ -0,0 +1,28 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Deployments {
+    /// List the recent deployments of your worker, with the message, git commit and author
+    /// `wrangler publish` recorded for each
+    List {
+        /// How many deployments to list
+        #[structopt(long, short = "n", default_value = "10")]
+        limit: usize,
+    },
+}
+
+pub fn deployments(deployments: Deployments, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+
+    match deployments {
+        Deployments::List { limit } => commands::deployments::list(&user, &target, limit),
+    }
+}
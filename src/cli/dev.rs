@@ -1,8 +1,10 @@
 use std::net::{IpAddr, Ipv4Addr};
 
 use super::Cli;
-use crate::commands::{self, dev::Protocol};
+use crate::commands::{self, dev::Chaos, dev::Protocol};
+use crate::kv;
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::message::{Message, StdOut};
 
 use anyhow::Result;
 
@@ -16,6 +18,8 @@ pub fn dev(
     cli_params: &Cli,
     inspect: bool,
     unauthenticated: bool,
+    chaos: Option<Chaos>,
+    local_kv: bool,
 ) -> Result<()> {
     log::info!("Starting dev server");
     let manifest = Manifest::new(&cli_params.config)?;
@@ -37,8 +41,20 @@ pub fn dev(
     let deployments = manifest.get_deployments(cli_params.environment.as_deref())?;
     let target = manifest.get_target(cli_params.environment.as_deref(), true)?;
     let user = GlobalUser::new().ok();
+    if local_kv {
+        match &user {
+            Some(user) => kv::local::seed(&target, user)?,
+            None => anyhow::bail!(
+                "--local-kv writes the local namespaces to the preview namespaces of your account, run `wrangler login` or `wrangler config` first"
+            ),
+        }
+    }
 
-    let server_config = commands::dev::ServerConfig::new(host, ip, port, upstream_protocol)?;
+    let mut server_config = commands::dev::ServerConfig::new(host, ip, port, upstream_protocol)?;
+    if let Some(chaos) = chaos {
+        StdOut::warn(&format!("--chaos is on: {}", chaos));
+        server_config = server_config.with_chaos(chaos);
+    }
 
     commands::dev::dev(
         target,
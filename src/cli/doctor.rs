@@ -0,0 +1,8 @@
+use super::Cli;
+use crate::commands;
+
+use anyhow::Result;
+
+pub fn doctor(cli_params: &Cli) -> Result<()> {
+    commands::doctor::doctor(&cli_params.config, cli_params.environment.as_deref())
+}
@@ -0,0 +1,53 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use structopt::StructOpt;
+use url::Url;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum DurableObjects {
+    /// Print the storage of a Durable Object as JSON, read through the debug endpoint of
+    /// `wrangler durable-objects helper`. Needs WRANGLER_DEBUG_TOKEN set to the secret of
+    /// the same name
+    Dump {
+        /// The binding of the Durable Object namespace
+        #[structopt(index = 1)]
+        namespace: String,
+
+        /// The hex id of the object, or the name its id is derived from
+        #[structopt(index = 2)]
+        object_id: String,
+
+        /// The URL of your worker. Defaults to its workers.dev address
+        #[structopt(long)]
+        url: Option<Url>,
+    },
+    /// Print the code of the debug endpoint `dump` reads storage through
+    Helper,
+}
+
+pub fn durable_objects(durable_objects: DurableObjects, cli_params: &Cli) -> Result<()> {
+    match durable_objects {
+        DurableObjects::Dump {
+            namespace,
+            object_id,
+            url,
+        } => {
+            let manifest = Manifest::new(&cli_params.config)?;
+            let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+            // only needed to look up the workers.dev subdomain
+            let user = match &url {
+                Some(_) => None,
+                None => Some(GlobalUser::new()?),
+            };
+            commands::durable_objects::dump(&target, &namespace, &object_id, url, user.as_ref())
+        }
+        DurableObjects::Helper => {
+            print!("{}", commands::durable_objects::HELPER);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use super::Cli;
+use crate::commands::estimate::{self, Estimate, EstimateTarget, Usage};
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::message::{Message, StdErr};
+
+use anyhow::Result;
+
+pub fn estimate(
+    requests: Option<f64>,
+    avg_cpu_ms: Option<f64>,
+    avg_duration_ms: Option<f64>,
+    days: u32,
+    cli_params: &Cli,
+) -> Result<()> {
+    if days == 0 {
+        anyhow::bail!("--days needs to be at least 1");
+    }
+    let manifest = Manifest::new(&cli_params.config)?;
+
+    // Supplied figures don't differ between environments, so only estimate the selected one.
+    let environments: Vec<Option<String>> = match (&cli_params.environment, &manifest.env) {
+        (Some(env), _) => vec![Some(env.clone())],
+        (None, Some(envs)) if requests.is_none() => {
+            let mut names: Vec<String> = envs.keys().cloned().collect();
+            names.sort();
+            std::iter::once(None)
+                .chain(names.into_iter().map(Some))
+                .collect()
+        }
+        (None, _) => vec![None],
+    };
+
+    let mut estimates = Vec::new();
+    for environment in environments {
+        let target = manifest.get_target(environment.as_deref(), false)?;
+        let usage = match (requests, avg_cpu_ms) {
+            (Some(requests), Some(avg_cpu_ms)) => Usage {
+                requests,
+                avg_cpu_ms,
+                avg_duration_ms: avg_duration_ms.unwrap_or(avg_cpu_ms),
+            },
+            (Some(_), None) => anyhow::bail!("--requests needs to be paired with --avg-cpu-ms"),
+            (None, _) => {
+                let user = GlobalUser::new()?;
+                let account_id = target.account_id.load()?.to_string();
+                StdErr::working(&format!(
+                    "Fetching the last {} days of analytics for {}",
+                    days, target.name
+                ));
+                estimate::fetch_usage(&user, &account_id, &target.name, days)?
+            }
+        };
+
+        let estimate = Estimate::new(&usage);
+        let target = EstimateTarget {
+            environment,
+            script_name: target.name,
+            usage_model: target.usage_model,
+        };
+        estimates.push((target, usage, estimate));
+    }
+
+    estimate::print_estimates(&estimates);
+    Ok(())
+}
+
+/// Parses a count like 50M, 2.5k or 1B
+pub fn parse_count(input: &str) -> Result<f64> {
+    let input = input.trim().replace('_', "");
+    let (number, multiplier) = match input.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&input[..input.len() - 1], 1e3),
+        Some('M') => (&input[..input.len() - 1], 1e6),
+        Some('B') => (&input[..input.len() - 1], 1e9),
+        _ => (input.as_str(), 1.0),
+    };
+    match number.parse::<f64>() {
+        Ok(number) if number >= 0.0 => Ok(number * multiplier),
+        _ => anyhow::bail!(
+            "Invalid count \"{}\" (use a number like 50000000, 50M or 2.5k)",
+            input
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_counts_with_suffixes() {
+        assert_eq!(parse_count("50M").unwrap(), 50_000_000.0);
+        assert_eq!(parse_count("2.5k").unwrap(), 2_500.0);
+        assert_eq!(parse_count("1B").unwrap(), 1_000_000_000.0);
+        assert_eq!(parse_count("1_000").unwrap(), 1_000.0);
+        assert!(parse_count("lots").is_err());
+        assert!(parse_count("-5M").is_err());
+    }
+}
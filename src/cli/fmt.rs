@@ -0,0 +1,8 @@
+use super::Cli;
+use crate::commands;
+
+use anyhow::Result;
+
+pub fn fmt(check: bool, cli_params: &Cli) -> Result<()> {
+    commands::fmt::fmt(&cli_params.config, check)
+}
@@ -0,0 +1,10 @@
+use super::Cli;
+use crate::commands::graph::{self, GraphFormat};
+use crate::settings::toml::Manifest;
+
+use anyhow::Result;
+
+pub fn graph(format: GraphFormat, cli_params: &Cli) -> Result<()> {
+    let manifest = Manifest::new(&cli_params.config)?;
+    graph::graph(&manifest, cli_params.environment.as_deref(), format)
+}
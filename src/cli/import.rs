@@ -0,0 +1,24 @@
+use std::env;
+use std::path::PathBuf;
+
+use super::Cli;
+use crate::commands;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::target::LazyAccountId;
+
+use anyhow::Result;
+
+pub fn import(name: &str, output: Option<PathBuf>, cli_params: &Cli) -> Result<()> {
+    commands::validate_worker_name(name)?;
+    let user = GlobalUser::new()?;
+
+    // there's no configuration file to take the account from yet
+    let account_id = LazyAccountId::from(
+        cli_params
+            .account_id
+            .clone()
+            .or_else(|| env::var("CF_ACCOUNT_ID").ok()),
+    );
+    let dir = output.unwrap_or_else(|| PathBuf::from(name));
+    commands::import::import(&user, account_id.load()?, name, &dir)
+}
@@ -1,9 +1,14 @@
 use std::path::PathBuf;
 
+use super::publish::check_protected;
 use super::Cli;
 use crate::commands;
-use crate::commands::kv::key::{parse_metadata, KVMetaData};
-use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::commands::kv::bulk::import::{BulkFormat, Columns};
+use crate::commands::kv::key::{parse_metadata, BatchOutput, KVMetaData, ListFormat, ValueOutput};
+use crate::commands::kv::namespace::TransferOptions;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{Manifest, Target};
+use crate::terminal::message::{self, Verbosity};
 
 use anyhow::{anyhow, Result};
 use clap::ArgGroup;
@@ -20,6 +25,10 @@ pub enum KvNamespace {
         /// Applies the command to the preview namespace
         #[structopt(name = "preview", long)]
         preview: bool,
+        /// Add the new namespace to the kv_namespaces of your configuration file also when
+        /// nobody is at the terminal, which takes --yes to confirm the changes to it
+        #[structopt(name = "update-config", long)]
+        update_config: bool,
     },
     /// Delete namespace
     Delete {
@@ -32,6 +41,82 @@ pub enum KvNamespace {
     },
     /// List all namespaces on your Cloudflare account
     List,
+    /// Write every key of a namespace to a file, one JSON object per line that
+    /// `kv:bulk put` can put back. An interrupted export resumes where it stopped
+    Export {
+        #[structopt(flatten)]
+        namespace: Namespace,
+
+        /// The file to write the keys to
+        #[structopt(long, short = "o", alias = "out", parse(from_os_str))]
+        output: PathBuf,
+
+        #[structopt(flatten)]
+        transfer: Transfer,
+    },
+    /// Put the keys of a file written by `kv:namespace export` into a namespace, with their
+    /// expirations and metadata, like one of another account. An interrupted import resumes
+    /// where it stopped
+    Import {
+        #[structopt(flatten)]
+        namespace: Namespace,
+
+        /// The file to read the keys from
+        #[structopt(index = 1, parse(from_os_str))]
+        input: PathBuf,
+
+        /// How many keys to write at a time, at most 1000
+        #[structopt(name = "batch-size", long, default_value = "1000")]
+        batch_size: usize,
+
+        /// Start over instead of resuming an interrupted import
+        #[structopt(long)]
+        restart: bool,
+    },
+    /// Copy the keys of a namespace to another namespace of the account, with their values,
+    /// expirations and metadata, like to promote staging data to production. An
+    /// interrupted copy resumes where it stopped
+    Copy {
+        #[structopt(flatten)]
+        namespace: Namespace,
+
+        /// The ID of the namespace to copy the keys to
+        #[structopt(name = "to-namespace-id", long)]
+        to_namespace_id: String,
+
+        #[structopt(flatten)]
+        transfer: Transfer,
+    },
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub struct Transfer {
+    /// How many keys to list, fetch and write at a time, at most 1000
+    #[structopt(name = "batch-size", long, default_value = "1000")]
+    pub batch_size: usize,
+
+    /// How many values to fetch at the same time
+    #[structopt(long, default_value = "8")]
+    pub parallelism: usize,
+
+    /// Start over instead of resuming an interrupted transfer
+    #[structopt(long)]
+    pub restart: bool,
+
+    /// Only transfer the keys starting with this prefix
+    #[structopt(long)]
+    pub prefix: Option<String>,
+}
+
+impl From<Transfer> for TransferOptions {
+    fn from(transfer: Transfer) -> Self {
+        TransferOptions {
+            batch_size: transfer.batch_size,
+            parallelism: transfer.parallelism,
+            restart: transfer.restart,
+            prefix: transfer.prefix,
+        }
+    }
 }
 
 #[derive(Debug, Clone, StructOpt)]
@@ -55,6 +140,20 @@ pub struct Namespace {
     pub namespace_id: Option<String>,
 }
 
+impl Namespace {
+    /// Returns the target of the selected environment along with the ID of the namespace,
+    /// looking it up in the kv_namespaces of that environment if a binding was passed.
+    fn resolve(self, manifest: &Manifest, env: Option<&str>) -> Result<(Target, String)> {
+        let target = manifest.get_target(env, self.preview)?;
+        let namespace_id = match (self.binding, self.namespace_id) {
+            (Some(binding), _) => commands::kv::get_namespace_id(&target, &binding)?,
+            (None, Some(namespace_id)) => namespace_id,
+            (None, None) => anyhow::bail!("Either --binding or --namespace-id is required"),
+        };
+        Ok((target, namespace_id))
+    }
+}
+
 #[derive(Debug, Clone, StructOpt)]
 #[structopt(rename_all = "lower")]
 pub enum KvKey {
@@ -84,9 +183,19 @@ pub enum KvKey {
         #[structopt(name = "metadata", long, short = "m")]
         metadata: Option<String>,
 
-        /// The value passed in is a path to a file; open and upload its contents
+        /// The value passed in is a path to a file, or - for standard input; its contents are
+        /// uploaded as they're read, so they never have to fit in memory
         #[structopt(name = "path", long, short = "p")]
         path: bool,
+
+        /// The value, or the contents of the file with --path, is base64, like the values of
+        /// `kv:key get --batch` that aren't text; it's decoded before it's written
+        #[structopt(long)]
+        base64: bool,
+
+        /// Act on the namespace kept in .wrangler/kv/ instead of the one on your account
+        #[structopt(long)]
+        local: bool,
     },
     /// Get a key's value from a namespace
     Get {
@@ -94,8 +203,31 @@ pub enum KvKey {
         namespace: Namespace,
 
         /// Key whose value to get
-        #[structopt(name = "key", index = 1)]
-        key: String,
+        #[structopt(name = "key", index = 1, required_unless = "batch")]
+        key: Option<String>,
+
+        /// Get the values of every key in this file, one key per line, concurrently.
+        /// Reads the keys from stdin if set to "-"
+        #[structopt(long, conflicts_with = "key", parse(from_os_str))]
+        batch: Option<PathBuf>,
+
+        /// Write the value of each key to its own file in this directory instead of
+        /// printing them as JSON lines
+        #[structopt(name = "output-dir", long, requires = "batch", parse(from_os_str))]
+        output_dir: Option<PathBuf>,
+
+        /// Write the value to this file, byte for byte, instead of printing it
+        #[structopt(long, short = "o", conflicts_with = "batch", parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        /// Print the value base64 encoded, for binary values
+        #[structopt(long, conflicts_with_all = &["batch", "output"])]
+        base64: bool,
+
+        /// Get the value from the namespace kept in .wrangler/kv/ instead of the one on
+        /// your account
+        #[structopt(long, conflicts_with = "batch")]
+        local: bool,
     },
     /// Delete a key and its value from a namespace
     Delete {
@@ -109,6 +241,10 @@ pub enum KvKey {
         /// Forces delete without user confirmation
         #[structopt(name = "force", long, short = "f")]
         force: bool,
+
+        /// Act on the namespace kept in .wrangler/kv/ instead of the one on your account
+        #[structopt(long)]
+        local: bool,
     },
     /// List all keys in a namespace. Produces JSON output
     List {
@@ -118,6 +254,18 @@ pub enum KvKey {
         /// The prefix for filtering listed keys
         #[structopt(name = "prefix", long, short = "p")]
         prefix: Option<String>,
+
+        /// Stop after listing this many keys
+        #[structopt(name = "limit", long, short = "l")]
+        limit: Option<usize>,
+
+        /// Print a single JSON array, or one JSON object per line
+        #[structopt(long, short = "f", default_value = "json", possible_values = &["json", "ndjson"])]
+        format: ListFormat,
+
+        /// Act on the namespace kept in .wrangler/kv/ instead of the one on your account
+        #[structopt(long)]
+        local: bool,
     },
 }
 
@@ -129,9 +277,32 @@ pub enum KvBulk {
         #[structopt(flatten)]
         namespace: Namespace,
 
-        /// The JSON file of key-value pairs to upload, in form [{\"key\":..., \"value\":...}\"...]
+        /// The JSON file of key-value pairs to upload, in form [{\"key\":..., \"value\":...}\"...],
+        /// or the CSV file or SQLite database to read them from with --format
         #[structopt(index = 1)]
         path: PathBuf,
+
+        /// What the file is
+        #[structopt(long, default_value = "json", possible_values = &["json", "csv", "sqlite"])]
+        format: BulkFormat,
+
+        /// The query selecting the rows of a SQLite database to upload. Reading SQLite
+        /// databases needs the sqlite3 command line tool, 3.33.0 or later
+        #[structopt(long)]
+        query: Option<String>,
+
+        /// The column of CSV or SQLite rows holding the key
+        #[structopt(long = "key-column", default_value = "key")]
+        key_column: String,
+
+        /// The column of CSV or SQLite rows holding the value
+        #[structopt(long = "value-column", default_value = "value")]
+        value_column: String,
+
+        /// The column of CSV or SQLite rows holding the number of seconds each pair expires
+        /// after, which never expire when it's empty
+        #[structopt(long = "ttl-column")]
+        ttl_column: Option<String>,
     },
     /// Delete multiple keys and their values from a namespace
     Delete {
@@ -154,57 +325,114 @@ pub fn kv_namespace(namespace: KvNamespace, cli_params: &Cli) -> Result<()> {
     let env = cli_params.environment.as_deref();
 
     match namespace {
-        KvNamespace::Create { binding, preview } => {
-            commands::kv::namespace::create(&manifest, preview, env, &user, &binding)
-        }
+        KvNamespace::Create {
+            binding,
+            preview,
+            update_config,
+        } => commands::kv::namespace::create(
+            &manifest,
+            &cli_params.config,
+            preview,
+            update_config,
+            env,
+            &user,
+            &binding,
+        ),
         KvNamespace::Delete { namespace, force } => {
-            let target = manifest.get_target(env, namespace.preview)?;
-            let id = if let Some(binding) = namespace.binding {
-                commands::kv::get_namespace_id(&target, &binding)?
-            } else {
-                namespace
-                    .namespace_id
-                    .expect("Namespace ID is required if binding isn't supplied")
-            };
+            check_protected(&manifest, env, "delete from", cli_params.yes_i_know)?;
+            let (target, id) = namespace.resolve(&manifest, env)?;
             commands::kv::namespace::delete(&target, &user, &id, force)
         }
         KvNamespace::List => {
             let target = manifest.get_target(env, false)?;
             commands::kv::namespace::list(&target, &user)
         }
+        KvNamespace::Export {
+            namespace,
+            output,
+            transfer,
+        } => {
+            let (target, id) = namespace.resolve(&manifest, env)?;
+            commands::kv::namespace::export(&target, &user, &id, &output, transfer.into())
+        }
+        KvNamespace::Import {
+            namespace,
+            input,
+            batch_size,
+            restart,
+        } => {
+            let (target, id) = namespace.resolve(&manifest, env)?;
+            let options = TransferOptions {
+                batch_size,
+                parallelism: 1,
+                restart,
+                prefix: None,
+            };
+            commands::kv::namespace::import(&target, &user, &id, &input, options)
+        }
+        KvNamespace::Copy {
+            namespace,
+            to_namespace_id,
+            transfer,
+        } => {
+            let (target, id) = namespace.resolve(&manifest, env)?;
+            commands::kv::namespace::copy(&target, &user, &id, &to_namespace_id, transfer.into())
+        }
     }
 }
 
 pub fn kv_key(key: KvKey, cli_params: &Cli) -> Result<()> {
-    let user = GlobalUser::new()?;
     let manifest = Manifest::new(&cli_params.config)?;
     let env = cli_params.environment.as_deref();
 
-    let target_and_namespace = |namespace: Namespace| -> Result<(_, _)> {
-        let target = manifest.get_target(env, namespace.preview)?;
-        let namespace_id = if let Some(binding) = namespace.binding {
-            commands::kv::get_namespace_id(&target, &binding)?
-        } else {
-            namespace
-                .namespace_id
-                .expect("Namespace ID is required if binding isn't supplied")
-        };
-        Ok((target, namespace_id))
-    };
+    let target_and_namespace = |namespace: Namespace| namespace.resolve(&manifest, env);
+
+    if key.is_local() {
+        return kv_key_local(key, target_and_namespace);
+    }
+    let user = GlobalUser::new()?;
 
     match key {
-        KvKey::Get { namespace, key } => {
+        KvKey::Get {
+            namespace,
+            key,
+            batch,
+            output_dir,
+            output,
+            base64,
+            ..
+        } => {
             let (target, namespace_id) = target_and_namespace(namespace)?;
-            commands::kv::key::get(&target, &user, &namespace_id, &key)
+            let output = ValueOutput {
+                path: output,
+                base64,
+                verbose: message::verbosity() == Verbosity::Verbose,
+            };
+            match (key, batch) {
+                (_, Some(batch)) => {
+                    let keys = commands::kv::key::read_batch_keys(&batch)?;
+                    let output = match output_dir {
+                        Some(dir) => BatchOutput::Dir(dir),
+                        None => BatchOutput::Ndjson,
+                    };
+                    commands::kv::key::get_batch(&target, &user, &namespace_id, keys, output)
+                }
+                (Some(key), None) => {
+                    commands::kv::key::get(&target, &user, &namespace_id, &key, &output)
+                }
+                (None, None) => unreachable!("structopt requires a key or --batch"),
+            }
         }
         KvKey::Put {
             namespace,
             key,
             value,
             path: is_file,
+            base64,
             expiration_ttl,
             expiration,
             metadata,
+            ..
         } => {
             let (target, namespace_id) = target_and_namespace(namespace)?;
             let expiration = expiration.as_ref().map(ToString::to_string);
@@ -220,6 +448,7 @@ pub fn kv_key(key: KvKey, cli_params: &Cli) -> Result<()> {
                     key,
                     value,
                     is_file,
+                    base64,
                     expiration,
                     expiration_ttl,
                     metadata,
@@ -230,13 +459,109 @@ pub fn kv_key(key: KvKey, cli_params: &Cli) -> Result<()> {
             namespace,
             key,
             force,
+            ..
         } => {
+            check_protected(&manifest, env, "delete from", cli_params.yes_i_know)?;
             let (target, namespace_id) = target_and_namespace(namespace)?;
             commands::kv::key::delete(&target, &user, &namespace_id, &key, force)
         }
-        KvKey::List { namespace, prefix } => {
+        KvKey::List {
+            namespace,
+            prefix,
+            limit,
+            format,
+            ..
+        } => {
             let (target, namespace_id) = target_and_namespace(namespace)?;
-            commands::kv::key::list(&target, &user, &namespace_id, prefix.as_deref())
+            commands::kv::key::list(
+                &target,
+                &user,
+                &namespace_id,
+                prefix.as_deref(),
+                limit,
+                format,
+            )
+        }
+    }
+}
+
+impl KvKey {
+    fn is_local(&self) -> bool {
+        match self {
+            KvKey::Put { local, .. }
+            | KvKey::Get { local, .. }
+            | KvKey::Delete { local, .. }
+            | KvKey::List { local, .. } => *local,
+        }
+    }
+}
+
+// The local namespaces are named after the ids of the namespaces of the configuration file,
+// so they are looked up the same way, but nothing is sent to the API
+fn kv_key_local(
+    key: KvKey,
+    target_and_namespace: impl Fn(Namespace) -> Result<(Target, String)>,
+) -> Result<()> {
+    match key {
+        KvKey::Get {
+            namespace,
+            key,
+            output,
+            base64,
+            ..
+        } => {
+            let (_, namespace_id) = target_and_namespace(namespace)?;
+            let key = key.expect("structopt requires a key without --batch");
+            let output = ValueOutput {
+                path: output,
+                base64,
+                verbose: message::verbosity() == Verbosity::Verbose,
+            };
+            commands::kv::key::local::get(&namespace_id, &key, &output)
+        }
+        KvKey::Put {
+            namespace,
+            key,
+            value,
+            path: is_file,
+            base64,
+            expiration_ttl,
+            expiration,
+            metadata,
+            ..
+        } => {
+            let (_, namespace_id) = target_and_namespace(namespace)?;
+            let metadata = parse_metadata(metadata.as_deref())
+                .map_err(|e| anyhow!("--metadata is not valid JSON: {}", e.to_string()))?;
+            commands::kv::key::local::put(KVMetaData {
+                namespace_id,
+                key,
+                value,
+                is_file,
+                base64,
+                expiration: expiration.as_ref().map(ToString::to_string),
+                expiration_ttl: expiration_ttl.as_ref().map(ToString::to_string),
+                metadata,
+            })
+        }
+        KvKey::Delete {
+            namespace,
+            key,
+            force,
+            ..
+        } => {
+            let (_, namespace_id) = target_and_namespace(namespace)?;
+            commands::kv::key::local::delete(&namespace_id, &key, force)
+        }
+        KvKey::List {
+            namespace,
+            prefix,
+            limit,
+            format,
+            ..
+        } => {
+            let (_, namespace_id) = target_and_namespace(namespace)?;
+            commands::kv::key::local::list(&namespace_id, prefix.as_deref(), limit, format)
         }
     }
 }
@@ -247,30 +572,250 @@ pub fn kv_bulk(bulk: KvBulk, cli_params: &Cli) -> Result<()> {
     let user = GlobalUser::new()?;
     let env = cli_params.environment.as_deref();
 
-    let target_and_namespace = |namespace: Namespace| -> Result<(_, _)> {
-        let target = manifest.get_target(env, namespace.preview)?;
-        let namespace_id = if let Some(binding) = namespace.binding {
-            commands::kv::get_namespace_id(&target, &binding)?
-        } else {
-            namespace
-                .namespace_id
-                .expect("Namespace ID is required if binding isn't supplied")
-        };
-        Ok((target, namespace_id))
-    };
+    let target_and_namespace = |namespace: Namespace| namespace.resolve(&manifest, env);
 
     match bulk {
-        KvBulk::Put { namespace, path } => {
+        KvBulk::Put {
+            namespace,
+            path,
+            format,
+            query,
+            key_column,
+            value_column,
+            ttl_column,
+        } => {
             let (target, namespace_id) = target_and_namespace(namespace)?;
-            commands::kv::bulk::put(&target, &user, &namespace_id, &path)
+            let columns = Columns {
+                key: key_column,
+                value: value_column,
+                ttl: ttl_column,
+            };
+            commands::kv::bulk::put(
+                &target,
+                &user,
+                &namespace_id,
+                &path,
+                format,
+                query.as_deref(),
+                &columns,
+            )
         }
         KvBulk::Delete {
             namespace,
             path,
             force,
         } => {
+            check_protected(&manifest, env, "delete from", cli_params.yes_i_know)?;
             let (target, namespace_id) = target_and_namespace(namespace)?;
             commands::kv::bulk::delete(&target, &user, &namespace_id, &path, force)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Command;
+
+    fn namespace_of(args: &str) -> Namespace {
+        let argv = std::iter::once("wrangler").chain(args.split_whitespace());
+        match Cli::from_iter_safe(argv).unwrap().command {
+            Command::KvKey(KvKey::Get { namespace, .. }) => namespace,
+            Command::KvBulk(KvBulk::Delete { namespace, .. }) => namespace,
+            Command::KvNamespace(KvNamespace::Delete { namespace, .. }) => namespace,
+            command => panic!("unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_accepts_bindings_on_every_namespace_command() {
+        for args in &[
+            "kv:key get --binding STATIC_DATA --preview key",
+            "kv:bulk delete -b STATIC_DATA --preview keys.json",
+            "kv:namespace delete --binding STATIC_DATA --preview",
+        ] {
+            let namespace = namespace_of(args);
+            assert_eq!(namespace.binding.as_deref(), Some("STATIC_DATA"));
+            assert!(namespace.preview);
+            assert_eq!(namespace.namespace_id, None);
+        }
+    }
+
+    #[test]
+    fn it_resolves_bindings_to_the_namespaces_of_the_environment() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+kv_namespaces = [{ binding = "STATIC_DATA", id = "top", preview_id = "top-preview" }]
+
+[env.staging]
+kv_namespaces = [{ binding = "STATIC_DATA", id = "staging", preview_id = "staging-preview" }]
+"#,
+        )
+        .unwrap();
+        let resolve = |args: &str, env: Option<&str>| {
+            namespace_of(args)
+                .resolve(&manifest, env)
+                .map(|(_, namespace_id)| namespace_id)
+        };
+
+        assert_eq!(
+            resolve("kv:key get --binding STATIC_DATA key", None).unwrap(),
+            "top"
+        );
+        assert_eq!(
+            resolve("kv:bulk delete -b STATIC_DATA --preview keys.json", None).unwrap(),
+            "top-preview"
+        );
+        assert_eq!(
+            resolve("kv:namespace delete --binding STATIC_DATA", Some("staging")).unwrap(),
+            "staging"
+        );
+        assert_eq!(
+            resolve("kv:key get --namespace-id abc key", Some("staging")).unwrap(),
+            "abc"
+        );
+        assert!(resolve("kv:key get --binding MISSING key", None).is_err());
+    }
+
+    #[test]
+    fn it_requires_exactly_one_namespace_specifier() {
+        assert!(Cli::from_iter_safe(&["wrangler", "kv:key", "get", "key"]).is_err());
+        assert!(Cli::from_iter_safe(&[
+            "wrangler",
+            "kv:key",
+            "get",
+            "--binding",
+            "STATIC_DATA",
+            "--namespace-id",
+            "abc",
+            "key"
+        ])
+        .is_err());
+        // --preview only makes sense when looking the namespace up in the configuration file
+        assert!(Cli::from_iter_safe(&[
+            "wrangler",
+            "kv:key",
+            "get",
+            "--namespace-id",
+            "abc",
+            "--preview",
+            "key"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn it_parses_key_list_options() {
+        let argv =
+            "wrangler kv:key list -b STATIC_DATA --prefix assets/ --limit 10 --format ndjson";
+        match Cli::from_iter_safe(argv.split_whitespace())
+            .unwrap()
+            .command
+        {
+            Command::KvKey(KvKey::List {
+                prefix,
+                limit,
+                format,
+                ..
+            }) => {
+                assert_eq!(prefix.as_deref(), Some("assets/"));
+                assert_eq!(limit, Some(10));
+                assert_eq!(format, ListFormat::Ndjson);
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_parses_batched_gets() {
+        let argv = "wrangler kv:key get -b STATIC_DATA --batch keys.txt --output-dir export";
+        match Cli::from_iter_safe(argv.split_whitespace())
+            .unwrap()
+            .command
+        {
+            Command::KvKey(KvKey::Get {
+                key,
+                batch,
+                output_dir,
+                ..
+            }) => {
+                assert_eq!(key, None);
+                assert_eq!(batch, Some(PathBuf::from("keys.txt")));
+                assert_eq!(output_dir, Some(PathBuf::from("export")));
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        let argv = "wrangler kv:key get -b STATIC_DATA --batch - key";
+        assert!(Cli::from_iter_safe(argv.split_whitespace()).is_err());
+        let argv = "wrangler kv:key get -b STATIC_DATA --output-dir export key";
+        assert!(Cli::from_iter_safe(argv.split_whitespace()).is_err());
+    }
+
+    #[test]
+    fn it_parses_value_outputs() {
+        let argv = "wrangler kv:key get -b STATIC_DATA logo.png --output logo.png --verbose";
+        match Cli::from_iter_safe(argv.split_whitespace())
+            .unwrap()
+            .command
+        {
+            Command::KvKey(KvKey::Get { output, base64, .. }) => {
+                assert_eq!(output, Some(PathBuf::from("logo.png")));
+                assert!(!base64);
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        let argv = "wrangler kv:key get -b STATIC_DATA logo.png --output logo.png --base64";
+        assert!(Cli::from_iter_safe(argv.split_whitespace()).is_err());
+        let argv = "wrangler kv:key get -b STATIC_DATA --batch keys.txt --base64";
+        assert!(Cli::from_iter_safe(argv.split_whitespace()).is_err());
+    }
+
+    #[test]
+    fn it_parses_bulk_imports() {
+        let argv = "wrangler kv:bulk put -b STATIC_DATA posts.csv --format csv --key-column id --value-column body --ttl-column ttl";
+        match Cli::from_iter_safe(argv.split_whitespace())
+            .unwrap()
+            .command
+        {
+            Command::KvBulk(KvBulk::Put {
+                format,
+                key_column,
+                value_column,
+                ttl_column,
+                ..
+            }) => {
+                assert_eq!(format, BulkFormat::Csv);
+                assert_eq!(key_column, "id");
+                assert_eq!(value_column, "body");
+                assert_eq!(ttl_column.as_deref(), Some("ttl"));
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_parses_local_variants() {
+        for argv in &[
+            "wrangler kv:key put -b STATIC_DATA --local key value",
+            "wrangler kv:key get -b STATIC_DATA --local key",
+            "wrangler kv:key delete -b STATIC_DATA --local -f key",
+            "wrangler kv:key list -b STATIC_DATA --local",
+        ] {
+            match Cli::from_iter_safe(argv.split_whitespace())
+                .unwrap()
+                .command
+            {
+                Command::KvKey(key) => assert!(key.is_local(), "{}", argv),
+                command => panic!("unexpected command {:?}", command),
+            }
+        }
+
+        let argv = "wrangler kv:key get -b STATIC_DATA --local --batch keys.txt";
+        assert!(Cli::from_iter_safe(argv.split_whitespace()).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use std::path::PathBuf;
+
+use super::Cli;
+use crate::commands;
+use crate::commands::logs::errors::ErrorFilter;
+use crate::commands::logs::DownloadOptions;
+use crate::commands::stats::StatsFormat;
+use crate::commands::tail::websocket::TailFormat;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Logs {
+    /// List the Logpush jobs that archive Workers trace events on your account
+    List,
+    /// Download and print archived logs of your worker from a Logpush destination
+    Download {
+        /// Name of the worker whose logs to print, defaults to the one in your configuration file
+        #[structopt(index = 1)]
+        name: Option<String>,
+
+        /// The Logpush job to read archives from (find using `wrangler logs list`)
+        #[structopt(long)]
+        job: Option<u64>,
+
+        /// Only include events from this time on (e.g. 2022-01-31 or 2022-01-31T12:00:00Z)
+        #[structopt(long, parse(try_from_str = parse_time))]
+        since: Option<DateTime<Utc>>,
+
+        /// Only include events up to this time (e.g. 2022-01-31 or 2022-01-31T12:00:00Z)
+        #[structopt(long, parse(try_from_str = parse_time))]
+        until: Option<DateTime<Utc>>,
+
+        /// Output format for log messages
+        #[structopt(long, short = "f", default_value = "json", possible_values = &["json", "pretty"])]
+        format: TailFormat,
+
+        /// Filter by invocation status
+        #[structopt(long, possible_values = &["ok", "error", "canceled"])]
+        status: Vec<String>,
+
+        /// Filter by HTTP method
+        #[structopt(long)]
+        method: Vec<String>,
+
+        /// Filter by a text match in console.log messages and exceptions
+        #[structopt(long)]
+        search: Option<String>,
+
+        /// Save the raw archives into this directory instead of printing them
+        #[structopt(long, short = "o")]
+        output: Option<PathBuf>,
+    },
+    /// Show when your worker failed recently, and how, from the Workers analytics
+    Errors {
+        /// Name of the worker whose errors to show, defaults to the one in your configuration file
+        #[structopt(index = 1)]
+        name: Option<String>,
+
+        /// Only include errors from this time on, defaults to a day ago (e.g. 2022-01-31 or 2022-01-31T12:00:00Z)
+        #[structopt(long, parse(try_from_str = parse_time))]
+        since: Option<DateTime<Utc>>,
+
+        /// Only include errors up to this time, defaults to now (e.g. 2022-01-31 or 2022-01-31T12:00:00Z)
+        #[structopt(long, parse(try_from_str = parse_time))]
+        until: Option<DateTime<Utc>>,
+
+        /// Filter errors by status, e.g. status=error or status=scriptThrewException
+        #[structopt(long, number_of_values = 1)]
+        filter: Vec<ErrorFilter>,
+
+        /// Show at most this many minutes with errors, most recent first
+        #[structopt(long, default_value = "100")]
+        limit: u64,
+
+        /// Print the errors as a table or as JSON
+        #[structopt(long, default_value = "table", possible_values = &["table", "json"])]
+        format: StatsFormat,
+    },
+}
+
+pub fn logs(logs: Logs, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let account_id = target.account_id.load()?.to_string();
+
+    match logs {
+        Logs::List => commands::logs::list(&user, &account_id),
+        Logs::Download {
+            name,
+            job,
+            since,
+            until,
+            format,
+            status,
+            method,
+            search,
+            output,
+        } => {
+            let script_name = name.unwrap_or(target.name);
+            let options = DownloadOptions {
+                job_id: job,
+                since,
+                until,
+                outcomes: status,
+                methods: method,
+                search,
+                format,
+                output,
+            };
+            commands::logs::download(&user, &account_id, &script_name, options)
+        }
+        Logs::Errors {
+            name,
+            since,
+            until,
+            filter,
+            limit,
+            format,
+        } => {
+            if limit == 0 {
+                anyhow::bail!("--limit needs to be at least 1");
+            }
+            let script_name = name.unwrap_or(target.name);
+            commands::logs::errors::errors(
+                &user,
+                &account_id,
+                &script_name,
+                since,
+                until,
+                &filter,
+                limit,
+                format,
+            )
+        }
+    }
+}
+
+fn parse_time(input: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    match NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        Ok(date) => Ok(DateTime::from_utc(date.and_hms(0, 0, 0), Utc)),
+        Err(_) => anyhow::bail!(
+            "Invalid time \"{}\" (use a date like 2022-01-31 or an RFC 3339 timestamp)",
+            input
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_dates_and_timestamps() {
+        assert_eq!(
+            parse_time("2022-01-31").unwrap().to_rfc3339(),
+            "2022-01-31T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_time("2022-01-31T12:30:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2022-01-31T10:30:00+00:00"
+        );
+        assert!(parse_time("yesterday").is_err());
+    }
+}
@@ -1,52 +1,108 @@
+pub mod api;
+pub mod auth;
 pub mod build;
+pub mod check;
+pub mod compat;
+pub mod completions;
 pub mod config;
+pub mod deployments;
 pub mod dev;
+pub mod doctor;
+pub mod durable_objects;
+pub mod estimate;
+pub mod fmt;
 pub mod generate;
+pub mod graph;
+pub mod import;
 pub mod init;
 pub mod kv;
 pub mod login;
 pub mod logout;
+pub mod logs;
 pub mod preview;
+pub mod previews;
 pub mod publish;
+pub mod queues;
+pub mod quota;
 pub mod route;
+pub mod scripts;
 pub mod secret;
+pub mod site;
+pub mod stats;
 pub mod subdomain;
 pub mod tail;
+pub mod vars;
+pub mod verify_site;
 pub mod whoami;
+pub mod zone;
 
 pub mod exec {
+    pub use super::api::api;
+    pub use super::auth::auth;
     pub use super::build::build;
-    pub use super::config::configure;
+    pub use super::check::check;
+    pub use super::compat::compat;
+    pub use super::completions::{complete, completions};
+    pub use super::config::{config, configure};
+    pub use super::deployments::deployments;
     pub use super::dev::dev;
+    pub use super::doctor::doctor;
+    pub use super::durable_objects::durable_objects;
+    pub use super::estimate::estimate;
+    pub use super::fmt::fmt;
     pub use super::generate::generate;
+    pub use super::graph::graph;
+    pub use super::import::import;
     pub use super::init::init;
     pub use super::kv::kv_bulk;
     pub use super::kv::kv_key;
     pub use super::kv::kv_namespace;
     pub use super::login::login;
     pub use super::logout::logout;
+    pub use super::logs::logs;
     pub use super::preview::preview;
+    pub use super::previews::previews;
     pub use super::publish::publish;
+    pub use super::queues::queues;
+    pub use super::quota::quota;
     pub use super::route::route;
+    pub use super::scripts::scripts;
     pub use super::secret::secret;
+    pub use super::site::site;
+    pub use super::stats::stats;
     pub use super::subdomain::subdomain;
     pub use super::tail::tail;
+    pub use super::vars::vars;
+    pub use super::verify_site::verify_site;
     pub use super::whoami::whoami;
+    pub use super::zone::zone;
 }
 
+use std::env;
 use std::net::IpAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use crate::commands::dev::Protocol;
+use crate::commands::completions::CompletionKind;
+use crate::commands::dev::{Chaos, Protocol};
+use crate::commands::graph::GraphFormat;
+use crate::commands::kv::key::STDIN;
+use crate::commands::stats::StatsFormat;
 use crate::commands::tail::websocket::TailFormat;
+use crate::http::Timeouts;
 use crate::preview::HttpMethod;
+use crate::settings;
 use crate::settings::toml::migrations::{
     DurableObjectsMigration, Migration, MigrationTag, Migrations, RenameClass, TransferClass,
 };
-use crate::settings::toml::TargetType;
-
-use clap::AppSettings;
+use crate::settings::toml::overrides::Overrides;
+use crate::settings::toml::{Manifest, TargetType};
+use crate::terminal::message::Verbosity;
+use crate::terminal::progress::ProgressFormat;
+use crate::watch::WatchOutput;
+
+use chrono::Duration;
+use clap::{AppSettings, Shell};
 use structopt::StructOpt;
 use url::Url;
 
@@ -59,18 +115,119 @@ use url::Url;
     setting = AppSettings::VersionlessSubcommands,
 )]
 pub struct Cli {
-    /// Toggle verbose output (when applicable)
+    /// Toggle verbose output (when applicable), including the requests made to the API
     #[structopt(long, global = true)]
     pub verbose: bool,
 
-    /// Path to configuration file.
-    #[structopt(long, short = "c", default_value = "wrangler.toml", global = true)]
+    /// Only print errors and the results of a command, like the URLs a worker was
+    /// published to
+    #[structopt(long, short = "q", global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Path to configuration file. Without one, wrangler.toml is looked for in the current
+    /// directory and then in each of its parents
+    #[structopt(
+        long,
+        short = "c",
+        env = "WRANGLER_CONFIG",
+        default_value = "wrangler.toml",
+        global = true
+    )]
     pub config: PathBuf,
 
-    /// Environment to perform a command on.
-    #[structopt(name = "env", long, short = "e", global = true)]
+    /// Environment to perform a command on. `publish` takes it more than once, to publish
+    /// to several environments
+    #[structopt(name = "env", long, short = "e", global = true, number_of_values = 1)]
+    pub environments: Vec<String>,
+
+    /// The environment of `--env`, set by `pick_environment` for the commands that work on
+    /// a single one
+    #[structopt(skip)]
     pub environment: Option<String>,
 
+    /// The account to act on instead of the account_id of the configuration file, its
+    /// environments and CF_ACCOUNT_ID
+    #[structopt(name = "account-id", long = "account-id", global = true)]
+    pub account_id: Option<String>,
+
+    /// Use the credentials of [profiles.<name>] in the global configuration file rather than
+    /// those of its top level, instead of the `profile` of the configuration file. `login`,
+    /// `config` and `logout` write and remove the credentials of the profile
+    #[structopt(long, env = "WRANGLER_PROFILE", global = true)]
+    pub profile: Option<String>,
+
+    /// Answer yes to every confirmation without asking, like writing the configuration file
+    /// or deleting a namespace
+    #[structopt(long, short = "y", global = true)]
+    pub yes: bool,
+
+    /// Never wait for input: prompts with a safe answer take it, and the others fail with
+    /// what to pass instead. Also the case without a terminal, or with CI=true
+    #[structopt(
+        name = "non-interactive",
+        long = "non-interactive",
+        env = "WRANGLER_NON_INTERACTIVE",
+        global = true
+    )]
+    pub non_interactive: bool,
+
+    /// Publish to or delete from an environment of `protected_environments` without
+    /// confirming it first
+    #[structopt(name = "yes-i-know", long = "yes-i-know", global = true)]
+    pub yes_i_know: bool,
+
+    /// Ask the API again for lookups like the workers.dev subdomain, zone details and KV
+    /// namespaces instead of using the answers cached for a few minutes. `publish` and
+    /// `preview` also build again instead of restoring the last build, or the WebAssembly of a
+    /// Rust project from .wrangler/target-cache
+    #[structopt(name = "no-cache", long, global = true)]
+    pub no_cache: bool,
+
+    /// Append a line to this file for every request to the API that changes something, with
+    /// its endpoint, method, status and duration
+    #[structopt(
+        name = "audit-log",
+        long = "audit-log",
+        env = "WRANGLER_AUDIT_LOG",
+        global = true
+    )]
+    pub audit_log: Option<PathBuf>,
+
+    /// Seconds a request to the API may take, instead of `timeout` in the [http] table of the
+    /// global configuration file
+    #[structopt(long, global = true, value_name = "seconds")]
+    pub timeout: Option<u64>,
+
+    /// Seconds the upload of a script may take, instead of `upload_timeout` in the [http]
+    /// table of the global configuration file
+    #[structopt(
+        name = "upload-timeout",
+        long = "upload-timeout",
+        global = true,
+        value_name = "seconds"
+    )]
+    pub upload_timeout: Option<u64>,
+
+    /// Seconds connecting to the API may take, instead of `connect_timeout` in the [http]
+    /// table of the global configuration file
+    #[structopt(
+        name = "connect-timeout",
+        long = "connect-timeout",
+        global = true,
+        value_name = "seconds"
+    )]
+    pub connect_timeout: Option<u64>,
+
+    /// Seconds an idle connection is kept open for the next request, 0 to not reuse them,
+    /// instead of `keep_alive` in the [http] table of the global configuration file
+    #[structopt(
+        name = "keep-alive",
+        long = "keep-alive",
+        global = true,
+        value_name = "seconds"
+    )]
+    pub keep_alive: Option<u64>,
+
     #[structopt(subcommand)]
     pub command: Command,
 }
@@ -93,10 +250,54 @@ pub enum Command {
     #[structopt(name = "route", setting = AppSettings::SubcommandRequiredElseHelp)]
     Route(route::Route),
 
+    /// List the scripts of your account or download one of them
+    #[structopt(name = "scripts", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Scripts(scripts::Scripts),
+
+    /// Inspect the zone your worker's routes belong to
+    #[structopt(name = "zone", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Zone(zone::Zone),
+
+    /// Send test messages to a queue or look at what's waiting in it
+    #[structopt(name = "queues", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Queues(queues::Queues),
+
+    /// Summarize how much of the resource limits of your account is in use
+    #[structopt(name = "quota")]
+    Quota {
+        /// Warn about resources at or above this percentage of their limit
+        #[structopt(long, default_value = "80")]
+        threshold: f64,
+
+        /// Compare against the limits of the Workers Paid plan instead of the free plan
+        #[structopt(long)]
+        paid: bool,
+    },
+
+    /// Check that a published site serves the assets it was published with
+    #[structopt(name = "verify-site")]
+    VerifySite {
+        /// The URL the site is served at
+        #[structopt(index = 1)]
+        url: Url,
+
+        /// How many randomly picked assets to check
+        #[structopt(long, default_value = "20")]
+        sample: usize,
+    },
+
+    /// Debug the Durable Objects of your worker
+    #[structopt(name = "durable-objects", setting = AppSettings::SubcommandRequiredElseHelp)]
+    DurableObjects(durable_objects::DurableObjects),
+
     /// Generate a secret that can be referenced in the worker script
     #[structopt(name = "secret", setting = AppSettings::SubcommandRequiredElseHelp)]
     Secret(secret::Secret),
 
+    /// Encrypt the values of vars into your configuration file
+    #[structopt(name = "vars", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Vars(vars::Vars),
+
     /// Generate a new worker project
     Generate {
         /// The name of your worker!
@@ -131,8 +332,29 @@ pub enum Command {
         site: bool,
     },
 
+    /// Create a project from a worker published in the dashboard: download its script, and
+    /// write a wrangler.toml with its routes, bindings and cron triggers
+    Import {
+        /// The name of the published script
+        #[structopt(index = 1)]
+        name: String,
+
+        /// The directory to create the project in, named after the script by default
+        #[structopt(long, short = "o")]
+        output: Option<PathBuf>,
+    },
+
     /// Build your worker
-    Build,
+    Build {
+        /// Build again whenever your project changes
+        #[structopt(long)]
+        watch: bool,
+
+        /// How --watch reports problems: "plain" for messages, or "lsp" for Language Server
+        /// Protocol diagnostics on stdout, for editor plugins
+        #[structopt(long, default_value = "plain", possible_values = &["plain", "lsp"])]
+        output: WatchOutput,
+    },
 
     /// Preview your code temporarily on cloudflareworkers.com
     Preview {
@@ -140,21 +362,81 @@ pub enum Command {
         #[structopt(index = 1, default_value = "get")]
         method: HttpMethod,
 
-        /// URL to open in the worker preview
-        #[structopt(short = "u", long, default_value = "https://example.com")]
+        /// URL to open in the worker preview. A bare path (e.g. /api) is resolved against
+        /// https://example.com
+        #[structopt(short = "u", long, default_value = "https://example.com", parse(try_from_str = preview::parse_url))]
         url: Url,
 
         /// Body string to post to your preview worker request
         #[structopt(index = 2)]
         body: Option<String>,
 
+        /// Type of request to preview your worker with, overrides the positional method
+        #[structopt(name = "request-method", long = "method")]
+        request_method: Option<HttpMethod>,
+
+        /// Body to send with the request, overrides the positional body. Use @<file> to read
+        /// the body from a file
+        #[structopt(name = "request-body", long = "body")]
+        request_body: Option<String>,
+
+        /// Header to send with the request, in the form "Name: value". Can be repeated
+        #[structopt(long = "header", short = "H", number_of_values = 1, parse(try_from_str = preview::parse_header))]
+        headers: Vec<(String, String)>,
+
         /// Watch your project for changes and update the preview automagically
         #[structopt(long)]
         watch: bool,
 
-        /// Don't open the browser on preview
+        /// Don't open the browser on preview, and print the full response to stdout
         #[structopt(long)]
         headless: bool,
+
+        /// Fail unless the response has this status code. Implies --headless
+        #[structopt(name = "expect-status", long)]
+        expect_status: Option<u16>,
+
+        /// Fail unless the response has this header, in the form "Name: value". Can be
+        /// repeated. Implies --headless
+        #[structopt(name = "expect-header", long, number_of_values = 1, parse(try_from_str = preview::parse_header))]
+        expect_header: Vec<(String, String)>,
+
+        /// Fail unless the response body contains this text. Can be repeated. Implies
+        /// --headless
+        #[structopt(name = "expect-body-contains", long, number_of_values = 1)]
+        expect_body_contains: Vec<String>,
+
+        /// Send the request to the hostname of the first route of your configuration file
+        /// instead of example.com, so that cookies, caching and the like behave like they
+        /// do in production
+        #[structopt(long)]
+        zoned: bool,
+
+        /// Copy the link to share the preview with to the clipboard
+        #[structopt(long)]
+        copy: bool,
+
+        /// Stop updating a watched preview after this long (e.g. 30m, 2h)
+        #[structopt(long, requires = "watch", parse(try_from_str = previews::parse_age))]
+        expires: Option<Duration>,
+
+        /// Preview the last build of the script with the current content of its [site],
+        /// without building it again
+        #[structopt(name = "site-only", long, conflicts_with = "watch")]
+        site_only: bool,
+
+        /// Serve the assets of your [site] from a local server, and send the rest of the
+        /// requests to the preview of your worker, instead of uploading every asset first
+        #[structopt(
+            name = "local-assets",
+            long,
+            conflicts_with_all = &["expect-status", "expect-header", "expect-body-contains", "copy"]
+        )]
+        local_assets: bool,
+
+        /// Inspect the preview using Chrome DevTools, for breakpoints and the console
+        #[structopt(long, conflicts_with = "local-assets")]
+        inspect: bool,
     },
 
     /// Start a local server for developing your worker
@@ -189,6 +471,16 @@ pub enum Command {
         /// Run wrangler dev unauthenticated
         #[structopt(long)]
         unauthenticated: bool,
+
+        /// Randomly fail or delay proxied requests, e.g. "fetch:0.05,latency:0.1:500" fails 5%
+        /// of requests with a 503 and holds back 10% of them for 500ms
+        #[structopt(long)]
+        chaos: Option<Chaos>,
+
+        /// Copy the local KV namespaces, filled with `kv:key put --local --preview`, into the
+        /// preview namespaces of your bindings before starting
+        #[structopt(name = "local-kv", long, conflicts_with = "unauthenticated")]
+        local_kv: bool,
     },
 
     /// Publish your worker to the orange cloud
@@ -201,10 +493,252 @@ pub enum Command {
         #[structopt(possible_value = "json")]
         output: Option<String>,
 
+        /// Print what takes up the upload, largest first: the modules bundled into the script
+        /// going by its source map, and every other file as a whole
+        #[structopt(long)]
+        analyze: bool,
+
+        /// Publish the current git branch as its own worker on workers.dev, named
+        /// <name>-<branch>. Clean these up with `wrangler previews prune`
+        #[structopt(name = "branch-preview", long)]
+        branch_preview: bool,
+
+        /// Publish every [[workers]] entry of a workspace configuration file, in dependency
+        /// order
+        #[structopt(long, conflicts_with_all = &["only", "branch-preview"])]
+        all: bool,
+
+        /// Publish only this [[workers]] entry of a workspace configuration file. Can be
+        /// repeated
+        #[structopt(long, number_of_values = 1, conflicts_with = "branch-preview")]
+        only: Vec<String>,
+
+        /// Publish only the [[workers]] entries of a workspace configuration file whose
+        /// directory, or one of whose `shared` directories, changed since this git ref,
+        /// counting uncommitted and untracked files
+        #[structopt(name = "changed-since", long, conflicts_with = "branch-preview")]
+        changed_since: Option<String>,
+
+        /// How to report progress: "plain" for spinners and progress bars, or "json" for
+        /// one machine-readable event per line on stderr, status messages included
+        #[structopt(name = "progress-format", long, default_value = "plain", possible_values = &["plain", "json"])]
+        progress_format: ProgressFormat,
+
+        /// A note about this deployment, kept in its history with the git commit and the
+        /// time it was published. See them with `wrangler deployments list`
+        #[structopt(long, short = "m")]
+        message: Option<String>,
+
+        /// Skip uploading the script when it's the same as the last one published from
+        /// this project. Routes and schedules are deployed all the same
+        #[structopt(name = "if-changed", long)]
+        if_changed: bool,
+
+        /// Publish even though publishing is frozen by a .wrangler-freeze file or by
+        /// `frozen = true` under [protection]. The reason is recorded with the deployment
+        #[structopt(name = "override-freeze", long, value_name = "reason")]
+        override_freeze: Option<String>,
+
+        /// Refuse to publish a `type = "typescript"` project with type errors
+        #[structopt(name = "check-types", long)]
+        check_types: bool,
+
+        /// Publish without asking when the worker was last changed by another project or by
+        /// something else than `wrangler publish`
+        #[structopt(long)]
+        force: bool,
+
+        /// Publish from a git checkout with uncommitted changes although `dirty_tree =
+        /// "refuse"` under [protection]
+        #[structopt(name = "allow-dirty", long)]
+        allow_dirty: bool,
+
+        /// Tag the commit after publishing, as deploy/<environment>/<date> unless a name is
+        /// given
+        #[structopt(long, value_name = "name")]
+        tag: Option<Option<String>>,
+
+        /// Publish only the routes, workers.dev subdomain, cron triggers, bindings and vars of
+        /// the configuration file, without building or uploading the script again, like to
+        /// move a route during an incident
+        #[structopt(name = "config-only", long, alias = "schedule-only", conflicts_with_all = &["branch-preview", "analyze", "if-changed", "check-types"])]
+        config_only: bool,
+
+        /// Publish only the content of the [site] bucket, uploading the last build of the
+        /// script again with the new asset manifest rather than building it
+        #[structopt(name = "site-only", long, conflicts_with_all = &["branch-preview", "config-only", "if-changed", "check-types", "canary", "promote", "abort"])]
+        site_only: bool,
+
+        /// With --site-only, print the files that would be uploaded and the ones the bucket
+        /// doesn't have anymore, like `wrangler site diff`, without publishing anything
+        #[structopt(name = "dry-run", long, requires = "site-only", conflicts_with_all = &["all", "only", "changed-since", "all-envs"])]
+        dry_run: bool,
+
+        /// Publish the worker as <name>-canary and send this percentage of the requests to
+        /// its routes to it, through a <name>-splitter worker the routes point to meanwhile.
+        /// Run it again to change the percentage
+        #[structopt(long, value_name = "percent", conflicts_with_all = &["branch-preview", "all", "only", "changed-since", "config-only", "promote", "abort"])]
+        canary: Option<u8>,
+
+        /// Finish a canary rollout by publishing the build of the canary over the worker and
+        /// pointing its routes back to it, deleting the canary and the splitter
+        #[structopt(long, conflicts_with_all = &["branch-preview", "all", "only", "changed-since", "config-only", "abort"])]
+        promote: bool,
+
+        /// Give up on a canary rollout, pointing the routes back to the worker as it was
+        /// and deleting the canary and the splitter
+        #[structopt(long, conflicts_with_all = &["branch-preview", "all", "only", "changed-since", "config-only"])]
+        abort: bool,
+
+        /// Publish the worker under this name instead of the one of the configuration file
+        /// and the environment, like a canary of it
+        #[structopt(name = "worker-name", long = "name", conflicts_with_all = &["all", "only", "all-envs"])]
+        name: Option<String>,
+
+        /// Publish to every environment of the configuration file, like to several --env.
+        /// The script is built once for the environments that build it the same way
+        #[structopt(name = "all-envs", long, conflicts_with_all = &["branch-preview", "all", "only", "changed-since", "canary", "promote", "abort"])]
+        all_envs: bool,
+
+        /// When publishing to several environments, stop at the first one that fails
+        /// instead of publishing to the rest
+        #[structopt(name = "fail-fast", long)]
+        fail_fast: bool,
+
+        /// Write a JSON report of the publish to this file, with the URLs, bindings, uploads,
+        /// time taken per phase and IDs of the API requests of every worker, whether the
+        /// publish succeeds or not
+        #[structopt(long, value_name = "path", parse(from_os_str))]
+        report: Option<PathBuf>,
+
         #[structopt(flatten)]
         migration: AdhocMigration,
     },
 
+    /// Check that your configuration would publish, without building or deploying anything
+    #[structopt(name = "check")]
+    Check {
+        /// Only check the configuration file, skipping the checks against your Cloudflare
+        /// account
+        #[structopt(long)]
+        offline: bool,
+    },
+
+    /// Find out which compatibility date and flags your worker needs
+    #[structopt(name = "compat", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Compat(compat::Compat),
+
+    /// Format your configuration file: sort its keys, write kv_namespaces as
+    /// [[kv_namespaces]] tables and remove trailing whitespace. Comments are kept, except
+    /// those above the keys it moves
+    #[structopt(name = "fmt")]
+    Fmt {
+        /// Only check whether the configuration file is formatted, failing if it isn't
+        #[structopt(long)]
+        check: bool,
+    },
+
+    /// View the history of deployments recorded by `wrangler publish`
+    #[structopt(name = "deployments", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Deployments(deployments::Deployments),
+
+    /// List or clean up the branch previews made by `wrangler publish --branch-preview`
+    #[structopt(name = "previews", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Previews(previews::Previews),
+
+    /// Set up a Workers Site, or clean up the asset namespaces of yours
+    #[structopt(name = "site", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Site(site::Site),
+
+    /// Print which workers of your configuration file bind to or depend on which others, as
+    /// a Graphviz DOT or Mermaid graph
+    #[structopt(name = "graph")]
+    Graph {
+        /// Output format of the graph
+        #[structopt(long, short = "f", default_value = "dot", possible_values = &["dot", "mermaid"])]
+        format: GraphFormat,
+    },
+
+    /// Print a completion script for your shell, which also completes the environments and
+    /// KV bindings of your configuration file in bash, zsh and fish
+    #[structopt(name = "completions")]
+    Completions {
+        /// The shell to complete wrangler in
+        #[structopt(possible_values = &["bash", "zsh", "fish", "powershell"])]
+        shell: Shell,
+    },
+
+    /// List the environments or KV bindings of the configuration file for completion scripts
+    #[structopt(name = "__complete", setting = AppSettings::Hidden)]
+    Complete {
+        #[structopt(possible_values = &["environments", "kv-bindings"])]
+        kind: CompletionKind,
+    },
+
+    /// Estimate the monthly cost of your worker on the bundled and unbound usage models
+    #[structopt(name = "estimate")]
+    Estimate {
+        /// Requests per month (e.g. 50M). Projected from your worker's analytics when omitted
+        #[structopt(long, requires = "avg-cpu-ms", parse(try_from_str = estimate::parse_count))]
+        requests: Option<f64>,
+
+        /// Average CPU time per request, in milliseconds
+        #[structopt(name = "avg-cpu-ms", long = "avg-cpu-ms", requires = "requests")]
+        avg_cpu_ms: Option<f64>,
+
+        /// Average duration per request, in milliseconds, billed by the unbound usage model.
+        /// Defaults to --avg-cpu-ms
+        #[structopt(
+            name = "avg-duration-ms",
+            long = "avg-duration-ms",
+            requires = "requests"
+        )]
+        avg_duration_ms: Option<f64>,
+
+        /// Number of past days of analytics to project a month from
+        #[structopt(long, default_value = "7")]
+        days: u32,
+    },
+
+    /// Send a request to the Cloudflare API with your credentials and print the response,
+    /// for endpoints wrangler has no command for. The :account and :zone segments of the
+    /// path are replaced with the account_id and zone_id of your configuration file
+    #[structopt(name = "api")]
+    Api {
+        /// The HTTP method, e.g. GET or POST
+        #[structopt(index = 1)]
+        method: String,
+
+        /// The path of the endpoint, e.g. /accounts/:account/workers/scripts
+        #[structopt(index = 2)]
+        path: String,
+
+        /// The body of the request, JSON unless a Content-Type header says otherwise. Use
+        /// @<file> to read it from a file
+        #[structopt(long, short = "d")]
+        data: Option<String>,
+
+        /// Header to send with the request, in the form "Name: value". Can be repeated
+        #[structopt(long = "header", short = "H", number_of_values = 1, parse(try_from_str = preview::parse_header))]
+        headers: Vec<(String, String)>,
+    },
+
+    /// Show the requests, errors, CPU time and subrequests of your worker from its analytics
+    #[structopt(name = "stats")]
+    Stats {
+        /// How far back to look (e.g. 30m, 24h, 7d)
+        #[structopt(long, default_value = "24h", parse(try_from_str = previews::parse_age))]
+        since: Duration,
+
+        /// Show every worker of the account instead of just this one
+        #[structopt(long)]
+        all: bool,
+
+        /// Print a table, or JSON for scripts
+        #[structopt(long, short = "f", default_value = "table", possible_values = &["table", "json"])]
+        format: StatsFormat,
+    },
+
     /// Authenticate Wrangler with a Cloudflare API Token or Global API Key
     #[structopt(name = "config")]
     Config {
@@ -215,6 +749,9 @@ pub enum Command {
         /// Do not verify provided credentials before writing out Wrangler config file
         #[structopt(name = "no-verify", long)]
         no_verify: bool,
+
+        #[structopt(subcommand)]
+        command: Option<config::ConfigCommand>,
     },
 
     /// Configure your workers.dev subdomain
@@ -229,6 +766,11 @@ pub enum Command {
     #[structopt(name = "whoami")]
     Whoami,
 
+    /// Check the tools your project builds with, your credentials, the connection to the
+    /// Cloudflare API and your configuration file, with hints to fix what's wrong
+    #[structopt(name = "doctor")]
+    Doctor,
+
     /// View a stream of logs from a published worker
     #[structopt(name = "tail")]
     Tail {
@@ -268,6 +810,19 @@ pub enum Command {
         #[structopt(long)]
         search: Option<String>,
 
+        /// Only show requests that used at least --threshold of CPU time
+        #[structopt(long = "slow-only")]
+        slow_only: bool,
+
+        /// CPU time for --slow-only, like 50ms or 1s
+        #[structopt(long, default_value = "50ms", parse(try_from_str = parse_milliseconds))]
+        threshold: u64,
+
+        /// Map the locations in stack traces back to the sources, with the source maps of the
+        /// last publish from this project
+        #[structopt(long)]
+        symbolicate: bool,
+
         /// Set the URL to forward log messages
         #[structopt(hidden = true)]
         url: Option<Url>,
@@ -281,6 +836,10 @@ pub enum Command {
         metrics_port: Option<u16>,
     },
 
+    /// Retrieve the logs of a published worker archived by Logpush, or its recent errors
+    #[structopt(name = "logs", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Logs(logs::Logs),
+
     /// Authenticate wrangler with your Cloudflare username and password
     #[structopt(name = "login")]
     Login {
@@ -293,11 +852,28 @@ pub enum Command {
         scopes_list: bool,
     },
 
+    /// Manage short-lived credentials
+    #[structopt(name = "auth", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Auth(auth::Auth),
+
     /// Logout from your current authentication method and remove any configuration files.
     /// It does not logout if you have authenticated wrangler through environment variables.
     #[structopt(name = "logout")]
     Logout,
 
+    /// Serve recorded responses of the Cloudflare API locally, for running wrangler end to
+    /// end without credentials by setting CF_API_BASE_URL to it
+    #[structopt(name = "mock-api", setting = AppSettings::Hidden)]
+    MockApi {
+        /// Port to serve the fixtures on
+        #[structopt(long, short = "p", default_value = "8788")]
+        port: u16,
+
+        /// JSON file of fixtures to serve, which take precedence over the built-in ones
+        #[structopt(long)]
+        fixtures: Option<PathBuf>,
+    },
+
     /// Report an error caught by wrangler to Cloudflare
     #[structopt(name = "report")]
     Report {
@@ -335,6 +911,190 @@ pub struct AdhocMigration {
     new_tag: Option<String>,
 }
 
+impl Cli {
+    /// The settings of the configuration file given on the command line
+    pub fn overrides(&self) -> Overrides {
+        Overrides {
+            account_id: self.account_id.clone(),
+            name: match &self.command {
+                Command::Publish { name, .. } => name.clone(),
+                _ => None,
+            },
+        }
+    }
+
+    /// The timeouts of the [http] table of the global configuration file, with the flags of
+    /// the same names over them. Commands that work offline leave the file alone.
+    pub fn timeouts(&self) -> Result<Timeouts, anyhow::Error> {
+        let mut timeouts = if self.command.is_offline() {
+            Timeouts::default()
+        } else {
+            settings::get_timeouts()?
+        };
+        timeouts.timeout = self.timeout.unwrap_or(timeouts.timeout);
+        timeouts.upload_timeout = self.upload_timeout.unwrap_or(timeouts.upload_timeout);
+        timeouts.connect_timeout = self.connect_timeout.unwrap_or(timeouts.connect_timeout);
+        timeouts.keep_alive = self.keep_alive.unwrap_or(timeouts.keep_alive);
+        Ok(timeouts)
+    }
+
+    /// The profile to use the credentials of: the one of `--profile`, or else the `profile`
+    /// of the environments, or the top level, of the configuration file. Publishing to several
+    /// environments fails unless they all have the same one.
+    pub fn profile(&self) -> Result<Option<String>, anyhow::Error> {
+        if self.profile.is_some() || !self.command.reads_config() {
+            return Ok(self.profile.clone());
+        }
+        let environments = match &self.command {
+            Command::Publish { all_envs: true, .. } => {
+                Manifest::read_environment_names(&self.config)
+                    .into_iter()
+                    .map(Some)
+                    .collect()
+            }
+            _ if self.environments.len() > 1 => {
+                self.environments.iter().cloned().map(Some).collect()
+            }
+            _ => vec![self.environment.clone()],
+        };
+        Manifest::read_shared_profile(&self.config, &environments)
+    }
+
+    /// Sets the environment to perform the command on from `--env`, which only `publish`
+    /// takes more than once
+    pub fn pick_environment(&mut self) -> Result<(), anyhow::Error> {
+        if self.environments.len() > 1 && !matches!(self.command, Command::Publish { .. }) {
+            anyhow::bail!("Only `wrangler publish` takes more than one --env")
+        }
+        self.environment = match self.environments.as_slice() {
+            [environment] => Some(environment.clone()),
+            _ => None,
+        };
+        Ok(())
+    }
+
+    pub fn verbosity(&self) -> Verbosity {
+        // stdout belongs to the diagnostics then
+        if let Command::Build {
+            output: WatchOutput::Lsp,
+            ..
+        } = self.command
+        {
+            return Verbosity::Quiet;
+        }
+        match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (_, true) => Verbosity::Verbose,
+            _ => Verbosity::Normal,
+        }
+    }
+
+    /// Switches to the closest parent directory with a configuration file when the
+    /// current one doesn't have it, since the paths in a configuration file are relative to
+    /// its directory. Paths given on the command line are made absolute beforehand so they
+    /// keep pointing to what the user meant.
+    pub fn locate_config(&mut self) -> Result<(), anyhow::Error> {
+        // only a bare file name is looked for in parent directories, not a path to a file
+        if !self.command.reads_config()
+            || self.config.exists()
+            || self.config.components().count() != 1
+        {
+            return Ok(());
+        }
+        let current_dir = env::current_dir()?;
+        let config_path = match Manifest::find(&current_dir, &self.config) {
+            Some(config_path) => config_path,
+            None => return Ok(()),
+        };
+        let project_dir = config_path.parent().unwrap_or(&current_dir);
+
+        self.command.make_paths_absolute(&current_dir);
+        log::info!("Running in {}", project_dir.display());
+        env::set_current_dir(project_dir)?;
+        Ok(())
+    }
+}
+
+impl Command {
+    fn reads_config(&self) -> bool {
+        !matches!(
+            self,
+            Command::Generate { .. }
+                | Command::Init { .. }
+                | Command::Import { .. }
+                | Command::Config { command: None, .. }
+                | Command::Login { .. }
+                | Command::Logout
+                | Command::Auth(_)
+                | Command::Whoami
+                | Command::MockApi { .. }
+                | Command::Report { .. }
+                | Command::Completions { .. }
+        )
+    }
+
+    /// Whether the command only works on the project, without sending requests or reading
+    /// your credentials
+    pub fn is_offline(&self) -> bool {
+        matches!(
+            self,
+            Command::Build { .. }
+                | Command::Check { offline: true }
+                | Command::Fmt { .. }
+                | Command::Config {
+                    command: Some(_),
+                    ..
+                }
+                | Command::Graph { .. }
+                | Command::Vars(_)
+                | Command::Completions { .. }
+                | Command::Complete { .. }
+        )
+    }
+
+    fn make_paths_absolute(&mut self, dir: &Path) {
+        let absolute = |path: &mut PathBuf| *path = dir.join(&*path);
+        match self {
+            Command::KvKey(kv::KvKey::Put {
+                value, path: true, ..
+            }) if value.as_str() != STDIN => {
+                *value = dir.join(&value).to_string_lossy().to_string()
+            }
+            Command::KvKey(kv::KvKey::Get {
+                batch,
+                output_dir,
+                output,
+                ..
+            }) => {
+                batch
+                    .iter_mut()
+                    .filter(|batch| batch.as_os_str() != "-")
+                    .for_each(absolute);
+                output_dir.iter_mut().for_each(absolute);
+                output.iter_mut().for_each(absolute);
+            }
+            Command::KvBulk(kv::KvBulk::Put { path, .. })
+            | Command::KvBulk(kv::KvBulk::Delete { path, .. }) => absolute(path),
+            Command::KvNamespace(kv::KvNamespace::Export { output, .. }) => absolute(output),
+            Command::KvNamespace(kv::KvNamespace::Import { input, .. }) => absolute(input),
+            Command::Logs(logs::Logs::Download { output, .. }) => {
+                output.iter_mut().for_each(absolute)
+            }
+            Command::Scripts(scripts::Scripts::Download { output, .. }) => {
+                output.iter_mut().for_each(absolute)
+            }
+            Command::Api {
+                data: Some(data), ..
+            } => {
+                if let Some(path) = data.strip_prefix('@') {
+                    *data = format!("@{}", dir.join(path).display());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 impl AdhocMigration {
     pub fn into_migrations(self) -> Option<Migrations> {
         let migration = DurableObjectsMigration {
@@ -407,6 +1167,21 @@ fn parse_ip_address(input: &str) -> Result<String, anyhow::Error> {
     }
 }
 
+// A duration like 50ms or 2s, in milliseconds
+fn parse_milliseconds(input: &str) -> Result<u64, anyhow::Error> {
+    let (number, scale) = match input.strip_suffix("ms") {
+        Some(number) => (number, 1),
+        None => match input.strip_suffix('s') {
+            Some(number) => (number, 1000),
+            None => (input, 1),
+        },
+    };
+    match number.trim().parse::<u64>() {
+        Ok(number) => Ok(number * scale),
+        Err(_) => anyhow::bail!("{} should be a duration like 50ms or 1s", input),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -481,4 +1256,213 @@ mod tests {
             assert!(false, "Unkown command {:?}", command)
         }
     }
+
+    #[test]
+    fn it_keeps_command_line_paths_relative_to_where_wrangler_runs() {
+        let dir = Path::new("/project/handlers");
+        let mut command =
+            Cli::from_iter(&["wrangler", "kv:bulk", "put", "-b", "KV", "keys.json"]).command;
+        command.make_paths_absolute(dir);
+        match command {
+            Command::KvBulk(kv::KvBulk::Put { path, .. }) => {
+                assert_eq!(path, dir.join("keys.json"))
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        let mut command =
+            Cli::from_iter(&["wrangler", "kv:key", "get", "-b", "KV", "--batch", "-"]).command;
+        command.make_paths_absolute(dir);
+        match command {
+            Command::KvKey(kv::KvKey::Get { batch, .. }) => {
+                assert_eq!(batch, Some(PathBuf::from("-")))
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        let mut command = Cli::from_iter(&[
+            "wrangler", "kv:key", "get", "-b", "KV", "logo", "-o", "logo.png",
+        ])
+        .command;
+        command.make_paths_absolute(dir);
+        match command {
+            Command::KvKey(kv::KvKey::Get { output, .. }) => {
+                assert_eq!(output, Some(dir.join("logo.png")))
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+        assert!(!Command::Whoami.reads_config());
+    }
+
+    #[test]
+    fn it_publishes_to_several_environments_only() {
+        let mut cli = Cli::from_iter(&["wrangler", "publish", "--env", "staging", "-e", "prod"]);
+        cli.pick_environment().unwrap();
+        assert_eq!(cli.environments, vec!["staging", "prod"]);
+        assert_eq!(cli.environment, None);
+
+        let mut cli = Cli::from_iter(&["wrangler", "-e", "staging", "build"]);
+        cli.pick_environment().unwrap();
+        assert_eq!(cli.environment.as_deref(), Some("staging"));
+
+        let mut cli = Cli::from_iter(&["wrangler", "build", "-e", "staging", "-e", "prod"]);
+        assert!(cli.pick_environment().is_err());
+    }
+
+    #[test]
+    fn it_reads_the_verbosity_from_any_position() {
+        let cli = Cli::from_iter(&["wrangler", "publish", "-q"]);
+        assert_eq!(cli.verbosity(), Verbosity::Quiet);
+        let cli = Cli::from_iter(&["wrangler", "--verbose", "whoami"]);
+        assert_eq!(cli.verbosity(), Verbosity::Verbose);
+        assert!(Cli::from_iter_safe(&["wrangler", "whoami", "--quiet", "--verbose"]).is_err());
+        let cli = Cli::from_iter(&["wrangler", "build", "--watch", "--output", "lsp"]);
+        assert_eq!(cli.verbosity(), Verbosity::Quiet);
+    }
+
+    #[test]
+    fn it_parses_tail_thresholds() {
+        assert_eq!(parse_milliseconds("50ms").unwrap(), 50);
+        assert_eq!(parse_milliseconds("2s").unwrap(), 2000);
+        assert_eq!(parse_milliseconds("75").unwrap(), 75);
+        assert!(parse_milliseconds("fast").is_err());
+    }
+
+    #[test]
+    fn it_only_takes_usage_figures_together() {
+        let estimate = |args: &[&str]| {
+            Cli::from_iter_safe(std::iter::once("wrangler").chain(args.iter().copied())).is_ok()
+        };
+        assert!(estimate(&["estimate"]));
+        assert!(estimate(&[
+            "estimate",
+            "--requests",
+            "50M",
+            "--avg-cpu-ms",
+            "5"
+        ]));
+        assert!(!estimate(&["estimate", "--requests", "50M"]));
+        assert!(!estimate(&["estimate", "--avg-cpu-ms", "5"]));
+        assert!(!estimate(&["estimate", "--avg-duration-ms", "20"]));
+    }
+
+    #[test]
+    fn it_takes_overrides_of_the_configuration_file() {
+        let cli = Cli::from_iter(&[
+            "wrangler",
+            "publish",
+            "--name",
+            "canary-worker",
+            "--account-id",
+            "abc",
+        ]);
+        assert_eq!(
+            cli.overrides(),
+            Overrides {
+                account_id: Some("abc".to_string()),
+                name: Some("canary-worker".to_string()),
+            }
+        );
+
+        let cli = Cli::from_iter(&["wrangler", "secret", "put", "--name", "TOKEN"]);
+        assert_eq!(cli.overrides(), Overrides::default());
+    }
+
+    #[test]
+    fn it_runs_project_only_commands_offline() {
+        let offline = |args: &[&str]| Cli::from_iter(args).command.is_offline();
+        assert!(offline(&["wrangler", "build"]));
+        assert!(offline(&["wrangler", "check", "--offline"]));
+        assert!(offline(&["wrangler", "fmt", "--check"]));
+        assert!(offline(&["wrangler", "config", "migrate", "--yes"]));
+        assert!(offline(&["wrangler", "vars", "encrypt", "TOKEN"]));
+        assert!(!offline(&["wrangler", "config", "--api-key"]));
+        assert!(!offline(&["wrangler", "check"]));
+        assert!(!offline(&["wrangler", "publish"]));
+    }
+
+    fn profile_of(config: &str, args: &[&str]) -> Result<Option<String>, anyhow::Error> {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("wrangler.toml");
+        std::fs::write(&config_path, config).unwrap();
+        let mut args = args.to_vec();
+        let config_path = config_path.to_string_lossy().to_string();
+        args.extend(&["--config", &config_path]);
+        let mut cli = Cli::from_iter(&args);
+        cli.pick_environment()?;
+        cli.profile()
+    }
+
+    #[test]
+    fn it_uses_the_profile_environments_share() {
+        let config = r#"
+            name = "worker"
+            profile = "personal"
+            [env.staging]
+            profile = "work"
+            [env.production]
+            profile = "work"
+        "#;
+        let profile = |args: &[&str]| profile_of(config, args).unwrap();
+        assert_eq!(
+            profile(&["wrangler", "publish"]).as_deref(),
+            Some("personal")
+        );
+        assert_eq!(
+            profile(&[
+                "wrangler",
+                "publish",
+                "--env",
+                "staging",
+                "--env",
+                "production"
+            ])
+            .as_deref(),
+            Some("work")
+        );
+        assert_eq!(
+            profile(&["wrangler", "publish", "--all-envs"]).as_deref(),
+            Some("work")
+        );
+    }
+
+    #[test]
+    fn it_refuses_environments_of_different_profiles() {
+        let config = r#"
+            name = "worker"
+            [env.staging]
+            profile = "test-account"
+            [env.production]
+            profile = "work"
+        "#;
+        assert!(profile_of(
+            config,
+            &[
+                "wrangler",
+                "publish",
+                "--env",
+                "staging",
+                "--env",
+                "production"
+            ]
+        )
+        .is_err());
+        assert!(profile_of(config, &["wrangler", "publish", "--all-envs"]).is_err());
+        assert_eq!(
+            profile_of(config, &["wrangler", "publish", "--env", "staging"])
+                .unwrap()
+                .as_deref(),
+            Some("test-account")
+        );
+        // a profile passed on the command line is the one every environment is published with
+        assert_eq!(
+            profile_of(
+                config,
+                &["wrangler", "publish", "--all-envs", "--profile", "work"]
+            )
+            .unwrap()
+            .as_deref(),
+            Some("work")
+        );
+    }
 }
@@ -1,22 +1,46 @@
 use super::Cli;
 use crate::commands;
-use crate::preview::{HttpMethod, PreviewOpt};
-use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::preview::{Expectations, HttpMethod, PreviewOpt};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{route, Manifest};
 
-use anyhow::{ensure, Result};
+use std::fs;
+
+use anyhow::{ensure, Context, Result};
+use chrono::Duration;
 use url::Url;
 
+const DEFAULT_PREVIEW_URL: &str = "https://example.com";
+
+#[allow(clippy::too_many_arguments)]
 pub fn preview(
     method: HttpMethod,
     url: Url,
     body: Option<String>,
+    headers: Vec<(String, String)>,
     watch: bool,
     headless: bool,
+    expect: Expectations,
+    zoned: bool,
+    copy: bool,
+    expires: Option<Duration>,
+    site_only: bool,
+    local_assets: bool,
+    inspect: bool,
+    no_cache: bool,
     cli_params: &Cli,
 ) -> Result<()> {
     log::info!("Getting project settings");
     let manifest = Manifest::new(&cli_params.config)?;
     let target = manifest.get_target(cli_params.environment.as_deref(), true)?;
+    let url = if zoned {
+        zoned_url(
+            url,
+            &manifest.route_patterns(cli_params.environment.as_deref())?,
+        )?
+    } else {
+        url
+    };
 
     // the preview command can be called with or without a Global User having been config'd
     // so we convert this Result into an Option
@@ -28,13 +52,114 @@ pub fn preview(
         "Invalid URL scheme (use either \"https\" or \"http\")"
     );
 
+    let body = match body {
+        Some(body) => Some(read_body(body)?),
+        None => None,
+    };
+
     let options = PreviewOpt {
         method,
         url,
         body,
+        headers,
         livereload: watch,
-        headless,
+        // there's nobody to look at a browser when checking the response
+        headless: headless || !expect.is_empty(),
+        expect,
+        copy,
+        expires: match expires {
+            Some(expires) => Some(expires.to_std()?),
+            None => None,
+        },
+        site_only,
+        local_assets,
+        no_cache,
+        inspect,
     };
 
     commands::preview(target, user, options, cli_params.verbose)
 }
+
+/// Parses the preview URL, resolving bare paths like `/api?x=1` against the default host.
+pub fn parse_url(input: &str) -> Result<Url> {
+    if input.starts_with('/') {
+        let base = Url::parse(DEFAULT_PREVIEW_URL)?;
+        Ok(base.join(input)?)
+    } else {
+        Ok(Url::parse(input)?)
+    }
+}
+
+/// Parses a `Name: value` header passed on the command line.
+pub fn parse_header(input: &str) -> Result<(String, String)> {
+    match input.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => anyhow::bail!(
+            "Invalid header \"{}\" (use the form \"Name: value\")",
+            input
+        ),
+    }
+}
+
+/// Points the preview URL at the hostname of the first route, unless it already has a host
+/// of its own, so that the worker sees the hostname it's published to
+fn zoned_url(mut url: Url, route_patterns: &[String]) -> Result<Url> {
+    let default_host = Url::parse(DEFAULT_PREVIEW_URL)?;
+    if url.host_str() != default_host.host_str() {
+        return Ok(url);
+    }
+    let host = match route_patterns.first() {
+        Some(pattern) => route::hostname(pattern),
+        None => anyhow::bail!(
+            "--zoned previews against the hostname of a route, but there are no routes in your configuration file"
+        ),
+    };
+    url.set_host(Some(&host))?;
+    Ok(url)
+}
+
+// `@path/to/file` reads the request body from a file, like curl does.
+pub(crate) fn read_body(body: String) -> Result<String> {
+    match body.strip_prefix('@') {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("Could not read request body from {}", path)),
+        None => Ok(body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_resolves_bare_paths_against_the_default_url() {
+        let url = parse_url("/api/users?page=2").unwrap();
+        assert_eq!(url.as_str(), "https://example.com/api/users?page=2");
+
+        let url = parse_url("http://my.site/path").unwrap();
+        assert_eq!(url.as_str(), "http://my.site/path");
+    }
+
+    #[test]
+    fn it_previews_zoned_against_the_first_route() {
+        let routes = vec!["*.shop.example.org/api/*".to_string()];
+        let url = zoned_url(parse_url("/api/cart?id=1").unwrap(), &routes).unwrap();
+        assert_eq!(url.as_str(), "https://shop.example.org/api/cart?id=1");
+
+        let url = zoned_url(parse_url("https://my.site/").unwrap(), &routes).unwrap();
+        assert_eq!(url.as_str(), "https://my.site/");
+        assert!(zoned_url(parse_url("/").unwrap(), &[]).is_err());
+    }
+
+    #[test]
+    fn it_parses_headers() {
+        assert_eq!(
+            parse_header("X-Foo: bar: baz").unwrap(),
+            ("X-Foo".to_string(), "bar: baz".to_string())
+        );
+        assert!(parse_header("X-Foo").is_err());
+        assert!(parse_header(": bar").is_err());
+    }
+}
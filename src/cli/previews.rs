@@ -0,0 +1,95 @@
+use super::Cli;
+use crate::commands;
+use crate::commands::previews::PruneOptions;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use chrono::Duration;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Previews {
+    /// List the branch previews published from this machine
+    List,
+    /// Delete the branch previews of your worker whose git branch no longer exists
+    Prune {
+        /// Delete every branch preview of your worker, even if its branch still exists
+        #[structopt(long)]
+        all: bool,
+
+        /// Also delete previews of branches that were merged into the default branch
+        #[structopt(long)]
+        merged: bool,
+
+        /// Also delete previews that weren't published to in this long (e.g. 14d, 12h, 2w)
+        #[structopt(name = "older-than", long, parse(try_from_str = parse_age))]
+        older_than: Option<Duration>,
+
+        /// Only print the previews that would be deleted
+        #[structopt(name = "dry-run", long)]
+        dry_run: bool,
+    },
+}
+
+pub fn previews(previews: Previews, cli_params: &Cli) -> Result<()> {
+    match previews {
+        Previews::List => commands::previews::list(),
+        Previews::Prune {
+            all,
+            merged,
+            older_than,
+            dry_run,
+        } => {
+            let user = GlobalUser::new()?;
+            let manifest = Manifest::new(&cli_params.config)?;
+            let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+            // the workers the configuration publishes to, which are never previews
+            let mut configured = vec![manifest.worker_name(None)];
+            for env in manifest.environment_names() {
+                configured.push(manifest.worker_name(Some(&env)));
+            }
+            let options = PruneOptions {
+                all,
+                merged,
+                older_than,
+                dry_run,
+            };
+            commands::previews::prune(&user, &target, &configured, options)
+        }
+    }
+}
+
+pub(crate) fn parse_age(input: &str) -> Result<Duration> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+    let amount: i64 = match amount.parse() {
+        Ok(amount) => amount,
+        Err(_) => anyhow::bail!("Invalid age \"{}\" (use e.g. 14d, 12h or 2w)", input),
+    };
+
+    match unit {
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" | "" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => anyhow::bail!("Invalid age \"{}\" (use e.g. 14d, 12h or 2w)", input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_ages() {
+        assert_eq!(parse_age("14d").unwrap(), Duration::days(14));
+        assert_eq!(parse_age("12h").unwrap(), Duration::hours(12));
+        assert_eq!(parse_age("2w").unwrap(), Duration::weeks(2));
+        assert_eq!(parse_age("3").unwrap(), Duration::days(3));
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("14 days").is_err());
+    }
+}
@@ -1,18 +1,54 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
 use super::AdhocMigration;
 use super::Cli;
 use crate::commands;
-use crate::settings::{global_user::GlobalUser, toml::Manifest};
-use crate::terminal::message::{Message, Output, StdOut};
+use crate::commands::canary;
+use crate::commands::deployments::{Deployment, Ownership};
+use crate::commands::git::{self, Checkout};
+use crate::commands::hooks::{self, HookContext};
+use crate::commands::publish::PublishOutput;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{DirtyTree, Manifest, Target};
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, Output, StdErr, StdOut};
+use crate::terminal::progress::{self, ProgressFormat};
 use crate::terminal::styles;
 
 use anyhow::Result;
 
+#[allow(clippy::too_many_arguments)]
 pub fn publish(
     release: bool,
     output: Option<String>,
     migration: AdhocMigration,
+    analyze: bool,
+    branch_preview: bool,
+    all: bool,
+    only: Vec<String>,
+    changed_since: Option<String>,
+    progress_format: ProgressFormat,
+    message: Option<String>,
+    no_cache: bool,
+    if_changed: bool,
+    override_freeze: Option<String>,
+    check_types: bool,
+    force: bool,
+    allow_dirty: bool,
+    tag: Option<Option<String>>,
+    config_only: bool,
+    site_only: bool,
+    dry_run: bool,
+    canary: Option<u8>,
+    promote: bool,
+    abort: bool,
+    all_envs: bool,
+    fail_fast: bool,
     cli_params: &Cli,
 ) -> Result<()> {
+    progress::set_format(progress_format);
+
     log::info!("Getting User settings");
     let user = GlobalUser::new()?;
 
@@ -30,17 +66,740 @@ pub fn publish(
     let manifest = Manifest::new(&cli_params.config)?;
     manifest.warn_about_compatibility_date();
 
+    let output = if output.as_deref() == Some("json") {
+        Output::Json
+    } else {
+        Output::PlainText
+    };
+
+    let migration = migration.into_migrations();
+    if config_only && migration.is_some() {
+        anyhow::bail!("Durable Object migrations are uploaded with the script, so they can't be published with --config-only")
+    }
+
+    let environments = if all_envs {
+        manifest.environment_names()
+    } else {
+        cli_params.environments.clone()
+    };
+    if all_envs || environments.len() > 1 {
+        if migration.is_some() {
+            anyhow::bail!("Durable Object migrations can't be passed on the command line when publishing to several environments; add them to each environment's configuration instead")
+        }
+        if branch_preview || all || !only.is_empty() || changed_since.is_some() {
+            anyhow::bail!(
+                "--branch-preview, --all, --only and --changed-since publish a single environment"
+            )
+        }
+        if canary.is_some() || promote || abort {
+            anyhow::bail!("A canary rollout is of a single environment")
+        }
+        if dry_run {
+            anyhow::bail!("--dry-run compares the bucket of a single environment")
+        }
+        if environments.is_empty() {
+            anyhow::bail!("There are no environments in your configuration file to publish to")
+        }
+        return publish_environments(
+            &user,
+            &manifest,
+            &environments,
+            output,
+            analyze,
+            no_cache,
+            if_changed,
+            message,
+            override_freeze.as_deref(),
+            check_types,
+            force,
+            allow_dirty,
+            tag,
+            config_only,
+            site_only,
+            fail_fast,
+            cli_params.yes_i_know,
+        );
+    }
+
+    if all || !only.is_empty() || changed_since.is_some() {
+        if migration.is_some() {
+            anyhow::bail!("Durable Object migrations can't be passed on the command line when publishing several workers; add them to each worker's configuration instead")
+        }
+        let only = match changed_since {
+            Some(git_ref) => match changed_workers(&manifest, &git_ref, only, cli_params)? {
+                Some(changed) => changed,
+                None => return Ok(()),
+            },
+            None => only,
+        };
+        return publish_workspace(
+            &user,
+            &manifest,
+            &only,
+            output,
+            analyze,
+            no_cache,
+            if_changed,
+            message,
+            override_freeze.as_deref(),
+            check_types,
+            force,
+            allow_dirty,
+            tag,
+            config_only,
+            site_only,
+            cli_params,
+        );
+    }
+
     let mut target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    if dry_run {
+        return commands::site::diff(&user, &target);
+    }
 
-    if let Some(migration) = migration.into_migrations() {
+    if let Some(migration) = migration {
         target.migrations = Some(migration);
     }
+    if check_types {
+        commands::build::check_types(&target)?;
+    }
+    let checkout = Checkout::current();
+    if let Some(checkout) = &checkout {
+        checkout.annotate(&mut target);
+    }
 
-    let output = if output.as_deref() == Some("json") {
-        Output::Json
+    // a branch preview is a worker of its own on workers.dev, which no freeze is about
+    if branch_preview {
+        let (deploy_config, preview) = commands::previews::prepare(&mut target)?;
+        commands::publish(
+            &user,
+            &mut target,
+            deploy_config,
+            output,
+            analyze,
+            no_cache,
+            if_changed,
+        )?;
+        return commands::previews::record(preview);
+    }
+
+    let freeze_override = check_freeze(
+        &manifest,
+        cli_params.environment.as_deref(),
+        override_freeze.as_deref(),
+    )?;
+    check_protected(
+        &manifest,
+        cli_params.environment.as_deref(),
+        "publish to",
+        cli_params.yes_i_know,
+    )?;
+    let deploy_config = manifest.get_deployments(cli_params.environment.as_deref())?;
+    if abort {
+        canary::finish(&user, &target, &deploy_config)?;
+        StdErr::success(&format!("Aborted the canary rollout of {}", target.name));
+        return Ok(());
+    }
+    check_dirty_tree(
+        &manifest,
+        cli_params.environment.as_deref(),
+        checkout.as_ref(),
+        allow_dirty,
+    )?;
+    check_ownership(&user, &target, force)?;
+    pre_publish_hooks(
+        &manifest,
+        cli_params.environment.as_deref(),
+        &target,
+        checkout.as_ref(),
+    )?;
+    // a canary isn't done publishing until it's promoted, so post-publish hooks wait for that
+    if let Some(percent) = canary {
+        return canary::start(&user, &target, &deploy_config, percent, output);
+    }
+    let published = if promote {
+        // the worker is the build of the canary before its routes are taken back from the
+        // splitter, so it isn't built again
+        canary::check_promotable(&target)?;
+        let published = commands::publish_built(
+            &user,
+            &mut target,
+            canary::remaining(deploy_config.clone()),
+            output,
+            analyze,
+            if_changed,
+        )?;
+        canary::finish(&user, &target, &deploy_config)?;
+        published
+    } else if config_only {
+        commands::publish_config(&user, &target, deploy_config, output)?
+    } else if site_only {
+        commands::publish_site(&user, &mut target, deploy_config, output, analyze)?
     } else {
-        Output::PlainText
+        commands::publish(
+            &user,
+            &mut target,
+            deploy_config,
+            output,
+            analyze,
+            no_cache,
+            if_changed,
+        )?
     };
-    let deploy_config = manifest.get_deployments(cli_params.environment.as_deref())?;
-    commands::publish(&user, &mut target, deploy_config, output)
+    record_deployment(&user, &target, message, freeze_override);
+    post_publish_hooks(
+        &manifest,
+        cli_params.environment.as_deref(),
+        &target,
+        checkout.as_ref(),
+        &published,
+    );
+    if let Some(name) = tag {
+        tag_commit(name.as_deref(), cli_params.environment.as_deref());
+    }
+    Ok(())
+}
+
+// The workers of `only`, or of the whole workspace, that changed since `git_ref`. `None` when
+// there is nothing to publish.
+fn changed_workers(
+    workspace: &Manifest,
+    git_ref: &str,
+    only: Vec<String>,
+    cli_params: &Cli,
+) -> Result<Option<Vec<String>>> {
+    let root = workspace_root(cli_params)?;
+    let root = root.to_string_lossy();
+    let diff =
+        commands::previews::git(&["-C", &root, "diff", "--name-only", "--relative", git_ref])?;
+    // new files git doesn't know about yet change a worker as much as edited ones
+    let untracked =
+        commands::previews::git(&["-C", &root, "ls-files", "--others", "--exclude-standard"])?;
+    let changed_files: Vec<PathBuf> = diff
+        .lines()
+        .chain(untracked.lines())
+        .map(PathBuf::from)
+        .collect();
+
+    // the configuration file can change any worker, so they all count as changed then
+    let config_changed = cli_params.config.file_name().map_or(false, |name| {
+        changed_files.iter().any(|file| file == Path::new(name))
+    });
+    let mut changed = if config_changed {
+        StdErr::info("The configuration file changed, so every worker is published");
+        workspace
+            .workspace_members(&[])?
+            .into_iter()
+            .map(|(manifest, _)| manifest.name)
+            .collect()
+    } else {
+        workspace.changed_workspace_members(&changed_files)
+    };
+    if !only.is_empty() {
+        changed.retain(|name| only.contains(name));
+    }
+
+    if changed.is_empty() {
+        StdOut::success(&format!("No workers changed since {}", git_ref));
+        return Ok(None);
+    }
+    StdErr::info(&format!("{} changed since {}", changed.join(", "), git_ref));
+    Ok(Some(changed))
+}
+
+fn workspace_root(cli_params: &Cli) -> Result<PathBuf> {
+    Ok(cli_params
+        .config
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+        .canonicalize()?)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn publish_workspace(
+    user: &GlobalUser,
+    workspace: &Manifest,
+    only: &[String],
+    output: Output,
+    analyze: bool,
+    no_cache: bool,
+    if_changed: bool,
+    message: Option<String>,
+    override_freeze: Option<&str>,
+    check_types: bool,
+    force: bool,
+    allow_dirty: bool,
+    tag: Option<Option<String>>,
+    config_only: bool,
+    site_only: bool,
+    cli_params: &Cli,
+) -> Result<()> {
+    let environment = cli_params.environment.as_deref();
+    let root = workspace_root(cli_params)?;
+    let original_dir = env::current_dir()?;
+    let members = workspace.workspace_members(only)?;
+    let checkout = Checkout::current();
+
+    // every worker is checked before any is published, so a freeze, a type error or a worker
+    // of someone else doesn't leave the workspace half published
+    let mut freeze_overrides = Vec::new();
+    for (manifest, path) in &members {
+        env::set_current_dir(root.join(path))?;
+        let freeze_override =
+            check_freeze(manifest, environment, override_freeze).and_then(|freeze_override| {
+                check_protected(manifest, environment, "publish to", cli_params.yes_i_know)?;
+                let target = manifest.get_target(environment, false)?;
+                if check_types {
+                    commands::build::check_types(&target)?;
+                }
+                if site_only && target.site.is_none() {
+                    anyhow::bail!("{} has no [site] to publish with --site-only", target.name)
+                }
+                check_dirty_tree(manifest, environment, checkout.as_ref(), allow_dirty)?;
+                check_ownership(user, &target, force)?;
+                pre_publish_hooks(manifest, environment, &target, checkout.as_ref())?;
+                Ok(freeze_override)
+            });
+        env::set_current_dir(&original_dir)?;
+        freeze_overrides.push(freeze_override?);
+    }
+
+    for ((manifest, path), freeze_override) in members.into_iter().zip(freeze_overrides) {
+        StdErr::working(&format!("Publishing {}", styles::highlight(&manifest.name)));
+
+        // every path in a worker's configuration is relative to its own directory
+        env::set_current_dir(root.join(&path))?;
+        let result = manifest
+            .get_target(environment, false)
+            .and_then(|mut target| {
+                if let Some(checkout) = &checkout {
+                    checkout.annotate(&mut target);
+                }
+                let deploy_config = manifest.get_deployments(environment)?;
+                let published = if config_only {
+                    commands::publish_config(user, &target, deploy_config, output)?
+                } else if site_only {
+                    commands::publish_site(user, &mut target, deploy_config, output, analyze)?
+                } else {
+                    commands::publish(
+                        user,
+                        &mut target,
+                        deploy_config,
+                        output,
+                        analyze,
+                        no_cache,
+                        if_changed,
+                    )?
+                };
+                record_deployment(user, &target, message.clone(), freeze_override);
+                post_publish_hooks(
+                    &manifest,
+                    environment,
+                    &target,
+                    checkout.as_ref(),
+                    &published,
+                );
+                Ok(())
+            });
+        env::set_current_dir(&original_dir)?;
+
+        if let Err(e) = result {
+            anyhow::bail!("Failed to publish {}: {}", manifest.name, e)
+        }
+    }
+
+    if let Some(name) = tag {
+        tag_commit(name.as_deref(), environment);
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn publish_environments(
+    user: &GlobalUser,
+    manifest: &Manifest,
+    environments: &[String],
+    output: Output,
+    analyze: bool,
+    no_cache: bool,
+    if_changed: bool,
+    message: Option<String>,
+    override_freeze: Option<&str>,
+    check_types: bool,
+    force: bool,
+    allow_dirty: bool,
+    tag: Option<Option<String>>,
+    config_only: bool,
+    site_only: bool,
+    fail_fast: bool,
+    yes_i_know: bool,
+) -> Result<()> {
+    let checkout = Checkout::current();
+
+    // every environment is checked before any is published, like the workers of a workspace
+    let mut freeze_overrides = Vec::new();
+    for environment in environments {
+        let environment = Some(environment.as_str());
+        let freeze_override = check_freeze(manifest, environment, override_freeze)?;
+        check_protected(manifest, environment, "publish to", yes_i_know)?;
+        let target = manifest.get_target(environment, false)?;
+        if check_types {
+            commands::build::check_types(&target)?;
+        }
+        if site_only && target.site.is_none() {
+            anyhow::bail!("{} has no [site] to publish with --site-only", target.name)
+        }
+        check_dirty_tree(manifest, environment, checkout.as_ref(), allow_dirty)?;
+        check_ownership(user, &target, force)?;
+        pre_publish_hooks(manifest, environment, &target, checkout.as_ref())?;
+        freeze_overrides.push(freeze_override);
+    }
+
+    // the target the script was last built for, to build again only for environments
+    // that build it another way
+    let mut built: Option<Target> = None;
+    let mut results = Vec::new();
+    for (environment, freeze_override) in environments.iter().zip(freeze_overrides) {
+        StdErr::working(&format!("Publishing to {}", styles::highlight(environment)));
+        let result = manifest
+            .get_target(Some(environment), false)
+            .and_then(|mut target| {
+                if let Some(checkout) = &checkout {
+                    checkout.annotate(&mut target);
+                }
+                let deploy_config = manifest.get_deployments(Some(environment))?;
+                let published = if config_only {
+                    commands::publish_config(user, &target, deploy_config, output)
+                } else if site_only {
+                    commands::publish_site(user, &mut target, deploy_config, output, analyze)
+                } else if built
+                    .as_ref()
+                    .map_or(false, |built| builds_alike(built, &target))
+                {
+                    StdErr::info(
+                        "Built the same way as the last environment, publishing that build",
+                    );
+                    commands::publish_built(
+                        user,
+                        &mut target,
+                        deploy_config,
+                        output,
+                        analyze,
+                        if_changed,
+                    )
+                } else {
+                    let published = commands::publish(
+                        user,
+                        &mut target,
+                        deploy_config,
+                        output,
+                        analyze,
+                        no_cache,
+                        if_changed,
+                    );
+                    if published.is_ok() {
+                        built = Some(target.clone());
+                    }
+                    published
+                };
+                let published = published?;
+                record_deployment(user, &target, message.clone(), freeze_override);
+                post_publish_hooks(
+                    manifest,
+                    Some(environment),
+                    &target,
+                    checkout.as_ref(),
+                    &published,
+                );
+                Ok(published)
+            });
+        let failed = result.is_err();
+        results.push((environment.as_str(), result));
+        if failed && fail_fast {
+            break;
+        }
+    }
+
+    // each environment on a line of its own, after all of their output
+    let mut failed = 0;
+    for (environment, result) in &results {
+        match result {
+            Ok(published) if published.urls.is_empty() => {
+                StdErr::success(&format!("{}: published {}", environment, published.name))
+            }
+            Ok(published) => StdErr::success(&format!(
+                "{}: published {} to {}",
+                environment,
+                published.name,
+                published.urls.join(", ")
+            )),
+            Err(e) => {
+                failed += 1;
+                StdErr::warn(&format!("{}: {:#}", environment, e));
+            }
+        }
+    }
+    for environment in &environments[results.len()..] {
+        StdErr::info(&format!("{}: skipped after the failure", environment));
+    }
+
+    let published: Vec<&str> = results
+        .iter()
+        .filter(|(_, result)| result.is_ok())
+        .map(|(environment, _)| *environment)
+        .collect();
+    match tag {
+        // a name of its own tags the commit once
+        Some(Some(name)) if !published.is_empty() => tag_commit(Some(&name), None),
+        Some(None) => {
+            for environment in published {
+                tag_commit(None, Some(environment));
+            }
+        }
+        _ => {}
+    }
+    if failed > 0 {
+        anyhow::bail!(
+            "Failed to publish to {} of {} environments",
+            failed,
+            environments.len()
+        )
+    }
+    Ok(())
+}
+
+// Whether `target` builds into the same script as `built`
+fn builds_alike(built: &Target, target: &Target) -> bool {
+    built.target_type == target.target_type
+        && built.webpack_config == target.webpack_config
+        && built.build == target.build
+        && built.site == target.site
+}
+
+// Refuses to publish an environment that is frozen unless the freeze is overridden, and
+// returns the reason it was overridden for, to record with the deployment
+pub(crate) fn check_freeze(
+    manifest: &Manifest,
+    environment: Option<&str>,
+    override_freeze: Option<&str>,
+) -> Result<Option<String>> {
+    let name = manifest.worker_name(environment);
+    let freeze = match (manifest.get_freeze(environment)?, override_freeze) {
+        (Some(freeze), _) => freeze,
+        (None, Some(_)) => {
+            StdErr::info(&format!(
+                "Publishing {} isn't frozen, ignoring --override-freeze",
+                name
+            ));
+            return Ok(None);
+        }
+        (None, None) => return Ok(None),
+    };
+    let reason = freeze.reason.as_deref().unwrap_or("no reason given");
+
+    match override_freeze {
+        Some(override_reason) => {
+            StdErr::warn(&format!(
+                "Publishing {} is frozen by {} ({}), overriding the freeze: {}",
+                name, freeze.source, reason, override_reason
+            ));
+            Ok(Some(override_reason.to_string()))
+        }
+        None => anyhow::bail!(
+            "Publishing {} is frozen by {}: {}\nPass {} to publish anyway; the reason is recorded with the deployment.",
+            name,
+            freeze.source,
+            reason,
+            styles::highlight("--override-freeze <reason>")
+        ),
+    }
+}
+
+// Asks to confirm publishing to or deleting from an environment of `protected_environments`
+// by typing its name, unless --yes-i-know was passed. `action` is what is done to it, like
+// "publish to".
+pub(crate) fn check_protected(
+    manifest: &Manifest,
+    environment: Option<&str>,
+    action: &str,
+    yes_i_know: bool,
+) -> Result<()> {
+    if !manifest.is_protected(environment) || yes_i_know {
+        return Ok(());
+    }
+    // the top level is confirmed by typing the name of its worker
+    let environment = match environment {
+        Some(environment) => {
+            StdErr::warn(&format!("{} is a protected environment", environment));
+            environment.to_string()
+        }
+        None => {
+            let name = manifest.worker_name(None);
+            StdErr::warn(&format!("The top level, {}, is protected", name));
+            name
+        }
+    };
+    let answer = interactive::get_user_input(
+        &format!("Type {} to {} it:", styles::highlight(&environment), action),
+        &format!(
+            "pass {} to {} it anyway",
+            styles::highlight("--yes-i-know"),
+            action
+        ),
+    )?;
+    if answer.trim() != environment {
+        anyhow::bail!("Did not {} {}", action, environment)
+    }
+    Ok(())
+}
+
+// Asks before overwriting a worker that another project or something else than `wrangler
+// publish` changed last. Failing to find out whose it is doesn't stop the publish.
+fn check_ownership(user: &GlobalUser, target: &Target, force: bool) -> Result<()> {
+    let warning = match commands::deployments::ownership(user, target) {
+        Ok(Ownership::Ours) => return Ok(()),
+        Ok(Ownership::Unknown(warning)) => {
+            StdErr::warn(&warning);
+            return Ok(());
+        }
+        Ok(Ownership::Theirs(warning)) => warning,
+        Err(e) => {
+            log::info!("Could not tell whose worker {} is: {}", target.name, e);
+            return Ok(());
+        }
+    };
+    StdErr::warn(&warning);
+    if force {
+        return Ok(());
+    }
+    if !interactive::confirm_or_fail(
+        &format!("Overwrite {}?", target.name),
+        &format!("pass {} to publish it anyway", styles::highlight("--force")),
+    )? {
+        anyhow::bail!("Did not publish {}", target.name)
+    }
+    Ok(())
+}
+
+// Warns about or refuses publishing uncommitted changes, as `dirty_tree` under the
+// [protection] of the environment says
+fn check_dirty_tree(
+    manifest: &Manifest,
+    environment: Option<&str>,
+    checkout: Option<&Checkout>,
+    allow_dirty: bool,
+) -> Result<()> {
+    let checkout = match checkout {
+        Some(checkout) if checkout.is_dirty() => checkout,
+        _ => return Ok(()),
+    };
+    let name = manifest.worker_name(environment);
+    let files = summarize(&checkout.changed_files);
+    match manifest.get_dirty_tree(environment)? {
+        DirtyTree::Allow => {}
+        DirtyTree::Refuse if !allow_dirty => anyhow::bail!(
+            "Refusing to publish {} with uncommitted changes to {}. Commit them, or pass {} to publish anyway",
+            name,
+            files,
+            styles::highlight("--allow-dirty")
+        ),
+        _ => StdErr::warn(&format!(
+            "Publishing {} with uncommitted changes to {}",
+            name, files
+        )),
+    }
+    Ok(())
+}
+
+fn summarize(files: &[String]) -> String {
+    const SHOWN: usize = 3;
+    if files.len() > SHOWN {
+        format!(
+            "{} and {} more",
+            files[..SHOWN].join(", "),
+            files.len() - SHOWN
+        )
+    } else {
+        files.join(", ")
+    }
+}
+
+// The worker is already live, so failing to tag the commit shouldn't fail the publish
+fn tag_commit(name: Option<&str>, environment: Option<&str>) {
+    match git::tag(name, environment) {
+        Ok(tag) => StdErr::success(&format!(
+            "Tagged the commit as {}, push the tag with {}",
+            tag,
+            styles::highlight(format!("`git push origin {}`", tag))
+        )),
+        Err(e) => StdErr::warn(&format!("Could not tag the commit: {}", e)),
+    }
+}
+
+// Runs the pre-publish hooks of `environment`, a failing one stops the publish
+fn pre_publish_hooks(
+    manifest: &Manifest,
+    environment: Option<&str>,
+    target: &Target,
+    checkout: Option<&Checkout>,
+) -> Result<()> {
+    let commands = manifest.get_hooks(environment)?.pre_publish;
+    let context = hook_context(environment, target, checkout, Vec::new());
+    hooks::run("pre-publish", &commands, &context)
+}
+
+// The worker is already live, so a failing post-publish hook shouldn't fail the publish
+fn post_publish_hooks(
+    manifest: &Manifest,
+    environment: Option<&str>,
+    target: &Target,
+    checkout: Option<&Checkout>,
+    published: &PublishOutput,
+) {
+    let commands = match manifest.get_hooks(environment) {
+        Ok(hooks) => hooks.post_publish,
+        Err(_) => return,
+    };
+    let context = hook_context(environment, target, checkout, published.urls.clone());
+    if let Err(e) = hooks::run("post-publish", &commands, &context) {
+        StdErr::warn(&e.to_string());
+    }
+}
+
+fn hook_context(
+    environment: Option<&str>,
+    target: &Target,
+    checkout: Option<&Checkout>,
+    urls: Vec<String>,
+) -> HookContext {
+    HookContext {
+        environment: environment.unwrap_or_default().to_string(),
+        script_name: target.name.clone(),
+        urls,
+        version: checkout
+            .map(|checkout| checkout.sha.clone())
+            .unwrap_or_default(),
+    }
+}
+
+// The worker is already live, so failing to keep its history shouldn't fail the publish
+pub(crate) fn record_deployment(
+    user: &GlobalUser,
+    target: &Target,
+    message: Option<String>,
+    freeze_override: Option<String>,
+) {
+    let mut deployment = Deployment::new(target, message);
+    deployment.freeze_override = freeze_override;
+    deployment.etag = match commands::deployments::published_etag(user, target) {
+        Ok(etag) => etag,
+        Err(e) => {
+            log::info!("Could not get the etag of {}: {}", target.name, e);
+            None
+        }
+    };
+    if let Err(e) = commands::deployments::record(user, target, &deployment) {
+        StdErr::warn(&format!("Could not record this deployment: {}", e));
+    }
 }
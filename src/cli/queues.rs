@@ -0,0 +1,44 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Queues {
+    /// Send a test message to a queue, as a producer worker would
+    Send {
+        /// The name of the queue
+        #[structopt(index = 1)]
+        queue: String,
+
+        /// The body of the message. Sent as JSON if it is valid JSON, as text otherwise
+        #[structopt(long)]
+        body: String,
+    },
+    /// Print how many messages are waiting in a queue and the first few of them. Only
+    /// works for queues with an HTTP pull consumer
+    Peek {
+        /// The name of the queue
+        #[structopt(index = 1)]
+        queue: String,
+
+        /// How many messages to print
+        #[structopt(long, short = "n", default_value = "10")]
+        count: u32,
+    },
+}
+
+pub fn queues(queues: Queues, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let account_id = target.account_id.load()?;
+
+    match queues {
+        Queues::Send { queue, body } => commands::queues::send(account_id, &queue, &body, &user),
+        Queues::Peek { queue, count } => commands::queues::peek(account_id, &queue, count, &user),
+    }
+}
@@ -0,0 +1,20 @@
+use super::Cli;
+use crate::commands::quota::{self, FREE_LIMITS, PAID_LIMITS};
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+
+pub fn quota(threshold: f64, paid: bool, cli_params: &Cli) -> Result<()> {
+    if !(0.0..=100.0).contains(&threshold) {
+        anyhow::bail!("--threshold needs to be a percentage between 0 and 100");
+    }
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let account_id = target.account_id.load()?;
+
+    let limits = if paid { PAID_LIMITS } else { FREE_LIMITS };
+    let quotas = quota::fetch(&user, account_id, &limits)?;
+    quota::print(&quotas, threshold);
+    Ok(())
+}
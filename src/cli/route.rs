@@ -1,3 +1,4 @@
+use super::publish::check_protected;
 use super::Cli;
 use crate::commands;
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
@@ -9,31 +10,50 @@ use structopt::StructOpt;
 #[structopt(rename_all = "lower")]
 pub enum Route {
     /// List all routes associated with a zone (outputs json)
-    List,
+    List {
+        /// The zone to list the routes of, instead of the zone_id of your configuration file
+        #[structopt(long)]
+        zone_id: Option<String>,
+    },
     /// Delete a route by ID
     Delete {
         /// The ID associated with the route you want to delete (find using `wrangler route list`)
         #[structopt(index = 1)]
         route_id: String,
+        /// The zone the route is in, instead of the zone_id of your configuration file
+        #[structopt(long)]
+        zone_id: Option<String>,
     },
 }
 
 pub fn route(route: Route, cli_params: &Cli) -> Result<()> {
     let user = GlobalUser::new()?;
     let manifest = Manifest::new(&cli_params.config)?;
-    let zone_id = manifest
+    let config_zone_id = manifest
         .get_environment(cli_params.environment.as_deref())?
-        .and_then(|e| e.zone_id.as_ref())
-        .or_else(|| manifest.zone_id.as_ref());
+        .and_then(|e| e.zone_id.clone())
+        .or_else(|| manifest.zone_id.clone());
+    let zone_id = match &route {
+        Route::List { zone_id } | Route::Delete { zone_id, .. } => zone_id.clone(),
+    }
+    .or(config_zone_id);
 
     let zone_id = zone_id.ok_or_else(|| {
         anyhow::anyhow!(
-        "You must specify a zone_id in your configuration file to use `wrangler route` commands."
+        "You must specify a zone_id in your configuration file or pass --zone-id to use `wrangler route` commands."
     )
     })?;
 
     match route {
-        Route::List => commands::route::list(zone_id, &user),
-        Route::Delete { route_id } => commands::route::delete(zone_id, &user, &route_id),
+        Route::List { .. } => commands::route::list(&zone_id, &user),
+        Route::Delete { route_id, .. } => {
+            check_protected(
+                &manifest,
+                cli_params.environment.as_deref(),
+                "delete from",
+                cli_params.yes_i_know,
+            )?;
+            commands::route::delete(&zone_id, &user, &route_id)
+        }
     }
 }
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Scripts {
+    /// List the scripts of your account, with when they were modified, their usage model
+    /// and their routes
+    List {
+        /// Download every script to show its size
+        #[structopt(long)]
+        size: bool,
+    },
+    /// Download the source of a published script
+    Download {
+        /// The name of the script to download
+        #[structopt(index = 1)]
+        name: String,
+
+        /// Where to save the script, or the directory to save the modules of a module worker
+        /// to. Scripts are printed and modules saved to a directory named after the script
+        /// without it.
+        #[structopt(long, short = "o")]
+        output: Option<PathBuf>,
+    },
+}
+
+pub fn scripts(scripts: Scripts, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let env = cli_params.environment.as_deref();
+    let account_id = manifest.get_account_id(env)?;
+
+    match scripts {
+        Scripts::List { size } => {
+            let zone_id = manifest
+                .get_environment(env)?
+                .and_then(|e| e.zone_id.as_ref())
+                .or_else(|| manifest.zone_id.as_ref());
+            commands::scripts::list(&user, &account_id, zone_id.map(String::as_str), size)
+        }
+        Scripts::Download { name, output } => {
+            commands::scripts::download(&user, &account_id, &name, output.as_deref())
+        }
+    }
+}
@@ -1,7 +1,10 @@
+use super::publish::check_protected;
 use super::Cli;
 use crate::commands;
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
 
+use std::iter;
+
 use anyhow::Result;
 use structopt::StructOpt;
 #[derive(Debug, Clone, StructOpt)]
@@ -11,6 +14,10 @@ pub enum Secret {
     Put {
         #[structopt(long, short = "n", index = 1)]
         name: String,
+
+        /// Put the secret on the canary of a rollout started with `wrangler publish --canary`
+        #[structopt(long)]
+        canary: bool,
     },
     /// Delete a secret variable from a script
     Delete {
@@ -19,6 +26,9 @@ pub enum Secret {
     },
     /// List all secrets for a script
     List,
+    /// Compare the vars and secrets of published scripts with `vars` and `secrets` of the
+    /// configuration file, for every environment unless --env is passed
+    Diff,
 }
 
 pub fn secret(secret: Secret, cli_params: &Cli) -> Result<()> {
@@ -27,10 +37,56 @@ pub fn secret(secret: Secret, cli_params: &Cli) -> Result<()> {
 
     log::info!("Getting project settings");
     let manifest = Manifest::new(&cli_params.config)?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    if let Secret::Diff = secret {
+        return diff(&user, &manifest, cli_params);
+    }
+
+    let mut target = manifest.get_target(cli_params.environment.as_deref(), false)?;
     match secret {
-        Secret::Put { name } => commands::secret::create_secret(&name, &user, &target),
-        Secret::Delete { name } => commands::secret::delete_secret(&name, &user, &target),
+        Secret::Put { name, canary } => {
+            if canary {
+                target.name = commands::canary::canary_name(&target.name);
+            }
+            commands::secret::create_secret(&name, &user, &target)
+        }
+        Secret::Delete { name } => {
+            check_protected(
+                &manifest,
+                cli_params.environment.as_deref(),
+                "delete from",
+                cli_params.yes_i_know,
+            )?;
+            commands::secret::delete_secret(&name, &user, &target)
+        }
         Secret::List => commands::secret::list_secrets(&user, &target),
     }
 }
+
+// Diffs every worker of a workspace, or the one worker of the configuration file, in every
+// environment or just the one passed with --env
+fn diff(user: &GlobalUser, manifest: &Manifest, cli_params: &Cli) -> Result<()> {
+    let workers = match manifest.workers {
+        Some(_) => manifest
+            .workspace_members(&[])?
+            .into_iter()
+            .map(|(worker, _)| worker)
+            .collect(),
+        None => vec![manifest.clone()],
+    };
+
+    let mut targets = Vec::new();
+    for worker in &workers {
+        let environments = match &cli_params.environment {
+            Some(environment) => vec![Some(environment.clone())],
+            None => iter::once(None)
+                .chain(worker.environment_names().into_iter().map(Some))
+                .collect(),
+        };
+        for environment in environments {
+            let target = worker.get_target(environment.as_deref(), false)?;
+            let secrets = worker.secret_names(environment.as_deref())?;
+            targets.push((target, environment, secrets));
+        }
+    }
+    commands::secret::diff(user, &targets)
+}
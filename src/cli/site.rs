@@ -0,0 +1,74 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Site {
+    /// Set up a Workers Site serving a directory of static files: the configuration file,
+    /// the worker serving the files and the namespace holding them
+    New {
+        /// The directory of static files to serve
+        #[structopt(index = 1)]
+        bucket: PathBuf,
+
+        /// The name of the worker, defaults to the name of the current directory
+        #[structopt(long)]
+        name: Option<String>,
+
+        /// Publish the site once it is set up
+        #[structopt(long)]
+        publish: bool,
+    },
+
+    /// Print the files of the bucket that publishing would upload, updated or added, and the
+    /// ones its namespace has that the bucket doesn't anymore, without uploading anything
+    Diff,
+
+    /// Delete the Workers Sites asset namespaces of the workers of this project and of its
+    /// branch previews that were deleted
+    Cleanup {
+        /// Only print the namespaces that would be deleted
+        #[structopt(name = "dry-run", long)]
+        dry_run: bool,
+
+        /// Delete the namespaces without asking to confirm
+        #[structopt(long, short = "f")]
+        force: bool,
+    },
+}
+
+pub fn site(site: Site, cli_params: &Cli) -> Result<()> {
+    match site {
+        Site::New {
+            bucket,
+            name,
+            publish,
+        } => {
+            let user = GlobalUser::new()?;
+            commands::site::new(&user, &cli_params.config, &bucket, name.as_deref(), publish)
+        }
+        Site::Diff => {
+            let user = GlobalUser::new()?;
+            let manifest = Manifest::new(&cli_params.config)?;
+            let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+            commands::site::diff(&user, &target)
+        }
+        Site::Cleanup { dry_run, force } => {
+            let user = GlobalUser::new()?;
+            let manifest = Manifest::new(&cli_params.config)?;
+            commands::site::cleanup(
+                &user,
+                &manifest,
+                cli_params.environment.as_deref(),
+                dry_run,
+                force,
+            )
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use super::Cli;
+use crate::commands;
+use crate::commands::stats::StatsFormat;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use chrono::Duration;
+
+pub fn stats(since: Duration, all: bool, format: StatsFormat, cli_params: &Cli) -> Result<()> {
+    if since <= Duration::zero() {
+        anyhow::bail!("--since needs to be a period of time in the past, e.g. 24h or 7d");
+    }
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let account_id = target.account_id.load()?;
+
+    let script_name = if all {
+        None
+    } else {
+        Some(target.name.as_str())
+    };
+    commands::stats::stats(&user, account_id, script_name, since, format)
+}
@@ -3,6 +3,7 @@ use crate::commands;
 use crate::commands::tail::filter::*;
 use crate::commands::tail::websocket::{TailFormat, TailOptions};
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::sourcemap::SourceMaps;
 
 use anyhow::Result;
 use url::Url;
@@ -19,6 +20,8 @@ pub fn tail(
     headers: Vec<String>,
     client_ips: Vec<String>,
     search: Option<String>,
+    slow_threshold: Option<u64>,
+    symbolicate: bool,
     cli_params: &Cli,
 ) -> Result<()> {
     let user = GlobalUser::new()?;
@@ -28,6 +31,11 @@ pub fn tail(
     let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
     let account_id = target.account_id.load()?.to_string();
     let script_name = name.unwrap_or(target.name);
+    let symbolicate = if symbolicate {
+        Some(SourceMaps::load(&script_name)?)
+    } else {
+        None
+    };
 
     let mut filters: Vec<Box<dyn TraceFilter>> = vec![];
     if !outcomes.is_empty() {
@@ -57,6 +65,8 @@ pub fn tail(
         TailOptions {
             once,
             format,
+            slow_threshold,
+            symbolicate,
             filters,
         },
     );
@@ -0,0 +1,26 @@
+use super::Cli;
+use crate::commands;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Vars {
+    /// Encrypt the value of a var into the [vars] of your configuration file. It is
+    /// decrypted when you publish, and bound as a secret. The key it's encrypted with is kept
+    /// in ~/.wrangler/config/vars.key, or read from $WRANGLER_VARS_KEY
+    Encrypt {
+        /// The name of the var
+        #[structopt(index = 1)]
+        name: String,
+    },
+}
+
+pub fn vars(vars: Vars, cli_params: &Cli) -> Result<()> {
+    match vars {
+        Vars::Encrypt { name } => {
+            commands::vars::encrypt(&cli_params.config, cli_params.environment.as_deref(), &name)
+        }
+    }
+}
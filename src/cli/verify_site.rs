@@ -0,0 +1,14 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use url::Url;
+
+pub fn verify_site(url: &Url, sample: usize, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+
+    commands::verify_site::verify_site(&user, &target, url, sample)
+}
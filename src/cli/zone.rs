@@ -0,0 +1,46 @@
+use super::Cli;
+use crate::commands;
+use crate::deploy::DeployTarget;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Zone {
+    /// Print the zone settings and page rules that affect your worker's routes, to debug
+    /// a worker that behaves differently on its routes than in `wrangler dev`
+    Inspect,
+}
+
+pub fn zone(zone: Zone, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let env = cli_params.environment.as_deref();
+
+    let deployments = manifest.get_deployments(env)?;
+    // the routes of a worker can be in several zones
+    let zones: Vec<_> = deployments
+        .into_iter()
+        .filter_map(|deployment| match deployment {
+            DeployTarget::Zoned(zoned) => Some(zoned),
+            _ => None,
+        })
+        .collect();
+    if zones.is_empty() {
+        anyhow::bail!(
+            "You must specify a zone_id and routes in your configuration file to use `wrangler zone` commands."
+        )
+    }
+
+    match zone {
+        Zone::Inspect => {
+            for zoned in zones {
+                let routes: Vec<String> = zoned.routes.into_iter().map(|r| r.pattern).collect();
+                commands::zone::inspect(&zoned.zone_id, &routes, &user)?;
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use reqwest::Method;
+
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdErr};
+
+/// Sends a request to `path` of the Cloudflare API with the credentials of `user` and prints
+/// the response, pretty printed if it's JSON. Fails after printing the response if the
+/// request did.
+pub fn request(
+    user: &GlobalUser,
+    method: Method,
+    path: &str,
+    headers: &[(String, String)],
+    body: Option<String>,
+) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let mut request = client.request(method, &http::api_url(path)?);
+    for (name, value) in headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    if let Some(body) = body {
+        // the API takes JSON almost everywhere, so that's what a body is unless it's said
+        if !headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        {
+            request = request.header("Content-Type", "application/json");
+        }
+        request = request.body(body);
+    }
+
+    let res = request.send_logged()?;
+    let status = res.status();
+    let text = res.text()?;
+    match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(json) => println!("{}", serde_json::to_string_pretty(&json)?),
+        Err(_) => println!("{}", text),
+    }
+
+    if !status.is_success() {
+        anyhow::bail!("The API responded with {}", status)
+    }
+    StdErr::success(&format!("The API responded with {}", status));
+    Ok(())
+}
+
+/// Replaces the `:account` and `:zone` segments of `path` with what `resolve` returns for
+/// them, so that it is only asked for the ones `path` has
+pub fn substitute_placeholders<F>(path: &str, mut resolve: F) -> Result<String>
+where
+    F: FnMut(&str) -> Result<String>,
+{
+    let (path, query) = match path.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (path, None),
+    };
+
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment.strip_prefix(':') {
+            Some(placeholder @ "account") | Some(placeholder @ "zone") => {
+                segments.push(resolve(placeholder)?)
+            }
+            Some(placeholder) => anyhow::bail!(
+                "Unknown placeholder :{} in {}, expected :account or :zone",
+                placeholder,
+                path
+            ),
+            None => segments.push(segment.to_string()),
+        }
+    }
+
+    let mut path = segments.join("/");
+    if let Some(query) = query {
+        path.push('?');
+        path.push_str(query);
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_substitutes_placeholders() {
+        let resolve = |placeholder: &str| Ok(format!("{}-id", placeholder));
+        assert_eq!(
+            substitute_placeholders("/accounts/:account/workers/scripts", resolve).unwrap(),
+            "/accounts/account-id/workers/scripts"
+        );
+        assert_eq!(
+            substitute_placeholders("zones/:zone/workers/routes?per_page=5", resolve).unwrap(),
+            "zones/zone-id/workers/routes?per_page=5"
+        );
+        assert!(substitute_placeholders("/accounts/:acount/workers", resolve).is_err());
+    }
+
+    #[test]
+    fn it_only_resolves_the_placeholders_it_needs() {
+        let path = substitute_placeholders("/user/tokens/verify", |_| {
+            anyhow::bail!("no configuration file")
+        })
+        .unwrap();
+        assert_eq!(path, "/user/tokens/verify");
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use reqwest::blocking::Client;
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::styles;
+
+/// The shells `wrangler auth session` prints the variables to set for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shell {
+    Sh,
+    Fish,
+    PowerShell,
+}
+
+impl std::str::FromStr for Shell {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "sh" => Ok(Shell::Sh),
+            "fish" => Ok(Shell::Fish),
+            "powershell" => Ok(Shell::PowerShell),
+            _ => anyhow::bail!("Unknown shell \"{}\", expected sh, fish or powershell", s),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Envelope<T> {
+    result: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenStatus {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenDetails {
+    policies: Value,
+    #[serde(default)]
+    condition: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedToken {
+    value: String,
+}
+
+/// Prints the commands that set CF_API_TOKEN in the current shell to a token that expires
+/// after `ttl`, so that nothing that outlives it has to be kept on disk. API tokens are
+/// exchanged for a new token with the same permissions; OAuth logins hand out their
+/// access token, which expires on its own. Global API keys can't be exchanged.
+pub fn session(user: &GlobalUser, ttl: Duration, shell: Shell) -> Result<()> {
+    let expires_on = Utc::now() + ttl;
+    let (token, expires_on) = match user {
+        GlobalUser::ApiTokenAuth { .. } => (create_token(user, expires_on)?, expires_on),
+        GlobalUser::OAuthTokenAuth {
+            oauth_token,
+            expiration_time,
+            ..
+        } => {
+            let token_expires_on = DateTime::parse_from_rfc3339(expiration_time)?.with_timezone(&Utc);
+            if token_expires_on < expires_on {
+                StdErr::warn(&format!(
+                    "The access token of your login expires before that, at {}. Run {} again then.",
+                    token_expires_on.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    styles::highlight("`wrangler auth session`")
+                ));
+            }
+            (oauth_token.clone(), token_expires_on.min(expires_on))
+        }
+        GlobalUser::GlobalKeyAuth { .. } => anyhow::bail!(
+            "A Global API key can't be exchanged for a short-lived token. Run {} or {} with an API token first.",
+            styles::highlight("`wrangler login`"),
+            styles::highlight("`wrangler config`")
+        ),
+    };
+
+    for line in exports(shell, &token) {
+        println!("{}", line);
+    }
+    StdErr::success(&format!(
+        "Created a session that expires at {}. Evaluate the lines above in your shell, e.g. with {}, to use it.",
+        expires_on.to_rfc3339_opts(SecondsFormat::Secs, true),
+        styles::highlight("eval \"$(wrangler auth session)\"")
+    ));
+    Ok(())
+}
+
+// Creates a token with the permissions of the API token of `user` that expires at
+// `expires_on`, which requires the API token to be allowed to create tokens
+fn create_token(user: &GlobalUser, expires_on: DateTime<Utc>) -> Result<String> {
+    let client = http::legacy_auth_client(user);
+    let status: TokenStatus = get(&client, "user/tokens/verify")?;
+    let details: TokenDetails = get(&client, &format!("user/tokens/{}", status.id))?;
+
+    let mut body = json!({
+        "name": format!("wrangler session until {}", expires_on.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        "policies": details.policies,
+        "expires_on": expires_on.to_rfc3339_opts(SecondsFormat::Secs, true),
+    });
+    if let Some(condition) = details.condition {
+        body["condition"] = condition;
+    }
+
+    let res = client
+        .post(&http::api_url("user/tokens")?)
+        .json(&body)
+        .send_logged_as(Method::POST)?;
+    let status = res.status();
+    if !status.is_success() {
+        let error = WranglerError::from_api_response(status, res.text()?);
+        anyhow::bail!(
+            "Failed to create a session token: {}\nThe API token wrangler is configured with needs the \"API Tokens: Edit\" permission to create one.",
+            error
+        )
+    }
+    Ok(res.json::<Envelope<CreatedToken>>()?.result.value)
+}
+
+fn get<T: serde::de::DeserializeOwned>(client: &Client, path: &str) -> Result<T> {
+    let res = client.get(&http::api_url(path)?).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json::<Envelope<T>>()?.result)
+}
+
+// The lines that set CF_API_TOKEN to `token` in `shell`, and unset the variables that
+// would take precedence over it
+fn exports(shell: Shell, token: &str) -> Vec<String> {
+    match shell {
+        Shell::Sh => vec![
+            "unset CF_API_KEY CF_EMAIL".to_string(),
+            format!("export CF_API_TOKEN='{}'", token),
+        ],
+        Shell::Fish => vec![
+            "set -e CF_API_KEY CF_EMAIL".to_string(),
+            format!("set -gx CF_API_TOKEN '{}'", token),
+        ],
+        Shell::PowerShell => vec![
+            "Remove-Item Env:CF_API_KEY, Env:CF_EMAIL -ErrorAction SilentlyContinue".to_string(),
+            format!("$Env:CF_API_TOKEN = '{}'", token),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_prints_exports_for_each_shell() {
+        assert_eq!(
+            exports(Shell::Sh, "abc"),
+            vec!["unset CF_API_KEY CF_EMAIL", "export CF_API_TOKEN='abc'"]
+        );
+        assert_eq!(exports(Shell::Fish, "abc")[1], "set -gx CF_API_TOKEN 'abc'");
+        assert_eq!(
+            exports(Shell::PowerShell, "abc")[1],
+            "$Env:CF_API_TOKEN = 'abc'"
+        );
+    }
+}
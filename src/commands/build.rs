@@ -0,0 +1,421 @@
+use std::fs;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+
+use crate::build::cache;
+use crate::build::command;
+use crate::commands;
+use crate::install;
+use crate::settings::toml::{Target, TargetType, UploadFormat};
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::styles;
+use crate::upload::krate::Krate;
+
+// wasm-pack drives `cargo build --target wasm32-unknown-unknown` and wasm-bindgen for us
+const WASM_PACK_ARGS: [&str; 3] = ["build", "--target", "no-modules"];
+const WORKER_JS: &str = "./worker/worker.js";
+
+/// Where the WebAssembly built from a `type = "rust"` project is kept, relative to the
+/// project, so that it's reused instead of compiled again while the crate doesn't change
+pub const TARGET_CACHE_DIR: &str = ".wrangler/target-cache";
+// Builds of a few versions of the crate are kept, for switching back and forth between them
+const TARGET_CACHE_ENTRIES: usize = 3;
+// Written to an entry of the cache whenever it's used, to know which ones to keep
+const LAST_USED: &str = "last-used";
+
+// What `type = "typescript"` compiles without a tsconfig.json, with the options the workers
+// runtime needs
+const TYPESCRIPT_ENTRY: &str = "src/index.ts";
+const TSCONFIG: &str = "tsconfig.json";
+const TSC_DEFAULT_ARGS: [&str; 13] = [
+    "--target",
+    "es2020",
+    "--module",
+    "es2020",
+    "--moduleResolution",
+    "node",
+    "--lib",
+    "es2020,webworker",
+    "--rootDir",
+    "src",
+    "--strict",
+    "--skipLibCheck",
+    "--sourceMap",
+];
+// tsc exits with 2 when it found type errors but wrote the JavaScript all the same
+const TSC_EMITTED_WITH_ERRORS: i32 = 2;
+
+/// The files produced by building a `type = "rust"` project, relative to the project root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RustArtifacts {
+    /// The compiled WebAssembly module, bound as `wasm` in the uploaded script
+    pub wasm_path: PathBuf,
+    /// The wasm-bindgen glue for `wasm_path`
+    pub bindgen_js_path: PathBuf,
+    /// The shim that is uploaded as the worker script: the glue followed by `worker/worker.js`
+    pub script_path: PathBuf,
+}
+
+impl RustArtifacts {
+    pub fn new(krate_path: &str) -> Result<RustArtifacts> {
+        let name = Krate::new(krate_path)?.name.replace("-", "_");
+        let root = Path::new(krate_path);
+
+        Ok(RustArtifacts {
+            wasm_path: root.join("pkg").join(format!("{}_bg.wasm", name)),
+            bindgen_js_path: root.join("pkg").join(format!("{}.js", name)),
+            script_path: root.join("worker").join("generated").join("script.js"),
+        })
+    }
+
+    /// Make sure a previous build left everything that needs to be uploaded behind.
+    pub fn verify(&self) -> Result<()> {
+        for path in &[&self.wasm_path, &self.script_path] {
+            if !path.is_file() {
+                anyhow::bail!(
+                    "Could not find {}. Run {} to build your project.",
+                    path.display(),
+                    styles::highlight("`wrangler build`")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // Concatenates the wasm-bindgen glue with the user's worker script, so that the worker
+    // can call into the wasm module bound as `wasm`.
+    fn generate_shim(&self, worker_js_path: &Path) -> Result<()> {
+        let bindgen_js = fs::read_to_string(&self.bindgen_js_path)?;
+        let worker_js = fs::read_to_string(worker_js_path)?;
+        let js = format!("{} {}", bindgen_js, worker_js);
+
+        if let Some(dir) = self.script_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.script_path, js.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Compiles a `type = "rust"` project to WebAssembly and generates the script to upload.
+/// With `cache`, the WebAssembly of the last build of the same Cargo.lock and sources is
+/// reused from `.wrangler/target-cache` instead of running wasm-pack again.
+pub fn rust(cache: bool) -> Result<RustArtifacts> {
+    let root = Path::new(".");
+    let cached = if cache {
+        Some(TargetCache::new(root)?)
+    } else {
+        None
+    };
+    if let Some(cached) = &cached {
+        if cached.restore()? {
+            StdErr::info(&format!(
+                "Your crate didn't change since it was last built, reusing the WebAssembly from {}",
+                TARGET_CACHE_DIR
+            ));
+            let artifacts = RustArtifacts::new("./")?;
+            artifacts.generate_shim(Path::new(WORKER_JS))?;
+            return Ok(artifacts);
+        }
+    }
+
+    let _ = which::which("rustc").map_err(|e| {
+        anyhow!(
+            "'rustc' not found: {}. Installation documentation can be found here: {}",
+            e,
+            styles::url("https://www.rust-lang.org/tools/install")
+        )
+    })?;
+
+    let binary_path = install::install_wasm_pack()?;
+    let artifacts = rust_with_wasm_pack(&binary_path)?;
+    if let Some(cached) = &cached {
+        // the build is there to upload either way
+        if let Err(e) = cached.store() {
+            log::info!("failed to cache the WebAssembly: {}", e);
+        }
+    }
+    Ok(artifacts)
+}
+
+// The `pkg` directory wasm-pack writes, kept in `.wrangler/target-cache/<fingerprint>` where
+// the fingerprint is a hash of the Cargo.lock and of every other file of the crate
+struct TargetCache {
+    root: PathBuf,
+    fingerprint: String,
+}
+
+impl TargetCache {
+    fn new(root: &Path) -> Result<Self> {
+        let mut hasher = Sha256::new();
+        hasher.update(WASM_PACK_ARGS.join(" ").as_bytes());
+        // neither are read by wasm-pack, and the shim is generated again on every build
+        let skipped = vec![root.join("pkg"), root.join("worker")];
+        cache::hash_files(&mut hasher, root, &skipped)?;
+        Ok(TargetCache {
+            root: root.to_path_buf(),
+            fingerprint: hex::encode(hasher.finalize()),
+        })
+    }
+
+    fn entries(&self) -> PathBuf {
+        self.root.join(TARGET_CACHE_DIR)
+    }
+
+    fn restore(&self) -> Result<bool> {
+        let cached = self.entries().join(&self.fingerprint);
+        if !cached.join("pkg").is_dir() {
+            return Ok(false);
+        }
+        cache::copy(&cached.join("pkg"), &self.root.join("pkg"))?;
+        mark_used(&cached)?;
+        log::info!("restored the WebAssembly from {}", cached.display());
+        Ok(true)
+    }
+
+    fn store(&self) -> Result<()> {
+        let entry = self.entries().join(&self.fingerprint);
+        cache::copy(&self.root.join("pkg"), &entry.join("pkg"))?;
+        mark_used(&entry)?;
+        self.prune()
+    }
+
+    // Removes all but the most recently used entries
+    fn prune(&self) -> Result<()> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(self.entries())? {
+            let path = entry?.path();
+            let last_used = fs::read_to_string(path.join(LAST_USED))
+                .ok()
+                .and_then(|used| used.trim().parse::<u64>().ok())
+                .unwrap_or_default();
+            entries.push((last_used, path));
+        }
+        entries.sort_by(|a, b| b.cmp(a));
+        for (_, path) in entries.into_iter().skip(TARGET_CACHE_ENTRIES) {
+            fs::remove_dir_all(path)?;
+        }
+        Ok(())
+    }
+}
+
+fn mark_used(entry: &Path) -> Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+    fs::write(entry.join(LAST_USED), now.to_string())?;
+    Ok(())
+}
+
+/// Like `rust`, but with an already installed `wasm-pack`.
+pub fn rust_with_wasm_pack(binary_path: &Path) -> Result<RustArtifacts> {
+    let command = command(&WASM_PACK_ARGS, binary_path);
+    let command_name = format!("{:?}", command);
+
+    StdErr::working("Compiling your project to WebAssembly...");
+    commands::run(command, &command_name)?;
+
+    let artifacts = RustArtifacts::new("./")?;
+    artifacts.generate_shim(Path::new(WORKER_JS))?;
+    Ok(artifacts)
+}
+
+/// Compiles a `type = "typescript"` project into the directory it's uploaded from. Type
+/// errors don't stop the compilation, `check_types` is what looks for them.
+pub fn typescript(target: &Target) -> Result<()> {
+    let out_dir = typescript_out_dir(target);
+    let args = tsc_args(Path::new("."), Some(&out_dir))?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    StdErr::working("Compiling your TypeScript...");
+    let output = command(&args, &tsc()?).output()?;
+    match output.status.code() {
+        Some(0) | Some(TSC_EMITTED_WITH_ERRORS) => Ok(()),
+        _ => anyhow::bail!(
+            "Compiling your TypeScript failed:\n{}",
+            String::from_utf8_lossy(&output.stdout).trim_end()
+        ),
+    }
+}
+
+/// Fails with the type errors of a `type = "typescript"` project, if it has any. Other
+/// projects have no types to check.
+pub fn check_types(target: &Target) -> Result<()> {
+    if target.target_type != TargetType::TypeScript {
+        StdErr::info(&format!(
+            "{} isn't a TypeScript project, so it has no types to check",
+            target.name
+        ));
+        return Ok(());
+    }
+    let args = tsc_args(Path::new("."), None)?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    StdErr::working("Checking types...");
+    let output = command(&args, &tsc()?).output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Your TypeScript has type errors:\n{}",
+            String::from_utf8_lossy(&output.stdout).trim_end()
+        )
+    }
+    Ok(())
+}
+
+fn typescript_out_dir(target: &Target) -> PathBuf {
+    match target.build.as_ref().map(|build| &build.upload) {
+        Some(UploadFormat::Modules { dir, .. }) => dir.clone(),
+        Some(UploadFormat::ServiceWorker { main: Some(main) }) => {
+            main.parent().map(Path::to_path_buf).unwrap_or_default()
+        }
+        _ => PathBuf::from("dist"),
+    }
+}
+
+// The tsconfig.json of the project decides what's compiled and how, if there is one.
+// Without `out_dir`, nothing is written.
+fn tsc_args(project_dir: &Path, out_dir: Option<&Path>) -> Result<Vec<String>> {
+    let mut args: Vec<String> = if project_dir.join(TSCONFIG).is_file() {
+        vec!["--project".to_string(), TSCONFIG.to_string()]
+    } else if project_dir.join(TYPESCRIPT_ENTRY).is_file() {
+        iter::once(TYPESCRIPT_ENTRY)
+            .chain(TSC_DEFAULT_ARGS.iter().copied())
+            .map(String::from)
+            .collect()
+    } else {
+        anyhow::bail!(
+            "Could not find {} or a {} to compile",
+            TYPESCRIPT_ENTRY,
+            TSCONFIG
+        )
+    };
+    match out_dir {
+        Some(out_dir) => {
+            args.push("--outDir".to_string());
+            args.push(out_dir.display().to_string());
+        }
+        None => args.push("--noEmit".to_string()),
+    }
+    Ok(args)
+}
+
+// The compiler the project installed, or else one on the PATH
+fn tsc() -> Result<PathBuf> {
+    let binary = if cfg!(target_os = "windows") {
+        "tsc.cmd"
+    } else {
+        "tsc"
+    };
+    let local = Path::new("node_modules").join(".bin").join(binary);
+    if local.is_file() {
+        return Ok(local);
+    }
+    which::which("tsc").map_err(|_| {
+        anyhow!(
+            "Could not find the TypeScript compiler. Install it with {}",
+            styles::highlight("`npm install --save-dev typescript`")
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scaffold(dir: &Path) {
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"my-worker\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.join("pkg")).unwrap();
+        fs::create_dir_all(dir.join("worker")).unwrap();
+        fs::write(dir.join("pkg").join("my_worker.js"), "const glue = 1;").unwrap();
+        fs::write(dir.join("pkg").join("my_worker_bg.wasm"), "").unwrap();
+        fs::write(dir.join("worker").join("worker.js"), "addEventListener()").unwrap();
+    }
+
+    #[test]
+    fn it_generates_the_shim_next_to_the_wasm() {
+        let dir = tempfile::tempdir().unwrap();
+        scaffold(dir.path());
+        let root = dir.path().to_str().unwrap();
+
+        let artifacts = RustArtifacts::new(root).unwrap();
+        assert_eq!(
+            artifacts.wasm_path,
+            dir.path().join("pkg").join("my_worker_bg.wasm")
+        );
+        assert!(artifacts.verify().is_err());
+
+        artifacts
+            .generate_shim(&dir.path().join("worker").join("worker.js"))
+            .unwrap();
+        assert!(artifacts.verify().is_ok());
+        assert_eq!(
+            fs::read_to_string(&artifacts.script_path).unwrap(),
+            "const glue = 1; addEventListener()"
+        );
+    }
+
+    #[test]
+    fn it_reuses_the_webassembly_of_an_unchanged_crate() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        scaffold(root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("Cargo.lock"), "version = 3").unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn a() {}").unwrap();
+
+        let cached = TargetCache::new(root).unwrap();
+        assert!(!cached.restore().unwrap());
+        cached.store().unwrap();
+        fs::remove_dir_all(root.join("pkg")).unwrap();
+        // the build writes to pkg and worker, which doesn't change what's built
+        fs::create_dir_all(root.join("pkg")).unwrap();
+        let cached = TargetCache::new(root).unwrap();
+        assert!(cached.restore().unwrap());
+        assert_eq!(
+            fs::read_to_string(root.join("pkg/my_worker.js")).unwrap(),
+            "const glue = 1;"
+        );
+
+        fs::write(root.join("Cargo.lock"), "version = 3\n[[package]]").unwrap();
+        assert!(!TargetCache::new(root).unwrap().restore().unwrap());
+    }
+
+    #[test]
+    fn it_keeps_the_most_recently_used_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        scaffold(root);
+        for version in 0..TARGET_CACHE_ENTRIES + 2 {
+            fs::write(root.join("Cargo.lock"), version.to_string()).unwrap();
+            TargetCache::new(root).unwrap().store().unwrap();
+        }
+        let entries = fs::read_dir(root.join(TARGET_CACHE_DIR)).unwrap().count();
+        assert_eq!(entries, TARGET_CACHE_ENTRIES);
+        // the latest build is one of them
+        assert!(TargetCache::new(root).unwrap().restore().unwrap());
+    }
+
+    #[test]
+    fn it_compiles_typescript_with_or_without_a_tsconfig() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(tsc_args(dir.path(), None).is_err());
+
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join(TYPESCRIPT_ENTRY), "export default {};").unwrap();
+        let args = tsc_args(dir.path(), Some(Path::new("dist"))).unwrap();
+        assert_eq!(args[0], TYPESCRIPT_ENTRY);
+        assert_eq!(args[args.len() - 2..], ["--outDir", "dist"]);
+
+        fs::write(dir.path().join(TSCONFIG), "{}").unwrap();
+        assert_eq!(
+            tsc_args(dir.path(), None).unwrap(),
+            vec!["--project", TSCONFIG, "--noEmit"]
+        );
+    }
+}
@@ -0,0 +1,369 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use reqwest::blocking::multipart::{Form, Part};
+use reqwest::Method;
+
+use crate::commands::{self, previews, secret, validate_worker_name};
+use crate::deploy::{Applied, DeployTarget, DeploymentSet, ZonedTarget};
+use crate::error::WranglerError;
+use crate::http::{self, CloudflareApi, HttpApi, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, Output, StdErr};
+use crate::terminal::styles;
+use crate::upload;
+
+// Where the build each canary was published from is remembered, so only that build is promoted
+const CANARY_DIR: &str = ".wrangler/canary";
+
+// Sends PERCENT of the requests to the canary. A client keeps going to the version it was
+// first sent to, so it doesn't flip between them from one request to the next.
+const SPLITTER: &str = r#"const COOKIE = "__wrangler_canary"
+
+addEventListener("fetch", event => {
+  event.respondWith(split(event.request))
+})
+
+async function split(request) {
+  const cookies = request.headers.get("Cookie") || ""
+  const assigned = cookies.match(new RegExp(`${COOKIE}=(stable|canary)`))
+  const version = assigned
+    ? assigned[1]
+    : Math.random() * 100 < Number(PERCENT) ? "canary" : "stable"
+  const response = await (version === "canary" ? CANARY : STABLE).fetch(request)
+  if (assigned) {
+    return response
+  }
+  const sticky = new Response(response.body, response)
+  sticky.headers.append("Set-Cookie", `${COOKIE}=${version}; Path=/; HttpOnly`)
+  return sticky
+}
+"#;
+
+/// The worker the new version is published as during a canary rollout of `script_name`
+pub fn canary_name(script_name: &str) -> String {
+    format!("{}-canary", script_name)
+}
+
+/// The worker the routes of `script_name` point to during a canary rollout, which splits
+/// their requests between it and its canary
+pub fn splitter_name(script_name: &str) -> String {
+    format!("{}-splitter", script_name)
+}
+
+/// Publishes `target` as the canary of its worker and sends `percent` of the requests to
+/// its routes to the canary. Running it again during a rollout publishes the canary again
+/// and changes how much of the traffic it gets. Secrets can't be copied, so the canary only
+/// gets traffic once it has every secret of the worker.
+pub fn start(
+    user: &GlobalUser,
+    target: &Target,
+    deployments: &[DeployTarget],
+    percent: u8,
+    out: Output,
+) -> Result<()> {
+    anyhow::ensure!(
+        (1..=99).contains(&percent),
+        "--canary takes the percentage of requests to send to the canary, between 1 and 99"
+    );
+    let zones = zones(target, deployments)?;
+    let account_id = target.account_id.load()?;
+    let stable = target.name.clone();
+    if !previews::list_scripts(&http::legacy_auth_client(user), account_id)?
+        .iter()
+        .any(|script| script.id == stable)
+    {
+        anyhow::bail!(
+            "{} isn't published yet, so there's nothing to roll out gradually; publish it with {} first",
+            stable,
+            styles::highlight("`wrangler publish`")
+        )
+    }
+    let canary = canary_name(&stable);
+    let splitter = splitter_name(&stable);
+    validate_worker_name(&canary)?;
+    validate_worker_name(&splitter)?;
+
+    // the canary lives on the routes only through the splitter
+    let mut canary_target = target.clone();
+    canary_target.name = canary.clone();
+    commands::publish(
+        user,
+        &mut canary_target,
+        Vec::new(),
+        out,
+        false,
+        false,
+        false,
+    )?;
+    record_build(
+        Path::new(CANARY_DIR),
+        &stable,
+        &upload::build_digest(target)?,
+    )?;
+
+    let missing = missing_secrets(
+        &secret::secret_names(user, account_id, &stable)?,
+        &secret::secret_names(user, account_id, &canary)?,
+    );
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "{} was published, but it gets no traffic until it has the secrets of {}: {}. Set each with {}, then run {} again",
+            canary,
+            stable,
+            missing.join(", "),
+            styles::highlight("`wrangler secret put <name> --canary`"),
+            styles::highlight(format!("`wrangler publish --canary {}`", percent))
+        )
+    }
+
+    upload_splitter(user, account_id, &splitter, &stable, &canary, percent)?;
+    point_to(&HttpApi::new(user), &zones, &splitter)?;
+    StdErr::success(&format!(
+        "{}% of the requests to the routes of {} go to {} now. Finish the rollout with {} or {}",
+        percent,
+        stable,
+        canary,
+        styles::highlight("`wrangler publish --promote`"),
+        styles::highlight("`wrangler publish --abort`")
+    ));
+    Ok(())
+}
+
+/// Fails unless the build of `target` is the one its canary was last published from, which is
+/// what `wrangler publish --promote` publishes over the worker without building it again
+pub fn check_promotable(target: &Target) -> Result<()> {
+    check_build(
+        Path::new(CANARY_DIR),
+        &target.name,
+        &upload::build_digest(target)?,
+    )
+}
+
+/// Ends the rollout of `target`: its routes point to the worker again, and the canary and
+/// the splitter are deleted. The build of the canary has to be published over the worker
+/// before promoting it, while aborting leaves the worker as it was.
+pub fn finish(user: &GlobalUser, target: &Target, deployments: &[DeployTarget]) -> Result<()> {
+    let zones = zones(target, deployments)?;
+    point_to(&HttpApi::new(user), &zones, &target.name)?;
+
+    let account_id = target.account_id.load()?;
+    let client = http::legacy_auth_client(user);
+    for script_name in &[splitter_name(&target.name), canary_name(&target.name)] {
+        previews::delete_worker(user, &client, account_id, script_name)?;
+    }
+    match fs::remove_file(build_path(Path::new(CANARY_DIR), &target.name)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+// The secrets of the worker that its canary lacks
+fn missing_secrets(stable: &[String], canary: &[String]) -> Vec<String> {
+    stable
+        .iter()
+        .filter(|name| !canary.contains(name))
+        .cloned()
+        .collect()
+}
+
+fn build_path(dir: &Path, stable: &str) -> PathBuf {
+    dir.join(stable)
+}
+
+fn record_build(dir: &Path, stable: &str, digest: &str) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(build_path(dir, stable), digest)?;
+    Ok(())
+}
+
+fn check_build(dir: &Path, stable: &str, digest: &str) -> Result<()> {
+    let recorded = match fs::read_to_string(build_path(dir, stable)) {
+        Ok(recorded) => recorded,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => anyhow::bail!(
+            "There is no canary of {} published from this project to promote; start a rollout with {}",
+            stable,
+            styles::highlight("`wrangler publish --canary <percent>`")
+        ),
+        Err(e) => return Err(e.into()),
+    };
+    if recorded.trim() != digest {
+        anyhow::bail!(
+            "The last build of {} isn't the one its canary runs, so promoting it would publish a version that got no traffic. Publish the build as the canary with {} first, or {} the rollout",
+            stable,
+            styles::highlight("`wrangler publish --canary <percent>`"),
+            styles::highlight("`wrangler publish --abort`")
+        )
+    }
+    Ok(())
+}
+
+// Points the routes of every zone to `script_name`, and back to what they were if that fails
+// for one of them
+fn point_to(api: &impl CloudflareApi, zones: &[ZonedTarget], script_name: &str) -> Result<()> {
+    let mut applied = Applied::default();
+    let pointed = zones
+        .iter()
+        .try_for_each(|zone| zone.point_to(api, script_name, &mut applied));
+    pointed.map_err(|e| {
+        let plan = applied.roll_back(api);
+        e.context(plan)
+    })
+}
+
+// The routes of `target`, which a canary needs since workers.dev can't be split
+fn zones(target: &Target, deployments: &[DeployTarget]) -> Result<Vec<ZonedTarget>> {
+    let zones: Vec<ZonedTarget> = deployments
+        .iter()
+        .filter_map(|deployment| match deployment {
+            DeployTarget::Zoned(zoned) => Some(zoned.clone()),
+            _ => None,
+        })
+        .collect();
+    if zones.is_empty() {
+        anyhow::bail!(
+            "{} has no routes to split between it and its canary; workers.dev can't be split",
+            target.name
+        )
+    }
+    Ok(zones)
+}
+
+fn upload_splitter(
+    user: &GlobalUser,
+    account_id: &str,
+    splitter: &str,
+    stable: &str,
+    canary: &str,
+    percent: u8,
+) -> Result<()> {
+    let metadata = splitter_metadata(stable, canary, percent);
+    let form = Form::new()
+        .part(
+            "metadata",
+            Part::text(metadata.to_string())
+                .file_name("metadata.json")
+                .mime_str("application/json")?,
+        )
+        .part(
+            "script",
+            Part::text(SPLITTER)
+                .file_name("script")
+                .mime_str("application/javascript")?,
+        );
+
+    let addr = http::api_url(&format!(
+        "accounts/{}/workers/scripts/{}",
+        account_id, splitter
+    ))?;
+    let res = http::legacy_auth_client(user)
+        .put(&addr)
+        .multipart(form)
+        .send_logged_as(Method::PUT)?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(())
+}
+
+// The bindings of the splitter to the two versions, and the share of the canary
+fn splitter_metadata(stable: &str, canary: &str, percent: u8) -> serde_json::Value {
+    serde_json::json!({
+        "body_part": "script",
+        "bindings": [
+            { "type": "service", "name": "STABLE", "service": stable, "environment": "production" },
+            { "type": "service", "name": "CANARY", "service": canary, "environment": "production" },
+            { "type": "plain_text", "name": "PERCENT", "text": percent.to_string() },
+        ],
+    })
+}
+
+/// The deployments of a promoted canary that aren't routes, which `finish` already points
+/// back at the worker
+pub fn remaining(deployments: DeploymentSet) -> DeploymentSet {
+    deployments
+        .into_iter()
+        .filter(|deployment| !matches!(deployment, DeployTarget::Zoned(_)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::http::MockApi;
+    use crate::settings::toml::Route;
+
+    #[test]
+    fn it_names_the_workers_of_a_rollout() {
+        assert_eq!(canary_name("api"), "api-canary");
+        assert_eq!(splitter_name("api"), "api-splitter");
+    }
+
+    fn zone(zone_id: &str, pattern: &str) -> ZonedTarget {
+        ZonedTarget {
+            zone_id: zone_id.to_string(),
+            routes: vec![Route {
+                id: None,
+                script: Some("api".to_string()),
+                pattern: pattern.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn it_splits_the_routes_and_points_them_back() {
+        let api = MockApi::new()
+            .with_route("one", "example.com/*", Some("api"))
+            .with_route("two", "example.org/*", Some("api"));
+        let zones = vec![zone("one", "example.com/*"), zone("two", "example.org/*")];
+        let script_of = |zone_id: &str| api.routes(zone_id)[0].script.clone();
+
+        point_to(&api, &zones, "api-splitter").unwrap();
+        assert_eq!(script_of("one").as_deref(), Some("api-splitter"));
+        assert_eq!(script_of("two").as_deref(), Some("api-splitter"));
+
+        point_to(&api, &zones, "api").unwrap();
+        assert_eq!(script_of("one").as_deref(), Some("api"));
+        assert_eq!(script_of("two").as_deref(), Some("api"));
+    }
+
+    #[test]
+    fn it_binds_the_splitter_to_both_versions() {
+        let metadata = splitter_metadata("api", "api-canary", 10);
+        let bindings = metadata["bindings"].as_array().unwrap();
+        assert_eq!(bindings[0]["service"], "api");
+        assert_eq!(bindings[1]["service"], "api-canary");
+        assert_eq!(bindings[2]["text"], "10");
+    }
+
+    #[test]
+    fn it_holds_the_canary_back_until_it_has_every_secret() {
+        let stable = vec!["API_KEY".to_string(), "DB_URL".to_string()];
+        assert_eq!(
+            missing_secrets(&stable, &["DB_URL".to_string()]),
+            vec!["API_KEY".to_string()]
+        );
+        assert!(missing_secrets(&stable, &stable).is_empty());
+    }
+
+    #[test]
+    fn it_only_promotes_the_build_of_the_canary() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let dir = tmpdir.path();
+        assert!(check_build(dir, "api", "abc")
+            .unwrap_err()
+            .to_string()
+            .contains("no canary of api"));
+
+        record_build(dir, "api", "abc").unwrap();
+        assert!(check_build(dir, "api", "abc").is_ok());
+        assert!(check_build(dir, "api", "def")
+            .unwrap_err()
+            .to_string()
+            .contains("isn't the one its canary runs"));
+    }
+}
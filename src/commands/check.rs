@@ -0,0 +1,145 @@
+use anyhow::Result;
+
+use crate::commands::publish::{validate_bucket_location, validate_target_required_fields_present};
+use crate::commands::subdomain::{self, Subdomain};
+use crate::commands::{validate_worker_name, zone};
+use crate::deploy::{DeployTarget, DeploymentSet};
+use crate::error::WranglerError;
+use crate::http::{self, HttpApi};
+use crate::kv::namespace;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+
+/// Runs the checks `wrangler publish` would fail on, without building or uploading
+/// anything. `user` enables the checks against the Cloudflare API, leave it out to only
+/// check the configuration file.
+pub fn check(
+    target: &Target,
+    deployments: &DeploymentSet,
+    user: Option<&GlobalUser>,
+) -> Result<()> {
+    let mut checks = Checks::default();
+
+    checks.run("required fields are present", || {
+        validate_target_required_fields_present(target)
+    });
+    checks.run("worker name is valid", || {
+        validate_worker_name(&target.name)
+    });
+    if let Some(site) = &target.site {
+        checks.run("site bucket exists", || {
+            validate_bucket_location(&site.bucket)
+        });
+    }
+
+    if let Some(user) = user {
+        for deployment in deployments {
+            match deployment {
+                DeployTarget::Zoned(zoned) => {
+                    checks.run("zone belongs to your account", || {
+                        zone::get_zone(&zoned.zone_id, user)
+                    });
+                    checks.run("routes are on the zone", || {
+                        zone::check_routes(&zoned.zone_id, &zoned.routes, user)
+                    });
+                    checks.run("routes are free or already yours", || {
+                        let conflicts = zoned.conflicts(&HttpApi::new(user))?;
+                        if let Some(route) = conflicts.first() {
+                            anyhow::bail!(
+                                "{} already points to {}",
+                                route.pattern,
+                                route.script.as_deref().unwrap_or("no worker")
+                            )
+                        }
+                        Ok(())
+                    });
+                }
+                DeployTarget::Zoneless(_) => {
+                    checks.run("workers.dev subdomain is registered", || {
+                        let registered = Subdomain::get(target.account_id.load()?, user)?;
+                        if registered.is_none() {
+                            anyhow::bail!(subdomain::NO_SUBDOMAIN_MSG)
+                        }
+                        Ok(())
+                    });
+                }
+                DeployTarget::DisableZoneless(_) | DeployTarget::Schedule(_) => {}
+            }
+        }
+
+        if !target.kv_namespaces.is_empty() {
+            checks.run("KV namespaces exist", || {
+                let client = http::cf_v4_client(user)?;
+                let existing = namespace::list(&client, target.account_id.load()?)?;
+                for kv in &target.kv_namespaces {
+                    if !existing.iter().any(|namespace| namespace.id == kv.id) {
+                        anyhow::bail!(
+                            "no namespace with id {} exists for binding {}",
+                            kv.id,
+                            kv.binding
+                        )
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    checks.finish()
+}
+
+#[derive(Default)]
+struct Checks {
+    failed: usize,
+}
+
+impl Checks {
+    fn run<F: FnOnce() -> Result<()>>(&mut self, name: &str, check: F) {
+        match check() {
+            Ok(()) => StdErr::success(name),
+            Err(e) => {
+                self.failed += 1;
+                StdErr::warn(&format!("{}: {:#}", name, e));
+            }
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        if self.failed > 0 {
+            anyhow::bail!(WranglerError::Config(format!(
+                "{} check(s) failed, `wrangler publish` would not succeed",
+                self.failed
+            )))
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::Manifest;
+
+    #[test]
+    fn it_fails_offline_on_an_invalid_configuration() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+name = "My.Worker"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+kv_namespaces = [{ binding = "CACHE", id = "" }]
+"#,
+        )
+        .unwrap();
+        let target = manifest.get_target(None, false).unwrap();
+        let deployments = manifest.get_deployments(None).unwrap();
+
+        let error = check(&target, &deployments, None).unwrap_err();
+        match error.downcast_ref::<WranglerError>() {
+            Some(WranglerError::Config(msg)) => assert!(msg.starts_with("2 check(s) failed")),
+            other => panic!("expected a config error, got {:?}", other),
+        }
+    }
+}
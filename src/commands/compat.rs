@@ -0,0 +1,224 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use prettytable::{Cell, Row, Table};
+use regex::Regex;
+
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::terminal::styles;
+use crate::upload::form;
+
+/// A behavior of the runtime that changed behind a compatibility flag, and what its use
+/// looks like in a script
+struct Feature {
+    flag: &'static str,
+    /// The compatibility date from which the flag is on by default, if it ever is
+    default_from: Option<&'static str>,
+    description: &'static str,
+    pattern: &'static str,
+}
+
+// Only the changes a script's source gives away; the ones that change how existing calls
+// behave with the same arguments can't be told apart by reading it
+const FEATURES: &[Feature] = &[
+    Feature {
+        flag: "formdata_parser_supports_files",
+        default_from: Some("2021-11-03"),
+        description: "files in parsed form data",
+        pattern: r"\.formData\(\s*\)",
+    },
+    Feature {
+        flag: "global_navigator",
+        default_from: Some("2022-03-21"),
+        description: "the global navigator",
+        pattern: r"\bnavigator\.userAgent\b",
+    },
+    Feature {
+        flag: "streams_enable_constructors",
+        default_from: Some("2022-11-30"),
+        description: "ReadableStream and WritableStream constructors",
+        pattern: r"\bnew\s+(ReadableStream|WritableStream)\s*\(",
+    },
+    Feature {
+        flag: "transformstream_enable_standard_constructor",
+        default_from: Some("2022-11-30"),
+        description: "TransformStream constructor with a transformer",
+        pattern: r"\bnew\s+TransformStream\s*\(\s*\{",
+    },
+    Feature {
+        flag: "nodejs_compat",
+        default_from: None,
+        description: "Node.js built-in modules",
+        pattern: r#"(\bfrom\s*|\brequire\(\s*|\bimport\(\s*)["']node:"#,
+    },
+];
+
+/// A use of a feature found in a script
+#[derive(Debug, PartialEq)]
+struct Usage {
+    flag: &'static str,
+    default_from: Option<&'static str>,
+    description: &'static str,
+    path: PathBuf,
+    line: usize,
+}
+
+impl Usage {
+    // Whether the compatibility `date` or `flags` turn the feature on
+    fn is_enabled_by(&self, date: Option<&str>, flags: &[String]) -> bool {
+        if flags.iter().any(|flag| flag == self.flag) {
+            return true;
+        }
+        match (self.default_from, date) {
+            // dates are all YYYY-MM-DD, so they compare as strings
+            (Some(default_from), Some(date)) => date >= default_from,
+            _ => false,
+        }
+    }
+}
+
+/// Scans the scripts of `target` for features that need a compatibility date or flag, and
+/// reports the minimum date and flags they need, failing when the configuration doesn't
+/// declare them
+pub fn check(target: &Target) -> Result<()> {
+    let mut usages = Vec::new();
+    for path in form::script_paths(target)? {
+        let source = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Could not read {}: {}", path.display(), e))?;
+        usages.extend(scan(&path, &source)?);
+    }
+
+    if usages.is_empty() {
+        StdOut::success("No features that need a compatibility date or flag were found");
+        return Ok(());
+    }
+
+    let date = target.compatibility_date.as_deref();
+    let flags = &target.compatibility_flags;
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Feature"),
+        Cell::new("Used at"),
+        Cell::new("Needs"),
+        Cell::new("Enabled"),
+    ]));
+    for usage in &usages {
+        let needs = match usage.default_from {
+            Some(default_from) => format!("{} or {}", default_from, usage.flag),
+            None => usage.flag.to_string(),
+        };
+        let enabled = if usage.is_enabled_by(date, flags) {
+            "yes"
+        } else {
+            "no"
+        };
+        table.add_row(Row::new(vec![
+            Cell::new(usage.description),
+            Cell::new(&format!("{}:{}", usage.path.display(), usage.line)),
+            Cell::new(&needs),
+            Cell::new(enabled),
+        ]));
+    }
+    print!("{}", table);
+
+    let (min_date, min_flags) = minimum(&usages);
+    if let Some(min_date) = min_date {
+        StdOut::info(&format!("Minimum compatibility_date: {}", min_date));
+    }
+    if !min_flags.is_empty() {
+        StdOut::info(&format!(
+            "Compatibility flags needed whatever the date: {}",
+            min_flags.join(", ")
+        ));
+    }
+
+    let missing = usages
+        .iter()
+        .filter(|usage| !usage.is_enabled_by(date, flags))
+        .count();
+    if missing > 0 {
+        anyhow::bail!(
+            "{} use(s) of features your configuration doesn't enable. Set compatibility_date and compatibility_flags in {} to at least the above.",
+            missing,
+            styles::highlight("wrangler.toml")
+        )
+    }
+    StdErr::success("Your configuration enables every feature the scripts use");
+    Ok(())
+}
+
+// Every use of a feature in `source`, with the line it's on
+fn scan(path: &Path, source: &str) -> Result<Vec<Usage>> {
+    let mut usages = Vec::new();
+    for feature in FEATURES {
+        let pattern = Regex::new(feature.pattern)?;
+        if let Some(found) = pattern.find(source) {
+            usages.push(Usage {
+                flag: feature.flag,
+                default_from: feature.default_from,
+                description: feature.description,
+                path: path.to_path_buf(),
+                line: source[..found.start()].matches('\n').count() + 1,
+            });
+        }
+    }
+    Ok(usages)
+}
+
+// The latest date any feature is on by default from, and the flags of the features that
+// never are
+fn minimum(usages: &[Usage]) -> (Option<&'static str>, Vec<&'static str>) {
+    let date = usages.iter().filter_map(|usage| usage.default_from).max();
+    let mut flags: Vec<&'static str> = usages
+        .iter()
+        .filter(|usage| usage.default_from.is_none())
+        .map(|usage| usage.flag)
+        .collect();
+    flags.sort_unstable();
+    flags.dedup();
+    (date, flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_features_with_their_line() {
+        let source = "addEventListener('fetch', e => {\n  const body = await e.request.formData()\n  return new Response(new ReadableStream({ start() {} }))\n})\n";
+        let usages = scan(Path::new("index.js"), source).unwrap();
+        let found: Vec<(&str, usize)> = usages.iter().map(|u| (u.flag, u.line)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("formdata_parser_supports_files", 2),
+                ("streams_enable_constructors", 3)
+            ]
+        );
+        assert!(scan(Path::new("index.js"), "new TransformStream()")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn it_reports_the_minimum_date_and_flags() {
+        let source = "import { Buffer } from 'node:buffer'\nnavigator.userAgent\nreq.formData()";
+        let usages = scan(Path::new("index.mjs"), source).unwrap();
+        assert_eq!(
+            minimum(&usages),
+            (Some("2022-03-21"), vec!["nodejs_compat"])
+        );
+    }
+
+    #[test]
+    fn it_checks_the_configured_date_and_flags() {
+        let usages = scan(Path::new("index.js"), "navigator.userAgent").unwrap();
+        let usage = &usages[0];
+        assert!(usage.is_enabled_by(Some("2022-04-01"), &[]));
+        assert!(!usage.is_enabled_by(Some("2021-12-01"), &[]));
+        assert!(!usage.is_enabled_by(None, &[]));
+        assert!(usage.is_enabled_by(None, &["global_navigator".to_string()]));
+    }
+}
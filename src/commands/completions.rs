@@ -0,0 +1,178 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::str::FromStr;
+
+use anyhow::Result;
+use clap::Shell;
+use structopt::StructOpt;
+
+use crate::cli::Cli;
+use crate::settings::toml::Manifest;
+
+/// What `wrangler __complete`, which the completion scripts run, lists from the
+/// configuration file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompletionKind {
+    Environments,
+    KvBindings,
+}
+
+impl FromStr for CompletionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "environments" => Ok(CompletionKind::Environments),
+            "kv-bindings" => Ok(CompletionKind::KvBindings),
+            _ => anyhow::bail!(
+                "Unknown completion \"{}\", expected environments or kv-bindings",
+                s
+            ),
+        }
+    }
+}
+
+// Completes the values of --env and --binding by asking wrangler, which knows where the
+// configuration file is. The generated function is renamed so that the one completing the
+// shell calls it for everything else.
+const BASH_DYNAMIC: &str = r#"
+_wrangler() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    case "${COMP_WORDS[COMP_CWORD-1]}" in
+        --env|-e)
+            COMPREPLY=($(compgen -W "$(wrangler __complete environments 2>/dev/null)" -- "${cur}"))
+            ;;
+        --binding|-b)
+            COMPREPLY=($(compgen -W "$(wrangler __complete kv-bindings 2>/dev/null)" -- "${cur}"))
+            ;;
+        *)
+            _wrangler_static "$@"
+            ;;
+    esac
+}
+"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_wrangler() {
+    case "${words[CURRENT-1]}" in
+        --env|-e)
+            compadd -- ${(f)"$(wrangler __complete environments 2>/dev/null)"}
+            ;;
+        --binding|-b)
+            compadd -- ${(f)"$(wrangler __complete kv-bindings 2>/dev/null)"}
+            ;;
+        *)
+            _wrangler_static "$@"
+            ;;
+    esac
+}
+
+_wrangler "$@"
+"#;
+
+const FISH_DYNAMIC: &str = r#"
+complete -c wrangler -l env -s e -x -a '(wrangler __complete environments 2>/dev/null)'
+complete -c wrangler -l binding -s b -x -a '(wrangler __complete kv-bindings 2>/dev/null)'
+"#;
+
+/// Writes the completion script of `shell` to `out`. PowerShell only completes the commands
+/// and flags, not the environments and bindings of the configuration file.
+pub fn completions(shell: Shell, out: &mut impl Write) -> Result<()> {
+    let mut script = Vec::new();
+    Cli::clap().gen_completions_to("wrangler", shell, &mut script);
+    let script = String::from_utf8(script)?;
+
+    let script = match shell {
+        Shell::Bash => format!(
+            "{}{}",
+            script.replacen("_wrangler() {", "_wrangler_static() {", 1),
+            BASH_DYNAMIC
+        ),
+        Shell::Zsh => {
+            let script = script.replacen("_wrangler() {", "_wrangler_static() {", 1);
+            // the script ends with calling the function it defines, which is now ours
+            let script = script.trim_end().trim_end_matches("_wrangler \"$@\"");
+            format!("{}{}", script, ZSH_DYNAMIC)
+        }
+        Shell::Fish => format!("{}{}", script, FISH_DYNAMIC),
+        _ => script,
+    };
+    out.write_all(script.as_bytes())?;
+    Ok(())
+}
+
+/// The environments or KV bindings of `manifest`, sorted, for the completion scripts
+pub fn complete(manifest: &Manifest, kind: CompletionKind) -> Vec<String> {
+    let values: BTreeSet<String> = match kind {
+        CompletionKind::Environments => manifest
+            .env
+            .iter()
+            .flat_map(|envs| envs.keys().cloned())
+            .collect(),
+        CompletionKind::KvBindings => manifest
+            .kv_namespaces
+            .iter()
+            .chain(
+                manifest
+                    .env
+                    .iter()
+                    .flat_map(|envs| envs.values().filter_map(|env| env.kv_namespaces.as_ref())),
+            )
+            .flatten()
+            .map(|namespace| namespace.binding.clone())
+            .collect(),
+    };
+    values.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn it_completes_from_the_configuration_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("wrangler.toml");
+        fs::write(
+            &config_path,
+            r#"
+name = "worker"
+type = "javascript"
+account_id = "abc"
+kv_namespaces = [{ binding = "CACHE", id = "1" }]
+
+[env.staging]
+kv_namespaces = [{ binding = "SESSIONS", id = "2" }, { binding = "CACHE", id = "3" }]
+
+[env.production]
+"#,
+        )
+        .unwrap();
+        let manifest = Manifest::new(&config_path).unwrap();
+
+        assert_eq!(
+            complete(&manifest, CompletionKind::Environments),
+            vec!["production", "staging"]
+        );
+        assert_eq!(
+            complete(&manifest, CompletionKind::KvBindings),
+            vec!["CACHE", "SESSIONS"]
+        );
+    }
+
+    #[test]
+    fn it_completes_environments_and_bindings_in_shells() {
+        for shell in &[Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let mut script = Vec::new();
+            completions(*shell, &mut script).unwrap();
+            let script = String::from_utf8(script).unwrap();
+            assert!(script.contains("__complete environments"), "{}", shell);
+            assert!(script.contains("__complete kv-bindings"), "{}", shell);
+        }
+
+        let mut script = Vec::new();
+        completions(Shell::PowerShell, &mut script).unwrap();
+        assert!(String::from_utf8(script).unwrap().contains("kv:namespace"));
+    }
+}
@@ -5,7 +5,7 @@ use std::os::unix::fs::PermissionsExt;
 #[cfg(not(target_os = "windows"))]
 use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use cloudflare::endpoints::user::{GetUserDetails, GetUserTokenStatus};
 use cloudflare::framework::apiclient::ApiClient;
 
@@ -34,7 +34,7 @@ pub fn global_config(user: &GlobalUser, verify: bool) -> Result<()> {
     invalidate_oauth_token("`wrangler config`".to_string());
 
     let config_file = get_global_config_path();
-    user.to_file(&config_file)?;
+    user.save(&config_file)?;
 
     // set permissions on the file
     #[cfg(not(target_os = "windows"))]
@@ -62,10 +62,9 @@ pub fn validate_credentials(user: &GlobalUser) -> Result<()> {
                     anyhow::bail!("Authentication check failed. Your token has status \"{}\", not \"active\".\nTry rolling your token on the Cloudflare dashboard.", success.result.status)
                 }
             }
-            Err(e) => anyhow::bail!(
-                "Authentication check failed. Please make sure your API token is correct.\n{}",
-                http::format_error(e, None)
-            ),
+            Err(e) => Err(http::api_error(e, None).context(
+                "Authentication check failed. Please make sure your API token is correct.",
+            )),
         },
         GlobalUser::GlobalKeyAuth { .. } => match client.request(&GetUserDetails {}) {
             Ok(_) => Ok(()),
@@ -0,0 +1,377 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use toml_edit::{Array, Document, InlineTable, Item, Table, Value};
+
+use crate::settings::toml::confirm_write;
+use crate::terminal::message::{Message, StdOut};
+
+/// Rewrites the settings of the configuration file at `config_path` that older versions of
+/// wrangler took into the ones that replaced them, asking before writing the changes
+/// unless --yes was passed.
+pub fn migrate(config_path: &Path) -> Result<()> {
+    let contents = fs::read_to_string(config_path)
+        .map_err(|e| anyhow!("Could not read {}: {}", config_path.display(), e))?;
+    let (migrated, changes) = migrate_config(&contents)?;
+
+    if changes.is_empty() {
+        StdOut::success(&format!("{} is up to date", config_path.display()));
+        return Ok(());
+    }
+    for change in &changes {
+        StdOut::info(change);
+    }
+    confirm_write(config_path, &migrated)?;
+    fs::write(config_path, &migrated)?;
+    StdOut::success(&format!("Migrated {}", config_path.display()));
+    Ok(())
+}
+
+/// Rewrites `private` into `workers_dev`, a single `route` into `routes`, `kv-namespaces`
+/// into `kv_namespaces` and the `name` of durable object classes into `binding`, and removes
+/// the `type` of environments, which take the type of the top level. Returns the
+/// migrated file and a description of each change. toml_edit keeps the comments and
+/// formatting of the rest of the file, but the settings that are replaced lose theirs.
+pub fn migrate_config(contents: &str) -> Result<(String, Vec<String>)> {
+    let original: toml::Value = toml::from_str(contents)?;
+    let mut doc = contents
+        .parse::<Document>()
+        .map_err(|e| anyhow!("toml_edit failed to parse the configuration file. {}", e))?;
+
+    let mut changes = Vec::new();
+    let root = match doc.root.as_table_mut() {
+        Some(root) => root,
+        None => anyhow::bail!("Expected the configuration file to be a table"),
+    };
+    migrate_environment_table(root, None, &mut changes)?;
+    if root.contains_key("env") {
+        if let Some(envs) = root.entry("env").as_table_mut() {
+            let names: Vec<String> = envs.iter().map(|(name, _)| name.to_string()).collect();
+            for name in names {
+                if let Some(env) = envs.entry(&name).as_table_mut() {
+                    migrate_environment_table(env, Some(&name), &mut changes)?;
+                }
+            }
+        }
+    }
+    if changes.is_empty() {
+        return Ok((contents.to_string(), changes));
+    }
+    let migrated = doc.to_string_in_original_order();
+
+    // make sure the settings rewritten mean what they're meant to
+    let reparsed: toml::Value = toml::from_str(&migrated)
+        .map_err(|e| anyhow!("Migrating would break the configuration file: {}", e))?;
+    if reparsed != migrated_value(original) {
+        anyhow::bail!(
+            "Some of the settings of the configuration file are written in a way `wrangler config migrate` can't rewrite, migrate them by hand"
+        )
+    }
+    Ok((migrated, changes))
+}
+
+// Migrates the top level, or the table of the environment `env`
+fn migrate_environment_table(
+    table: &mut Table,
+    env: Option<&str>,
+    changes: &mut Vec<String>,
+) -> Result<()> {
+    let place = match env {
+        Some(env) => format!("[env.{}]", env),
+        None => "the top level".to_string(),
+    };
+
+    if let Some(private) = value(table, "private").and_then(Value::as_bool) {
+        table.remove("private");
+        if table.contains_key("workers_dev") {
+            changes.push(format!(
+                "Removed `private` from {}, `workers_dev` is set there",
+                place
+            ));
+        } else {
+            *table.entry("workers_dev") = toml_edit::value(!private);
+            changes.push(format!(
+                "Replaced `private = {}` with `workers_dev = {}` in {}",
+                private, !private, place
+            ));
+        }
+    }
+
+    if let Some(route) = value(table, "route")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+    {
+        table.remove("route");
+        if route.is_empty() {
+            changes.push(format!("Removed the empty `route` from {}", place));
+        } else if table.contains_key("routes") {
+            add_route(table.entry("routes"), &route, &place)?;
+            changes.push(format!("Moved `route` into `routes` in {}", place));
+        } else {
+            let mut routes = Array::default();
+            push(&mut routes, Value::from(route.as_str()), &place)?;
+            *table.entry("routes") = toml_edit::value(routes);
+            changes.push(format!("Replaced `route` with `routes` in {}", place));
+        }
+    }
+
+    if table.contains_key("kv-namespaces") {
+        if table.contains_key("kv_namespaces") {
+            anyhow::bail!(
+                "Both `kv-namespaces` and `kv_namespaces` are set in {}, merge them by hand",
+                place
+            )
+        }
+        if let Some(namespaces) = table.remove("kv-namespaces") {
+            *table.entry("kv_namespaces") = namespaces;
+            changes.push(format!(
+                "Renamed `kv-namespaces` to `kv_namespaces` in {}",
+                place
+            ));
+        }
+    }
+
+    if env.is_some() && table.contains_key("type") {
+        table.remove("type");
+        changes.push(format!(
+            "Removed `type` from {}, environments take the type of the top level",
+            place
+        ));
+    }
+
+    if table.contains_key("durable_objects") {
+        if let Some(durable_objects) = table.entry("durable_objects").as_table_mut() {
+            if durable_objects.contains_key("classes") {
+                let renamed = rename_class_names(durable_objects.entry("classes"));
+                if renamed > 0 {
+                    changes.push(format!(
+                        "Renamed `name` to `binding` in the durable object classes of {}",
+                        place
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// The value of `key` in `table`, if it is set to one rather than to a table
+fn value<'a>(table: &'a mut Table, key: &str) -> Option<&'a Value> {
+    if table.contains_key(key) {
+        table.entry(key).as_value()
+    } else {
+        None
+    }
+}
+
+// Adds `route` to the end of `routes`, as a table like the others when they're tables
+fn add_route(routes: &mut Item, route: &str, place: &str) -> Result<()> {
+    match routes {
+        // [[routes]]
+        Item::ArrayOfTables(tables) => {
+            let mut table = Table::new();
+            *table.entry("pattern") = toml_edit::value(route);
+            tables.append(table);
+        }
+        // routes = [...]
+        Item::Value(Value::Array(routes)) => {
+            let route = if routes.iter().any(|r| r.as_inline_table().is_some()) {
+                let mut table = InlineTable::default();
+                table.get_or_insert("pattern", route);
+                table.fmt();
+                Value::InlineTable(table)
+            } else {
+                Value::from(route)
+            };
+            push(routes, route, place)?;
+        }
+        _ => anyhow::bail!("`routes` in {} is not a list of routes", place),
+    }
+    Ok(())
+}
+
+fn push(array: &mut Array, value: Value, place: &str) -> Result<()> {
+    array
+        .push(value)
+        .map_err(|_| anyhow!("`routes` in {} mixes routes of different kinds", place))
+}
+
+// Renames the `name` of the classes that have no `binding` to `binding`, and returns how
+// many it renamed
+fn rename_class_names(classes: &mut Item) -> usize {
+    let mut renamed = 0;
+    match classes {
+        // [[durable_objects.classes]]
+        Item::ArrayOfTables(tables) => {
+            for i in 0..tables.len() {
+                let class = tables.get_mut(i).expect("index is in bounds");
+                if class.contains_key("name") && !class.contains_key("binding") {
+                    if let Some(name) = class.remove("name") {
+                        *class.entry("binding") = name;
+                        renamed += 1;
+                    }
+                }
+            }
+        }
+        // classes = [{ ... }]
+        Item::Value(Value::Array(classes)) => {
+            let mut updated = Array::default();
+            for class in classes.iter() {
+                let mut class = class.clone();
+                if let Some(table) = class.as_inline_table_mut() {
+                    if !table.contains_key("binding") {
+                        if let Some(name) = table.remove("name") {
+                            table.get_or_insert("binding", name);
+                            table.fmt();
+                            renamed += 1;
+                        }
+                    }
+                }
+                // the classes were all tables or all something else already
+                let _ = updated.push(class);
+            }
+            *classes = updated;
+        }
+        _ => {}
+    }
+    renamed
+}
+
+// What the original file means once migrated, for checking the lines rewritten against
+fn migrated_value(mut config: toml::Value) -> toml::Value {
+    migrate_environment(&mut config);
+    if let Some(envs) = config.get_mut("env").and_then(toml::Value::as_table_mut) {
+        for env in envs.values_mut() {
+            if let Some(env) = env.as_table_mut() {
+                env.remove("type");
+            }
+            migrate_environment(env);
+        }
+    }
+    config
+}
+
+fn migrate_environment(environment: &mut toml::Value) {
+    let environment = match environment.as_table_mut() {
+        Some(environment) => environment,
+        None => return,
+    };
+    if let Some(toml::Value::Boolean(private)) = environment.get("private").cloned() {
+        environment.remove("private");
+        environment
+            .entry("workers_dev".to_string())
+            .or_insert(toml::Value::Boolean(!private));
+    }
+    if let Some(toml::Value::String(route)) = environment.get("route").cloned() {
+        environment.remove("route");
+        if !route.is_empty() {
+            match environment.get_mut("routes") {
+                Some(toml::Value::Array(routes)) => {
+                    let route = if routes.iter().any(toml::Value::is_table) {
+                        let mut table = toml::value::Table::new();
+                        table.insert("pattern".to_string(), toml::Value::String(route));
+                        toml::Value::Table(table)
+                    } else {
+                        toml::Value::String(route)
+                    };
+                    routes.push(route)
+                }
+                _ => {
+                    let route = toml::Value::String(route);
+                    environment.insert("routes".to_string(), toml::Value::Array(vec![route]));
+                }
+            }
+        }
+    }
+    if let Some(namespaces) = environment.remove("kv-namespaces") {
+        environment.insert("kv_namespaces".to_string(), namespaces);
+    }
+    let classes = environment
+        .get_mut("durable_objects")
+        .and_then(|durable_objects| durable_objects.get_mut("classes"))
+        .and_then(toml::Value::as_array_mut);
+    for class in classes.into_iter().flatten() {
+        if let Some(class) = class.as_table_mut() {
+            if !class.contains_key("binding") {
+                if let Some(name) = class.remove("name") {
+                    class.insert("binding".to_string(), name);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_migrates_older_settings() {
+        let config = r#"name = "worker" # the name
+type = "javascript"
+private = true
+route = "example.com/*"
+
+[[kv-namespaces]]
+binding = "CACHE"
+id = "1234"
+
+[env.staging]
+type = "javascript"
+workers_dev = true
+private = false
+route = "staging.example.com/*"
+routes = ["api.staging.example.com/*"]
+
+[[env.staging.durable_objects.classes]]
+name = "COUNTER"
+class_name = "Counter"
+"#;
+        let (migrated, changes) = migrate_config(config).unwrap();
+        assert!(migrated.starts_with("name = \"worker\" # the name\n"));
+        assert_eq!(
+            toml::from_str::<toml::Value>(&migrated).unwrap(),
+            toml::from_str::<toml::Value>(
+                r#"name = "worker"
+type = "javascript"
+workers_dev = false
+routes = ["example.com/*"]
+
+[[kv_namespaces]]
+binding = "CACHE"
+id = "1234"
+
+[env.staging]
+workers_dev = true
+routes = ["api.staging.example.com/*", "staging.example.com/*"]
+
+[[env.staging.durable_objects.classes]]
+binding = "COUNTER"
+class_name = "Counter"
+"#
+            )
+            .unwrap()
+        );
+        assert_eq!(changes.len(), 7);
+    }
+
+    #[test]
+    fn it_adds_a_route_to_routes_of_tables() {
+        let config = "[env.staging]\nroute = \"staging.example.com/*\"\nroutes = [{ pattern = \"api.staging.example.com/*\", zone_id = \"1234\" }]\n";
+        let (migrated, _) = migrate_config(config).unwrap();
+        let migrated: toml::Value = toml::from_str(&migrated).unwrap();
+        assert_eq!(
+            migrated["env"]["staging"]["routes"][1]["pattern"].as_str(),
+            Some("staging.example.com/*")
+        );
+    }
+
+    #[test]
+    fn it_leaves_current_configuration_files_alone() {
+        let config = "name = \"worker\"\nworkers_dev = true # \"private\"\n\n[env.production]\nroutes = [\"example.com/*\"]\n";
+        let (migrated, changes) = migrate_config(config).unwrap();
+        assert_eq!(migrated, config);
+        assert!(changes.is_empty());
+    }
+}
@@ -0,0 +1,448 @@
+use std::env;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::blocking::Client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::kv;
+use crate::commands::previews::{self, ScriptResponse};
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::kv::key::KeyList;
+use crate::kv::namespace::{self, UpsertedNamespace};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::ui::Table;
+
+/// The KV namespace wrangler keeps the deployment history of every worker of an account in
+pub const NAMESPACE_TITLE: &str = "__wrangler_deployments";
+
+/// What `wrangler publish` records about each deployment
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Deployment {
+    pub script_name: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_sha: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Why the deployment went ahead although publishing was frozen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freeze_override: Option<String>,
+    /// Where the deployment was published from, see `project`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// The etag of the script that was published, to tell whether its content changed since
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+}
+
+impl Deployment {
+    /// A deployment of `target` happening now, from the current git checkout if there is one
+    pub fn new(target: &Target, message: Option<String>) -> Self {
+        let author = previews::git(&["config", "user.name"])
+            .ok()
+            .filter(|name| !name.is_empty())
+            .or_else(|| env::var("USER").ok());
+        Deployment {
+            script_name: target.name.clone(),
+            timestamp: Utc::now(),
+            message,
+            git_sha: previews::git(&["rev-parse", "HEAD"]).ok(),
+            author,
+            freeze_override: None,
+            project: project(),
+            etag: None,
+        }
+    }
+
+    // Zero padded so the keys of a worker sort by the time they were deployed
+    fn key(&self) -> String {
+        format!(
+            "{}{:015}",
+            key_prefix(&self.script_name),
+            self.timestamp.timestamp_millis()
+        )
+    }
+}
+
+// The remote of the git checkout along with the directory in it, which is the same for
+// everyone working on the project, or else the directory itself
+fn project() -> Option<String> {
+    match previews::git(&["remote", "get-url", "origin"]) {
+        Ok(remote) if !remote.is_empty() => {
+            let prefix = previews::git(&["rev-parse", "--show-prefix"]).unwrap_or_default();
+            Some(format!("{} {}", remote, prefix).trim_end().to_string())
+        }
+        _ => env::current_dir()
+            .and_then(|dir| dir.canonicalize())
+            .ok()
+            .map(|dir| dir.display().to_string()),
+    }
+}
+
+fn key_prefix(script_name: &str) -> String {
+    format!("{}:", script_name)
+}
+
+/// Stores `deployment` in the deployment history of the account of `target`
+pub fn record(user: &GlobalUser, target: &Target, deployment: &Deployment) -> Result<()> {
+    let namespace_id = match namespace::upsert(target, user, NAMESPACE_TITLE.to_string())? {
+        UpsertedNamespace::Created(namespace) => namespace.id,
+        UpsertedNamespace::Reused(namespace) => namespace.id,
+    };
+
+    let res = http::legacy_auth_client(user)
+        .put(&value_addr(target, &namespace_id, &deployment.key())?)
+        .body(serde_json::to_vec(deployment)?)
+        .send_logged_as(Method::PUT)?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(())
+}
+
+/// Whether the worker publishing `target` would overwrite is this project's
+#[derive(Debug, PartialEq)]
+pub enum Ownership {
+    /// There is no such worker yet, or it was last published from this project
+    Ours,
+    /// The worker exists but there's no telling where from, which is worth a warning
+    Unknown(String),
+    /// The worker was changed by something else than `wrangler publish` or published from
+    /// another project, which is worth asking about
+    Theirs(String),
+}
+
+/// Compares the worker `target` publishes to, if it exists, with its recorded deployments
+pub fn ownership(user: &GlobalUser, target: &Target) -> Result<Ownership> {
+    let script = match published_script(user, target)? {
+        Some(script) => script,
+        None => return Ok(Ownership::Ours),
+    };
+    let latest = latest(user, target)?;
+    Ok(compare(
+        &target.name,
+        &script,
+        latest.as_ref(),
+        &Deployment::new(target, None),
+    ))
+}
+
+/// The etag of the script of `target` as it is published now, for `Deployment::etag`
+pub fn published_etag(user: &GlobalUser, target: &Target) -> Result<Option<String>> {
+    Ok(published_script(user, target)?.and_then(|script| script.etag))
+}
+
+fn published_script(user: &GlobalUser, target: &Target) -> Result<Option<ScriptResponse>> {
+    let client = http::legacy_auth_client(user);
+    Ok(previews::list_scripts(&client, target.account_id.load()?)?
+        .into_iter()
+        .find(|script| script.id == target.name))
+}
+
+fn compare(
+    name: &str,
+    script: &ScriptResponse,
+    latest: Option<&Deployment>,
+    this: &Deployment,
+) -> Ownership {
+    let latest = match latest {
+        Some(latest) => latest,
+        None => {
+            return Ownership::Unknown(format!(
+                "{} already exists, but `wrangler publish` never recorded deploying it",
+                name
+            ))
+        }
+    };
+    let by = latest
+        .author
+        .as_ref()
+        .map(|author| format!(" by {}", author))
+        .unwrap_or_default();
+    match &latest.etag {
+        // secrets and settings change when the script was modified, but not its content
+        Some(etag) if script.etag.as_ref() != Some(etag) => {
+            return Ownership::Theirs(format!(
+                "The script of {} was replaced on {} by something else than `wrangler publish`, which last published it on {}{}",
+                name,
+                script.modified_on.format("%Y-%m-%d %H:%M UTC"),
+                latest.timestamp.format("%Y-%m-%d %H:%M UTC"),
+                by
+            ))
+        }
+        Some(_) => {}
+        // older deployments don't say what was published. The script is uploaded a moment
+        // before its deployment is recorded.
+        None if script.modified_on > latest.timestamp + Duration::minutes(1) => {
+            return Ownership::Unknown(format!(
+                "{} was modified on {}, after `wrangler publish` last published it on {}{}; maybe only its secrets or settings changed",
+                name,
+                script.modified_on.format("%Y-%m-%d %H:%M UTC"),
+                latest.timestamp.format("%Y-%m-%d %H:%M UTC"),
+                by
+            ))
+        }
+        None => {}
+    }
+    match (&latest.project, &this.project) {
+        (Some(theirs), Some(ours)) if theirs != ours => Ownership::Theirs(format!(
+            "{} was last published from {}{}, not from this project",
+            name, theirs, by
+        )),
+        // older deployments only tell who published them
+        (None, _) if latest.author.is_some() && latest.author != this.author => {
+            Ownership::Unknown(format!("{} was last published{}", name, by))
+        }
+        _ => Ownership::Ours,
+    }
+}
+
+// The last recorded deployment of `target`, if there is one
+fn latest(user: &GlobalUser, target: &Target) -> Result<Option<Deployment>> {
+    let client = http::cf_v4_client(user)?;
+    let namespace_id = match namespace::list(&client, target.account_id.load()?)?
+        .into_iter()
+        .find(|ns| ns.title == NAMESPACE_TITLE)
+    {
+        Some(namespace) => namespace.id,
+        None => return Ok(None),
+    };
+    let mut keys = Vec::new();
+    for key in KeyList::new(
+        target,
+        client,
+        &namespace_id,
+        Some(&key_prefix(&target.name)),
+    )? {
+        match key {
+            Ok(key) => keys.push(key.name),
+            Err(e) => return Err(kv::api_error(e)),
+        }
+    }
+    match newest(keys, 1).pop() {
+        Some(key) => Ok(Some(fetch(
+            &http::legacy_auth_client(user),
+            target,
+            &namespace_id,
+            &key,
+        )?)),
+        None => Ok(None),
+    }
+}
+
+/// Prints the last `limit` deployments of `target`, newest first
+pub fn list(user: &GlobalUser, target: &Target, limit: usize) -> Result<()> {
+    let client = http::cf_v4_client(user)?;
+    let namespaces = namespace::list(&client, target.account_id.load()?)?;
+    let namespace_id = match namespaces
+        .into_iter()
+        .find(|ns| ns.title == NAMESPACE_TITLE)
+    {
+        Some(namespace) => namespace.id,
+        None => anyhow::bail!("No deployments have been recorded on this account yet"),
+    };
+
+    let mut keys = Vec::new();
+    for key in KeyList::new(
+        target,
+        client,
+        &namespace_id,
+        Some(&key_prefix(&target.name)),
+    )? {
+        match key {
+            Ok(key) => keys.push(key.name),
+            Err(e) => return Err(kv::api_error(e)),
+        }
+    }
+    let keys = newest(keys, limit);
+    if keys.is_empty() {
+        anyhow::bail!("No deployments of {} have been recorded yet", target.name)
+    }
+
+    let client = http::legacy_auth_client(user);
+    let mut table = Table::new(&["Deployed", "Author", "Commit", "Message"]);
+    for key in keys {
+        let deployment = fetch(&client, target, &namespace_id, &key)?;
+        let commit = deployment
+            .git_sha
+            .as_deref()
+            .map(|sha| sha.chars().take(7).collect())
+            .unwrap_or_default();
+        table.add_row(vec![
+            deployment
+                .timestamp
+                .format("%Y-%m-%d %H:%M:%S UTC")
+                .to_string(),
+            deployment.author.clone().unwrap_or_default(),
+            commit,
+            describe(&deployment),
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+// The message of a deployment, along with why it overrode a freeze if it did
+fn describe(deployment: &Deployment) -> String {
+    let message = deployment.message.as_deref().unwrap_or_default();
+    match &deployment.freeze_override {
+        Some(reason) if message.is_empty() => format!("[freeze overridden: {}]", reason),
+        Some(reason) => format!("{} [freeze overridden: {}]", message, reason),
+        None => message.to_string(),
+    }
+}
+
+fn newest(mut keys: Vec<String>, limit: usize) -> Vec<String> {
+    keys.sort_unstable_by(|a, b| b.cmp(a));
+    keys.truncate(limit);
+    keys
+}
+
+fn fetch(client: &Client, target: &Target, namespace_id: &str, key: &str) -> Result<Deployment> {
+    let res = client
+        .get(&value_addr(target, namespace_id, key)?)
+        .send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json()?)
+}
+
+fn value_addr(target: &Target, namespace_id: &str, key: &str) -> Result<String> {
+    http::api_url(&format!(
+        "accounts/{}/storage/kv/namespaces/{}/values/{}",
+        target.account_id.load()?,
+        namespace_id,
+        kv::url_encode_key(key)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn it_lists_the_newest_deployments_first() {
+        let deployment = |millis| Deployment {
+            script_name: "worker".to_string(),
+            timestamp: Utc.timestamp_millis(millis),
+            message: None,
+            git_sha: None,
+            author: None,
+            freeze_override: None,
+            project: None,
+            etag: None,
+        };
+        let keys = vec![
+            deployment(999).key(),
+            deployment(1_650_000_000_000).key(),
+            deployment(1_600_000_000_000).key(),
+        ];
+
+        assert_eq!(keys[0], "worker:000000000000999");
+        assert_eq!(
+            newest(keys, 2),
+            vec!["worker:001650000000000", "worker:001600000000000"]
+        );
+    }
+
+    #[test]
+    fn it_tells_whose_worker_it_is() {
+        let deployment = |project: &str, author: &str| Deployment {
+            script_name: "worker".to_string(),
+            timestamp: Utc.timestamp(1_650_000_000, 0),
+            message: None,
+            git_sha: None,
+            author: Some(author.to_string()),
+            freeze_override: None,
+            project: Some(project.to_string()).filter(|p| !p.is_empty()),
+            etag: Some("abc".to_string()),
+        };
+        let script = |seconds: i64, etag: &str| ScriptResponse {
+            id: "worker".to_string(),
+            modified_on: Utc.timestamp(seconds, 0),
+            etag: Some(etag.to_string()),
+        };
+        let this = deployment("git@example.com:team/worker.git", "ada");
+        let published = script(1_650_000_010, "abc");
+
+        assert_eq!(
+            compare("worker", &published, Some(&this), &this),
+            Ownership::Ours
+        );
+        assert!(matches!(
+            compare("worker", &published, None, &this),
+            Ownership::Unknown(_)
+        ));
+        // a secret put later modifies the script, but not its content
+        assert_eq!(
+            compare("worker", &script(1_650_100_000, "abc"), Some(&this), &this),
+            Ownership::Ours
+        );
+        assert!(matches!(
+            compare("worker", &script(1_650_100_000, "def"), Some(&this), &this),
+            Ownership::Theirs(_)
+        ));
+        let other = deployment("/home/grace/worker", "grace");
+        assert!(matches!(
+            compare("worker", &published, Some(&other), &this),
+            Ownership::Theirs(message) if message.contains("/home/grace/worker")
+        ));
+        let unrecorded = deployment("", "grace");
+        assert!(matches!(
+            compare("worker", &published, Some(&unrecorded), &this),
+            Ownership::Unknown(_)
+        ));
+    }
+
+    #[test]
+    fn it_only_warns_about_changes_to_scripts_published_without_an_etag() {
+        let before_etags = Deployment {
+            script_name: "worker".to_string(),
+            timestamp: Utc.timestamp(1_650_000_000, 0),
+            message: None,
+            git_sha: None,
+            author: None,
+            freeze_override: None,
+            project: None,
+            etag: None,
+        };
+        let modified = ScriptResponse {
+            id: "worker".to_string(),
+            modified_on: Utc.timestamp(1_650_100_000, 0),
+            etag: Some("abc".to_string()),
+        };
+        assert!(matches!(
+            compare("worker", &modified, Some(&before_etags), &before_etags),
+            Ownership::Unknown(message) if message.contains("secrets or settings")
+        ));
+    }
+
+    #[test]
+    fn it_shows_overridden_freezes_with_the_message() {
+        let mut deployment = Deployment {
+            script_name: "worker".to_string(),
+            timestamp: Utc.timestamp_millis(0),
+            message: Some("Hotfix".to_string()),
+            git_sha: None,
+            author: None,
+            freeze_override: None,
+            project: None,
+            etag: None,
+        };
+        assert_eq!(describe(&deployment), "Hotfix");
+        deployment.freeze_override = Some("outage".to_string());
+        assert_eq!(describe(&deployment), "Hotfix [freeze overridden: outage]");
+        deployment.message = None;
+        assert_eq!(describe(&deployment), "[freeze overridden: outage]");
+    }
+}
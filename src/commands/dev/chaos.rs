@@ -0,0 +1,154 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Result;
+use hyper::{Body, Response, StatusCode};
+use rand::Rng;
+use tokio::time::sleep;
+
+const DEFAULT_LATENCY_MS: u64 = 1000;
+
+/// Faults `wrangler dev --chaos` injects into the requests it proxies to the preview
+/// worker. Bindings such as KV run next to the worker on Cloudflare's edge, so only the
+/// requests passing through the dev server can be disrupted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Chaos {
+    /// Share of requests answered with a 503 instead of reaching the worker
+    pub fetch: f64,
+    /// Share of requests held back for `latency_ms` before being proxied
+    pub latency: f64,
+    pub latency_ms: u64,
+}
+
+impl Chaos {
+    pub fn is_enabled(&self) -> bool {
+        self.fetch > 0.0 || self.latency > 0.0
+    }
+
+    /// Delays the request if it was picked for latency, then returns the response to fail
+    /// it with if it was picked for failure.
+    pub async fn disrupt(&self) -> Option<Response<Body>> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let (delay, fail) = {
+            let mut rng = rand::thread_rng();
+            (rng.gen_bool(self.latency), rng.gen_bool(self.fetch))
+        };
+
+        if delay {
+            sleep(Duration::from_millis(self.latency_ms)).await;
+        }
+        if fail {
+            let response = Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header("cf-wrangler-chaos", "fetch")
+                .body(Body::from(
+                    "This request was failed by `wrangler dev --chaos`",
+                ))
+                .expect("Could not build chaos response");
+            return Some(response);
+        }
+        None
+    }
+}
+
+impl fmt::Display for Chaos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}% of requests fail with a 503, {}% are delayed by {}ms",
+            self.fetch * 100.0,
+            self.latency * 100.0,
+            self.latency_ms
+        )
+    }
+}
+
+// Parses a comma separated list of faults like "fetch:0.05,latency:0.1:500", where the
+// number after each fault is the share of requests it applies to.
+impl FromStr for Chaos {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chaos = Chaos {
+            latency_ms: DEFAULT_LATENCY_MS,
+            ..Default::default()
+        };
+
+        for fault in s.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let mut parts = fault.split(':');
+            let kind = parts.next().unwrap_or_default();
+            let rate = match parts.next().map(str::parse::<f64>) {
+                Some(Ok(rate)) if (0.0..=1.0).contains(&rate) => rate,
+                _ => anyhow::bail!(
+                    "Invalid fault \"{}\", expected a rate between 0 and 1 like \"{}:0.1\"",
+                    fault,
+                    kind
+                ),
+            };
+
+            match kind {
+                "fetch" => chaos.fetch = rate,
+                "latency" => {
+                    chaos.latency = rate;
+                    if let Some(ms) = parts.next() {
+                        chaos.latency_ms = match ms.parse() {
+                            Ok(ms) => ms,
+                            Err(_) => anyhow::bail!(
+                                "Invalid latency \"{}\" in \"{}\", expected milliseconds",
+                                ms,
+                                fault
+                            ),
+                        };
+                    }
+                }
+                "kv" => anyhow::bail!(
+                    "KV bindings run on Cloudflare's edge during `wrangler dev`, so their failures can't be injected locally. Use \"fetch\" or \"latency\" instead"
+                ),
+                _ => anyhow::bail!(
+                    "Unknown fault \"{}\", expected \"fetch\" or \"latency\"",
+                    kind
+                ),
+            }
+
+            if parts.next().is_some() {
+                anyhow::bail!("Invalid fault \"{}\"", fault)
+            }
+        }
+
+        Ok(chaos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_fault_rates() {
+        let chaos: Chaos = "fetch:0.05, latency:0.1:250".parse().unwrap();
+        assert_eq!(
+            chaos,
+            Chaos {
+                fetch: 0.05,
+                latency: 0.1,
+                latency_ms: 250,
+            }
+        );
+        assert!(chaos.is_enabled());
+
+        let chaos: Chaos = "latency:1".parse().unwrap();
+        assert_eq!(chaos.latency_ms, DEFAULT_LATENCY_MS);
+    }
+
+    #[test]
+    fn it_rejects_faults_it_cannot_inject() {
+        assert!("fetch:1.5".parse::<Chaos>().is_err());
+        assert!("fetch".parse::<Chaos>().is_err());
+        assert!("latency:0.1:soon".parse::<Chaos>().is_err());
+        assert!("kv:0.1".parse::<Chaos>().is_err());
+        assert!("dns:0.1".parse::<Chaos>().is_err());
+    }
+}
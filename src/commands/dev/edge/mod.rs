@@ -9,6 +9,7 @@ use watch::watch_for_changes;
 
 use crate::commands::dev::{socket, Protocol, ServerConfig};
 use crate::deploy::DeployTarget;
+use crate::http::SendLogged;
 use crate::login::check_update_oauth_token;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
@@ -108,7 +109,7 @@ fn dev_once(
         client
             .post(session.prewarm_url)
             .header("cf-workers-preview-token", &preview_token)
-            .send()?
+            .send_logged()?
             .error_for_status()?;
         Some(target.name.clone())
     } else {
@@ -136,7 +137,7 @@ fn dev_once(
 
     let devtools_listener = runtime.spawn(socket::listen(
         session.websocket_url,
-        server_config.clone(),
+        format!("http://{}", server_config.listening_address),
         inspect,
         Some(refresh_session_sender),
     ));
@@ -54,7 +54,21 @@ pub async fn https(
                 let req_method = parts.method.to_string();
                 let now: DateTime<Local> = Local::now();
                 let path = get_path_as_str(&parts.uri);
+                let chaos = server_config.chaos.clone();
                 async move {
+                    if let Some(resp) = chaos.disrupt().await {
+                        println!(
+                            "[{}] {} {}{} {:?} {} (--chaos)",
+                            now.format("%Y-%m-%d %H:%M:%S"),
+                            req_method,
+                            host,
+                            path,
+                            version,
+                            resp.status()
+                        );
+                        return Ok(resp);
+                    }
+
                     let mut req = preview_request(
                         parts,
                         body,
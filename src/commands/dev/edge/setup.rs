@@ -3,6 +3,7 @@ use std::fmt;
 use std::path::Path;
 
 use crate::deploy::DeployTarget;
+use crate::http::SendLogged;
 use crate::kv::bulk;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
@@ -11,7 +12,7 @@ use crate::terminal::message::{Message, StdOut};
 use crate::upload;
 
 use anyhow::{anyhow, Result};
-use reqwest::{StatusCode, Url};
+use reqwest::{Method, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
@@ -48,8 +49,9 @@ pub(super) fn upload(
     let response = client
         .post(&address)
         .header("cf-preview-upload-config-token", session_token)
+        .timeout(crate::http::timeouts().upload())
         .multipart(script_upload_form)
-        .send()?;
+        .send_logged_as(Method::POST)?;
 
     if response.status() == StatusCode::BAD_REQUEST {
         return Err(BadRequestError(crate::format_api_errors(response.text()?)).into());
@@ -94,7 +96,7 @@ impl Session {
         };
 
         let client = crate::http::legacy_auth_client(user);
-        let response = client.get(exchange_url).send()?.error_for_status()?;
+        let response = client.get(exchange_url).send_logged()?.error_for_status()?;
         let text = &response.text()?;
         let response: InspectorV4ApiResponse = serde_json::from_str(text)?;
         let websocket_url = format!(
@@ -129,23 +131,22 @@ fn get_session_config(target: &DeployTarget) -> serde_json::Value {
 
 fn get_session_address(target: &DeployTarget) -> Result<String> {
     let addr = match target {
-        DeployTarget::Zoned(config) => format!(
-            "https://api.cloudflare.com/client/v4/zones/{}/workers/edge-preview",
-            config.zone_id
-        ),
+        DeployTarget::Zoned(config) => {
+            crate::http::api_url(&format!("zones/{}/workers/edge-preview", config.zone_id))?
+        }
         // TODO: zoneless is probably wrong
-        DeployTarget::Zoneless(config) => format!(
-            "https://api.cloudflare.com/client/v4/accounts/{}/workers/subdomain/edge-preview",
+        DeployTarget::Zoneless(config) => crate::http::api_url(&format!(
+            "accounts/{}/workers/subdomain/edge-preview",
             config.account_id.load()?,
-        ),
+        ))?,
         _ => unreachable!(),
     };
     Ok(addr)
 }
 
 fn get_upload_address(target: &mut Target) -> Result<String> {
-    Ok(format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/edge-preview",
+    crate::http::api_url(&format!(
+        "accounts/{}/workers/scripts/{}/edge-preview",
         target.account_id.load()?,
         target.name
     ))
@@ -155,7 +156,7 @@ fn get_exchange_url(deploy_target: &DeployTarget, user: &GlobalUser) -> Result<U
     let client = crate::http::legacy_auth_client(user);
     let address = get_session_address(deploy_target)?;
     let url = Url::parse(&address)?;
-    let response = client.get(url).send()?.error_for_status()?;
+    let response = client.get(url).send_logged()?.error_for_status()?;
     let text = &response.text()?;
     let response: SessionV4ApiResponse = serde_json::from_str(text)?;
     let url = Url::parse(&response.result.exchange_url)?;
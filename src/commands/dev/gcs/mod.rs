@@ -6,12 +6,15 @@ mod watch;
 use setup::{get_preview_id, get_session_id};
 use watch::watch_for_changes;
 
+pub(crate) use server::forward;
+
 use crate::commands::dev::{socket, Protocol, ServerConfig};
+use crate::http::SendLogged;
 use crate::settings::toml::Target;
 
 use anyhow::Result;
 use std::sync::{Arc, Mutex};
-use std::thread;
+use std::thread::{self, JoinHandle};
 use tokio::runtime::Runtime as TokioRuntime;
 use url::Url;
 
@@ -42,16 +45,8 @@ pub fn dev(
         verbose,
     )?;
 
-    // prewarm the request so `--inspect` works right away
-    // note that this doesn't make a normal GET request, since that might affect the worker state
     if inspect.is_some() {
-        let client = reqwest::blocking::Client::builder().build()?;
-        client
-            .post("https://prewarm.cloudflareworkers.com/")
-            .header("CF-EW-Preview", &preview_id)
-            .body("") // so reqwest will set the Content-Length header
-            .send()?
-            .error_for_status()?;
+        prewarm(&preview_id)?;
     }
 
     // the local server needs the preview ID to properly route
@@ -92,7 +87,7 @@ pub fn dev(
     runtime.block_on(async {
         let devtools_listener = runtime.spawn(socket::listen(
             socket_url.clone(),
-            server_config.clone(),
+            format!("http://{}", server_config.listening_address),
             inspect,
             None,
         ));
@@ -115,6 +110,35 @@ pub fn dev(
     })
 }
 
+/// Bridges the inspector of the preview session `session_id` to Chrome DevTools on a thread
+/// of its own, for `wrangler preview --inspect`. `url` is what DevTools shows the worker as.
+pub(crate) fn inspect(
+    session_id: &str,
+    preview_id: &str,
+    worker_name: String,
+    url: String,
+) -> Result<JoinHandle<Result<()>>> {
+    prewarm(preview_id)?;
+    let socket_url = get_socket_url(session_id)?;
+    Ok(thread::spawn(move || {
+        let runtime = TokioRuntime::new()?;
+        runtime.block_on(socket::listen(socket_url, url, Some(worker_name), None))
+    }))
+}
+
+// prewarm the request so `--inspect` works right away
+// note that this doesn't make a normal GET request, since that might affect the worker state
+fn prewarm(preview_id: &str) -> Result<()> {
+    let client = reqwest::blocking::Client::builder().build()?;
+    client
+        .post("https://prewarm.cloudflareworkers.com/")
+        .header("CF-EW-Preview", preview_id)
+        .body("") // so reqwest will set the Content-Length header
+        .send_logged()?
+        .error_for_status()?;
+    Ok(())
+}
+
 fn get_socket_url(session_id: &str) -> Result<Url, url::ParseError> {
     Url::parse(&format!(
         "wss://cloudflareworkers.com/inspect/{}",
@@ -58,6 +58,19 @@ pub async fn https(server_config: ServerConfig, preview_id: Arc<Mutex<String>>)
                 let path = get_path_as_str(&parts.uri);
 
                 async move {
+                    if let Some(resp) = server_config.chaos.disrupt().await {
+                        println!(
+                            "[{}] {} {}{} {:?} {} (--chaos)",
+                            now.format("%Y-%m-%d %H:%M:%S"),
+                            req_method,
+                            server_config.host,
+                            path,
+                            version,
+                            resp.status()
+                        );
+                        return Ok(resp);
+                    }
+
                     // send the request to the preview service
                     let resp = client
                         .request(preview_request(parts, body, preview_id.to_owned()))
@@ -4,12 +4,15 @@ mod https;
 pub use self::http::http;
 pub use self::https::https;
 
-use crate::commands::dev::gcs::headers::structure_request;
+use crate::commands::dev::gcs::headers::{destructure_response, structure_request};
 use crate::commands::dev::utils::get_path_as_str;
 
+use anyhow::Result;
+use hyper::client::HttpConnector;
 use hyper::header::{HeaderName, HeaderValue};
 use hyper::http::uri::InvalidUri;
-use hyper::{Body, Request, Uri};
+use hyper::{Body, Client, Request, Response, Uri};
+use hyper_rustls::HttpsConnector;
 
 const PREVIEW_HOST: &str = "rawhttp.cloudflareworkers.com";
 
@@ -41,3 +44,19 @@ pub fn preview_request(
 
     Request::from_parts(parts, body)
 }
+
+/// Sends `req` to the worker previewed as `preview_id`, and returns the response of the
+/// worker without what the preview service adds to it
+pub async fn forward(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    req: Request<Body>,
+    preview_id: String,
+) -> Result<Response<Body>> {
+    let (parts, body) = req.into_parts();
+    let resp = client
+        .request(preview_request(parts, body, preview_id))
+        .await?;
+    let (mut parts, body) = resp.into_parts();
+    destructure_response(&mut parts)?;
+    Ok(Response::from_parts(parts, body))
+}
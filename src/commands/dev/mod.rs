@@ -1,3 +1,4 @@
+mod chaos;
 mod edge;
 mod gcs;
 mod server_config;
@@ -5,9 +6,9 @@ mod socket;
 mod tls;
 mod utils;
 
-use hyper::client::HttpConnector;
-use hyper::Body;
-use hyper_rustls::HttpsConnector;
+pub use chaos::Chaos;
+pub(crate) use gcs::forward as forward_to_preview;
+pub(crate) use gcs::inspect as inspect_preview;
 pub use server_config::Protocol;
 pub use server_config::ServerConfig;
 
@@ -19,8 +20,11 @@ use crate::terminal::message::{Message, StdOut};
 use crate::terminal::styles;
 
 use anyhow::Result;
+use hyper::client::HttpConnector;
+use hyper::Body;
+use hyper_rustls::HttpsConnector;
 
-fn client() -> hyper::Client<HttpsConnector<HttpConnector>> {
+pub(crate) fn client() -> hyper::Client<HttpsConnector<HttpConnector>> {
     let builder = hyper_rustls::HttpsConnectorBuilder::new()
         .with_native_roots()
         .https_or_http();
@@ -5,6 +5,8 @@ pub use protocol::Protocol;
 
 use host::Host;
 
+use super::Chaos;
+
 use anyhow::Result;
 use std::net::{IpAddr, SocketAddr, TcpListener};
 
@@ -12,6 +14,7 @@ use std::net::{IpAddr, SocketAddr, TcpListener};
 pub struct ServerConfig {
     pub host: Host,
     pub listening_address: SocketAddr,
+    pub chaos: Chaos,
 }
 
 impl ServerConfig {
@@ -42,6 +45,12 @@ impl ServerConfig {
         Ok(ServerConfig {
             host,
             listening_address,
+            chaos: Chaos::default(),
         })
     }
+
+    /// Injects the faults of `wrangler dev --chaos` into the proxied requests
+    pub fn with_chaos(self, chaos: Chaos) -> Self {
+        ServerConfig { chaos, ..self }
+    }
 }
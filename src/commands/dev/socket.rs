@@ -31,8 +31,6 @@ use tokio_tungstenite::{connect_async, tungstenite, MaybeTlsStream, WebSocketStr
 use anyhow::{anyhow, Result};
 use url::Url;
 
-use super::ServerConfig;
-
 const KEEP_ALIVE_INTERVAL: u64 = 10;
 const DEVTOOLS_PORT: u16 = 9230;
 
@@ -40,9 +38,10 @@ const DEVTOOLS_PORT: u16 = 9230;
 /// parse all console messages, and print them to stdout
 ///
 /// `inspect` should be the name of the worker if `--inspect` is passed, or `None` otherwise.
+/// `url` is what DevTools shows the worker as.
 pub async fn listen(
     socket_url: Url,
-    server_config: ServerConfig,
+    url: String,
     inspect: Option<String>,
     refresh_session_sender: Option<Sender<Option<()>>>,
 ) -> Result<()> {
@@ -63,8 +62,9 @@ pub async fn listen(
         // parse all incoming messages and print them to stdout
         if let Some(worker_name) = &inspect {
             StdErr::help(&format!(
-                "Open chrome://inspect, click 'Configure', and add localhost:{}",
-                DEVTOOLS_PORT
+                "Open chrome://inspect, click 'Configure', and add localhost:{}, or open {} in Chrome",
+                DEVTOOLS_PORT,
+                devtools_url(&socket_url)
             ));
 
             // Construct our SocketAddr to listen on...
@@ -83,14 +83,14 @@ pub async fn listen(
             let make_service = make_service_fn(|_conn| {
                 let socket_url = socket_url.clone();
                 let remote_stream = remote_stream.clone();
-                let listening_address = server_config.listening_address.to_string();
+                let url = url.clone();
                 let worker_name = worker_name.clone();
                 async move {
                     Ok::<_, Infallible>(service_fn(move |req| {
                         devtools_http_request(
                             req,
                             socket_url.clone(),
-                            listening_address.clone(),
+                            url.clone(),
                             uuid,
                             remote_stream.clone(),
                             worker_name.clone(),
@@ -253,7 +253,7 @@ async fn start_websocket(
 async fn devtools_http_request(
     req: Request<Body>,
     remote_ws: Url,
-    listening_address: String,
+    url: String,
     uuid: uuid::Uuid,
     remote_stream: Arc<tokio::sync::Mutex<WebSocketStream<MaybeTlsStream<TcpStream>>>>,
     worker_name: String,
@@ -274,18 +274,19 @@ async fn devtools_http_request(
         [ {{
             "description": "wrangler dev --inspect instance",
             "devtoolsFrontendUrl": "devtools://devtools/bundled/js_app.html?experiments=true&v8only=true&ws=localhost:{port}{path}",
-            "devtoolsFrontendUrlCompat": "devtools://devtools/bundled/inspector.html?experiments=true&v8only=true&ws=localhost:{port}{path}",
+            "devtoolsFrontendUrlCompat": "{devtools_url}",
             "id": "{uuid}",
             "type": "node",
             "title": "wrangler[{worker}]",
-            "url": "http://{local_address}",
+            "url": "{url}",
             "faviconUrl": "https://workers.cloudflare.com/resources/logo/logo.svg",
             "webSocketDebuggerUrl": "ws://localhost:{port}{path}"
           }} ]
         "#,
             uuid = uuid,
             worker = worker_name,
-            local_address = listening_address,
+            url = url,
+            devtools_url = devtools_url(&remote_ws),
             port = DEVTOOLS_PORT,
             path = remote_ws.path()
         );
@@ -309,6 +310,15 @@ async fn devtools_http_request(
         .map_err(Into::into)
 }
 
+/// The link that opens Chrome DevTools attached to the inspector bridged from `socket_url`
+pub fn devtools_url(socket_url: &Url) -> String {
+    format!(
+        "devtools://devtools/bundled/inspector.html?experiments=true&v8only=true&ws=localhost:{}{}",
+        DEVTOOLS_PORT,
+        socket_url.path()
+    )
+}
+
 // Endlessly retry connecting to the chrome devtools instance with exponential backoff.
 // The backoff maxes out at 60 seconds.
 async fn connect_retry(
@@ -0,0 +1,321 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::commands::publish::{validate_bucket_location, validate_target_required_fields_present};
+use crate::commands::validate_worker_name;
+use crate::commands::whoami::fetch_accounts_uncached;
+use crate::http;
+use crate::install::{self, dependencies};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{Manifest, Target, TargetType};
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::styles;
+
+// Slower than this and every command that talks to the API will feel it
+const SLOW_API: Duration = Duration::from_secs(2);
+
+/// What a check found: whether it passed, with a detail or the reason it didn't, and what
+/// to do about it
+enum Outcome {
+    Pass(String),
+    /// Works for now, but worth knowing about
+    Warn(String, String),
+    Fail(String, String),
+}
+
+/// Checks the tools the project needs, the credentials, the connection to the Cloudflare
+/// API and the configuration file, and reports how to fix what failed.
+pub fn doctor(config_path: &Path, env: Option<&str>) -> Result<()> {
+    let mut report = Report::default();
+
+    let target = match Manifest::new(config_path).and_then(|m| m.get_target(env, false)) {
+        Ok(target) => {
+            report.add("configuration file", check_target(&target));
+            Some(target)
+        }
+        Err(e) => {
+            report.add(
+                "configuration file",
+                Outcome::Fail(
+                    format!("{:#}", e),
+                    format!(
+                        "Fix {}, or create one with {}",
+                        config_path.display(),
+                        styles::highlight("`wrangler init`")
+                    ),
+                ),
+            );
+            None
+        }
+    };
+
+    let target_type = target.as_ref().map(|target| &target.target_type);
+    for tool in tools(target_type) {
+        report.add(tool.name, tool.check());
+    }
+
+    let user = match GlobalUser::new() {
+        Ok(user) => Some(user),
+        Err(e) => {
+            report.add(
+                "credentials",
+                Outcome::Fail(format!("{:#}", e), login_hint()),
+            );
+            None
+        }
+    };
+    report.add("Cloudflare API is reachable", check_api());
+    if let Some(user) = &user {
+        report.add("credentials", check_credentials(user, target.as_ref()));
+    }
+
+    report.finish()
+}
+
+fn check_target(target: &Target) -> Outcome {
+    let mut checks = vec![
+        validate_target_required_fields_present(target),
+        validate_worker_name(&target.name),
+    ];
+    if let Some(site) = &target.site {
+        checks.push(validate_bucket_location(&site.bucket));
+    }
+    match checks.into_iter().find_map(Result::err) {
+        Some(e) => Outcome::Fail(
+            format!("{:#}", e),
+            format!(
+                "Run {} for the rest of what publishing needs",
+                styles::highlight("`wrangler check`")
+            ),
+        ),
+        None => Outcome::Pass(format!(
+            "{} is a {} project",
+            target.name, target.target_type
+        )),
+    }
+}
+
+fn check_api() -> Outcome {
+    let url = match http::api_url("user/tokens/verify") {
+        Ok(url) => url,
+        Err(e) => return Outcome::Fail(format!("{:#}", e), "Check CF_API_BASE_URL".to_string()),
+    };
+    let started = Instant::now();
+    // any response at all means the API can be reached, credentials are checked on their own
+    match http::client().get(&url).send() {
+        Ok(_) => {
+            let elapsed = started.elapsed();
+            let detail = format!("responded in {}ms", elapsed.as_millis());
+            if elapsed > SLOW_API {
+                Outcome::Warn(
+                    detail,
+                    "The connection to api.cloudflare.com is slow, check your network or proxy"
+                        .to_string(),
+                )
+            } else {
+                Outcome::Pass(detail)
+            }
+        }
+        Err(e) => Outcome::Fail(
+            e.to_string(),
+            "Check your network, and set HTTPS_PROXY if you're behind a proxy".to_string(),
+        ),
+    }
+}
+
+fn check_credentials(user: &GlobalUser, target: Option<&Target>) -> Outcome {
+    let accounts = match fetch_accounts_uncached(user) {
+        Ok(accounts) => accounts,
+        Err(e) => return Outcome::Fail(format!("{:#}", e), login_hint()),
+    };
+    let configured = target.and_then(|target| target.account_id.if_present());
+    match configured {
+        Some(id) if !accounts.iter().any(|account| &account.id == id) => Outcome::Fail(
+            format!("account_id {} is not one of your accounts", id),
+            format!(
+                "Run {} to see the accounts you have access to",
+                styles::highlight("`wrangler whoami`")
+            ),
+        ),
+        _ => Outcome::Pass(format!("with access to {} account(s)", accounts.len())),
+    }
+}
+
+fn login_hint() -> String {
+    format!(
+        "Run {} or {}",
+        styles::highlight("`wrangler login`"),
+        styles::highlight("`wrangler config`")
+    )
+}
+
+/// A program builds depend on
+#[derive(Debug, PartialEq)]
+struct Tool {
+    name: &'static str,
+    /// How the program is installed when it isn't found
+    install: Install,
+}
+
+#[derive(Debug, PartialEq)]
+enum Install {
+    /// Has to be on the PATH, from the URL to get it from
+    Path(&'static str),
+    /// Downloaded to the wrangler cache on the first build that needs it
+    Cache { tool: &'static str, version: String },
+}
+
+impl Tool {
+    fn check(&self) -> Outcome {
+        match &self.install {
+            Install::Path(url) => match version_of(self.name) {
+                Some(version) => Outcome::Pass(version),
+                None => Outcome::Fail(
+                    "not found on your PATH".to_string(),
+                    format!("Install it from {}", styles::url(url)),
+                ),
+            },
+            Install::Cache { tool, version } => match install::installed(tool, version) {
+                Some(path) => Outcome::Pass(format!("v{} at {}", version, path.display())),
+                None => Outcome::Warn(
+                    "not installed yet".to_string(),
+                    format!(
+                        "Wrangler downloads v{} of {} on your first build",
+                        version, tool
+                    ),
+                ),
+            },
+        }
+    }
+}
+
+// The tools the builds of `target_type` run, or those of every type without a project
+fn tools(target_type: Option<&TargetType>) -> Vec<Tool> {
+    let node = || Tool {
+        name: "node",
+        install: Install::Path("https://nodejs.org"),
+    };
+    let npm = || Tool {
+        name: "npm",
+        install: Install::Path("https://nodejs.org"),
+    };
+    let rust = || {
+        vec![
+            Tool {
+                name: "rustc",
+                install: Install::Path("https://www.rust-lang.org/tools/install"),
+            },
+            Tool {
+                name: "wasm-pack",
+                install: Install::Cache {
+                    tool: "wasm-pack",
+                    version: dependencies::WASM_PACK_VERSION.to_string(),
+                },
+            },
+        ]
+    };
+    let webpack = || Tool {
+        name: "webpack",
+        install: Install::Cache {
+            tool: "wranglerjs",
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+    };
+
+    match target_type {
+        // builds of JavaScript projects are whatever command they configure
+        Some(TargetType::JavaScript) => vec![],
+        Some(TargetType::TypeScript) => vec![node()],
+        Some(TargetType::Rust) => rust(),
+        Some(TargetType::Webpack) => vec![node(), npm(), webpack()],
+        None => {
+            let mut tools = vec![node(), npm(), webpack()];
+            tools.extend(rust());
+            tools
+        }
+    }
+}
+
+// The first line `<program> --version` prints, if it runs
+fn version_of(program: &str) -> Option<String> {
+    let program = which::which(program).ok()?;
+    let output = crate::build::command(&["--version"], &program)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+}
+
+#[derive(Default)]
+struct Report {
+    passed: usize,
+    failed: usize,
+    hints: Vec<(String, String)>,
+}
+
+impl Report {
+    fn add(&mut self, name: &str, outcome: Outcome) {
+        match outcome {
+            Outcome::Pass(detail) => {
+                self.passed += 1;
+                StdErr::success(&format!("{}: {}", name, detail));
+            }
+            Outcome::Warn(detail, hint) => {
+                self.passed += 1;
+                StdErr::info(&format!("{}: {}", name, detail));
+                self.hints.push((name.to_string(), hint));
+            }
+            Outcome::Fail(detail, hint) => {
+                self.failed += 1;
+                StdErr::warn(&format!("{}: {}", name, detail));
+                self.hints.push((name.to_string(), hint));
+            }
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        for (name, hint) in &self.hints {
+            StdErr::help(&format!("{}: {}", name, hint));
+        }
+        if self.failed > 0 {
+            anyhow::bail!(
+                "{} of {} checks failed",
+                self.failed,
+                self.passed + self.failed
+            )
+        }
+        StdErr::success(&format!("All {} checks passed", self.passed));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(tools: Vec<Tool>) -> Vec<&'static str> {
+        tools.iter().map(|tool| tool.name).collect()
+    }
+
+    #[test]
+    fn it_checks_the_tools_the_project_builds_with() {
+        assert_eq!(
+            names(tools(Some(&TargetType::JavaScript))),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            names(tools(Some(&TargetType::Rust))),
+            vec!["rustc", "wasm-pack"]
+        );
+        assert_eq!(
+            names(tools(Some(&TargetType::Webpack))),
+            vec!["node", "npm", "webpack"]
+        );
+        assert_eq!(tools(None).len(), 5);
+    }
+}
@@ -0,0 +1,172 @@
+use std::env;
+
+use anyhow::{anyhow, Result};
+use url::Url;
+
+use crate::commands::subdomain::{self, Subdomain};
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+
+// The secret the debug endpoint compares the token wrangler sends against
+const DEBUG_TOKEN: &str = "WRANGLER_DEBUG_TOKEN";
+
+/// The debug endpoint `dump` reads the storage of an object through. There is no API to
+/// read the storage of a Durable Object from outside of it, so the worker has to expose it.
+pub const HELPER: &str = r#"// Reads the storage of a Durable Object for `wrangler durable-objects dump`. Set the
+// WRANGLER_DEBUG_TOKEN secret with `wrangler secret put WRANGLER_DEBUG_TOKEN`, and call
+// this first in the fetch handler of your worker:
+//
+//   const dump = await dumpDurableObject(request, env);
+//   if (dump) return dump;
+export async function dumpDurableObject(request, env) {
+  const match = new URL(request.url).pathname.match(
+    /^\/__wrangler\/durable-objects\/([^/]+)\/([^/]+)$/
+  );
+  if (!match) return null;
+  const token = env.WRANGLER_DEBUG_TOKEN;
+  if (!token || request.headers.get("Authorization") !== `Bearer ${token}`) {
+    return new Response("Unauthorized", { status: 401 });
+  }
+  const namespace = env[match[1]];
+  if (!namespace) return new Response("No such binding", { status: 404 });
+  const name = decodeURIComponent(match[2]);
+  const id = /^[0-9a-f]{64}$/.test(name)
+    ? namespace.idFromString(name)
+    : namespace.idFromName(name);
+  return namespace.get(id).fetch("https://durable-object/__wrangler/storage");
+}
+
+// And this first in the fetch method of your Durable Object class:
+//
+//   if (new URL(request.url).pathname === "/__wrangler/storage") {
+//     return dumpStorage(this.state.storage);
+//   }
+export async function dumpStorage(storage) {
+  const entries = Object.fromEntries(await storage.list());
+  return new Response(JSON.stringify(entries), {
+    headers: { "Content-Type": "application/json" },
+  });
+}
+"#;
+
+/// Prints the storage of the object of the `binding` namespace with the given id, or the
+/// id derived from the given name, as JSON. Reads it through the endpoint of `HELPER` on
+/// the worker at `url`, or on the workers.dev address of the worker without one.
+pub fn dump(
+    target: &Target,
+    binding: &str,
+    object: &str,
+    url: Option<Url>,
+    user: Option<&GlobalUser>,
+) -> Result<()> {
+    check_binding(target, binding)?;
+    let token = env::var(DEBUG_TOKEN).map_err(|_| {
+        anyhow!(
+            "Set {} to the value of the {} secret of your worker",
+            DEBUG_TOKEN,
+            DEBUG_TOKEN
+        )
+    })?;
+
+    let base = match url {
+        Some(url) => url,
+        None => {
+            let user =
+                user.ok_or_else(|| anyhow!("Pass --url or log in to use the workers.dev address"))?;
+            let registered = match Subdomain::get(target.account_id.load()?, user)? {
+                Some(registered) => registered,
+                None => anyhow::bail!(subdomain::NO_SUBDOMAIN_MSG),
+            };
+            Url::parse(&format!(
+                "https://{}.{}.workers.dev",
+                target.name, registered
+            ))?
+        }
+    };
+    let endpoint = dump_endpoint(&base, binding, object)?;
+
+    let res = http::client()
+        .get(endpoint.as_str())
+        .bearer_auth(token)
+        .send_logged()?;
+    let status = res.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        anyhow::bail!(
+            "{} has no debug endpoint for {}, add the one `wrangler durable-objects helper` prints to your worker",
+            base,
+            binding
+        )
+    }
+    if !status.is_success() {
+        anyhow::bail!(
+            "Reading the storage failed with {}: {}",
+            status,
+            res.text()?
+        )
+    }
+
+    let storage: serde_json::Value = res.json()?;
+    println!("{}", serde_json::to_string_pretty(&storage)?);
+    Ok(())
+}
+
+fn check_binding(target: &Target, binding: &str) -> Result<()> {
+    let classes = target
+        .durable_objects
+        .as_ref()
+        .and_then(|d| d.classes.as_ref());
+    let bindings: Vec<&str> = classes
+        .into_iter()
+        .flatten()
+        .map(|class| class.binding.as_str())
+        .collect();
+    if !bindings.contains(&binding) {
+        anyhow::bail!(
+            "There is no Durable Object binding \"{}\" in your configuration file. Bindings: {}",
+            binding,
+            bindings.join(", ")
+        )
+    }
+    Ok(())
+}
+
+fn dump_endpoint(base: &Url, binding: &str, object: &str) -> Result<Url> {
+    let mut endpoint = base.clone();
+    endpoint
+        .path_segments_mut()
+        .map_err(|_| anyhow!("{} can't be the URL of a worker", base))?
+        .pop_if_empty()
+        .extend(&["__wrangler", "durable-objects", binding, object]);
+    Ok(endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::Manifest;
+
+    #[test]
+    fn it_builds_the_endpoint_of_an_object() {
+        let base = Url::parse("https://counter.example.workers.dev/").unwrap();
+        assert_eq!(
+            dump_endpoint(&base, "COUNTER", "user 1").unwrap().as_str(),
+            "https://counter.example.workers.dev/__wrangler/durable-objects/COUNTER/user%201"
+        );
+
+        let manifest: Manifest = toml::from_str(
+            r#"
+name = "counter"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+[durable_objects]
+bindings = [{ name = "COUNTER", class_name = "Counter" }]
+"#,
+        )
+        .unwrap();
+        let target = manifest.get_target(None, false).unwrap();
+        assert!(check_binding(&target, "COUNTER").is_ok());
+        assert!(check_binding(&target, "SESSIONS").is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use prettytable::{Cell, Row, Table};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::UsageModel;
+use crate::terminal::message::{Message, StdOut};
+
+const DAYS_PER_MONTH: f64 = 30.0;
+const MILLION: f64 = 1_000_000.0;
+
+/// Every Worker is billed as if it used 128MB of memory
+const MEMORY_GB: f64 = 0.125;
+
+const SUBSCRIPTION: f64 = 5.0;
+
+const BUNDLED_INCLUDED_REQUESTS: f64 = 10.0 * MILLION;
+const BUNDLED_PER_MILLION_REQUESTS: f64 = 0.50;
+const BUNDLED_CPU_LIMIT_MS: f64 = 50.0;
+
+const UNBOUND_INCLUDED_REQUESTS: f64 = MILLION;
+const UNBOUND_PER_MILLION_REQUESTS: f64 = 0.15;
+const UNBOUND_INCLUDED_GB_SECONDS: f64 = 400_000.0;
+const UNBOUND_PER_MILLION_GB_SECONDS: f64 = 12.50;
+
+/// The monthly usage of a worker, either supplied by the user or projected from analytics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Usage {
+    pub requests: f64,
+    pub avg_cpu_ms: f64,
+    pub avg_duration_ms: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+    /// `None` when the worker needs more CPU time than the bundled model allows
+    pub bundled: Option<f64>,
+    pub unbound: f64,
+}
+
+impl Estimate {
+    pub fn new(usage: &Usage) -> Self {
+        let bundled = if usage.avg_cpu_ms > BUNDLED_CPU_LIMIT_MS {
+            None
+        } else {
+            Some(
+                SUBSCRIPTION
+                    + overage(usage.requests, BUNDLED_INCLUDED_REQUESTS)
+                        * BUNDLED_PER_MILLION_REQUESTS,
+            )
+        };
+
+        let gb_seconds = usage.requests * usage.avg_duration_ms / 1000.0 * MEMORY_GB;
+        let unbound = SUBSCRIPTION
+            + overage(usage.requests, UNBOUND_INCLUDED_REQUESTS) * UNBOUND_PER_MILLION_REQUESTS
+            + overage(gb_seconds, UNBOUND_INCLUDED_GB_SECONDS) * UNBOUND_PER_MILLION_GB_SECONDS;
+
+        Estimate { bundled, unbound }
+    }
+
+    pub fn cheaper(&self) -> UsageModel {
+        match self.bundled {
+            Some(bundled) if bundled <= self.unbound => UsageModel::Bundled,
+            _ => UsageModel::Unbound,
+        }
+    }
+}
+
+/// Millions of units used beyond what is included in the plan
+fn overage(used: f64, included: f64) -> f64 {
+    (used - included).max(0.0) / MILLION
+}
+
+/// A worker to estimate, and the usage model it is currently configured with.
+pub struct EstimateTarget {
+    pub environment: Option<String>,
+    pub script_name: String,
+    pub usage_model: Option<UsageModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse {
+    data: Option<GraphQLData>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLData {
+    viewer: Viewer,
+}
+
+#[derive(Debug, Deserialize)]
+struct Viewer {
+    accounts: Vec<AccountAnalytics>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountAnalytics {
+    workers_invocations_adaptive: Vec<Invocations>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Invocations {
+    sum: InvocationsSum,
+    quantiles: InvocationsQuantiles,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvocationsSum {
+    requests: f64,
+    /// Reported in GB-s
+    duration: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InvocationsQuantiles {
+    /// Reported in microseconds
+    cpu_time_p50: f64,
+}
+
+const INVOCATIONS_QUERY: &str = r#"
+query($accountTag: string, $scriptName: string, $since: Time, $until: Time) {
+  viewer {
+    accounts(filter: { accountTag: $accountTag }) {
+      workersInvocationsAdaptive(
+        limit: 1
+        filter: { scriptName: $scriptName, datetime_geq: $since, datetime_leq: $until }
+      ) {
+        sum { requests duration }
+        quantiles { cpuTimeP50 }
+      }
+    }
+  }
+}
+"#;
+
+/// Projects a month of usage from the worker's analytics over the past `days`.
+pub fn fetch_usage(
+    user: &GlobalUser,
+    account_id: &str,
+    script_name: &str,
+    days: u32,
+) -> Result<Usage> {
+    let until = Utc::now();
+    let since = until - Duration::days(days.into());
+
+    let client = http::legacy_auth_client(user);
+    let res = client
+        .post(&http::api_url("graphql")?)
+        .json(&json!({
+            "query": INVOCATIONS_QUERY,
+            "variables": {
+                "accountTag": account_id,
+                "scriptName": script_name,
+                "since": since.to_rfc3339(),
+                "until": until.to_rfc3339(),
+            }
+        }))
+        .send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+
+    let res: GraphQLResponse = res.json()?;
+    if let Some(errors) = res.errors {
+        let messages: Vec<String> = errors.into_iter().map(|e| e.message).collect();
+        anyhow::bail!(
+            "Failed to fetch analytics for {}: {}",
+            script_name,
+            messages.join(", ")
+        )
+    }
+
+    let invocations = res
+        .data
+        .and_then(|data| data.viewer.accounts.into_iter().next())
+        .and_then(|account| account.workers_invocations_adaptive.into_iter().next());
+
+    Ok(match invocations {
+        Some(invocations) => project_usage(&invocations, days),
+        None => Usage {
+            requests: 0.0,
+            avg_cpu_ms: 0.0,
+            avg_duration_ms: 0.0,
+        },
+    })
+}
+
+fn project_usage(invocations: &Invocations, days: u32) -> Usage {
+    let requests = invocations.sum.requests;
+    let avg_duration_ms = if requests > 0.0 {
+        invocations.sum.duration / MEMORY_GB / requests * 1000.0
+    } else {
+        0.0
+    };
+
+    Usage {
+        requests: requests * DAYS_PER_MONTH / f64::from(days),
+        avg_cpu_ms: invocations.quantiles.cpu_time_p50 / 1000.0,
+        avg_duration_ms,
+    }
+}
+
+pub fn print_estimates(estimates: &[(EstimateTarget, Usage, Estimate)]) {
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Environment"),
+        Cell::new("Worker"),
+        Cell::new("Requests/month"),
+        Cell::new("Avg CPU"),
+        Cell::new("Bundled"),
+        Cell::new("Unbound"),
+        Cell::new("Configured"),
+        Cell::new("Cheaper"),
+    ]));
+
+    for (target, usage, estimate) in estimates {
+        let configured = match target.usage_model {
+            Some(UsageModel::Unbound) => "unbound",
+            _ => "bundled",
+        };
+        let bundled = match estimate.bundled {
+            Some(cost) => format!("${:.2}", cost),
+            None => format!("n/a (>{}ms CPU)", BUNDLED_CPU_LIMIT_MS),
+        };
+        table.add_row(Row::new(vec![
+            Cell::new(target.environment.as_deref().unwrap_or("(default)")),
+            Cell::new(&target.script_name),
+            Cell::new(&format!("{:.0}", usage.requests)),
+            Cell::new(&format!("{:.1}ms", usage.avg_cpu_ms)),
+            Cell::new(&bundled),
+            Cell::new(&format!("${:.2}", estimate.unbound)),
+            Cell::new(configured),
+            Cell::new(estimate.cheaper().as_ref()),
+        ]));
+    }
+
+    print!("{}", table);
+    StdOut::info(&format!(
+        "Estimates include the ${:.2} Workers Paid subscription, and assume each worker is the only one on the account. The subscription and included usage are shared across all of an account's workers.",
+        SUBSCRIPTION
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(requests: f64, avg_cpu_ms: f64, avg_duration_ms: f64) -> Usage {
+        Usage {
+            requests,
+            avg_cpu_ms,
+            avg_duration_ms,
+        }
+    }
+
+    #[test]
+    fn it_charges_only_the_subscription_within_included_usage() {
+        let estimate = Estimate::new(&usage(MILLION, 5.0, 5.0));
+        assert_eq!(estimate.bundled, Some(5.0));
+        assert_eq!(estimate.unbound, 5.0);
+    }
+
+    #[test]
+    fn it_charges_overage() {
+        // 50M requests of 5ms: 40M extra bundled requests, 49M extra unbound requests and
+        // 50M * 0.005s * 0.125GB = 31,250 GB-s, within the included duration.
+        let estimate = Estimate::new(&usage(50.0 * MILLION, 5.0, 5.0));
+        assert_eq!(estimate.bundled, Some(5.0 + 40.0 * 0.50));
+        assert!((estimate.unbound - (5.0 + 49.0 * 0.15)).abs() < 1e-9);
+        assert_eq!(estimate.cheaper(), UsageModel::Unbound);
+
+        // 10M requests of 1s: 1,250,000 GB-s, 850,000 of which beyond the included duration
+        let estimate = Estimate::new(&usage(10.0 * MILLION, 5.0, 1000.0));
+        assert!((estimate.unbound - (5.0 + 9.0 * 0.15 + 0.85 * 12.50)).abs() < 1e-9);
+        assert_eq!(estimate.cheaper(), UsageModel::Bundled);
+    }
+
+    #[test]
+    fn it_rules_out_bundled_above_the_cpu_limit() {
+        let estimate = Estimate::new(&usage(MILLION, 80.0, 80.0));
+        assert_eq!(estimate.bundled, None);
+        assert_eq!(estimate.cheaper(), UsageModel::Unbound);
+    }
+
+    #[test]
+    fn it_projects_a_month_from_analytics() {
+        let invocations = Invocations {
+            sum: InvocationsSum {
+                requests: 1000.0,
+                duration: 1000.0 * 0.010 * MEMORY_GB,
+            },
+            quantiles: InvocationsQuantiles {
+                cpu_time_p50: 2500.0,
+            },
+        };
+        let usage = project_usage(&invocations, 7);
+        assert!((usage.requests - 1000.0 * 30.0 / 7.0).abs() < 1e-9);
+        assert!((usage.avg_cpu_ms - 2.5).abs() < 1e-9);
+        assert!((usage.avg_duration_ms - 10.0).abs() < 1e-9);
+    }
+}
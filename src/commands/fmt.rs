@@ -0,0 +1,305 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use toml_edit::{ArrayOfTables, Document, Item, Table, Value};
+
+use crate::terminal::diff;
+use crate::terminal::message::{Message, StdOut};
+
+// The order the keys of the top level and of each environment are sorted into. Keys that
+// aren't in here keep their order after these.
+const KEY_ORDER: &[&str] = &[
+    "extends",
+    "name",
+    "type",
+    "account_id",
+    "zone_id",
+    "workers_dev",
+    "route",
+    "routes",
+    "compatibility_date",
+    "compatibility_flags",
+    "usage_model",
+    "limits",
+    "webpack_config",
+    "private",
+    "protection",
+    "build",
+    "site",
+    "dev",
+    "triggers",
+    "durable_objects",
+    "kv_namespaces",
+    "vars",
+    "secrets",
+    "text_blobs",
+    "wasm_modules",
+    "bindings",
+    "migrations",
+    "workers",
+];
+
+/// Formats the configuration file at `config_path`, or with `check` only reports whether
+/// it is formatted, showing what formatting would change.
+pub fn fmt(config_path: &Path, check: bool) -> Result<()> {
+    let contents = fs::read_to_string(config_path)
+        .map_err(|e| anyhow!("Could not read {}: {}", config_path.display(), e))?;
+    let formatted = format_config(&contents)?;
+
+    if formatted == contents {
+        StdOut::success(&format!("{} is formatted", config_path.display()));
+        return Ok(());
+    }
+    let dropped = dropped_comments(&contents, &formatted);
+    if dropped > 0 {
+        StdOut::warn(&format!(
+            "Formatting drops {} comment(s) above keys it moves",
+            dropped
+        ));
+    }
+    if check {
+        diff::print(&contents, &formatted);
+        anyhow::bail!(
+            "{} isn't formatted, run `wrangler fmt` to format it",
+            config_path.display()
+        )
+    }
+    fs::write(config_path, &formatted)?;
+    StdOut::success(&format!("Formatted {}", config_path.display()));
+    Ok(())
+}
+
+/// Sorts the keys of the top level and of each environment, turns inline kv_namespaces
+/// arrays into [[kv_namespaces]] tables, and removes trailing whitespace and extra blank
+/// lines. toml_edit keeps the comments and formatting of the file, but comments above the
+/// keys it moves are dropped.
+pub fn format_config(contents: &str) -> Result<String> {
+    let original: toml::Value = toml::from_str(contents)?;
+    let mut doc = contents
+        .parse::<Document>()
+        .map_err(|e| anyhow!("toml_edit failed to parse the configuration file. {}", e))?;
+
+    let root = match doc.root.as_table_mut() {
+        Some(root) => root,
+        None => anyhow::bail!("Expected the configuration file to be a table"),
+    };
+    format_environment_table(root)?;
+    if root.contains_key("env") {
+        if let Some(envs) = root.entry("env").as_table_mut() {
+            let names: Vec<String> = envs.iter().map(|(name, _)| name.to_string()).collect();
+            for name in names {
+                if let Some(env) = envs.entry(&name).as_table_mut() {
+                    format_environment_table(env)?;
+                }
+            }
+        }
+    }
+    let formatted = doc.to_string_in_original_order();
+
+    // make sure formatting never changes what the file means; whitespace in a string
+    // spanning several lines is part of the string, so then it's left as it is
+    let normalized = normalized(original);
+    let tidied = tidy(&formatted);
+    if toml::from_str::<toml::Value>(&tidied).ok().as_ref() == Some(&normalized) {
+        return Ok(tidied);
+    }
+    let reparsed: toml::Value = toml::from_str(&formatted)
+        .map_err(|e| anyhow!("Formatting would break the configuration file: {}", e))?;
+    if reparsed != normalized {
+        anyhow::bail!("Formatting would change the meaning of the configuration file")
+    }
+    Ok(formatted)
+}
+
+// How many comments `formatted` lost of those in `contents`
+fn dropped_comments(contents: &str, formatted: &str) -> usize {
+    let comments = |text: &str| {
+        text.lines()
+            .filter(|line| line.trim_start().starts_with('#'))
+            .count()
+    };
+    comments(contents).saturating_sub(comments(formatted))
+}
+
+// The top level and environments take the same keys, so they're formatted the same way
+fn format_environment_table(table: &mut Table) -> Result<()> {
+    if table.contains_key("kv-namespaces") && !table.contains_key("kv_namespaces") {
+        if let Some(namespaces) = table.remove("kv-namespaces") {
+            *table.entry("kv_namespaces") = namespaces;
+        }
+    }
+    if table.contains_key("kv_namespaces") {
+        let namespaces = table.entry("kv_namespaces");
+        if let Some(tables) = array_of_tables(namespaces)? {
+            *namespaces = tables;
+        }
+    }
+    sort_values(table);
+    Ok(())
+}
+
+// Sorts the keys with values of `table` into KEY_ORDER, leaving the keys that are in order
+// already where they are. Tables are written after them, in the order they were in.
+fn sort_values(table: &mut Table) {
+    let keys: Vec<String> = table
+        .iter()
+        .filter(|(_, item)| item.is_value())
+        .map(|(key, _)| key.to_string())
+        .collect();
+    let mut sorted = keys.clone();
+    // a stable sort, so the keys it doesn't know keep their order
+    sorted.sort_by_key(|key| rank(key));
+    let in_order = keys.iter().zip(&sorted).take_while(|(a, b)| a == b).count();
+    // toml_edit only adds keys at the end of a table, so every key after the first one out
+    // of order is moved
+    for key in &sorted[in_order..] {
+        if let Some(item) = table.remove(key) {
+            *table.entry(key) = item;
+        }
+    }
+}
+
+fn rank(key: &str) -> usize {
+    KEY_ORDER
+        .iter()
+        .position(|known| *known == key)
+        .unwrap_or(KEY_ORDER.len())
+}
+
+/// An array of inline tables, like `kv_namespaces = [{ binding = "CACHE", id = "..." }]`,
+/// as an array of tables
+fn array_of_tables(item: &Item) -> Result<Option<Item>> {
+    let array = match item.as_value().and_then(Value::as_array) {
+        Some(array) if !array.is_empty() => array,
+        _ => return Ok(None),
+    };
+    let mut tables = ArrayOfTables::new();
+    for value in array.iter() {
+        let inline = match value.as_inline_table() {
+            Some(inline) => inline,
+            None => return Ok(None),
+        };
+        let mut table = Table::new();
+        for (key, value) in inline.iter() {
+            *table.entry(key) = match (value.as_str(), value.as_integer(), value.as_bool()) {
+                (Some(s), _, _) => toml_edit::value(s),
+                (_, Some(i), _) => toml_edit::value(i),
+                (_, _, Some(b)) => toml_edit::value(b),
+                _ => anyhow::bail!("Could not write `{}` of kv_namespaces as a table", key),
+            };
+        }
+        tables.append(table);
+    }
+    Ok(Some(Item::ArrayOfTables(tables)))
+}
+
+// Removes trailing whitespace, blank lines at the start and end, and more than one blank
+// line in a row
+fn tidy(formatted: &str) -> String {
+    let mut out = String::new();
+    let mut blank = false;
+    for line in formatted.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        if blank {
+            out.push('\n');
+        }
+        out.push_str(line);
+        out.push('\n');
+        blank = false;
+    }
+    out
+}
+
+// What the original file means once formatted, with the kv-namespaces spelling of
+// kv_namespaces replaced
+fn normalized(mut config: toml::Value) -> toml::Value {
+    rename_kv_namespaces(&mut config);
+    if let Some(envs) = config.get_mut("env").and_then(toml::Value::as_table_mut) {
+        for env in envs.values_mut() {
+            rename_kv_namespaces(env);
+        }
+    }
+    config
+}
+
+fn rename_kv_namespaces(section: &mut toml::Value) {
+    if let Some(section) = section.as_table_mut() {
+        if !section.contains_key("kv_namespaces") {
+            if let Some(namespaces) = section.remove("kv-namespaces") {
+                section.insert("kv_namespaces".to_string(), namespaces);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_formats_a_configuration_file() {
+        let contents = r#"# my worker
+
+name = "worker" # the name
+kv_namespaces = [
+  { binding = "CACHE", id = "abc" },
+]
+account_id = "123"
+type = "javascript"   
+
+
+[env.staging]
+name = "worker-staging"
+route = "staging.example.com/*"
+kv-namespaces = [{ binding = "CACHE", id = "def", preview_id = "ghi" }]
+[site]
+bucket = "./public"
+entry-point = "workers-site"
+"#;
+
+        let formatted = format_config(contents).unwrap();
+        assert!(formatted.starts_with("# my worker\n"));
+        assert!(formatted.contains("name = \"worker\" # the name\n"));
+        assert!(!formatted.contains("\n\n\n"));
+        assert!(formatted.lines().all(|line| line == line.trim_end()));
+        assert!(formatted.contains("[[kv_namespaces]]\n"));
+        assert!(formatted.contains("[[env.staging.kv_namespaces]]\n"));
+        assert!(!formatted.contains("kv-namespaces"));
+
+        let position = |key: &str| formatted.find(key).unwrap();
+        assert!(position("name = \"worker\"") < position("type = "));
+        assert!(position("type = ") < position("account_id = "));
+        assert!(position("name = \"worker-staging\"") < position("route = "));
+
+        let formatted_again = format_config(&formatted).unwrap();
+        assert_eq!(
+            toml::from_str::<toml::Value>(&formatted_again).unwrap(),
+            toml::from_str::<toml::Value>(&formatted).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_keeps_multiline_values_as_they_are() {
+        let contents = "name = \"worker\"\nroutes = [\n    \"example.com/*\",\n]\n";
+        assert_eq!(format_config(contents).unwrap(), contents);
+
+        let contents = "name = \"worker\"\nmessage = \"\"\"\nline  \n\n\nline\n\"\"\"\n";
+        let formatted = format_config(contents).unwrap();
+        assert!(formatted.contains("line  \n\n\nline"));
+        assert!(format_config("name = [\n").is_err());
+    }
+
+    #[test]
+    fn it_counts_the_comments_it_drops() {
+        assert_eq!(
+            dropped_comments("# a\n# b\nname = 1\n", "# a\nname = 1\n"),
+            1
+        );
+        assert_eq!(dropped_comments("name = 1\n", "# a\nname = 1\n"), 0);
+    }
+}
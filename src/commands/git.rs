@@ -0,0 +1,114 @@
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+
+use crate::commands::previews;
+use crate::settings::toml::Target;
+
+/// The var the commit a worker was published from is handed to it in, unless its
+/// configuration file has a var of that name already
+pub const GIT_SHA_VAR: &str = "WRANGLER_GIT_SHA";
+
+/// The commit checked out in the current directory, and the tracked files changed since
+pub struct Checkout {
+    pub sha: String,
+    pub changed_files: Vec<String>,
+}
+
+impl Checkout {
+    /// `None` outside of a git checkout, or when git isn't installed
+    pub fn current() -> Option<Self> {
+        let sha = previews::git(&["rev-parse", "HEAD"]).ok()?;
+        let diff = previews::git(&["diff", "--name-only", "HEAD"]).ok()?;
+        Some(Checkout {
+            sha,
+            changed_files: diff.lines().map(str::to_string).collect(),
+        })
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.changed_files.is_empty()
+    }
+
+    /// Hands the commit to the worker of `target` in `GIT_SHA_VAR`, marked as dirty when
+    /// there are uncommitted changes
+    pub fn annotate(&self, target: &mut Target) {
+        let sha = if self.is_dirty() {
+            format!("{}-dirty", self.sha)
+        } else {
+            self.sha.clone()
+        };
+        target
+            .vars
+            .get_or_insert_with(Default::default)
+            .entry(GIT_SHA_VAR.to_string())
+            .or_insert(sha);
+    }
+}
+
+/// Tags the commit checked out as `name`, or as `deploy/<environment>/<date>` by default.
+/// Returns the tag, which gets a number appended when it exists already, like from a
+/// publish earlier the same day.
+pub fn tag(name: Option<&str>, environment: Option<&str>) -> Result<String> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => default_tag(environment, Utc::today().naive_utc()),
+    };
+    let mut tag = name.clone();
+    let mut n = 1;
+    while tag_exists(&tag) {
+        n += 1;
+        tag = format!("{}-{}", name, n);
+    }
+    previews::git(&["tag", &tag])?;
+    Ok(tag)
+}
+
+fn tag_exists(tag: &str) -> bool {
+    let reference = format!("refs/tags/{}", tag);
+    previews::git(&["rev-parse", "--quiet", "--verify", &reference]).is_ok()
+}
+
+fn default_tag(environment: Option<&str>, date: NaiveDate) -> String {
+    format!(
+        "deploy/{}/{}",
+        environment.unwrap_or("default"),
+        date.format("%F")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_names_tags_after_the_environment_and_the_day() {
+        let date = NaiveDate::from_ymd(2024, 5, 1);
+        assert_eq!(
+            default_tag(Some("production"), date),
+            "deploy/production/2024-05-01"
+        );
+        assert_eq!(default_tag(None, date), "deploy/default/2024-05-01");
+    }
+
+    #[test]
+    fn it_hands_the_commit_to_the_worker() {
+        let checkout = Checkout {
+            sha: "4f2a9c1".to_string(),
+            changed_files: vec!["src/index.js".to_string()],
+        };
+        let mut target = Target::default();
+        checkout.annotate(&mut target);
+        assert_eq!(target.vars.as_ref().unwrap()[GIT_SHA_VAR], "4f2a9c1-dirty");
+
+        let mut target = Target {
+            vars: Some(
+                vec![(GIT_SHA_VAR.to_string(), "pinned".to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        checkout.annotate(&mut target);
+        assert_eq!(target.vars.as_ref().unwrap()[GIT_SHA_VAR], "pinned");
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::Result;
+
+use crate::settings::toml::Manifest;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => anyhow::bail!("Unknown graph format \"{}\", expected dot or mermaid", s),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EdgeKind {
+    /// A `depends_on` entry of a `[[workers]]` table
+    DependsOn,
+    /// A Durable Object binding to a class exported by another script
+    DurableObject { binding: String, class_name: String },
+    /// A service binding, a `[[bindings]]` entry of type "service"
+    Service { binding: String },
+    /// An entry of `tail_consumers`, from the tailed worker to the one its events go to
+    TailConsumer,
+    /// A queue binding, a `[[bindings]]` entry of type "queue", to a worker with the queue
+    /// in its `queue_consumers`. Queues without a consumer in the file become a node of
+    /// their own.
+    Queue { binding: String, queue: String },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+}
+
+/// Which workers of a configuration file call, or have to be published before, which
+/// other workers. Scripts referenced by a binding but not configured in the file are
+/// included as well, since they are usually owned by another project.
+#[derive(Debug, Default, PartialEq)]
+pub struct WorkerGraph {
+    /// Script names of the workers in the configuration file
+    pub workers: Vec<String>,
+    /// Script names that are only known from bindings, and queues no worker consumes
+    pub external: Vec<String>,
+    pub edges: Vec<Edge>,
+}
+
+impl WorkerGraph {
+    pub fn new(manifest: &Manifest, env: Option<&str>) -> Result<WorkerGraph> {
+        let members = match &manifest.workers {
+            Some(workers) if !workers.is_empty() => manifest
+                .workspace_members(&[])?
+                .into_iter()
+                .map(|(member, _)| member)
+                .collect(),
+            _ => vec![manifest.clone()],
+        };
+
+        let mut graph = WorkerGraph::default();
+        // [[workers]] refer to each other by name, bindings by the script name of an environment
+        let mut script_names = HashMap::new();
+        for member in &members {
+            let script = member.worker_name(env);
+            script_names.insert(member.name.clone(), script.clone());
+            graph.workers.push(script);
+        }
+
+        for worker in manifest.workers.iter().flatten() {
+            for dependency in &worker.depends_on {
+                graph.add_edge(
+                    &script_names[&worker.name],
+                    &script_names[dependency],
+                    EdgeKind::DependsOn,
+                );
+            }
+        }
+
+        // an environment's settings replace the top level ones, they're not merged
+        let mut consumers: HashMap<&str, Vec<&str>> = HashMap::new();
+        for member in &members {
+            let queues = match member.get_environment(env).unwrap_or_default() {
+                Some(environment) => environment.queue_consumers.as_ref(),
+                None => member.queue_consumers.as_ref(),
+            };
+            for queue in queues.into_iter().flatten() {
+                consumers
+                    .entry(queue.as_str())
+                    .or_default()
+                    .push(script_names[&member.name].as_str());
+            }
+        }
+
+        for member in &members {
+            let script = &script_names[&member.name];
+            let environment = member.get_environment(env).unwrap_or_default();
+            let durable_objects = match environment {
+                Some(environment) => environment.durable_objects.as_ref(),
+                None => member.durable_objects.as_ref(),
+            };
+            let classes = durable_objects.and_then(|d| d.classes.as_ref());
+            for class in classes.into_iter().flatten() {
+                match &class.script_name {
+                    Some(other) if other != script => graph.add_edge(
+                        script,
+                        other,
+                        EdgeKind::DurableObject {
+                            binding: class.binding.clone(),
+                            class_name: class.class_name.clone(),
+                        },
+                    ),
+                    _ => {}
+                }
+            }
+
+            let bindings = match environment {
+                Some(environment) => environment.bindings.as_ref(),
+                None => member.bindings.as_ref(),
+            };
+            for binding in bindings.into_iter().flatten() {
+                let field = |name: &str| binding.fields.get(name).and_then(|v| v.as_str());
+                match (
+                    binding.binding_type.as_str(),
+                    field("service"),
+                    field("queue_name"),
+                ) {
+                    ("service", Some(service), _) if service != script.as_str() => graph.add_edge(
+                        script,
+                        service,
+                        EdgeKind::Service {
+                            binding: binding.name.clone(),
+                        },
+                    ),
+                    ("queue", _, Some(queue)) => {
+                        let kind = EdgeKind::Queue {
+                            binding: binding.name.clone(),
+                            queue: queue.to_string(),
+                        };
+                        match consumers.get(queue) {
+                            Some(consumers) => {
+                                for consumer in consumers {
+                                    graph.add_edge(script, consumer, kind.clone());
+                                }
+                            }
+                            None => graph.add_edge(script, queue, kind),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let tail_consumers = match environment {
+                Some(environment) => environment.tail_consumers.as_ref(),
+                None => member.tail_consumers.as_ref(),
+            };
+            for consumer in tail_consumers.into_iter().flatten() {
+                graph.add_edge(script, consumer, EdgeKind::TailConsumer);
+            }
+        }
+
+        Ok(graph)
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str, kind: EdgeKind) {
+        if !self.workers.iter().any(|w| w == to) && !self.external.iter().any(|w| w == to) {
+            self.external.push(to.to_string());
+        }
+        self.edges.push(Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            kind,
+        });
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.render_dot(),
+            GraphFormat::Mermaid => self.render_mermaid(),
+        }
+    }
+
+    fn render_dot(&self) -> String {
+        let mut out = "digraph workers {\n".to_string();
+        for worker in &self.workers {
+            out.push_str(&format!("  {:?};\n", worker));
+        }
+        for worker in &self.external {
+            out.push_str(&format!("  {:?} [style=dashed];\n", worker));
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "  {:?} -> {:?} [label={:?}];\n",
+                edge.from,
+                edge.to,
+                edge.kind.label()
+            ));
+        }
+        out.push('}');
+        out
+    }
+
+    fn render_mermaid(&self) -> String {
+        // script names may contain dashes, which mermaid doesn't allow in node ids
+        let ids: HashMap<&str, String> = self
+            .workers
+            .iter()
+            .chain(&self.external)
+            .enumerate()
+            .map(|(i, worker)| (worker.as_str(), format!("w{}", i)))
+            .collect();
+
+        let mut out = "graph LR\n".to_string();
+        for worker in &self.workers {
+            out.push_str(&format!("  {}[\"{}\"]\n", ids[worker.as_str()], worker));
+        }
+        for worker in &self.external {
+            out.push_str(&format!("  {}([\"{}\"])\n", ids[worker.as_str()], worker));
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "  {} -->|\"{}\"| {}\n",
+                ids[edge.from.as_str()],
+                edge.kind.label(),
+                ids[edge.to.as_str()]
+            ));
+        }
+        out.trim_end().to_string()
+    }
+}
+
+impl EdgeKind {
+    fn label(&self) -> String {
+        match self {
+            EdgeKind::DependsOn => "depends on".to_string(),
+            EdgeKind::DurableObject {
+                binding,
+                class_name,
+            } => format!("{} ({})", binding, class_name),
+            EdgeKind::Service { binding } => format!("{} (service)", binding),
+            EdgeKind::TailConsumer => "tail consumer".to_string(),
+            EdgeKind::Queue { binding, queue } => format!("{} (queue {})", binding, queue),
+        }
+    }
+}
+
+pub fn graph(manifest: &Manifest, env: Option<&str>, format: GraphFormat) -> Result<()> {
+    let graph = WorkerGraph::new(manifest, env)?;
+    println!("{}", graph.render(format));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKSPACE: &str = r#"
+type = "javascript"
+account_id = "1234"
+
+[[workers]]
+name = "api"
+path = "api"
+[workers.durable_objects]
+classes = [
+    { binding = "COUNTER", class_name = "Counter", script_name = "counters" },
+    { binding = "SESSION", class_name = "Session" },
+]
+
+[[workers]]
+name = "counters"
+path = "counters"
+
+[[workers]]
+name = "frontend"
+path = "frontend"
+depends_on = ["api"]
+[workers.durable_objects]
+classes = [
+    { binding = "RATE_LIMITER", class_name = "RateLimiter", script_name = "limits" },
+]
+"#;
+
+    #[test]
+    fn it_collects_the_edges_of_a_workspace() {
+        let manifest: Manifest = toml::from_str(WORKSPACE).unwrap();
+        let graph = WorkerGraph::new(&manifest, None).unwrap();
+
+        assert_eq!(graph.workers, vec!["api", "counters", "frontend"]);
+        assert_eq!(graph.external, vec!["limits"]);
+        assert_eq!(
+            graph.edges,
+            vec![
+                Edge {
+                    from: "frontend".to_string(),
+                    to: "api".to_string(),
+                    kind: EdgeKind::DependsOn,
+                },
+                Edge {
+                    from: "api".to_string(),
+                    to: "counters".to_string(),
+                    kind: EdgeKind::DurableObject {
+                        binding: "COUNTER".to_string(),
+                        class_name: "Counter".to_string(),
+                    },
+                },
+                Edge {
+                    from: "frontend".to_string(),
+                    to: "limits".to_string(),
+                    kind: EdgeKind::DurableObject {
+                        binding: "RATE_LIMITER".to_string(),
+                        class_name: "RateLimiter".to_string(),
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_collects_service_tail_and_queue_edges() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+type = "javascript"
+account_id = "1234"
+
+[[workers]]
+name = "api"
+bindings = [
+    { type = "service", name = "AUTH", service = "auth" },
+    { type = "queue", name = "JOBS", queue_name = "jobs" },
+    { type = "queue", name = "AUDIT", queue_name = "audit" },
+]
+tail_consumers = ["logger"]
+
+[[workers]]
+name = "auth"
+[workers.env.staging]
+tail_consumers = ["logger-staging"]
+
+[[workers]]
+name = "worker"
+queue_consumers = ["jobs"]
+"#,
+        )
+        .unwrap();
+        let graph = WorkerGraph::new(&manifest, None).unwrap();
+
+        assert_eq!(graph.workers, vec!["api", "auth", "worker"]);
+        assert_eq!(graph.external, vec!["audit", "logger"]);
+        let edges: Vec<(&str, &str, String)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.kind.label()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("api", "auth", "AUTH (service)".to_string()),
+                ("api", "worker", "JOBS (queue jobs)".to_string()),
+                ("api", "audit", "AUDIT (queue audit)".to_string()),
+                ("api", "logger", "tail consumer".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_renders_dot_and_mermaid() {
+        let manifest: Manifest = toml::from_str(WORKSPACE).unwrap();
+        let graph = WorkerGraph::new(&manifest, None).unwrap();
+
+        let dot = graph.render(GraphFormat::Dot);
+        assert!(dot.starts_with("digraph workers {"));
+        assert!(dot.contains("  \"limits\" [style=dashed];"));
+        assert!(dot.contains("  \"frontend\" -> \"api\" [label=\"depends on\"];"));
+
+        let mermaid = graph.render(GraphFormat::Mermaid);
+        assert!(mermaid.starts_with("graph LR"));
+        assert!(mermaid.contains("  w3([\"limits\"])"));
+        assert!(mermaid.contains("  w0 -->|\"COUNTER (Counter)\"| w1"));
+    }
+}
@@ -0,0 +1,98 @@
+use std::process::Command;
+
+use anyhow::Result;
+
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::styles;
+
+/// What a publish hook is told about the publish it runs around, as environment variables
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HookContext {
+    /// The environment published to, empty for the top level
+    pub environment: String,
+    pub script_name: String,
+    /// The URLs the worker is published to, known to post-publish hooks only
+    pub urls: Vec<String>,
+    /// The commit published, empty outside of a git checkout
+    pub version: String,
+}
+
+impl HookContext {
+    fn vars(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("WRANGLER_ENV", self.environment.clone()),
+            ("WRANGLER_SCRIPT_NAME", self.script_name.clone()),
+            (
+                "WRANGLER_SCRIPT_URL",
+                self.urls.first().cloned().unwrap_or_default(),
+            ),
+            ("WRANGLER_SCRIPT_URLS", self.urls.join(" ")),
+            ("WRANGLER_VERSION", self.version.clone()),
+        ]
+    }
+}
+
+/// Runs `commands` one after the other through the shell, stopping at the first one that fails
+pub fn run(stage: &str, commands: &[String], context: &HookContext) -> Result<()> {
+    for cmd in commands {
+        StdErr::working(&format!(
+            "Running {} hook {}",
+            stage,
+            styles::highlight(cmd)
+        ));
+        let status = shell(cmd)
+            .envs(context.vars())
+            .status()
+            .map_err(|e| anyhow::anyhow!("Could not run the {} hook `{}`: {}", stage, cmd, e))?;
+        if !status.success() {
+            anyhow::bail!("The {} hook `{}` failed with {}", stage, cmd, status)
+        }
+    }
+    Ok(())
+}
+
+fn shell(cmd: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.arg("/C");
+        c.arg(cmd);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c");
+        c.arg(cmd);
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn it_hands_the_context_to_hooks_and_stops_at_a_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let context = HookContext {
+            environment: "production".to_string(),
+            script_name: "api".to_string(),
+            urls: vec!["https://api.example.com/*".to_string()],
+            version: "abc123".to_string(),
+        };
+
+        let commands = vec![
+            format!(
+                "echo \"$WRANGLER_ENV $WRANGLER_SCRIPT_NAME $WRANGLER_SCRIPT_URL $WRANGLER_VERSION\" > {}",
+                out.display()
+            ),
+            "exit 3".to_string(),
+            format!("rm {}", out.display()),
+        ];
+        assert!(run("pre-publish", &commands, &context).is_err());
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "production api https://api.example.com/* abc123\n"
+        );
+    }
+}
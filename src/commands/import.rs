@@ -0,0 +1,474 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use reqwest::blocking::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use toml::value::{Table, Value};
+
+use crate::commands::fmt::format_config;
+use crate::commands::scripts::{fetch_source, split_multipart};
+use crate::error::WranglerError;
+use crate::http::{self, CloudflareApi, HttpApi, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::fetch_zones;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::{styles, ui};
+
+// the directory of the project the script, or the modules of a module worker, are saved to
+const SOURCE_DIR: &str = "src";
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    result: T,
+}
+
+/// The bindings and settings of a published script
+#[derive(Debug, Default, Deserialize)]
+struct ScriptSettings {
+    #[serde(default)]
+    bindings: Vec<Binding>,
+    compatibility_date: Option<String>,
+    #[serde(default)]
+    compatibility_flags: Vec<String>,
+    usage_model: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Binding {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    /// The value of a plain text binding
+    text: Option<String>,
+    /// The namespace of a KV binding
+    namespace_id: Option<String>,
+    /// The class of a Durable Object binding, and the script it's in if it isn't this one
+    class_name: Option<String>,
+    script_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Schedules {
+    schedules: Vec<Schedule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Schedule {
+    cron: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Subdomain {
+    enabled: bool,
+}
+
+/// What a published worker is configured with in the dashboard
+#[derive(Debug, Default)]
+struct Published {
+    settings: ScriptSettings,
+    crons: Vec<String>,
+    /// The pattern of each route of the worker, with the zone it's in
+    routes: Vec<(String, String)>,
+    workers_dev: bool,
+}
+
+/// Downloads the published script `name` into the directory `dir`, along with a
+/// wrangler.toml that has its routes, bindings and cron triggers and a package.json that
+/// points to the script, so that a worker made in the dashboard can be kept under version
+/// control and published with wrangler from then on
+pub fn import(user: &GlobalUser, account_id: &str, name: &str, dir: &Path) -> Result<()> {
+    // the source files are only known once they're downloaded, so they're checked again then
+    check_not_there(
+        dir,
+        &[
+            PathBuf::from("wrangler.toml"),
+            PathBuf::from("package.json"),
+        ],
+        name,
+    )?;
+
+    let client = http::legacy_auth_client(user);
+    let spinner = ui::spinner(&format!("Downloading {}", name));
+    let source = fetch_source(&client, account_id, name)?;
+    spinner.set_message("Looking up its bindings, routes and cron triggers");
+    let script_path = format!("accounts/{}/workers/scripts/{}", account_id, name);
+    let published = Published {
+        settings: get(&client, &format!("{}/settings", script_path))?,
+        crons: get::<Schedules>(&client, &format!("{}/schedules", script_path))?
+            .schedules
+            .into_iter()
+            .map(|schedule| schedule.cron)
+            .collect(),
+        routes: routes(user, account_id, name)?,
+        workers_dev: match get::<Subdomain>(&client, &format!("{}/subdomain", script_path)) {
+            Ok(subdomain) => subdomain.enabled,
+            Err(e) => {
+                log::info!("Could not tell whether {} is on workers.dev: {}", name, e);
+                false
+            }
+        },
+    };
+    spinner.finish_and_clear();
+
+    let (files, modules) = match &source.boundary {
+        Some(boundary) => {
+            let mut files = Vec::new();
+            for (module_name, contents) in split_multipart(&source.body, boundary) {
+                files.push((
+                    Path::new(SOURCE_DIR).join(module_path(&module_name)?),
+                    contents,
+                ));
+            }
+            (files, true)
+        }
+        None => (
+            vec![(Path::new(SOURCE_DIR).join("index.js"), source.body)],
+            false,
+        ),
+    };
+    // the main module is the first part
+    let main = match files.first() {
+        Some((path, _)) => path.clone(),
+        None => anyhow::bail!("{} has no modules", name),
+    };
+    let paths: Vec<PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+    check_not_there(dir, &paths, name)?;
+
+    for (path, contents) in &files {
+        let full_path = dir.join(path);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(full_path, contents)?;
+    }
+
+    let (config, skipped) = config(name, account_id, modules, &published)?;
+    fs::write(dir.join("wrangler.toml"), config)?;
+    fs::write(
+        dir.join("package.json"),
+        package_json(name, &main, modules)?,
+    )?;
+
+    StdOut::success(&format!("Imported {} into {}", name, dir.display()));
+    if !skipped.is_empty() {
+        StdOut::warn(&format!(
+            "These bindings can't be downloaded, add them to wrangler.toml before publishing: {}",
+            skipped.join(", ")
+        ));
+    }
+    if published
+        .settings
+        .bindings
+        .iter()
+        .any(|binding| binding.kind == "secret_text")
+    {
+        StdOut::info(&format!(
+            "The values of secrets stay with the published worker, {} checks that they're all set",
+            styles::highlight("wrangler secret diff")
+        ));
+    }
+    StdOut::info(&format!(
+        "Check the project with {} before publishing it with {}",
+        styles::highlight("wrangler check"),
+        styles::highlight("wrangler publish")
+    ));
+    Ok(())
+}
+
+fn get<T: DeserializeOwned>(client: &Client, path: &str) -> Result<T> {
+    let addr = http::api_url(path)?;
+    let res = client.get(&addr).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json::<ApiResponse<T>>()?.result)
+}
+
+// The routes of the script `name` in every zone of the account, since a worker made in the
+// dashboard can be on routes of several
+fn routes(user: &GlobalUser, account_id: &str, name: &str) -> Result<Vec<(String, String)>> {
+    let api = HttpApi::new(user);
+    let mut routes = Vec::new();
+    for zone in fetch_zones(user, Some(account_id))? {
+        for route in api.list_routes(&zone.id)? {
+            if route.script.as_deref() == Some(name) {
+                routes.push((route.pattern, zone.id.clone()));
+            }
+        }
+    }
+    Ok(routes)
+}
+
+// Fails before anything is written when one of `paths` would overwrite a file in `dir`
+fn check_not_there(dir: &Path, paths: &[PathBuf], name: &str) -> Result<()> {
+    for path in paths {
+        let path = dir.join(path);
+        if path.exists() {
+            anyhow::bail!(
+                "{} already exists, import {} into another directory with --output",
+                path.display(),
+                name
+            )
+        }
+    }
+    Ok(())
+}
+
+// The path of a module in the source directory. Modules are named by the script, so only
+// the plain parts of their names are kept, which keeps them inside of it.
+fn module_path(name: &str) -> Result<PathBuf> {
+    let path: PathBuf = Path::new(name)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part),
+            _ => None,
+        })
+        .collect();
+    if path.as_os_str().is_empty() {
+        anyhow::bail!("The module {:?} has no name to save it under", name)
+    }
+    Ok(path)
+}
+
+// The wrangler.toml of the imported worker, and the bindings it couldn't be given, by name
+// and type: those of files that a service worker can't be downloaded with, and those of
+// kinds wrangler can't configure.
+fn config(
+    name: &str,
+    account_id: &str,
+    modules: bool,
+    published: &Published,
+) -> Result<(String, Vec<String>)> {
+    let mut config = Table::new();
+    config.insert("name".to_string(), name.into());
+    config.insert("type".to_string(), "javascript".into());
+    if modules {
+        config.insert("format".to_string(), "modules".into());
+    }
+    config.insert("account_id".to_string(), account_id.into());
+    config.insert("workers_dev".to_string(), published.workers_dev.into());
+
+    let zones: BTreeSet<&str> = published
+        .routes
+        .iter()
+        .map(|(_, zone_id)| zone_id.as_str())
+        .collect();
+    match zones.into_iter().collect::<Vec<_>>().as_slice() {
+        [] => {}
+        [zone_id] => {
+            config.insert("zone_id".to_string(), (*zone_id).into());
+            let patterns: Vec<Value> = published
+                .routes
+                .iter()
+                .map(|(pattern, _)| pattern.as_str().into())
+                .collect();
+            config.insert("routes".to_string(), patterns.into());
+        }
+        _ => {
+            let routes: Vec<Value> = published
+                .routes
+                .iter()
+                .map(|(pattern, zone_id)| {
+                    let mut route = Table::new();
+                    route.insert("pattern".to_string(), pattern.as_str().into());
+                    route.insert("zone_id".to_string(), zone_id.as_str().into());
+                    Value::Table(route)
+                })
+                .collect();
+            config.insert("routes".to_string(), routes.into());
+        }
+    }
+
+    let settings = &published.settings;
+    if let Some(compatibility_date) = &settings.compatibility_date {
+        config.insert(
+            "compatibility_date".to_string(),
+            compatibility_date.as_str().into(),
+        );
+    }
+    if !settings.compatibility_flags.is_empty() {
+        config.insert(
+            "compatibility_flags".to_string(),
+            settings.compatibility_flags.clone().into(),
+        );
+    }
+    if let Some(usage_model) = &settings.usage_model {
+        config.insert("usage_model".to_string(), usage_model.as_str().into());
+    }
+    if !published.crons.is_empty() {
+        let mut triggers = Table::new();
+        triggers.insert("crons".to_string(), published.crons.clone().into());
+        config.insert("triggers".to_string(), Value::Table(triggers));
+    }
+
+    let mut vars = Table::new();
+    let mut secrets = Vec::new();
+    let mut kv_namespaces = Vec::new();
+    let mut durable_objects = Vec::new();
+    let mut skipped = Vec::new();
+    for binding in &settings.bindings {
+        match (
+            binding.kind.as_str(),
+            &binding.namespace_id,
+            &binding.class_name,
+        ) {
+            ("plain_text", _, _) => {
+                let text = binding.text.clone().unwrap_or_default();
+                vars.insert(binding.name.clone(), text.into());
+            }
+            ("secret_text", _, _) => secrets.push(binding.name.clone()),
+            ("kv_namespace", Some(id), _) => {
+                let mut namespace = Table::new();
+                namespace.insert("binding".to_string(), binding.name.as_str().into());
+                namespace.insert("id".to_string(), id.as_str().into());
+                kv_namespaces.push(Value::Table(namespace));
+            }
+            ("durable_object_namespace", _, Some(class_name)) => {
+                let mut class = Table::new();
+                class.insert("name".to_string(), binding.name.as_str().into());
+                class.insert("class_name".to_string(), class_name.as_str().into());
+                if let Some(script_name) = binding.script_name.as_deref() {
+                    if script_name != name {
+                        class.insert("script_name".to_string(), script_name.into());
+                    }
+                }
+                durable_objects.push(Value::Table(class));
+            }
+            (kind, _, _) => skipped.push(format!("{} ({})", binding.name, kind)),
+        }
+    }
+    if !vars.is_empty() {
+        config.insert("vars".to_string(), Value::Table(vars));
+    }
+    if !secrets.is_empty() {
+        config.insert("secrets".to_string(), secrets.into());
+    }
+    if !kv_namespaces.is_empty() {
+        config.insert("kv_namespaces".to_string(), kv_namespaces.into());
+    }
+    if !durable_objects.is_empty() {
+        let mut table = Table::new();
+        table.insert("bindings".to_string(), durable_objects.into());
+        config.insert("durable_objects".to_string(), Value::Table(table));
+    }
+
+    let config = format_config(&toml::to_string(&Value::Table(config))?)?;
+    Ok((config, skipped))
+}
+
+// A package.json with the script as its `main`, or the main module as its `module`, which is
+// where wrangler looks for them
+fn package_json(name: &str, main: &Path, modules: bool) -> Result<String> {
+    let mut package = serde_json::Map::new();
+    package.insert("name".to_string(), name.into());
+    package.insert("private".to_string(), true.into());
+    package.insert(
+        if modules { "module" } else { "main" }.to_string(),
+        main.to_string_lossy().replace('\\', "/").into(),
+    );
+    Ok(format!("{}\n", serde_json::to_string_pretty(&package)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::{Manifest, RouteEntry};
+
+    fn binding(name: &str, kind: &str) -> Binding {
+        Binding {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            text: None,
+            namespace_id: None,
+            class_name: None,
+            script_name: None,
+        }
+    }
+
+    #[test]
+    fn it_configures_the_imported_worker_like_the_dashboard_does() {
+        let published = Published {
+            settings: ScriptSettings {
+                bindings: vec![
+                    Binding {
+                        text: Some("api.example.com".to_string()),
+                        ..binding("API_HOST", "plain_text")
+                    },
+                    binding("DB_PASSWORD", "secret_text"),
+                    Binding {
+                        namespace_id: Some("0f2ac74b498b48028cb68387c421e279".to_string()),
+                        ..binding("CACHE", "kv_namespace")
+                    },
+                    Binding {
+                        class_name: Some("Counter".to_string()),
+                        script_name: Some("api".to_string()),
+                        ..binding("COUNTER", "durable_object_namespace")
+                    },
+                    binding("TEMPLATE", "text_blob"),
+                ],
+                compatibility_date: Some("2021-11-01".to_string()),
+                ..ScriptSettings::default()
+            },
+            crons: vec!["*/5 * * * *".to_string()],
+            routes: vec![
+                ("example.com/api/*".to_string(), "zone1".to_string()),
+                ("api.example.com/*".to_string(), "zone1".to_string()),
+            ],
+            workers_dev: false,
+        };
+
+        let (config, skipped) = config("api", "account1", true, &published).unwrap();
+        assert_eq!(skipped, vec!["TEMPLATE (text_blob)".to_string()]);
+
+        let manifest: Manifest = toml::from_str(&config).unwrap();
+        assert_eq!(manifest.name, "api");
+        assert_eq!(manifest.zone_id.as_deref(), Some("zone1"));
+        assert_eq!(manifest.workers_dev, Some(false));
+        assert_eq!(
+            manifest.routes,
+            Some(vec![
+                RouteEntry::from("example.com/api/*"),
+                RouteEntry::from("api.example.com/*")
+            ])
+        );
+        assert_eq!(manifest.compatibility_date.as_deref(), Some("2021-11-01"));
+        assert_eq!(manifest.triggers.unwrap().crons, vec!["*/5 * * * *"]);
+        assert_eq!(
+            manifest.vars.unwrap().get("API_HOST").map(String::as_str),
+            Some("api.example.com")
+        );
+        assert_eq!(manifest.secrets, Some(vec!["DB_PASSWORD".to_string()]));
+        let kv_namespaces = manifest.kv_namespaces.unwrap();
+        assert_eq!(kv_namespaces[0].binding, "CACHE");
+        let classes = manifest.durable_objects.unwrap().classes.unwrap();
+        assert_eq!(classes[0].class_name, "Counter");
+        assert_eq!(classes[0].script_name, None);
+        assert!(config.contains("format = \"modules\""));
+    }
+
+    #[test]
+    fn it_doesnt_overwrite_the_source_of_a_project() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmpdir.path().join(SOURCE_DIR)).unwrap();
+        fs::write(tmpdir.path().join(SOURCE_DIR).join("index.js"), "").unwrap();
+
+        let paths = [Path::new(SOURCE_DIR).join("worker.mjs")];
+        assert!(check_not_there(tmpdir.path(), &paths, "worker").is_ok());
+        let paths = [Path::new(SOURCE_DIR).join("index.js")];
+        assert!(check_not_there(tmpdir.path(), &paths, "worker").is_err());
+    }
+
+    #[test]
+    fn it_keeps_modules_inside_of_the_project() {
+        assert_eq!(module_path("index.mjs").unwrap(), Path::new("index.mjs"));
+        assert_eq!(
+            module_path("../lib/utils.mjs").unwrap(),
+            Path::new("lib/utils.mjs")
+        );
+        assert!(module_path("/").is_err());
+    }
+}
@@ -48,7 +48,7 @@ entry-point = "workers-site"
     Ok(())
 }
 
-fn get_current_dirname() -> Result<String> {
+pub(crate) fn get_current_dirname() -> Result<String> {
     let current_path = std::env::current_dir()?;
     let parent = current_path.parent();
     let dirname = match parent {
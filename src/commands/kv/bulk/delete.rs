@@ -4,17 +4,16 @@ use std::fs;
 use std::fs::metadata;
 use std::path::Path;
 
-use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
-
 use anyhow::Result;
-use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::kv::bulk::delete;
+use crate::kv::bulk::KeyValuePair;
 use crate::kv::bulk::BATCH_KEY_MAX;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::interactive;
 use crate::terminal::message::{Message, StdOut};
+use crate::terminal::ui;
 
 pub fn run(
     target: &Target,
@@ -24,10 +23,13 @@ pub fn run(
     force: bool,
 ) -> Result<()> {
     if !force {
-        match interactive::confirm(&format!(
-            "Are you sure you want to delete all keys in {}?",
-            filename.display()
-        )) {
+        match interactive::confirm_or_fail(
+            &format!(
+                "Are you sure you want to delete all keys in {}?",
+                filename.display()
+            ),
+            "pass --force to delete them",
+        ) {
             Ok(true) => (),
             Ok(false) => {
                 StdOut::info(&format!("Not deleting keys in {}", filename.display()));
@@ -60,9 +62,7 @@ pub fn run(
     StdOut::working(&format!("deleting {} key value pairs", len));
 
     let progress_bar = if len > BATCH_KEY_MAX {
-        let pb = ProgressBar::new(len as u64);
-        pb.set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
-        Some(pb)
+        Some(ui::bar(len as u64))
     } else {
         None
     };
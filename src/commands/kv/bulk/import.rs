@@ -0,0 +1,229 @@
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+use crate::kv::bulk::KeyValuePair;
+
+/// What `kv:bulk put` reads key-value pairs from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BulkFormat {
+    /// A JSON array of key-value pairs
+    Json,
+    /// A CSV file with a header row naming its columns
+    Csv,
+    /// The rows a query returns from a SQLite database
+    Sqlite,
+}
+
+impl FromStr for BulkFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(BulkFormat::Json),
+            "csv" => Ok(BulkFormat::Csv),
+            "sqlite" => Ok(BulkFormat::Sqlite),
+            _ => anyhow::bail!("Unknown format \"{}\", expected json, csv or sqlite", s),
+        }
+    }
+}
+
+/// The columns of rows that make up each key-value pair
+#[derive(Clone, Debug, PartialEq)]
+pub struct Columns {
+    pub key: String,
+    pub value: String,
+    /// The number of seconds the pair lives for; rows with it empty never expire
+    pub ttl: Option<String>,
+}
+
+/// Reads a pair from each row of the CSV file at `path`
+pub fn from_csv(path: &Path, columns: &Columns) -> Result<Vec<KeyValuePair>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let headers = reader.headers()?.clone();
+    let index = |column: &str| {
+        headers
+            .iter()
+            .position(|header| header == column)
+            .ok_or_else(|| anyhow::anyhow!("{} has no column named \"{}\"", path.display(), column))
+    };
+    let key = index(&columns.key)?;
+    let value = index(&columns.value)?;
+    let ttl = columns.ttl.as_deref().map(index).transpose()?;
+
+    let mut pairs = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        // the header is the first line
+        let row = i + 2;
+        let field = |index: usize| record.get(index).unwrap_or_default().to_string();
+        pairs.push(pair(row, field(key), field(value), ttl.map(field))?);
+    }
+    Ok(pairs)
+}
+
+/// Reads a pair from each row `query` returns from the SQLite database at `path`, through the
+/// `sqlite3` command line tool, version 3.33 or later
+pub fn from_sqlite(path: &Path, query: &str, columns: &Columns) -> Result<Vec<KeyValuePair>> {
+    let version = Command::new("sqlite3")
+        .arg("-version")
+        .output()
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to run sqlite3, which reading SQLite databases requires: {}",
+                e
+            )
+        })?;
+    check_sqlite_version(&String::from_utf8_lossy(&version.stdout))?;
+
+    let output = Command::new("sqlite3")
+        .arg("-json")
+        .arg("-readonly")
+        .arg(path)
+        .arg(query)
+        .output()
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to run sqlite3, which reading SQLite databases requires: {}",
+                e
+            )
+        })?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "sqlite3 failed to run the query: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+    rows_to_pairs(&String::from_utf8(output.stdout)?, columns)
+}
+
+// `sqlite3 -json` is only there from 3.33.0 on, and older versions take `-json` for a file
+// name, so the version is checked first. `sqlite3 -version` prints like
+// "3.31.1 2020-01-27 19:55:54 3bfa9cc97da10598521b342961df8f5f68c7388fa117345eeb516eaa837b2323"
+fn check_sqlite_version(version: &str) -> Result<()> {
+    let numbers: Vec<u32> = version
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map_while(|number| number.parse().ok())
+        .collect();
+    match numbers.as_slice() {
+        [major, minor, ..] if (*major, *minor) >= (3, 33) => Ok(()),
+        _ => anyhow::bail!(
+            "Reading SQLite databases requires sqlite3 3.33.0 or later, but the installed sqlite3 is {}",
+            version.split_whitespace().next().unwrap_or("of an unknown version")
+        ),
+    }
+}
+
+// `sqlite3 -json` prints nothing at all for queries that return no rows
+fn rows_to_pairs(json: &str, columns: &Columns) -> Result<Vec<KeyValuePair>> {
+    if json.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let rows: Vec<Map<String, Value>> = serde_json::from_str(json)?;
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let field = |column: &str| match row.get(column) {
+                Some(Value::String(s)) => Ok(s.clone()),
+                Some(Value::Null) => Ok(String::new()),
+                Some(value) => Ok(value.to_string()),
+                None => Err(anyhow::anyhow!(
+                    "The query returns no column named \"{}\"",
+                    column
+                )),
+            };
+            let ttl = columns.ttl.as_deref().map(field).transpose()?;
+            pair(i + 1, field(&columns.key)?, field(&columns.value)?, ttl)
+        })
+        .collect()
+}
+
+fn pair(row: usize, key: String, value: String, ttl: Option<String>) -> Result<KeyValuePair> {
+    if key.is_empty() {
+        anyhow::bail!("Row {} has an empty key", row)
+    }
+    let expiration_ttl = match ttl.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(ttl) => Some(ttl.parse::<i64>().map_err(|_| {
+            anyhow::anyhow!(
+                "Row {} has a TTL of \"{}\", not a number of seconds",
+                row,
+                ttl
+            )
+        })?),
+    };
+    Ok(KeyValuePair {
+        key,
+        value,
+        expiration_ttl,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn columns(ttl: Option<&str>) -> Columns {
+        Columns {
+            key: "id".to_string(),
+            value: "body".to_string(),
+            ttl: ttl.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn it_reads_pairs_from_csv() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("rows.csv");
+        fs::write(
+            &path,
+            "id,title,body,ttl\na,first,\"hello, world\",60\nb,second,bye,\n",
+        )
+        .unwrap();
+
+        let pairs = from_csv(&path, &columns(Some("ttl"))).unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[0].key, "a");
+        assert_eq!(pairs[0].value, "hello, world");
+        assert_eq!(pairs[0].expiration_ttl, Some(60));
+        assert_eq!(pairs[1].expiration_ttl, None);
+
+        let missing = Columns {
+            value: "content".to_string(),
+            ..columns(None)
+        };
+        assert!(from_csv(&path, &missing).is_err());
+    }
+
+    #[test]
+    fn it_converts_sqlite_rows_to_pairs() {
+        let json = r#"[{"id":"a","body":"hello","ttl":3600},{"id":7,"body":null,"ttl":null}]"#;
+        let pairs = rows_to_pairs(json, &columns(Some("ttl"))).unwrap();
+        assert_eq!(pairs[0].expiration_ttl, Some(3600));
+        assert_eq!(pairs[1].key, "7");
+        assert_eq!(pairs[1].value, "");
+        assert_eq!(pairs[1].expiration_ttl, None);
+
+        assert!(rows_to_pairs("", &columns(None)).unwrap().is_empty());
+        assert!(rows_to_pairs(r#"[{"id":"a"}]"#, &columns(None)).is_err());
+    }
+
+    #[test]
+    fn it_requires_a_sqlite3_with_json_output() {
+        assert!(check_sqlite_version("3.33.0 2020-08-14 13:23:32 fca8dc8b578f").is_ok());
+        assert!(check_sqlite_version("3.40.1 2022-12-28 14:03:47 df5c253c0b3d").is_ok());
+        let error = check_sqlite_version("3.31.1 2020-01-27 19:55:54 3bfa9cc97da1").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("the installed sqlite3 is 3.31.1"));
+        assert!(check_sqlite_version("").is_err());
+    }
+}
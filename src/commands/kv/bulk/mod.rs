@@ -1,4 +1,5 @@
 pub mod delete;
+pub mod import;
 pub mod put;
 
 pub use delete::run as delete;
@@ -4,26 +4,64 @@ use std::fs;
 use std::fs::metadata;
 use std::path::Path;
 
-use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
-
 use anyhow::{anyhow, Result};
-use indicatif::{ProgressBar, ProgressStyle};
 
+use super::import::{self, BulkFormat, Columns};
 use crate::kv::bulk::put;
+use crate::kv::bulk::KeyValuePair;
 use crate::kv::bulk::BATCH_KEY_MAX;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::message::{Message, StdErr};
-pub fn run(target: &Target, user: &GlobalUser, namespace_id: &str, filename: &Path) -> Result<()> {
-    let pairs: Vec<KeyValuePair> = match &metadata(filename) {
+use crate::terminal::ui;
+
+pub fn run(
+    target: &Target,
+    user: &GlobalUser,
+    namespace_id: &str,
+    filename: &Path,
+    format: BulkFormat,
+    query: Option<&str>,
+    columns: &Columns,
+) -> Result<()> {
+    let pairs = match (format, query) {
+        (BulkFormat::Json, _) => from_json(filename)?,
+        (BulkFormat::Csv, _) => import::from_csv(filename, columns)?,
+        (BulkFormat::Sqlite, Some(query)) => import::from_sqlite(filename, query, columns)?,
+        (BulkFormat::Sqlite, None) => {
+            anyhow::bail!("--query is required to read key-value pairs from a SQLite database")
+        }
+    };
+
+    let len = pairs.len();
+
+    StdErr::working(&format!("uploading {} key value pairs", len));
+    let progress_bar = if len > BATCH_KEY_MAX {
+        Some(ui::bar(len as u64))
+    } else {
+        None
+    };
+
+    put(target, user, namespace_id, pairs, &progress_bar)?;
+
+    if let Some(pb) = &progress_bar {
+        pb.finish_with_message(&format!("uploaded {} key value pairs", len));
+    }
+
+    StdErr::success("Success");
+    Ok(())
+}
+
+fn from_json(filename: &Path) -> Result<Vec<KeyValuePair>> {
+    match &metadata(filename) {
         Ok(file_type) if file_type.is_file() => {
             let data = fs::read_to_string(filename)?;
-            let data_vec = serde_json::from_str(&data);
+            let data_vec = serde_json::from_str(&data).or_else(|_| from_json_lines(&data));
             match data_vec {
                 Ok(data_vec) => Ok(data_vec),
                 Err(_) => {
                     // Hide '{' in this error message from the formatting machinery in anyhow macro
-                    let msg = "Failed to decode JSON. Please make sure to follow the format, [{\"key\": \"test_key\", \"value\": \"test_value\"}, ...]";
+                    let msg = "Failed to decode JSON. Please make sure to follow the format, [{\"key\": \"test_key\", \"value\": \"test_value\"}, ...], or to put one such object per line";
                     Err(anyhow!(msg))
                 }
             }
@@ -33,25 +71,27 @@ pub fn run(target: &Target, user: &GlobalUser, namespace_id: &str, filename: &Pa
             filename.display()
         )),
         Err(e) => Err(anyhow!("{}", e)),
-    }?;
-
-    let len = pairs.len();
+    }
+}
 
-    StdErr::working(&format!("uploading {} key value pairs", len));
-    let progress_bar = if len > BATCH_KEY_MAX {
-        let pb = ProgressBar::new(len as u64);
-        pb.set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
-        Some(pb)
-    } else {
-        None
-    };
+// One pair per line, the way `kv:namespace export` writes them
+fn from_json_lines(data: &str) -> serde_json::Result<Vec<KeyValuePair>> {
+    data.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
 
-    put(target, user, namespace_id, pairs, &progress_bar)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if let Some(pb) = &progress_bar {
-        pb.finish_with_message(&format!("uploaded {} key value pairs", len));
+    #[test]
+    fn it_reads_one_pair_per_line() {
+        let data = "{\"key\":\"a\",\"value\":\"1\"}\n\n{\"key\":\"b\",\"value\":\"//4=\",\"base64\":true}\n";
+        let pairs = from_json_lines(data).unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[1].base64, Some(true));
+        assert!(from_json_lines("{\"key\":\"a\"").is_err());
     }
-
-    StdErr::success("Success");
-    Ok(())
 }
@@ -1,10 +1,9 @@
 use cloudflare::endpoints::workerskv::delete_key::DeleteKey;
-use cloudflare::framework::apiclient::ApiClient;
 
 use anyhow::Result;
 
 use crate::commands::kv::format_error;
-use crate::http;
+use crate::http::{self, AuditedRequest};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::interactive;
@@ -13,7 +12,10 @@ pub fn delete(target: &Target, user: &GlobalUser, id: &str, key: &str, force: bo
     let client = http::cf_v4_client(user)?;
 
     if !force {
-        match interactive::confirm(&format!("Are you sure you want to delete key \"{}\"?", key)) {
+        match interactive::confirm_or_fail(
+            &format!("Are you sure you want to delete key \"{}\"?", key),
+            "pass --force to delete it",
+        ) {
             Ok(true) => (),
             Ok(false) => {
                 StdOut::info(&format!("Not deleting key \"{}\"", key));
@@ -26,7 +28,7 @@ pub fn delete(target: &Target, user: &GlobalUser, id: &str, key: &str, force: bo
     let msg = format!("Deleting key \"{}\"", key);
     StdOut::working(&msg);
 
-    let response = client.request(&DeleteKey {
+    let response = client.request_audited(&DeleteKey {
         account_identifier: target.account_id.load()?,
         namespace_identifier: id,
         key, // this is url encoded within cloudflare-rs
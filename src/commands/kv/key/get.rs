@@ -5,34 +5,136 @@
 
 use cloudflare::framework::response::ApiFailure;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use futures::{stream, StreamExt};
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
 
 use crate::commands::kv;
-use crate::http;
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
-use std::io::{self, Write};
+use crate::terminal::message::{Message, StdErr};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
 
-pub fn get(target: &Target, user: &GlobalUser, id: &str, key: &str) -> Result<()> {
-    let api_endpoint = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
+// How many values a batched get fetches at the same time
+const BATCH_CONCURRENCY: usize = 8;
+
+/// How `kv:key get` writes the value of a single key
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValueOutput {
+    /// Write the value to this file instead of stdout
+    pub path: Option<PathBuf>,
+    /// Print the value base64 encoded, for values that would garble a terminal
+    pub base64: bool,
+    /// Also print the expiration and metadata of the key, to stderr
+    pub verbose: bool,
+}
+
+impl ValueOutput {
+    /// Writes `value` byte for byte, so binary values come out the way they were put
+    pub fn write(&self, value: &[u8]) -> Result<()> {
+        match &self.path {
+            Some(path) => fs::write(path, value)
+                .map_err(|e| anyhow!("Could not write {}: {}", path.display(), e))?,
+            None if self.base64 => println!("{}", base64::encode(value)),
+            None => {
+                let stdout = io::stdout();
+                let mut out = stdout.lock();
+                out.write_all(value)?;
+                out.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the expiration and metadata of a key when `verbose`. They go to stderr so that
+    /// the value on stdout can still be piped.
+    pub fn describe(&self, expiration: Option<u64>, metadata: Option<&serde_json::Value>) {
+        if self.verbose {
+            for line in describe_key(expiration, metadata) {
+                StdErr::info(&line);
+            }
+        }
+    }
+}
+
+fn describe_key(expiration: Option<u64>, metadata: Option<&serde_json::Value>) -> Vec<String> {
+    let expiration = match expiration {
+        Some(expiration) => format!("Expires at {} (unix time)", expiration),
+        None => "Does not expire".to_string(),
+    };
+    let metadata = match metadata {
+        Some(metadata) => format!("Metadata: {}", metadata),
+        None => "No metadata".to_string(),
+    };
+    vec![expiration, metadata]
+}
+
+// A key as the keys endpoint lists it, which is the only place the API has its expiration
+#[derive(Deserialize)]
+struct ListedKey {
+    name: String,
+    expiration: Option<u64>,
+    metadata: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ListedKeys {
+    result: Vec<ListedKey>,
+}
+
+/// Where `kv:key get --batch` writes the values it fetches
+#[derive(Clone, Debug, PartialEq)]
+pub enum BatchOutput {
+    /// One JSON object per key on stdout, in the order the values arrive
+    Ndjson,
+    /// One file per key in this directory, named after the url-encoded key
+    Dir(PathBuf),
+}
+
+// Same shape as the entries `kv:bulk put` accepts, so an export can be put back as is
+#[derive(Serialize)]
+struct BatchValue<'a> {
+    key: &'a str,
+    value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base64: Option<bool>,
+}
+
+pub fn get(
+    target: &Target,
+    user: &GlobalUser,
+    id: &str,
+    key: &str,
+    output: &ValueOutput,
+) -> Result<()> {
+    let namespace_addr = http::api_url(&format!(
+        "accounts/{}/storage/kv/namespaces/{}",
         target.account_id.load()?,
-        id,
-        kv::url_encode_key(key)
-    );
+        id
+    ))?;
+    let api_endpoint = format!("{}/values/{}", namespace_addr, kv::url_encode_key(key));
 
     let client = http::legacy_auth_client(user);
 
-    let res = client.get(&api_endpoint).send()?;
+    let res = client.get(&api_endpoint).send_logged()?;
 
     let response_status = res.status();
     if response_status.is_success() {
-        let body = res.bytes()?;
         // We don't use message::success because we don't want to include the emoji/formatting
         // in case someone is piping this to stdin.
-        // This will probably fail for non-UTF8 on Windows, but should at least work for people
-        // getting binary data from KV on Unix-y systems.
-        io::stdout().write_all(&*body)?;
+        output.write(&res.bytes()?)?;
+        if output.verbose {
+            let listed = fetch_listed_key(&client, &namespace_addr, key)?;
+            output.describe(
+                listed.as_ref().and_then(|listed| listed.expiration),
+                listed.as_ref().and_then(|listed| listed.metadata.as_ref()),
+            );
+        }
     } else {
         // This is logic pulled from cloudflare-rs for pretty error formatting right now;
         // it will be redundant when we switch to using cloudflare-rs for all API requests.
@@ -46,3 +148,156 @@ pub fn get(target: &Target, user: &GlobalUser, id: &str, key: &str) -> Result<()
 
     Ok(())
 }
+
+/// Reads the keys of a batched get from a file with one key per line, or from stdin if
+/// `path` is "-".
+pub fn read_batch_keys(path: &Path) -> Result<Vec<String>> {
+    let mut contents = String::new();
+    if path == Path::new("-") {
+        io::stdin().read_to_string(&mut contents)?;
+    } else {
+        contents = fs::read_to_string(path)?;
+    }
+    Ok(parse_batch_keys(&contents))
+}
+
+fn parse_batch_keys(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn get_batch(
+    target: &Target,
+    user: &GlobalUser,
+    id: &str,
+    keys: Vec<String>,
+    output: BatchOutput,
+) -> Result<()> {
+    if let BatchOutput::Dir(dir) = &output {
+        fs::create_dir_all(dir)?;
+    }
+
+    let values_addr = http::api_url(&format!(
+        "accounts/{}/storage/kv/namespaces/{}/values",
+        target.account_id.load()?,
+        id
+    ))?;
+    let total = keys.len();
+    let failed = http::block_on(async {
+        let client = http::legacy_auth_client_async(user);
+        let (client, values_addr) = (&client, &values_addr);
+        let mut fetched = stream::iter(keys)
+            .map(|key| async move {
+                let value = fetch_value(client, values_addr, &key).await;
+                (key, value)
+            })
+            .buffer_unordered(BATCH_CONCURRENCY);
+
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        let mut failed = 0;
+        while let Some((key, value)) = fetched.next().await {
+            match value {
+                Ok(value) => match &output {
+                    BatchOutput::Ndjson => {
+                        let batch_value = match String::from_utf8(value) {
+                            Ok(value) => BatchValue {
+                                key: &key,
+                                value,
+                                base64: None,
+                            },
+                            Err(e) => BatchValue {
+                                key: &key,
+                                value: base64::encode(e.as_bytes()),
+                                base64: Some(true),
+                            },
+                        };
+                        writeln!(out, "{}", serde_json::to_string(&batch_value)?)?;
+                    }
+                    BatchOutput::Dir(dir) => fs::write(dir.join(kv::url_encode_key(&key)), value)?,
+                },
+                Err(e) => {
+                    failed += 1;
+                    StdErr::warn(&format!("Could not get {}: {}", key, e));
+                }
+            }
+        }
+        out.flush()?;
+        Ok::<_, anyhow::Error>(failed)
+    })??;
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} key(s) could not be fetched", failed, total)
+    }
+    Ok(())
+}
+
+// Every other key that starts with `key` sorts after it, so it's first in the listing if
+// it's there at all
+fn fetch_listed_key(client: &Client, namespace_addr: &str, key: &str) -> Result<Option<ListedKey>> {
+    let res = client
+        .get(&format!("{}/keys", namespace_addr))
+        .query(&[("prefix", key), ("limit", "10")])
+        .send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    let listed: ListedKeys = res.json()?;
+    Ok(listed.result.into_iter().find(|listed| listed.name == key))
+}
+
+async fn fetch_value(client: &reqwest::Client, values_addr: &str, key: &str) -> Result<Vec<u8>> {
+    let request = client.get(&format!("{}/{}", values_addr, kv::url_encode_key(key)));
+    let res = http::send_logged_async(request).await?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text().await?))
+    }
+    Ok(res.bytes().await?.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_one_key_per_line() {
+        let keys = parse_batch_keys("first\r\nsecond key\n\nthird/nested\n");
+        assert_eq!(keys, vec!["first", "second key", "third/nested"]);
+    }
+
+    #[test]
+    fn it_describes_expiration_and_metadata() {
+        let metadata = serde_json::json!({ "type": "image/png" });
+        assert_eq!(
+            describe_key(Some(1700000000), Some(&metadata)),
+            vec![
+                "Expires at 1700000000 (unix time)",
+                "Metadata: {\"type\":\"image/png\"}"
+            ]
+        );
+        assert_eq!(
+            describe_key(None, None),
+            vec!["Does not expire", "No metadata"]
+        );
+    }
+
+    #[test]
+    fn it_writes_values_to_a_file_unchanged() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("value.bin");
+        let value = [0xff, 0x00, 0xfe, b'\n'];
+        let output = ValueOutput {
+            path: Some(path.clone()),
+            ..Default::default()
+        };
+        output.write(&value)?;
+        assert_eq!(fs::read(&path)?, value);
+        Ok(())
+    }
+}
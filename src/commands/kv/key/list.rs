@@ -1,5 +1,8 @@
 extern crate serde_json;
 
+use std::io::{self, Write};
+use std::str::FromStr;
+
 use crate::commands::kv;
 use crate::http;
 use crate::kv::key::KeyList;
@@ -8,38 +11,86 @@ use crate::settings::toml::Target;
 
 use anyhow::Result;
 
-// Note: this function only prints keys in json form, given that
-// the number of entries in each json blob is variable (so csv and tsv
-// representation won't make sense)
+// Note: keys are only printed in json form, given that the number of entries
+// in each json blob is variable (so csv and tsv representation won't make sense)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ListFormat {
+    /// A single JSON array of keys
+    Json,
+    /// One JSON object per line, printed as soon as each page of keys is fetched
+    Ndjson,
+}
+
+impl FromStr for ListFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(ListFormat::Json),
+            "ndjson" => Ok(ListFormat::Ndjson),
+            _ => anyhow::bail!("Unknown format \"{}\", expected json or ndjson", s),
+        }
+    }
+}
+
+// Follows the pagination cursors of the Workers KV API until `limit` keys
+// have been listed, or every key matching `prefix` if there is no limit.
 pub fn list(
     target: &Target,
     user: &GlobalUser,
     namespace_id: &str,
     prefix: Option<&str>,
+    limit: Option<usize>,
+    format: ListFormat,
 ) -> Result<()> {
     let client = http::cf_v4_client(user)?;
     let key_list = KeyList::new(target, client, namespace_id, prefix)?;
+    let key_list = key_list.take(limit.unwrap_or(usize::MAX));
 
-    print!("["); // Open json list bracket
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    if format == ListFormat::Json {
+        write!(out, "[")?; // Open json list bracket
+    }
 
     let mut first_key = true;
 
     for key_result in key_list {
-        match key_result {
-            Ok(key) => {
+        let key = match key_result {
+            Ok(key) => key,
+            Err(e) => return Err(kv::api_error(e)),
+        };
+
+        match format {
+            ListFormat::Json => {
                 if first_key {
                     first_key = false;
                 } else {
-                    print!(",");
+                    write!(out, ",")?;
                 }
-
-                print!("{}", serde_json::to_string(&key)?);
+                write!(out, "{}", serde_json::to_string(&key)?)?;
             }
-            Err(e) => print!("{}", kv::format_error(e)),
+            ListFormat::Ndjson => writeln!(out, "{}", serde_json::to_string(&key)?)?,
         }
     }
 
-    print!("]"); // Close json list bracket
+    if format == ListFormat::Json {
+        write!(out, "]")?; // Close json list bracket
+    }
+    out.flush()?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_list_formats() {
+        assert_eq!("json".parse::<ListFormat>().unwrap(), ListFormat::Json);
+        assert_eq!("ndjson".parse::<ListFormat>().unwrap(), ListFormat::Ndjson);
+        assert!("csv".parse::<ListFormat>().is_err());
+    }
+}
@@ -0,0 +1,98 @@
+//! The `--local` variants of the `kv:key` commands, which act on the namespaces kept in
+//! `.wrangler/kv/` instead of the ones of your account.
+
+use std::io::{self, Read, Write};
+
+use anyhow::Result;
+
+use super::put::value_reader;
+use super::{KVMetaData, ListFormat, ValueOutput};
+use crate::kv::local::{self, LocalNamespace};
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdOut};
+
+pub fn put(data: KVMetaData) -> Result<()> {
+    let mut value = Vec::new();
+    value_reader(&data)?.0.read_to_end(&mut value)?;
+    // a TTL takes precedence over an expiration, like it does for the API
+    let expiration = match (&data.expiration_ttl, &data.expiration) {
+        (Some(ttl), _) => Some(local::now() + ttl.parse::<u64>()?),
+        (None, Some(expiration)) => Some(expiration.parse()?),
+        (None, None) => None,
+    };
+
+    let mut namespace = LocalNamespace::open(&data.namespace_id)?;
+    namespace.put(&data.key, &value, expiration, data.metadata);
+    namespace.save()?;
+    StdOut::success("Success");
+    Ok(())
+}
+
+pub fn get(id: &str, key: &str, output: &ValueOutput) -> Result<()> {
+    let namespace = LocalNamespace::open(id)?;
+    match namespace.get(key)? {
+        Some(value) => output.write(&value)?,
+        None => anyhow::bail!("There is no key \"{}\" in the local namespace {}", key, id),
+    }
+    if let Some(listed) = namespace
+        .keys(Some(key))
+        .into_iter()
+        .find(|k| k.name == key)
+    {
+        output.describe(listed.expiration, listed.metadata.as_ref());
+    }
+    Ok(())
+}
+
+pub fn delete(id: &str, key: &str, force: bool) -> Result<()> {
+    if !force
+        && !interactive::confirm_or_fail(
+            &format!(
+                "Are you sure you want to delete key \"{}\" from the local namespace?",
+                key
+            ),
+            "pass --force to delete it",
+        )?
+    {
+        StdOut::info(&format!("Not deleting key \"{}\"", key));
+        return Ok(());
+    }
+
+    let mut namespace = LocalNamespace::open(id)?;
+    if namespace.delete(key) {
+        namespace.save()?;
+        StdOut::success("Success");
+    } else {
+        StdOut::info(&format!(
+            "There is no key \"{}\" in the local namespace",
+            key
+        ));
+    }
+    Ok(())
+}
+
+pub fn list(
+    id: &str,
+    prefix: Option<&str>,
+    limit: Option<usize>,
+    format: ListFormat,
+) -> Result<()> {
+    let keys = LocalNamespace::open(id)?.keys(prefix);
+    let keys = keys.iter().take(limit.unwrap_or(usize::MAX));
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match format {
+        ListFormat::Json => {
+            let keys: Vec<_> = keys.collect();
+            write!(out, "{}", serde_json::to_string(&keys)?)?;
+        }
+        ListFormat::Ndjson => {
+            for key in keys {
+                writeln!(out, "{}", serde_json::to_string(key)?)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
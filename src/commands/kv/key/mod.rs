@@ -1,9 +1,10 @@
 mod delete;
 mod get;
 mod list;
+pub mod local;
 mod put;
 
 pub use delete::delete;
-pub use get::get;
-pub use list::list;
-pub use put::{parse_metadata, put, KVMetaData};
+pub use get::{get, get_batch, read_batch_keys, BatchOutput, ValueOutput};
+pub use list::{list, ListFormat};
+pub use put::{parse_metadata, put, KVMetaData, STDIN};
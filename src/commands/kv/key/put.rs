@@ -2,26 +2,33 @@
 // when https://github.com/cloudflare/cloudflare-rs/issues/26 is handled (this is
 // because the SET key request body is not json--it is the raw value).
 
-use std::fs;
-use std::fs::metadata;
+use std::fs::{metadata, File};
+use std::io::{self, Read};
 
 use anyhow::Result;
 use cloudflare::framework::response::ApiFailure;
 use url::Url;
 
 use crate::commands::kv;
-use crate::http;
+use crate::http::{self, SendLogged};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::sites::VALUE_MAX_SIZE;
 use crate::terminal::message::{Message, StdOut};
 use regex::Regex;
-use reqwest::blocking::multipart;
+use reqwest::blocking::{multipart, Body};
+use reqwest::Method;
+
+/// What `--path` reads the value from standard input with
+pub const STDIN: &str = "-";
 
 pub struct KVMetaData {
     pub namespace_id: String,
     pub key: String,
     pub value: String,
     pub is_file: bool,
+    /// The value is base64, decoded before it's written
+    pub base64: bool,
     pub expiration: Option<String>,
     pub expiration_ttl: Option<String>,
     pub metadata: Option<serde_json::Value>,
@@ -49,12 +56,12 @@ pub fn parse_metadata(arg: Option<&str>) -> Result<Option<serde_json::Value>> {
 }
 
 pub fn put(target: &Target, user: &GlobalUser, data: KVMetaData) -> Result<()> {
-    let api_endpoint = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
+    let api_endpoint = http::api_url(&format!(
+        "accounts/{}/storage/kv/namespaces/{}/values/{}",
         target.account_id.load()?,
         &data.namespace_id,
         kv::url_encode_key(&data.key)
-    );
+    ))?;
 
     // Add expiration and expiration_ttl query options as necessary.
     let mut query_params: Vec<(&str, &str)> = vec![];
@@ -93,44 +100,177 @@ fn get_response(
 ) -> Result<reqwest::blocking::Response> {
     let url_into_str = url.to_string();
     let client = http::legacy_auth_client(user);
-    let value_body = get_request_body(&data)?;
+    let (value, len) = value_reader(&data)?;
     let res = match &data.metadata {
         Some(metadata) => {
-            let value_part = multipart::Part::bytes(value_body);
+            let value_part = match len {
+                Some(len) => multipart::Part::reader_with_length(value, len),
+                None => multipart::Part::reader(value),
+            };
             let form = multipart::Form::new()
                 .part("value", value_part)
                 .text("metadata", metadata.to_string());
-            client.put(&url_into_str).multipart(form).send()?
+            client
+                .put(&url_into_str)
+                .multipart(form)
+                .send_logged_as(Method::PUT)?
+        }
+        None => {
+            let body = match len {
+                Some(len) => Body::sized(value, len),
+                None => Body::new(value),
+            };
+            client
+                .put(&url_into_str)
+                .body(body)
+                .send_logged_as(Method::PUT)?
         }
-        None => client.put(&url_into_str).body(value_body).send()?,
     };
     Ok(res)
 }
 
-// If is_file is true, overwrite value to be the contents of the given
-// filename in the 'value' arg.
-fn get_request_body(data: &KVMetaData) -> Result<Vec<u8>> {
-    if data.is_file {
-        match &metadata(&data.value) {
-            Ok(file_type) if file_type.is_file() => Ok(fs::read(&data.value)?),
-            Ok(file_type) if file_type.is_dir() => anyhow::bail!(
-                "--path argument takes a file, {} is a directory",
-                data.value
-            ),
-            Ok(_) => anyhow::bail!(
-                "--path argument points to an entity that is not a file or a directory: {}",
-                data.value
-            ),
-            Err(e) => anyhow::bail!("{}", e),
+/// The value to write, read from the file or standard input given with `--path` as it's
+/// uploaded rather than all at once, along with its length when it's known beforehand
+pub(super) fn value_reader(data: &KVMetaData) -> Result<(Box<dyn Read + Send>, Option<u64>)> {
+    if data.base64 {
+        // base64 is decoded whole, it's only for values that were small enough to print
+        let mut encoded = Vec::new();
+        raw_value_reader(data)?.0.read_to_end(&mut encoded)?;
+        encoded.retain(|b| !b.is_ascii_whitespace());
+        let value = base64::decode(&encoded)
+            .map_err(|e| anyhow::anyhow!("The value isn't valid base64: {}", e))?;
+        ensure_size(value.len() as u64)?;
+        let len = value.len() as u64;
+        return Ok((Box::new(io::Cursor::new(value)), Some(len)));
+    }
+    raw_value_reader(data)
+}
+
+fn raw_value_reader(data: &KVMetaData) -> Result<(Box<dyn Read + Send>, Option<u64>)> {
+    if !data.is_file {
+        let value = data.value.clone().into_bytes();
+        let len = value.len() as u64;
+        ensure_size(len)?;
+        return Ok((Box::new(io::Cursor::new(value)), Some(len)));
+    }
+    if data.value == STDIN {
+        let stdin = SizeLimited {
+            inner: io::stdin(),
+            read: 0,
+        };
+        return Ok((Box::new(stdin), None));
+    }
+    match &metadata(&data.value) {
+        Ok(file_type) if file_type.is_file() => {
+            ensure_size(file_type.len())?;
+            Ok((Box::new(File::open(&data.value)?), Some(file_type.len())))
         }
-    } else {
-        Ok(data.value.clone().into())
+        Ok(file_type) if file_type.is_dir() => anyhow::bail!(
+            "--path argument takes a file, {} is a directory",
+            data.value
+        ),
+        Ok(_) => anyhow::bail!(
+            "--path argument points to an entity that is not a file or a directory: {}",
+            data.value
+        ),
+        Err(e) => anyhow::bail!("{}", e),
+    }
+}
+
+fn ensure_size(len: u64) -> Result<()> {
+    anyhow::ensure!(len <= VALUE_MAX_SIZE, too_large(len));
+    Ok(())
+}
+
+fn too_large(len: u64) -> String {
+    format!(
+        "The value is {} bytes, more than the {} MiB a value of Workers KV can be",
+        len,
+        VALUE_MAX_SIZE / 1024 / 1024
+    )
+}
+
+// Standard input has no length to check up front, so it fails once it has given more than a
+// value can be
+struct SizeLimited<R> {
+    inner: R,
+    read: u64,
+}
+
+impl<R: Read> Read for SizeLimited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        if self.read > VALUE_MAX_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "The value is more than the {} MiB a value of Workers KV can be",
+                    VALUE_MAX_SIZE / 1024 / 1024
+                ),
+            ));
+        }
+        Ok(n)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
+
+    fn value(value: &str, is_file: bool, base64: bool) -> KVMetaData {
+        KVMetaData {
+            namespace_id: "namespace".to_string(),
+            key: "key".to_string(),
+            value: value.to_string(),
+            is_file,
+            base64,
+            expiration: None,
+            expiration_ttl: None,
+            metadata: None,
+        }
+    }
+
+    fn read_value(data: &KVMetaData) -> Result<Vec<u8>> {
+        let (mut reader, _) = value_reader(data)?;
+        let mut value = Vec::new();
+        reader.read_to_end(&mut value)?;
+        Ok(value)
+    }
+
+    #[test]
+    fn it_reads_values_from_files_and_base64() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("value.bin");
+        fs::write(&path, [0xff, 0xfe, 0x00]).unwrap();
+        let path = path.to_string_lossy();
+
+        assert_eq!(read_value(&value("hello", false, false)).unwrap(), b"hello");
+        assert_eq!(value_reader(&value(&path, true, false)).unwrap().1, Some(3));
+        assert_eq!(
+            read_value(&value(&path, true, false)).unwrap(),
+            [0xff, 0xfe, 0x00]
+        );
+        assert_eq!(
+            read_value(&value("//4A\n", false, true)).unwrap(),
+            [0xff, 0xfe, 0x00]
+        );
+        assert!(read_value(&value("not base64!", false, true)).is_err());
+        assert!(value_reader(&value(&dir.path().to_string_lossy(), true, false)).is_err());
+    }
+
+    #[test]
+    fn it_stops_reading_past_the_value_size_limit() {
+        let mut limited = SizeLimited {
+            inner: io::repeat(0).take(VALUE_MAX_SIZE + 1),
+            read: 0,
+        };
+        let err = io::copy(&mut limited, &mut io::sink()).unwrap_err();
+        assert!(err.to_string().contains("MiB"));
+        assert!(ensure_size(VALUE_MAX_SIZE).is_ok());
+        assert!(ensure_size(VALUE_MAX_SIZE + 1).is_err());
+    }
 
     #[test]
     fn metadata_parser_legal() {
@@ -17,6 +17,10 @@ pub fn format_error(e: ApiFailure) -> String {
     http::format_error(e, Some(&kv_help))
 }
 
+pub fn api_error(e: ApiFailure) -> anyhow::Error {
+    http::api_error(e, Some(&kv_help))
+}
+
 // kv_help() provides more detailed explanations of Workers KV API error codes.
 // See https://api.cloudflare.com/#workers-kv-namespace-errors for details.
 fn kv_help(error_code: u16) -> &'static str {
@@ -71,16 +75,30 @@ pub fn get_namespace_id(target: &Target, binding: &str) -> Result<String> {
         }
     }
 
+    let bindings: Vec<&str> = target
+        .kv_namespaces
+        .iter()
+        .map(|ns| ns.binding.as_str())
+        .collect();
+    if bindings.is_empty() {
+        // environments don't inherit kv_namespaces, which is easy to trip over
+        anyhow::bail!(
+            "Namespace binding \"{}\" not found in \"{}\", which has no kv_namespaces configured. Note that environments don't inherit the kv_namespaces of the top level of your configuration file",
+            binding,
+            target.name
+        )
+    }
     anyhow::bail!(
-        "Namespace binding \"{}\" not found in \"{}\"",
+        "Namespace binding \"{}\" not found in \"{}\", the configured bindings are: {}",
         binding,
-        target.name
+        target.name,
+        bindings.join(", ")
     )
 }
 
 const KV_ASCII_SET: &AsciiSet = &CONTROLS.add(b'/');
 
-fn url_encode_key(key: &str) -> String {
+pub(crate) fn url_encode_key(key: &str) -> String {
     utf8_percent_encode(key, KV_ASCII_SET).to_string()
 }
 
@@ -113,13 +131,54 @@ mod tests {
             text_blobs: None,
             build: None,
             wasm_modules: None,
+            bindings: None,
             usage_model: None,
+            limits: None,
             compatibility_date: None,
             compatibility_flags: Vec::new(),
         };
         assert!(kv::get_namespace_id(&target_with_dup_kv_bindings, "").is_err());
     }
 
+    #[test]
+    fn it_resolves_bindings_to_namespace_ids() {
+        let target = Target {
+            account_id: None.into(),
+            kv_namespaces: vec![
+                KvNamespace {
+                    id: "first-id".to_string(),
+                    binding: "FIRST".to_string(),
+                },
+                KvNamespace {
+                    id: "second-id".to_string(),
+                    binding: "SECOND".to_string(),
+                },
+            ],
+            durable_objects: None,
+            migrations: None,
+            name: "test-target".to_string(),
+            target_type: TargetType::Webpack,
+            webpack_config: None,
+            site: None,
+            vars: None,
+            text_blobs: None,
+            build: None,
+            wasm_modules: None,
+            bindings: None,
+            usage_model: None,
+            limits: None,
+            compatibility_date: None,
+            compatibility_flags: Vec::new(),
+        };
+        assert_eq!(
+            kv::get_namespace_id(&target, "SECOND").unwrap(),
+            "second-id"
+        );
+
+        let err = kv::get_namespace_id(&target, "THIRD").unwrap_err();
+        assert!(err.to_string().contains("FIRST, SECOND"));
+    }
+
     #[test]
     fn it_encodes_slash() {
         assert_eq!(kv::url_encode_key("/slash"), "%2Fslash");
@@ -1,15 +1,24 @@
-use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
 use regex::Regex;
+use toml_edit::{Array, Document, InlineTable, Item, Table, Value};
 
 use crate::commands::kv;
 use crate::http;
-use crate::kv::namespace::create;
+use crate::kv::namespace::{create, namespaces_cache_key};
 use crate::settings::global_user::GlobalUser;
-use crate::settings::toml::{ConfigKvNamespace, KvNamespace, Manifest};
+use crate::settings::toml::{confirm_write, ConfigKvNamespace, KvNamespace, Manifest};
+use crate::terminal::interactive;
 use crate::terminal::message::{Message, StdOut};
+
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     manifest: &Manifest,
+    config_path: &Path,
     is_preview: bool,
+    update_config: bool,
     env: Option<&str>,
     user: &GlobalUser,
     binding: &str,
@@ -30,20 +39,37 @@ pub fn run(
 
     match result {
         Ok(success) => {
-            let namespace = success.result;
+            http::cache::invalidate(user, &namespaces_cache_key(&account_id));
+            let namespace = KvNamespace {
+                binding: binding.to_string(),
+                id: success.result.id,
+            };
             StdOut::success("Success!");
-            println!(
-                "{}",
-                toml_modification_instructions(
-                    KvNamespace {
-                        binding: binding.to_string(),
-                        id: namespace.id,
-                    },
-                    manifest.kv_namespaces.as_ref(),
-                    env,
-                    is_preview,
-                )
-            );
+
+            // the changes to the configuration file are shown and confirmed before they're
+            // written, so that's the question for whoever is at the terminal
+            let update_config =
+                update_config || interactive::is_interactive() || interactive::assume_yes();
+
+            if update_config {
+                add_to_config(config_path, env, &namespace, is_preview)?;
+                StdOut::success(&format!(
+                    "Added binding \"{}\" to {}",
+                    binding,
+                    config_path.display()
+                ));
+            } else {
+                let existing = match env {
+                    Some(env) => manifest
+                        .get_environment(Some(env))?
+                        .and_then(|e| e.kv_namespaces.as_ref()),
+                    None => manifest.kv_namespaces.as_ref(),
+                };
+                println!(
+                    "{}",
+                    toml_modification_instructions(namespace, existing, env, is_preview)
+                );
+            }
         }
         Err(e) => print!("{}", kv::format_error(e)),
     }
@@ -61,6 +87,108 @@ fn validate_binding(binding: &str) -> Result<()> {
     Ok(())
 }
 
+/// Adds `namespace` to the kv_namespaces of the configuration file at `config_path`, or
+/// sets its (preview) ID if the binding is already there, once the changes are confirmed.
+/// toml_edit keeps the formatting and comments of the rest of the file intact.
+fn add_to_config(
+    config_path: &Path,
+    env: Option<&str>,
+    namespace: &KvNamespace,
+    is_preview: bool,
+) -> Result<()> {
+    let contents = fs::read_to_string(config_path)?;
+    let mut doc = contents
+        .parse::<Document>()
+        .map_err(|e| anyhow!("toml_edit failed to parse {}. {}", config_path.display(), e))?;
+
+    let section = match env {
+        Some(env) => &mut doc["env"][env],
+        None => &mut doc.root,
+    };
+    add_namespace(section, namespace, is_preview)?;
+
+    let contents = doc.to_string_in_original_order();
+    confirm_write(config_path, &contents)?;
+    fs::write(config_path, contents)?;
+    Ok(())
+}
+
+fn add_namespace(section: &mut Item, namespace: &KvNamespace, is_preview: bool) -> Result<()> {
+    let id_key = if is_preview { "preview_id" } else { "id" };
+    let table = match section.as_table_mut() {
+        Some(table) => table,
+        None => anyhow::bail!("Expected a table to add kv_namespaces to"),
+    };
+    // both spellings are accepted, so stick to whichever the file already uses
+    let key = if table.contains_key("kv-namespaces") {
+        "kv-namespaces"
+    } else {
+        "kv_namespaces"
+    };
+
+    let item = table.entry(key);
+    match item {
+        // [[kv_namespaces]]
+        Item::ArrayOfTables(tables) => {
+            for i in 0..tables.len() {
+                let table = tables.get_mut(i).expect("index is in bounds");
+                if table.entry("binding").as_str() == Some(namespace.binding.as_str()) {
+                    *table.entry(id_key) = toml_edit::value(namespace.id.as_str());
+                    return Ok(());
+                }
+            }
+            let mut table = Table::new();
+            *table.entry("binding") = toml_edit::value(namespace.binding.as_str());
+            *table.entry(id_key) = toml_edit::value(namespace.id.as_str());
+            tables.append(table);
+        }
+        // kv_namespaces = [{ ... }]
+        Item::Value(Value::Array(namespaces)) => {
+            let mut updated = Array::default();
+            let mut found = false;
+            for existing in namespaces.iter() {
+                let mut existing = existing.clone();
+                if let Some(table) = existing.as_inline_table_mut() {
+                    if table.get("binding").and_then(Value::as_str)
+                        == Some(namespace.binding.as_str())
+                    {
+                        table.remove(id_key);
+                        table.get_or_insert(id_key, namespace.id.as_str());
+                        table.fmt();
+                        found = true;
+                    }
+                }
+                push(&mut updated, existing)?;
+            }
+            if !found {
+                push(&mut updated, inline_namespace(namespace, id_key))?;
+            }
+            *item = Item::Value(Value::Array(updated));
+        }
+        Item::None => {
+            let mut namespaces = Array::default();
+            push(&mut namespaces, inline_namespace(namespace, id_key))?;
+            *item = toml_edit::value(namespaces);
+        }
+        _ => anyhow::bail!("{} in your configuration file should be an array", key),
+    }
+    Ok(())
+}
+
+fn inline_namespace(namespace: &KvNamespace, id_key: &str) -> Value {
+    let mut table = InlineTable::default();
+    table.get_or_insert("binding", namespace.binding.as_str());
+    table.get_or_insert(id_key, namespace.id.as_str());
+    table.fmt();
+    Value::InlineTable(table)
+}
+
+fn push(array: &mut Array, value: Value) -> Result<()> {
+    array
+        .push(value)
+        .map_err(|_| anyhow!("kv_namespaces can only contain tables"))
+}
+
 fn toml_modification_instructions(
     new_namespace: KvNamespace,
     all_namespaces: Option<&Vec<ConfigKvNamespace>>,
@@ -271,6 +399,96 @@ mod tests {
         assert!(!msg.contains("kv_namespaces = ["));
     }
 
+    fn added_to_config(config: &str, env: Option<&str>, is_preview: bool) -> (String, Manifest) {
+        let mut doc = config.parse::<Document>().unwrap();
+        let section = match env {
+            Some(env) => &mut doc["env"][env],
+            None => &mut doc.root,
+        };
+        let namespace = KvNamespace {
+            id: "new_id".to_string(),
+            binding: "FOO".to_string(),
+        };
+        add_namespace(section, &namespace, is_preview).unwrap();
+
+        let config = doc.to_string_in_original_order();
+        let manifest = toml::from_str(&config).unwrap();
+        (config, manifest)
+    }
+
+    #[test]
+    fn it_adds_namespaces_to_the_config() {
+        let (config, manifest) = added_to_config(
+            r#"
+# my worker
+name = "worker"
+type = "javascript"
+"#,
+            None,
+            false,
+        );
+        assert!(config.contains("# my worker"));
+        assert_eq!(
+            manifest.kv_namespaces,
+            Some(vec![ConfigKvNamespace {
+                binding: "FOO".to_string(),
+                id: Some("new_id".to_string()),
+                preview_id: None,
+            }])
+        );
+    }
+
+    #[test]
+    fn it_sets_the_preview_id_of_existing_bindings() {
+        let (_, manifest) = added_to_config(
+            r#"
+name = "worker"
+type = "javascript"
+
+[env.staging]
+kv_namespaces = [
+    { binding = "BAR", id = "bar_id" },
+    { binding = "FOO", id = "foo_id" },
+]
+"#,
+            Some("staging"),
+            true,
+        );
+        let namespaces = manifest.env.unwrap()["staging"]
+            .kv_namespaces
+            .clone()
+            .unwrap();
+        assert_eq!(namespaces.len(), 2);
+        assert_eq!(
+            namespaces[1],
+            ConfigKvNamespace {
+                binding: "FOO".to_string(),
+                id: Some("foo_id".to_string()),
+                preview_id: Some("new_id".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn it_appends_to_arrays_of_tables() {
+        let (_, manifest) = added_to_config(
+            r#"
+name = "worker"
+type = "javascript"
+
+[[kv-namespaces]]
+binding = "BAR"
+id = "bar_id"
+"#,
+            None,
+            false,
+        );
+        let namespaces = manifest.kv_namespaces.unwrap();
+        assert_eq!(namespaces.len(), 2);
+        assert_eq!(namespaces[1].binding, "FOO");
+        assert_eq!(namespaces[1].id.as_deref(), Some("new_id"));
+    }
+
     #[test]
     fn it_can_detect_invalid_binding() {
         let invalid_bindings = vec!["hi there", "1234"];
@@ -1,6 +1,6 @@
 use crate::commands::kv;
 use crate::http;
-use crate::kv::namespace::delete;
+use crate::kv::namespace::{delete, namespaces_cache_key};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::interactive;
@@ -12,10 +12,10 @@ pub fn run(target: &Target, user: &GlobalUser, id: &str, force: bool) -> Result<
     let client = http::cf_v4_client(user)?;
 
     if !force {
-        match interactive::confirm(&format!(
-            "Are you sure you want to delete namespace {}?",
-            id
-        )) {
+        match interactive::confirm_or_fail(
+            &format!("Are you sure you want to delete namespace {}?", id),
+            "pass --force to delete it",
+        ) {
             Ok(true) => (),
             Ok(false) => {
                 StdOut::info(&format!("Not deleting namespace {}", id));
@@ -28,9 +28,11 @@ pub fn run(target: &Target, user: &GlobalUser, id: &str, force: bool) -> Result<
     let msg = format!("Deleting namespace {}", id);
     StdOut::working(&msg);
 
-    let response = delete(client, target.account_id.load()?, id);
+    let account_id = target.account_id.load()?;
+    let response = delete(client, account_id, id);
     match response {
         Ok(_) => {
+            http::cache::invalidate(user, &namespaces_cache_key(account_id));
             StdOut::success("Success");
             StdOut::warn(
                 "Make sure to remove this \"kv-namespace\" entry from your configuration file!",
@@ -1,5 +1,5 @@
 use crate::http;
-use crate::kv::namespace::list;
+use crate::kv::namespace::{list, namespaces_cache_key};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 
@@ -7,12 +7,10 @@ use anyhow::Result;
 
 pub fn run(target: &Target, user: &GlobalUser) -> Result<()> {
     let client = http::cf_v4_client(user)?;
-    let result = list(&client, target);
-    match result {
-        Ok(namespaces) => {
-            println!("{}", serde_json::to_string(&namespaces)?);
-        }
-        Err(e) => anyhow::bail!(e),
-    }
+    let account_id = target.account_id.load()?;
+    let namespaces = http::cache::cached(user, &namespaces_cache_key(account_id), || {
+        list(&client, account_id)
+    })?;
+    println!("{}", serde_json::to_string(&namespaces)?);
     Ok(())
 }
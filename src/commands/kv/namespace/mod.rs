@@ -1,7 +1,9 @@
 mod create;
 mod delete;
 mod list;
+mod transfer;
 
 pub use create::run as create;
 pub use delete::run as delete;
 pub use list::run as list;
+pub use transfer::{copy, export, import, TransferOptions};
@@ -0,0 +1,530 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use futures::{stream, StreamExt, TryStreamExt};
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::kv;
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::kv::bulk::{self, KeyValuePair};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::ui;
+
+// The most keys the API lists at once
+const MAX_BATCH_SIZE: usize = 1000;
+// The API only takes expirations at least this many seconds away
+const MIN_EXPIRATION_SECS: i64 = 60;
+
+/// How a transfer of the keys of a namespace goes about it
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferOptions {
+    /// How many keys are listed, fetched and written at a time
+    pub batch_size: usize,
+    /// How many values are fetched at the same time
+    pub parallelism: usize,
+    /// Start over instead of resuming from the checkpoint of an interrupted transfer
+    pub restart: bool,
+    /// Only transfer the keys starting with this
+    pub prefix: Option<String>,
+}
+
+/// How far a transfer got, saved after every batch so an interrupted transfer can resume
+/// from the batch it stopped at
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+struct Checkpoint {
+    /// The cursor of the next batch of keys, `None` before the first one
+    cursor: Option<String>,
+    /// How many keys were transferred
+    keys: usize,
+    /// How long the export file was after the last complete batch, so that the lines of a
+    /// batch written halfway are cut off. For an import, how far into the file it got
+    #[serde(default)]
+    bytes: u64,
+    /// The prefix of the keys being transferred, which a resumed transfer has to keep
+    #[serde(default)]
+    prefix: Option<String>,
+}
+
+impl Checkpoint {
+    fn load(path: &Path, restart: bool) -> Result<Self> {
+        if restart || !path.exists() {
+            return Ok(Checkpoint::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to read the checkpoint {}, pass --restart to start over: {}",
+                path.display(),
+                e
+            )
+        })
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // written whole then renamed, so that an interruption never leaves half a checkpoint
+        let partial = path.with_extension("partial");
+        fs::write(&partial, serde_json::to_string(self)?)?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KeysPage {
+    result: Vec<ListedKey>,
+    result_info: Option<CursorInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CursorInfo {
+    cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListedKey {
+    name: String,
+    expiration: Option<i64>,
+    metadata: Option<serde_json::Value>,
+}
+
+/// Writes every key of the namespace `id` to `output`, with its value, expiration and
+/// metadata, one JSON object per line in the shape `kv:bulk put` takes. The progress is
+/// kept in `<output>.checkpoint` until the export completes.
+pub fn export(
+    target: &Target,
+    user: &GlobalUser,
+    id: &str,
+    output: &Path,
+    options: TransferOptions,
+) -> Result<()> {
+    let checkpoint_path = append_extension(output, "checkpoint");
+    let mut checkpoint = Checkpoint::load(&checkpoint_path, options.restart)?;
+    let mut file = OpenOptions::new().create(true).write(true).open(output)?;
+    file.set_len(checkpoint.bytes)?;
+    file.seek(SeekFrom::End(0))?;
+
+    transfer(
+        target,
+        user,
+        id,
+        &checkpoint_path,
+        &mut checkpoint,
+        options,
+        |pairs| {
+            let mut lines = String::new();
+            for pair in pairs {
+                lines.push_str(&serde_json::to_string(&pair)?);
+                lines.push('\n');
+            }
+            file.write_all(lines.as_bytes())?;
+            file.sync_data()?;
+            Ok(file.seek(SeekFrom::Current(0))?)
+        },
+    )?;
+
+    StdErr::success(&format!(
+        "Exported {} key(s) to {}",
+        checkpoint.keys,
+        output.display()
+    ));
+    Ok(())
+}
+
+/// Writes every key of the namespace `from` to the namespace `to`, with its value, expiration
+/// and metadata. Keys about to expire are left out, the API refuses them. The progress is
+/// kept in `.wrangler/kv/` until the copy completes.
+pub fn copy(
+    target: &Target,
+    user: &GlobalUser,
+    from: &str,
+    to: &str,
+    options: TransferOptions,
+) -> Result<()> {
+    let checkpoint_path = Path::new(".wrangler")
+        .join("kv")
+        .join(format!("copy-{}-{}.checkpoint", from, to));
+    let mut checkpoint = Checkpoint::load(&checkpoint_path, options.restart)?;
+    let mut expired = 0;
+
+    transfer(
+        target,
+        user,
+        from,
+        &checkpoint_path,
+        &mut checkpoint,
+        options,
+        |pairs| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            let (pairs, gone) = unexpired(pairs, now);
+            expired += gone;
+            if !pairs.is_empty() {
+                bulk::put(target, user, to, pairs, &None)?;
+            }
+            Ok(0)
+        },
+    )?;
+
+    if expired > 0 {
+        StdErr::warn(&format!(
+            "{} key(s) were about to expire, so they weren't copied",
+            expired
+        ));
+    }
+    StdErr::success(&format!(
+        "Copied {} key(s) to {}",
+        checkpoint.keys - expired,
+        to
+    ));
+    Ok(())
+}
+
+/// Puts the keys of `input`, a file written by `export`, into the namespace `id` a batch at a
+/// time, with their expirations and metadata. Keys that expired since the export are left
+/// out. The progress is kept in `.wrangler/kv/` until the import completes.
+pub fn import(
+    target: &Target,
+    user: &GlobalUser,
+    id: &str,
+    input: &Path,
+    options: TransferOptions,
+) -> Result<()> {
+    check_batch_size(options.batch_size)?;
+    let file_name = input
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let checkpoint_path = Path::new(".wrangler")
+        .join("kv")
+        .join(format!("import-{}-{}.checkpoint", id, file_name));
+    let mut checkpoint = Checkpoint::load(&checkpoint_path, options.restart)?;
+    if checkpoint.keys > 0 {
+        StdErr::info(&format!(
+            "Resuming an interrupted import after {} key(s)",
+            checkpoint.keys
+        ));
+    }
+
+    let mut reader = BufReader::new(File::open(input)?);
+    reader.seek(SeekFrom::Start(checkpoint.bytes))?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let spinner = ui::spinner("");
+    let mut expired = 0;
+
+    loop {
+        let (pairs, read) = read_batch(&mut reader, options.batch_size)?;
+        if read == 0 {
+            break;
+        }
+        let count = pairs.len();
+        let (pairs, gone) = unexpired(pairs, now);
+        expired += gone;
+        if !pairs.is_empty() {
+            bulk::put(target, user, id, pairs, &None)?;
+        }
+        checkpoint.bytes += read;
+        checkpoint.keys += count;
+        checkpoint.save(&checkpoint_path)?;
+        spinner.set_message(&format!("{} key(s) imported", checkpoint.keys));
+    }
+
+    spinner.finish_and_clear();
+    if checkpoint_path.exists() {
+        fs::remove_file(&checkpoint_path)?;
+    }
+    if expired > 0 {
+        StdErr::warn(&format!(
+            "{} key(s) expired since the export, so they weren't imported",
+            expired
+        ));
+    }
+    StdErr::success(&format!(
+        "Imported {} key(s) from {}",
+        checkpoint.keys - expired,
+        input.display()
+    ));
+    Ok(())
+}
+
+// Reads the pairs of up to `batch_size` lines, returning them with how many bytes were read
+fn read_batch(reader: &mut impl BufRead, batch_size: usize) -> Result<(Vec<KeyValuePair>, u64)> {
+    let mut pairs = Vec::new();
+    let mut read = 0;
+    let mut line = String::new();
+    while pairs.len() < batch_size {
+        line.clear();
+        let n = reader.read_line(&mut line)?;
+        if n == 0 {
+            break;
+        }
+        read += n as u64;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let pair = serde_json::from_str(&line).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to read a key, each line should be one written by `kv:namespace export`: {}",
+                e
+            )
+        })?;
+        pairs.push(pair);
+    }
+    Ok((pairs, read))
+}
+
+// The pairs that haven't expired at `now`, or won't before the API takes them, and how many
+// were left out
+fn unexpired(pairs: Vec<KeyValuePair>, now: i64) -> (Vec<KeyValuePair>, usize) {
+    let count = pairs.len();
+    let pairs: Vec<KeyValuePair> = pairs
+        .into_iter()
+        .filter(|pair| match pair.expiration {
+            Some(expiration) => expiration >= now + MIN_EXPIRATION_SECS,
+            None => true,
+        })
+        .collect();
+    let expired = count - pairs.len();
+    (pairs, expired)
+}
+
+fn check_batch_size(batch_size: usize) -> Result<()> {
+    if batch_size == 0 || batch_size > MAX_BATCH_SIZE {
+        anyhow::bail!("--batch-size must be between 1 and {}", MAX_BATCH_SIZE)
+    }
+    Ok(())
+}
+
+// Lists the keys of `id` a batch at a time, from where `checkpoint` left off, fetches their
+// values and hands them to `write`, which returns the length of the export it writes to.
+// The checkpoint is saved after every batch and removed once every key is transferred.
+fn transfer<F>(
+    target: &Target,
+    user: &GlobalUser,
+    id: &str,
+    checkpoint_path: &Path,
+    checkpoint: &mut Checkpoint,
+    options: TransferOptions,
+    mut write: F,
+) -> Result<()>
+where
+    F: FnMut(Vec<KeyValuePair>) -> Result<u64>,
+{
+    check_batch_size(options.batch_size)?;
+    if checkpoint.keys > 0 {
+        StdErr::info(&format!(
+            "Resuming an interrupted transfer after {} key(s)",
+            checkpoint.keys
+        ));
+        if checkpoint.prefix != options.prefix {
+            anyhow::bail!(
+                "The interrupted transfer was of {}, pass --restart to start over",
+                match &checkpoint.prefix {
+                    Some(prefix) => format!("the keys starting with {}", prefix),
+                    None => "every key".to_string(),
+                }
+            )
+        }
+    }
+    checkpoint.prefix = options.prefix.clone();
+
+    let client = http::legacy_auth_client(user);
+    let namespace_addr = http::api_url(&format!(
+        "accounts/{}/storage/kv/namespaces/{}",
+        target.account_id.load()?,
+        id
+    ))?;
+    let spinner = ui::spinner("");
+
+    loop {
+        let KeysPage {
+            result: keys,
+            result_info,
+        } = list_keys(
+            &client,
+            &namespace_addr,
+            checkpoint.cursor.as_deref(),
+            options.prefix.as_deref(),
+            options.batch_size,
+        )?;
+        let count = keys.len();
+        // the values of a batch are fetched a few at a time, and kept in the order of the keys
+        let pairs = http::block_on(async {
+            let client = http::legacy_auth_client_async(user);
+            let namespace_addr = &namespace_addr;
+            stream::iter(keys)
+                .map(|key| fetch_pair(&client, namespace_addr, key))
+                .buffered(options.parallelism.max(1))
+                .try_collect::<Vec<_>>()
+                .await
+        })??;
+        checkpoint.bytes = write(pairs)?;
+        checkpoint.keys += count;
+        checkpoint.cursor = result_info
+            .and_then(|info| info.cursor)
+            .filter(|cursor| !cursor.is_empty());
+        spinner.set_message(&format!("{} key(s) transferred", checkpoint.keys));
+
+        if checkpoint.cursor.is_none() {
+            break;
+        }
+        checkpoint.save(checkpoint_path)?;
+    }
+
+    spinner.finish_and_clear();
+    if checkpoint_path.exists() {
+        fs::remove_file(checkpoint_path)?;
+    }
+    Ok(())
+}
+
+fn list_keys(
+    client: &Client,
+    namespace_addr: &str,
+    cursor: Option<&str>,
+    prefix: Option<&str>,
+    limit: usize,
+) -> Result<KeysPage> {
+    let mut query = vec![("limit", limit.to_string())];
+    if let Some(cursor) = cursor {
+        query.push(("cursor", cursor.to_string()));
+    }
+    if let Some(prefix) = prefix {
+        query.push(("prefix", prefix.to_string()));
+    }
+    let res = client
+        .get(&format!("{}/keys", namespace_addr))
+        .query(&query)
+        .send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json()?)
+}
+
+async fn fetch_pair(
+    client: &reqwest::Client,
+    namespace_addr: &str,
+    key: ListedKey,
+) -> Result<KeyValuePair> {
+    let request = client.get(&format!(
+        "{}/values/{}",
+        namespace_addr,
+        kv::url_encode_key(&key.name)
+    ));
+    let res = http::send_logged_async(request).await?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(
+            "Could not get {}: {}",
+            key.name,
+            WranglerError::from_api_response(status, res.text().await?)
+        )
+    }
+    Ok(to_pair(key, res.bytes().await?.to_vec()))
+}
+
+// Values that aren't text are kept as base64
+fn to_pair(key: ListedKey, value: Vec<u8>) -> KeyValuePair {
+    let (value, base64) = match String::from_utf8(value) {
+        Ok(value) => (value, None),
+        Err(e) => (base64::encode(e.as_bytes()), Some(true)),
+    };
+    KeyValuePair {
+        key: key.name,
+        value,
+        expiration: key.expiration,
+        expiration_ttl: None,
+        base64,
+        metadata: key.metadata,
+    }
+}
+
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_saves_and_loads_checkpoints() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("keys.jsonl.checkpoint");
+        assert_eq!(
+            Checkpoint::load(&path, false).unwrap(),
+            Checkpoint::default()
+        );
+
+        let checkpoint = Checkpoint {
+            cursor: Some("abc".to_string()),
+            keys: 2000,
+            bytes: 4096,
+            prefix: Some("user:".to_string()),
+        };
+        checkpoint.save(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path, false).unwrap(), checkpoint);
+        assert_eq!(
+            Checkpoint::load(&path, true).unwrap(),
+            Checkpoint::default()
+        );
+    }
+
+    #[test]
+    fn it_keeps_binary_values_as_base64() {
+        let key = |name: &str| ListedKey {
+            name: name.to_string(),
+            expiration: Some(1700000000),
+            metadata: None,
+        };
+        let text = to_pair(key("text"), b"hello".to_vec());
+        assert_eq!(text.value, "hello");
+        assert_eq!(text.base64, None);
+        assert_eq!(text.expiration, Some(1700000000));
+
+        let binary = to_pair(key("binary"), vec![0xff, 0xfe]);
+        assert_eq!(binary.value, "//4=");
+        assert_eq!(binary.base64, Some(true));
+    }
+
+    #[test]
+    fn it_reads_exports_a_batch_at_a_time() {
+        let export = "{\"key\":\"a\",\"value\":\"1\"}\n\n{\"key\":\"b\",\"value\":\"//4=\",\"base64\":true}\n{\"key\":\"c\",\"value\":\"3\",\"expiration\":100}\n";
+        let mut reader = std::io::Cursor::new(export);
+
+        let (pairs, read) = read_batch(&mut reader, 2).unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[1].base64, Some(true));
+        assert_eq!(read, reader.position());
+
+        let (pairs, _) = read_batch(&mut reader, 2).unwrap();
+        assert_eq!(pairs.len(), 1);
+        let (kept, expired) = unexpired(pairs, 100);
+        assert!(kept.is_empty());
+        assert_eq!(expired, 1);
+
+        assert_eq!(read_batch(&mut reader, 2).unwrap().1, 0);
+        assert!(read_batch(&mut std::io::Cursor::new("not json\n"), 2).is_err());
+    }
+
+    #[test]
+    fn it_names_checkpoints_after_the_export() {
+        assert_eq!(
+            append_extension(Path::new("out/keys.jsonl"), "checkpoint"),
+            PathBuf::from("out/keys.jsonl.checkpoint")
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::login::{AUTH_URL, CLIENT_ID};
-use crate::settings::get_global_config_path;
-use crate::settings::global_user::GlobalUser;
+use crate::settings::global_user::{self, GlobalUser};
+use crate::settings::keychain;
+use crate::settings::{get_global_config_path, read_settings};
 use crate::terminal::message::{Message, StdOut};
 
 use anyhow::Result;
@@ -38,6 +39,21 @@ pub fn run() -> Result<()> {
         has_auth = false;
     }
 
+    // a profile only has its credentials in the configuration file, and the others stay
+    if let Some(profile) = global_user::profile() {
+        let config_path = get_global_config_path();
+        if global_user::remove_profile(&config_path, profile)? {
+            StdOut::success(&format!("Removed the profile {}", profile));
+        } else if has_auth {
+            println!("The profile {} has no credentials in the config file. If you wish to unauthenticate `wrangler`, please unset your environment variables (e.g. \"CF_API_TOKEN\", \"CF_API_KEY\", or \"CF_EMAIL\").", profile);
+        }
+        return Ok(());
+    }
+
+    if let Err(e) = keychain::delete() {
+        log::debug!("Failed to remove the credentials from the keychain: {}", e);
+    }
+
     // Delete configuration file if existent
     let config_path = get_global_config_path();
     if config_path.exists() {
@@ -48,8 +64,15 @@ pub fn run() -> Result<()> {
                 "configuration file"
             }
         };
-        print!("Removing {}..", config_path_str);
-        fs::remove_file(config_path)?;
+        // settings like the proxy aren't credentials, so a file that has some keeps them
+        let settings = read_settings(&config_path);
+        if settings.is_empty() {
+            print!("Removing {}..", config_path_str);
+            fs::remove_file(&config_path)?;
+        } else {
+            print!("Removing the credentials of {}..", config_path_str);
+            fs::write(&config_path, toml::to_string(&settings)?)?;
+        }
         println!(" success!");
     } else if has_auth {
         // (in)correct environment variables are set
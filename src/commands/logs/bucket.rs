@@ -0,0 +1,362 @@
+use std::env;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+use reqwest::blocking::Client;
+use ring::hmac;
+use sha2::{Digest, Sha256};
+use url::form_urlencoded;
+
+use crate::http::{self, SendLogged};
+
+const AWS_ACCESS_KEY_ID: &str = "AWS_ACCESS_KEY_ID";
+const AWS_SECRET_ACCESS_KEY: &str = "AWS_SECRET_ACCESS_KEY";
+
+// SigV4 percent-encodes everything except the unreserved characters of RFC 3986
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+const PATH_SEGMENT: &AsciiSet = &UNRESERVED.remove(b'/');
+
+/// Where a Logpush job writes its archives, parsed from the job's `destination_conf`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Destination {
+    pub host: String,
+    pub region: String,
+    pub bucket: String,
+    /// Everything before the `{DATE}` placeholder of the configured path
+    pub prefix: String,
+    access_key_id: Option<String>,
+    secret_access_key: Option<String>,
+}
+
+impl Destination {
+    /// Parses `r2://bucket/path?account-id=...` and `s3://bucket/path?region=...` destinations.
+    ///
+    /// S3-compatible destinations can name their host with an `endpoint` parameter.
+    pub fn parse(destination_conf: &str) -> Result<Destination> {
+        let (scheme, rest) = match destination_conf.split_once("://") {
+            Some(parts) => parts,
+            None => anyhow::bail!("Unrecognized Logpush destination {}", destination_conf),
+        };
+        let (location, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (bucket, path) = location.split_once('/').unwrap_or((location, ""));
+        let params: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.to_owned())
+        };
+
+        let prefix = match path.find("{DATE}") {
+            Some(index) => path[..index].to_string(),
+            None => path.to_string(),
+        };
+
+        let (host, region) = match scheme {
+            "r2" => match param("account-id") {
+                Some(account_id) => (
+                    format!("{}.r2.cloudflarestorage.com", account_id),
+                    "auto".to_string(),
+                ),
+                None => anyhow::bail!("R2 destination {} has no account-id", destination_conf),
+            },
+            "s3" => {
+                let region = param("region").unwrap_or_else(|| "us-east-1".to_string());
+                let host = param("endpoint")
+                    .map(|endpoint| endpoint.trim_start_matches("https://").to_string())
+                    .unwrap_or_else(|| format!("s3.{}.amazonaws.com", region));
+                (host, region)
+            }
+            _ => anyhow::bail!(
+                "wrangler can only read Logpush archives from R2 and S3-compatible destinations, not {}://",
+                scheme
+            ),
+        };
+
+        Ok(Destination {
+            host,
+            region,
+            bucket: bucket.to_string(),
+            prefix,
+            access_key_id: param("access-key-id"),
+            secret_access_key: param("secret-access-key"),
+        })
+    }
+}
+
+/// Credentials used to read from the bucket. Taken from the standard `AWS_*` environment
+/// variables, falling back to any keys included in the destination itself.
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl Credentials {
+    pub fn new(destination: &Destination) -> Result<Credentials> {
+        let access_key_id = env::var(AWS_ACCESS_KEY_ID)
+            .ok()
+            .or_else(|| destination.access_key_id.clone());
+        let secret_access_key = env::var(AWS_SECRET_ACCESS_KEY)
+            .ok()
+            .or_else(|| destination.secret_access_key.clone());
+
+        match (access_key_id, secret_access_key) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(Credentials {
+                access_key_id,
+                secret_access_key,
+            }),
+            _ => anyhow::bail!(
+                "Reading Logpush archives requires credentials for the {} bucket; set {} and {}",
+                destination.bucket,
+                AWS_ACCESS_KEY_ID,
+                AWS_SECRET_ACCESS_KEY
+            ),
+        }
+    }
+}
+
+/// A minimal, read-only S3 client: just enough to list and fetch Logpush archives.
+pub struct Bucket {
+    client: Client,
+    destination: Destination,
+    credentials: Credentials,
+}
+
+impl Bucket {
+    pub fn new(destination: Destination, credentials: Credentials) -> Bucket {
+        Bucket {
+            client: http::client(),
+            destination,
+            credentials,
+        }
+    }
+
+    /// Lists the keys of every object below the destination's prefix.
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let mut query = vec![
+                ("list-type".to_string(), "2".to_string()),
+                ("prefix".to_string(), self.destination.prefix.clone()),
+            ];
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token".to_string(), token.to_owned()));
+            }
+
+            let body = self.get("", &query)?.text()?;
+            keys.append(&mut xml_values(&body, "Key"));
+
+            continuation_token = xml_values(&body, "NextContinuationToken").pop();
+            if continuation_token.is_none() {
+                break Ok(keys);
+            }
+        }
+    }
+
+    /// Downloads the object stored under `key`.
+    pub fn download(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(self.get(key, &[])?.bytes()?.to_vec())
+    }
+
+    fn get(&self, key: &str, query: &[(String, String)]) -> Result<reqwest::blocking::Response> {
+        let path = if key.is_empty() {
+            format!("/{}", self.destination.bucket)
+        } else {
+            format!("/{}/{}", self.destination.bucket, key)
+        };
+        let path = utf8_percent_encode(&path, PATH_SEGMENT).to_string();
+        let query = canonical_query(query);
+        let url = if query.is_empty() {
+            format!("https://{}{}", self.destination.host, path)
+        } else {
+            format!("https://{}{}?{}", self.destination.host, path, query)
+        };
+
+        let headers = sign(
+            &self.credentials,
+            &self.destination,
+            &path,
+            &query,
+            Utc::now(),
+        );
+        let mut request = self.client.get(&url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        let response = request.send_logged()?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!(
+                "Request to {} failed with {}: {}",
+                url,
+                status,
+                response.text()?
+            );
+        }
+        Ok(response)
+    }
+}
+
+fn canonical_query(query: &[(String, String)]) -> String {
+    let mut encoded: Vec<String> = query
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                utf8_percent_encode(key, UNRESERVED),
+                utf8_percent_encode(value, UNRESERVED)
+            )
+        })
+        .collect();
+    encoded.sort();
+    encoded.join("&")
+}
+
+/// Returns the headers that authenticate a `GET` with AWS Signature Version 4.
+fn sign(
+    credentials: &Credentials,
+    destination: &Destination,
+    canonical_uri: &str,
+    canonical_query: &str,
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex::encode(Sha256::digest(b""));
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+    let canonical_request = format!(
+        "GET\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        canonical_uri,
+        canonical_query,
+        destination.host,
+        payload_hash,
+        amz_date,
+        signed_headers,
+        payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, destination.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = signing_key(
+        &credentials.secret_access_key,
+        &date,
+        &destination.region,
+        "s3",
+    );
+    let key = hmac::Key::new(hmac::HMAC_SHA256, &key);
+    let signature = hex::encode(hmac::sign(&key, string_to_sign.as_bytes()));
+
+    vec![
+        (
+            "Authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                credentials.access_key_id, scope, signed_headers, signature
+            ),
+        ),
+        ("x-amz-content-sha256", payload_hash),
+        ("x-amz-date", amz_date),
+    ]
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    // each part of the scope is signed with the key derived from the one before it
+    let secret = format!("AWS4{}", secret);
+    [date, region, service, "aws4_request"]
+        .iter()
+        .fold(secret.into_bytes(), |key, part| {
+            let key = hmac::Key::new(hmac::HMAC_SHA256, &key);
+            hmac::sign(&key, part.as_bytes()).as_ref().to_vec()
+        })
+}
+
+// ListObjectsV2 answers in XML; the handful of elements we need are never nested.
+fn xml_values(xml: &str, element: &str) -> Vec<String> {
+    let re = Regex::new(&format!("<{0}>(.*?)</{0}>", element)).unwrap();
+    re.captures_iter(xml)
+        .map(|capture| {
+            capture[1]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_r2_destinations() {
+        let destination = Destination::parse(
+            "r2://logs/workers/{DATE}?account-id=abc123&access-key-id=key&secret-access-key=secret",
+        )
+        .unwrap();
+
+        assert_eq!(destination.host, "abc123.r2.cloudflarestorage.com");
+        assert_eq!(destination.region, "auto");
+        assert_eq!(destination.bucket, "logs");
+        assert_eq!(destination.prefix, "workers/");
+        assert_eq!(destination.access_key_id, Some("key".to_string()));
+    }
+
+    #[test]
+    fn it_parses_s3_destinations() {
+        let destination = Destination::parse("s3://logs/{DATE}?region=eu-west-1").unwrap();
+        assert_eq!(destination.host, "s3.eu-west-1.amazonaws.com");
+        assert_eq!(destination.prefix, "");
+
+        let destination =
+            Destination::parse("s3://logs?region=auto&endpoint=https://minio.example.com").unwrap();
+        assert_eq!(destination.host, "minio.example.com");
+
+        assert!(Destination::parse("gs://logs/{DATE}").is_err());
+    }
+
+    #[test]
+    fn it_derives_sigv4_signing_keys() {
+        // https://docs.aws.amazon.com/general/latest/gr/signature-v4-examples.html
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn it_reads_list_objects_responses() {
+        let xml = "<ListBucketResult><Contents><Key>20220101/a&amp;b.log.gz</Key></Contents>\
+                   <Contents><Key>20220101/c.log.gz</Key></Contents>\
+                   <NextContinuationToken>token</NextContinuationToken></ListBucketResult>";
+        assert_eq!(
+            xml_values(xml, "Key"),
+            vec!["20220101/a&b.log.gz", "20220101/c.log.gz"]
+        );
+        assert_eq!(xml_values(xml, "NextContinuationToken"), vec!["token"]);
+    }
+}
@@ -0,0 +1,319 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use prettytable::{Cell, Row, Table};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::commands::stats::StatsFormat;
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdErr};
+
+/// The analytics dataset only goes back this far.
+const RETENTION_DAYS: i64 = 30;
+
+/// A `--filter` given to `wrangler logs errors`, as `key=value`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorFilter {
+    /// `status=error` matches every failed invocation, anything else the exact status
+    /// reported by the analytics API, e.g. `status=scriptThrewException`
+    Status(String),
+}
+
+impl FromStr for ErrorFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('=') {
+            Some(("status", value)) if !value.is_empty() => {
+                Ok(ErrorFilter::Status(value.to_string()))
+            }
+            Some((key, _)) if key != "status" => {
+                anyhow::bail!("Unknown filter \"{}\", expected status=<status>", key)
+            }
+            _ => anyhow::bail!("Invalid filter \"{}\", expected status=<status>", s),
+        }
+    }
+}
+
+/// The failed invocations of a script during one minute
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ErrorEvent {
+    pub datetime: String,
+    pub script_name: String,
+    pub status: String,
+    pub requests: u64,
+    pub errors: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse {
+    data: Option<GraphQLData>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLData {
+    viewer: Viewer,
+}
+
+#[derive(Debug, Deserialize)]
+struct Viewer {
+    accounts: Vec<AccountAnalytics>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountAnalytics {
+    workers_invocations_adaptive: Vec<Invocations>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Invocations {
+    dimensions: InvocationsDimensions,
+    sum: InvocationsSum,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InvocationsDimensions {
+    datetime_minute: String,
+    script_name: String,
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvocationsSum {
+    requests: u64,
+    errors: u64,
+}
+
+impl From<Invocations> for ErrorEvent {
+    fn from(invocations: Invocations) -> Self {
+        ErrorEvent {
+            datetime: invocations.dimensions.datetime_minute,
+            script_name: invocations.dimensions.script_name,
+            status: invocations.dimensions.status,
+            requests: invocations.sum.requests,
+            errors: invocations.sum.errors,
+        }
+    }
+}
+
+// Grouped by minute and status, most recent first
+const ERRORS_QUERY: &str = r#"
+query($accountTag: string, $limit: uint64!, $filter: AccountWorkersInvocationsAdaptiveFilter_InputObject) {
+  viewer {
+    accounts(filter: { accountTag: $accountTag }) {
+      workersInvocationsAdaptive(limit: $limit, filter: $filter, orderBy: [datetimeMinute_DESC]) {
+        dimensions { datetimeMinute scriptName status }
+        sum { requests errors }
+      }
+    }
+  }
+}
+"#;
+
+/// Prints when `script_name` failed between `since` and `until`, and how, from the Workers
+/// analytics. Unlike archived logs these hold no messages, but need no Logpush job.
+#[allow(clippy::too_many_arguments)]
+pub fn errors(
+    user: &GlobalUser,
+    account_id: &str,
+    script_name: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    filters: &[ErrorFilter],
+    limit: u64,
+    format: StatsFormat,
+) -> Result<()> {
+    let until = until.unwrap_or_else(Utc::now);
+    let oldest = Utc::now() - chrono::Duration::days(RETENTION_DAYS);
+    let since = match since {
+        Some(since) if since < oldest => {
+            StdErr::warn(&format!(
+                "Errors are only kept for {} days, showing those since {}",
+                RETENTION_DAYS,
+                oldest.to_rfc3339()
+            ));
+            oldest
+        }
+        Some(since) => since,
+        None => until - chrono::Duration::days(1),
+    };
+    if since >= until {
+        anyhow::bail!("--since needs to be earlier than --until")
+    }
+
+    let events = fetch_errors(user, account_id, script_name, since, until, filters, limit)?;
+    match format {
+        StatsFormat::Json => println!("{}", serde_json::to_string_pretty(&events)?),
+        StatsFormat::Table if events.is_empty() => {
+            StdErr::info(&format!("{} had no errors in this period", script_name))
+        }
+        StatsFormat::Table => print!("{}", table(&events)),
+    }
+    Ok(())
+}
+
+fn fetch_errors(
+    user: &GlobalUser,
+    account_id: &str,
+    script_name: &str,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    filters: &[ErrorFilter],
+    limit: u64,
+) -> Result<Vec<ErrorEvent>> {
+    let client = http::legacy_auth_client(user);
+    let res = client
+        .post(&http::api_url("graphql")?)
+        .json(&json!({
+            "query": ERRORS_QUERY,
+            "variables": {
+                "accountTag": account_id,
+                "limit": limit,
+                "filter": filter(script_name, since, until, filters),
+            }
+        }))
+        .send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+
+    let res: GraphQLResponse = res.json()?;
+    if let Some(errors) = res.errors {
+        let messages: Vec<String> = errors.into_iter().map(|e| e.message).collect();
+        anyhow::bail!("Failed to fetch analytics: {}", messages.join(", "))
+    }
+
+    Ok(res
+        .data
+        .and_then(|data| data.viewer.accounts.into_iter().next())
+        .map(|account| account.workers_invocations_adaptive)
+        .unwrap_or_default()
+        .into_iter()
+        .map(ErrorEvent::from)
+        .collect())
+}
+
+// Without a status filter, every invocation that didn't succeed is an error.
+fn filter(
+    script_name: &str,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    filters: &[ErrorFilter],
+) -> serde_json::Value {
+    let mut filter = json!({
+        "scriptName": script_name,
+        "datetime_geq": since.to_rfc3339(),
+        "datetime_leq": until.to_rfc3339(),
+        "status_neq": "success",
+    });
+
+    let statuses: Vec<&str> = filters
+        .iter()
+        .map(|ErrorFilter::Status(status)| status.as_str())
+        .filter(|status| *status != "error")
+        .collect();
+    if !statuses.is_empty() {
+        filter["status_in"] = json!(statuses);
+    }
+    filter
+}
+
+fn table(events: &[ErrorEvent]) -> Table {
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Time"),
+        Cell::new("Status"),
+        Cell::new("Errors"),
+        Cell::new("Requests"),
+    ]));
+    for event in events {
+        table.add_row(Row::new(vec![
+            Cell::new(&event.datetime),
+            Cell::new(&event.status),
+            Cell::new(&event.errors.to_string()),
+            Cell::new(&event.requests.to_string()),
+        ]));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn it_parses_filters() {
+        assert_eq!(
+            "status=error".parse::<ErrorFilter>().unwrap(),
+            ErrorFilter::Status("error".to_string())
+        );
+        assert!("status=".parse::<ErrorFilter>().is_err());
+        assert!("method=GET".parse::<ErrorFilter>().is_err());
+        assert!("error".parse::<ErrorFilter>().is_err());
+    }
+
+    #[test]
+    fn it_narrows_the_statuses_only_when_asked() {
+        let since = Utc.ymd(2022, 1, 1).and_hms(0, 0, 0);
+        let until = Utc.ymd(2022, 1, 2).and_hms(0, 0, 0);
+
+        let all = filter("api", since, until, &[ErrorFilter::Status("error".into())]);
+        assert_eq!(all["status_neq"], json!("success"));
+        assert!(all.get("status_in").is_none());
+
+        let thrown = filter(
+            "api",
+            since,
+            until,
+            &[ErrorFilter::Status("scriptThrewException".into())],
+        );
+        assert_eq!(thrown["status_in"], json!(["scriptThrewException"]));
+        assert_eq!(thrown["scriptName"], json!("api"));
+    }
+
+    #[test]
+    fn it_reads_error_events() {
+        let res: GraphQLResponse = serde_json::from_value(json!({
+            "data": { "viewer": { "accounts": [{ "workersInvocationsAdaptive": [{
+                "dimensions": {
+                    "datetimeMinute": "2022-01-01T12:03:00Z",
+                    "scriptName": "api",
+                    "status": "scriptThrewException"
+                },
+                "sum": { "requests": 40, "errors": 3 }
+            }] }] } },
+            "errors": null
+        }))
+        .unwrap();
+        let invocations = res
+            .data
+            .unwrap()
+            .viewer
+            .accounts
+            .remove(0)
+            .workers_invocations_adaptive
+            .remove(0);
+
+        let event = ErrorEvent::from(invocations);
+        assert_eq!(event.status, "scriptThrewException");
+        assert_eq!(event.errors, 3);
+        assert_eq!(
+            serde_json::to_value(&event).unwrap()["datetime"],
+            json!("2022-01-01T12:03:00Z")
+        );
+    }
+}
@@ -0,0 +1,358 @@
+/// `wrangler logs` gives access to the trace events that Logpush archived for a Worker,
+/// complementing the real-time `wrangler tail`:
+///     1. wrangler asks the Cloudflare API for the account's `workers_trace_events` Logpush jobs.
+///     2. The job's destination tells wrangler which R2 or S3-compatible bucket to read from.
+///     3. Archives are downloaded as-is, or decompressed, filtered and printed locally.
+/// `wrangler logs errors` instead asks the Workers analytics when a script failed, which needs
+/// no Logpush job but carries no messages.
+pub mod bucket;
+pub mod errors;
+pub mod record;
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use prettytable::{Cell, Row, Table};
+use serde::Deserialize;
+
+use crate::commands::tail::event::TraceEvent;
+use crate::commands::tail::websocket::TailFormat;
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdErr};
+
+use bucket::{Bucket, Credentials, Destination};
+use record::{archive_span, LogpushRecord};
+
+pub const WORKERS_DATASET: &str = "workers_trace_events";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogpushJob {
+    pub id: u64,
+    pub name: Option<String>,
+    pub dataset: Option<String>,
+    pub destination_conf: String,
+    pub enabled: bool,
+    pub last_complete: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListJobsV4ApiResponse {
+    pub result: Vec<LogpushJob>,
+}
+
+/// Which archived events to retrieve, and what to do with them.
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    pub job_id: Option<u64>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub outcomes: Vec<String>,
+    pub methods: Vec<String>,
+    pub search: Option<String>,
+    pub format: TailFormat,
+    /// Save the raw archives into this directory instead of printing their events
+    pub output: Option<PathBuf>,
+}
+
+/// Fetches the account's Logpush jobs for the Workers trace events dataset.
+pub fn fetch_jobs(user: &GlobalUser, account_id: &str) -> Result<Vec<LogpushJob>> {
+    let client = http::legacy_auth_client(user);
+    let addr = http::api_url(&format!("accounts/{}/logpush/jobs", account_id))?;
+
+    let res = client.get(&addr).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    let res: ListJobsV4ApiResponse = res.json()?;
+
+    Ok(res
+        .result
+        .into_iter()
+        .filter(|job| job.dataset.as_deref() == Some(WORKERS_DATASET))
+        .collect())
+}
+
+pub fn list(user: &GlobalUser, account_id: &str) -> Result<()> {
+    let jobs = fetch_jobs(user, account_id)?;
+    if jobs.is_empty() {
+        StdErr::info(&format!(
+            "No Logpush jobs for the {} dataset were found on this account.",
+            WORKERS_DATASET
+        ));
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("ID"),
+        Cell::new("Name"),
+        Cell::new("Enabled"),
+        Cell::new("Last Complete"),
+        Cell::new("Destination"),
+    ]));
+    for job in jobs {
+        let destination = job
+            .destination_conf
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        table.add_row(Row::new(vec![
+            Cell::new(&job.id.to_string()),
+            Cell::new(job.name.as_deref().unwrap_or("")),
+            Cell::new(&job.enabled.to_string()),
+            Cell::new(job.last_complete.as_deref().unwrap_or("never")),
+            Cell::new(&destination),
+        ]));
+    }
+    print!("{}", table);
+    Ok(())
+}
+
+pub fn download(
+    user: &GlobalUser,
+    account_id: &str,
+    script_name: &str,
+    options: DownloadOptions,
+) -> Result<()> {
+    let job = select_job(fetch_jobs(user, account_id)?, options.job_id)?;
+    let destination = Destination::parse(&job.destination_conf)?;
+    let credentials = Credentials::new(&destination)?;
+    let bucket = Bucket::new(destination, credentials);
+
+    StdErr::working(&format!("Listing archives of Logpush job {}", job.id));
+    let keys: Vec<String> = bucket
+        .list()?
+        .into_iter()
+        .filter(|key| in_range(key, &options))
+        .collect();
+    StdErr::info(&format!("Found {} archives", keys.len()));
+
+    for key in keys {
+        if let Some(output) = &options.output {
+            let path = archive_path(output, &key)?;
+            let archive = bucket.download(&key)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, archive)?;
+            StdErr::working(&format!("Saved {}", path.display()));
+            continue;
+        }
+
+        let archive = bucket.download(&key)?;
+        print_archive(&archive, script_name, &options)?;
+    }
+
+    Ok(())
+}
+
+// Keys come from the bucket, so one like `../../.bashrc` must not be written outside `output`.
+fn archive_path(output: &Path, key: &str) -> Result<PathBuf> {
+    let key = Path::new(key);
+    if !key
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        anyhow::bail!(
+            "Refusing to save the archive {} outside of {}",
+            key.display(),
+            output.display()
+        );
+    }
+    Ok(output.join(key))
+}
+
+fn select_job(jobs: Vec<LogpushJob>, job_id: Option<u64>) -> Result<LogpushJob> {
+    match job_id {
+        Some(id) => match jobs.into_iter().find(|job| job.id == id) {
+            Some(job) => Ok(job),
+            None => anyhow::bail!(
+                "Logpush job {} does not exist or does not export {}",
+                id,
+                WORKERS_DATASET
+            ),
+        },
+        None => match jobs.as_slice() {
+            [] => anyhow::bail!(
+                "No Logpush jobs for the {} dataset were found on this account.",
+                WORKERS_DATASET
+            ),
+            [job] => Ok(job.clone()),
+            _ => anyhow::bail!(
+                "This account has several {} Logpush jobs; pick one with --job (see `wrangler logs list`)",
+                WORKERS_DATASET
+            ),
+        },
+    }
+}
+
+// Archives that don't follow Logpush's naming are kept, since there's no telling what they hold.
+fn in_range(key: &str, options: &DownloadOptions) -> bool {
+    match archive_span(key) {
+        Some((start, end)) => {
+            let starts_in_time = options
+                .until
+                .map_or(true, |until| start <= until.naive_utc());
+            let ends_in_time = options.since.map_or(true, |since| end >= since.naive_utc());
+            starts_in_time && ends_in_time
+        }
+        None => true,
+    }
+}
+
+fn print_archive(archive: &[u8], script_name: &str, options: &DownloadOptions) -> Result<()> {
+    let reader = BufReader::new(GzDecoder::new(archive));
+    for line in reader.lines() {
+        let line = line?;
+        let record: LogpushRecord = match serde_json::from_str(&line) {
+            Ok(record) => record,
+            Err(err) => {
+                log::debug!("Skipping unreadable Logpush record: {}", err);
+                continue;
+            }
+        };
+
+        if record.script_name != script_name || !matches(&record, options) {
+            continue;
+        }
+
+        match options.format {
+            TailFormat::Json => println!("{}", line),
+            TailFormat::Pretty => println!("{}", TraceEvent::from(record)),
+        }
+    }
+    Ok(())
+}
+
+fn matches(record: &LogpushRecord, options: &DownloadOptions) -> bool {
+    let timestamp = record.event_timestamp_ms;
+    if let Some(since) = options.since {
+        if timestamp < since.timestamp_millis() {
+            return false;
+        }
+    }
+    if let Some(until) = options.until {
+        if timestamp > until.timestamp_millis() {
+            return false;
+        }
+    }
+
+    if !options.outcomes.is_empty() {
+        let outcome = match record.outcome.as_str() {
+            "ok" => "ok",
+            "canceled" => "canceled",
+            _ => "error",
+        };
+        if !options.outcomes.iter().any(|o| o == outcome) {
+            return false;
+        }
+    }
+
+    let request = record.event.as_ref().and_then(|e| e.request.as_ref());
+    if !options.methods.is_empty() {
+        match request {
+            Some(request)
+                if options
+                    .methods
+                    .iter()
+                    .any(|m| m.eq_ignore_ascii_case(&request.method)) => {}
+            _ => return false,
+        }
+    }
+
+    if let Some(query) = &options.search {
+        let in_logs = record
+            .logs
+            .iter()
+            .any(|log| log.message.to_string().contains(query.as_str()));
+        let in_exceptions = record
+            .exceptions
+            .iter()
+            .any(|e| e.message.contains(query.as_str()));
+        if !in_logs && !in_exceptions {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn options() -> DownloadOptions {
+        DownloadOptions {
+            job_id: None,
+            since: None,
+            until: None,
+            outcomes: Vec::new(),
+            methods: Vec::new(),
+            search: None,
+            format: TailFormat::Json,
+            output: None,
+        }
+    }
+
+    fn record(outcome: &str, method: &str, log: &str) -> LogpushRecord {
+        serde_json::from_value(serde_json::json!({
+            "ScriptName": "my-worker",
+            "Outcome": outcome,
+            "EventTimestampMs": 1640995200000i64,
+            "Event": { "Request": { "URL": "https://example.com/", "Method": method } },
+            "Logs": [{ "Level": "log", "Message": [log] }],
+            "Exceptions": []
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_filters_records() {
+        let mut options = options();
+        options.outcomes = vec!["error".to_string()];
+        options.methods = vec!["post".to_string()];
+        options.search = Some("boom".to_string());
+
+        assert!(matches(&record("exception", "POST", "boom"), &options));
+        assert!(!matches(&record("ok", "POST", "boom"), &options));
+        assert!(!matches(&record("exception", "GET", "boom"), &options));
+        assert!(!matches(&record("exception", "POST", "fine"), &options));
+    }
+
+    #[test]
+    fn it_skips_archives_outside_the_requested_time() {
+        let mut options = options();
+        options.since = Some(Utc.ymd(2022, 1, 1).and_hms(12, 0, 0));
+
+        assert!(!in_range(
+            "20220101/20220101T000000Z_20220101T000030Z_abc.log.gz",
+            &options
+        ));
+        assert!(in_range(
+            "20220101/20220101T115959Z_20220101T120030Z_abc.log.gz",
+            &options
+        ));
+        assert!(in_range("README", &options));
+    }
+
+    #[test]
+    fn it_saves_archives_inside_the_output_directory() {
+        let output = Path::new("logs");
+        assert_eq!(
+            archive_path(output, "20220101/abc.log.gz").unwrap(),
+            output.join("20220101").join("abc.log.gz")
+        );
+        assert!(archive_path(output, "../abc.log.gz").is_err());
+        assert!(archive_path(output, "20220101/../../abc.log.gz").is_err());
+        assert!(archive_path(output, "/etc/abc.log.gz").is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::NaiveDateTime;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::commands::tail::event::{EventItem, ExceptionItem, LogItem, RequestItem, TraceEvent};
+
+/// A single line of a `workers_trace_events` Logpush archive.
+///
+/// Not every field is shown here, only the ones necessary for filtering and Display.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LogpushRecord {
+    pub script_name: String,
+    pub outcome: String,
+    pub event_timestamp_ms: i64,
+    #[serde(default)]
+    pub event: Option<LogpushEvent>,
+    #[serde(default)]
+    pub logs: Vec<LogpushLog>,
+    #[serde(default)]
+    pub exceptions: Vec<LogpushException>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LogpushEvent {
+    pub request: Option<LogpushRequest>,
+    pub cron: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LogpushRequest {
+    #[serde(rename = "URL")]
+    pub url: String,
+    pub method: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LogpushLog {
+    pub level: String,
+    pub message: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LogpushException {
+    pub name: String,
+    pub message: String,
+}
+
+impl From<LogpushRecord> for TraceEvent {
+    fn from(record: LogpushRecord) -> Self {
+        let event = record.event.unwrap_or(LogpushEvent {
+            request: None,
+            cron: None,
+        });
+        TraceEvent {
+            timestamp: record.event_timestamp_ms,
+            outcome: record.outcome,
+            logs: record
+                .logs
+                .into_iter()
+                .map(|log| LogItem {
+                    level: log.level,
+                    message: log.message,
+                })
+                .collect(),
+            exceptions: record
+                .exceptions
+                .into_iter()
+                .map(|exception| ExceptionItem {
+                    name: exception.name,
+                    message: exception.message,
+                    stack: None,
+                })
+                .collect(),
+            event: EventItem {
+                request: event.request.map(|request| RequestItem {
+                    url: request.url,
+                    method: request.method,
+                    cf: None,
+                }),
+                cron: event.cron,
+            },
+        }
+    }
+}
+
+/// The time span covered by an archive, taken from Logpush's
+/// `<start>_<end>_<hash>.log.gz` object naming.
+pub fn archive_span(key: &str) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let re = Regex::new(r"(\d{8}T\d{6}Z)_(\d{8}T\d{6}Z)").unwrap();
+    let captures = re.captures(key)?;
+    let parse = |s: &str| NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ").ok();
+    Some((parse(&captures[1])?, parse(&captures[2])?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_converts_logpush_records_to_trace_events() {
+        let line = r#"{"DispatchNamespace":"","Event":{"RayID":"6d2f","Request":{"URL":"https://example.com/","Method":"GET"},"Response":{"Status":200}},"EventTimestampMs":1640995200000,"EventType":"fetch","Exceptions":[],"Logs":[{"Level":"log","Message":["hello"],"TimestampMs":1640995200001}],"Outcome":"ok","ScriptName":"my-worker","ScriptTags":[]}"#;
+        let record: LogpushRecord = serde_json::from_str(line).unwrap();
+        assert_eq!(record.script_name, "my-worker");
+
+        let event = TraceEvent::from(record);
+        assert_eq!(event.timestamp, 1640995200000);
+        assert_eq!(event.event.request.unwrap().method, "GET");
+        assert_eq!(event.logs.len(), 1);
+    }
+
+    #[test]
+    fn it_reads_the_span_of_an_archive() {
+        let (start, end) =
+            archive_span("workers/20220101/20220101T000000Z_20220101T000030Z_1a2b3c.log.gz")
+                .unwrap();
+        assert_eq!(start.to_string(), "2022-01-01 00:00:00");
+        assert_eq!(end.to_string(), "2022-01-01 00:00:30");
+
+        assert!(archive_span("workers/README").is_none());
+    }
+}
@@ -0,0 +1,215 @@
+use std::convert::Infallible;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::terminal::message::{Message, StdOut};
+
+// The responses served when no fixtures are given, enough to publish workers and sites and
+// to manage KV namespaces and keys
+const BUILT_IN_FIXTURES: &str = include_str!("fixtures.json");
+
+// The path the v4 API is served under, which requests may or may not start with depending on
+// the CF_API_BASE_URL they were made with
+const API_PREFIX: &str = "/client/v4";
+
+/// A recorded response of the API. `*` in `path` matches any one segment of the path of a
+/// request, and queries are ignored.
+#[derive(Clone, Debug, Deserialize)]
+struct Fixture {
+    method: String,
+    path: String,
+    #[serde(default = "default_status")]
+    status: u16,
+    /// Served in the envelope of v4 API responses
+    #[serde(default)]
+    result: Value,
+    result_info: Option<Value>,
+    /// Served as is instead, for the endpoints that don't respond with JSON
+    body: Option<String>,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+impl Fixture {
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        if !self.method.eq_ignore_ascii_case(method.as_str()) {
+            return false;
+        }
+        let mut expected = self.path.trim_matches('/').split('/');
+        let mut actual = path.trim_matches('/').split('/');
+        loop {
+            match (expected.next(), actual.next()) {
+                (None, None) => return true,
+                (Some("*"), Some(_)) => continue,
+                (Some(expected), Some(actual)) if expected == actual => continue,
+                _ => return false,
+            }
+        }
+    }
+
+    fn response(&self) -> Response<Body> {
+        let builder = Response::builder().status(self.status);
+        match &self.body {
+            Some(body) => builder
+                .header("Content-Type", "application/octet-stream")
+                .body(Body::from(body.clone())),
+            None => {
+                let mut envelope = envelope(self.status < 400, json!([]), self.result.clone());
+                if let Some(result_info) = &self.result_info {
+                    envelope["result_info"] = result_info.clone();
+                }
+                builder
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(envelope.to_string()))
+            }
+        }
+        .unwrap()
+    }
+}
+
+fn envelope(success: bool, errors: Value, result: Value) -> Value {
+    json!({
+        "success": success,
+        "errors": errors,
+        "messages": [],
+        "result": result,
+    })
+}
+
+/// Serves the fixtures in `fixtures`, then the built-in ones, at `port` until interrupted, so
+/// that wrangler can be run end to end without credentials or a network by pointing
+/// CF_API_BASE_URL at it.
+pub fn serve(port: u16, fixtures: Option<&Path>) -> Result<()> {
+    let fixtures = Arc::new(load(fixtures)?);
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+
+    StdOut::info(&format!(
+        "Serving {} API fixtures at http://{}",
+        fixtures.len(),
+        addr
+    ));
+    StdOut::info(&format!(
+        "Run wrangler with CF_API_BASE_URL=http://{}{} and any CF_API_TOKEN to use them",
+        addr, API_PREFIX
+    ));
+
+    let make_service = make_service_fn(move |_| {
+        let fixtures = fixtures.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let fixtures = fixtures.clone();
+                async move { Ok::<_, Infallible>(respond(&fixtures, &req)) }
+            }))
+        }
+    });
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(async {
+            let server = Server::try_bind(&addr)?.serve(make_service);
+            server
+                .with_graceful_shutdown(async {
+                    tokio::signal::ctrl_c().await.ok();
+                })
+                .await?;
+            Ok::<_, anyhow::Error>(())
+        })
+}
+
+fn load(fixtures: Option<&Path>) -> Result<Vec<Fixture>> {
+    let mut loaded = Vec::new();
+    if let Some(path) = fixtures {
+        let contents = fs::read_to_string(path)?;
+        let custom: Vec<Fixture> = serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+        loaded.extend(custom);
+    }
+    loaded.extend(serde_json::from_str::<Vec<Fixture>>(BUILT_IN_FIXTURES)?);
+    Ok(loaded)
+}
+
+fn respond(fixtures: &[Fixture], req: &Request<Body>) -> Response<Body> {
+    let path = req.uri().path();
+    let path = path.strip_prefix(API_PREFIX).unwrap_or(path);
+    match fixtures.iter().find(|f| f.matches(req.method(), path)) {
+        Some(fixture) => {
+            StdOut::info(&format!("{} {} {}", req.method(), path, fixture.status));
+            fixture.response()
+        }
+        None => {
+            let message = format!("No fixture for {} {}", req.method(), path);
+            StdOut::warn(&message);
+            let errors = json!([{ "code": 10000, "message": message }]);
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .header("Content-Type", "application/json")
+                .body(Body::from(envelope(false, errors, Value::Null).to_string()))
+                .unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_the_built_in_fixtures() {
+        assert!(!load(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_matches_paths_with_wildcards() {
+        let fixtures = load(None).unwrap();
+        let find = |method: Method, path: &str| {
+            fixtures
+                .iter()
+                .find(|f| f.matches(&method, path))
+                .map(|f| f.path.as_str())
+        };
+
+        assert_eq!(
+            find(Method::PUT, "/accounts/abc/workers/scripts/worker"),
+            Some("/accounts/*/workers/scripts/*")
+        );
+        assert_eq!(
+            find(
+                Method::GET,
+                "accounts/abc/workers/scripts/worker/subdomain/"
+            ),
+            Some("/accounts/*/workers/scripts/*/subdomain")
+        );
+        assert_eq!(find(Method::PATCH, "/accounts/abc/workers/scripts"), None);
+        assert_eq!(find(Method::GET, "/accounts/abc/unknown"), None);
+    }
+
+    #[test]
+    fn it_lets_custom_fixtures_take_precedence() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("fixtures.json");
+        fs::write(
+            &path,
+            r#"[{ "method": "GET", "path": "/accounts", "status": 403, "result": null }]"#,
+        )
+        .unwrap();
+
+        let fixtures = load(Some(&path)).unwrap();
+        let accounts = fixtures
+            .iter()
+            .find(|f| f.matches(&Method::GET, "/accounts"))
+            .unwrap();
+        assert_eq!(accounts.status, 403);
+        assert_eq!(accounts.response().status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -1,26 +1,56 @@
 use std::process::Command;
 
+pub mod api;
+pub mod auth;
+pub mod build;
+pub mod canary;
+pub mod check;
+pub mod compat;
+pub mod completions;
 pub mod config;
+pub mod config_migrate;
+pub mod deployments;
 pub mod dev;
+pub mod doctor;
+pub mod durable_objects;
+pub mod estimate;
+pub mod fmt;
 pub mod generate;
+pub mod git;
+pub mod graph;
+pub mod hooks;
+pub mod import;
 pub mod init;
 pub mod kv;
 pub mod login;
 pub mod logout;
+pub mod logs;
+pub mod mock_api;
+pub mod palette;
 mod preview;
+pub mod previews;
 pub mod publish;
+pub mod publish_report;
+pub mod queues;
+pub mod quota;
 pub mod report;
 pub mod route;
+pub mod scripts;
 pub mod secret;
+pub mod site;
+pub mod stats;
 pub mod subdomain;
 pub mod tail;
+pub mod vars;
+pub mod verify_site;
 pub mod whoami;
+pub mod zone;
 
 pub use self::config::global_config;
 pub use self::preview::run as preview;
 pub use generate::generate;
 pub use init::init;
-pub use publish::publish;
+pub use publish::{publish, publish_built, publish_config, publish_site};
 pub use whoami::whoami;
 
 use anyhow::Result;
@@ -0,0 +1,373 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+use crate::cli::Cli;
+use crate::settings::toml::Manifest;
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+
+// How many invocations are kept in the history, and how many of them the palette offers
+const HISTORY_LIMIT: usize = 100;
+const RECENT_SHOWN: usize = 5;
+const MATCHES_SHOWN: usize = 10;
+
+// The palette only comes up at a terminal, but if its prompts can't be answered anyway
+const PALETTE_INSTEAD: &str = "pass the command to run, see `wrangler --help`";
+
+// The commands the palette offers, with what they're for. Not every command is here,
+// only the ones someone who doesn't use wrangler every day is likely to be looking for.
+const COMMANDS: &[(&str, &str)] = &[
+    ("dev", "Start a local server for developing your worker"),
+    ("publish", "Publish your worker"),
+    ("preview", "Preview your worker in the browser"),
+    ("build", "Build your worker"),
+    ("check", "Check that your configuration would publish"),
+    ("tail", "Stream the logs of your published worker"),
+    ("logs download", "Print archived logs of your worker"),
+    ("secret put", "Add or update a secret of your worker"),
+    ("secret list", "List the secrets of your worker"),
+    ("kv:namespace list", "List your KV namespaces"),
+    ("kv:key list", "List the keys of a KV namespace"),
+    ("kv:key get", "Print the value of a key"),
+    ("kv:key put", "Write a value to a key"),
+    ("route list", "List the routes of your zone"),
+    (
+        "zone inspect",
+        "Show the zone settings that affect your routes",
+    ),
+    ("subdomain", "Show or register your workers.dev subdomain"),
+    ("graph", "Show which of your workers depend on which others"),
+    ("estimate", "Estimate the monthly cost of your worker"),
+    ("whoami", "Show the account you're logged in to"),
+    ("login", "Log in to your Cloudflare account"),
+    ("generate", "Generate a new worker project from a template"),
+    (
+        "init",
+        "Create a configuration file for an existing project",
+    ),
+];
+
+// Commands that don't read the configuration file, so there's nothing to fill in for them
+const GLOBAL_COMMANDS: &[&str] = &["whoami", "login", "generate", "init"];
+// Commands that act on a KV namespace, which `--binding` can pick from the configuration
+const KV_COMMANDS: &[&str] = &["kv:key list", "kv:key get", "kv:key put"];
+// The flags the history keeps the values of, which are names from the configuration file.
+// Every other argument could be a secret, like the value of `kv:key put`.
+const RECORDED_FLAGS: &[&str] = &["--env", "-e", "--binding", "-b"];
+// Subcommands that change or delete something, which are confirmed before they're run
+const DESTRUCTIVE: &[&str] = &["publish", "put", "delete", "prune", "rollback"];
+
+#[derive(Clone, Debug, PartialEq)]
+struct Entry {
+    /// The arguments that follow `wrangler` when this entry is run
+    args: Vec<String>,
+    description: String,
+    /// Whether this entry was run before, in which case its flags are already filled in
+    recent: bool,
+}
+
+/// Lets somebody running `wrangler` without arguments find a command by typing part of its
+/// name, then fills in the flags that can be taken from the configuration file. Returns
+/// the arguments to run wrangler with, or `None` if nothing was picked.
+pub fn pick() -> Result<Option<Vec<String>>> {
+    let mut entries: Vec<Entry> = read_history()
+        .into_iter()
+        .rev()
+        .take(RECENT_SHOWN)
+        .map(|args| Entry {
+            args,
+            description: "recently run".to_string(),
+            recent: true,
+        })
+        .collect();
+    entries.extend(COMMANDS.iter().map(|(command, description)| Entry {
+        args: command.split(' ').map(str::to_string).collect(),
+        description: description.to_string(),
+        recent: false,
+    }));
+
+    let mut query = String::new();
+    let entry = loop {
+        let matches = filter(&entries, &query);
+        if matches.is_empty() {
+            StdOut::warn(&format!("No command matches \"{}\"", query));
+        }
+        for (i, entry) in matches.iter().take(MATCHES_SHOWN).enumerate() {
+            let command = format!("{:<24}", entry.args.join(" "));
+            println!(
+                "{:>3}  {} {}",
+                i + 1,
+                styles::highlight(command),
+                entry.description
+            );
+        }
+
+        let input = interactive::get_user_input(
+            "Type to narrow down the commands, a number to run one, or nothing to quit:",
+            PALETTE_INSTEAD,
+        )?;
+        if input.is_empty() {
+            return Ok(None);
+        }
+        match input.parse::<usize>() {
+            Ok(n) if (1..=matches.len().min(MATCHES_SHOWN)).contains(&n) => {
+                break matches[n - 1].clone()
+            }
+            _ => query = input,
+        }
+    };
+
+    // the history keeps no arguments besides the flags of the configuration, so those of a
+    // recent entry are asked for like those of any other
+    let mut args = entry.args;
+    if !entry.recent {
+        args.extend(config_flags(&args.join(" "))?);
+    }
+    let extra = interactive::get_user_input(
+        &format!(
+            "wrangler {} (add any other arguments, then press enter)",
+            args.join(" ")
+        ),
+        PALETTE_INSTEAD,
+    )?;
+    args.extend(extra.split_whitespace().map(str::to_string));
+
+    let command = format!("wrangler {}", args.join(" "));
+    if is_destructive(&args) {
+        if !interactive::confirm_or_fail(&format!("Run `{}`?", command), PALETTE_INSTEAD)? {
+            return Ok(None);
+        }
+    } else {
+        StdOut::info(&format!("Running `{}`", command));
+    }
+    Ok(Some(args))
+}
+
+// Whether one of the subcommands of `args`, which come before any flag, changes something
+fn is_destructive(args: &[String]) -> bool {
+    args.iter()
+        .take_while(|arg| !arg.starts_with('-'))
+        .any(|arg| DESTRUCTIVE.contains(&arg.as_str()))
+}
+
+// Entries whose name contains the letters of `query` in order, best matches first
+fn filter<'a>(entries: &'a [Entry], query: &str) -> Vec<&'a Entry> {
+    let mut scored: Vec<(usize, &Entry)> = entries
+        .iter()
+        .filter_map(|entry| fuzzy_score(&entry.args.join(" "), query).map(|score| (score, entry)))
+        .collect();
+    // sorting is stable, so recent entries stay ahead of commands that match as well
+    scored.sort_by_key(|(score, _)| *score);
+    scored.into_iter().map(|(_, entry)| entry).collect()
+}
+
+// How many characters the letters of `query` are spread over in `candidate`, or `None`
+// if they aren't all in it in order. Lower is better.
+fn fuzzy_score(candidate: &str, query: &str) -> Option<usize> {
+    let candidate = candidate.to_lowercase();
+    let mut chars = candidate.char_indices();
+    let mut start = None;
+    let mut end = 0;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let (i, _) = chars.find(|(_, c)| *c == wanted)?;
+        start.get_or_insert(i);
+        end = i;
+    }
+    Some(start.map_or(0, |start| end - start))
+}
+
+// Flags of `command` that the configuration file has the only sensible values for, or
+// that are worth asking about because it has several
+fn config_flags(command: &str) -> Result<Vec<String>> {
+    if GLOBAL_COMMANDS.contains(&command) {
+        return Ok(Vec::new());
+    }
+    let config_path = match Manifest::find(&env::current_dir()?, Path::new("wrangler.toml")) {
+        Some(config_path) => config_path,
+        None => return Ok(Vec::new()),
+    };
+    let manifest = Manifest::new(&config_path)?;
+    let mut flags = Vec::new();
+
+    let mut environments: Vec<String> = manifest
+        .env
+        .iter()
+        .flat_map(|e| e.keys().cloned())
+        .collect();
+    environments.sort();
+    let env = choose("Which environment?", "the top level", &environments)?;
+    if let Some(env) = &env {
+        flags.extend(vec!["--env".to_string(), env.clone()]);
+    }
+
+    if KV_COMMANDS.contains(&command) {
+        let target = manifest.get_target(env.as_deref(), false)?;
+        let bindings: Vec<String> = target
+            .kv_namespaces
+            .into_iter()
+            .map(|kv| kv.binding)
+            .collect();
+        let binding = match bindings.as_slice() {
+            [binding] => Some(binding.clone()),
+            _ => choose("Which KV namespace?", "another one", &bindings)?,
+        };
+        if let Some(binding) = binding {
+            flags.extend(vec!["--binding".to_string(), binding]);
+        }
+    }
+
+    Ok(flags)
+}
+
+fn choose(prompt: &str, none: &str, options: &[String]) -> Result<Option<String>> {
+    if options.is_empty() {
+        return Ok(None);
+    }
+    println!("{}", prompt);
+    println!("{:>3}  {}", 0, none);
+    for (i, option) in options.iter().enumerate() {
+        println!("{:>3}  {}", i + 1, option);
+    }
+    loop {
+        let input = interactive::get_user_input("Enter a number:", PALETTE_INSTEAD)?;
+        match input.parse::<usize>() {
+            Ok(0) => return Ok(None),
+            Ok(n) if n <= options.len() => return Ok(Some(options[n - 1].clone())),
+            _ => {}
+        }
+    }
+}
+
+fn history_path() -> PathBuf {
+    Path::new(".wrangler").join("history")
+}
+
+// One JSON array of arguments per line, oldest first. Entries of older versions of wrangler,
+// which kept every argument, are cut down to what `recorded_args` keeps.
+fn read_history() -> Vec<Vec<String>> {
+    let contents = fs::read_to_string(history_path()).unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Vec<String>>(line).ok())
+        .filter_map(|args| {
+            let args: Vec<OsString> = args.into_iter().map(OsString::from).collect();
+            recorded_args(&args)
+        })
+        .collect()
+}
+
+/// Remembers the command wrangler was run with in the project it ran in, for `pick` to offer
+/// it again. Only its subcommands and the flags in `RECORDED_FLAGS` are written down, never
+/// values like those of `kv:key put`. Failing to do so never fails the command.
+pub fn record(args: &[OsString]) {
+    if !Path::new("wrangler.toml").exists() {
+        return;
+    }
+    let args = match recorded_args(args) {
+        Some(args) => args,
+        None => return,
+    };
+    if let Err(e) = append_history(args) {
+        log::info!("Could not record the command in the history: {}", e);
+    }
+}
+
+// The subcommands of `args`, the arguments that follow `wrangler`, with the flags of
+// `RECORDED_FLAGS` and their values. `None` if they aren't a command of this wrangler.
+fn recorded_args(args: &[OsString]) -> Option<Vec<String>> {
+    let matches = Cli::clap()
+        .get_matches_from_safe(std::iter::once(&OsString::from("wrangler")).chain(args))
+        .ok()?;
+    let mut recorded = Vec::new();
+    let mut matches = &matches;
+    while let (name, Some(subcommand)) = matches.subcommand() {
+        recorded.push(name.to_string());
+        matches = subcommand;
+    }
+
+    let args: Vec<String> = args
+        .iter()
+        .map(|a| a.to_string_lossy().to_string())
+        .collect();
+    for pair in args.windows(2) {
+        if RECORDED_FLAGS.contains(&pair[0].as_str()) && !pair[1].starts_with('-') {
+            recorded.extend_from_slice(pair);
+        }
+    }
+    Some(recorded)
+}
+
+fn append_history(args: Vec<String>) -> Result<()> {
+    let mut history = read_history();
+    history.retain(|entry| *entry != args);
+    history.push(args);
+    let start = history.len().saturating_sub(HISTORY_LIMIT);
+
+    fs::create_dir_all(".wrangler")?;
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(history_path())?;
+    for entry in &history[start..] {
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_ranks_tighter_matches_first() {
+        assert_eq!(fuzzy_score("publish", "pub"), Some(2));
+        assert_eq!(fuzzy_score("kv:key put", "kvput"), Some(9));
+        assert_eq!(fuzzy_score("publish", "dev"), None);
+        assert_eq!(fuzzy_score("anything", ""), Some(0));
+
+        let entries: Vec<Entry> = COMMANDS
+            .iter()
+            .map(|(command, description)| Entry {
+                args: command.split(' ').map(str::to_string).collect(),
+                description: description.to_string(),
+                recent: false,
+            })
+            .collect();
+        let matches: Vec<String> = filter(&entries, "put")
+            .iter()
+            .map(|entry| entry.args.join(" "))
+            .collect();
+        assert_eq!(matches, vec!["secret put", "kv:key put"]);
+    }
+
+    #[test]
+    fn it_never_records_values() {
+        let args = |line: &str| -> Vec<OsString> { line.split(' ').map(OsString::from).collect() };
+        assert_eq!(
+            recorded_args(&args("kv:key put --binding DATA token hunter2")).unwrap(),
+            vec!["kv:key", "put", "--binding", "DATA"]
+        );
+        assert_eq!(
+            recorded_args(&args("--env staging secret put API_KEY")).unwrap(),
+            vec!["secret", "put", "--env", "staging"]
+        );
+        assert!(recorded_args(&args("not-a-command")).is_none());
+    }
+
+    #[test]
+    fn it_tells_destructive_commands_apart() {
+        let args = |line: &str| -> Vec<String> { line.split(' ').map(str::to_string).collect() };
+        assert!(is_destructive(&args("kv:key put --binding DATA key value")));
+        assert!(is_destructive(&args("publish --env production")));
+        assert!(!is_destructive(&args("kv:key get --binding DATA put")));
+        assert!(!is_destructive(&args("tail --env production")));
+    }
+}
@@ -0,0 +1,494 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::blocking::Client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::validate_worker_name;
+use crate::deploy::{DeployTarget, DeploymentSet, ZonelessTarget};
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::kv::namespace;
+use crate::settings::get_wrangler_home_dir;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::terminal::ui::Table;
+
+// Script names end up as the left-most label of <script>.<subdomain>.workers.dev
+const MAX_SCRIPT_NAME_LEN: usize = 63;
+const DEFAULT_BRANCHES: &[&str] = &["main", "master"];
+/// Tags a preview with the name of the worker it's a preview of, so `prune` can tell it
+/// from a worker that only happens to be named like one
+pub const PREVIEW_OF_VAR: &str = "WRANGLER_PREVIEW_OF";
+
+/// A worker deployed to workers.dev by `wrangler publish --branch-preview`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BranchPreview {
+    pub account_id: String,
+    pub script_name: String,
+    pub worker: String,
+    pub branch: String,
+    pub published_at: DateTime<Utc>,
+}
+
+/// Turns `target` into a per-branch preview of itself: the script is renamed after the
+/// current git branch and only deployed to workers.dev. Returns the deployments to use
+/// in place of the configured ones.
+pub fn prepare(target: &mut Target) -> Result<(DeploymentSet, BranchPreview)> {
+    let branch = current_branch()?;
+    if default_branches()?.contains(&branch) {
+        anyhow::bail!(
+            "You are on the default branch \"{}\"; run `wrangler publish` without --branch-preview to publish your worker",
+            branch
+        )
+    }
+
+    let script_name = preview_script_name(&target.name, &branch);
+    validate_worker_name(&script_name)?;
+
+    let preview = BranchPreview {
+        account_id: target.account_id.load()?.to_string(),
+        script_name: script_name.clone(),
+        worker: target.name.clone(),
+        branch,
+        published_at: Utc::now(),
+    };
+
+    // routes belong to the production worker, a preview only ever lives on workers.dev
+    let deployments = vec![DeployTarget::Zoneless(ZonelessTarget {
+        account_id: target.account_id.clone(),
+        script_name: script_name.clone(),
+    })];
+    target
+        .vars
+        .get_or_insert_with(Default::default)
+        .insert(PREVIEW_OF_VAR.to_string(), target.name.clone());
+    target.name = script_name;
+
+    Ok((deployments, preview))
+}
+
+/// Remembers a published preview so `wrangler previews prune` can clean it up later.
+pub fn record(preview: BranchPreview) -> Result<()> {
+    let mut previews = load()?;
+    previews.retain(|p| p.script_name != preview.script_name || p.account_id != preview.account_id);
+    previews.push(preview);
+    save(&previews)
+}
+
+/// The previews published to the account from this machine
+pub(crate) fn recorded(account_id: &str) -> Result<Vec<BranchPreview>> {
+    let mut previews = load()?;
+    previews.retain(|p| p.account_id == account_id);
+    Ok(previews)
+}
+
+pub fn list() -> Result<()> {
+    let previews = load()?;
+    if previews.is_empty() {
+        StdOut::info("No branch previews have been published from this machine");
+        return Ok(());
+    }
+
+    let mut table = Table::new(&["Script", "Worker", "Branch", "Published"]);
+    for preview in &previews {
+        table.add_row(vec![
+            preview.script_name.clone(),
+            preview.worker.clone(),
+            preview.branch.clone(),
+            preview.published_at.format("%Y-%m-%d %H:%M").to_string(),
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+pub struct PruneOptions {
+    /// Delete every preview of the worker, whatever the state of its branch
+    pub all: bool,
+    /// Also delete previews of branches merged into the default branch
+    pub merged: bool,
+    /// Also delete previews that haven't been published to for this long
+    pub older_than: Option<Duration>,
+    pub dry_run: bool,
+}
+
+/// Deletes the branch previews of `target` whose branch was deleted, along with their Workers
+/// Sites namespaces. `options` adds more reasons for a preview to go. The previews are listed
+/// and confirmed before anything is deleted.
+///
+/// Previews are found among the scripts of the account, so ones published from CI are
+/// cleaned up too: a script counts as a preview when it was published from this machine,
+/// or when it's named after a branch of this repository and tagged with `PREVIEW_OF_VAR`.
+/// The scripts in `configured`, the names the environments of the manifest publish to, are
+/// never deleted, even when a branch is named like an environment.
+pub fn prune(
+    user: &GlobalUser,
+    target: &Target,
+    configured: &[String],
+    options: PruneOptions,
+) -> Result<()> {
+    let account_id = target.account_id.load()?.to_string();
+    let client = http::legacy_auth_client(user);
+
+    let scripts = list_scripts(&client, &account_id)?;
+    let branches = branches_by_script(&target.name, &branch_refs(&[])?);
+    let merged = if options.merged {
+        let default_branch = default_branch()?;
+        branches_by_script(&target.name, &branch_refs(&["--merged", &default_branch])?)
+    } else {
+        HashMap::new()
+    };
+
+    let mut previews = load()?;
+    let recorded: HashMap<String, String> = previews
+        .iter()
+        .filter(|p| p.account_id == account_id && p.worker == target.name)
+        .map(|p| (p.script_name.clone(), p.branch.clone()))
+        .collect();
+
+    let mut stale = Vec::new();
+    for script in &scripts {
+        if configured.contains(&script.id) {
+            continue;
+        }
+        let branch = match recorded.get(&script.id) {
+            Some(branch) => branch,
+            None => match branches.get(&script.id) {
+                Some(branch) if is_preview_of(&client, &account_id, &script.id, &target.name)? => {
+                    branch
+                }
+                // named like a preview, but nothing says it's one
+                _ => continue,
+            },
+        };
+
+        let age = Utc::now() - script.modified_on;
+        let reason = if options.all {
+            "--all"
+        } else if !branches.contains_key(&script.id) {
+            "branch deleted"
+        } else if merged.contains_key(&script.id) {
+            "branch merged"
+        } else if options.older_than.map_or(false, |d| age > d) {
+            "not published to recently"
+        } else {
+            continue;
+        };
+        stale.push((script.id.clone(), branch.clone(), reason));
+    }
+
+    if stale.is_empty() {
+        StdOut::info("No branch previews to prune");
+    }
+    for (script_name, branch, reason) in &stale {
+        StdOut::info(&format!(
+            "{}{} (branch \"{}\", {})",
+            if options.dry_run { "Would delete " } else { "" },
+            script_name,
+            branch,
+            reason
+        ));
+    }
+
+    let mut deleted = HashSet::new();
+    if !stale.is_empty()
+        && !options.dry_run
+        && !interactive::confirm_or_fail(
+            &format!(
+                "Are you sure you want to delete these {} branch preview(s)?",
+                stale.len()
+            ),
+            "pass --yes to delete them",
+        )?
+    {
+        StdOut::info("Not deleting any branch preview");
+        return Ok(());
+    }
+    for (script_name, _, _) in stale {
+        if options.dry_run {
+            break;
+        }
+        match delete_worker(user, &client, &account_id, &script_name) {
+            Ok(()) => {
+                StdOut::success(&format!("Deleted {}", script_name));
+                deleted.insert(script_name);
+            }
+            Err(e) => StdErr::warn(&format!("Could not delete {}: {}", script_name, e)),
+        }
+    }
+
+    // forget previews that are gone, including ones deleted from the dashboard
+    let existing: HashSet<&str> = scripts.iter().map(|s| s.id.as_str()).collect();
+    previews.retain(|p| {
+        p.account_id != account_id
+            || (existing.contains(p.script_name.as_str()) && !deleted.contains(&p.script_name))
+    });
+    save(&previews)
+}
+
+/// Deletes the script `script_name`, if it's still there, along with the asset namespaces
+/// of the Workers Site it was published with
+pub(crate) fn delete_worker(
+    user: &GlobalUser,
+    client: &Client,
+    account_id: &str,
+    script_name: &str,
+) -> Result<()> {
+    let addr = http::api_url(&format!(
+        "accounts/{}/workers/scripts/{}",
+        account_id, script_name
+    ))?;
+    let res = client.delete(&addr).send_logged_as(Method::DELETE)?;
+    let status = res.status();
+    if !status.is_success() && status != reqwest::StatusCode::NOT_FOUND {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+
+    // previews of a Workers Site get their own asset namespaces, see sites::add_namespace
+    let titles = [
+        format!("__{}-workers_sites_assets", script_name),
+        format!("__{}-workers_sites_assets_preview", script_name),
+    ];
+    let cf_client = http::cf_v4_client(user)?;
+    for namespace in namespace::list(&cf_client, account_id)? {
+        if titles.contains(&namespace.title) {
+            if let Err(e) = namespace::delete(http::cf_v4_client(user)?, account_id, &namespace.id)
+            {
+                return Err(http::api_error(e, None));
+            }
+            http::cache::invalidate(user, &namespace::namespaces_cache_key(account_id));
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct ListScriptsResponse {
+    result: Vec<ScriptResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ScriptResponse {
+    pub id: String,
+    pub modified_on: DateTime<Utc>,
+    /// A hash of the content of the script, which changing its secrets or settings keeps
+    #[serde(default)]
+    pub etag: Option<String>,
+}
+
+pub(crate) fn list_scripts(client: &Client, account_id: &str) -> Result<Vec<ScriptResponse>> {
+    let addr = http::api_url(&format!("accounts/{}/workers/scripts", account_id))?;
+    let res = client.get(&addr).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json::<ListScriptsResponse>()?.result)
+}
+
+#[derive(Debug, Deserialize)]
+struct ScriptSettingsResponse {
+    result: ScriptSettings,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScriptSettings {
+    #[serde(default)]
+    bindings: Vec<serde_json::Value>,
+}
+
+/// Whether `script_name` was published by `wrangler publish --branch-preview` of `worker`
+fn is_preview_of(
+    client: &Client,
+    account_id: &str,
+    script_name: &str,
+    worker: &str,
+) -> Result<bool> {
+    let addr = http::api_url(&format!(
+        "accounts/{}/workers/scripts/{}/settings",
+        account_id, script_name
+    ))?;
+    let res = client.get(&addr).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    let settings = res.json::<ScriptSettingsResponse>()?.result;
+    Ok(is_tagged_as_preview_of(&settings.bindings, worker))
+}
+
+fn is_tagged_as_preview_of(bindings: &[serde_json::Value], worker: &str) -> bool {
+    bindings.iter().any(|binding| {
+        binding["type"] == "plain_text"
+            && binding["name"] == PREVIEW_OF_VAR
+            && binding["text"] == worker
+    })
+}
+
+/// Maps the preview script name each branch would get to the branch's name.
+fn branches_by_script(worker: &str, branches: &[String]) -> HashMap<String, String> {
+    branches
+        .iter()
+        .map(|branch| {
+            // remote-tracking branches count too, CI rarely has local ones
+            let name = branch.strip_prefix("origin/").unwrap_or(branch);
+            (preview_script_name(worker, name), name.to_string())
+        })
+        .collect()
+}
+
+fn branch_refs(filter: &[&str]) -> Result<Vec<String>> {
+    let mut args = vec!["for-each-ref", "--format=%(refname:short)"];
+    args.extend_from_slice(filter);
+    args.extend_from_slice(&["refs/heads", "refs/remotes/origin"]);
+
+    Ok(git(&args)?
+        .lines()
+        .filter(|r| *r != "origin/HEAD" && *r != "origin")
+        .map(String::from)
+        .collect())
+}
+
+fn preview_script_name(worker: &str, branch: &str) -> String {
+    let mut slug = String::new();
+    for c in branch.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-');
+
+    let mut name = format!("{}-{}", worker, slug);
+    name.truncate(MAX_SCRIPT_NAME_LEN);
+    name.trim_end_matches('-').to_string()
+}
+
+fn current_branch() -> Result<String> {
+    let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"])?;
+    if branch == "HEAD" {
+        anyhow::bail!("--branch-preview needs a checked out branch, but HEAD is detached")
+    }
+    Ok(branch)
+}
+
+fn default_branches() -> Result<Vec<String>> {
+    // origin/HEAD points at the remote's default branch when the repo was cloned
+    if let Ok(head) = git(&["symbolic-ref", "--short", "refs/remotes/origin/HEAD"]) {
+        if let Some(branch) = head.strip_prefix("origin/") {
+            return Ok(vec![branch.to_string()]);
+        }
+    }
+    Ok(DEFAULT_BRANCHES.iter().map(|b| b.to_string()).collect())
+}
+
+/// The ref that branches are merged into, preferring the remote's view of it.
+fn default_branch() -> Result<String> {
+    for branch in default_branches()? {
+        for candidate in &[format!("origin/{}", branch), branch] {
+            if git(&["rev-parse", "--verify", "--quiet", candidate]).is_ok() {
+                return Ok(candidate.to_string());
+            }
+        }
+    }
+    anyhow::bail!(
+        "Could not find the default branch of this repository to check for merged branches"
+    )
+}
+
+pub(crate) fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git").args(args).output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn previews_path() -> PathBuf {
+    get_wrangler_home_dir().join("previews.json")
+}
+
+fn load() -> Result<Vec<BranchPreview>> {
+    let path = previews_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn save(previews: &[BranchPreview]) -> Result<()> {
+    let path = previews_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(previews)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_derives_script_names_from_branches() {
+        assert_eq!(
+            preview_script_name("myworker", "feature/Login"),
+            "myworker-feature-login"
+        );
+        assert_eq!(
+            preview_script_name("myworker", "fix--typo_"),
+            "myworker-fix-typo"
+        );
+
+        let long = preview_script_name("myworker", &"a-".repeat(50));
+        assert!(long.len() <= MAX_SCRIPT_NAME_LEN);
+        assert!(!long.ends_with('-'));
+    }
+
+    #[test]
+    fn it_maps_local_and_remote_branches_to_scripts() {
+        let branches = vec!["feature/login".to_string(), "origin/fix-typo".to_string()];
+        let by_script = branches_by_script("myworker", &branches);
+        assert_eq!(
+            by_script.get("myworker-feature-login").map(String::as_str),
+            Some("feature/login")
+        );
+        assert_eq!(
+            by_script.get("myworker-fix-typo").map(String::as_str),
+            Some("fix-typo")
+        );
+    }
+
+    #[test]
+    fn it_only_takes_scripts_tagged_with_the_worker_for_previews() {
+        let tagged = serde_json::json!([
+            { "type": "plain_text", "name": "WRANGLER_GIT_SHA", "text": "abc123" },
+            { "type": "plain_text", "name": PREVIEW_OF_VAR, "text": "myworker" },
+        ]);
+        let tagged = tagged.as_array().unwrap();
+        assert!(is_tagged_as_preview_of(tagged, "myworker"));
+        assert!(!is_tagged_as_preview_of(tagged, "otherworker"));
+
+        let untagged = serde_json::json!([
+            { "type": "plain_text", "name": "WRANGLER_GIT_SHA", "text": "abc123" },
+        ]);
+        assert!(!is_tagged_as_preview_of(
+            untagged.as_array().unwrap(),
+            "myworker"
+        ));
+    }
+}
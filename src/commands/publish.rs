@@ -2,20 +2,24 @@ use std::env;
 use std::path::Path;
 
 use anyhow::Result;
-use indicatif::{ProgressBar, ProgressStyle};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
 
-use crate::build::build_target;
-use crate::deploy::{self, DeploymentSet};
-use crate::http::{self, Feature};
+use crate::build::cache::{BuildCache, CACHE_DIR};
+use crate::build::{self, build_target, Build};
+use crate::commands::{publish_report, subdomain, zone};
+use crate::deploy::{self, DeployTarget, DeploymentSet};
+use crate::error::WranglerError;
+use crate::http::{self, CloudflareApi, Feature, HttpApi, SendLogged};
 use crate::kv::bulk;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::migrations::{MigrationTag, Migrations};
 use crate::settings::toml::Target;
-use crate::sites;
+use crate::sites::{self, AssetManifest};
 use crate::terminal::emoji;
-use crate::terminal::message::{Message, Output, StdErr, StdOut};
+use crate::terminal::message::{self, Message, Output, StdErr, StdOut};
+use crate::terminal::progress::{self, Phase};
+use crate::terminal::ui;
 use crate::upload;
 
 #[derive(Serialize, Deserialize, Default)]
@@ -31,33 +35,129 @@ pub fn publish(
     target: &mut Target,
     deployments: DeploymentSet,
     out: Output,
-) -> Result<()> {
+    analyze: bool,
+    no_cache: bool,
+    if_changed: bool,
+) -> Result<PublishOutput> {
+    publish_report::begin(&target.name);
     validate_target_required_fields_present(target)?;
+    check_deployments(user, target, &deployments)?;
+    let api = HttpApi::new(user);
 
-    let run_deploy = |target: &Target| match deploy::deploy(user, &deployments) {
-        Ok(results) => {
-            build_output_message(results, target.name.clone(), out);
-            Ok(())
-        }
-        Err(e) => Err(e),
+    build_script(target, no_cache)?;
+    if !progress::is_json() {
+        build::warn_about_unused_config(target, &route_patterns(&deployments));
+    }
+    upload_and_deploy(&api, user, target, &deployments, out, analyze, if_changed)
+}
+
+/// Publishes the last build of the script to `target` without building it again, for
+/// publishing one build to several environments
+pub fn publish_built(
+    user: &GlobalUser,
+    target: &mut Target,
+    deployments: DeploymentSet,
+    out: Output,
+    analyze: bool,
+    if_changed: bool,
+) -> Result<PublishOutput> {
+    publish_report::begin(&target.name);
+    validate_target_required_fields_present(target)?;
+    check_deployments(user, target, &deployments)?;
+    let api = HttpApi::new(user);
+    upload_and_deploy(&api, user, target, &deployments, out, analyze, if_changed)
+}
+
+fn upload_and_deploy(
+    api: &HttpApi,
+    user: &GlobalUser,
+    target: &mut Target,
+    deployments: &DeploymentSet,
+    out: Output,
+    analyze: bool,
+    if_changed: bool,
+) -> Result<PublishOutput> {
+    if let Some(build_config) = &target.build {
+        build_config.verify_upload_dir()?;
+    }
+    set_migration_tag(user, target)?;
+
+    let asset_manifest = match &target.site {
+        Some(_) => Some(upload_buckets(api, user, target)?),
+        None => None,
     };
+    // Next, upload and deploy the worker with the updated asset_manifest
+    publish_script(api, user, target, asset_manifest, analyze, if_changed)?;
+    deploy_and_report(api, user, target, deployments, out)
+}
 
-    // Build the script before uploading and log build result
-    let build_result = build_target(target);
+/// Syncs the bucket of the site of `target` and uploads the last build of its script again
+/// with the asset manifest of the bucket, without building it first, for updates to the
+/// content of a site alone
+pub fn publish_site(
+    user: &GlobalUser,
+    target: &mut Target,
+    deployments: DeploymentSet,
+    out: Output,
+    analyze: bool,
+) -> Result<PublishOutput> {
+    publish_report::begin(&target.name);
+    if target.site.is_none() {
+        anyhow::bail!(
+            "{} has no [site] to publish the content of, leave out --site-only to publish it",
+            target.name
+        )
+    }
+    validate_target_required_fields_present(target)?;
+    check_deployments(user, target, &deployments)?;
+    if let Some(build_config) = &target.build {
+        build_config.verify_upload_dir()?;
+    }
+    set_migration_tag(user, target)?;
+
+    let api = HttpApi::new(user);
+    let asset_manifest = upload_buckets(&api, user, target)?;
+    StdErr::working("Publishing the last build of the script with the new asset manifest");
+    publish_script(&api, user, target, Some(asset_manifest), analyze, false)?;
+    deploy_and_report(&api, user, target, &deployments, out)
+}
+
+// Builds the script before uploading and logs the build result, unless the last build of
+// the same inputs can be restored
+fn build_script(target: &Target, no_cache: bool) -> Result<()> {
+    let build_cache = if no_cache {
+        None
+    } else {
+        BuildCache::new(target)?
+    };
+    let build_result = progress::phase(Phase::Build, || match &build_cache {
+        Some(cache) if cache.restore()? => Ok(format!(
+            "Nothing changed since the last build, restored it from {}",
+            CACHE_DIR
+        )),
+        Some(cache) => {
+            let msg = build_target(target)?;
+            // a build that can't be cached can still be published
+            if let Err(e) = cache.store() {
+                log::info!("failed to cache the build: {}", e);
+            }
+            Ok(msg)
+        }
+        None if no_cache => Build::new(target).without_cache().run(),
+        None => build_target(target),
+    });
     match build_result {
         Ok(msg) => {
             StdErr::success(&msg);
             Ok(())
         }
-        Err(e) => Err(e),
-    }?;
-
-    if let Some(build_config) = &target.build {
-        build_config.verify_upload_dir()?;
+        Err(e) => Err(WranglerError::tag(e, WranglerError::Build)),
     }
+}
 
+fn set_migration_tag(user: &GlobalUser, target: &mut Target) -> Result<()> {
     if target.migrations.is_some() {
-        // Can't do this in the if below, since that one takes a mutable borrow on target
+        // the tag is fetched before the mutable borrow on target below
         let client = http::legacy_auth_client(user);
         let script_migration_tag = get_migration_tag(&client, target)?;
 
@@ -66,55 +166,182 @@ pub fn publish(
             Migrations::List { script_tag, .. } => *script_tag = script_migration_tag,
         };
     }
+    Ok(())
+}
 
-    if let Some(site_config) = &target.site {
-        let path = &site_config.bucket.clone();
-        validate_bucket_location(path)?;
-
-        let site_namespace = sites::add_namespace(user, target, false)?;
-
-        let (to_upload, asset_manifest) = sites::sync(target, user, &site_namespace.id, path)?;
+/// Uploads the files of the bucket of the site of `target` that changed to its namespace,
+/// returning the asset manifest to upload the script with
+fn upload_buckets(api: &HttpApi, user: &GlobalUser, target: &Target) -> Result<AssetManifest> {
+    let site_config = target.site.as_ref().expect("the target has a site");
+    let path = &site_config.bucket.clone();
+    let integrity = site_config.integrity.unwrap_or_default();
+    validate_bucket_location(path)?;
+
+    let site_namespace = sites::add_namespace(user, target, false)?;
+
+    let (mut to_upload, asset_manifest) = progress::phase(Phase::Sync, || {
+        sites::sync(target, user, &site_namespace.id, path)
+    })?;
+
+    if integrity {
+        let secret = env::var(sites::INTEGRITY_SECRET_VAR).ok();
+        if secret.is_none() {
+            StdErr::warn(&format!(
+                "{} isn't set, so the integrity manifest won't be signed",
+                sites::INTEGRITY_SECRET_VAR
+            ));
+        }
+        let integrity = sites::IntegrityManifest::new(path, &asset_manifest, secret.as_deref())?;
+        to_upload.push(integrity.to_pair()?);
+    }
 
-        // First, upload all existing files in bucket directory
-        StdErr::working("Uploading site files");
-        let upload_progress_bar = if to_upload.len() > bulk::BATCH_KEY_MAX {
-            let upload_progress_bar = ProgressBar::new(to_upload.len() as u64);
-            upload_progress_bar
-                .set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
-            Some(upload_progress_bar)
-        } else {
-            None
-        };
+    // First, upload all existing files in bucket directory
+    StdErr::working("Uploading site files");
+    let upload_report = sites::UploadReport::new(&to_upload);
+    let upload_progress_bar = if to_upload.len() > bulk::BATCH_KEY_MAX && !progress::is_json() {
+        Some(ui::bar(to_upload.len() as u64))
+    } else {
+        None
+    };
 
-        bulk::put(
+    // what was written is kept track of, so an interrupted upload can pick up from there
+    let journal = sites::UploadJournal::open(&site_namespace.id);
+    progress::phase(Phase::UploadAssets, || {
+        bulk::put_with(
+            api,
             target,
-            user,
             &site_namespace.id,
             to_upload,
             &upload_progress_bar,
-        )?;
+            |written| journal.record(written),
+        )
+    })?;
+    journal.finish()?;
 
-        if let Some(pb) = upload_progress_bar {
-            pb.finish_with_message("Done Uploading");
-        }
+    if let Some(pb) = upload_progress_bar {
+        pb.finish_with_message("Done Uploading");
+    }
+    if !progress::is_json() {
+        upload_report.print();
+    }
+    Ok(asset_manifest)
+}
 
-        let upload_client = http::featured_legacy_auth_client(user, Feature::Sites);
+// The script of a site is uploaded as part of Workers Sites
+fn publish_script(
+    api: &HttpApi,
+    user: &GlobalUser,
+    target: &Target,
+    asset_manifest: Option<AssetManifest>,
+    analyze: bool,
+    if_changed: bool,
+) -> Result<()> {
+    progress::phase(Phase::UploadScript, || match asset_manifest {
+        Some(asset_manifest) => upload::script(
+            &HttpApi::featured(user, Feature::Sites),
+            target,
+            Some(asset_manifest),
+            analyze,
+            if_changed,
+        ),
+        None => upload::script(api, target, None, analyze, if_changed),
+    })
+}
 
-        // Next, upload and deploy the worker with the updated asset_manifest
-        upload::script(&upload_client, target, Some(asset_manifest))?;
+/// Publishes the routes, workers.dev subdomain, schedules and bindings of `target` to its
+/// script without building or uploading the script again, which has to be published already
+pub fn publish_config(
+    user: &GlobalUser,
+    target: &Target,
+    deployments: DeploymentSet,
+    out: Output,
+) -> Result<PublishOutput> {
+    publish_report::begin(&target.name);
+    validate_target_required_fields_present(target)?;
+    check_deployments(user, target, &deployments)?;
 
-        run_deploy(target)?;
-    } else {
-        let upload_client = http::legacy_auth_client(user);
+    let api = HttpApi::new(user);
+    StdErr::working("Updating the bindings of the script");
+    progress::phase(Phase::UploadScript, || upload::settings(&api, target))?;
+    deploy_and_report(&api, user, target, &deployments, out)
+}
 
-        upload::script(&upload_client, target, None)?;
-        run_deploy(target)?;
+// Fails before anything is uploaded when the deployments can't work out
+fn check_deployments(
+    user: &GlobalUser,
+    target: &Target,
+    deployments: &[DeployTarget],
+) -> Result<()> {
+    // fail before building and uploading if there's nowhere to publish to on workers.dev
+    if deployments
+        .iter()
+        .any(|deployment| matches!(deployment, DeployTarget::Zoneless(_)))
+    {
+        subdomain::ensure_subdomain(target.account_id.load()?, user)?;
     }
 
+    // the API only fails with an opaque error when creating a route outside of its zone,
+    // which is after the script was uploaded
+    for deployment in deployments {
+        if let DeployTarget::Zoned(zoned) = deployment {
+            zone::check_routes(&zoned.zone_id, &zoned.routes, user)?;
+            // tokens without access to DNS can still publish, records that couldn't be
+            // created are warned about on their own
+            if let Err(e) = zone::ensure_dns_records(&zoned.zone_id, &zoned.routes, user) {
+                log::info!("could not check the DNS records of the routes: {}", e);
+            }
+            // nor do they need to read page rules
+            if let Err(e) = zone::preflight(&zoned.zone_id, &zoned.routes, user) {
+                log::info!("could not check what else applies to the routes: {}", e);
+            }
+        }
+    }
     Ok(())
 }
 
-fn build_output_message(deploy_results: deploy::DeployResults, target_name: String, out: Output) {
+fn deploy_and_report(
+    api: &impl CloudflareApi,
+    user: &GlobalUser,
+    target: &Target,
+    deployments: &[DeployTarget],
+    out: Output,
+) -> Result<PublishOutput> {
+    // the script is live by now, so a failure here leaves it published without its routes
+    let results = progress::phase(Phase::Deploy, || {
+        deploy::deploy_with(api, user, deployments)
+    })
+    .map_err(|e| {
+        e.context(format!(
+            "{} was uploaded but not fully deployed. Once the problem is fixed, finish the deploy with `wrangler publish --config-only`, with the same --env if any",
+            target.name
+        ))
+    })?;
+    let output = build_output_message(results, target.name.clone(), out);
+    publish_report::published(target, &output);
+    let routes = route_patterns(deployments);
+    if routes.len() > 1 && out == Output::PlainText && !message::is_quiet() {
+        deploy::print_route_health(&routes);
+    }
+    Ok(output)
+}
+
+/// The route patterns of every zone the deployments publish to
+pub(crate) fn route_patterns(deployments: &[DeployTarget]) -> Vec<String> {
+    deployments
+        .iter()
+        .filter_map(|deployment| match deployment {
+            DeployTarget::Zoned(zoned) => Some(zoned.routes.iter().map(|r| r.pattern.clone())),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+fn build_output_message(
+    deploy_results: deploy::DeployResults,
+    target_name: String,
+    out: Output,
+) -> PublishOutput {
     let deploy::DeployResults { urls, schedules } = deploy_results;
 
     let mut msg = "Successfully published your script ".to_owned();
@@ -125,15 +352,23 @@ fn build_output_message(deploy_results: deploy::DeployResults, target_name: Stri
         msg.push_str(&format!("with this schedule\n {}\n", schedules.join("\n ")));
     }
 
+    if out == Output::PlainText && message::is_quiet() {
+        // the URLs are all a quiet publish prints, so scripts can pick them up
+        for url in &urls {
+            StdOut::message(url);
+        }
+    }
     StdErr::success(&msg);
+    let output = PublishOutput {
+        success: true,
+        name: target_name,
+        urls,
+        schedules,
+    };
     if out == Output::Json {
-        StdOut::as_json(&PublishOutput {
-            success: true,
-            name: target_name,
-            urls,
-            schedules,
-        });
+        StdOut::as_json(&output);
     }
+    output
 }
 
 // We don't want folks setting their bucket to the top level directory,
@@ -165,7 +400,7 @@ pub fn validate_bucket_location(bucket: &Path) -> Result<()> {
     Ok(())
 }
 
-fn validate_target_required_fields_present(target: &Target) -> Result<()> {
+pub(crate) fn validate_target_required_fields_present(target: &Target) -> Result<()> {
     let mut missing_fields = Vec::new();
 
     if target.name.is_empty() {
@@ -189,13 +424,13 @@ fn validate_target_required_fields_present(target: &Target) -> Result<()> {
     };
 
     if !missing_fields.is_empty() {
-        anyhow::bail!(
+        anyhow::bail!(WranglerError::Config(format!(
             "{} Your configuration file is missing the {} {:?} which {} required to publish your worker!",
             emoji::WARN,
             field_pluralization,
             missing_fields,
             is_are,
-        );
+        )));
     };
 
     Ok(())
@@ -208,12 +443,12 @@ fn get_migration_tag(client: &Client, target: &Target) -> Result<MigrationTag, a
     // endpoint, we could use that here instead of listing all of the scripts. Listing isn't too bad
     // today though, as most accounts are limited to 30 scripts anyways.
 
-    let addr = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+    let addr = http::api_url(&format!(
+        "accounts/{}/workers/scripts",
         target.account_id.load()?
-    );
+    ))?;
 
-    let res: ListScriptsV4ApiResponse = client.get(&addr).send()?.json()?;
+    let res: ListScriptsV4ApiResponse = client.get(&addr).send_logged()?.json()?;
 
     let tag = match res.result.into_iter().find(|s| s.id == target.name) {
         Some(ScriptResponse {
@@ -0,0 +1,288 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+use crate::commands::publish::PublishOutput;
+use crate::http;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::progress::{self, Phase, ProgressEvent};
+
+static REPORT: OnceCell<Mutex<Report>> = OnceCell::new();
+
+/// What `wrangler publish --report` writes, for CI dashboards and audits
+#[derive(Debug, Serialize)]
+struct Report {
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    elapsed_ms: u128,
+    workers: Vec<WorkerReport>,
+    #[serde(skip)]
+    started: Instant,
+}
+
+#[derive(Debug, Serialize)]
+struct WorkerReport {
+    name: String,
+    /// Whether the worker got as far as its routes and schedules being deployed
+    published: bool,
+    urls: Vec<String>,
+    schedules: Vec<String>,
+    assets_uploaded: usize,
+    /// `None` when the script wasn't uploaded, like with `--if-changed` or `--config-only`
+    script_bytes: Option<u64>,
+    bindings: Vec<BindingReport>,
+    phases: Vec<PhaseReport>,
+    elapsed_ms: u128,
+    /// The `cf-ray` IDs of the responses of the API, to look requests up by with support
+    request_ids: Vec<String>,
+    #[serde(skip)]
+    started: Instant,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct BindingReport {
+    #[serde(rename = "type")]
+    binding_type: String,
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct PhaseReport {
+    phase: Phase,
+    success: bool,
+    elapsed_ms: u128,
+}
+
+impl WorkerReport {
+    fn new(name: &str) -> Self {
+        WorkerReport {
+            name: name.to_string(),
+            published: false,
+            urls: Vec::new(),
+            schedules: Vec::new(),
+            assets_uploaded: 0,
+            script_bytes: None,
+            bindings: Vec::new(),
+            phases: Vec::new(),
+            elapsed_ms: 0,
+            request_ids: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+
+    fn add(&mut self, events: Vec<ProgressEvent>, request_ids: Vec<String>) {
+        for event in events {
+            match event {
+                ProgressEvent::PhaseCompleted {
+                    phase,
+                    success,
+                    elapsed_ms,
+                } => self.phases.push(PhaseReport {
+                    phase,
+                    success,
+                    elapsed_ms,
+                }),
+                ProgressEvent::Uploaded {
+                    phase: Phase::UploadAssets,
+                    files,
+                    ..
+                } => self.assets_uploaded = files,
+                ProgressEvent::Uploaded {
+                    phase: Phase::UploadScript,
+                    bytes,
+                    ..
+                } => self.script_bytes = Some(bytes),
+                _ => {}
+            }
+        }
+        self.request_ids.extend(request_ids);
+        self.elapsed_ms = self.started.elapsed().as_millis();
+    }
+}
+
+/// Runs `publish`, writing the report of what it did to `path` whether it succeeds or not
+pub fn with_report(path: Option<&Path>, publish: impl FnOnce() -> Result<()>) -> Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => return publish(),
+    };
+    progress::record();
+    http::record_request_ids();
+    let _ = REPORT.set(Mutex::new(Report {
+        success: false,
+        error: None,
+        elapsed_ms: 0,
+        workers: Vec::new(),
+        started: Instant::now(),
+    }));
+
+    let result = publish();
+    // the report not being written doesn't undo the publish
+    if let Err(e) = write(path, &result) {
+        StdErr::warn(&format!(
+            "Failed to write the report to {}: {}",
+            path.display(),
+            e
+        ));
+    }
+    result
+}
+
+/// Starts the report of the worker `name` publishes as, when there's a report
+pub(crate) fn begin(name: &str) {
+    if let Some(report) = REPORT.get() {
+        let mut report = report.lock().unwrap();
+        collect(&mut report);
+        report.workers.push(WorkerReport::new(name));
+    }
+}
+
+/// Completes the report of the worker last begun with where `target` was published to
+pub(crate) fn published(target: &Target, output: &PublishOutput) {
+    if let Some(report) = REPORT.get() {
+        let mut report = report.lock().unwrap();
+        collect(&mut report);
+        if let Some(worker) = report.workers.last_mut() {
+            worker.published = true;
+            worker.urls = output.urls.clone();
+            worker.schedules = output.schedules.clone();
+            worker.bindings = bindings(target);
+        }
+    }
+}
+
+// What happened since the worker last begun did, happened to it
+fn collect(report: &mut Report) {
+    let (events, request_ids) = (progress::take_recorded(), http::take_request_ids());
+    if let Some(worker) = report.workers.last_mut() {
+        worker.add(events, request_ids);
+    }
+}
+
+fn write(path: &Path, result: &Result<()>) -> Result<()> {
+    let report = match REPORT.get() {
+        Some(report) => report,
+        None => return Ok(()),
+    };
+    let mut report = report.lock().unwrap();
+    collect(&mut report);
+    report.success = result.is_ok();
+    report.error = result.as_ref().err().map(|e| format!("{:#}", e));
+    report.elapsed_ms = report.started.elapsed().as_millis();
+    fs::write(path, serde_json::to_string_pretty(&*report)?)?;
+    Ok(())
+}
+
+// The names of the bindings of `target` by their type, leaving their values out
+fn bindings(target: &Target) -> Vec<BindingReport> {
+    let binding = |binding_type: &str, name: &str| BindingReport {
+        binding_type: binding_type.to_string(),
+        name: name.to_string(),
+    };
+    let mut bindings: Vec<BindingReport> = target
+        .kv_namespaces
+        .iter()
+        .map(|kv| binding("kv_namespace", &kv.binding))
+        .collect();
+    if let Some(classes) = target
+        .durable_objects
+        .as_ref()
+        .and_then(|d| d.classes.as_ref())
+    {
+        bindings.extend(
+            classes
+                .iter()
+                .map(|class| binding("durable_object_namespace", &class.binding)),
+        );
+    }
+    let mut named = |binding_type: &str, mut names: Vec<&String>| {
+        names.sort();
+        bindings.extend(names.into_iter().map(|name| binding(binding_type, name)));
+    };
+    named(
+        "plain_text",
+        target.vars.iter().flatten().map(|v| v.0).collect(),
+    );
+    named(
+        "text_blob",
+        target.text_blobs.iter().flatten().map(|b| b.0).collect(),
+    );
+    named(
+        "wasm_module",
+        target.wasm_modules.iter().flatten().map(|m| m.0).collect(),
+    );
+    for custom in target.bindings.iter().flatten() {
+        bindings.push(binding(&custom.binding_type, &custom.name));
+    }
+    bindings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::KvNamespace;
+
+    #[test]
+    fn it_reports_phases_uploads_and_bindings() {
+        let mut worker = WorkerReport::new("worker");
+        worker.add(
+            vec![
+                ProgressEvent::Uploaded {
+                    phase: Phase::UploadAssets,
+                    files: 3,
+                    total_files: 3,
+                    bytes: 300,
+                    total_bytes: 300,
+                },
+                ProgressEvent::PhaseCompleted {
+                    phase: Phase::UploadAssets,
+                    success: true,
+                    elapsed_ms: 42,
+                },
+            ],
+            vec!["6c2a1f1e3b2c4d5e-LHR".to_string()],
+        );
+        assert_eq!(worker.assets_uploaded, 3);
+        assert_eq!(worker.script_bytes, None);
+        assert_eq!(
+            worker.phases,
+            vec![PhaseReport {
+                phase: Phase::UploadAssets,
+                success: true,
+                elapsed_ms: 42
+            }]
+        );
+        assert_eq!(worker.request_ids, vec!["6c2a1f1e3b2c4d5e-LHR"]);
+
+        let target = Target {
+            kv_namespaces: vec![KvNamespace {
+                id: "1234".to_string(),
+                binding: "CACHE".to_string(),
+            }],
+            vars: Some(
+                vec![
+                    ("MODE".to_string(), "incident".to_string()),
+                    ("API".to_string(), "https://api.example.com".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(bindings(&target)).unwrap(),
+            serde_json::json!([
+                { "type": "kv_namespace", "name": "CACHE" },
+                { "type": "plain_text", "name": "API" },
+                { "type": "plain_text", "name": "MODE" },
+            ])
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::Result;
+use reqwest::blocking::Client;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdOut};
+
+// How long peeked messages stay hidden from consumers if releasing them fails
+const PEEK_VISIBILITY_TIMEOUT_MS: u64 = 1000;
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    // sending a message has no result
+    #[serde(default)]
+    result: T,
+}
+
+#[derive(Deserialize)]
+struct Queue {
+    queue_id: String,
+    queue_name: String,
+}
+
+#[derive(Default, Deserialize)]
+struct PulledMessages {
+    #[serde(default)]
+    message_backlog_count: Option<u64>,
+    #[serde(default)]
+    messages: Vec<PulledMessage>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct PulledMessage {
+    id: String,
+    #[serde(default)]
+    attempts: u32,
+    body: Value,
+    // only needed to release the message again
+    #[serde(skip_serializing)]
+    lease_id: String,
+}
+
+/// Sends a message to a queue the way a producer worker would. A body that is valid JSON
+/// is sent as JSON, anything else as text.
+pub fn send(account_id: &str, queue: &str, body: &str, user: &GlobalUser) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let queue_id = queue_id(&client, account_id, queue)?;
+
+    let message = match serde_json::from_str::<Value>(body) {
+        Ok(body) => json!({ "body": body, "content_type": "json" }),
+        Err(_) => json!({ "body": body, "content_type": "text" }),
+    };
+    post::<Value>(
+        &client,
+        &format!("accounts/{}/queues/{}/messages", account_id, queue_id),
+        &message,
+    )?;
+
+    StdOut::success(&format!("Sent a message to {}", queue));
+    Ok(())
+}
+
+/// Prints how many messages are waiting in a queue and up to `count` of them. The API only
+/// lets wrangler read messages from queues with an HTTP pull consumer, and reading one
+/// counts as a delivery attempt, so peeked messages are released again right away instead
+/// of waiting for their visibility timeout.
+pub fn peek(account_id: &str, queue: &str, count: u32, user: &GlobalUser) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let queue_id = queue_id(&client, account_id, queue)?;
+
+    let pulled: PulledMessages = post(
+        &client,
+        &format!("accounts/{}/queues/{}/messages/pull", account_id, queue_id),
+        &json!({
+            "batch_size": count,
+            "visibility_timeout_ms": PEEK_VISIBILITY_TIMEOUT_MS,
+        }),
+    )?;
+
+    if let Some(backlog) = pulled.message_backlog_count {
+        StdOut::info(&format!("{} message(s) waiting in {}", backlog, queue));
+    }
+    for message in &pulled.messages {
+        println!("{}", serde_json::to_string(message)?);
+    }
+    if !pulled.messages.is_empty() {
+        post::<Value>(
+            &client,
+            &format!("accounts/{}/queues/{}/messages/ack", account_id, queue_id),
+            &release(&pulled.messages),
+        )?;
+    }
+    Ok(())
+}
+
+// Retrying a message without a delay hands it back to the queue without acknowledging it
+fn release(messages: &[PulledMessage]) -> Value {
+    let retries: Vec<Value> = messages
+        .iter()
+        .map(|message| json!({ "lease_id": message.lease_id, "delay_seconds": 0 }))
+        .collect();
+    json!({ "acks": [], "retries": retries })
+}
+
+fn queue_id(client: &Client, account_id: &str, queue: &str) -> Result<String> {
+    let queues: Vec<Queue> = get(client, &format!("accounts/{}/queues", account_id))?;
+    match queues.into_iter().find(|q| q.queue_name == queue) {
+        Some(found) => Ok(found.queue_id),
+        None => anyhow::bail!("There is no queue named \"{}\" on your account", queue),
+    }
+}
+
+fn get<T: DeserializeOwned + Default>(client: &Client, path: &str) -> Result<T> {
+    let res = client.get(&http::api_url(path)?).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json::<ApiResponse<T>>()?.result)
+}
+
+fn post<T: DeserializeOwned + Default>(client: &Client, path: &str, body: &Value) -> Result<T> {
+    let res = client
+        .post(&http::api_url(path)?)
+        .json(body)
+        .send_logged_as(Method::POST)?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json::<ApiResponse<T>>()?.result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_pulled_messages() {
+        let response = r#"{
+            "success": true,
+            "result": {
+                "message_backlog_count": 3,
+                "messages": [{ "id": "b01b5594f784d0165c2985833f5660dd", "attempts": 1, "body": "{\"id\":1}", "lease_id": "abc" }]
+            }
+        }"#;
+
+        let pulled = serde_json::from_str::<ApiResponse<PulledMessages>>(response)
+            .unwrap()
+            .result;
+        assert_eq!(pulled.message_backlog_count, Some(3));
+        assert_eq!(pulled.messages[0].attempts, 1);
+        assert_eq!(pulled.messages[0].body, Value::from("{\"id\":1}"));
+    }
+
+    #[test]
+    fn it_releases_peeked_messages_without_acknowledging_them() {
+        let message = PulledMessage {
+            id: "b01b5594f784d0165c2985833f5660dd".to_string(),
+            attempts: 1,
+            body: Value::from("hello"),
+            lease_id: "abc".to_string(),
+        };
+
+        assert_eq!(
+            release(&[message]),
+            json!({ "acks": [], "retries": [{ "lease_id": "abc", "delay_seconds": 0 }] })
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use number_prefix::NumberPrefix;
+use prettytable::{Cell, Row, Table};
+use reqwest::blocking::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::kv::namespace;
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+/// The account limits of a Workers plan. `None` means the plan has no limit on a resource.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub kv_namespaces: Option<u64>,
+    pub kv_storage_bytes: Option<u64>,
+    pub scripts: Option<u64>,
+    pub durable_object_namespaces: Option<u64>,
+    pub cron_triggers: Option<u64>,
+}
+
+pub const FREE_LIMITS: Limits = Limits {
+    kv_namespaces: Some(100),
+    kv_storage_bytes: Some(1024 * 1024 * 1024),
+    scripts: Some(100),
+    durable_object_namespaces: None,
+    cron_triggers: Some(5),
+};
+
+pub const PAID_LIMITS: Limits = Limits {
+    kv_namespaces: Some(100),
+    kv_storage_bytes: None,
+    scripts: Some(500),
+    durable_object_namespaces: None,
+    cron_triggers: Some(250),
+};
+
+/// How much of a resource the account uses, and how much of it the plan allows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quota {
+    pub resource: &'static str,
+    pub used: u64,
+    pub limit: Option<u64>,
+    /// Whether `used` and `limit` are sizes in bytes rather than counts
+    pub bytes: bool,
+}
+
+impl Quota {
+    /// The percentage of the limit in use, if there is a limit
+    pub fn utilization(&self) -> Option<f64> {
+        self.limit
+            .filter(|limit| *limit > 0)
+            .map(|limit| self.used as f64 / limit as f64 * 100.0)
+    }
+
+    fn format(&self, amount: u64) -> String {
+        if !self.bytes {
+            return amount.to_string();
+        }
+        match NumberPrefix::binary(amount as f64) {
+            NumberPrefix::Standalone(bytes) => format!("{} bytes", bytes),
+            NumberPrefix::Prefixed(prefix, n) => format!("{:.1} {}B", n, prefix),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    result: T,
+}
+
+#[derive(Deserialize)]
+struct Script {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct Schedules {
+    schedules: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse {
+    data: Option<GraphQLData>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLData {
+    viewer: Viewer,
+}
+
+#[derive(Debug, Deserialize)]
+struct Viewer {
+    accounts: Vec<AccountStorage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountStorage {
+    kv_storage_adaptive_groups: Vec<StorageGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StorageGroup {
+    max: StorageMax,
+    dimensions: StorageDimensions,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageMax {
+    byte_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageDimensions {
+    namespace_id: String,
+}
+
+// Newest first, so the first group of each namespace is its current size
+const KV_STORAGE_QUERY: &str = r#"
+query($accountTag: string, $since: Date) {
+  viewer {
+    accounts(filter: { accountTag: $accountTag }) {
+      kvStorageAdaptiveGroups(limit: 10000, filter: { date_geq: $since }, orderBy: [date_DESC]) {
+        max { byteCount }
+        dimensions { namespaceId }
+      }
+    }
+  }
+}
+"#;
+
+/// Looks up how much of each resource with an account limit the account uses
+pub fn fetch(user: &GlobalUser, account_id: &str, limits: &Limits) -> Result<Vec<Quota>> {
+    let client = http::legacy_auth_client(user);
+
+    let kv_namespaces = namespace::list(&http::cf_v4_client(user)?, account_id)?;
+    let kv_storage = kv_storage_bytes(&client, account_id)?;
+    let scripts: Vec<Script> = get(&client, &format!("accounts/{}/workers/scripts", account_id))?;
+    let durable_object_namespaces: Vec<serde_json::Value> = get(
+        &client,
+        &format!("accounts/{}/workers/durable_objects/namespaces", account_id),
+    )?;
+
+    StdErr::working(&format!(
+        "Counting the cron triggers of {} worker(s)",
+        scripts.len()
+    ));
+    let mut cron_triggers = 0;
+    for script in &scripts {
+        let schedules: Schedules = get(
+            &client,
+            &format!(
+                "accounts/{}/workers/scripts/{}/schedules",
+                account_id, script.id
+            ),
+        )?;
+        cron_triggers += schedules.schedules.len() as u64;
+    }
+
+    let quota = |resource, used, limit| Quota {
+        resource,
+        used,
+        limit,
+        bytes: false,
+    };
+    Ok(vec![
+        quota(
+            "KV namespaces",
+            kv_namespaces.len() as u64,
+            limits.kv_namespaces,
+        ),
+        Quota {
+            bytes: true,
+            ..quota("KV storage", kv_storage, limits.kv_storage_bytes)
+        },
+        quota("Workers", scripts.len() as u64, limits.scripts),
+        quota(
+            "Durable Object namespaces",
+            durable_object_namespaces.len() as u64,
+            limits.durable_object_namespaces,
+        ),
+        quota("Cron triggers", cron_triggers, limits.cron_triggers),
+    ])
+}
+
+/// Prints the quotas in a table, and warns about the ones whose utilization is at or
+/// above `threshold` percent
+pub fn print(quotas: &[Quota], threshold: f64) {
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Resource"),
+        Cell::new("Used"),
+        Cell::new("Limit"),
+        Cell::new("Utilization"),
+    ]));
+    for quota in quotas {
+        let limit = match quota.limit {
+            Some(limit) => quota.format(limit),
+            None => "none".to_string(),
+        };
+        let utilization = match quota.utilization() {
+            Some(utilization) => format!("{:.0}%", utilization),
+            None => "-".to_string(),
+        };
+        table.add_row(Row::new(vec![
+            Cell::new(quota.resource),
+            Cell::new(&quota.format(quota.used)),
+            Cell::new(&limit),
+            Cell::new(&utilization),
+        ]));
+    }
+    print!("{}", table);
+
+    for quota in over_threshold(quotas, threshold) {
+        StdOut::warn(&format!(
+            "{}: {:.0}% of the limit of your plan is in use",
+            quota.resource,
+            quota.utilization().unwrap_or_default()
+        ));
+    }
+}
+
+fn over_threshold(quotas: &[Quota], threshold: f64) -> impl Iterator<Item = &Quota> {
+    quotas.iter().filter(move |quota| {
+        quota
+            .utilization()
+            .map_or(false, |utilization| utilization >= threshold)
+    })
+}
+
+fn kv_storage_bytes(client: &Client, account_id: &str) -> Result<u64> {
+    let since = (Utc::now() - Duration::days(2))
+        .format("%Y-%m-%d")
+        .to_string();
+    let res = client
+        .post(&http::api_url("graphql")?)
+        .json(&json!({
+            "query": KV_STORAGE_QUERY,
+            "variables": { "accountTag": account_id, "since": since }
+        }))
+        .send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+
+    let res: GraphQLResponse = res.json()?;
+    if let Some(errors) = res.errors {
+        let messages: Vec<String> = errors.into_iter().map(|e| e.message).collect();
+        anyhow::bail!("Failed to fetch KV storage: {}", messages.join(", "))
+    }
+    let groups = res
+        .data
+        .and_then(|data| data.viewer.accounts.into_iter().next())
+        .map(|account| account.kv_storage_adaptive_groups)
+        .unwrap_or_default();
+    Ok(latest_total(&groups))
+}
+
+fn latest_total(groups: &[StorageGroup]) -> u64 {
+    let mut seen = HashSet::new();
+    groups
+        .iter()
+        .filter(|group| seen.insert(group.dimensions.namespace_id.as_str()))
+        .map(|group| group.max.byte_count)
+        .sum()
+}
+
+fn get<T: DeserializeOwned>(client: &Client, path: &str) -> Result<T> {
+    let res = client.get(&http::api_url(path)?).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json::<ApiResponse<T>>()?.result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(namespace_id: &str, byte_count: u64) -> StorageGroup {
+        StorageGroup {
+            max: StorageMax { byte_count },
+            dimensions: StorageDimensions {
+                namespace_id: namespace_id.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn it_sums_the_latest_size_of_each_namespace() {
+        let groups = vec![group("a", 300), group("b", 20), group("a", 100)];
+        assert_eq!(latest_total(&groups), 320);
+    }
+
+    #[test]
+    fn it_warns_only_above_the_threshold() {
+        let quota = |resource, used, limit| Quota {
+            resource,
+            used,
+            limit,
+            bytes: false,
+        };
+        let quotas = vec![
+            quota("KV namespaces", 85, Some(100)),
+            quota("Workers", 10, Some(100)),
+            quota("Durable Object namespaces", 40, None),
+        ];
+
+        let warned: Vec<&str> = over_threshold(&quotas, 80.0)
+            .map(|quota| quota.resource)
+            .collect();
+        assert_eq!(warned, vec!["KV namespaces"]);
+        assert_eq!(quotas[2].utilization(), None);
+    }
+}
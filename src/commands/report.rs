@@ -1,12 +1,11 @@
 use crate::{
-    http,
+    http::{self, AuditedRequest},
     reporter::{self, Report},
     settings,
 };
 
 use anyhow::{anyhow, Result};
 use cloudflare::framework::{
-    apiclient::ApiClient,
     endpoint::{Endpoint, Method},
     response::ApiFailure,
 };
@@ -32,7 +31,7 @@ pub fn run(log: Option<&Path>) -> Result<()> {
     let user = settings::global_user::GlobalUser::new()?;
     let report = reporter::read_log(log)?;
     let client = http::cf_v4_client(&user)?;
-    if let Err(e) = client.request(&ErrorReport(report)) {
+    if let Err(e) = client.request_audited(&ErrorReport(report)) {
         match e {
             ApiFailure::Error(code, _) => {
                 // a 409 Conflict will be returned if we have detected that a report has already
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use cloudflare::endpoints::workers::ListRoutes;
+use cloudflare::framework::apiclient::ApiClient;
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+use serde::Deserialize;
+
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::ui::Table;
+
+#[derive(Debug, Deserialize)]
+struct ListScriptsResponse {
+    result: Vec<ScriptSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScriptSummary {
+    id: String,
+    modified_on: DateTime<Utc>,
+    usage_model: Option<String>,
+    #[serde(default)]
+    routes: Option<Vec<ScriptRoute>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScriptRoute {
+    pattern: String,
+}
+
+/// The source of a script as the API returns it
+pub(crate) struct ScriptSource {
+    /// The multipart boundary of module workers, which come back as one part per module
+    pub boundary: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Prints every script of the account with when it was last modified, its usage model and
+/// its routes. The routes of `zone_id` are listed too, for accounts whose scripts don't
+/// come with theirs. `sizes` downloads each script to tell its size.
+pub fn list(user: &GlobalUser, account_id: &str, zone_id: Option<&str>, sizes: bool) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let scripts = list_scripts(&client, account_id)?;
+    let zone_routes = match zone_id {
+        Some(zone_id) => routes_by_script(user, zone_id)?,
+        None => HashMap::new(),
+    };
+
+    let mut header = vec!["Name", "Modified", "Usage model", "Routes"];
+    if sizes {
+        header.push("Size");
+    }
+    let mut table = Table::new(&header);
+    for script in &scripts {
+        let mut routes: Vec<&str> = script
+            .routes
+            .iter()
+            .flatten()
+            .map(|route| route.pattern.as_str())
+            .chain(
+                zone_routes
+                    .get(&script.id)
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str),
+            )
+            .collect();
+        routes.sort_unstable();
+        routes.dedup();
+
+        let mut row = vec![
+            script.id.clone(),
+            script.modified_on.format("%F %R").to_string(),
+            script.usage_model.as_deref().unwrap_or("-").to_string(),
+            routes.join("\n"),
+        ];
+        if sizes {
+            let source = fetch_source(&client, account_id, &script.id)?;
+            row.push(format_size(source.body.len()));
+        }
+        table.add_row(row);
+    }
+
+    if scripts.is_empty() {
+        StdErr::info("There are no scripts on this account");
+    } else {
+        table.print();
+    }
+    Ok(())
+}
+
+/// Saves the source of the script `name` to `output`, or prints it when there's no
+/// `output`. The modules of module workers are saved to the directory `output`, or to a
+/// directory named after the script.
+pub fn download(
+    user: &GlobalUser,
+    account_id: &str,
+    name: &str,
+    output: Option<&Path>,
+) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let source = fetch_source(&client, account_id, name)?;
+
+    match &source.boundary {
+        Some(boundary) => {
+            let dir = output
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| PathBuf::from(name));
+            fs::create_dir_all(&dir)?;
+            let parts = split_multipart(&source.body, boundary);
+            for (filename, contents) in &parts {
+                // modules are named by the script, keep them inside of the directory
+                let filename = Path::new(filename)
+                    .file_name()
+                    .ok_or_else(|| anyhow::anyhow!("{} has a module without a name", name))?;
+                fs::write(dir.join(filename), contents)?;
+            }
+            StdErr::success(&format!(
+                "Saved the {} module(s) of {} to {}",
+                parts.len(),
+                name,
+                dir.display()
+            ));
+        }
+        None => match output {
+            Some(path) => {
+                fs::write(path, &source.body)?;
+                StdErr::success(&format!("Saved {} to {}", name, path.display()));
+            }
+            None => io::stdout().write_all(&source.body)?,
+        },
+    }
+    Ok(())
+}
+
+fn list_scripts(client: &Client, account_id: &str) -> Result<Vec<ScriptSummary>> {
+    let addr = http::api_url(&format!("accounts/{}/workers/scripts", account_id))?;
+    let res = client.get(&addr).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json::<ListScriptsResponse>()?.result)
+}
+
+pub(crate) fn fetch_source(client: &Client, account_id: &str, name: &str) -> Result<ScriptSource> {
+    let addr = http::api_url(&format!("accounts/{}/workers/scripts/{}", account_id, name))?;
+    let res = client.get(&addr).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    let boundary = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .filter(|content_type| content_type.starts_with("multipart/form-data"))
+        .and_then(|content_type| content_type.split("boundary=").nth(1))
+        .map(|boundary| boundary.trim_matches('"').to_string());
+    Ok(ScriptSource {
+        boundary,
+        body: res.bytes()?.to_vec(),
+    })
+}
+
+fn routes_by_script(user: &GlobalUser, zone_id: &str) -> Result<HashMap<String, Vec<String>>> {
+    let client = http::cf_v4_client(user)?;
+    let routes = match client.request(&ListRoutes {
+        zone_identifier: zone_id,
+    }) {
+        Ok(success) => success.result,
+        Err(e) => return Err(http::api_error(e, None)),
+    };
+    let mut by_script: HashMap<String, Vec<String>> = HashMap::new();
+    for route in routes {
+        if let Some(script) = route.script {
+            by_script.entry(script).or_default().push(route.pattern);
+        }
+    }
+    Ok(by_script)
+}
+
+// The filename (or the name, for parts without one) and contents of each part of a
+// multipart/form-data body
+pub(crate) fn split_multipart(body: &[u8], boundary: &str) -> Vec<(String, Vec<u8>)> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    for part in split_bytes(body, delimiter.as_bytes()).into_iter().skip(1) {
+        // the closing delimiter is followed by `--`
+        if part.starts_with(b"--") {
+            break;
+        }
+        let part = part.strip_prefix(b"\r\n").unwrap_or(part);
+        let (headers, contents) = match find(part, b"\r\n\r\n") {
+            Some(i) => (&part[..i], &part[i + 4..]),
+            None => continue,
+        };
+        let contents = contents.strip_suffix(b"\r\n").unwrap_or(contents);
+        let headers = String::from_utf8_lossy(headers);
+        let name =
+            disposition_param(&headers, "filename").or_else(|| disposition_param(&headers, "name"));
+        if let Some(name) = name {
+            parts.push((name, contents.to_vec()));
+        }
+    }
+    parts
+}
+
+fn disposition_param(headers: &str, param: &str) -> Option<String> {
+    let disposition = headers.lines().find(|line| {
+        line.to_ascii_lowercase()
+            .starts_with("content-disposition:")
+    })?;
+    disposition.split(';').find_map(|field| {
+        let (key, value) = field.trim().split_once('=')?;
+        if key == param {
+            Some(value.trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn split_bytes<'a>(haystack: &'a [u8], needle: &[u8]) -> Vec<&'a [u8]> {
+    let mut pieces = Vec::new();
+    let mut rest = haystack;
+    while let Some(i) = find(rest, needle) {
+        pieces.push(&rest[..i]);
+        rest = &rest[i + needle.len()..];
+    }
+    pieces.push(rest);
+    pieces
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_the_modules_of_module_workers() {
+        let body = "--abc\r\n\
+            Content-Disposition: form-data; name=\"index.mjs\"; filename=\"index.mjs\"\r\n\
+            Content-Type: application/javascript+module\r\n\
+            \r\n\
+            export default {}\r\n\
+            --abc\r\n\
+            Content-Disposition: form-data; name=\"utils.wasm\"\r\n\
+            \r\n\
+            \0asm\r\n\
+            --abc--\r\n";
+        let parts = split_multipart(body.as_bytes(), "abc");
+        assert_eq!(
+            parts,
+            vec![
+                ("index.mjs".to_string(), b"export default {}".to_vec()),
+                ("utils.wasm".to_string(), b"\0asm".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_formats_sizes() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(2048), "2.0 KiB");
+    }
+}
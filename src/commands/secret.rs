@@ -2,17 +2,28 @@ use cloudflare::endpoints::workers::{CreateSecret, CreateSecretParams, DeleteSec
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
 
+use std::collections::{BTreeMap, HashMap};
+
 use anyhow::Result;
+use reqwest::blocking::Client;
+use serde::Deserialize;
 
-use crate::http;
+use crate::error::WranglerError;
+use crate::http::{self, AuditedRequest, HttpApi, SendLogged};
+use crate::settings::encrypted_vars;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::interactive;
 use crate::terminal::message::{Message, StdOut};
+use crate::terminal::ui::Table;
 use crate::upload;
 
-fn format_error(e: ApiFailure) -> String {
-    http::format_error(e, Some(&secret_errors))
+// the types of the bindings vars and secrets are uploaded as
+const PLAIN_TEXT: &str = "plain_text";
+const SECRET_TEXT: &str = "secret_text";
+
+fn api_error(e: ApiFailure) -> anyhow::Error {
+    http::api_error(e, Some(&secret_errors))
 }
 
 // secret_errors() provides more detailed explanations of API error codes.
@@ -42,8 +53,13 @@ pub fn upload_draft_worker(
             let error = &api_errors.errors[0];
             if error.code == 10007 {
                 StdOut::working(&format!("Worker {} doesn't exist in the API yet. Creating a draft Worker so we can create new secret.", target.name));
-                let upload_client = http::legacy_auth_client(user);
-                Some(upload::script(&upload_client, target, None))
+                Some(upload::script(
+                    &HttpApi::new(user),
+                    target,
+                    None,
+                    false,
+                    false,
+                ))
             } else {
                 None
             }
@@ -53,10 +69,13 @@ pub fn upload_draft_worker(
 }
 
 pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<()> {
-    let secret_value = interactive::get_user_input_multi_line(&format!(
-        "Enter the secret text you'd like assigned to the variable {} on the script named {}:",
-        name, target.name
-    ));
+    let secret_value = interactive::get_user_input_multi_line(
+        &format!(
+            "Enter the secret text you'd like assigned to the variable {} on the script named {}:",
+            name, target.name
+        ),
+        "pipe the secret to wrangler",
+    )?;
 
     if secret_value.is_empty() {
         anyhow::bail!("Your secret cannot be empty.")
@@ -75,7 +94,7 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
         secret_type: "secret_text".to_string(),
     };
 
-    let response = client.request(&CreateSecret {
+    let response = client.request_audited(&CreateSecret {
         account_identifier: target.account_id.load()?,
         script_name: &target.name,
         params: params.clone(),
@@ -84,10 +103,10 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
     match response {
         Ok(_) => StdOut::success(&format!("Success! Uploaded secret {}.", name)),
         Err(e) => match upload_draft_worker(&e, user, target) {
-            None => anyhow::bail!(format_error(e)),
+            None => return Err(api_error(e)),
             Some(draft_upload_response) => match draft_upload_response {
                 Ok(_) => {
-                    let retry_response = client.request(&CreateSecret {
+                    let retry_response = client.request_audited(&CreateSecret {
                         account_identifier: target.account_id.load()?,
                         script_name: &target.name,
                         params,
@@ -95,7 +114,7 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
 
                     match retry_response {
                         Ok(_) => StdOut::success(&format!("Success! Uploaded secret {}.", name)),
-                        Err(e) => anyhow::bail!(format_error(e)),
+                        Err(e) => return Err(api_error(e)),
                     }
                 }
                 Err(e) => anyhow::bail!(e),
@@ -126,7 +145,7 @@ pub fn delete_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
 
     let client = http::cf_v4_client(user)?;
 
-    let response = client.request(&DeleteSecret {
+    let response = client.request_audited(&DeleteSecret {
         account_identifier: target.account_id.load()?,
         script_name: &target.name,
         secret_name: name,
@@ -134,7 +153,7 @@ pub fn delete_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
 
     match response {
         Ok(_) => StdOut::success(&format!("Success! Deleted secret {}.", name)),
-        Err(e) => anyhow::bail!(format_error(e)),
+        Err(e) => return Err(api_error(e)),
     }
 
     Ok(())
@@ -153,8 +172,249 @@ pub fn list_secrets(user: &GlobalUser, target: &Target) -> Result<()> {
             let secrets = success.result;
             println!("{}", serde_json::to_string(&secrets)?);
         }
-        Err(e) => anyhow::bail!(format_error(e)),
+        Err(e) => return Err(api_error(e)),
     }
 
     Ok(())
 }
+
+/// The names of the secrets bound to `script_name`
+pub(crate) fn secret_names(
+    user: &GlobalUser,
+    account_id: &str,
+    script_name: &str,
+) -> Result<Vec<String>> {
+    let client = http::cf_v4_client(user)?;
+    match client.request(&ListSecrets {
+        account_identifier: account_id,
+        script_name,
+    }) {
+        Ok(success) => Ok(success.result.into_iter().map(|s| s.name).collect()),
+        Err(e) => Err(api_error(e)),
+    }
+}
+
+/// How a var or secret bound to a published worker differs from its configuration
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Drift {
+    /// Declared in the configuration file but not bound to the worker
+    Missing,
+    /// Bound to the worker but not declared in the configuration file
+    Extra,
+    /// Bound to the worker with another value, or as a secret where a plain var is declared
+    /// or the other way around
+    Stale,
+}
+
+/// A binding of a published worker that doesn't match its configuration
+#[derive(Clone, Debug, PartialEq)]
+pub struct BindingDrift {
+    pub name: String,
+    /// Whether the binding is, or is meant to be, secret
+    pub secret: bool,
+    pub drift: Drift,
+}
+
+impl BindingDrift {
+    fn status(&self) -> &'static str {
+        match self.drift {
+            Drift::Missing => "missing",
+            Drift::Extra => "extra",
+            Drift::Stale => "stale",
+        }
+    }
+
+    fn fix(&self) -> String {
+        match (self.drift, self.secret) {
+            (Drift::Missing, true) | (Drift::Stale, true) => {
+                format!("wrangler secret put {}", self.name)
+            }
+            (Drift::Extra, true) => format!(
+                "wrangler secret delete {}, or add it to `secrets`",
+                self.name
+            ),
+            (_, false) => "wrangler publish".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ScriptSettings {
+    #[serde(default)]
+    bindings: Vec<DeployedBinding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeployedBinding {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    /// The value of a plain text binding. The API never returns the value of a secret.
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    result: T,
+}
+
+/// Compares the vars and secrets bound to the published worker of every target with the
+/// `vars` and `secrets` of its configuration, each target given with the environment it's of
+/// and the names of its secrets. Secret values are never read, only whether they're bound.
+/// Fails if any binding differs, so that a CI job can check for it.
+pub fn diff(user: &GlobalUser, targets: &[(Target, Option<String>, Vec<String>)]) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let mut table = Table::new(&["Worker", "Environment", "Binding", "Kind", "Status", "Fix"]);
+    let mut drifted = 0;
+    for (target, environment, secrets) in targets {
+        let deployed = deployed_bindings(&client, target)?;
+        let vars = target.vars.clone().unwrap_or_default();
+        for drift in binding_drift(&vars, secrets, &deployed) {
+            drifted += 1;
+            table.add_row(vec![
+                target.name.clone(),
+                environment.clone().unwrap_or_default(),
+                drift.name.clone(),
+                if drift.secret { "secret" } else { "var" }.to_string(),
+                drift.status().to_string(),
+                drift.fix(),
+            ]);
+        }
+    }
+
+    if table.is_empty() {
+        StdOut::success("The vars and secrets of every worker match the configuration file");
+        return Ok(());
+    }
+    table.print();
+    anyhow::bail!(
+        "{} var{} or secret{} differ from the configuration file",
+        drifted,
+        if drifted == 1 { "" } else { "s" },
+        if drifted == 1 { "" } else { "s" }
+    )
+}
+
+// The text bindings of the published worker of `target`, none if it isn't published yet
+fn deployed_bindings(client: &Client, target: &Target) -> Result<Vec<DeployedBinding>> {
+    let addr = http::api_url(&format!(
+        "accounts/{}/workers/scripts/{}/settings",
+        target.account_id.load()?,
+        target.name
+    ))?;
+    let res = client.get(&addr).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        let error = WranglerError::from_api_response(status, res.text()?);
+        if error.has_api_code(10007) {
+            StdOut::info(&format!("{} hasn't been published yet", target.name));
+            return Ok(Vec::new());
+        }
+        anyhow::bail!(error)
+    }
+    Ok(res
+        .json::<ApiResponse<ScriptSettings>>()?
+        .result
+        .bindings
+        .into_iter()
+        .filter(|binding| binding.kind == PLAIN_TEXT || binding.kind == SECRET_TEXT)
+        .collect())
+}
+
+// How the text bindings of a published worker differ from `vars` and the names of `secrets`.
+// Encrypted vars are bound as secrets, so only the values of plain vars are compared.
+fn binding_drift(
+    vars: &HashMap<String, String>,
+    secrets: &[String],
+    deployed: &[DeployedBinding],
+) -> Vec<BindingDrift> {
+    // by name, whether the binding is secret and the value it should have if it isn't
+    let mut declared: BTreeMap<&str, (bool, Option<&str>)> = BTreeMap::new();
+    for (name, value) in vars {
+        let secret = encrypted_vars::is_encrypted(value);
+        let value = if secret { None } else { Some(value.as_str()) };
+        declared.insert(name.as_str(), (secret, value));
+    }
+    for name in secrets {
+        declared.entry(name.as_str()).or_insert((true, None));
+    }
+
+    let mut drifts = Vec::new();
+    for (name, (secret, value)) in &declared {
+        let drift = match deployed.iter().find(|binding| binding.name == *name) {
+            None => Some(Drift::Missing),
+            Some(binding) if (binding.kind == SECRET_TEXT) != *secret => Some(Drift::Stale),
+            Some(binding) if !secret && binding.text.as_deref() != *value => Some(Drift::Stale),
+            Some(_) => None,
+        };
+        if let Some(drift) = drift {
+            drifts.push(BindingDrift {
+                name: name.to_string(),
+                secret: *secret,
+                drift,
+            });
+        }
+    }
+    for binding in deployed {
+        if !declared.contains_key(binding.name.as_str()) {
+            drifts.push(BindingDrift {
+                name: binding.name.clone(),
+                secret: binding.kind == SECRET_TEXT,
+                drift: Drift::Extra,
+            });
+        }
+    }
+    drifts.sort_by(|a, b| a.name.cmp(&b.name));
+    drifts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bound(name: &str, kind: &str, text: Option<&str>) -> DeployedBinding {
+        DeployedBinding {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            text: text.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn it_finds_missing_extra_and_stale_bindings() {
+        let vars: HashMap<String, String> = vec![
+            ("API_HOST", "api.example.com"),
+            ("LOG_LEVEL", "debug"),
+            ("SIGNING_KEY", "encrypted:v1:abcdef"),
+            ("TIMEOUT", "30"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let secrets = vec!["DB_PASSWORD".to_string(), "STRIPE_KEY".to_string()];
+        let deployed = vec![
+            bound("API_HOST", PLAIN_TEXT, Some("api.example.com")),
+            bound("LOG_LEVEL", PLAIN_TEXT, Some("info")),
+            bound("SIGNING_KEY", SECRET_TEXT, None),
+            bound("TIMEOUT", SECRET_TEXT, None),
+            bound("DB_PASSWORD", SECRET_TEXT, None),
+            bound("OLD_TOKEN", SECRET_TEXT, None),
+        ];
+
+        let drift = |name: &str, secret, drift| BindingDrift {
+            name: name.to_string(),
+            secret,
+            drift,
+        };
+        assert_eq!(
+            binding_drift(&vars, &secrets, &deployed),
+            vec![
+                drift("LOG_LEVEL", false, Drift::Stale),
+                drift("OLD_TOKEN", true, Drift::Extra),
+                drift("STRIPE_KEY", true, Drift::Missing),
+                drift("TIMEOUT", false, Drift::Stale),
+            ]
+        );
+        assert!(binding_drift(&HashMap::new(), &[], &[]).is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Result;
+use cloudflare::endpoints::workerskv::WorkersKvNamespace;
+
+use crate::commands::init::get_current_dirname;
+use crate::commands::previews::{self, list_scripts};
+use crate::commands::publish::validate_bucket_location;
+use crate::commands::{self, validate_worker_name};
+use crate::http;
+use crate::kv::namespace;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{Manifest, Site, Target, TargetType};
+use crate::sites;
+use crate::terminal::message::{Message, Output, StdErr, StdOut};
+use crate::terminal::{interactive, styles};
+
+// See sites::namespace_title
+const TITLE_SUFFIXES: &[&str] = &["-workers_sites_assets", "-workers_sites_assets_preview"];
+
+/// Sets up a Workers Site serving the static files in `bucket` in one go: the configuration
+/// file with its `[site]`, the worker serving the assets and the namespace holding them, and
+/// with `publish`, publishes it too
+pub fn new(
+    user: &GlobalUser,
+    config_path: &Path,
+    bucket: &Path,
+    name: Option<&str>,
+    publish: bool,
+) -> Result<()> {
+    if config_path.exists() {
+        anyhow::bail!(
+            "{} already exists; add a [site] to it instead, see `wrangler init --help`",
+            config_path.display()
+        )
+    }
+    validate_bucket_location(bucket)?;
+    let dirname = get_current_dirname()?;
+    let name = name.unwrap_or(&dirname);
+    validate_worker_name(name)?;
+
+    let site = Site {
+        bucket: bucket.to_path_buf(),
+        ..Site::default()
+    };
+    let project_dir = config_path.parent().unwrap_or_else(|| Path::new("./"));
+    Manifest::generate(
+        name.to_string(),
+        Some(TargetType::Webpack),
+        project_dir,
+        Some(site.clone()),
+    )?;
+    site.scaffold_worker()?;
+    StdOut::success(&format!(
+        "Created {} and the worker serving {}",
+        config_path.display(),
+        bucket.display()
+    ));
+
+    let manifest = Manifest::new(config_path)?;
+    let mut target = manifest.get_target(None, false)?;
+    sites::add_namespace(user, &mut target, false)?;
+
+    if publish {
+        let deployments = manifest.get_deployments(None)?;
+        commands::publish(
+            user,
+            &mut target,
+            deployments,
+            Output::PlainText,
+            false,
+            false,
+            false,
+        )?;
+    } else {
+        StdOut::help(&format!(
+            "Run {} to publish your site",
+            styles::highlight("`wrangler publish`")
+        ));
+    }
+    Ok(())
+}
+
+/// Prints the files of the bucket of `target` that a sync would upload to its namespace, and
+/// the ones the namespace has that the bucket doesn't anymore, without writing anything
+pub fn diff(user: &GlobalUser, target: &Target) -> Result<()> {
+    let site = match &target.site {
+        Some(site) => site,
+        None => anyhow::bail!("{} has no [site] to compare", target.name),
+    };
+    validate_bucket_location(&site.bucket)?;
+
+    // the namespace isn't created when it's missing, every file is new to it then
+    let title = sites::namespace_title(target, false);
+    let account_id = target.account_id.load()?;
+    let remote_keys = match namespace::list(&http::cf_v4_client(user)?, account_id)?
+        .into_iter()
+        .find(|namespace| namespace.title == title)
+    {
+        Some(namespace) => sites::remote_keys(target, user, &namespace.id)?,
+        None => {
+            StdErr::info(&format!("{} doesn't exist yet", title));
+            HashSet::new()
+        }
+    };
+
+    let (assets, asset_manifest, _) = sites::directory_keys_values(target, &site.bucket, None)?;
+    sites::BucketDiff::new(&assets, &asset_manifest, &remote_keys).print();
+    Ok(())
+}
+
+/// Deletes the Workers Sites asset namespaces of the project that no worker uses anymore.
+/// Every publish and preview of a site creates a namespace named after its worker, so
+/// workers deleted from the dashboard and branch previews that were deleted leave theirs
+/// behind.
+///
+/// Only the namespaces of the workers of the project are looked at: the one of the top
+/// level, the ones of the environments of the configuration file and the branch previews
+/// published from this machine. Each is deleted once the account has no script of its
+/// worker anymore.
+pub fn cleanup(
+    user: &GlobalUser,
+    manifest: &Manifest,
+    env: Option<&str>,
+    dry_run: bool,
+    force: bool,
+) -> Result<()> {
+    let account_id = manifest.get_account_id(env)?;
+    let client = http::legacy_auth_client(user);
+
+    let in_use: HashSet<String> = list_scripts(&client, &account_id)?
+        .into_iter()
+        .map(|script| script.id)
+        .collect();
+    let mut workers: HashSet<String> = HashSet::new();
+    workers.insert(manifest.worker_name(None));
+    for env in manifest.environment_names() {
+        workers.insert(manifest.worker_name(Some(&env)));
+    }
+    workers.extend(previews::recorded(
+        &account_id,
+        &manifest.worker_name(None),
+    )?);
+
+    let stale: Vec<WorkersKvNamespace> = namespace::list(&http::cf_v4_client(user)?, &account_id)?
+        .into_iter()
+        .filter(|namespace| is_stale(&workers, &in_use, &namespace.title))
+        .collect();
+    if stale.is_empty() {
+        StdOut::info("No unused Workers Sites namespaces to delete");
+        return Ok(());
+    }
+
+    for namespace in &stale {
+        StdOut::info(&format!(
+            "{}{} ({})",
+            if dry_run { "Would delete " } else { "" },
+            namespace.title,
+            namespace.id
+        ));
+    }
+    if dry_run {
+        return Ok(());
+    }
+    if !force
+        && !interactive::confirm_or_fail(
+            &format!(
+                "Are you sure you want to delete these {} namespace(s) and their assets?",
+                stale.len()
+            ),
+            "pass --force to delete them",
+        )?
+    {
+        StdOut::info("Not deleting any namespace");
+        return Ok(());
+    }
+
+    let mut deleted = 0;
+    for ns in &stale {
+        match namespace::delete(http::cf_v4_client(user)?, &account_id, &ns.id) {
+            Ok(_) => deleted += 1,
+            Err(e) => StdErr::warn(&format!(
+                "Could not delete {}: {}",
+                ns.title,
+                http::format_error(e, None)
+            )),
+        }
+    }
+    http::cache::invalidate(user, &namespace::namespaces_cache_key(&account_id));
+    StdOut::success(&format!("Deleted {} namespace(s)", deleted));
+    Ok(())
+}
+
+// Whether `title` is the asset namespace of one of `workers` that isn't in `in_use`
+fn is_stale(workers: &HashSet<String>, in_use: &HashSet<String>, title: &str) -> bool {
+    match site_worker(title) {
+        Some(worker) => workers.contains(worker) && !in_use.contains(worker),
+        None => false,
+    }
+}
+
+// The worker an asset namespace title is named after
+fn site_worker(title: &str) -> Option<&str> {
+    let title = title.strip_prefix("__")?;
+    TITLE_SUFFIXES
+        .iter()
+        .rev()
+        .find_map(|suffix| title.strip_suffix(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_worker_of_asset_namespaces() {
+        assert_eq!(site_worker("__blog-workers_sites_assets"), Some("blog"));
+        assert_eq!(
+            site_worker("__blog-staging-workers_sites_assets_preview"),
+            Some("blog-staging")
+        );
+        assert_eq!(site_worker("blog-workers_sites_assets"), None);
+        assert_eq!(site_worker("__blog-cache"), None);
+    }
+
+    #[test]
+    fn it_only_deletes_unused_namespaces_of_the_workers_of_the_project() {
+        let set = |names: &[&str]| -> HashSet<String> {
+            names.iter().map(|name| name.to_string()).collect()
+        };
+        let workers = set(&["blog", "blog-staging", "blog-qa", "blog-old-feature"]);
+        let in_use = set(&["blog", "blog-staging", "blog-unrelated"]);
+        let titles = [
+            "__blog-workers_sites_assets",
+            "__blog-workers_sites_assets_preview",
+            "__blog-staging-workers_sites_assets",
+            "__blog-old-feature-workers_sites_assets",
+            "__blog-qa-workers_sites_assets_preview",
+            "__blog-other-project-workers_sites_assets",
+            "__blogroll-workers_sites_assets",
+            "blog-cache",
+        ];
+        let stale: Vec<&str> = titles
+            .iter()
+            .copied()
+            .filter(|title| is_stale(&workers, &in_use, title))
+            .collect();
+        assert_eq!(
+            stale,
+            vec![
+                "__blog-old-feature-workers_sites_assets",
+                "__blog-qa-workers_sites_assets_preview"
+            ]
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use prettytable::{Cell, Row, Table};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdOut};
+
+/// How `wrangler stats` prints the usage of each script
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatsFormat {
+    Table,
+    Json,
+}
+
+impl FromStr for StatsFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "table" => Ok(StatsFormat::Table),
+            "json" => Ok(StatsFormat::Json),
+            _ => anyhow::bail!("Unknown format \"{}\", expected table or json", s),
+        }
+    }
+}
+
+/// The usage of a script over the period `wrangler stats` looked at
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ScriptStats {
+    pub script_name: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub subrequests: u64,
+    pub cpu_time_p50_ms: f64,
+    pub cpu_time_p90_ms: f64,
+    pub cpu_time_p99_ms: f64,
+}
+
+impl ScriptStats {
+    /// The percentage of requests that failed
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.requests as f64 * 100.0
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse {
+    data: Option<GraphQLData>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLData {
+    viewer: Viewer,
+}
+
+#[derive(Debug, Deserialize)]
+struct Viewer {
+    accounts: Vec<AccountAnalytics>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountAnalytics {
+    workers_invocations_adaptive: Vec<Invocations>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Invocations {
+    dimensions: InvocationsDimensions,
+    sum: InvocationsSum,
+    quantiles: InvocationsQuantiles,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InvocationsDimensions {
+    script_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvocationsSum {
+    requests: u64,
+    errors: u64,
+    subrequests: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InvocationsQuantiles {
+    /// Reported in microseconds
+    cpu_time_p50: f64,
+    cpu_time_p90: f64,
+    cpu_time_p99: f64,
+}
+
+impl From<Invocations> for ScriptStats {
+    fn from(invocations: Invocations) -> Self {
+        ScriptStats {
+            script_name: invocations.dimensions.script_name,
+            requests: invocations.sum.requests,
+            errors: invocations.sum.errors,
+            subrequests: invocations.sum.subrequests,
+            cpu_time_p50_ms: invocations.quantiles.cpu_time_p50 / 1000.0,
+            cpu_time_p90_ms: invocations.quantiles.cpu_time_p90 / 1000.0,
+            cpu_time_p99_ms: invocations.quantiles.cpu_time_p99 / 1000.0,
+        }
+    }
+}
+
+// Grouped by script, busiest first
+const INVOCATIONS_QUERY: &str = r#"
+query($accountTag: string, $filter: AccountWorkersInvocationsAdaptiveFilter_InputObject) {
+  viewer {
+    accounts(filter: { accountTag: $accountTag }) {
+      workersInvocationsAdaptive(limit: 10000, filter: $filter, orderBy: [sum_requests_DESC]) {
+        dimensions { scriptName }
+        sum { requests errors subrequests }
+        quantiles { cpuTimeP50 cpuTimeP90 cpuTimeP99 }
+      }
+    }
+  }
+}
+"#;
+
+/// Prints the requests, errors, subrequests and CPU time percentiles of the scripts of the
+/// account over the past `since`, of just `script_name` if it's given
+pub fn stats(
+    user: &GlobalUser,
+    account_id: &str,
+    script_name: Option<&str>,
+    since: Duration,
+    format: StatsFormat,
+) -> Result<()> {
+    let until = Utc::now();
+    let stats = fetch_stats(user, account_id, script_name, until - since, until)?;
+
+    match format {
+        StatsFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+        StatsFormat::Table if stats.is_empty() => {
+            StdOut::info("No requests were made to your workers in this period")
+        }
+        StatsFormat::Table => print!("{}", table(&stats)),
+    }
+    Ok(())
+}
+
+fn fetch_stats(
+    user: &GlobalUser,
+    account_id: &str,
+    script_name: Option<&str>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<ScriptStats>> {
+    let mut filter = json!({
+        "datetime_geq": since.to_rfc3339(),
+        "datetime_leq": until.to_rfc3339(),
+    });
+    if let Some(script_name) = script_name {
+        filter["scriptName"] = json!(script_name);
+    }
+
+    let client = http::legacy_auth_client(user);
+    let res = client
+        .post(&http::api_url("graphql")?)
+        .json(&json!({
+            "query": INVOCATIONS_QUERY,
+            "variables": {
+                "accountTag": account_id,
+                "filter": filter,
+            }
+        }))
+        .send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+
+    let res: GraphQLResponse = res.json()?;
+    if let Some(errors) = res.errors {
+        let messages: Vec<String> = errors.into_iter().map(|e| e.message).collect();
+        anyhow::bail!("Failed to fetch analytics: {}", messages.join(", "))
+    }
+
+    Ok(res
+        .data
+        .and_then(|data| data.viewer.accounts.into_iter().next())
+        .map(|account| account.workers_invocations_adaptive)
+        .unwrap_or_default()
+        .into_iter()
+        .map(ScriptStats::from)
+        .collect())
+}
+
+fn table(stats: &[ScriptStats]) -> Table {
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Worker"),
+        Cell::new("Requests"),
+        Cell::new("Errors"),
+        Cell::new("CPU p50"),
+        Cell::new("CPU p90"),
+        Cell::new("CPU p99"),
+        Cell::new("Subrequests"),
+    ]));
+    for script in stats {
+        table.add_row(Row::new(vec![
+            Cell::new(&script.script_name),
+            Cell::new(&script.requests.to_string()),
+            Cell::new(&format!("{} ({:.2}%)", script.errors, script.error_rate())),
+            Cell::new(&format!("{:.1}ms", script.cpu_time_p50_ms)),
+            Cell::new(&format!("{:.1}ms", script.cpu_time_p90_ms)),
+            Cell::new(&format!("{:.1}ms", script.cpu_time_p99_ms)),
+            Cell::new(&script.subrequests.to_string()),
+        ]));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_the_stats_of_each_script() {
+        let res: GraphQLResponse = serde_json::from_value(json!({
+            "data": { "viewer": { "accounts": [{ "workersInvocationsAdaptive": [{
+                "dimensions": { "scriptName": "api" },
+                "sum": { "requests": 2000, "errors": 5, "subrequests": 3100 },
+                "quantiles": { "cpuTimeP50": 1200.0, "cpuTimeP90": 4500.0, "cpuTimeP99": 12000.0 }
+            }] }] } },
+            "errors": null
+        }))
+        .unwrap();
+        let invocations = res
+            .data
+            .unwrap()
+            .viewer
+            .accounts
+            .remove(0)
+            .workers_invocations_adaptive
+            .remove(0);
+
+        let stats = ScriptStats::from(invocations);
+        assert_eq!(stats.script_name, "api");
+        assert_eq!(stats.cpu_time_p50_ms, 1.2);
+        assert_eq!(stats.cpu_time_p99_ms, 12.0);
+        assert_eq!(stats.error_rate(), 0.25);
+        assert_eq!(
+            serde_json::to_value(&stats).unwrap()["subrequests"],
+            json!(3100)
+        );
+    }
+
+    #[test]
+    fn it_has_no_error_rate_without_requests() {
+        let stats = ScriptStats {
+            script_name: "idle".to_string(),
+            requests: 0,
+            errors: 0,
+            subrequests: 0,
+            cpu_time_p50_ms: 0.0,
+            cpu_time_p90_ms: 0.0,
+            cpu_time_p99_ms: 0.0,
+        };
+        assert_eq!(stats.error_rate(), 0.0);
+    }
+}
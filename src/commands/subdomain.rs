@@ -1,10 +1,13 @@
-use crate::http;
+use crate::error::WranglerError;
+use crate::http::{self, CloudflareApi, HttpApi, SendLogged};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::message::{Message, StdOut};
+use crate::terminal::progress;
 use crate::terminal::{emoji, interactive};
 
 use anyhow::Result;
+use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize)]
@@ -14,26 +17,11 @@ pub struct Subdomain {
 
 impl Subdomain {
     pub fn get(account_id: &str, user: &GlobalUser) -> Result<Option<String>> {
-        let addr = subdomain_addr(account_id);
-
-        let client = http::legacy_auth_client(user);
-
-        let response = client.get(&addr).send()?;
-
-        if !response.status().is_success() {
-            anyhow::bail!(
-                "{} There was an error fetching your subdomain.\n Status Code: {}\n Msg: {}",
-                emoji::WARN,
-                response.status(),
-                response.text()?,
-            )
-        }
-        let response: SubdomainResponse = serde_json::from_str(&response.text()?)?;
-        Ok(response.result.map(|r| r.subdomain))
+        HttpApi::new(user).subdomain(account_id)
     }
 
     pub fn put(name: &str, account_id: &str, user: &GlobalUser) -> Result<()> {
-        let addr = subdomain_addr(account_id);
+        let addr = subdomain_addr(account_id)?;
         let subdomain = Subdomain {
             subdomain: name.to_string(),
         };
@@ -45,46 +33,28 @@ impl Subdomain {
             .put(&addr)
             .header("allow-rename", "1")
             .body(subdomain_request)
-            .send()?;
+            .send_logged_as(Method::PUT)?;
 
         let response_status = response.status();
         if !response_status.is_success() {
-            let response_text = &response.text()?;
-            let r: SubdomainResponse = serde_json::from_str(response_text)?;
-            let api_error = r.errors.first().unwrap();
+            let response_text = response.text()?;
             log::debug!("Status Code: {}", response_status);
             log::debug!("Status Message: {}", response_text);
-            let msg = if response_status == 403 && api_error.code == 10031 {
-                format!(
+            let error = WranglerError::from_api_response(response_status, response_text);
+            if response_status == 403 && error.has_api_code(10031) {
+                anyhow::bail!(
                     "{} Your requested subdomain is not available. Please pick another one.",
                     emoji::WARN
                 )
-            } else {
-                format!(
-                    "{} There was an error creating your requested subdomain.\n Status Code: {}\n Msg: {}",
-                    emoji::WARN,
-                    response_status,
-                    response_text
-                )
-            };
-            anyhow::bail!(msg)
+            }
+            anyhow::bail!(error)
         }
+        http::cache::invalidate(user, &http::subdomain_cache_key(account_id));
         StdOut::success(&format!("Success! You've registered {}.", name));
         Ok(())
     }
 }
 
-#[derive(Deserialize)]
-struct SubdomainResponse {
-    result: Option<SubdomainResult>,
-    errors: Vec<Error>,
-}
-
-#[derive(Deserialize)]
-struct SubdomainResult {
-    subdomain: String,
-}
-
 #[derive(Deserialize)]
 struct ScriptResponse {
     result: Vec<ScriptResult>,
@@ -96,28 +66,71 @@ struct ScriptResult {
     available_on_subdomain: bool,
 }
 
-#[derive(Deserialize)]
-struct Error {
-    code: i64,
+fn subdomain_addr(account_id: &str) -> Result<String> {
+    http::api_url(&format!("accounts/{}/workers/subdomain", account_id))
 }
 
-fn subdomain_addr(account_id: &str) -> String {
-    format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/subdomain",
-        account_id
-    )
+pub const NO_SUBDOMAIN_MSG: &str = "Before publishing to workers.dev, you must register a subdomain. Please choose a name for your subdomain and run `wrangler subdomain <name>`.";
+
+// workers.dev subdomains have to be a valid DNS label
+fn validate_subdomain_name(name: &str) -> Result<()> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if name.is_empty()
+        || name.len() > 63
+        || !valid_chars
+        || name.starts_with('-')
+        || name.ends_with('-')
+    {
+        anyhow::bail!(
+            "Subdomain \"{}\" is invalid. Use up to 63 lowercase letters, numbers and dashes, not starting or ending with a dash.",
+            name
+        )
+    }
+    Ok(())
 }
 
-fn register_subdomain(name: &str, user: &GlobalUser, target: &Target) -> Result<()> {
+fn register_subdomain(name: &str, user: &GlobalUser, account_id: &str) -> Result<()> {
     let msg = format!(
         "Registering your subdomain, {}.workers.dev, this could take up to a minute.",
         name
     );
     StdOut::working(&msg);
-    Subdomain::put(name, target.account_id.load()?, user)
+    Subdomain::put(name, account_id, user)
+}
+
+/// Makes sure the account has a workers.dev subdomain to publish to, offering to register
+/// one if it doesn't and somebody is at the terminal to pick a name.
+pub fn ensure_subdomain(account_id: &str, user: &GlobalUser) -> Result<()> {
+    if Subdomain::get(account_id, user)?.is_some() {
+        return Ok(());
+    }
+    if !interactive::is_interactive() || progress::is_json() {
+        anyhow::bail!(NO_SUBDOMAIN_MSG)
+    }
+
+    StdOut::info("Your account does not have a workers.dev subdomain yet, which is needed to publish to workers.dev.");
+    loop {
+        let name = interactive::get_user_input(
+            "Choose a subdomain to register, or leave it empty to cancel:",
+            "register one with `wrangler subdomain <name>`",
+        )?;
+        if name.is_empty() {
+            anyhow::bail!(NO_SUBDOMAIN_MSG)
+        }
+        // an unavailable name shouldn't end the publish, let the user pick another one
+        let registered = validate_subdomain_name(&name)
+            .and_then(|_| register_subdomain(&name, user, account_id));
+        match registered {
+            Ok(()) => return Ok(()),
+            Err(e) => StdOut::user_error(&e.to_string()),
+        }
+    }
 }
 
 pub fn set_subdomain(name: &str, user: &GlobalUser, target: &Target) -> Result<()> {
+    validate_subdomain_name(name)?;
     let account_id = target.account_id.load()?;
     let subdomain = Subdomain::get(account_id, user)?;
     if let Some(subdomain) = subdomain {
@@ -159,7 +172,7 @@ pub fn set_subdomain(name: &str, user: &GlobalUser, target: &Target) -> Result<(
         }
     }
 
-    register_subdomain(name, user, target)
+    register_subdomain(name, user, account_id)
 }
 
 pub fn get_subdomain(user: &GlobalUser, target: &Target) -> Result<()> {
@@ -176,22 +189,18 @@ pub fn get_subdomain(user: &GlobalUser, target: &Target) -> Result<()> {
 }
 
 fn get_subdomain_scripts(account_id: &str, user: &GlobalUser) -> Result<Vec<String>> {
-    let addr = scripts_addr(account_id);
+    let addr = scripts_addr(account_id)?;
 
     let client = http::legacy_auth_client(user);
 
     let response = client
         .get(&addr)
         .query(&[("include_subdomain_availability", "1")])
-        .send()?;
+        .send_logged()?;
 
-    if !response.status().is_success() {
-        anyhow::bail!(
-            "{} There was an error fetching scripts.\n Status Code: {}\n Msg: {}",
-            emoji::WARN,
-            response.status(),
-            response.text()?,
-        )
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, response.text()?))
     }
     let response: ScriptResponse = serde_json::from_str(&response.text()?)?;
     let mut scripts: Vec<String> = Vec::new();
@@ -203,9 +212,30 @@ fn get_subdomain_scripts(account_id: &str, user: &GlobalUser) -> Result<Vec<Stri
     Ok(scripts)
 }
 
-fn scripts_addr(account_id: &str) -> String {
-    format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
-        account_id
-    )
+fn scripts_addr(account_id: &str) -> Result<String> {
+    http::api_url(&format!("accounts/{}/workers/scripts", account_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_validates_subdomain_names() {
+        for name in &["my-team", "team42", "a"] {
+            assert!(validate_subdomain_name(name).is_ok(), "{}", name);
+        }
+        let too_long = "a".repeat(64);
+        for name in &[
+            "",
+            "My-Team",
+            "my_team",
+            "-team",
+            "team-",
+            "my.team",
+            too_long.as_str(),
+        ] {
+            assert!(validate_subdomain_name(name).is_err(), "{}", name);
+        }
+    }
 }
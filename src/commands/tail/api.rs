@@ -1,10 +1,10 @@
 use crate::http;
 use crate::settings::global_user::GlobalUser;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use cloudflare::{
     endpoints::workers::{CreateTail, CreateTailParams, DeleteTail},
-    framework::{async_api::ApiClient, response::ApiFailure},
+    framework::response::ApiFailure,
 };
 use reqwest::StatusCode;
 use url::Url;
@@ -49,15 +49,17 @@ impl Tail {
     /// If successful, the tail must be kept-alive before its expiration time.
     pub async fn create(&mut self) -> Result<()> {
         match self.id {
-            None => match http::cf_v4_api_client_async(&self.user)?
-                .request(&CreateTail {
+            None => match http::request_audited_async(
+                &http::cf_v4_api_client_async(&self.user)?,
+                &CreateTail {
                     account_identifier: &self.account_id,
                     script_name: &self.script_name,
                     params: CreateTailParams {
                         url: self.url.clone().map(String::from),
                     },
-                })
-                .await
+                },
+            )
+            .await
             {
                 Ok(response) => {
                     let tail = response.result;
@@ -68,9 +70,7 @@ impl Tail {
                     )?);
                     Ok(())
                 }
-                Err(err) => {
-                    anyhow::bail!("Failed to create tail: {}", http::format_error(err, None))
-                }
+                Err(err) => Err(http::api_error(err, None).context("Failed to create tail")),
             },
             _ => Ok(()),
         }
@@ -79,13 +79,15 @@ impl Tail {
     /// Deletes the tail and unattaches it from the Worker.
     pub async fn delete(&mut self) -> Result<()> {
         match self.id.clone() {
-            Some(tail_id) => match http::cf_v4_api_client_async(&self.user)?
-                .request(&DeleteTail {
+            Some(tail_id) => match http::request_audited_async(
+                &http::cf_v4_api_client_async(&self.user)?,
+                &DeleteTail {
                     account_identifier: &self.account_id,
                     script_name: &self.script_name,
                     tail_id: &tail_id,
-                })
-                .await
+                },
+            )
+            .await
             {
                 Ok(_) | Err(ApiFailure::Error(StatusCode::NOT_FOUND, _)) => {
                     log::info!("Deleted tail: {}", &tail_id);
@@ -93,9 +95,7 @@ impl Tail {
                     self.url = None;
                     Ok(())
                 }
-                Err(err) => {
-                    anyhow::bail!("Failed to delete tail: {}", http::format_error(err, None))
-                }
+                Err(err) => Err(http::api_error(err, None).context("Failed to delete tail")),
             },
             _ => Ok(()),
         }
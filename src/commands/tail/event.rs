@@ -22,6 +22,36 @@ pub struct TraceEvent {
     pub logs: Vec<LogItem>,
     pub exceptions: Vec<ExceptionItem>,
     pub event: EventItem,
+    /// Milliseconds of CPU time the event used, when the runtime reports it
+    #[serde(default, rename = "cpuTime", skip_serializing_if = "Option::is_none")]
+    pub cpu_time: Option<f64>,
+    /// Milliseconds from the start to the end of the event, when the runtime reports it
+    #[serde(default, rename = "wallTime", skip_serializing_if = "Option::is_none")]
+    pub wall_time: Option<f64>,
+}
+
+impl TraceEvent {
+    /// Whether the event used at least `threshold` milliseconds of CPU time. Events without
+    /// a CPU time aren't considered slow.
+    pub fn is_slow(&self, threshold: u64) -> bool {
+        self.cpu_time
+            .map_or(false, |cpu_time| cpu_time >= threshold as f64)
+    }
+
+    fn timing(&self) -> Option<String> {
+        let mut timing = Vec::new();
+        if let Some(cpu_time) = self.cpu_time {
+            timing.push(format!("{:.0}ms CPU", cpu_time));
+        }
+        if let Some(wall_time) = self.wall_time {
+            timing.push(format!("{:.0}ms wall", wall_time));
+        }
+        if timing.is_empty() {
+            None
+        } else {
+            Some(timing.join(", "))
+        }
+    }
 }
 
 /// An event item.
@@ -61,7 +91,8 @@ pub struct LogItem {
 pub struct ExceptionItem {
     pub name: String,
     pub message: String,
-    // TODO(soon): we really need to implement stacktraces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack: Option<String>,
 }
 
 impl Display for TraceEvent {
@@ -77,6 +108,7 @@ impl Display for TraceEvent {
             "canceled" => style("Canceled").yellow(),
             "exception" => style("Error").red(),
             "exceededCpu" => style("Exceeded Limit").red(),
+            "exceededMemory" => style("Exceeded Memory").red(),
             _ => style("System Error").red(),
         };
         match self.event.request.clone() {
@@ -102,6 +134,9 @@ impl Display for TraceEvent {
                 _ => write!(f, "[{}] [?] [{}] <unknown event>", timestamp, outcome),
             },
         }?;
+        if let Some(timing) = self.timing() {
+            write!(f, " {}", style(format!("({})", timing)).dim())?;
+        }
         for log in self.logs.iter() {
             let prefix = style("|").dim();
             write!(f, "\n {} {}", prefix, log)?;
@@ -157,6 +192,45 @@ impl Display for ExceptionItem {
     fn fmt(&self, f: &mut Formatter) -> Result {
         let name = style(&self.name).red().bold();
         let message = style(&self.message).red();
-        write!(f, "[{}] {}", name, message)
+        write!(f, "[{}] {}", name, message)?;
+        if let Some(stack) = &self.stack {
+            // the first line of a stack is the name and message again
+            for line in stack.lines().skip(1) {
+                write!(f, "\n     {}", style(line.trim()).dim())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_the_cpu_time_of_events() {
+        let event: TraceEvent = serde_json::from_str(
+            r#"{
+                "eventTimestamp": 1650000000000,
+                "outcome": "ok",
+                "logs": [],
+                "exceptions": [],
+                "event": { "request": { "url": "https://example.com/", "method": "GET" } },
+                "cpuTime": 62.4,
+                "wallTime": 140
+            }"#,
+        )
+        .unwrap();
+        assert!(event.is_slow(50));
+        assert!(!event.is_slow(100));
+        assert_eq!(event.timing().unwrap(), "62ms CPU, 140ms wall");
+
+        let event = TraceEvent {
+            cpu_time: None,
+            wall_time: None,
+            ..event
+        };
+        assert!(!event.is_slow(0));
+        assert_eq!(event.timing(), None);
     }
 }
@@ -3,6 +3,7 @@ use crate::http::feature::user_agent;
 use super::api::Tail;
 use super::event::{TraceEvent, PROTOCOL_ID};
 use super::filter::TraceFilter;
+use crate::sourcemap::SourceMaps;
 
 use anyhow::Result;
 use futures_util::{SinkExt, StreamExt};
@@ -41,6 +42,14 @@ pub struct TailOptions {
     pub once: bool,
     #[serde(skip_serializing)]
     pub format: TailFormat,
+    /// Only events that used at least this many milliseconds of CPU time are printed.
+    /// The runtime can't filter on it, so it's done here.
+    #[serde(skip_serializing)]
+    pub slow_threshold: Option<u64>,
+    /// Locations in the stack traces and logs of events are mapped back to the sources with
+    /// these, when set.
+    #[serde(skip_serializing)]
+    pub symbolicate: Option<SourceMaps>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub filters: Vec<Box<dyn TraceFilter>>,
 }
@@ -76,43 +85,69 @@ impl WebSocketTail {
         }
     }
 
-    /// Reads a message from the WebSocket and prints it.
-    pub async fn read_once(&mut self) -> Result<()> {
+    /// Reads a message from the WebSocket and prints it. Returns whether an event was printed.
+    pub async fn read_once(&mut self) -> Result<bool> {
         tokio::select! {
             frame = self.websocket.next() => {
                 match frame {
                     Some(Ok(message)) if message.is_text() || message.is_binary() => {
-                        match self.options.format {
-                            TailFormat::Json => {
-                                println!("{}", message);
-                                Ok(())
-                            },
-                            TailFormat::Pretty => match serde_json::from_str::<TraceEvent>(&message.to_string()) {
-                                Ok(event) => {
-                                    println!("{}", event);
-                                    Ok(())
-                                },
-                                Err(err) => {
-                                    log::debug!("Failed to pretty-print tail: {}", err);
-                                    self.close(CloseCode::Protocol, "wrangler is closing due to a protocol violation").await
-                                },
-                            }
-                        }
+                        self.print(&message.to_string()).await
                     },
                     Some(Ok(message)) if message.is_close() => {
                         anyhow::bail!("Received close from WebSocket tail: {}", message)
                     },
                     Some(Err(err)) => {
                         log::debug!("Received error from WebSocket tail: {}", err);
-                        self.close(CloseCode::Abnormal, "wrangler is closing due to an error").await
+                        self.close(CloseCode::Abnormal, "wrangler is closing due to an error").await.map(|_| false)
                     },
-                    _ => Ok(()),
+                    _ => Ok(false),
                 }
             },
             _ = tokio::signal::ctrl_c() => {
-                self.close(CloseCode::Away, "wrangler is closing due to ctrl-c").await
+                self.close(CloseCode::Away, "wrangler is closing due to ctrl-c").await.map(|_| false)
+            }
+        }
+    }
+
+    /// Prints a `TraceEvent` in the format of the tail, unless it's faster than the slow
+    /// threshold. Returns whether it was printed.
+    async fn print(&mut self, message: &str) -> Result<bool> {
+        let symbolicated;
+        let message = match (&self.options.symbolicate, serde_json::from_str(message)) {
+            (Some(source_maps), Ok(mut value)) => {
+                source_maps.symbolicate_json(&mut value);
+                symbolicated = serde_json::to_string(&value)?;
+                &symbolicated
             }
+            _ => message,
+        };
+        if let (TailFormat::Json, None) = (&self.options.format, self.options.slow_threshold) {
+            println!("{}", message);
+            return Ok(true);
+        }
+        let event = match serde_json::from_str::<TraceEvent>(message) {
+            Ok(event) => event,
+            Err(err) => {
+                log::debug!("Failed to parse tail: {}", err);
+                return self
+                    .close(
+                        CloseCode::Protocol,
+                        "wrangler is closing due to a protocol violation",
+                    )
+                    .await
+                    .map(|_| false);
+            }
+        };
+        if let Some(threshold) = self.options.slow_threshold {
+            if !event.is_slow(threshold) {
+                return Ok(false);
+            }
+        }
+        match self.options.format {
+            TailFormat::Json => println!("{}", message),
+            TailFormat::Pretty => println!("{}", event),
         }
+        Ok(true)
     }
 
     /// Reads and prints messages from the WebSocket in a loop.
@@ -123,7 +158,7 @@ impl WebSocketTail {
             }
             match self.read_once().await {
                 Err(err) => break Err(err),
-                Ok(_) if self.options.once => {
+                Ok(true) if self.options.once => {
                     break self
                         .close(
                             CloseCode::Normal,
@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use toml_edit::{Document, Item, Value};
+
+use crate::settings::encrypted_vars;
+use crate::settings::toml::confirm_write;
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdOut};
+
+/// Asks for the value of the var `name`, and writes it encrypted to the `[vars]` of the
+/// configuration file at `config_path`, or of the environment `env`
+pub fn encrypt(config_path: &Path, env: Option<&str>, name: &str) -> Result<()> {
+    let value = interactive::get_user_input_multi_line(
+        &format!(
+            "Enter the value you'd like encrypted into the var {}:",
+            name
+        ),
+        "pipe the value to wrangler",
+    )?;
+    if value.is_empty() {
+        anyhow::bail!("The value of a var cannot be empty.")
+    }
+
+    let encrypted = encrypted_vars::encrypt(name, &value)?;
+    let contents = fs::read_to_string(config_path)?;
+    let contents = set_var(&contents, env, name, &encrypted)?;
+    confirm_write(config_path, &contents)?;
+    fs::write(config_path, contents)?;
+    StdOut::success(&format!(
+        "Encrypted {} into {}. It is decrypted and bound as a secret when you publish.",
+        name,
+        config_path.display()
+    ));
+    Ok(())
+}
+
+// Sets the var, keeping the formatting and comments of the rest of the file
+fn set_var(contents: &str, env: Option<&str>, name: &str, value: &str) -> Result<String> {
+    let mut doc = contents
+        .parse::<Document>()
+        .map_err(|e| anyhow!("toml_edit failed to parse the configuration file. {}", e))?;
+
+    let section = match env {
+        Some(env) if doc["env"][env].is_none() => {
+            anyhow::bail!("There is no environment {} in the configuration file", env)
+        }
+        Some(env) => &mut doc["env"][env],
+        None => &mut doc.root,
+    };
+    let table = match section.as_table_mut() {
+        Some(table) => table,
+        None => anyhow::bail!("Expected a table to add vars to"),
+    };
+
+    let vars = table.entry("vars");
+    if vars.is_none() {
+        *vars = toml_edit::table();
+    }
+    match vars {
+        // [vars]
+        Item::Table(vars) => *vars.entry(name) = toml_edit::value(value),
+        // vars = { ... }
+        Item::Value(Value::InlineTable(vars)) => {
+            vars.remove(name);
+            vars.get_or_insert(name, value);
+            vars.fmt();
+        }
+        _ => anyhow::bail!("vars in your configuration file should be a table"),
+    }
+    Ok(doc.to_string_in_original_order())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_sets_vars_of_the_top_level_and_environments() -> Result<()> {
+        let config = "name = \"worker\"\n\n[vars]\nMODE = \"live\" # or \"test\"\n\n[env.staging]\nvars = { MODE = \"test\" }\n";
+
+        let updated = set_var(config, None, "TOKEN", "encrypted:v1:abcd")?;
+        let parsed: toml::Value = toml::from_str(&updated)?;
+        assert_eq!(parsed["vars"]["TOKEN"].as_str(), Some("encrypted:v1:abcd"));
+        assert!(updated.contains("MODE = \"live\" # or \"test\""));
+
+        let updated = set_var(config, Some("staging"), "MODE", "encrypted:v1:abcd")?;
+        let parsed: toml::Value = toml::from_str(&updated)?;
+        assert_eq!(
+            parsed["env"]["staging"]["vars"]["MODE"].as_str(),
+            Some("encrypted:v1:abcd")
+        );
+
+        assert!(set_var(config, Some("production"), "TOKEN", "encrypted:v1:abcd").is_err());
+        Ok(())
+    }
+}
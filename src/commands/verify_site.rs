@@ -0,0 +1,101 @@
+use std::env;
+
+use anyhow::Result;
+use rand::seq::IteratorRandom;
+use url::Url;
+
+use crate::commands::kv;
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::kv::namespace;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::sites::{self, IntegrityManifest, INTEGRITY_KEY, INTEGRITY_SECRET_VAR};
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+/// Fetches `sample` random assets of the site of `target` from `url`, and checks them
+/// against the integrity manifest uploaded when the site was published
+pub fn verify_site(user: &GlobalUser, target: &Target, url: &Url, sample: usize) -> Result<()> {
+    let manifest = fetch_manifest(user, target)?;
+    match env::var(INTEGRITY_SECRET_VAR) {
+        Ok(secret) => manifest.verify(&secret)?,
+        Err(_) => StdErr::warn(&format!(
+            "{} isn't set, so the signature of the integrity manifest isn't checked",
+            INTEGRITY_SECRET_VAR
+        )),
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut paths = manifest.assets.keys().choose_multiple(&mut rng, sample);
+    paths.sort();
+    StdErr::working(&format!(
+        "Checking {} of the {} assets of {}",
+        paths.len(),
+        manifest.assets.len(),
+        url
+    ));
+
+    // without a trailing slash, joining would replace the last segment of `url`
+    let mut base = url.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+    let client = http::client();
+    let mut mismatches = Vec::new();
+    for path in paths {
+        let asset_url = base.join(path.trim_start_matches('/'))?;
+        let res = client.get(asset_url.as_str()).send_logged()?;
+        let status = res.status();
+        if !status.is_success() {
+            mismatches.push(format!("{} responded with {}", path, status));
+            continue;
+        }
+        if sites::sha256(&res.bytes()?) != manifest.assets[path] {
+            mismatches.push(format!("{} doesn't match its published hash", path));
+        }
+    }
+
+    if mismatches.is_empty() {
+        StdOut::success("Every asset checked matches the published site");
+        return Ok(());
+    }
+    for mismatch in &mismatches {
+        StdOut::warn(mismatch);
+    }
+    anyhow::bail!(
+        "{} asset(s) don't match the published site, it may be partially synced or tampered with",
+        mismatches.len()
+    )
+}
+
+fn fetch_manifest(user: &GlobalUser, target: &Target) -> Result<IntegrityManifest> {
+    let account_id = target.account_id.load()?;
+    let title = sites::namespace_title(target, false);
+    let namespace_id = match namespace::list(&http::cf_v4_client(user)?, account_id)?
+        .into_iter()
+        .find(|ns| ns.title == title)
+    {
+        Some(namespace) => namespace.id,
+        None => anyhow::bail!("{} has no published site", target.name),
+    };
+
+    let res = http::legacy_auth_client(user)
+        .get(&http::api_url(&format!(
+            "accounts/{}/storage/kv/namespaces/{}/values/{}",
+            account_id,
+            namespace_id,
+            kv::url_encode_key(INTEGRITY_KEY)
+        ))?)
+        .send_logged()?;
+    let status = res.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        anyhow::bail!(
+            "{} was published without an integrity manifest, set integrity = true under [site] and publish it again",
+            target.name
+        )
+    }
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json()?)
+}
@@ -1,14 +1,22 @@
 use crate::http;
-use crate::settings::global_user::GlobalUser;
+use crate::settings::global_user::{self, GlobalUser};
 use crate::terminal::message::{Message, StdOut};
 use crate::terminal::{emoji, styles};
-use cloudflare::endpoints::account::{self, Account};
+use cloudflare::endpoints::account;
 use cloudflare::endpoints::user::GetUserDetails;
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
 
 use anyhow::Result;
 use prettytable::{Cell, Row, Table};
+use serde::{Deserialize, Serialize};
+
+/// The parts of an account wrangler cares about, small enough to be cached between commands
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct AccountSummary {
+    pub id: String,
+    pub name: String,
+}
 
 /// Return a string representing the token type based on user
 fn get_token_type(
@@ -45,6 +53,12 @@ pub fn whoami(user: &GlobalUser) -> Result<()> {
     let accounts = fetch_accounts(user)?;
     let table = format_accounts(user, accounts, &mut missing_permissions);
     let mut msg = format!("{} You are logged in with {}!\n", emoji::WAVING, auth);
+    if let Some(profile) = global_user::profile() {
+        msg.push_str(&format!(
+            "\nThe credentials are those of the profile {}.\n",
+            styles::highlight(profile)
+        ));
+    }
     let num_permissions_missing = missing_permissions.len();
     if num_permissions_missing > 0 {
         let login_msg = styles::highlight("`wrangler login`");
@@ -116,17 +130,28 @@ fn fetch_auth_token_email(
                 }
                 Ok(None)
             }
-            ApiFailure::Invalid(_) => anyhow::bail!(http::format_error(e, None)),
+            ApiFailure::Invalid(_) => Err(http::api_error(e, None)),
         },
     }
 }
 
 /// Fetch the accounts associated with a user
-pub(crate) fn fetch_accounts(user: &GlobalUser) -> Result<Vec<Account>> {
+pub(crate) fn fetch_accounts(user: &GlobalUser) -> Result<Vec<AccountSummary>> {
+    http::cache::cached(user, "accounts", || fetch_accounts_uncached(user))
+}
+
+pub(crate) fn fetch_accounts_uncached(user: &GlobalUser) -> Result<Vec<AccountSummary>> {
     let client = http::cf_v4_client(user)?;
     let response = client.request(&account::ListAccounts { params: None });
     match response {
-        Ok(res) => Ok(res.result),
+        Ok(res) => Ok(res
+            .result
+            .into_iter()
+            .map(|account| AccountSummary {
+                id: account.id,
+                name: account.name,
+            })
+            .collect()),
         Err(e) => {
             match e {
                 ApiFailure::Error(_, ref api_errors) => {
@@ -143,7 +168,7 @@ pub(crate) fn fetch_accounts(user: &GlobalUser) -> Result<Vec<Account>> {
                 ApiFailure::Invalid(_) => StdOut::info("Something went wrong in processing a request. Please consider raising an issue at https://github.com/cloudflare/wrangler/issues"),
             }
 
-            anyhow::bail!(http::format_error(e, None))
+            Err(http::api_error(e, None))
         }
     }
 }
@@ -151,7 +176,7 @@ pub(crate) fn fetch_accounts(user: &GlobalUser) -> Result<Vec<Account>> {
 /// Format a user's accounts into a nice table
 fn format_accounts(
     user: &GlobalUser,
-    accounts: Vec<Account>,
+    accounts: Vec<AccountSummary>,
     missing_permissions: &mut Vec<String>,
 ) -> Table {
     let mut table = Table::new();
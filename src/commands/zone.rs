@@ -0,0 +1,560 @@
+use anyhow::Result;
+use prettytable::{Cell, Row, Table};
+use reqwest::blocking::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::route::{self, Route};
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::terminal::styles;
+
+// What a proxied record points to when there's no origin behind it, only workers
+const PLACEHOLDER_ADDRESS: &str = "100::";
+
+// Zone settings that change what a worker on a route sees or how its responses are served
+const WORKER_SETTINGS: &[&str] = &["ssl", "always_use_https", "cache_level"];
+
+// Page rule actions that answer a request before any worker sees it
+const REDIRECTING_ACTIONS: &[&str] = &["forwarding_url", "always_use_https"];
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    result: T,
+}
+
+#[derive(Deserialize)]
+struct ZoneSetting {
+    id: String,
+    value: Value,
+}
+
+#[derive(Deserialize)]
+struct PageRule {
+    priority: i64,
+    targets: Vec<PageRuleTarget>,
+    actions: Vec<PageRuleAction>,
+}
+
+#[derive(Deserialize)]
+struct PageRuleTarget {
+    constraint: PageRuleConstraint,
+}
+
+#[derive(Deserialize)]
+struct PageRuleConstraint {
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct PageRuleAction {
+    id: String,
+    #[serde(default)]
+    value: Value,
+}
+
+/// Prints the settings of a zone that affect workers running on its routes, and the
+/// active page rules whose pattern overlaps one of `routes`.
+pub fn inspect(zone_id: &str, routes: &[String], user: &GlobalUser) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+
+    let settings: Vec<ZoneSetting> = get(&client, &format!("zones/{}/settings", zone_id))?;
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Setting"),
+        Cell::new("Value"),
+        Cell::new("Effect on workers"),
+    ]));
+    for setting in settings
+        .iter()
+        .filter(|s| WORKER_SETTINGS.contains(&s.id.as_str()))
+    {
+        let value = display_value(&setting.value);
+        table.add_row(Row::new(vec![
+            Cell::new(&setting.id),
+            Cell::new(&value),
+            Cell::new(setting_effect(&setting.id, &value)),
+        ]));
+    }
+    print!("{}", table);
+
+    let page_rules: Vec<PageRule> = get(
+        &client,
+        &format!("zones/{}/pagerules?status=active&order=priority", zone_id),
+    )?;
+    let mut overlapping = Vec::new();
+    for rule in &page_rules {
+        for target in &rule.targets {
+            let pattern = target.constraint.value.as_str();
+            let matching: Vec<&str> = routes
+                .iter()
+                .filter(|route| patterns_overlap(pattern, route))
+                .map(String::as_str)
+                .collect();
+            if !matching.is_empty() {
+                overlapping.push((rule, pattern, matching));
+            }
+        }
+    }
+
+    if overlapping.is_empty() {
+        StdOut::info("No active page rules overlap the routes of your worker");
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Priority"),
+        Cell::new("Page rule"),
+        Cell::new("Actions"),
+        Cell::new("Overlapping routes"),
+    ]));
+    for (rule, pattern, routes) in overlapping {
+        let actions: Vec<String> = rule
+            .actions
+            .iter()
+            .map(|action| match &action.value {
+                Value::Null => action.id.clone(),
+                value => format!("{}: {}", action.id, display_value(value)),
+            })
+            .collect();
+        table.add_row(Row::new(vec![
+            Cell::new(&rule.priority.to_string()),
+            Cell::new(pattern),
+            Cell::new(&actions.join("\n")),
+            Cell::new(&routes.join("\n")),
+        ]));
+    }
+    print!("{}", table);
+    Ok(())
+}
+
+/// Fails unless the zone exists and the credentials in use are allowed to read it
+pub fn get_zone(zone_id: &str, user: &GlobalUser) -> Result<()> {
+    zone_details(zone_id, user)?;
+    Ok(())
+}
+
+/// Fails unless the hostname of every one of `routes` is within the zone, which the API
+/// otherwise only reports as an opaque error when creating the route
+pub fn check_routes(zone_id: &str, routes: &[Route], user: &GlobalUser) -> Result<()> {
+    let zone = zone_details(zone_id, user)?;
+    let zone_name = zone["name"].as_str().unwrap_or_default();
+    for route in routes {
+        if !route::is_in_zone(&route.pattern, zone_name) {
+            anyhow::bail!(
+                "The route {} isn't on {}, the zone with id {}. Set zone_id to the zone of {}",
+                route.pattern,
+                zone_name,
+                zone_id,
+                route::hostname(&route.pattern)
+            )
+        }
+    }
+    Ok(())
+}
+
+/// Warns about what will keep requests for `routes` from reaching the worker, or send some of
+/// them to another one: active page rules that redirect requests matching a route, and routes
+/// of other workers that overlap one, where the more specific pattern wins
+pub fn preflight(zone_id: &str, routes: &[Route], user: &GlobalUser) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let page_rules: Vec<PageRule> = get(
+        &client,
+        &format!("zones/{}/pagerules?status=active&order=priority", zone_id),
+    )?;
+    let worker_routes: Vec<Route> = get(&client, &format!("zones/{}/workers/routes", zone_id))?;
+
+    let warnings = precedence_conflicts(routes, &page_rules, &worker_routes);
+    for warning in &warnings {
+        StdErr::warn(warning);
+    }
+    if !warnings.is_empty() {
+        StdErr::info(&format!(
+            "Run {} to see every page rule that applies to your routes",
+            styles::highlight("`wrangler zone inspect`")
+        ));
+    }
+    Ok(())
+}
+
+/// Offers to create a proxied placeholder record for the hostnames of `routes` that have no
+/// DNS record in the zone, since requests for them never reach the worker. Without a
+/// terminal to ask at, it only warns about them.
+pub fn ensure_dns_records(zone_id: &str, routes: &[Route], user: &GlobalUser) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let zone = zone_details(zone_id, user)?;
+    let zone_name = zone["name"].as_str().unwrap_or_default();
+    for hostname in dns_hostnames(routes) {
+        if has_record(&client, zone_id, &hostname)? {
+            continue;
+        }
+        // a wildcard record serves the hostnames below it that have none of their own
+        let mut covered = false;
+        for wildcard in wildcard_names(&hostname, zone_name) {
+            if has_record(&client, zone_id, &wildcard)? {
+                covered = true;
+                break;
+            }
+        }
+        if covered {
+            continue;
+        }
+
+        let missing = format!(
+            "{} has no DNS record, so requests for it won't reach your worker.",
+            hostname
+        );
+        if !interactive::is_interactive() {
+            StdErr::warn(&format!(
+                "{} Add a proxied record for it, like an AAAA record to {}",
+                missing, PLACEHOLDER_ADDRESS
+            ));
+            continue;
+        }
+        StdErr::warn(&missing);
+        let prompt = format!(
+            "Create a proxied AAAA record from {} to {}?",
+            hostname, PLACEHOLDER_ADDRESS
+        );
+        if interactive::confirm(&prompt)? {
+            match create_placeholder_record(&client, zone_id, &hostname) {
+                Ok(()) => StdErr::success(&format!(
+                    "Created a proxied record for {}",
+                    styles::url(&hostname)
+                )),
+                Err(e) => StdErr::warn(&format!(
+                    "Could not create a record for {}, add one from the dashboard: {}",
+                    hostname, e
+                )),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn has_record(client: &Client, zone_id: &str, name: &str) -> Result<bool> {
+    let records: Vec<Value> = get(
+        client,
+        &format!("zones/{}/dns_records?name={}", zone_id, name),
+    )?;
+    Ok(!records.is_empty())
+}
+
+// The wildcard records of the zone that could serve `hostname`, closest first: for
+// a.b.example.com on example.com, *.b.example.com and *.example.com
+fn wildcard_names(hostname: &str, zone_name: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut parent = hostname;
+    while let Some((_, rest)) = parent.split_once('.') {
+        if rest != zone_name && !rest.ends_with(&format!(".{}", zone_name)) {
+            break;
+        }
+        names.push(format!("*.{}", rest));
+        parent = rest;
+    }
+    names
+}
+
+fn create_placeholder_record(client: &Client, zone_id: &str, hostname: &str) -> Result<()> {
+    let addr = http::api_url(&format!("zones/{}/dns_records", zone_id))?;
+    let res = client
+        .post(&addr)
+        .json(&json!({
+            "type": "AAAA",
+            "name": hostname,
+            "content": PLACEHOLDER_ADDRESS,
+            "proxied": true,
+            // automatic
+            "ttl": 1,
+        }))
+        .send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(())
+}
+
+// The hostnames of `routes` a single record can serve, once each. Routes on every subdomain
+// need a wildcard record, which isn't a placeholder to create in passing.
+fn dns_hostnames(routes: &[Route]) -> Vec<String> {
+    let mut hostnames: Vec<String> = Vec::new();
+    for route in routes {
+        let pattern = route.pattern.splitn(2, "://").last().unwrap_or_default();
+        if pattern.starts_with('*') {
+            continue;
+        }
+        let hostname = route::hostname(pattern);
+        if !hostname.is_empty() && !hostnames.contains(&hostname) {
+            hostnames.push(hostname);
+        }
+    }
+    hostnames
+}
+
+// The name and plan of a zone hardly ever change, but every publish to a route asks for them
+fn zone_details(zone_id: &str, user: &GlobalUser) -> Result<Value> {
+    http::cache::cached(user, &format!("zone_{}", zone_id), || {
+        get(
+            &http::legacy_auth_client(user),
+            &format!("zones/{}", zone_id),
+        )
+    })
+}
+
+fn get<T: DeserializeOwned>(client: &Client, path: &str) -> Result<T> {
+    let addr = http::api_url(path)?;
+    let res = client.get(&addr).send_logged()?;
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.json::<ApiResponse<T>>()?.result)
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn setting_effect(id: &str, value: &str) -> &'static str {
+    match (id, value) {
+        ("ssl", "off") => "Requests reach your worker over HTTP only",
+        ("ssl", "flexible") => "fetch() to your origin uses HTTP, even for https:// URLs",
+        ("ssl", _) => "fetch() to your origin uses HTTPS",
+        ("always_use_https", "on") => "HTTP requests are redirected before reaching your worker",
+        ("cache_level", "bypass") => "Responses from fetch() are never cached",
+        ("cache_level", _) => "Responses from fetch() may be served from cache",
+        _ => "",
+    }
+}
+
+// Why requests for `routes` may not reach their worker, given the page rules and the worker
+// routes of their zone. Routes that have exactly the pattern of another worker's aren't
+// here, since publishing reports them as conflicts.
+fn precedence_conflicts(
+    routes: &[Route],
+    page_rules: &[PageRule],
+    worker_routes: &[Route],
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for route in routes {
+        for rule in page_rules {
+            let redirects: Vec<&str> = rule
+                .actions
+                .iter()
+                .map(|action| action.id.as_str())
+                .filter(|id| REDIRECTING_ACTIONS.contains(id))
+                .collect();
+            if redirects.is_empty() {
+                continue;
+            }
+            if let Some(target) = rule
+                .targets
+                .iter()
+                .find(|target| patterns_overlap(&target.constraint.value, &route.pattern))
+            {
+                warnings.push(format!(
+                    "The page rule {} ({}) redirects requests matching {} before they reach your worker",
+                    target.constraint.value,
+                    redirects.join(", "),
+                    route.pattern
+                ));
+            }
+        }
+
+        for other in worker_routes {
+            if other.script == route.script
+                || other.pattern == route.pattern
+                || !patterns_overlap(&other.pattern, &route.pattern)
+            {
+                continue;
+            }
+            let worker = other.script.as_deref().unwrap_or("no worker");
+            let (more_specific, less_specific) = if is_more_specific(&other.pattern, &route.pattern)
+            {
+                (worker, "your worker")
+            } else {
+                ("your worker", worker)
+            };
+            warnings.push(format!(
+                "The route {} of {} overlaps {}: requests matching both go to {}, whose route is more specific, rather than to {}",
+                other.pattern, worker, route.pattern, more_specific, less_specific
+            ));
+        }
+    }
+    warnings
+}
+
+// Of two overlapping patterns, the one that matches fewer requests: the one with the longer
+// part before its first wildcard
+fn is_more_specific(a: &str, b: &str) -> bool {
+    let literal_len = |pattern: &str| {
+        let pattern = pattern
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+        pattern.split('*').next().unwrap_or_default().len()
+    };
+    literal_len(a) > literal_len(b)
+}
+
+// Route and page rule patterns only support `*` wildcards, which match any run of
+// characters, except that a leading one only matches within the hostname. Two of them
+// overlap when some URL matches both, worked out by walking both patterns at once and
+// letting a wildcard of either take up what the other has.
+fn patterns_overlap(a: &str, b: &str) -> bool {
+    let split_scheme = |pattern: &str| match pattern.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme.to_string()), rest.as_bytes().to_vec()),
+        None => (None, pattern.as_bytes().to_vec()),
+    };
+    let ((a_scheme, a), (b_scheme, b)) = (split_scheme(a), split_scheme(b));
+    if let (Some(a_scheme), Some(b_scheme)) = (a_scheme, b_scheme) {
+        if a_scheme != b_scheme {
+            return false;
+        }
+    }
+
+    let mut seen = vec![vec![false; b.len() + 1]; a.len() + 1];
+    let mut pending = vec![(0, 0)];
+    while let Some((i, j)) = pending.pop() {
+        if seen[i][j] {
+            continue;
+        }
+        seen[i][j] = true;
+        if i == a.len() && j == b.len() {
+            return true;
+        }
+        if a.get(i) == Some(&b'*') {
+            pending.push((i + 1, j));
+            if j < b.len() && (i > 0 || b[j] != b'/') {
+                pending.push((i, j + 1));
+            }
+        }
+        if b.get(j) == Some(&b'*') {
+            pending.push((i, j + 1));
+            if i < a.len() && (j > 0 || a[i] != b'/') {
+                pending.push((i + 1, j));
+            }
+        }
+        if let (Some(x), Some(y)) = (a.get(i), b.get(j)) {
+            if x == y && *x != b'*' {
+                pending.push((i + 1, j + 1));
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_overlapping_patterns() {
+        assert!(patterns_overlap("example.com/*", "example.com/api/*"));
+        assert!(patterns_overlap(
+            "https://example.com/api/*",
+            "example.com/api/v1"
+        ));
+        assert!(patterns_overlap("*example.com/*", "example.com/api/*"));
+        assert!(patterns_overlap("*.example.com/*", "shop.example.com/api"));
+        assert!(patterns_overlap("*/api/*", "example.com/api/v1"));
+        assert!(!patterns_overlap("example.com/blog/*", "example.com/api/*"));
+        assert!(!patterns_overlap("other.com/*", "example.com/*"));
+        // a leading wildcard still has to be followed by the rest of the pattern
+        assert!(!patterns_overlap("*.example.com/*", "example.com/*"));
+        assert!(!patterns_overlap("*.other.com/*", "example.com/api/*"));
+        assert!(!patterns_overlap("*/api/*", "example.com/blog/api/*"));
+        assert!(!patterns_overlap(
+            "http://example.com/*",
+            "https://example.com/*"
+        ));
+    }
+
+    #[test]
+    fn it_warns_about_redirects_and_overlapping_workers() {
+        let route = |pattern: &str, script: &str| Route {
+            id: None,
+            script: Some(script.to_string()),
+            pattern: pattern.to_string(),
+        };
+        let page_rules: Vec<PageRule> = serde_json::from_value(json!([
+            {
+                "priority": 2,
+                "targets": [{ "constraint": { "value": "example.com/old/*" } }],
+                "actions": [{ "id": "forwarding_url", "value": { "url": "https://example.com/new" } }]
+            },
+            {
+                "priority": 1,
+                "targets": [{ "constraint": { "value": "example.com/*" } }],
+                "actions": [{ "id": "cache_level", "value": "bypass" }]
+            }
+        ]))
+        .unwrap();
+        let worker_routes = vec![
+            route("example.com/*", "site"),
+            route("example.com/old/*", "legacy"),
+            route("example.com/api/*", "api"),
+            route("example.com/blog/*", "blog"),
+        ];
+
+        let warnings = precedence_conflicts(
+            &[
+                route("example.com/old/*", "api"),
+                route("example.com/api/*", "api"),
+            ],
+            &page_rules,
+            &worker_routes,
+        );
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("forwarding_url"));
+        assert!(
+            warnings[1].starts_with("The route example.com/* of site overlaps example.com/old/*")
+        );
+        assert!(warnings[1]
+            .contains("go to your worker, whose route is more specific, rather than to site"));
+        assert!(warnings[2].contains("overlaps example.com/api/*"));
+    }
+
+    #[test]
+    fn it_looks_up_the_records_of_exact_hostnames() {
+        let routes: Vec<Route> = [
+            "shop.example.com/*",
+            "https://shop.example.com/api/*",
+            "*.example.com/*",
+            "*example.com/*",
+            "example.com/blog/*",
+        ]
+        .iter()
+        .map(|pattern| Route {
+            id: None,
+            script: None,
+            pattern: pattern.to_string(),
+        })
+        .collect();
+        assert_eq!(
+            dns_hostnames(&routes),
+            vec!["shop.example.com", "example.com"]
+        );
+    }
+
+    #[test]
+    fn it_looks_for_the_wildcard_records_above_a_hostname() {
+        assert_eq!(
+            wildcard_names("a.b.example.com", "example.com"),
+            vec!["*.b.example.com", "*.example.com"]
+        );
+        assert_eq!(
+            wildcard_names("shop.example.com", "example.com"),
+            vec!["*.example.com"]
+        );
+        assert!(wildcard_names("example.com", "example.com").is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use prettytable::{Cell, Row, Table};
+
+use crate::http;
+use crate::terminal::message::{Message, StdErr};
+
+/// The response header a worker can set to say which version of it served a request
+pub const VERSION_HEADER: &str = "x-worker-version";
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends a HEAD request to a URL each of `routes` matches, and prints the status and
+/// version each responds with, so routes shadowed by page rules or other workers stand out
+pub fn print_route_health(routes: &[String]) {
+    StdErr::working("Checking the routes respond");
+    let client = http::client();
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Route"),
+        Cell::new("URL"),
+        Cell::new("Status"),
+        Cell::new("Version"),
+    ]));
+
+    let mut failing = 0;
+    for route in routes {
+        let url = match probe_url(route) {
+            Some(url) => url,
+            None => {
+                table.add_row(Row::new(vec![
+                    Cell::new(route),
+                    Cell::new("-"),
+                    Cell::new("no URL to check"),
+                    Cell::new("-"),
+                ]));
+                continue;
+            }
+        };
+        let (status, version) = match client.head(&url).timeout(PROBE_TIMEOUT).send() {
+            Ok(res) => {
+                if !res.status().is_success() {
+                    failing += 1;
+                }
+                let version = res
+                    .headers()
+                    .get(VERSION_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("-")
+                    .to_string();
+                (res.status().to_string(), version)
+            }
+            Err(e) => {
+                failing += 1;
+                log::info!("HEAD {} failed: {}", url, e);
+                ("unreachable".to_string(), "-".to_string())
+            }
+        };
+        table.add_row(Row::new(vec![
+            Cell::new(route),
+            Cell::new(&url),
+            Cell::new(&status),
+            Cell::new(&version),
+        ]));
+    }
+
+    eprint!("{}", table);
+    if failing > 0 {
+        StdErr::warn(&format!(
+            "{} route(s) didn't respond successfully, they may be shadowed by a page rule or another worker's more specific route",
+            failing
+        ));
+    }
+}
+
+// A URL the pattern matches, made of its hostname and the literal part of its path. Patterns
+// on every subdomain of a hostname match no URL in particular.
+fn probe_url(pattern: &str) -> Option<String> {
+    if pattern.starts_with("*.") {
+        return None;
+    }
+    let pattern = pattern.trim_start_matches('*');
+    let (host, path) = match pattern.find('/') {
+        Some(i) => pattern.split_at(i),
+        None => (pattern, "/"),
+    };
+    Some(format!("https://{}{}", host, path.trim_end_matches('*')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_urls_routes_match() {
+        assert_eq!(
+            probe_url("example.com/*").as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(
+            probe_url("*example.com/api/v1*").as_deref(),
+            Some("https://example.com/api/v1")
+        );
+        assert_eq!(
+            probe_url("api.example.com").as_deref(),
+            Some("https://api.example.com/")
+        );
+        assert_eq!(probe_url("*.example.com/*"), None);
+    }
+}
@@ -1,14 +1,21 @@
+mod health;
 mod schedule;
 mod zoned;
 mod zoneless;
 
-use anyhow::Result;
-use indicatif::{ProgressBar, ProgressStyle};
+pub use health::print_route_health;
 pub use schedule::ScheduleTarget;
 pub use zoned::ZonedTarget;
 pub use zoneless::ZonelessTarget;
 
+use anyhow::Result;
+use indicatif::ProgressBar;
+use zoned::RouteUploadResult;
+
+use crate::http::{CloudflareApi, HttpApi};
 use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Route;
+use crate::terminal::ui;
 
 /// A set of deploy targets.
 pub type DeploymentSet = Vec<DeployTarget>;
@@ -17,37 +24,191 @@ pub type DeploymentSet = Vec<DeployTarget>;
 pub enum DeployTarget {
     Zoned(ZonedTarget),
     Zoneless(ZonelessTarget),
+    /// Takes the script off workers.dev, for a worker that's only meant to be reached on
+    /// its routes
+    DisableZoneless(ZonelessTarget),
     Schedule(ScheduleTarget),
 }
 
 pub fn deploy(user: &GlobalUser, deploy_targets: &[DeployTarget]) -> Result<DeployResults> {
-    let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
-    let spinner = ProgressBar::new_spinner().with_style(style);
-    spinner.enable_steady_tick(20);
+    deploy_with(&HttpApi::new(user), user, deploy_targets)
+}
+
+/// Like `deploy`, with the routes and the workers.dev subdomain published through `api`.
+/// When a step fails, the routes this deploy created are deleted again and the error tells
+/// what else was left applied.
+pub fn deploy_with(
+    api: &impl CloudflareApi,
+    user: &GlobalUser,
+    deploy_targets: &[DeployTarget],
+) -> Result<DeployResults> {
+    let spinner = ui::spinner("Deploying...");
+    let mut applied = Applied::default();
     let mut results = DeployResults::default();
-    for target in deploy_targets {
-        match target {
-            DeployTarget::Zoned(zoned) => {
-                spinner.set_message("Configuring routes...");
-                let route_urls = zoned.deploy(user)?;
-                results.urls.extend(route_urls);
-            }
-            DeployTarget::Zoneless(zoneless) => {
-                spinner.set_message("Configuring workers.dev...");
-                let worker_dev = zoneless.deploy(user)?;
-                results.urls.push(worker_dev);
+    let deployed = deploy_targets.iter().try_for_each(|target| {
+        deploy_target(api, user, target, &spinner, &mut applied, &mut results)
+    });
+    spinner.finish_and_clear();
+
+    match deployed {
+        Ok(()) => Ok(results),
+        Err(e) => {
+            let plan = applied.roll_back(api);
+            Err(e.context(plan))
+        }
+    }
+}
+
+fn deploy_target(
+    api: &impl CloudflareApi,
+    user: &GlobalUser,
+    target: &DeployTarget,
+    spinner: &ProgressBar,
+    applied: &mut Applied,
+    results: &mut DeployResults,
+) -> Result<()> {
+    match target {
+        DeployTarget::Zoned(zoned) => {
+            spinner.set_message("Configuring routes...");
+            let published = zoned.deploy(api)?;
+            for result in &published {
+                if let RouteUploadResult::New(route) = result {
+                    applied.routes.push((zoned.zone_id.clone(), route.clone()));
+                }
             }
-            DeployTarget::Schedule(schedule) => {
-                spinner.set_message("Configuring schedules...");
-                let schedules = schedule.deploy(user)?;
-                results.schedules.extend(schedules);
+            // the routes that could be created stay until the deploy is rolled back
+            let failed: Vec<String> = published
+                .iter()
+                .filter(|result| matches!(result, RouteUploadResult::Error(_)))
+                .map(|result| result.to_string())
+                .collect();
+            if !failed.is_empty() {
+                anyhow::bail!("Could not publish every route:\n {}", failed.join("\n "))
             }
+            results
+                .urls
+                .extend(published.iter().map(|result| result.to_string()));
+        }
+        DeployTarget::Zoneless(zoneless) => {
+            spinner.set_message("Configuring workers.dev...");
+            let worker_dev = zoneless.deploy(api)?;
+            applied
+                .steps
+                .push(format!("the script was made available on {}", worker_dev));
+            results.urls.push(worker_dev);
+        }
+        DeployTarget::DisableZoneless(zoneless) => {
+            spinner.set_message("Disabling workers.dev...");
+            zoneless.disable(api)?;
+            applied
+                .steps
+                .push("the script was taken off workers.dev".to_string());
+        }
+        DeployTarget::Schedule(schedule) => {
+            spinner.set_message("Configuring schedules...");
+            let schedules = schedule.deploy(user)?;
+            applied.steps.push(format!(
+                "the schedules were set to {}",
+                schedules.join(", ")
+            ));
+            results.schedules.extend(schedules);
         }
     }
+    Ok(())
+}
 
-    spinner.finish_and_clear();
+/// What a deploy changed so far, to undo or account for when a later step fails
+#[derive(Default)]
+pub(crate) struct Applied {
+    /// The routes created, with the zone they're in
+    routes: Vec<(String, Route)>,
+    /// The routes pointed to another script, as they were before, with the zone they're in
+    repointed: Vec<(String, Route)>,
+    /// The other steps that went through, which are left in place
+    steps: Vec<String>,
+}
+
+impl Applied {
+    /// Deletes the routes that were created and points the ones that were taken over back
+    /// to their script, returning what happened to each step and how to undo by hand what
+    /// couldn't be
+    pub(crate) fn roll_back(self, api: &impl CloudflareApi) -> String {
+        let mut removed = Vec::new();
+        let mut left = Vec::new();
+        for (zone_id, route) in self.routes.iter().rev() {
+            let id = match &route.id {
+                Some(id) => id,
+                // without its id the route can't be deleted, `wrangler route list` finds it
+                None => {
+                    left.push(format!(
+                        "wrangler route list --zone-id {}  # then delete {} by its id",
+                        zone_id, route.pattern
+                    ));
+                    continue;
+                }
+            };
+            match api.delete_route(zone_id, id) {
+                Ok(()) => removed.push(route.pattern.clone()),
+                Err(e) => {
+                    log::info!("failed to delete route {}: {}", route.pattern, e);
+                    left.push(format!(
+                        "wrangler route delete --zone-id {} {}  # {}",
+                        zone_id, id, route.pattern
+                    ));
+                }
+            }
+        }
 
-    Ok(results)
+        let mut restored = Vec::new();
+        let mut not_restored = Vec::new();
+        for (zone_id, previous) in self.repointed.iter().rev() {
+            let script = previous.script.as_deref().unwrap_or("no worker");
+            let result = match &previous.id {
+                Some(id) => api.update_route(zone_id, id, previous),
+                None => Err(anyhow::anyhow!("the route has no id")),
+            };
+            match result {
+                Ok(()) => restored.push(format!("{} => {}", previous.pattern, script)),
+                Err(e) => {
+                    log::info!("failed to restore route {}: {}", previous.pattern, e);
+                    not_restored.push(format!(
+                        "{} in zone {} => {}",
+                        previous.pattern, zone_id, script
+                    ));
+                }
+            }
+        }
+
+        let mut plan = vec!["The deploy stopped partway".to_string()];
+        if !removed.is_empty() {
+            plan.push(format!(
+                "Deleted the routes it had created: {}",
+                removed.join(", ")
+            ));
+        }
+        if !restored.is_empty() {
+            plan.push(format!(
+                "Pointed the routes it had taken over back to their worker: {}",
+                restored.join(", ")
+            ));
+        }
+        for step in &self.steps {
+            plan.push(format!("Left in place: {}", step));
+        }
+        if !left.is_empty() {
+            plan.push(format!(
+                "Delete the routes it created that are still there with:\n  {}",
+                left.join("\n  ")
+            ));
+        }
+        if !not_restored.is_empty() {
+            plan.push(format!(
+                "Point these routes back to the worker they had in the dashboard:\n  {}",
+                not_restored.join("\n  ")
+            ));
+        }
+        plan.join("\n")
+    }
 }
 
 #[derive(Default)]
@@ -55,3 +216,85 @@ pub struct DeployResults {
     pub urls: Vec<String>,
     pub schedules: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::MockApi;
+
+    fn zone(zone_id: &str, patterns: &[&str]) -> ZonedTarget {
+        ZonedTarget {
+            zone_id: zone_id.to_string(),
+            routes: patterns
+                .iter()
+                .map(|pattern| Route {
+                    id: None,
+                    script: Some("worker".to_string()),
+                    pattern: pattern.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn zoned(zone_id: &str, patterns: &[&str]) -> DeployTarget {
+        DeployTarget::Zoned(zone(zone_id, patterns))
+    }
+
+    #[test]
+    fn it_deletes_the_routes_it_created_when_a_route_fails() {
+        let api = MockApi::new().with_route("one", "example.com/kept", Some("worker"));
+        let user = GlobalUser::ApiTokenAuth {
+            api_token: "token".to_string(),
+        };
+        // the same pattern twice fails the second time around
+        let targets = vec![
+            zoned("one", &["example.com/kept", "example.com/a"]),
+            zoned("two", &["example.org/b", "example.org/b"]),
+        ];
+
+        let error = deploy_with(&api, &user, &targets).err().unwrap();
+        assert_eq!(api.routes("one").len(), 1);
+        assert!(api.routes("two").is_empty());
+        let message = format!("{:#}", error);
+        assert!(message.contains("Deleted the routes it had created: example.org/b, example.com/a"));
+        assert!(message.contains("example.org/b => creation failed"));
+    }
+
+    #[test]
+    fn it_points_the_routes_it_took_over_back_when_a_route_fails() {
+        let api = MockApi::new().with_route("zone", "example.com/a", Some("other"));
+        let mut applied = Applied::default();
+        // the same pattern twice fails the second time around
+        let pointed = zone("zone", &["example.com/a", "example.com/b", "example.com/b"]).point_to(
+            &api,
+            "splitter",
+            &mut applied,
+        );
+        assert!(pointed.is_err());
+
+        let plan = applied.roll_back(&api);
+        let routes = api.routes("zone");
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].script.as_deref(), Some("other"));
+        assert!(plan.contains("Deleted the routes it had created: example.com/b"));
+        assert!(plan.contains("back to their worker: example.com/a => other"));
+    }
+
+    #[test]
+    fn it_doesnt_delete_a_route_it_has_no_id_for() {
+        let api = MockApi::new().with_route("zone", "example.com/a", Some("worker"));
+        let mut applied = Applied::default();
+        applied.routes.push((
+            "zone".to_string(),
+            Route {
+                id: None,
+                script: Some("worker".to_string()),
+                pattern: "example.com/a".to_string(),
+            },
+        ));
+
+        let plan = applied.roll_back(&api);
+        assert_eq!(api.routes("zone").len(), 1);
+        assert!(plan.contains("wrangler route list --zone-id zone  # then delete example.com/a"));
+    }
+}
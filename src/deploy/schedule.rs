@@ -1,7 +1,9 @@
-use crate::http;
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
 use crate::settings::global_user::GlobalUser;
 
 use anyhow::Result;
+use reqwest::Method;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ScheduleTarget {
@@ -13,10 +15,10 @@ pub struct ScheduleTarget {
 impl ScheduleTarget {
     pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<String>> {
         log::info!("publishing schedules");
-        let schedule_worker_addr = format!(
-            "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/schedules",
+        let schedule_worker_addr = http::api_url(&format!(
+            "accounts/{}/workers/scripts/{}/schedules",
             self.account_id, self.script_name,
-        );
+        ))?;
 
         let client = http::legacy_auth_client(user);
 
@@ -25,12 +27,12 @@ impl ScheduleTarget {
             .put(&schedule_worker_addr)
             .header("Content-Type", "application/json")
             .body(build_schedules_request(&self.crons))
-            .send()?;
+            .send_logged_as(Method::PUT)?;
 
         let status = res.status();
         let text = res.text()?;
         if !status.is_success() {
-            anyhow::bail!(crate::format_api_errors(text))
+            anyhow::bail!(WranglerError::from_api_response(status, text))
         }
 
         Ok(self.crons.clone())
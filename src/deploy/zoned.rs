@@ -3,11 +3,9 @@ use std::fmt;
 use anyhow::Result;
 use serde::Serialize;
 
-use cloudflare::endpoints::workers::{CreateRoute, CreateRouteParams, ListRoutes};
-use cloudflare::framework::apiclient::ApiClient;
-
-use crate::http;
-use crate::settings::global_user::GlobalUser;
+use super::Applied;
+use crate::http::CloudflareApi;
+use crate::settings::toml::route;
 use crate::settings::toml::{Route, RouteConfig};
 use crate::terminal::message::{Message, StdOut};
 
@@ -18,111 +16,132 @@ pub struct ZonedTarget {
 }
 
 impl ZonedTarget {
-    pub fn build(script_name: &str, route_config: &RouteConfig) -> Result<Self> {
-        match route_config.zone_id.as_ref() {
-            Some(zone_id) if !zone_id.is_empty() => {
-                let new_route = |route: &String| Route {
-                    id: None,
-                    script: Some(script_name.to_string()),
-                    pattern: route.to_string(),
-                };
-                let routes: Vec<Route> = route_config
-                    .route
-                    .iter()
-                    .map(new_route)
-                    .chain(route_config.routes.iter().flatten().filter_map(|route| {
-                        if route.is_empty() {
-                            StdOut::warn("your configuration file contains an empty route");
-                            None
-                        } else {
-                            Some(new_route(route))
-                        }
-                    }))
-                    .collect();
-
-                Ok(Self {
-                    zone_id: zone_id.to_owned(),
-                    routes,
-                })
+    /// The routes of `route_config` grouped by their zone: the one a route names of its own,
+    /// or else the `zone_id` of the configuration. Zones are in the order of their first route.
+    pub fn build(script_name: &str, route_config: &RouteConfig) -> Result<Vec<Self>> {
+        let default_zone_id = route_config
+            .zone_id
+            .as_deref()
+            .filter(|zone_id| !zone_id.is_empty());
+        let single = route_config
+            .route
+            .iter()
+            .map(|route| (route.as_str(), None));
+        let routes = route_config.routes.iter().flatten().filter_map(|route| {
+            if route.pattern().is_empty() {
+                StdOut::warn("your configuration file contains an empty route");
+                None
+            } else {
+                Some((route.pattern(), route.zone_id()))
+            }
+        });
+
+        let mut zones: Vec<ZonedTarget> = Vec::new();
+        for (pattern, zone_id) in single.chain(routes) {
+            let zone_id = match zone_id.or(default_zone_id) {
+                Some(zone_id) => zone_id,
+                None => anyhow::bail!("field `zone_id` is required to deploy to routes, set it at the top level or for the route {}", pattern),
+            };
+            route::validate_pattern(pattern)?;
+            let route = Route {
+                id: None,
+                script: Some(script_name.to_string()),
+                pattern: pattern.to_string(),
+            };
+            match zones.iter_mut().find(|zoned| zoned.zone_id == zone_id) {
+                Some(zoned) => zoned.routes.push(route),
+                None => zones.push(ZonedTarget {
+                    zone_id: zone_id.to_string(),
+                    routes: vec![route],
+                }),
             }
-            _ => anyhow::bail!("field `zone_id` is required to deploy to routes"),
         }
+        Ok(zones)
     }
 
-    pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<String>> {
+    pub fn deploy(&self, api: &impl CloudflareApi) -> Result<Vec<RouteUploadResult>> {
         log::info!("publishing to zone {}", self.zone_id);
 
-        let published_routes = publish_routes(user, self)?;
+        publish_routes(api, self)
+    }
 
-        let display_results: Vec<String> = published_routes.iter().map(|r| r.to_string()).collect();
+    /// Points every route at `script_name`, creating the ones that don't exist yet. Unlike
+    /// `deploy`, this takes over routes that point to another worker. What it changed is
+    /// recorded in `applied`, so it can be rolled back when a later step fails.
+    pub(crate) fn point_to(
+        &self,
+        api: &impl CloudflareApi,
+        script_name: &str,
+        applied: &mut Applied,
+    ) -> Result<()> {
+        let existing_routes = api.list_routes(&self.zone_id)?;
+        for route in &self.routes {
+            let route = Route {
+                id: None,
+                script: Some(script_name.to_string()),
+                pattern: route.pattern.clone(),
+            };
+            match existing_routes
+                .iter()
+                .find(|existing| existing.pattern == route.pattern)
+            {
+                Some(existing) if existing.script == route.script => {}
+                Some(Route {
+                    id: Some(id),
+                    script,
+                    ..
+                }) => {
+                    api.update_route(&self.zone_id, id, &route)?;
+                    let previous = Route {
+                        id: Some(id.clone()),
+                        script: script.clone(),
+                        pattern: route.pattern.clone(),
+                    };
+                    applied.repointed.push((self.zone_id.clone(), previous));
+                }
+                _ => {
+                    let created = api.create_route(&self.zone_id, &route)?;
+                    applied.routes.push((self.zone_id.clone(), created));
+                }
+            }
+        }
+        Ok(())
+    }
 
-        Ok(display_results)
+    /// Routes of the zone that match one of ours but already point to another worker
+    pub fn conflicts(&self, api: &impl CloudflareApi) -> Result<Vec<Route>> {
+        let existing_routes = api.list_routes(&self.zone_id)?;
+        let conflicts = existing_routes
+            .into_iter()
+            .filter(|existing| {
+                self.routes.iter().any(|route| {
+                    route.pattern == existing.pattern && route.script != existing.script
+                })
+            })
+            .collect();
+
+        Ok(conflicts)
     }
 }
 
 pub fn publish_routes(
-    user: &GlobalUser,
+    api: &impl CloudflareApi,
     zoned_config: &ZonedTarget,
 ) -> Result<Vec<RouteUploadResult>> {
     // For the moment, we'll just make this call once and make all our decisions based on the response.
     // There is a possibility of race conditions, but we just report back the results and allow the
     // user to decide how to proceed.
-    let existing_routes = fetch_all(user, &zoned_config.zone_id)?;
+    let existing_routes = api.list_routes(&zoned_config.zone_id)?;
 
     let deployed_routes = zoned_config
         .routes
         .iter()
-        .map(|route| deploy_route(user, &zoned_config.zone_id, route, &existing_routes))
+        .map(|route| deploy_route(api, &zoned_config.zone_id, route, &existing_routes))
         .collect();
 
     Ok(deployed_routes)
 }
 
-fn fetch_all(user: &GlobalUser, zone_identifier: &str) -> Result<Vec<Route>> {
-    let client = http::cf_v4_client(user)?;
-
-    let routes: Vec<Route> = match client.request(&ListRoutes { zone_identifier }) {
-        Ok(success) => success.result.iter().map(Route::from).collect(),
-        Err(e) => anyhow::bail!("{}", http::format_error(e, None)), // TODO: add suggestion fn
-    };
-
-    Ok(routes)
-}
-
-fn create(user: &GlobalUser, zone_identifier: &str, route: &Route) -> Result<Route> {
-    let client = http::cf_v4_client(user)?;
-
-    log::info!("Creating your route {:#?}", &route.pattern,);
-    match client.request(&CreateRoute {
-        zone_identifier,
-        params: CreateRouteParams {
-            pattern: route.pattern.clone(),
-            script: route.script.clone(),
-        },
-    }) {
-        Ok(response) => Ok(Route {
-            id: Some(response.result.id),
-            pattern: route.pattern.clone(),
-            script: route.script.clone(),
-        }),
-        Err(e) => anyhow::bail!("{}", http::format_error(e, Some(&routes_error_help))),
-    }
-}
-
-// TODO: improve this error message to reference wrangler route commands
-fn routes_error_help(error_code: u16) -> &'static str {
-    match error_code {
-        10020 => {
-            r#"
-            A worker with a different name was previously deployed to the specified route.
-            If you would like to overwrite that worker,
-            you will need to change `name` in your configuration file to match the currently deployed worker,
-            or navigate to https://dash.cloudflare.com/workers and rename or delete that worker.\n"#
-        }
-        _ => "",
-    }
-}
-
 #[derive(Debug, Serialize)]
 pub enum RouteUploadResult {
     Same(Route),
@@ -150,7 +169,7 @@ impl fmt::Display for RouteUploadResult {
 }
 
 fn deploy_route(
-    user: &GlobalUser,
+    api: &impl CloudflareApi,
     zone_id: &str,
     route: &Route,
     existing_routes: &[Route],
@@ -176,7 +195,7 @@ fn deploy_route(
     }
 
     // if none of the existing routes match this one, we should create a new route
-    match create(user, zone_id, route) {
+    match api.create_route(zone_id, route) {
         // we want to show the new route along with its id
         Ok(created) => RouteUploadResult::New(created),
         // if there is an error, we want to know which route triggered it
@@ -190,3 +209,58 @@ fn deploy_route(
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::MockApi;
+
+    fn target(routes: &[&str]) -> ZonedTarget {
+        ZonedTarget {
+            zone_id: "zone".to_string(),
+            routes: routes
+                .iter()
+                .map(|pattern| Route {
+                    id: None,
+                    script: Some("worker".to_string()),
+                    pattern: pattern.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn it_creates_the_routes_that_dont_exist() {
+        let api = MockApi::new()
+            .with_route("zone", "example.com/a", Some("worker"))
+            .with_route("zone", "example.com/b", Some("other"));
+        let results = publish_routes(
+            &api,
+            &target(&["example.com/a", "example.com/b", "example.com/c"]),
+        )
+        .unwrap();
+
+        assert!(matches!(results[0], RouteUploadResult::Same(_)));
+        assert!(
+            matches!(&results[1], RouteUploadResult::Conflict(r) if r.script.as_deref() == Some("other"))
+        );
+        assert!(matches!(&results[2], RouteUploadResult::New(r) if r.id.is_some()));
+        assert_eq!(api.routes("zone").len(), 3);
+    }
+
+    #[test]
+    fn it_points_every_route_to_a_worker() {
+        let api = MockApi::new()
+            .with_route("zone", "example.com/a", Some("worker"))
+            .with_route("zone", "example.com/b", Some("other"));
+        target(&["example.com/a", "example.com/b", "example.com/c"])
+            .point_to(&api, "splitter", &mut Applied::default())
+            .unwrap();
+
+        let routes = api.routes("zone");
+        assert_eq!(routes.len(), 3);
+        assert!(routes
+            .iter()
+            .all(|route| route.script.as_deref() == Some("splitter")));
+    }
+}
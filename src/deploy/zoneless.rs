@@ -1,6 +1,5 @@
-use crate::commands::subdomain::Subdomain;
-use crate::http;
-use crate::settings::global_user::GlobalUser;
+use crate::commands::subdomain::NO_SUBDOMAIN_MSG;
+use crate::http::CloudflareApi;
 use crate::settings::toml::target::LazyAccountId;
 use crate::settings::toml::RouteConfig;
 
@@ -20,41 +19,57 @@ impl ZonelessTarget {
         })
     }
 
-    pub fn deploy(&self, user: &GlobalUser) -> Result<String> {
+    pub fn deploy(&self, api: &impl CloudflareApi) -> Result<String> {
         log::info!("publishing to workers.dev subdomain");
         log::info!("checking that subdomain is registered");
-        let subdomain = match Subdomain::get(self.account_id.load()?, user)? {
+        let account_id = self.account_id.load()?;
+        let subdomain = match api.subdomain(account_id)? {
             Some(subdomain) => subdomain,
-            None => anyhow::bail!("Before publishing to workers.dev, you must register a subdomain. Please choose a name for your subdomain and run `wrangler subdomain <name>`.")
+            None => anyhow::bail!(NO_SUBDOMAIN_MSG),
         };
 
-        let sd_worker_addr = format!(
-            "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/subdomain",
-            self.account_id.load()?,
-            self.script_name,
-        );
-
-        let client = http::legacy_auth_client(user);
-
         log::info!("Making public on subdomain...");
-        let res = client
-            .post(&sd_worker_addr)
-            .header("Content-type", "application/json")
-            .body(build_subdomain_request())
-            .send()?;
-
-        let status = res.status();
-        let text = res.text()?;
-        if !status.is_success() {
-            anyhow::bail!(crate::format_api_errors(text))
-        }
+        api.enable_subdomain(account_id, &self.script_name)?;
 
         let deploy_address = format!("https://{}.{}.workers.dev", self.script_name, subdomain);
 
         Ok(deploy_address)
     }
+
+    pub fn disable(&self, api: &impl CloudflareApi) -> Result<()> {
+        let account_id = self.account_id.load()?;
+        // nothing can be on workers.dev without a subdomain
+        if api.subdomain(account_id)?.is_none() {
+            return Ok(());
+        }
+
+        log::info!("Disabling on subdomain...");
+        api.disable_subdomain(account_id, &self.script_name)
+    }
 }
 
-fn build_subdomain_request() -> String {
-    serde_json::json!({ "enabled": true }).to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::MockApi;
+
+    #[test]
+    fn it_publishes_to_the_subdomain_of_the_account() {
+        let target = ZonelessTarget {
+            account_id: Some("account".to_string()).into(),
+            script_name: "worker".to_string(),
+        };
+        assert!(target.deploy(&MockApi::new()).is_err());
+        assert!(target.disable(&MockApi::new()).is_ok());
+
+        let api = MockApi::new().with_subdomain("team");
+        assert_eq!(
+            target.deploy(&api).unwrap(),
+            "https://worker.team.workers.dev"
+        );
+        assert!(api.is_on_subdomain("worker"));
+
+        target.disable(&api).unwrap();
+        assert!(!api.is_on_subdomain("worker"));
+    }
 }
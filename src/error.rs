@@ -0,0 +1,295 @@
+use std::error::Error;
+use std::fmt;
+
+use cloudflare::framework::response::ApiErrors;
+use reqwest::StatusCode;
+
+use crate::terminal::emoji;
+
+const TEMP_NOTICE_ES_MODULES_DO_BETA: &str = "Your account does not have permission to do this! While Durable Objects are in Beta, the modules format is limited to accounts which have opted-in to the Beta. You may do so by following the instructions here: https://developers.cloudflare.com/workers/learning/using-durable-objects";
+
+/// The exit codes of `wrangler`, so that scripts can tell what kind of failure stopped a
+/// command without reading its output. Anything not listed exits with `FAILURE`.
+pub mod exit_code {
+    /// A failure of no kind below
+    pub const FAILURE: i32 = 1;
+    /// The configuration file is missing, can't be read or has invalid settings
+    pub const CONFIG: i32 = 3;
+    /// The credentials are missing, or the API refused them
+    pub const AUTH: i32 = 4;
+    /// The worker could not be built
+    pub const BUILD: i32 = 5;
+    /// The Cloudflare API failed a request
+    pub const API: i32 = 6;
+    /// What would be uploaded breaks a limit or a rule, so nothing was
+    pub const VALIDATION: i32 = 7;
+}
+
+/// One entry of the `errors` array in the JSON envelope of Cloudflare API responses
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiErrorDetail {
+    pub code: u16,
+    pub message: String,
+}
+
+/// Errors that callers may want to tell apart, e.g. to retry or to explain them differently.
+/// They are returned wrapped in an `anyhow::Error`, use `downcast_ref` to match on them.
+#[derive(Debug)]
+pub enum WranglerError {
+    /// The configuration file is missing settings or has invalid ones
+    Config(String),
+    /// The Cloudflare API rejected a request
+    Api {
+        status: StatusCode,
+        errors: Vec<ApiErrorDetail>,
+        body: String,
+        /// How wrangler words the failure, when it has words of its own for it
+        message: Option<String>,
+    },
+    /// The worker could not be built
+    Build(String),
+    /// The credentials are missing, invalid or not allowed to do what was asked
+    Auth(String),
+    /// What would be uploaded breaks a limit or a rule, like an asset too large for KV
+    Validation(String),
+}
+
+impl WranglerError {
+    /// Maps an unsuccessful API response into an error, keeping the codes and messages of
+    /// its JSON envelope if the body has one.
+    pub fn from_api_response(status: StatusCode, body: String) -> WranglerError {
+        WranglerError::Api {
+            status,
+            errors: parse_api_errors(&body),
+            body,
+            message: None,
+        }
+    }
+
+    /// `error` as an error of the kind `kind` makes, keeping its message and causes, unless
+    /// it is of a kind already
+    pub fn tag(error: anyhow::Error, kind: fn(String) -> WranglerError) -> anyhow::Error {
+        if error.chain().any(|cause| cause.is::<WranglerError>()) {
+            error
+        } else {
+            kind(format!("{:#}", error)).into()
+        }
+    }
+
+    /// Whether this is an API error carrying the given error code
+    pub fn has_api_code(&self, code: u16) -> bool {
+        match self {
+            WranglerError::Api { errors, .. } => errors.iter().any(|e| e.code == code),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for WranglerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WranglerError::Config(msg)
+            | WranglerError::Build(msg)
+            | WranglerError::Auth(msg)
+            | WranglerError::Validation(msg) => write!(f, "{}", msg),
+            WranglerError::Api {
+                message: Some(message),
+                ..
+            } => write!(f, "{}", message),
+            WranglerError::Api { errors, body, .. } => {
+                write!(f, "{}", describe_api_errors(errors, body))
+            }
+        }
+    }
+}
+
+impl Error for WranglerError {}
+
+/// The exit code for a command that failed with `error`, going by the first `WranglerError`
+/// in its chain of causes
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    if crate::login::is_auth_error(error) {
+        return exit_code::AUTH;
+    }
+    match error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<WranglerError>())
+    {
+        Some(WranglerError::Config(_)) => exit_code::CONFIG,
+        Some(WranglerError::Auth(_)) => exit_code::AUTH,
+        Some(WranglerError::Build(_)) => exit_code::BUILD,
+        Some(WranglerError::Api { .. }) => exit_code::API,
+        Some(WranglerError::Validation(_)) => exit_code::VALIDATION,
+        None => exit_code::FAILURE,
+    }
+}
+
+/// Explains what the user can do about an error code of the Cloudflare API, if anything.
+/// See https://api.cloudflare.com/#workers-scripts-errors for the codes.
+pub fn api_guidance(code: u16) -> Option<&'static str> {
+    match code {
+        10000 => Some("Your user configuration is invalid, please run wrangler login or wrangler config and enter a new set of credentials."),
+        10007 => Some("The script name is invalid or no script with this name exists on your account. Check the `name` in your configuration file."),
+        10021 => Some("Your script failed validation. Run `wrangler preview` to check it for errors before publishing."),
+        10026 => Some("workers.dev is not enabled for your account. Register a subdomain with `wrangler subdomain <name>` or set `workers_dev = false`."),
+        10027 => Some("Your script is larger than the size limit. Run `wrangler publish --analyze` to see what takes up the most space."),
+        10031 => Some("Your requested subdomain is not available. Please pick another one."),
+        10034 => Some("You need to verify your account's email address before you can publish. You can do this by checking your email or logging in to https://dash.cloudflare.com."),
+        10075 => Some("Setting a Usage Model requires a Paid plan with Unbound enabled. You can do this in the dash by logging in to https://dash.cloudflare.com/?account=workers/plans"),
+        _ => None,
+    }
+}
+
+pub(crate) fn parse_api_errors(body: &str) -> Vec<ApiErrorDetail> {
+    match serde_json::from_str::<ApiErrors>(body) {
+        Ok(api_errors) => api_errors
+            .errors
+            .into_iter()
+            .map(|e| ApiErrorDetail {
+                code: e.code,
+                message: e.message,
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub(crate) fn describe_api_errors(errors: &[ApiErrorDetail], body: &str) -> String {
+    let mut msg = "Something went wrong with the request to Cloudflare...\n".to_string();
+
+    // if we have no useful detail to extract from the API error, it is likely better to print the
+    // raw response value so the end-user can attempt to resolve an issue
+    if errors.is_empty() {
+        msg.push('\n');
+        msg.push_str(body);
+        return msg;
+    }
+
+    // handle possible case of opt-in required modules usage
+    // TODO: remove this after DO beta restrictions are lifted
+    if errors
+        .iter()
+        .any(|e| e.message.contains("workers.api.error.not_entitled"))
+    {
+        msg.push_str("\n\n");
+        msg.push_str(TEMP_NOTICE_ES_MODULES_DO_BETA)
+    }
+
+    // add all api errors to the accumulator string, along with what to do about them
+    let formatted_errors: Vec<String> = errors
+        .iter()
+        .map(|e| match api_guidance(e.code) {
+            Some(guidance) => format!(
+                "{} [API code: {}]\n{} {}",
+                e.message,
+                e.code,
+                emoji::SLEUTH,
+                guidance
+            ),
+            None => format!("{} [API code: {}]", e.message, e.code),
+        })
+        .collect();
+    msg.push_str(&formatted_errors.join("\n"));
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cloudflare::framework::response::ApiFailure;
+
+    #[test]
+    fn it_keeps_the_codes_of_api_errors() {
+        let body = r#"{
+  "result": null,
+  "success": false,
+  "errors": [{ "code": 10013, "message": "workers.api.error.unknown" }],
+  "messages": []
+}"#;
+        let error = WranglerError::from_api_response(StatusCode::BAD_REQUEST, body.to_string());
+
+        assert!(error.has_api_code(10013));
+        assert!(!error.has_api_code(10000));
+        assert!(error
+            .to_string()
+            .ends_with("workers.api.error.unknown [API code: 10013]"));
+
+        let error = anyhow::Error::new(error);
+        match error.downcast_ref::<WranglerError>() {
+            Some(WranglerError::Api { status, errors, .. }) => {
+                assert_eq!(*status, StatusCode::BAD_REQUEST);
+                assert_eq!(errors.len(), 1);
+            }
+            other => panic!("expected an API error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_explains_known_api_error_codes() {
+        let body = r#"{"success": false, "errors": [{ "code": 10026, "message": "workers.api.error.subdomain_disabled" }], "messages": []}"#;
+        let error = WranglerError::from_api_response(StatusCode::BAD_REQUEST, body.to_string());
+
+        let msg = error.to_string();
+        assert!(msg.contains("workers.api.error.subdomain_disabled [API code: 10026]\n"));
+        assert!(msg.contains("wrangler subdomain <name>"));
+        assert!(!msg.contains(body));
+    }
+
+    #[test]
+    fn it_exits_with_the_code_of_the_kind_of_error() {
+        let build = anyhow::Error::new(WranglerError::Build("webpack failed".to_string()))
+            .context("Failed to publish api");
+        assert_eq!(exit_code(&build), exit_code::BUILD);
+
+        let forbidden = WranglerError::from_api_response(StatusCode::FORBIDDEN, String::new());
+        assert_eq!(exit_code(&forbidden.into()), exit_code::AUTH);
+        let not_found = WranglerError::from_api_response(StatusCode::NOT_FOUND, String::new());
+        assert_eq!(exit_code(&not_found.into()), exit_code::API);
+
+        assert_eq!(exit_code(&anyhow::anyhow!("oops")), exit_code::FAILURE);
+    }
+
+    #[test]
+    fn it_exits_with_the_code_of_each_kind_of_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("wrangler.toml");
+        std::fs::write(&config_path, "name = \"worker\"\nworkers-dev = true\n").unwrap();
+        let config = crate::settings::toml::Manifest::new(&config_path);
+        assert_eq!(exit_code(&config.unwrap_err()), exit_code::CONFIG);
+
+        let build = WranglerError::tag(anyhow::anyhow!("Build failed!"), WranglerError::Build);
+        assert_eq!(exit_code(&build), exit_code::BUILD);
+        // a failure that has a kind keeps it
+        let config = anyhow::Error::new(WranglerError::Config("no build".to_string()));
+        assert_eq!(
+            exit_code(&WranglerError::tag(config, WranglerError::Build)),
+            exit_code::CONFIG
+        );
+
+        let body = r#"{"errors": [{ "code": 10013, "message": "workers.api.error.unknown" }]}"#;
+        let api_errors: ApiErrors = serde_json::from_str(body).unwrap();
+        let failure = ApiFailure::Error(StatusCode::BAD_REQUEST, api_errors);
+        let api = crate::http::api_error(failure, None);
+        assert_eq!(exit_code(&api), exit_code::API);
+        assert!(api
+            .to_string()
+            .contains("Code 10013: workers.api.error.unknown"));
+
+        let auth = anyhow::Error::new(WranglerError::Auth("log in first".to_string()));
+        assert_eq!(exit_code(&auth), exit_code::AUTH);
+        let validation = anyhow::Error::new(WranglerError::Validation("too big".to_string()));
+        assert_eq!(exit_code(&validation), exit_code::VALIDATION);
+    }
+
+    #[test]
+    fn it_falls_back_to_the_raw_body() {
+        let error = WranglerError::from_api_response(
+            StatusCode::BAD_GATEWAY,
+            "<html>bad gateway</html>".to_string(),
+        );
+
+        assert!(!error.has_api_code(10027));
+        assert!(error.to_string().ends_with("\n<html>bad gateway</html>"));
+    }
+}
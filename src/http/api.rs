@@ -0,0 +1,315 @@
+use anyhow::Result;
+use cloudflare::endpoints::workers::{CreateRoute, CreateRouteParams, DeleteRoute, ListRoutes};
+use cloudflare::framework::apiclient::ApiClient;
+use futures::future::LocalBoxFuture;
+use once_cell::unsync::OnceCell;
+use reqwest::blocking::multipart::Form;
+use reqwest::blocking::Client;
+use reqwest::Method;
+use serde::Deserialize;
+
+use crate::error::WranglerError;
+use crate::kv::bulk::{KeyValuePair, BULK_TIMEOUT};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Route;
+
+use super::{AuditedRequest, Feature, SendLogged};
+
+/// The endpoints of the API that publishing and the KV commands use. `HttpApi` sends their
+/// requests, while `MockApi` answers them in memory, so what a command does can be tested
+/// without an account.
+pub trait CloudflareApi {
+    fn upload_script(&self, account_id: &str, script_name: &str, form: Form) -> Result<()>;
+
+    /// Updates the bindings and settings of a script without uploading it again
+    fn update_settings(&self, account_id: &str, script_name: &str, form: Form) -> Result<()>;
+
+    fn list_routes(&self, zone_id: &str) -> Result<Vec<Route>>;
+
+    /// Creates `route`, returning it with its id
+    fn create_route(&self, zone_id: &str, route: &Route) -> Result<Route>;
+
+    fn update_route(&self, zone_id: &str, route_id: &str, route: &Route) -> Result<()>;
+
+    fn delete_route(&self, zone_id: &str, route_id: &str) -> Result<()>;
+
+    /// The workers.dev subdomain of the account, if it has registered one
+    fn subdomain(&self, account_id: &str) -> Result<Option<String>>;
+
+    /// Makes a script available on the workers.dev subdomain of its account
+    fn enable_subdomain(&self, account_id: &str, script_name: &str) -> Result<()>;
+
+    /// Takes a script off the workers.dev subdomain of its account
+    fn disable_subdomain(&self, account_id: &str, script_name: &str) -> Result<()>;
+
+    /// Writes one batch of pairs. Batches can be written concurrently, see `http::block_on`.
+    fn put_bulk<'a>(
+        &'a self,
+        account_id: &'a str,
+        namespace_id: &'a str,
+        pairs: &'a [KeyValuePair],
+    ) -> LocalBoxFuture<'a, Result<()>>;
+
+    /// Deletes one batch of keys, like `put_bulk` writes them
+    fn delete_bulk<'a>(
+        &'a self,
+        account_id: &'a str,
+        namespace_id: &'a str,
+        keys: &'a [String],
+    ) -> LocalBoxFuture<'a, Result<()>>;
+}
+
+/// The API of Cloudflare, with the credentials of `user`
+pub struct HttpApi<'a> {
+    user: &'a GlobalUser,
+    feature: Option<Feature>,
+    async_client: OnceCell<reqwest::Client>,
+}
+
+impl<'a> HttpApi<'a> {
+    pub fn new(user: &'a GlobalUser) -> Self {
+        HttpApi {
+            user,
+            feature: None,
+            async_client: OnceCell::new(),
+        }
+    }
+
+    /// Sends the uploads of scripts as part of `feature`, like `featured_legacy_auth_client`
+    pub fn featured(user: &'a GlobalUser, feature: Feature) -> Self {
+        HttpApi {
+            feature: Some(feature),
+            ..HttpApi::new(user)
+        }
+    }
+
+    fn client(&self) -> Client {
+        match self.feature {
+            Some(feature) => super::featured_legacy_auth_client(self.user, feature),
+            None => super::legacy_auth_client(self.user),
+        }
+    }
+
+    // one client for every batch of a bulk operation, so they share its connections
+    fn async_client(&self) -> &reqwest::Client {
+        self.async_client
+            .get_or_init(|| super::legacy_auth_client_async(self.user))
+    }
+
+    fn set_subdomain(&self, account_id: &str, script_name: &str, enabled: bool) -> Result<()> {
+        let addr = super::api_url(&format!(
+            "accounts/{}/workers/scripts/{}/subdomain",
+            account_id, script_name
+        ))?;
+        let res = self
+            .client()
+            .post(&addr)
+            .json(&serde_json::json!({ "enabled": enabled }))
+            .send_logged_as(Method::POST)?;
+        check(res)
+    }
+
+    async fn bulk(
+        &self,
+        method: Method,
+        account_id: &str,
+        namespace_id: &str,
+        body: serde_json::Value,
+    ) -> Result<()> {
+        let addr = super::api_url(&format!(
+            "accounts/{}/storage/kv/namespaces/{}/bulk",
+            account_id, namespace_id
+        ))?;
+        // KV operations can be lengthy if payloads are large
+        let request = self
+            .async_client()
+            .request(method, &addr)
+            .timeout(BULK_TIMEOUT)
+            .json(&body);
+        let res = super::send_logged_async(request).await?;
+        let status = res.status();
+        if !status.is_success() {
+            anyhow::bail!(WranglerError::from_api_response(status, res.text().await?))
+        }
+        Ok(())
+    }
+}
+
+impl CloudflareApi for HttpApi<'_> {
+    fn upload_script(&self, account_id: &str, script_name: &str, form: Form) -> Result<()> {
+        let addr = super::api_url(&format!(
+            "accounts/{}/workers/scripts/{}",
+            account_id, script_name
+        ))?;
+        let res = self
+            .client()
+            .put(&addr)
+            .timeout(super::timeouts().upload())
+            .multipart(form)
+            .send_logged_as(Method::PUT)?;
+        check(res)
+    }
+
+    fn update_settings(&self, account_id: &str, script_name: &str, form: Form) -> Result<()> {
+        let addr = super::api_url(&format!(
+            "accounts/{}/workers/scripts/{}/settings",
+            account_id, script_name
+        ))?;
+        let res = self
+            .client()
+            .patch(&addr)
+            .multipart(form)
+            .send_logged_as(Method::PATCH)?;
+        check(res)
+    }
+
+    fn list_routes(&self, zone_id: &str) -> Result<Vec<Route>> {
+        let client = super::cf_v4_client(self.user)?;
+        match client.request(&ListRoutes {
+            zone_identifier: zone_id,
+        }) {
+            Ok(success) => Ok(success.result.iter().map(Route::from).collect()),
+            Err(e) => Err(super::api_error(e, None)), // TODO: add suggestion fn
+        }
+    }
+
+    fn create_route(&self, zone_id: &str, route: &Route) -> Result<Route> {
+        let client = super::cf_v4_client(self.user)?;
+        log::info!("Creating your route {:#?}", &route.pattern);
+        let created = client.request_audited(&CreateRoute {
+            zone_identifier: zone_id,
+            params: CreateRouteParams {
+                pattern: route.pattern.clone(),
+                script: route.script.clone(),
+            },
+        });
+        match created {
+            Ok(response) => Ok(Route {
+                id: Some(response.result.id),
+                pattern: route.pattern.clone(),
+                script: route.script.clone(),
+            }),
+            Err(e) => Err(super::api_error(e, Some(&routes_error_help))),
+        }
+    }
+
+    fn update_route(&self, zone_id: &str, route_id: &str, route: &Route) -> Result<()> {
+        log::info!("Pointing {} to {:?}", route.pattern, route.script);
+        let addr = super::api_url(&format!("zones/{}/workers/routes/{}", zone_id, route_id))?;
+        let res = self
+            .client()
+            .put(&addr)
+            .json(&serde_json::json!({ "pattern": route.pattern, "script": route.script }))
+            .send_logged_as(Method::PUT)?;
+        check(res)
+    }
+
+    fn delete_route(&self, zone_id: &str, route_id: &str) -> Result<()> {
+        let client = super::cf_v4_client(self.user)?;
+        let deleted = client.request_audited(&DeleteRoute {
+            zone_identifier: zone_id,
+            identifier: route_id,
+        });
+        match deleted {
+            Ok(_) => Ok(()),
+            Err(e) => Err(super::api_error(e, Some(&delete_error_help))),
+        }
+    }
+
+    fn subdomain(&self, account_id: &str) -> Result<Option<String>> {
+        let cache_key = subdomain_cache_key(account_id);
+        if let Some(subdomain) = super::cache::get(self.user, &cache_key) {
+            return Ok(Some(subdomain));
+        }
+
+        let addr = super::api_url(&format!("accounts/{}/workers/subdomain", account_id))?;
+        let res = self.client().get(&addr).send_logged()?;
+        let status = res.status();
+        if !status.is_success() {
+            anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+        }
+        let response: SubdomainResponse = serde_json::from_str(&res.text()?)?;
+        let subdomain = response.result.map(|r| r.subdomain);
+        // an account without a subdomain can register one any moment, so that isn't cached
+        if let Some(subdomain) = &subdomain {
+            super::cache::store(self.user, &cache_key, subdomain);
+        }
+        Ok(subdomain)
+    }
+
+    fn enable_subdomain(&self, account_id: &str, script_name: &str) -> Result<()> {
+        self.set_subdomain(account_id, script_name, true)
+    }
+
+    fn disable_subdomain(&self, account_id: &str, script_name: &str) -> Result<()> {
+        self.set_subdomain(account_id, script_name, false)
+    }
+
+    fn put_bulk<'a>(
+        &'a self,
+        account_id: &'a str,
+        namespace_id: &'a str,
+        pairs: &'a [KeyValuePair],
+    ) -> LocalBoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let body = serde_json::to_value(pairs)?;
+            self.bulk(Method::PUT, account_id, namespace_id, body).await
+        })
+    }
+
+    fn delete_bulk<'a>(
+        &'a self,
+        account_id: &'a str,
+        namespace_id: &'a str,
+        keys: &'a [String],
+    ) -> LocalBoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let body = serde_json::to_value(keys)?;
+            self.bulk(Method::DELETE, account_id, namespace_id, body)
+                .await
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct SubdomainResponse {
+    result: Option<SubdomainResult>,
+}
+
+#[derive(Deserialize)]
+struct SubdomainResult {
+    subdomain: String,
+}
+
+pub(crate) fn subdomain_cache_key(account_id: &str) -> String {
+    format!("subdomain_{}", account_id)
+}
+
+fn check(res: reqwest::blocking::Response) -> Result<()> {
+    let status = res.status();
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(())
+}
+
+// TODO: improve this error message to reference wrangler route commands
+fn routes_error_help(error_code: u16) -> &'static str {
+    match error_code {
+        10020 => {
+            r#"
+            A worker with a different name was previously deployed to the specified route.
+            If you would like to overwrite that worker,
+            you will need to change `name` in your configuration file to match the currently deployed worker,
+            or navigate to https://dash.cloudflare.com/workers and rename or delete that worker.\n"#
+        }
+        _ => "",
+    }
+}
+
+fn delete_error_help(code: u16) -> &'static str {
+    match code {
+        10005 => "Confirm the route id by running `wrangler route list`",
+        _ => "",
+    }
+}
@@ -0,0 +1,177 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use cloudflare::framework::apiclient::ApiClient;
+use cloudflare::framework::async_api;
+use cloudflare::framework::endpoint::{self, Endpoint};
+use cloudflare::framework::response::{ApiFailure, ApiResponse, ApiResult};
+use once_cell::sync::OnceCell;
+use reqwest::{Method, StatusCode};
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+/// The header every request to the API carries the correlation ID of this run in
+pub const CORRELATION_ID_HEADER: &str = "x-wrangler-correlation-id";
+
+static CORRELATION_ID: OnceCell<String> = OnceCell::new();
+static SENT: AtomicBool = AtomicBool::new(false);
+static AUDIT_LOG: OnceCell<PathBuf> = OnceCell::new();
+
+/// The ID the requests of this run of wrangler share, so that they can be found together in
+/// the logs of the account
+pub fn correlation_id() -> &'static str {
+    CORRELATION_ID.get_or_init(|| Uuid::new_v4().to_simple().to_string())
+}
+
+/// Whether a client was set up to send requests with the correlation ID, so that it's worth
+/// telling when something fails
+pub fn correlation_id_sent() -> bool {
+    SENT.load(Ordering::Relaxed)
+}
+
+pub(crate) fn mark_sent() {
+    SENT.store(true, Ordering::Relaxed);
+}
+
+/// Appends a line to the file at `path` for every request that changes something, for the
+/// rest of the process. Only the first call has an effect.
+pub fn set_audit_log(path: PathBuf) {
+    let _ = AUDIT_LOG.set(path);
+}
+
+/// Adds the request to the audit log, if there is one and the request changes something.
+/// `status` is `None` when no response came back.
+pub(crate) fn record(method: &Method, endpoint: &str, status: Option<StatusCode>, took: Duration) {
+    let path = match AUDIT_LOG.get() {
+        Some(path) => path,
+        None => return,
+    };
+    if !is_mutation(method) {
+        return;
+    }
+
+    let line = json!({
+        "time": Utc::now().to_rfc3339(),
+        "correlation_id": correlation_id(),
+        "method": method.as_str(),
+        "endpoint": endpoint,
+        "status": status.map(|status| status.as_u16()),
+        "duration_ms": took.as_millis() as u64,
+    });
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = written {
+        log::warn!("Could not write to the audit log {}: {}", path.display(), e);
+    }
+}
+
+/// Sends the requests of the cloudflare-rs client like `ApiClient::request`, adding the ones
+/// that change something to the audit log like `SendLogged` does. Every request of that
+/// client that changes something is sent with this.
+pub trait AuditedRequest {
+    fn request_audited<ResultType, QueryType, BodyType>(
+        &self,
+        endpoint: &dyn Endpoint<ResultType, QueryType, BodyType>,
+    ) -> ApiResponse<ResultType>
+    where
+        ResultType: ApiResult,
+        QueryType: Serialize,
+        BodyType: Serialize;
+}
+
+impl<C: ApiClient> AuditedRequest for C {
+    fn request_audited<ResultType, QueryType, BodyType>(
+        &self,
+        endpoint: &dyn Endpoint<ResultType, QueryType, BodyType>,
+    ) -> ApiResponse<ResultType>
+    where
+        ResultType: ApiResult,
+        QueryType: Serialize,
+        BodyType: Serialize,
+    {
+        let start = Instant::now();
+        let response = self.request(endpoint);
+        record_endpoint(
+            endpoint.method(),
+            &endpoint.path(),
+            &response,
+            start.elapsed(),
+        );
+        response
+    }
+}
+
+/// `AuditedRequest::request_audited` of the async client
+pub async fn request_audited_async<ResultType, QueryType, BodyType>(
+    client: &async_api::Client,
+    endpoint: &(dyn Endpoint<ResultType, QueryType, BodyType> + Send + Sync),
+) -> ApiResponse<ResultType>
+where
+    ResultType: ApiResult,
+    QueryType: Serialize,
+    BodyType: Serialize,
+{
+    use async_api::ApiClient as _;
+
+    let start = Instant::now();
+    let response = client.request(endpoint).await;
+    record_endpoint(
+        endpoint.method(),
+        &endpoint.path(),
+        &response,
+        start.elapsed(),
+    );
+    response
+}
+
+// Adds a request of the cloudflare-rs client to the audit log, by the path of its endpoint
+// in the API like `SendLogged` has it
+fn record_endpoint<T>(
+    method: endpoint::Method,
+    path: &str,
+    response: &Result<T, ApiFailure>,
+    took: Duration,
+) {
+    let method = match method {
+        endpoint::Method::Get => Method::GET,
+        endpoint::Method::Post => Method::POST,
+        endpoint::Method::Put => Method::PUT,
+        endpoint::Method::Delete => Method::DELETE,
+        endpoint::Method::Patch => Method::PATCH,
+    };
+    let path = super::api_url(path)
+        .ok()
+        .and_then(|url| url::Url::parse(&url).ok())
+        .map_or_else(|| path.to_string(), |url| url.path().to_string());
+    let status = match response {
+        Ok(_) => Some(StatusCode::OK),
+        Err(ApiFailure::Error(status, _)) => Some(*status),
+        Err(ApiFailure::Invalid(_)) => None,
+    };
+    record(&method, &path, status, took);
+}
+
+fn is_mutation(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_only_audits_changes() {
+        assert!(is_mutation(&Method::PUT));
+        assert!(is_mutation(&Method::DELETE));
+        assert!(!is_mutation(&Method::GET));
+        assert_eq!(correlation_id(), correlation_id());
+    }
+}
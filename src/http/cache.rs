@@ -0,0 +1,171 @@
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use twox_hash::XxHash64;
+
+use crate::settings::get_wrangler_home_dir;
+use crate::settings::global_user::GlobalUser;
+
+// Lookups like the account list rarely change, but a single publish across several
+// environments can ask for them many times. Five minutes keeps slow links happy
+// without making a freshly created resource invisible for long.
+const CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+static BYPASS: AtomicBool = AtomicBool::new(false);
+
+/// Makes every lookup ask the API again instead of reading the cache, for `--no-cache`.
+/// What the API answers is still cached for the commands that run after.
+pub fn set_bypass(bypass: bool) {
+    BYPASS.store(bypass, Ordering::Relaxed);
+}
+
+#[derive(Deserialize, Serialize)]
+struct CacheEntry<T> {
+    created_at: u64,
+    value: T,
+}
+
+/// Returns the cached value stored under `key` for the given credentials if it is still
+/// fresh, otherwise calls `fetch` and caches its result.
+///
+/// Only use this for idempotent lookups; failing to read or write the cache is never an
+/// error, it just means the API gets called.
+pub fn cached<T, F>(user: &GlobalUser, key: &str, fetch: F) -> Result<T>
+where
+    T: DeserializeOwned + Serialize,
+    F: FnOnce() -> Result<T>,
+{
+    if let Some(value) = get(user, key) {
+        return Ok(value);
+    }
+
+    let value = fetch()?;
+    store(user, key, &value);
+    Ok(value)
+}
+
+/// The cached value stored under `key` for the given credentials, if it is still fresh, for
+/// lookups that only cache some of their results
+pub fn get<T: DeserializeOwned>(user: &GlobalUser, key: &str) -> Option<T> {
+    if BYPASS.load(Ordering::Relaxed) {
+        return None;
+    }
+    let path = cache_path(user, key);
+    let value = read_entry(&path)?;
+    log::info!("Using cached {} from {}", key, path.display());
+    Some(value)
+}
+
+/// Caches `value` under `key` for the given credentials, see `get`
+pub fn store<T: Serialize>(user: &GlobalUser, key: &str, value: &T) {
+    if let Err(e) = write_entry(&cache_path(user, key), value) {
+        log::info!("Could not cache {}: {}", key, e);
+    }
+}
+
+/// Drops the cached value stored under `key`, e.g. after creating or deleting a resource.
+pub fn invalidate(user: &GlobalUser, key: &str) {
+    let path = cache_path(user, key);
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            log::info!("Could not invalidate cached {}: {}", key, e);
+        }
+    }
+}
+
+fn read_entry<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    let entry: CacheEntry<T> = serde_json::from_str(&contents).ok()?;
+    let age = now().checked_sub(entry.created_at)?;
+
+    if age < CACHE_TTL.as_secs() {
+        Some(entry.value)
+    } else {
+        None
+    }
+}
+
+fn write_entry<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let entry = CacheEntry {
+        created_at: now(),
+        value,
+    };
+    fs::write(path, serde_json::to_string(&entry)?)?;
+    Ok(())
+}
+
+// Caches are kept apart per set of credentials so that switching tokens never
+// shows resources belonging to another account.
+fn cache_path(user: &GlobalUser, key: &str) -> PathBuf {
+    get_wrangler_home_dir()
+        .join("cache")
+        .join(credentials_hash(user))
+        .join(format!("{}.json", key))
+}
+
+fn credentials_hash(user: &GlobalUser) -> String {
+    let mut hasher = XxHash64::default();
+    match user {
+        GlobalUser::ApiTokenAuth { api_token } => hasher.write(api_token.as_bytes()),
+        // the access token is refreshed regularly, but the refresh token identifies the login
+        GlobalUser::OAuthTokenAuth { refresh_token, .. } => hasher.write(refresh_token.as_bytes()),
+        GlobalUser::GlobalKeyAuth { email, api_key } => {
+            hasher.write(email.as_bytes());
+            hasher.write(api_key.as_bytes());
+        }
+    }
+    format!("{:x}", hasher.finish())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_separates_caches_by_credentials() {
+        let token_a = GlobalUser::ApiTokenAuth {
+            api_token: "a".to_string(),
+        };
+        let token_b = GlobalUser::ApiTokenAuth {
+            api_token: "b".to_string(),
+        };
+        assert_ne!(credentials_hash(&token_a), credentials_hash(&token_b));
+        assert_eq!(
+            credentials_hash(&token_a),
+            credentials_hash(&token_a.clone())
+        );
+    }
+
+    #[test]
+    fn it_ignores_expired_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("accounts.json");
+
+        let accounts = vec!["account".to_string()];
+        write_entry(&path, &accounts).unwrap();
+        assert_eq!(read_entry::<Vec<String>>(&path), Some(accounts.clone()));
+
+        let stale = CacheEntry {
+            created_at: now() - CACHE_TTL.as_secs(),
+            value: accounts,
+        };
+        fs::write(&path, serde_json::to_string(&stale).unwrap()).unwrap();
+        assert_eq!(read_entry::<Vec<String>>(&path), None);
+    }
+}
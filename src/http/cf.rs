@@ -1,23 +1,36 @@
 use std::env;
-use std::time::Duration;
 
 use cloudflare::framework::async_api;
 use cloudflare::framework::auth::Credentials;
 use cloudflare::framework::response::ApiFailure;
 use cloudflare::framework::{Environment, HttpApiClient, HttpApiClientConfig};
 use http::StatusCode;
+use once_cell::sync::OnceCell;
 
 use anyhow::Result;
 
-use crate::http::{feature::headers, DEFAULT_HTTP_TIMEOUT_SECONDS};
+use crate::error::{ApiErrorDetail, WranglerError};
+use crate::http::{feature::headers, timeouts};
 use crate::settings::global_user::GlobalUser;
 use crate::terminal::emoji;
 use crate::terminal::message::{Message, StdOut};
 
 const CF_API_BASE_URL: &str = "CF_API_BASE_URL";
 
+static API_BASE: OnceCell<url::Url> = OnceCell::new();
+
+/// Sends every request to the API to `base` rather than to the one in `CF_API_BASE_URL`, for
+/// the rest of the process. Only the first call has an effect.
+pub fn set_api_base(base: url::Url) {
+    let _ = API_BASE.set(base);
+}
+
 // Allow endpoint to be configured via an environment variable
 pub fn get_environment() -> Result<Environment> {
+    if let Some(base) = API_BASE.get() {
+        return Ok(Environment::Custom(base.clone()));
+    }
+
     let env_hostname = match env::var(CF_API_BASE_URL) {
         Ok(value) => {
             if let Ok(url) = url::Url::parse(&value) {
@@ -32,9 +45,20 @@ pub fn get_environment() -> Result<Environment> {
     Ok(Environment::Custom(env_hostname))
 }
 
+/// The URL of `path` in the v4 API, e.g. `accounts/{id}/workers/scripts`, relative to the
+/// base in `CF_API_BASE_URL` if it is set so that every request goes to the same endpoint
+pub fn api_url(path: &str) -> Result<String> {
+    let base = url::Url::from(&get_environment()?);
+    Ok(format!(
+        "{}/{}",
+        base.as_str().trim_end_matches('/'),
+        path.trim_start_matches('/')
+    ))
+}
+
 pub fn cf_v4_client(user: &GlobalUser) -> Result<HttpApiClient> {
     let config = HttpApiClientConfig {
-        http_timeout: Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECONDS),
+        http_timeout: timeouts().request(),
         default_headers: headers(None),
     };
 
@@ -45,7 +69,7 @@ pub fn cf_v4_client(user: &GlobalUser) -> Result<HttpApiClient> {
 
 pub fn cf_v4_api_client_async(user: &GlobalUser) -> Result<async_api::Client> {
     let config = HttpApiClientConfig {
-        http_timeout: Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECONDS),
+        http_timeout: timeouts().request(),
         default_headers: headers(None),
     };
 
@@ -79,6 +103,36 @@ pub fn format_error(e: ApiFailure, err_helper: Option<&dyn Fn(u16) -> &'static s
     }
 }
 
+/// `e` worded like `format_error`, as an error `wrangler` exits with the code of failed API
+/// requests for, or of refused credentials
+pub fn api_error(e: ApiFailure, err_helper: Option<&dyn Fn(u16) -> &'static str>) -> anyhow::Error {
+    let answered = match &e {
+        ApiFailure::Error(status, api_errors) => Some((
+            *status,
+            api_errors
+                .errors
+                .iter()
+                .map(|e| ApiErrorDetail {
+                    code: e.code,
+                    message: e.message.clone(),
+                })
+                .collect(),
+        )),
+        ApiFailure::Invalid(_) => None,
+    };
+    let message = format_error(e, err_helper);
+    match answered {
+        Some((status, errors)) => WranglerError::Api {
+            status,
+            errors,
+            body: String::new(),
+            message: Some(message),
+        }
+        .into(),
+        None => anyhow::anyhow!(message),
+    }
+}
+
 // For handling cases where the API gateway returns errors via HTTP status codes
 // (no API-specific, more granular error code is given).
 fn print_status_code_context(status_code: StatusCode) {
@@ -130,5 +184,18 @@ mod tests {
 
         let expected_environment_url = url::Url::from(&Environment::Production);
         assert_eq!(test_environment_url, expected_environment_url);
+
+        // Test #4
+        // Tests that the URLs of the legacy clients are built on the same base URL
+        assert_eq!(
+            api_url("accounts/1234/workers/scripts").unwrap(),
+            "https://api.cloudflare.com/client/v4/accounts/1234/workers/scripts"
+        );
+        env::set_var(CF_API_BASE_URL, "http://127.0.0.1:8787/client/v4");
+        assert_eq!(
+            api_url("/graphql").unwrap(),
+            "http://127.0.0.1:8787/client/v4/graphql"
+        );
+        env::remove_var(CF_API_BASE_URL);
     }
 }
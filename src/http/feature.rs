@@ -1,5 +1,6 @@
 use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
 
+use crate::http::audit::{self, CORRELATION_ID_HEADER};
 use crate::install;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -13,6 +14,11 @@ pub(crate) fn headers(feature: Option<Feature>) -> HeaderMap {
         USER_AGENT,
         HeaderValue::from_str(&get_user_agent(feature)).unwrap(),
     );
+    headers.insert(
+        CORRELATION_ID_HEADER,
+        HeaderValue::from_static(audit::correlation_id()),
+    );
+    audit::mark_sent();
     headers
 }
 
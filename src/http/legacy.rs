@@ -1,11 +1,24 @@
+use once_cell::sync::OnceCell;
 use reqwest::blocking::{Client, ClientBuilder};
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::redirect::Policy;
-use std::time::Duration;
 
-use crate::http::{feature::headers, Feature, DEFAULT_HTTP_TIMEOUT_SECONDS};
+use crate::http::{feature::headers, proxy, timeouts, Feature};
 use crate::settings::global_user::GlobalUser;
 
+type Configure = Box<dyn Fn(ClientBuilder) -> ClientBuilder + Send + Sync>;
+
+static CONFIGURE: OnceCell<Configure> = OnceCell::new();
+
+/// Has `configure` set up the clients below after wrangler does, e.g. to trust the root
+/// certificate of a proxy, for the rest of the process. Only the first call has an effect.
+/// The async and cloudflare-rs clients only follow `set_api_base`.
+pub fn set_client_config(
+    configure: impl Fn(ClientBuilder) -> ClientBuilder + Send + Sync + 'static,
+) {
+    let _ = CONFIGURE.set(Box::new(configure));
+}
+
 // TODO: remove this and replace it entirely with cloudflare-rs
 pub fn client() -> Client {
     builder()
@@ -22,6 +35,28 @@ pub fn featured_legacy_auth_client(user: &GlobalUser, feature: Feature) -> Clien
     get_client(user, Some(feature))
 }
 
+/// Like `legacy_auth_client`, for sending requests concurrently on a runtime, see
+/// `http::block_on`
+pub fn legacy_auth_client_async(user: &GlobalUser) -> reqwest::Client {
+    let mut headers = headers(None);
+    add_auth_headers(&mut headers, user);
+
+    let timeouts = timeouts();
+    let builder = reqwest::Client::builder()
+        .connect_timeout(timeouts.connect())
+        .timeout(timeouts.request())
+        .pool_idle_timeout(timeouts.keep_alive())
+        .pool_max_idle_per_host(timeouts.max_idle_connections())
+        .default_headers(headers)
+        .redirect(Policy::none());
+    match proxy::proxy() {
+        Some(proxy) => builder.proxy(proxy),
+        None => builder,
+    }
+    .build()
+    .expect("could not create authenticated http client")
+}
+
 fn get_client(user: &GlobalUser, feature: Option<Feature>) -> Client {
     let mut headers = headers(feature);
     add_auth_headers(&mut headers, user);
@@ -34,10 +69,20 @@ fn get_client(user: &GlobalUser, feature: Option<Feature>) -> Client {
 }
 
 fn builder() -> ClientBuilder {
-    let builder = reqwest::blocking::Client::builder();
-    builder
-        .connect_timeout(Duration::from_secs(10))
-        .timeout(Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECONDS))
+    let timeouts = timeouts();
+    let builder = reqwest::blocking::Client::builder()
+        .connect_timeout(timeouts.connect())
+        .timeout(timeouts.request())
+        .pool_idle_timeout(timeouts.keep_alive())
+        .pool_max_idle_per_host(timeouts.max_idle_connections());
+    let builder = match proxy::proxy() {
+        Some(proxy) => builder.proxy(proxy),
+        None => builder,
+    };
+    match CONFIGURE.get() {
+        Some(configure) => configure(builder),
+        None => builder,
+    }
 }
 
 fn add_auth_headers(headers: &mut HeaderMap, user: &GlobalUser) {
@@ -0,0 +1,208 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::OnceCell;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode, Url};
+
+use super::{audit, scheduler};
+
+// Headers whose values are credentials, and never make it into the logs
+const SECRET_HEADERS: &[&str] = &[
+    "authorization",
+    "x-auth-key",
+    "x-auth-user-service-key",
+    "cookie",
+    "set-cookie",
+    "cf-access-token",
+];
+
+static REQUEST_IDS: OnceCell<Mutex<Vec<String>>> = OnceCell::new();
+
+/// Keeps the `cf-ray` IDs of the responses to requests sent with `send_logged` from now on,
+/// which Cloudflare support can look the requests up by, for `take_request_ids`
+pub fn record_request_ids() {
+    let _ = REQUEST_IDS.set(Mutex::new(Vec::new()));
+}
+
+/// The IDs of the responses received since the last call, once `record_request_ids` was
+/// called
+pub fn take_request_ids() -> Vec<String> {
+    match REQUEST_IDS.get() {
+        Some(ids) => std::mem::take(&mut *ids.lock().unwrap()),
+        None => Vec::new(),
+    }
+}
+
+/// Sends requests like `RequestBuilder::send`, logging the request and the response at the
+/// debug level so `--verbose` shows them. Only headers are logged, with credentials
+/// redacted, since bodies are often large or binary. Requests wait their turn when the rate
+/// limit of the API is running out, see `scheduler`, and the ones that change something go
+/// to the audit log, see `audit`. Requests that change something are sent with
+/// `send_logged_as`, which doesn't depend on their body being cloneable to find the method.
+pub trait SendLogged {
+    fn send_logged(self) -> reqwest::Result<Response>;
+
+    /// Like `send_logged`, for requests that change something, so the audit log gets their
+    /// `method` even when their body is streamed and can't be looked into before it's sent
+    fn send_logged_as(self, method: Method) -> reqwest::Result<Response>;
+}
+
+impl SendLogged for RequestBuilder {
+    fn send_logged(self) -> reqwest::Result<Response> {
+        // requests with a streamed body can't be cloned, so only their response is logged
+        let method = match self.try_clone().map(RequestBuilder::build) {
+            Some(Ok(request)) => {
+                log_request(request.method(), request.url(), request.headers());
+                Some(request.method().clone())
+            }
+            _ => None,
+        };
+        send(self, method)
+    }
+
+    fn send_logged_as(self, method: Method) -> reqwest::Result<Response> {
+        send(self, Some(method))
+    }
+}
+
+fn send(request: RequestBuilder, method: Option<Method>) -> reqwest::Result<Response> {
+    let delay = scheduler::delay();
+    if !delay.is_zero() {
+        log_delay(delay);
+        thread::sleep(delay);
+    }
+    let start = Instant::now();
+    let response = request.send();
+    match &response {
+        Ok(response) => {
+            scheduler::observe(response.status(), response.headers());
+            log_response(start, response.status(), response.url(), response.headers());
+            audit(method, response.url(), Some(response.status()), start);
+        }
+        Err(e) => {
+            log_failure(start, e);
+            if let Some(url) = e.url() {
+                audit(method, url, None, start);
+            }
+        }
+    }
+    response
+}
+
+/// `SendLogged::send_logged` of the async client
+pub async fn send_logged_async(
+    request: reqwest::RequestBuilder,
+) -> reqwest::Result<reqwest::Response> {
+    let method = match request.try_clone().map(reqwest::RequestBuilder::build) {
+        Some(Ok(request)) => {
+            log_request(request.method(), request.url(), request.headers());
+            Some(request.method().clone())
+        }
+        _ => None,
+    };
+
+    let delay = scheduler::delay();
+    if !delay.is_zero() {
+        log_delay(delay);
+        tokio::time::sleep(delay).await;
+    }
+    let start = Instant::now();
+    let response = request.send().await;
+    match &response {
+        Ok(response) => {
+            scheduler::observe(response.status(), response.headers());
+            log_response(start, response.status(), response.url(), response.headers());
+            audit(method, response.url(), Some(response.status()), start);
+        }
+        Err(e) => {
+            log_failure(start, e);
+            if let Some(url) = e.url() {
+                audit(method, url, None, start);
+            }
+        }
+    }
+    response
+}
+
+fn audit(method: Option<Method>, url: &Url, status: Option<StatusCode>, start: Instant) {
+    if let Some(method) = method {
+        audit::record(&method, url.path(), status, start.elapsed());
+    }
+}
+
+fn log_request(method: &Method, url: &Url, headers: &HeaderMap) {
+    log::debug!("{} {}{}", method, url, format_headers(headers));
+}
+
+fn log_response(start: Instant, status: StatusCode, url: &Url, headers: &HeaderMap) {
+    if let (Some(ids), Some(id)) = (
+        REQUEST_IDS.get(),
+        headers.get("cf-ray").and_then(|id| id.to_str().ok()),
+    ) {
+        ids.lock().unwrap().push(id.to_string());
+    }
+    log::debug!(
+        "{} from {} in {}ms{}",
+        status,
+        url,
+        start.elapsed().as_millis(),
+        format_headers(headers)
+    );
+}
+
+fn log_delay(delay: Duration) {
+    log::debug!(
+        "Waiting {}ms for the rate limit of the API",
+        delay.as_millis()
+    );
+}
+
+fn log_failure(start: Instant, e: &reqwest::Error) {
+    log::debug!(
+        "Request failed after {}ms: {}",
+        start.elapsed().as_millis(),
+        e
+    );
+}
+
+fn format_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_secret(name.as_str()) {
+                "[redacted]"
+            } else {
+                value.to_str().unwrap_or("[binary]")
+            };
+            format!("\n  {}: {}", name, value)
+        })
+        .collect()
+}
+
+fn is_secret(header: &str) -> bool {
+    SECRET_HEADERS
+        .iter()
+        .any(|secret| secret.eq_ignore_ascii_case(header))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn it_redacts_credentials() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer abc123"));
+        headers.insert("X-Auth-Key", HeaderValue::from_static("abc123"));
+        headers.insert("cf-ray", HeaderValue::from_static("6b2f-SJC"));
+
+        let formatted = format_headers(&headers);
+        assert!(!formatted.contains("abc123"));
+        assert!(formatted.contains("authorization: [redacted]"));
+        assert!(formatted.contains("cf-ray: 6b2f-SJC"));
+    }
+}
@@ -0,0 +1,206 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::Result;
+use futures::future::LocalBoxFuture;
+use reqwest::blocking::multipart::Form;
+
+use crate::kv::bulk::KeyValuePair;
+use crate::settings::toml::Route;
+
+use super::CloudflareApi;
+
+/// A `CloudflareApi` that keeps what it's sent in memory, for testing commands. It knows of
+/// one account and any number of zones and namespaces.
+#[derive(Default)]
+pub struct MockApi {
+    state: RefCell<State>,
+}
+
+#[derive(Default)]
+struct State {
+    subdomain: Option<String>,
+    scripts: Vec<String>,
+    on_subdomain: HashSet<String>,
+    routes: HashMap<String, Vec<Route>>,
+    namespaces: HashMap<String, BTreeMap<String, String>>,
+    next_id: usize,
+}
+
+impl State {
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        self.next_id.to_string()
+    }
+}
+
+impl MockApi {
+    pub fn new() -> Self {
+        MockApi::default()
+    }
+
+    /// With `subdomain` registered as the workers.dev subdomain of the account
+    pub fn with_subdomain(self, subdomain: &str) -> Self {
+        self.state.borrow_mut().subdomain = Some(subdomain.to_string());
+        self
+    }
+
+    /// With a route of `pattern` in the zone that points to `script`
+    pub fn with_route(self, zone_id: &str, pattern: &str, script: Option<&str>) -> Self {
+        {
+            let mut state = self.state.borrow_mut();
+            let id = state.next_id();
+            state
+                .routes
+                .entry(zone_id.to_string())
+                .or_default()
+                .push(Route {
+                    id: Some(id),
+                    script: script.map(str::to_string),
+                    pattern: pattern.to_string(),
+                });
+        }
+        self
+    }
+
+    /// The names of the scripts uploaded, in the order they were
+    pub fn scripts(&self) -> Vec<String> {
+        self.state.borrow().scripts.clone()
+    }
+
+    pub fn is_on_subdomain(&self, script_name: &str) -> bool {
+        self.state.borrow().on_subdomain.contains(script_name)
+    }
+
+    pub fn routes(&self, zone_id: &str) -> Vec<Route> {
+        let state = self.state.borrow();
+        state.routes.get(zone_id).cloned().unwrap_or_default()
+    }
+
+    /// The keys and values written to the namespace
+    pub fn namespace(&self, namespace_id: &str) -> BTreeMap<String, String> {
+        let state = self.state.borrow();
+        state
+            .namespaces
+            .get(namespace_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+impl CloudflareApi for MockApi {
+    fn upload_script(&self, _: &str, script_name: &str, _: Form) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        if !state.scripts.iter().any(|script| script == script_name) {
+            state.scripts.push(script_name.to_string());
+        }
+        Ok(())
+    }
+
+    fn update_settings(&self, _: &str, script_name: &str, _: Form) -> Result<()> {
+        if !self.scripts().iter().any(|script| script == script_name) {
+            anyhow::bail!("script {} is not published", script_name)
+        }
+        Ok(())
+    }
+
+    fn list_routes(&self, zone_id: &str) -> Result<Vec<Route>> {
+        Ok(self.routes(zone_id))
+    }
+
+    fn create_route(&self, zone_id: &str, route: &Route) -> Result<Route> {
+        let mut state = self.state.borrow_mut();
+        let created = Route {
+            id: Some(state.next_id()),
+            ..route.clone()
+        };
+        let routes = state.routes.entry(zone_id.to_string()).or_default();
+        if routes
+            .iter()
+            .any(|existing| existing.pattern == route.pattern)
+        {
+            anyhow::bail!("route {} already exists", route.pattern)
+        }
+        routes.push(created.clone());
+        Ok(created)
+    }
+
+    fn update_route(&self, zone_id: &str, route_id: &str, route: &Route) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        match state.routes.get_mut(zone_id).and_then(|routes| {
+            routes
+                .iter_mut()
+                .find(|r| r.id.as_deref() == Some(route_id))
+        }) {
+            Some(existing) => {
+                existing.pattern = route.pattern.clone();
+                existing.script = route.script.clone();
+                Ok(())
+            }
+            None => anyhow::bail!("no route {} in zone {}", route_id, zone_id),
+        }
+    }
+
+    fn delete_route(&self, zone_id: &str, route_id: &str) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        let routes = state.routes.entry(zone_id.to_string()).or_default();
+        let count = routes.len();
+        routes.retain(|route| route.id.as_deref() != Some(route_id));
+        if routes.len() == count {
+            anyhow::bail!("no route {} in zone {}", route_id, zone_id)
+        }
+        Ok(())
+    }
+
+    fn subdomain(&self, _: &str) -> Result<Option<String>> {
+        Ok(self.state.borrow().subdomain.clone())
+    }
+
+    fn enable_subdomain(&self, _: &str, script_name: &str) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        state.on_subdomain.insert(script_name.to_string());
+        Ok(())
+    }
+
+    fn disable_subdomain(&self, _: &str, script_name: &str) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        state.on_subdomain.remove(script_name);
+        Ok(())
+    }
+
+    fn put_bulk<'a>(
+        &'a self,
+        _: &'a str,
+        namespace_id: &'a str,
+        pairs: &'a [KeyValuePair],
+    ) -> LocalBoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut state = self.state.borrow_mut();
+            let namespace = state
+                .namespaces
+                .entry(namespace_id.to_string())
+                .or_default();
+            for pair in pairs {
+                namespace.insert(pair.key.clone(), pair.value.clone());
+            }
+            Ok(())
+        })
+    }
+
+    fn delete_bulk<'a>(
+        &'a self,
+        _: &'a str,
+        namespace_id: &'a str,
+        keys: &'a [String],
+    ) -> LocalBoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut state = self.state.borrow_mut();
+            if let Some(namespace) = state.namespaces.get_mut(namespace_id) {
+                for key in keys {
+                    namespace.remove(key);
+                }
+            }
+            Ok(())
+        })
+    }
+}
@@ -1,8 +1,46 @@
+mod api;
+pub mod audit;
+pub(crate) mod cache;
 pub(self) mod cf;
 pub(crate) mod feature;
 pub(self) mod legacy;
+mod logging;
+mod mock;
+pub mod proxy;
+mod scheduler;
+mod timeouts;
 
 pub const DEFAULT_HTTP_TIMEOUT_SECONDS: u64 = 60;
-pub use cf::{cf_v4_api_client_async, cf_v4_client, format_error, get_environment};
+pub(crate) use api::subdomain_cache_key;
+pub use api::{CloudflareApi, HttpApi};
+pub use audit::{request_audited_async, AuditedRequest};
+pub use cache::set_bypass as set_cache_bypass;
+pub use cf::{
+    api_error, api_url, cf_v4_api_client_async, cf_v4_client, format_error, get_environment,
+    set_api_base,
+};
 pub use feature::Feature;
-pub use legacy::{client, featured_legacy_auth_client, legacy_auth_client};
+pub use legacy::{
+    client, featured_legacy_auth_client, legacy_auth_client, legacy_auth_client_async,
+    set_client_config,
+};
+pub use logging::{record_request_ids, send_logged_async, take_request_ids, SendLogged};
+pub use mock::MockApi;
+pub use timeouts::{set_timeouts, timeouts, Timeouts};
+
+use std::future::Future;
+
+use anyhow::Result;
+
+/// How many requests the async clients have in flight at once, to go fast without having the
+/// API rate limit them. Past that, they're paced by the rate limit the API reports.
+pub const MAX_CONCURRENT_REQUESTS: usize = 4;
+
+/// Runs `future`, like the concurrent requests of an async client, to completion on a runtime
+/// of its own
+pub fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(runtime.block_on(future))
+}
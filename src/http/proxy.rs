@@ -0,0 +1,82 @@
+use std::env;
+
+use reqwest::{Proxy, Url};
+
+use crate::settings;
+
+/// Makes the `proxy` of the global configuration file the proxy of every client, unless
+/// `HTTPS_PROXY` or `HTTP_PROXY` already set one. The clients cloudflare-rs creates only
+/// read those variables, so setting them is how its requests go through the proxy too.
+pub fn init() {
+    if env_var("HTTPS_PROXY").is_some() || env_var("HTTP_PROXY").is_some() {
+        return;
+    }
+    if let Some(proxy) = settings::get_proxy() {
+        log::info!("Using the proxy of the global configuration file");
+        env::set_var("HTTPS_PROXY", &proxy);
+        env::set_var("HTTP_PROXY", &proxy);
+    }
+}
+
+// reqwest picks up the proxy variables by itself but doesn't skip the hosts in NO_PROXY,
+// so the clients we build ourselves look all three up here instead
+pub(super) fn proxy() -> Option<Proxy> {
+    let https = env_var("HTTPS_PROXY");
+    let http = env_var("HTTP_PROXY");
+    if https.is_none() && http.is_none() {
+        return None;
+    }
+    let no_proxy = env_var("NO_PROXY").unwrap_or_default();
+
+    Some(Proxy::custom(move |url: &Url| {
+        if bypasses(&no_proxy, url.host_str()?) {
+            return None;
+        }
+        // credentials in the URL of the proxy are sent to it with basic auth
+        match url.scheme() {
+            "https" => https.clone().or_else(|| http.clone()),
+            _ => http.clone(),
+        }
+    }))
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(name)
+        .or_else(|_| env::var(name.to_lowercase()))
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+// NO_PROXY is a comma separated list of hosts, each of which also covers its subdomains
+fn bypasses(no_proxy: &str, host: &str) -> bool {
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            entry == "*"
+                || host == entry
+                || host
+                    .strip_suffix(entry)
+                    .map_or(false, |subdomain| subdomain.ends_with('.'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_skips_the_hosts_in_no_proxy() {
+        let no_proxy = "localhost, .internal.example.com,10.0.0.1";
+
+        assert!(bypasses(no_proxy, "localhost"));
+        assert!(bypasses(no_proxy, "internal.example.com"));
+        assert!(bypasses(no_proxy, "kv.internal.example.com"));
+        assert!(bypasses(no_proxy, "10.0.0.1"));
+        assert!(!bypasses(no_proxy, "api.cloudflare.com"));
+        assert!(!bypasses(no_proxy, "notinternal.example.com"));
+        assert!(bypasses("*", "api.cloudflare.com"));
+        assert!(!bypasses("", "api.cloudflare.com"));
+    }
+}
@@ -0,0 +1,197 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
+// Once fewer requests than this are left of the rate limit, the rest are spread out over
+// what's left of its window instead of being sent as fast as they can
+const LOW_REMAINING: u64 = 20;
+// How long to hold off after a 429 that doesn't say how long for
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+// No request waits longer than this, in case a header is off
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+// One for the whole process, since every command shares the rate limit of the account
+static SCHEDULER: Lazy<Mutex<Scheduler>> = Lazy::new(Default::default);
+
+/// How long to wait before sending the next request, which counts it against the rate limit
+pub(super) fn delay() -> Duration {
+    SCHEDULER.lock().unwrap().reserve(Instant::now())
+}
+
+/// Keeps track of the rate limit the API reports in the headers of a response
+pub(super) fn observe(status: StatusCode, headers: &HeaderMap) {
+    let limit = RateLimit::from_headers(headers);
+    SCHEDULER
+        .lock()
+        .unwrap()
+        .observe(status, &limit, Instant::now());
+}
+
+/// Paces the requests sent with `send_logged` and `send_logged_async` by what the responses
+/// so far said of the rate limit, so that concurrent requests slow down before the API turns
+/// them away rather than after
+#[derive(Debug, Default)]
+struct Scheduler {
+    /// Requests left of the current window, less the ones sent since a response said so
+    remaining: Option<u64>,
+    reset_at: Option<Instant>,
+    /// The next request isn't sent before this
+    next_at: Option<Instant>,
+}
+
+impl Scheduler {
+    fn reserve(&mut self, now: Instant) -> Duration {
+        self.expire(now);
+        let mut at = self.next_at.filter(|at| *at > now).unwrap_or(now);
+        if let (Some(remaining), Some(reset_at)) = (self.remaining, self.reset_at) {
+            if remaining == 0 {
+                at = at.max(reset_at);
+            } else if remaining < LOW_REMAINING {
+                // what's left of the window is shared out among the requests left
+                let spacing = reset_at.saturating_duration_since(at) / remaining as u32;
+                self.next_at = Some(at + spacing);
+            }
+        }
+        self.remaining = self.remaining.map(|remaining| remaining.saturating_sub(1));
+        (at - now).min(MAX_DELAY)
+    }
+
+    fn observe(&mut self, status: StatusCode, limit: &RateLimit, now: Instant) {
+        self.expire(now);
+        if let Some(remaining) = limit.remaining {
+            // responses to concurrent requests come back in any order, so the lowest count
+            // is the latest
+            self.remaining = Some(self.remaining.map_or(remaining, |r| r.min(remaining)));
+        }
+        if let Some(reset) = limit.reset {
+            self.reset_at = Some(now + reset);
+        }
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_at = now + limit.retry_after.unwrap_or(DEFAULT_RETRY_AFTER);
+            self.next_at = Some(self.next_at.map_or(retry_at, |at| at.max(retry_at)));
+        }
+    }
+
+    // Nothing is known of a new window until a response says
+    fn expire(&mut self, now: Instant) {
+        if self.reset_at.map_or(false, |reset_at| reset_at <= now) {
+            self.remaining = None;
+            self.reset_at = None;
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct RateLimit {
+    remaining: Option<u64>,
+    /// How long until the window of the rate limit starts over
+    reset: Option<Duration>,
+    retry_after: Option<Duration>,
+}
+
+impl RateLimit {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        let mut limit = RateLimit {
+            remaining: number("ratelimit-remaining").or_else(|| number("x-ratelimit-remaining")),
+            reset: number("ratelimit-reset")
+                .or_else(|| number("x-ratelimit-reset"))
+                .map(until),
+            retry_after: number("retry-after").map(Duration::from_secs),
+        };
+        // newer endpoints combine them into one header, like `"default";r=50;t=30`
+        if let Some(combined) = headers.get("ratelimit").and_then(|v| v.to_str().ok()) {
+            for param in combined.split(';') {
+                match param.trim().split_once('=') {
+                    Some(("r", r)) => limit.remaining = limit.remaining.or(r.parse().ok()),
+                    Some(("t", t)) => {
+                        limit.reset = limit.reset.or(t.parse().ok().map(Duration::from_secs))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        limit
+    }
+}
+
+// Resets are in seconds from now, or with some endpoints a unix timestamp
+fn until(reset: u64) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    if reset > now / 2 {
+        Duration::from_secs(reset.saturating_sub(now))
+    } else {
+        Duration::from_secs(reset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn it_reads_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("12"));
+        headers.insert(
+            "ratelimit",
+            HeaderValue::from_static("\"default\";r=50;t=30"),
+        );
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(
+            RateLimit::from_headers(&headers),
+            RateLimit {
+                remaining: Some(12),
+                reset: Some(Duration::from_secs(30)),
+                retry_after: Some(Duration::from_secs(7)),
+            }
+        );
+    }
+
+    #[test]
+    fn it_spreads_the_last_requests_over_the_window() {
+        let now = Instant::now();
+        let mut scheduler = Scheduler::default();
+        assert_eq!(scheduler.reserve(now), Duration::ZERO);
+
+        let limit = RateLimit {
+            remaining: Some(4),
+            reset: Some(Duration::from_secs(8)),
+            retry_after: None,
+        };
+        scheduler.observe(StatusCode::OK, &limit, now);
+        assert_eq!(scheduler.reserve(now), Duration::ZERO);
+        assert_eq!(scheduler.reserve(now), Duration::from_secs(2));
+        // with none left, the rest wait for the window to start over
+        scheduler.reserve(now);
+        scheduler.reserve(now);
+        assert_eq!(scheduler.reserve(now), Duration::from_secs(8));
+        assert_eq!(
+            scheduler.reserve(now + Duration::from_secs(9)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn it_holds_off_after_being_rate_limited() {
+        let now = Instant::now();
+        let mut scheduler = Scheduler::default();
+        let limit = RateLimit {
+            retry_after: Some(Duration::from_secs(3)),
+            ..Default::default()
+        };
+        scheduler.observe(StatusCode::TOO_MANY_REQUESTS, &limit, now);
+        assert_eq!(scheduler.reserve(now), Duration::from_secs(3));
+    }
+}
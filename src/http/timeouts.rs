@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+
+use super::DEFAULT_HTTP_TIMEOUT_SECONDS;
+
+// a script with its wasm can be megabytes, which takes a while over a slow link
+const DEFAULT_UPLOAD_TIMEOUT_SECONDS: u64 = 300;
+const DEFAULT_CONNECT_TIMEOUT_SECONDS: u64 = 10;
+// what reqwest keeps idle connections for by default
+const DEFAULT_KEEP_ALIVE_SECONDS: u64 = 90;
+
+static TIMEOUTS: OnceCell<Timeouts> = OnceCell::new();
+
+/// How long the clients wait on the API, in seconds. Set by the `[http]` table of the global
+/// configuration file and the flags of the same names.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    /// For a whole request, from connecting to reading the end of the response
+    pub timeout: u64,
+    /// Like `timeout`, for uploads of scripts
+    pub upload_timeout: u64,
+    /// For setting up a connection
+    pub connect_timeout: u64,
+    /// For keeping an idle connection open for the next request, 0 to not reuse connections
+    pub keep_alive: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            timeout: DEFAULT_HTTP_TIMEOUT_SECONDS,
+            upload_timeout: DEFAULT_UPLOAD_TIMEOUT_SECONDS,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT_SECONDS,
+            keep_alive: DEFAULT_KEEP_ALIVE_SECONDS,
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn request(&self) -> Duration {
+        Duration::from_secs(self.timeout)
+    }
+
+    pub fn upload(&self) -> Duration {
+        Duration::from_secs(self.upload_timeout)
+    }
+
+    pub fn connect(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout)
+    }
+
+    pub fn keep_alive(&self) -> Duration {
+        Duration::from_secs(self.keep_alive)
+    }
+
+    /// How many idle connections to a host are kept open, none without keep-alive
+    pub fn max_idle_connections(&self) -> usize {
+        match self.keep_alive {
+            0 => 0,
+            _ => usize::MAX,
+        }
+    }
+
+    fn check(&self) -> Result<()> {
+        let zero = [
+            ("timeout", self.timeout),
+            ("upload_timeout", self.upload_timeout),
+            ("connect_timeout", self.connect_timeout),
+        ]
+        .iter()
+        .find(|(_, seconds)| *seconds == 0)
+        .map(|(name, _)| *name);
+        match zero {
+            Some(name) => anyhow::bail!("`{}` has to be at least a second", name),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Has every client built after this wait on the API for as long as `timeouts` say, for the
+/// rest of the process. Only the first call has an effect.
+pub fn set_timeouts(timeouts: Timeouts) -> Result<()> {
+    timeouts.check()?;
+    let _ = TIMEOUTS.set(timeouts);
+    Ok(())
+}
+
+/// The timeouts set with `set_timeouts`, or the defaults
+pub fn timeouts() -> Timeouts {
+    TIMEOUTS.get().copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_fills_in_the_timeouts_left_out() {
+        let timeouts: Timeouts = toml::from_str("upload_timeout = 900\nkeep_alive = 0").unwrap();
+        assert_eq!(timeouts.upload(), Duration::from_secs(900));
+        assert_eq!(timeouts.keep_alive, 0);
+        assert_eq!(timeouts.timeout, DEFAULT_HTTP_TIMEOUT_SECONDS);
+        assert!(timeouts.check().is_ok());
+
+        let none = Timeouts {
+            connect_timeout: 0,
+            ..Timeouts::default()
+        };
+        assert!(none.check().is_err());
+        assert!(toml::from_str::<Timeouts>("timout = 5").is_err());
+    }
+}
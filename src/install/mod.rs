@@ -1,12 +1,17 @@
 pub mod dependencies;
 pub mod target;
 
+use crate::http::{self, SendLogged};
 use crate::terminal::emoji;
+use crate::terminal::message::{Message, StdErr};
 
 use anyhow::{anyhow, Result};
 use binary_install::{Cache, Download};
 use log::info;
+use reqwest::StatusCode;
 use semver::Version;
+use sha2::{Digest, Sha256};
+use url::Url;
 
 use std::env;
 use std::fs;
@@ -14,6 +19,8 @@ use std::path::{Path, PathBuf};
 
 use once_cell::sync::Lazy;
 
+const DEFAULT_DOWNLOAD_BASE: &str = "https://workers.cloudflare.com";
+
 static CACHE: Lazy<Cache> =
     Lazy::new(|| get_wrangler_cache().expect("Could not get Wrangler cache location"));
 
@@ -65,6 +72,15 @@ pub fn install(
     Ok(download)
 }
 
+/// Where `version` of `tool_name` is installed in the wrangler cache, without installing it
+pub fn installed(tool_name: &str, version: &str) -> Option<PathBuf> {
+    let version = Version::parse(version).ok()?;
+    match get_installation(tool_name, &version) {
+        Ok(Some((_, location))) => Some(location),
+        _ => None,
+    }
+}
+
 fn tool_needs_update(tool_name: &str, target_version: Version) -> Result<ToolDownload> {
     let current_installation = get_installation(tool_name, &target_version);
     // if something goes wrong checking the current installation
@@ -118,14 +134,22 @@ fn download_prebuilt(
 
     info!("prebuilt artifact {}", url);
 
+    // the cache unpacks the artifact, after we've made sure it's the one that was published
+    let download_dir = tempfile::tempdir()?;
+    let artifact_path = download_verified(&url, download_dir.path())?;
+    let artifact_url = match Url::from_file_path(&artifact_path) {
+        Ok(artifact_url) => artifact_url.to_string(),
+        Err(_) => anyhow::bail!("could not read {}", artifact_path.display()),
+    };
+
     // no binaries are expected; downloading it as an artifact
     let res = if !binaries.is_empty() {
         CACHE
-            .download_version(true, tool_name, binaries, &url, version)
+            .download_version(true, tool_name, binaries, &artifact_url, version)
             .map_err(|e| e.compat())?
     } else {
         CACHE
-            .download_artifact_version(tool_name, &url, version)
+            .download_artifact_version(tool_name, &artifact_url, version)
             .map_err(|e| e.compat())?
     };
 
@@ -135,11 +159,82 @@ fn download_prebuilt(
     }
 }
 
+// Downloads `url` into `dir` and checks it against the SHA-256 checksum published next to
+// it at `<url>.sha256`. The checksum comes from the same server, or mirror, as the artifact,
+// so it catches corrupted and truncated downloads, not a server that serves a tampered
+// artifact along with its checksum. Nothing is installed from an artifact that fails the
+// check; one without a published checksum is installed with a warning that it's unverified.
+fn download_verified(url: &str, dir: &Path) -> Result<PathBuf> {
+    let client = http::client();
+    let fetch = |url: &str| -> Result<Option<Vec<u8>>> {
+        let res = client.get(url).send_logged()?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !res.status().is_success() {
+            anyhow::bail!("downloading {} failed with {}", url, res.status())
+        }
+        Ok(Some(res.bytes()?.to_vec()))
+    };
+
+    let artifact = match fetch(url)? {
+        Some(artifact) => artifact,
+        None => anyhow::bail!("downloading {} failed with {}", url, StatusCode::NOT_FOUND),
+    };
+    let checksum_url = format!("{}.sha256", url);
+    match fetch(&checksum_url) {
+        Ok(Some(checksum)) => verify_checksum(&artifact, &String::from_utf8_lossy(&checksum))
+            .map_err(|e| anyhow!("{} was not installed: {}", url, e))?,
+        Ok(None) => StdErr::warn(&format!(
+            "No checksum is published for {}, so it's installed without checking that it downloaded intact",
+            url
+        )),
+        Err(e) => anyhow::bail!(
+            "{}\nThe checksum of {} is needed to verify it before it is installed",
+            e,
+            url
+        ),
+    }
+
+    let path = dir.join("artifact.tar.gz");
+    fs::write(&path, artifact)?;
+    Ok(path)
+}
+
+fn verify_checksum(contents: &[u8], published: &str) -> Result<()> {
+    // checksum files are either just the digest or the output of `sha256sum`
+    let expected = published
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let actual = hex::encode(Sha256::digest(contents));
+    if expected != actual {
+        anyhow::bail!(
+            "its SHA-256 checksum is {} but {} was published for it. The download is corrupted",
+            actual,
+            expected
+        )
+    }
+    Ok(())
+}
+
+// Where prebuilt binaries are downloaded from. `WRANGLER_MIRROR` points this to a mirror
+// of workers.cloudflare.com, for machines that can't reach it.
+fn download_base() -> String {
+    match env::var("WRANGLER_MIRROR") {
+        Ok(mirror) if !mirror.is_empty() => mirror.trim_end_matches('/').to_string(),
+        _ => DEFAULT_DOWNLOAD_BASE.to_string(),
+    }
+}
+
 fn prebuilt_url(tool_name: &str, owner: &str, version: &str) -> Option<String> {
     if tool_name == "wranglerjs" {
         Some(format!(
-            "https://workers.cloudflare.com/get-wranglerjs-binary/{0}/v{1}.tar.gz",
-            tool_name, version
+            "{0}/get-wranglerjs-binary/{1}/v{2}.tar.gz",
+            download_base(),
+            tool_name,
+            version
         ))
     } else {
         let target = if target::LINUX && target::x86_64 {
@@ -153,8 +248,12 @@ fn prebuilt_url(tool_name: &str, owner: &str, version: &str) -> Option<String> {
         };
 
         let url = format!(
-            "https://workers.cloudflare.com/get-binary/{0}/{1}/v{2}/{3}.tar.gz",
-            owner, tool_name, version, target
+            "{0}/get-binary/{1}/{2}/v{3}/{4}.tar.gz",
+            download_base(),
+            owner,
+            tool_name,
+            version,
+            target
         );
         Some(url)
     }
@@ -167,3 +266,20 @@ fn get_wrangler_cache() -> Result<Cache> {
         Cache::new("wrangler").map_err(|e| anyhow::Error::from(e.compat()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_verifies_published_checksums() {
+        let contents = b"wasm-pack";
+        let digest = hex::encode(Sha256::digest(contents));
+
+        assert!(verify_checksum(contents, &digest).is_ok());
+        assert!(verify_checksum(contents, &format!("{}  wasm-pack.tar.gz\n", digest)).is_ok());
+        assert!(verify_checksum(contents, &digest.to_uppercase()).is_ok());
+        assert!(verify_checksum(b"something else", &digest).is_err());
+        assert!(verify_checksum(contents, "").is_err());
+    }
+}
@@ -1,38 +1,36 @@
 use std::time::Duration;
 
 use anyhow::Result;
+use futures::{stream, StreamExt};
 use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
 
-use cloudflare::endpoints::workerskv::delete_bulk::DeleteBulk;
-use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
-use cloudflare::endpoints::workerskv::write_bulk::WriteBulk;
-use cloudflare::framework::apiclient::ApiClient;
-use cloudflare::framework::auth::Credentials;
-use cloudflare::framework::{HttpApiClient, HttpApiClientConfig};
-
-use crate::commands::kv::format_error;
-use crate::http;
-use crate::http::feature::headers;
+use crate::http::{self, CloudflareApi, HttpApi};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::progress::{self, Phase, ProgressEvent};
 
 const API_MAX_PAIRS: usize = 10000;
 // The consts below are halved from the API's true capacity to help avoid
 // hammering it with large requests.
 pub const BATCH_KEY_MAX: usize = API_MAX_PAIRS / 2;
 const UPLOAD_MAX_SIZE: usize = 50 * 1024 * 1024;
-
-// Create a special API client that has a longer timeout than usual, given that KV operations
-// can be lengthy if payloads are large.
-fn bulk_api_client(user: &GlobalUser) -> Result<HttpApiClient> {
-    let config = HttpApiClientConfig {
-        http_timeout: Duration::from_secs(5 * 60),
-        default_headers: headers(None),
-    };
-
-    let environment = http::get_environment()?;
-
-    HttpApiClient::new(Credentials::from(user.to_owned()), config, environment)
+pub(crate) const BULK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// A key value pair of the bulk write API. The one of cloudflare-rs has no field for the
+/// metadata of a key, so these are written with a request of our own.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct KeyValuePair {
+    pub key: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_ttl: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base64: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
 }
 
 pub fn put(
@@ -42,24 +40,63 @@ pub fn put(
     pairs: Vec<KeyValuePair>,
     progress_bar: &Option<ProgressBar>,
 ) -> Result<()> {
-    let client = bulk_api_client(user)?;
-
-    for b in batch_keys_values(pairs) {
-        match client.request(&WriteBulk {
-            account_identifier: target.account_id.load()?,
-            namespace_identifier: namespace_id,
-            bulk_key_value_pairs: b.to_owned(),
-        }) {
-            Ok(_) => {}
-            Err(e) => anyhow::bail!("{}", format_error(e)),
-        }
+    put_with(
+        &HttpApi::new(user),
+        target,
+        namespace_id,
+        pairs,
+        progress_bar,
+        |_| Ok(()),
+    )
+}
 
-        if let Some(pb) = &progress_bar {
-            pb.inc(b.len() as u64);
-        }
-    }
+/// Like `put`, but calls `on_written` with each batch of pairs once it's written. Batches are
+/// written a few at a time, so they may finish in any order.
+pub fn put_with<F>(
+    api: &impl CloudflareApi,
+    target: &Target,
+    namespace_id: &str,
+    pairs: Vec<KeyValuePair>,
+    progress_bar: &Option<ProgressBar>,
+    mut on_written: F,
+) -> Result<()>
+where
+    F: FnMut(&[KeyValuePair]) -> Result<()>,
+{
+    let account_id = target.account_id.load()?;
+
+    let total_files = pairs.len();
+    let total_bytes: u64 = pairs.iter().map(|p| p.value.len() as u64).sum();
+    let (mut files, mut bytes) = (0, 0);
+
+    http::block_on(async {
+        let mut written = stream::iter(batch_keys_values(pairs))
+            .map(|b| async move {
+                let written = api.put_bulk(account_id, namespace_id, &b).await;
+                written.map(|()| b)
+            })
+            .buffer_unordered(http::MAX_CONCURRENT_REQUESTS);
+
+        while let Some(b) = written.next().await {
+            let b = b?;
+            on_written(&b)?;
+
+            if let Some(pb) = &progress_bar {
+                pb.inc(b.len() as u64);
+            }
 
-    Ok(())
+            files += b.len();
+            bytes += b.iter().map(|p| p.value.len() as u64).sum::<u64>();
+            progress::emit(ProgressEvent::Uploaded {
+                phase: Phase::UploadAssets,
+                files,
+                total_files,
+                bytes,
+                total_bytes,
+            });
+        }
+        Ok::<_, anyhow::Error>(())
+    })?
 }
 
 pub fn delete(
@@ -69,24 +106,41 @@ pub fn delete(
     keys: Vec<String>,
     progress_bar: &Option<ProgressBar>,
 ) -> Result<()> {
-    let client = bulk_api_client(user)?;
-
-    for b in batch_keys(keys) {
-        match client.request(&DeleteBulk {
-            account_identifier: target.account_id.load()?,
-            namespace_identifier: namespace_id,
-            bulk_keys: b.to_owned(),
-        }) {
-            Ok(_) => {}
-            Err(e) => anyhow::bail!("{}", format_error(e)),
-        }
+    delete_with(
+        &HttpApi::new(user),
+        target,
+        namespace_id,
+        keys,
+        progress_bar,
+    )
+}
 
-        if let Some(pb) = &progress_bar {
-            pb.inc(b.len() as u64);
+/// Like `delete`, with the batches of keys deleted through `api`
+pub fn delete_with(
+    api: &impl CloudflareApi,
+    target: &Target,
+    namespace_id: &str,
+    keys: Vec<String>,
+    progress_bar: &Option<ProgressBar>,
+) -> Result<()> {
+    let account_id = target.account_id.load()?;
+
+    http::block_on(async {
+        let mut deleted = stream::iter(batch_keys(keys))
+            .map(|b| async move {
+                let deleted = api.delete_bulk(account_id, namespace_id, &b).await;
+                deleted.map(|()| b.len())
+            })
+            .buffer_unordered(http::MAX_CONCURRENT_REQUESTS);
+
+        while let Some(count) = deleted.next().await {
+            let count = count?;
+            if let Some(pb) = &progress_bar {
+                pb.inc(count as u64);
+            }
         }
-    }
-
-    Ok(())
+        Ok::<_, anyhow::Error>(())
+    })?
 }
 
 fn batch_keys_values(mut pairs: Vec<KeyValuePair>) -> Vec<Vec<KeyValuePair>> {
@@ -142,3 +196,40 @@ fn batch_keys(mut keys: Vec<String>) -> Vec<Vec<String>> {
 
     batches
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::MockApi;
+
+    #[test]
+    fn it_writes_and_deletes_every_batch() {
+        let api = MockApi::new();
+        let target = Target {
+            account_id: Some("account".to_string()).into(),
+            ..Default::default()
+        };
+        let pairs: Vec<KeyValuePair> = (0..BATCH_KEY_MAX + 10)
+            .map(|i| KeyValuePair {
+                key: format!("key-{}", i),
+                value: i.to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        let mut batches = 0;
+        put_with(&api, &target, "namespace", pairs, &None, |_| {
+            batches += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(batches, 2);
+        assert_eq!(api.namespace("namespace").len(), BATCH_KEY_MAX + 10);
+
+        let keys = (10..BATCH_KEY_MAX + 10)
+            .map(|i| format!("key-{}", i))
+            .collect();
+        delete_with(&api, &target, "namespace", keys, &None).unwrap();
+        assert_eq!(api.namespace("namespace").len(), 10);
+    }
+}
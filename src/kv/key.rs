@@ -71,32 +71,25 @@ impl Iterator for KeyList {
     type Item = Result<Key, ApiFailure>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Attempt to extract next key from vector of keys in KeyList.
-        // If no key vector or no keys left, go to fallback case below to
-        // attempt to fetch the next page of keys from the Workers KV API.
-        if let Some(mut keys) = self.keys_result.to_owned() {
-            let key = keys.pop();
-            self.keys_result = Some(keys);
-
-            if let Some(k) = key {
-                return Some(Ok(k));
+        loop {
+            // Attempt to extract next key from the current page of keys, which is stored
+            // in reverse so that popping yields the keys in the order the API returned them.
+            if let Some(key) = self.keys_result.as_mut().and_then(Vec::pop) {
+                return Some(Ok(key));
             }
-        }
-        // Fallback case (if no remaining keys are found)
-        if self.cursor.is_none() && self.init_fetch {
-            None // Nothing left to fetch
-        } else {
-            if !self.init_fetch {
-                // At this point, initial fetch is being performed.
-                self.init_fetch = true;
+            // Fallback case (if no remaining keys are found): fetch the next page of keys
+            // from the Workers KV API, if there is one.
+            if self.cursor.is_none() && self.init_fetch {
+                return None; // Nothing left to fetch
             }
+            // From here on, an empty cursor means every page has been fetched.
+            self.init_fetch = true;
             match self.get_batch() {
                 Ok(mut keys) => {
-                    let key = keys.pop();
+                    keys.reverse();
                     self.keys_result = Some(keys);
-                    key.map(Ok)
                 }
-                Err(e) => Some(Err(e)),
+                Err(e) => return Some(Err(e)),
             }
         }
     }
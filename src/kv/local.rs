@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::kv::bulk::{self, KeyValuePair};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+
+/// A KV namespace kept in `.wrangler/kv/` of the project, for seeding and inspecting data
+/// without writing to the namespaces of your account. It's read and written by the `--local`
+/// variants of the `kv:key` commands. `wrangler dev` runs your worker on Cloudflare's edge,
+/// which can't reach this store, so `wrangler dev --local-kv` copies it into the preview
+/// namespaces first, see `seed`.
+#[derive(Debug)]
+pub struct LocalNamespace {
+    path: PathBuf,
+    entries: BTreeMap<String, LocalEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct LocalEntry {
+    /// The value, base64 encoded since values don't have to be text
+    value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expiration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<serde_json::Value>,
+}
+
+/// A key of a local namespace, printed the way `kv:key list` prints the keys of the API
+#[derive(Debug, PartialEq, Serialize)]
+pub struct LocalKey {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl LocalNamespace {
+    /// Opens the local namespace with the given id, which is empty until something is put
+    /// into it
+    pub fn open(namespace_id: &str) -> Result<Self> {
+        Self::open_in(&Path::new(".wrangler").join("kv"), namespace_id)
+    }
+
+    fn open_in(dir: &Path, namespace_id: &str) -> Result<Self> {
+        if namespace_id.is_empty() || namespace_id.contains(&['/', '\\', '.'][..]) {
+            anyhow::bail!("\"{}\" is not a valid namespace id", namespace_id)
+        }
+        let path = dir.join(format!("{}.json", namespace_id));
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| {
+                anyhow::anyhow!("{} is not a local namespace: {}", path.display(), e)
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => anyhow::bail!("Could not read {}: {}", path.display(), e),
+        };
+        Ok(LocalNamespace { path, entries })
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.entries.get(key) {
+            Some(entry) if !is_expired(entry) => Ok(Some(base64::decode(&entry.value)?)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn put(
+        &mut self,
+        key: &str,
+        value: &[u8],
+        expiration: Option<u64>,
+        metadata: Option<serde_json::Value>,
+    ) {
+        let entry = LocalEntry {
+            value: base64::encode(value),
+            expiration,
+            metadata,
+        };
+        self.entries.insert(key.to_string(), entry);
+    }
+
+    /// Returns whether there was a value to delete
+    pub fn delete(&mut self, key: &str) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    /// The keys that start with `prefix` and haven't expired, in lexicographic order like
+    /// the API lists them
+    pub fn keys(&self, prefix: Option<&str>) -> Vec<LocalKey> {
+        self.entries
+            .iter()
+            .filter(|(name, entry)| {
+                name.starts_with(prefix.unwrap_or_default()) && !is_expired(entry)
+            })
+            .map(|(name, entry)| LocalKey {
+                name: name.clone(),
+                expiration: entry.expiration,
+                metadata: entry.metadata.clone(),
+            })
+            .collect()
+    }
+
+    /// The values that haven't expired, the way the bulk API writes them
+    pub fn pairs(&self) -> Vec<KeyValuePair> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| !is_expired(entry))
+            .map(|(name, entry)| KeyValuePair {
+                key: name.clone(),
+                value: entry.value.clone(),
+                expiration: entry.expiration.map(|expiration| expiration as i64),
+                expiration_ttl: None,
+                base64: Some(true),
+                metadata: entry.metadata.clone(),
+            })
+            .collect()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.entries)?)?;
+        Ok(())
+    }
+}
+
+/// Writes the local namespace of each KV namespace of `target` into it, for `wrangler dev` to
+/// see the data of the local store. `target` is the one of a preview, so its namespaces are
+/// the preview namespaces, and the local ones are filled with `kv:key put --local --preview`.
+pub fn seed(target: &Target, user: &GlobalUser) -> Result<()> {
+    for namespace in &target.kv_namespaces {
+        let pairs = LocalNamespace::open(&namespace.id)?.pairs();
+        if pairs.is_empty() {
+            continue;
+        }
+        let count = pairs.len();
+        bulk::put(target, user, &namespace.id, pairs, &None)?;
+        StdErr::info(&format!(
+            "Copied {} key(s) of the local namespace into {}",
+            count, namespace.binding
+        ));
+    }
+    Ok(())
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default()
+}
+
+fn is_expired(entry: &LocalEntry) -> bool {
+    entry
+        .expiration
+        .map_or(false, |expiration| expiration <= now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_persists_values_between_opens() -> Result<()> {
+        let dir = tempdir()?;
+
+        let mut namespace = LocalNamespace::open_in(dir.path(), "abc123")?;
+        namespace.put(
+            "greeting",
+            b"hello",
+            None,
+            Some(serde_json::json!({"lang": "en"})),
+        );
+        namespace.put("expired", b"gone", Some(1), None);
+        namespace.put("other", &[0, 159, 146, 150], None, None);
+        namespace.save()?;
+
+        let mut namespace = LocalNamespace::open_in(dir.path(), "abc123")?;
+        assert_eq!(namespace.get("greeting")?, Some(b"hello".to_vec()));
+        assert_eq!(namespace.get("other")?, Some(vec![0, 159, 146, 150]));
+        assert_eq!(namespace.get("expired")?, None);
+        let names: Vec<String> = namespace.keys(None).into_iter().map(|k| k.name).collect();
+        assert_eq!(names, vec!["greeting", "other"]);
+        assert_eq!(namespace.keys(Some("gr"))[0].name, "greeting");
+
+        assert!(namespace.delete("greeting"));
+        assert!(!namespace.delete("greeting"));
+        assert!(LocalNamespace::open_in(dir.path(), "../escape").is_err());
+
+        let pairs = namespace.pairs();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].key, "other");
+        assert_eq!(pairs[0].base64, Some(true));
+        Ok(())
+    }
+
+    #[test]
+    fn it_only_takes_a_missing_file_for_an_empty_namespace() -> Result<()> {
+        let dir = tempdir()?;
+        assert!(LocalNamespace::open_in(dir.path(), "missing")?
+            .keys(None)
+            .is_empty());
+
+        fs::write(dir.path().join("corrupt.json"), "{ not json")?;
+        assert!(LocalNamespace::open_in(dir.path(), "corrupt").is_err());
+
+        // a directory in place of the file can't be read
+        fs::create_dir(dir.path().join("unreadable.json"))?;
+        assert!(LocalNamespace::open_in(dir.path(), "unreadable").is_err());
+        Ok(())
+    }
+}
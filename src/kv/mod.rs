@@ -1,3 +1,4 @@
 pub mod bulk;
 pub mod key;
+pub mod local;
 pub mod namespace;
@@ -4,12 +4,14 @@ use cloudflare::endpoints::workerskv::WorkersKvNamespace;
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::{ApiFailure, ApiSuccess};
 
+use crate::http::AuditedRequest;
+
 pub fn create(
     client: &impl ApiClient,
     account_id: &str,
     title: &str,
 ) -> Result<ApiSuccess<WorkersKvNamespace>, ApiFailure> {
-    client.request(&CreateNamespace {
+    client.request_audited(&CreateNamespace {
         account_identifier: account_id,
         params: CreateNamespaceParams {
             title: title.to_string(),
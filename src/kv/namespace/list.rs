@@ -8,11 +8,10 @@ use anyhow::Result;
 use serde::Deserialize;
 
 use crate::commands::kv;
-use crate::settings::toml::Target;
 
 const MAX_NAMESPACES_PER_PAGE: u32 = 1000;
 
-pub fn list(client: &impl ApiClient, target: &Target) -> Result<Vec<WorkersKvNamespace>> {
+pub fn list(client: &impl ApiClient, account_id: &str) -> Result<Vec<WorkersKvNamespace>> {
     let mut namespaces: Vec<WorkersKvNamespace> = Vec::new();
     let mut all_namespaces_added = false;
     let mut page_number = 1;
@@ -23,7 +22,7 @@ pub fn list(client: &impl ApiClient, target: &Target) -> Result<Vec<WorkersKvNam
         };
 
         match client.request(&ListNamespaces {
-            account_identifier: target.account_id.load()?,
+            account_identifier: account_id,
             params,
         }) {
             Ok(response) => {
@@ -31,7 +30,7 @@ pub fn list(client: &impl ApiClient, target: &Target) -> Result<Vec<WorkersKvNam
                 page_number += 1;
                 all_namespaces_added = namespaces.len() >= get_total(&response)?;
             }
-            Err(e) => anyhow::bail!("{}", kv::format_error(e)),
+            Err(e) => return Err(kv::api_error(e)),
         }
     }
     Ok(namespaces)
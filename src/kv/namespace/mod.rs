@@ -6,4 +6,4 @@ mod upsert;
 pub use create::create;
 pub use delete::delete;
 pub use list::list;
-pub use upsert::{upsert, UpsertedNamespace};
+pub use upsert::{namespaces_cache_key, upsert, UpsertedNamespace};
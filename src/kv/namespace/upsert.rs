@@ -26,21 +26,43 @@ pub fn upsert(target: &Target, user: &GlobalUser, title: String) -> Result<Upser
                 if api_errors.errors.iter().any(|e| e.code == 10014) {
                     log::info!("Namespace {} already exists.", title);
 
-                    match list(&client, target)?
-                        .iter()
-                        .find(|ns| ns.title == title) {
-                        Some(namespace) => Ok(UpsertedNamespace::Reused(namespace.to_owned())),
+                    let account_id = target.account_id.load()?;
+                    let cache_key = namespaces_cache_key(account_id);
+                    let find = |namespaces: Vec<WorkersKvNamespace>| {
+                        namespaces.into_iter().find(|ns| ns.title == title)
+                    };
+
+                    // a cached list can predate the namespace, so fall back to asking the API
+                    let cached =
+                        http::cache::cached(user, &cache_key, || list(&client, account_id))?;
+                    let namespace = match find(cached) {
+                        Some(namespace) => Some(namespace),
+                        None => {
+                            http::cache::invalidate(user, &cache_key);
+                            find(http::cache::cached(user, &cache_key, || {
+                                list(&client, account_id)
+                            })?)
+                        }
+                    };
+
+                    match namespace {
+                        Some(namespace) => Ok(UpsertedNamespace::Reused(namespace)),
                         None => anyhow::bail!("namespace already exists, but could not be found in the API's listed namespaces"),
                     }
                 } else {
-                    anyhow::bail!("{}", http::format_error(e, Some(&error_suggestions)))
+                    Err(http::api_error(e, Some(&error_suggestions)))
                 }
             }
-            _ => anyhow::bail!("{}", http::format_error(e, Some(&error_suggestions))),
+            _ => Err(http::api_error(e, Some(&error_suggestions))),
         },
     }
 }
 
+/// The key under which the namespaces of an account are cached
+pub(crate) fn namespaces_cache_key(account_id: &str) -> String {
+    format!("kv_namespaces_{}", account_id)
+}
+
 fn error_suggestions(code: u16) -> &'static str {
     match code {
         10014 => "Namespace already exists, try using a different namespace.",
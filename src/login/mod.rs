@@ -1,4 +1,7 @@
 pub mod http;
+mod stale;
+
+pub use stale::{is_auth_error, offer_login};
 
 use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
@@ -61,8 +64,10 @@ pub fn run(scopes: Option<&[String]>) -> Result<()> {
     let (auth_url, csrf_state) = client_state.url();
 
     // Navigate to authorization endpoint
-    let browser_permission =
-        interactive::confirm("Allow Wrangler to open a page in your browser?")?;
+    let browser_permission = interactive::confirm_or_fail(
+        "Allow Wrangler to open a page in your browser?",
+        "set CF_API_TOKEN, or run `wrangler config` where there's a terminal",
+    )?;
     if !browser_permission {
         anyhow::bail!("In order to log in you must allow Wrangler to open your browser. If you don't want to do this consider using `wrangler config`");
     }
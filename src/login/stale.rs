@@ -0,0 +1,113 @@
+use anyhow::Result;
+use cloudflare::endpoints::user::{GetUserDetails, GetUserTokenStatus};
+use cloudflare::framework::apiclient::ApiClient;
+use cloudflare::framework::response::ApiFailure;
+use reqwest::StatusCode;
+
+use crate::error::WranglerError;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+// API codes for credentials the API doesn't accept at all, as opposed to credentials
+// that are valid but lack a permission
+const INVALID_CREDENTIALS_CODES: &[u16] = &[6003, 6111, 9103, 9106, 9109, 10000];
+
+/// Whether `error` is the API refusing the configured credentials. Credentials missing from
+/// the configuration, or a request that never got an answer, aren't.
+pub fn is_auth_error(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<WranglerError>() {
+        Some(WranglerError::Api { status, errors, .. }) => {
+            *status == StatusCode::UNAUTHORIZED
+                || *status == StatusCode::FORBIDDEN
+                || errors
+                    .iter()
+                    .any(|e| INVALID_CREDENTIALS_CODES.contains(&e.code))
+        }
+        _ => false,
+    }
+}
+
+/// Asks the API whether `user` is still allowed in at all, to tell expired or revoked
+/// credentials apart from ones missing a permission. Only an answer of the API that says
+/// so counts, not a request that failed on the way.
+fn is_stale(user: &GlobalUser) -> Result<bool> {
+    let client = http::cf_v4_client(user)?;
+    let stale = match user {
+        GlobalUser::ApiTokenAuth { .. } => match client.request(&GetUserTokenStatus {}) {
+            Ok(success) => success.result.status != "active",
+            Err(e) => is_refused(&e),
+        },
+        GlobalUser::GlobalKeyAuth { .. } | GlobalUser::OAuthTokenAuth { .. } => {
+            match client.request(&GetUserDetails {}) {
+                Ok(_) => false,
+                Err(e) => is_refused(&e),
+            }
+        }
+    };
+    Ok(stale)
+}
+
+// Whether the API answered that the credentials aren't accepted
+fn is_refused(failure: &ApiFailure) -> bool {
+    match failure {
+        ApiFailure::Error(status, _) => {
+            *status == StatusCode::UNAUTHORIZED || *status == StatusCode::FORBIDDEN
+        }
+        ApiFailure::Invalid(_) => false,
+    }
+}
+
+/// Offers to log in again after a command failed because the configured credentials expired
+/// or were revoked, then tells the user to run the command again: it may have done part of
+/// its work already, so it isn't run again on its own. Nothing is offered without anybody
+/// at the terminal, or with `--yes`, since logging in needs a browser.
+pub fn offer_login(error: &anyhow::Error) -> Result<()> {
+    if !is_auth_error(error) || !interactive::is_interactive() || interactive::assume_yes() {
+        return Ok(());
+    }
+    let user = match GlobalUser::new() {
+        Ok(user) => user,
+        Err(_) => return Ok(()),
+    };
+    if !is_stale(&user)? {
+        return Ok(());
+    }
+
+    if GlobalUser::is_from_environment() {
+        StdOut::warn("The credentials in your CF_API_TOKEN, CF_API_KEY or CF_EMAIL environment variables are no longer valid.");
+        return Ok(());
+    }
+    if !interactive::confirm(
+        "Your Cloudflare credentials have expired or been revoked. Log in again?",
+    )? {
+        return Ok(());
+    }
+
+    super::run(None)?;
+    StdErr::info("Logged in again. Run the command again to retry it.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_recognizes_auth_errors() {
+        let forbidden = WranglerError::from_api_response(
+            StatusCode::FORBIDDEN,
+            r#"{"success": false, "errors": [{"code": 10000, "message": "Authentication error"}], "messages": []}"#.to_string(),
+        );
+        assert!(is_auth_error(&forbidden.into()));
+
+        let not_found =
+            WranglerError::from_api_response(StatusCode::NOT_FOUND, "not found".to_string());
+        assert!(!is_auth_error(&not_found.into()));
+        assert!(!is_auth_error(&anyhow::anyhow!("Authentication error")));
+        assert!(!is_auth_error(
+            &WranglerError::Auth("config path does not exist".to_string()).into()
+        ));
+    }
+}
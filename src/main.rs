@@ -4,21 +4,41 @@ extern crate text_io;
 extern crate tokio;
 
 use std::env;
+use std::ffi::OsString;
 
 use wrangler::cli::{exec, Cli, Command};
-use wrangler::commands;
+use wrangler::commands::{self, palette};
+use wrangler::error;
+use wrangler::http;
 use wrangler::installer;
+use wrangler::login;
+use wrangler::preview::Expectations;
 use wrangler::reporter;
+use wrangler::settings::global_user;
+use wrangler::settings::toml::overrides;
+use wrangler::terminal::interactive;
+use wrangler::terminal::message::{self, Verbosity};
 use wrangler::version::check_for_updates;
 
 use anyhow::Result;
 use structopt::StructOpt;
 
-fn main() -> Result<()> {
+// Failures exit with the code of their kind, see `error::exit_code`
+fn main() {
+    if let Err(e) = try_main() {
+        eprintln!("Error: {:?}", e);
+        // the requests of this run can be looked up by it
+        if http::audit::correlation_id_sent() {
+            eprintln!("Correlation ID: {}", http::audit::correlation_id());
+        }
+        std::process::exit(error::exit_code(&e));
+    }
+}
+
+fn try_main() -> Result<()> {
     if !cfg!(debug_assertions) {
         reporter::init();
     }
-    env_logger::init();
 
     if let Ok(me) = env::current_exe() {
         // If we're actually running as the installer then execute our
@@ -32,17 +52,82 @@ fn main() -> Result<()> {
             installer::install()?;
         }
     }
-    run()?;
-    check_for_updates();
+    let args = command_line()?;
+    let cli = Cli::from_iter(&args);
+    // commands that work on the project alone are run in tight edit loops, so they skip the
+    // global configuration file and the update check, which can wait on crates.io
+    let offline = cli.command.is_offline();
+    if !offline {
+        http::proxy::init();
+    }
+    if let Err(e) = run(&args, cli) {
+        // expired or revoked credentials can be replaced before the command is run again
+        login::offer_login(&e)?;
+        return Err(e);
+    }
+    if !offline {
+        check_for_updates();
+    }
     Ok(())
 }
 
-fn run() -> Result<()> {
-    let cli = Cli::from_args();
+// Without arguments, let somebody at the terminal pick a command rather than only print help
+fn command_line() -> Result<Vec<OsString>> {
+    let args: Vec<OsString> = env::args_os().collect();
+    if args.len() > 1 || !interactive::is_interactive() {
+        return Ok(args);
+    }
+    Ok(match palette::pick()? {
+        Some(picked) => args
+            .into_iter()
+            .take(1)
+            .chain(picked.into_iter().map(OsString::from))
+            .collect(),
+        None => args,
+    })
+}
+
+// RUST_LOG still picks what's logged when it's set
+fn init_logger(verbosity: Verbosity) {
+    let level = match verbosity {
+        Verbosity::Verbose => "wrangler=debug,warn",
+        Verbosity::Normal | Verbosity::Quiet => "error",
+    };
+    let _ = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level))
+        .try_init();
+}
+
+fn run(args: &[OsString], mut cli: Cli) -> Result<()> {
+    init_logger(cli.verbosity());
+    message::set_verbosity(cli.verbosity());
+    http::set_cache_bypass(cli.no_cache);
+    if cli.yes {
+        interactive::set_assume_yes();
+    }
+    if cli.non_interactive {
+        interactive::set_non_interactive();
+    }
+    if let Some(audit_log) = &cli.audit_log {
+        http::audit::set_audit_log(audit_log.clone());
+    }
+    http::set_timeouts(cli.timeouts()?)?;
+    cli.pick_environment()?;
+    cli.locate_config()?;
+    if let Some(profile) = cli.profile()? {
+        log::info!("Using the credentials of the profile {}", profile);
+        global_user::set_profile(profile);
+    }
+    overrides::set(cli.overrides());
     let cli_params = cli.clone();
 
-    match cli.command {
-        Command::Config { api_key, no_verify } => exec::configure(api_key, no_verify),
+    let result = match cli.command {
+        Command::Config {
+            command: Some(command),
+            ..
+        } => exec::config(command, &cli_params),
+        Command::Config {
+            api_key, no_verify, ..
+        } => exec::configure(api_key, no_verify),
         Command::Generate {
             name,
             site,
@@ -54,14 +139,47 @@ fn run() -> Result<()> {
             site,
             target_type,
         } => exec::init(name, site, target_type),
-        Command::Build => exec::build(&cli_params),
+        Command::Import { name, output } => exec::import(&name, output, &cli_params),
+        Command::Build { watch, output } => exec::build(watch, output, &cli_params),
         Command::Preview {
             method,
             url,
             body,
+            request_method,
+            request_body,
+            headers,
             watch,
             headless,
-        } => exec::preview(method, url, body, watch, headless, &cli_params),
+            expect_status,
+            expect_header,
+            expect_body_contains,
+            zoned,
+            copy,
+            expires,
+            site_only,
+            local_assets,
+            inspect,
+        } => exec::preview(
+            request_method.unwrap_or(method),
+            url,
+            request_body.or(body),
+            headers,
+            watch,
+            headless,
+            Expectations {
+                status: expect_status,
+                headers: expect_header,
+                body_contains: expect_body_contains,
+            },
+            zoned,
+            copy,
+            expires,
+            site_only,
+            local_assets,
+            inspect,
+            cli_params.no_cache,
+            &cli_params,
+        ),
         Command::Dev {
             host,
             ip,
@@ -70,6 +188,8 @@ fn run() -> Result<()> {
             upstream_protocol,
             inspect,
             unauthenticated,
+            chaos,
+            local_kv,
         } => exec::dev(
             host,
             ip,
@@ -79,16 +199,102 @@ fn run() -> Result<()> {
             &cli_params,
             inspect,
             unauthenticated,
+            chaos,
+            local_kv,
         ),
         Command::Whoami => exec::whoami(),
+        Command::Doctor => exec::doctor(&cli_params),
         Command::Publish {
             release,
             output,
             migration,
-        } => exec::publish(release, output, migration, &cli_params),
+            analyze,
+            branch_preview,
+            all,
+            only,
+            changed_since,
+            progress_format,
+            message,
+            if_changed,
+            override_freeze,
+            check_types,
+            force,
+            allow_dirty,
+            tag,
+            config_only,
+            site_only,
+            dry_run,
+            canary,
+            promote,
+            abort,
+            all_envs,
+            fail_fast,
+            report,
+            ..
+        } => commands::publish_report::with_report(report.as_deref(), || {
+            exec::publish(
+                release,
+                output,
+                migration,
+                analyze,
+                branch_preview,
+                all,
+                only,
+                changed_since,
+                progress_format,
+                message,
+                cli_params.no_cache,
+                if_changed,
+                override_freeze,
+                check_types,
+                force,
+                allow_dirty,
+                tag,
+                config_only,
+                site_only,
+                dry_run,
+                canary,
+                promote,
+                abort,
+                all_envs,
+                fail_fast,
+                &cli_params,
+            )
+        }),
+        Command::Check { offline } => exec::check(offline, &cli_params),
+        Command::Compat(compat) => exec::compat(compat, &cli_params),
+        Command::Fmt { check } => exec::fmt(check, &cli_params),
+        Command::Deployments(deployments) => exec::deployments(deployments, &cli_params),
+        Command::Previews(previews) => exec::previews(previews, &cli_params),
+        Command::Site(site) => exec::site(site, &cli_params),
+        Command::Graph { format } => exec::graph(format, &cli_params),
+        Command::Completions { shell } => exec::completions(shell),
+        Command::Complete { kind } => exec::complete(kind, &cli_params),
+        Command::Api {
+            method,
+            path,
+            data,
+            headers,
+        } => exec::api(&method, &path, &headers, data, &cli_params),
+        Command::Stats { since, all, format } => exec::stats(since, all, format, &cli_params),
+        Command::Estimate {
+            requests,
+            avg_cpu_ms,
+            avg_duration_ms,
+            days,
+        } => exec::estimate(requests, avg_cpu_ms, avg_duration_ms, days, &cli_params),
         Command::Subdomain { name } => exec::subdomain(name, &cli_params),
         Command::Route(route) => exec::route(route, &cli_params),
+        Command::Scripts(scripts) => exec::scripts(scripts, &cli_params),
+        Command::Zone(zone) => exec::zone(zone, &cli_params),
+        Command::Queues(queues) => exec::queues(queues, &cli_params),
+        Command::Quota { threshold, paid } => exec::quota(threshold, paid, &cli_params),
+        Command::VerifySite { url, sample } => exec::verify_site(&url, sample, &cli_params),
+        Command::DurableObjects(durable_objects) => {
+            exec::durable_objects(durable_objects, &cli_params)
+        }
         Command::Secret(secret) => exec::secret(secret, &cli_params),
+        Command::Vars(vars) => exec::vars(vars, &cli_params),
         Command::KvNamespace(namespace) => exec::kv_namespace(namespace, &cli_params),
         Command::KvKey(key) => exec::kv_key(key, &cli_params),
         Command::KvBulk(bulk) => exec::kv_bulk(bulk, &cli_params),
@@ -103,6 +309,9 @@ fn run() -> Result<()> {
             header,
             ip_address,
             search,
+            slow_only,
+            threshold,
+            symbolicate,
             ..
         } => exec::tail(
             name,
@@ -115,15 +324,30 @@ fn run() -> Result<()> {
             header,
             ip_address,
             search,
+            if slow_only { Some(threshold) } else { None },
+            symbolicate,
             &cli_params,
         ),
+        Command::Logs(logs) => exec::logs(logs, &cli_params),
         Command::Login {
             scopes,
             scopes_list,
         } => exec::login(&scopes, scopes_list),
         Command::Logout => exec::logout(),
+        Command::Auth(auth) => exec::auth(auth),
+        Command::MockApi { port, fixtures } => commands::mock_api::serve(port, fixtures.as_deref()),
         Command::Report { log } => commands::report::run(log.as_deref()).map(|_| {
             eprintln!("Report submission sucessful. Thank you!");
         }),
+    };
+
+    // completion scripts run wrangler on every tab, which isn't worth remembering
+    let completing = matches!(
+        cli_params.command,
+        Command::Completions { .. } | Command::Complete { .. }
+    );
+    if result.is_ok() && !completing {
+        palette::record(&args[1..]);
     }
+    result
 }
@@ -0,0 +1,80 @@
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
+/// Assertions on the response of a preview, turning `wrangler preview` into a smoke test.
+#[derive(Clone, Debug, Default)]
+pub struct Expectations {
+    pub status: Option<u16>,
+    pub headers: Vec<(String, String)>,
+    pub body_contains: Vec<String>,
+}
+
+impl Expectations {
+    pub fn is_empty(&self) -> bool {
+        self.status.is_none() && self.headers.is_empty() && self.body_contains.is_empty()
+    }
+
+    /// Returns a description of every expectation the response doesn't meet.
+    pub fn failures(&self, status: StatusCode, headers: &HeaderMap, body: &str) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        if let Some(expected) = self.status {
+            if status.as_u16() != expected {
+                failures.push(format!("expected status {}, got {}", expected, status));
+            }
+        }
+
+        for (name, expected) in &self.headers {
+            let values: Vec<&str> = headers
+                .get_all(name.as_str())
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .collect();
+            // media types and the like are case-insensitive, so be lenient about the value
+            if !values.iter().any(|v| v.eq_ignore_ascii_case(expected)) {
+                let actual = if values.is_empty() {
+                    "no such header".to_string()
+                } else {
+                    format!("got \"{}\"", values.join(", "))
+                };
+                failures.push(format!(
+                    "expected header \"{}: {}\", {}",
+                    name, expected, actual
+                ));
+            }
+        }
+
+        for expected in &self.body_contains {
+            if !body.contains(expected.as_str()) {
+                failures.push(format!("expected the body to contain \"{}\"", expected));
+            }
+        }
+
+        failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, CONTENT_TYPE};
+
+    #[test]
+    fn it_reports_unmet_expectations() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        let expectations = Expectations {
+            status: Some(200),
+            headers: vec![("Content-Type".to_string(), "application/JSON".to_string())],
+            body_contains: vec!["ok".to_string()],
+        };
+        assert!(expectations
+            .failures(StatusCode::OK, &headers, r#"{"status":"ok"}"#)
+            .is_empty());
+
+        let failures = expectations.failures(StatusCode::NOT_FOUND, &HeaderMap::new(), "nope");
+        assert_eq!(failures.len(), 3);
+        assert_eq!(failures[0], "expected status 200, got 404 Not Found");
+    }
+}
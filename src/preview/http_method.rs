@@ -16,10 +16,22 @@ impl Default for HttpMethod {
 impl FromStr for HttpMethod {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self> {
-        match s {
+        match s.to_lowercase().as_str() {
             "get" => Ok(HttpMethod::Get),
             "post" => Ok(HttpMethod::Post),
-            _ => Ok(HttpMethod::default()),
+            _ => anyhow::bail!("Preview can't send {} requests, only get and post", s),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_rejects_methods_it_cant_send() {
+        assert!(matches!("POST".parse(), Ok(HttpMethod::Post)));
+        assert!("put".parse::<HttpMethod>().is_err());
+        assert!("delete".parse::<HttpMethod>().is_err());
+    }
+}
@@ -0,0 +1,186 @@
+use std::convert::Infallible;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use anyhow::Result;
+use hyper::header::{CACHE_CONTROL, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Response, Server, StatusCode};
+use percent_encoding::percent_decode_str;
+
+use super::upload::upload_without_assets;
+use super::RequestPayload;
+use crate::commands::dev;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::{open_browser, styles};
+use crate::watch::watch_and_build;
+
+/// Serves the site of `target` from its bucket on a local server, and sends every other
+/// request to the preview of the script, which was uploaded as `script_id` without the
+/// assets. With `watch`, the script is uploaded again whenever it changes, while changes to
+/// the assets are served as soon as they're made.
+pub fn serve(
+    target: Target,
+    user: &GlobalUser,
+    payload: RequestPayload,
+    script_id: String,
+    watch: bool,
+    headless: bool,
+) -> Result<()> {
+    let bucket = match &target.site {
+        Some(site) => site.bucket.clone(),
+        None => anyhow::bail!("{} has no [site] to serve the assets of", target.name),
+    };
+    let preview_id = Arc::new(Mutex::new(payload.preview_id(&script_id)));
+
+    if watch {
+        let (target, user, preview_id) = (target.clone(), user.clone(), preview_id.clone());
+        thread::spawn(move || watch_script(target, &user, payload, preview_id));
+    }
+
+    http::block_on(async move {
+        let bucket = Arc::new(bucket);
+        let client = dev::client();
+        let make_service = make_service_fn(move |_| {
+            let (bucket, client, preview_id) = (bucket.clone(), client.clone(), preview_id.clone());
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let (bucket, client) = (bucket.clone(), client.clone());
+                    let preview_id = preview_id.lock().unwrap().clone();
+                    async move {
+                        let method = req.method().clone();
+                        let path = req.uri().path().to_string();
+                        let (resp, source) = match asset_path(&bucket, &method, &path) {
+                            Some(asset) => (serve_asset(&asset, &method)?, "local"),
+                            None => (
+                                dev::forward_to_preview(&client, req, preview_id).await?,
+                                "worker",
+                            ),
+                        };
+                        println!("{} {} {} ({})", method, path, resp.status(), source);
+                        Ok::<_, anyhow::Error>(resp)
+                    }
+                }))
+            }
+        });
+
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let server = Server::try_bind(&addr)?.serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        StdOut::info(&format!(
+            "Serving the assets of your site at {}, and sending the rest of the requests to your worker",
+            styles::url(&url)
+        ));
+        if !headless {
+            open_browser(&url)?;
+        }
+        server.await?;
+        Ok(())
+    })?
+}
+
+fn serve_asset(asset: &Path, method: &Method) -> Result<Response<Body>> {
+    let content_type = mime_guess::from_path(asset).first_or_octet_stream();
+    let body = if method == Method::HEAD {
+        Body::empty()
+    } else {
+        Body::from(fs::read(asset)?)
+    };
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, content_type.as_ref())
+        // the files change while the preview runs
+        .header(CACHE_CONTROL, "no-cache")
+        .body(body)?)
+}
+
+/// The file of `bucket` a request is for, mapped the way the asset handler of Workers Sites
+/// maps them: paths ending in `/` are for their index.html, and paths without an extension
+/// for the index.html of the directory they name. Requests for anything else, or for files
+/// that aren't there, go to the worker.
+fn asset_path(bucket: &Path, method: &Method, path: &str) -> Option<PathBuf> {
+    if method != Method::GET && method != Method::HEAD {
+        return None;
+    }
+    let mut relative = percent_decode_str(path)
+        .decode_utf8()
+        .ok()?
+        .trim_start_matches('/')
+        .to_string();
+    if relative.is_empty() || relative.ends_with('/') {
+        relative.push_str("index.html");
+    } else if Path::new(&relative).extension().is_none() {
+        relative.push_str("/index.html");
+    }
+
+    // nothing outside of the bucket
+    let relative = Path::new(&relative);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    let asset = bucket.join(relative);
+    if asset.is_file() {
+        Some(asset)
+    } else {
+        None
+    }
+}
+
+fn watch_script(
+    target: Target,
+    user: &GlobalUser,
+    payload: RequestPayload,
+    preview_id: Arc<Mutex<String>>,
+) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    watch_and_build(&target, Some(tx), None)?;
+
+    while rx.recv().is_ok() {
+        match upload_without_assets(&mut target.clone(), user) {
+            Ok(script_id) => {
+                *preview_id.lock().unwrap() = payload.preview_id(&script_id);
+                StdOut::preview("Updated preview with changes");
+            }
+            Err(e) => StdOut::user_error(&format!("Could not update the preview: {}", e)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_maps_requests_to_assets_like_workers_sites() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let bucket = dir.path();
+        fs::create_dir_all(bucket.join("about"))?;
+        fs::create_dir_all(bucket.join("css"))?;
+        fs::write(bucket.join("index.html"), "home")?;
+        fs::write(bucket.join("about/index.html"), "about")?;
+        fs::write(bucket.join("css/main site.css"), "body {}")?;
+
+        let get = |path| asset_path(bucket, &Method::GET, path);
+        assert_eq!(get("/"), Some(bucket.join("index.html")));
+        assert_eq!(get("/about"), Some(bucket.join("about/index.html")));
+        assert_eq!(get("/about/"), Some(bucket.join("about/index.html")));
+        assert_eq!(
+            get("/css/main%20site.css"),
+            Some(bucket.join("css/main site.css"))
+        );
+        assert_eq!(get("/api/users"), None);
+        assert_eq!(get("/../index.html"), None);
+        assert_eq!(asset_path(bucket, &Method::POST, "/"), None);
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
+mod expect;
+pub use expect::Expectations;
+
 mod fiddle_messenger;
 use fiddle_messenger::*;
 
 mod http_method;
+mod local_assets;
 pub use http_method::HttpMethod;
 
 mod request_payload;
@@ -10,21 +14,25 @@ pub use request_payload::RequestPayload;
 mod upload;
 pub use upload::upload;
 
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, RecvTimeoutError};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use log::info;
+use reqwest::header::HeaderMap;
+use reqwest::{StatusCode, Version};
 use url::Url;
 use ws::{Sender, WebSocket};
 
-use crate::build::build_target;
-use crate::http;
+use crate::build::{build_target, Build};
+use crate::commands::dev;
+use crate::http::{self, SendLogged};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::{Target, UploadFormat};
-use crate::terminal::message::{Message, StdOut};
-use crate::terminal::open_browser;
+use crate::terminal::message::{Message, StdErr, StdOut};
 use crate::terminal::styles;
+use crate::terminal::{copy_to_clipboard, open_browser};
 use crate::watch::watch_and_build;
 
 pub fn preview(
@@ -43,9 +51,24 @@ pub fn preview(
         anyhow::bail!("wrangler preview is not yet supported for scripts that use Durable Objects. Please use wrangler dev instead.");
     }
 
-    build_target(&target)?;
-
     let sites_preview: bool = target.site.is_some();
+    if options.site_only && !sites_preview {
+        anyhow::bail!(
+            "{} has no [site] to preview the content of, leave out --site-only to preview it",
+            target.name
+        )
+    }
+    if options.local_assets && !sites_preview {
+        anyhow::bail!(
+            "{} has no [site] to serve the assets of, leave out --local-assets to preview it",
+            target.name
+        )
+    }
+    if options.no_cache {
+        Build::new(&target).without_cache().run()?;
+    } else if !options.site_only {
+        build_target(&target)?;
+    }
 
     if user.is_none() {
         let wrangler_config_msg = styles::highlight("`wrangler config`");
@@ -56,12 +79,51 @@ pub fn preview(
         );
     }
 
+    if options.local_assets {
+        let user = match &user {
+            Some(user) => user,
+            None => anyhow::bail!("Previewing a site with --local-assets needs your credentials"),
+        };
+        let script_id = upload::upload_without_assets(&mut target, user)?;
+        let request_payload = RequestPayload::create(
+            options.method.clone(),
+            options.url.clone(),
+            options.body.clone(),
+            options.headers.clone(),
+        );
+        return local_assets::serve(
+            target,
+            user,
+            request_payload,
+            script_id,
+            options.livereload,
+            options.headless,
+        );
+    }
+
     let script_id = upload(&mut target, user.as_ref(), sites_preview, verbose)?;
 
-    let request_payload = RequestPayload::create(options.method, options.url, options.body);
+    let request_payload = RequestPayload::create(
+        options.method.clone(),
+        options.url.clone(),
+        options.body.clone(),
+        options.headers.clone(),
+    );
 
     let session = &request_payload.session;
     let browser_url = &request_payload.browser_url;
+    share(&script_id, browser_url, options.copy);
+
+    let inspector = if options.inspect {
+        Some(dev::inspect_preview(
+            session,
+            &request_payload.preview_id(&script_id),
+            target.name.clone(),
+            browser_url.clone(),
+        )?)
+    } else {
+        None
+    };
 
     if options.livereload {
         // explicitly use 127.0.0.1, since localhost can resolve to 2 addresses
@@ -79,7 +141,13 @@ pub fn preview(
         }
 
         // Make a the initial request to the URL
-        client_request(&request_payload, &script_id, sites_preview);
+        client_request(
+            &request_payload,
+            &script_id,
+            sites_preview,
+            options.headless,
+            &options.expect,
+        )?;
 
         let broadcaster = server.broadcaster();
         thread::spawn(move || server.run());
@@ -88,18 +156,29 @@ pub fn preview(
             user.as_ref(),
             broadcaster,
             verbose,
-            options.headless,
             request_payload,
+            &options,
         )?;
     } else {
         if !options.headless {
-            open_browser(&format!(
-                "https://cloudflareworkers.com/?hide_editor#{0}:{1}",
-                script_id, browser_url
-            ))?;
+            open_browser(&share_url(&script_id, browser_url))?;
         }
 
-        client_request(&request_payload, &script_id, sites_preview);
+        client_request(
+            &request_payload,
+            &script_id,
+            sites_preview,
+            options.headless,
+            &options.expect,
+        )?;
+
+        // the preview lives on after the request, keep DevTools attached to it until stopped
+        if let Some(inspector) = inspector {
+            StdOut::info("Inspecting the preview, press Ctrl-C to stop");
+            inspector
+                .join()
+                .map_err(|_| anyhow::anyhow!("The DevTools bridge stopped unexpectedly"))??;
+        }
     }
 
     Ok(())
@@ -110,54 +189,115 @@ pub struct PreviewOpt {
     pub method: HttpMethod,
     pub url: Url,
     pub body: Option<String>,
+    pub headers: Vec<(String, String)>,
     pub livereload: bool,
     pub headless: bool,
+    pub expect: Expectations,
+    /// Copy the link to share the preview with to the clipboard
+    pub copy: bool,
+    /// How long a watched preview session lasts before wrangler stops updating it
+    pub expires: Option<Duration>,
+    /// Preview the last build of the script with the current content of its site
+    pub site_only: bool,
+    /// Serve the assets of the site from a local server instead of uploading them
+    pub local_assets: bool,
+    /// Always compile, instead of reusing what was compiled from the same sources
+    pub no_cache: bool,
+    /// Let Chrome DevTools attach to the preview session
+    pub inspect: bool,
 }
 
-fn client_request(payload: &RequestPayload, script_id: &str, sites_preview: bool) {
+/// The link to the preview on cloudflareworkers.com, without the live reload of this
+/// session, so that anyone can open it
+pub fn share_url(script_id: &str, browser_url: &str) -> String {
+    format!(
+        "https://cloudflareworkers.com/?hide_editor#{0}:{1}",
+        script_id, browser_url
+    )
+}
+
+fn share(script_id: &str, browser_url: &str, copy: bool) {
+    let url = share_url(script_id, browser_url);
+    StdOut::info(&format!("Share this preview: {}", styles::url(&url)));
+    if copy {
+        match copy_to_clipboard(&url) {
+            Ok(()) => StdOut::info("Copied the link to the clipboard"),
+            Err(e) => StdOut::warn(&e.to_string()),
+        }
+    }
+}
+
+fn client_request(
+    payload: &RequestPayload,
+    script_id: &str,
+    sites_preview: bool,
+    headless: bool,
+    expect: &Expectations,
+) -> Result<()> {
     let client = http::client();
 
-    let method = &payload.method;
     let url = &payload.service_url;
-    let body = &payload.body;
-
     let cookie = payload.cookie(script_id);
 
-    let worker_res = match method {
-        HttpMethod::Get => get(url, &cookie, &client).unwrap(),
-        HttpMethod::Post => post(url, &cookie, body, &client).unwrap(),
+    let mut request = match payload.method {
+        HttpMethod::Get => client.get(url),
+        HttpMethod::Post => {
+            let msg = format!("POST {}", url);
+            StdOut::preview(&msg);
+            client.post(url)
+        }
     };
+    request = request.header("Cookie", cookie);
+    for (name, value) in &payload.headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    if let (HttpMethod::Post, Some(body)) = (&payload.method, &payload.body) {
+        request = request.body(body.to_string());
+    }
+
+    let worker_res = request.send_logged()?;
+    let version = worker_res.version();
+    let status = worker_res.status();
+    let headers = worker_res.headers().clone();
+    let body = worker_res.text()?;
 
-    let msg = if sites_preview {
-        "Your Worker is a Workers Site, please preview it in browser window.".to_string()
+    if headless {
+        // print the raw response so that it can be inspected by scripts and CI jobs
+        print_response(version, status, &headers, &body);
     } else {
-        format!("Your Worker responded with: {}", worker_res)
-    };
-    StdOut::preview(&msg);
-}
+        let msg = if sites_preview {
+            "Your Worker is a Workers Site, please preview it in browser window.".to_string()
+        } else {
+            format!("Your Worker responded with: {}", body)
+        };
+        StdOut::preview(&msg);
+    }
 
-fn get(url: &str, cookie: &str, client: &reqwest::blocking::Client) -> Result<String> {
-    let res = client.get(url).header("Cookie", cookie).send();
-    Ok(res?.text()?)
+    if !expect.is_empty() {
+        let failures = expect.failures(status, &headers, &body);
+        if !failures.is_empty() {
+            anyhow::bail!(
+                "The response did not match expectations:\n- {}",
+                failures.join("\n- ")
+            )
+        }
+        // on stderr, so the printed response stays what scripts read from stdout
+        StdErr::success("The response matched all expectations");
+    }
+    Ok(())
 }
 
-fn post(
-    url: &str,
-    cookie: &str,
-    body: &Option<String>,
-    client: &reqwest::blocking::Client,
-) -> Result<String> {
-    let res = match body {
-        Some(s) => client
-            .post(url)
-            .header("Cookie", cookie)
-            .body(s.to_string())
-            .send(),
-        None => client.post(url).header("Cookie", cookie).send(),
-    };
-    let msg = format!("POST {}", url);
-    StdOut::preview(&msg);
-    Ok(res?.text()?)
+fn print_response(version: Version, status: StatusCode, headers: &HeaderMap, body: &str) {
+    println!("{:?} {}", version, status);
+    for (name, value) in headers {
+        println!(
+            "{}: {}",
+            name,
+            value.to_str().unwrap_or("<non-ascii value>")
+        );
+    }
+    println!();
+    println!("{}", body);
 }
 
 fn watch_for_changes(
@@ -165,17 +305,39 @@ fn watch_for_changes(
     user: Option<&GlobalUser>,
     broadcaster: Sender,
     verbose: bool,
-    headless: bool,
     request_payload: RequestPayload,
+    options: &PreviewOpt,
 ) -> Result<()> {
     let sites_preview: bool = target.site.is_some();
+    let headless = options.headless;
+    let deadline = options.expires.map(|expires| Instant::now() + expires);
+    if let Some(expires) = options.expires {
+        StdOut::info(&format!(
+            "The preview session expires in {} minute(s)",
+            expires.as_secs() / 60
+        ));
+    }
 
     let (tx, rx) = channel();
     watch_and_build(&target, Some(tx), None)?;
 
-    while rx.recv().is_ok() {
+    loop {
+        let changed = match deadline {
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(RecvTimeoutError::from),
+        };
+        match changed {
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => {
+                StdOut::info("The preview session expired");
+                break;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
         if let Ok(new_id) = upload(&mut target, user, sites_preview, verbose) {
             let script_id = new_id.to_string();
+            share(&script_id, &request_payload.browser_url, false);
 
             let msg = FiddleMessage {
                 session_id: request_payload.session.clone(),
@@ -193,7 +355,15 @@ fn watch_for_changes(
                 }
             }
 
-            client_request(&request_payload, &script_id, sites_preview);
+            if let Err(e) = client_request(
+                &request_payload,
+                &script_id,
+                sites_preview,
+                headless,
+                &options.expect,
+            ) {
+                StdOut::user_error(&format!("Request to preview failed: {}", e));
+            }
         }
     }
 
@@ -11,10 +11,16 @@ pub struct RequestPayload {
     pub browser_url: String,
     pub service_url: String,
     pub body: Option<String>,
+    pub headers: Vec<(String, String)>,
 }
 
 impl RequestPayload {
-    pub fn create(method: HttpMethod, url: Url, body: Option<String>) -> RequestPayload {
+    pub fn create(
+        method: HttpMethod,
+        url: Url,
+        body: Option<String>,
+        headers: Vec<(String, String)>,
+    ) -> RequestPayload {
         let session = Uuid::new_v4().to_simple().to_string();
 
         let https = if url.scheme() == "https" { 1 } else { 0 };
@@ -42,13 +48,16 @@ impl RequestPayload {
             browser_url,
             service_url,
             body,
+            headers,
         }
     }
 
     pub fn cookie(&self, script_id: &str) -> String {
-        format!(
-            "__ew_fiddle_preview={}{}{}{}",
-            script_id, self.session, self.https, self.domain
-        )
+        format!("__ew_fiddle_preview={}", self.preview_id(script_id))
+    }
+
+    /// What the preview service knows this session of the script as
+    pub fn preview_id(&self, script_id: &str) -> String {
+        format!("{}{}{}{}", script_id, self.session, self.https, self.domain)
     }
 }
@@ -2,9 +2,11 @@ use std::path::Path;
 
 use anyhow::Result;
 use reqwest::blocking::Client;
+use reqwest::Method;
 use serde::Deserialize;
 
-use crate::http;
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
 use crate::kv::bulk;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
@@ -102,6 +104,23 @@ pub fn upload(
     Ok(preview.id)
 }
 
+/// Like `upload`, but with an empty namespace bound for the site, for when its assets are
+/// served locally instead of by the worker
+pub fn upload_without_assets(target: &mut Target, user: &GlobalUser) -> Result<String> {
+    let missing_fields = validate(target);
+    if !missing_fields.is_empty() {
+        anyhow::bail!(
+            "Your configuration file is missing the following fields: {:?}",
+            missing_fields
+        )
+    }
+
+    add_namespace(user, target, true)?;
+    let client = http::legacy_auth_client(user);
+    let preview = authenticated_upload(&client, target, Some(AssetManifest::new()))?;
+    Ok(preview.id)
+}
+
 fn validate(target: &Target) -> Vec<&str> {
     let mut missing_fields = Vec::new();
 
@@ -130,24 +149,25 @@ fn authenticated_upload(
     target: &Target,
     asset_manifest: Option<AssetManifest>,
 ) -> Result<Preview> {
-    let create_address = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/preview",
+    let create_address = http::api_url(&format!(
+        "accounts/{}/workers/scripts/{}/preview",
         target.account_id.load()?,
         target.name
-    );
+    ))?;
     log::info!("address: {}", create_address);
 
     let script_upload_form = upload::form::build(target, asset_manifest, None)?;
 
     let res = client
         .post(&create_address)
+        .timeout(http::timeouts().upload())
         .multipart(script_upload_form)
-        .send()?;
+        .send_logged_as(Method::POST)?;
 
     let status = res.status();
     let text = res.text()?;
     if !status.is_success() {
-        anyhow::bail!(crate::format_api_errors(text))
+        anyhow::bail!(WranglerError::from_api_response(status, text))
     }
 
     log::info!("Response from preview: {:#?}", text);
@@ -184,13 +204,14 @@ fn unauthenticated_upload(target: &Target) -> Result<Preview> {
     let client = http::client();
     let res = client
         .post(create_address)
+        .timeout(http::timeouts().upload())
         .multipart(script_upload_form)
-        .send()?;
+        .send_logged_as(Method::POST)?;
 
     let status = res.status();
     let text = res.text()?;
     if !status.is_success() {
-        anyhow::bail!(crate::format_api_errors(text))
+        anyhow::bail!(WranglerError::from_api_response(status, text))
     }
 
     log::info!("Response from preview: {:#?}", text);
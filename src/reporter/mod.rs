@@ -236,7 +236,10 @@ fn load_project_info() -> ProjectInfo {
         }
 
         if let Some(routes) = manifest.routes {
-            project_info.base.insert("routes".into(), routes.join(","));
+            let patterns: Vec<&str> = routes.iter().map(|route| route.pattern()).collect();
+            project_info
+                .base
+                .insert("routes".into(), patterns.join(","));
         }
 
         if let Some(route) = manifest.route {
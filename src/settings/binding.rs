@@ -1,9 +1,19 @@
 use serde::Serialize;
 
+use crate::settings::toml::CustomBinding;
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum Binding {
+    Typed(TypedBinding),
+    /// Written out as it is configured, with the `type` the configuration file gives it
+    Custom(CustomBinding),
+}
+
 #[derive(Serialize, Debug)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
-pub enum Binding {
+pub enum TypedBinding {
     WasmModule {
         name: String,
         part: String,
@@ -26,15 +36,19 @@ pub enum Binding {
         name: String,
         text: String,
     },
+    SecretText {
+        name: String,
+        text: String,
+    },
 }
 
 impl Binding {
     pub fn new_wasm_module(name: String, part: String) -> Binding {
-        Binding::WasmModule { name, part }
+        Binding::Typed(TypedBinding::WasmModule { name, part })
     }
 
     pub fn new_kv_namespace(name: String, namespace_id: String) -> Binding {
-        Binding::KvNamespace { name, namespace_id }
+        Binding::Typed(TypedBinding::KvNamespace { name, namespace_id })
     }
 
     pub fn new_durable_object_namespace(
@@ -42,18 +56,26 @@ impl Binding {
         class_name: String,
         script_name: Option<String>,
     ) -> Binding {
-        Binding::DurableObjectsClass {
+        Binding::Typed(TypedBinding::DurableObjectsClass {
             name,
             class_name,
             script_name,
-        }
+        })
     }
 
     pub fn new_text_blob(name: String, part: String) -> Binding {
-        Binding::TextBlob { name, part }
+        Binding::Typed(TypedBinding::TextBlob { name, part })
     }
 
     pub fn new_plain_text(name: String, text: String) -> Binding {
-        Binding::PlainText { name, text }
+        Binding::Typed(TypedBinding::PlainText { name, text })
+    }
+
+    pub fn new_secret_text(name: String, text: String) -> Binding {
+        Binding::Typed(TypedBinding::SecretText { name, text })
+    }
+
+    pub fn new_custom(binding: CustomBinding) -> Binding {
+        Binding::Custom(binding)
     }
 }
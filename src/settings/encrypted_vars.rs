@@ -0,0 +1,156 @@
+//! Values of `[vars]` encrypted with `wrangler vars encrypt`, so that sensitive values can
+//! be kept in the configuration file with the rest of the settings. They're decrypted when
+//! the worker is uploaded, and bound as secrets rather than as plain text.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use super::get_wrangler_home_dir;
+
+/// Set to the base64 encoded key to encrypt and decrypt vars with, like on CI, instead of
+/// the key kept in the wrangler home directory
+pub const KEY_VAR: &str = "WRANGLER_VARS_KEY";
+
+// Marks a value as encrypted, with the version of the format after it
+const PREFIX: &str = "encrypted:v1:";
+const KEY_LEN: usize = 32;
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+/// Encrypts `value` for the var `name`, creating the key if there isn't one yet
+pub fn encrypt(name: &str, value: &str) -> Result<String> {
+    seal(&load_key(true)?, name, value)
+}
+
+/// The value of the var `name`, decrypted if it was encrypted
+pub fn decrypt(name: &str, value: &str) -> Result<String> {
+    if !is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    open(&load_key(false)?, name, value)
+}
+
+fn key_path() -> PathBuf {
+    get_wrangler_home_dir().join("config").join("vars.key")
+}
+
+fn load_key(create: bool) -> Result<Vec<u8>> {
+    if let Ok(key) = env::var(KEY_VAR) {
+        return decode_key(&key).map_err(|e| anyhow!("${} is not a valid key: {}", KEY_VAR, e));
+    }
+    let path = key_path();
+    match fs::read_to_string(&path) {
+        Ok(key) => {
+            decode_key(&key).map_err(|e| anyhow!("{} is not a valid key: {}", path.display(), e))
+        }
+        Err(_) if create => {
+            let mut key = vec![0; KEY_LEN];
+            SystemRandom::new()
+                .fill(&mut key)
+                .map_err(|_| anyhow!("Could not generate a key to encrypt vars with"))?;
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, base64::encode(&key))?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+            }
+            log::info!("Created the key to encrypt vars with at {}", path.display());
+            Ok(key)
+        }
+        Err(_) => anyhow::bail!(
+            "There is no key to decrypt vars with. Set ${} to the key they were encrypted with, which is in {} where they were",
+            KEY_VAR,
+            path.display()
+        ),
+    }
+}
+
+fn decode_key(encoded: &str) -> Result<Vec<u8>> {
+    let key = base64::decode(encoded.trim())?;
+    if key.len() != KEY_LEN {
+        anyhow::bail!("expected {} bytes, base64 encoded", KEY_LEN)
+    }
+    Ok(key)
+}
+
+fn cipher(key: &[u8]) -> Result<LessSafeKey> {
+    let key = UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow!("Invalid key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+// The name of the var is authenticated with the value, so a value can't be moved to
+// another var
+fn seal(key: &[u8], name: &str, value: &str) -> Result<String> {
+    let mut nonce = [0; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("Could not generate a nonce"))?;
+    let mut sealed = value.as_bytes().to_vec();
+    cipher(key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(name.as_bytes()),
+            &mut sealed,
+        )
+        .map_err(|_| anyhow!("Could not encrypt {}", name))?;
+
+    let mut encoded = nonce.to_vec();
+    encoded.extend(sealed);
+    Ok(format!("{}{}", PREFIX, base64::encode(encoded)))
+}
+
+fn open(key: &[u8], name: &str, value: &str) -> Result<String> {
+    // the nonce, followed by the sealed value
+    let mut nonce = base64::decode(&value[PREFIX.len()..])
+        .map_err(|e| anyhow!("The encrypted value of {} is not valid: {}", name, e))?;
+    if nonce.len() < NONCE_LEN {
+        anyhow::bail!("The encrypted value of {} is too short", name)
+    }
+    let mut sealed = nonce.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&nonce)
+        .map_err(|_| anyhow!("The encrypted value of {} is not valid", name))?;
+    let opened = cipher(key)?
+        .open_in_place(nonce, Aad::from(name.as_bytes()), &mut sealed)
+        .map_err(|_| {
+            anyhow!(
+                "Could not decrypt {}, it was encrypted with another key or for another var",
+                name
+            )
+        })?;
+    Ok(String::from_utf8(opened.to_vec())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_decrypts_what_it_encrypts() -> Result<()> {
+        let key = [7; KEY_LEN];
+        let sealed = seal(&key, "API_TOKEN", "hunter2")?;
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("hunter2"));
+        assert_eq!(open(&key, "API_TOKEN", &sealed)?, "hunter2");
+        // a fresh nonce every time
+        assert_ne!(seal(&key, "API_TOKEN", "hunter2")?, sealed);
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuses_other_keys_and_vars() -> Result<()> {
+        let sealed = seal(&[7; KEY_LEN], "API_TOKEN", "hunter2")?;
+        assert!(open(&[8; KEY_LEN], "API_TOKEN", &sealed).is_err());
+        assert!(open(&[7; KEY_LEN], "OTHER_TOKEN", &sealed).is_err());
+        Ok(())
+    }
+}
@@ -1,7 +1,15 @@
 use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
 
+use anyhow::Result;
+
+use crate::http::Timeouts;
+
 pub const DEFAULT_CONFIG_FILE_NAME: &str = "default.toml";
+// The keys of the global configuration file that aren't credentials, which are kept when the
+// credentials are written or removed
+const SETTINGS_KEYS: [&str; 4] = ["proxy", "update_check", "http", "profiles"];
 
 pub fn get_wrangler_home_dir() -> PathBuf {
     if let Ok(value) = env::var("WRANGLER_HOME") {
@@ -21,3 +29,60 @@ pub fn get_global_config_path() -> PathBuf {
     log::info!("Using global config file: {}", global_config_file.display());
     global_config_file
 }
+
+/// The `proxy` of the global configuration file, if it has one
+pub fn get_proxy() -> Option<String> {
+    read_proxy(&get_global_config_path())
+}
+
+/// The `proxy` of the configuration file at `config_path`, if it has one
+pub fn read_proxy(config_path: &Path) -> Option<String> {
+    read_config(config_path)?
+        .get("proxy")
+        .and_then(toml::Value::as_str)
+        .filter(|proxy| !proxy.is_empty())
+        .map(str::to_string)
+}
+
+/// Whether the global configuration file allows checking crates.io for newer versions of
+/// wrangler, which it does unless it has `update_check = false`
+pub fn update_check_enabled() -> bool {
+    read_update_check(&get_global_config_path())
+}
+
+pub fn read_update_check(config_path: &Path) -> bool {
+    read_config(config_path)
+        .and_then(|config| config.get("update_check").and_then(toml::Value::as_bool))
+        .unwrap_or(true)
+}
+
+/// The `[http]` table of the global configuration file, or the default timeouts
+pub fn get_timeouts() -> Result<Timeouts> {
+    read_timeouts(&get_global_config_path())
+}
+
+pub fn read_timeouts(config_path: &Path) -> Result<Timeouts> {
+    match read_config(config_path).and_then(|config| config.get("http").cloned()) {
+        Some(http) => http.try_into().map_err(|e| {
+            anyhow::anyhow!("Invalid [http] table in {}: {}", config_path.display(), e)
+        }),
+        None => Ok(Timeouts::default()),
+    }
+}
+
+/// The settings of the configuration file at `config_path`, the keys that aren't credentials
+pub fn read_settings(config_path: &Path) -> toml::value::Table {
+    let config = match read_config(config_path) {
+        Some(toml::Value::Table(config)) => config,
+        _ => return toml::value::Table::new(),
+    };
+    config
+        .into_iter()
+        .filter(|(key, _)| SETTINGS_KEYS.contains(&key.as_str()))
+        .collect()
+}
+
+pub(super) fn read_config(config_path: &Path) -> Option<toml::Value> {
+    let config = fs::read_to_string(config_path).ok()?;
+    toml::from_str(&config).ok()
+}
@@ -0,0 +1,168 @@
+use std::env;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+
+// Where the credentials are kept in the keychain
+const SERVICE: &str = "wrangler";
+const ACCOUNT: &str = "credentials";
+
+/// Set to keep credentials in the plaintext configuration file, even where there is a keychain
+pub const NO_KEYCHAIN_VAR: &str = "WRANGLER_NO_KEYCHAIN";
+
+/// The platform keychain wrangler keeps credentials in, through the command line tools of
+/// macOS (`security`) and of the Secret Service on Linux (`secret-tool`). There is no
+/// support for the Windows Credential Manager.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Keychain {
+    MacOs,
+    SecretService,
+}
+
+static DETECTED: OnceCell<Result<Keychain, Unavailable>> = OnceCell::new();
+
+/// Why credentials can't be kept in a keychain here
+#[derive(Clone, Debug, PartialEq)]
+pub enum Unavailable {
+    /// `NO_KEYCHAIN_VAR` is set
+    OptedOut,
+    /// The platform has no keychain wrangler supports, or its tool isn't installed
+    Unsupported(String),
+}
+
+impl Keychain {
+    /// The keychain of this platform, looked for once per process
+    fn detect() -> Result<Self, Unavailable> {
+        DETECTED.get_or_init(Self::find).clone()
+    }
+
+    fn find() -> Result<Self, Unavailable> {
+        if env::var_os(NO_KEYCHAIN_VAR).is_some() {
+            return Err(Unavailable::OptedOut);
+        }
+        let (keychain, tool) = if cfg!(target_os = "macos") {
+            (Keychain::MacOs, "security")
+        } else if cfg!(target_os = "linux") {
+            (Keychain::SecretService, "secret-tool")
+        } else {
+            return Err(Unavailable::Unsupported(format!(
+                "wrangler can't keep credentials in the keychain of {}",
+                env::consts::OS
+            )));
+        };
+        // secret-tool exits with an error without a subcommand, but only runs if it exists
+        let installed = Command::new(tool)
+            .arg("--help")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok();
+        if installed {
+            Ok(keychain)
+        } else {
+            Err(Unavailable::Unsupported(format!(
+                "`{}` isn't installed, which wrangler keeps credentials in the keychain with",
+                tool
+            )))
+        }
+    }
+}
+
+/// Whether credentials can be kept in a keychain here, and why not
+pub fn availability() -> Result<(), Unavailable> {
+    Keychain::detect().map(|_| ())
+}
+
+fn detect_or_fail() -> Result<Keychain> {
+    match Keychain::detect() {
+        Ok(keychain) => Ok(keychain),
+        Err(Unavailable::OptedOut) => anyhow::bail!(
+            "{} is set, so credentials aren't kept in the keychain",
+            NO_KEYCHAIN_VAR
+        ),
+        Err(Unavailable::Unsupported(reason)) => anyhow::bail!("{}", reason),
+    }
+}
+
+/// Keeps `secret` in the keychain, replacing what was there
+pub fn store(secret: &str) -> Result<()> {
+    // base64, so the secret never needs quoting; it's passed on stdin so it never shows up
+    // in the arguments of a process
+    let encoded = base64::encode(secret);
+    let output = match detect_or_fail()? {
+        Keychain::MacOs => run(
+            Command::new("security").arg("-i"),
+            &format!(
+                "add-generic-password -U -s {} -a {} -w {}\n",
+                SERVICE, ACCOUNT, encoded
+            ),
+        )?,
+        Keychain::SecretService => run(
+            Command::new("secret-tool").args(&[
+                "store",
+                "--label=wrangler credentials",
+                "service",
+                SERVICE,
+                "account",
+                ACCOUNT,
+            ]),
+            &encoded,
+        )?,
+    };
+    if !output.status.success() {
+        anyhow::bail!(
+            "Could not store credentials in the keychain: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+    Ok(())
+}
+
+/// The secret in the keychain, if there is one. Fails when there is no keychain to look in.
+pub fn load() -> Result<Option<String>> {
+    let output = match detect_or_fail()? {
+        Keychain::MacOs => Command::new("security")
+            .args(&["find-generic-password", "-s", SERVICE, "-a", ACCOUNT, "-w"])
+            .output()?,
+        Keychain::SecretService => Command::new("secret-tool")
+            .args(&["lookup", "service", SERVICE, "account", ACCOUNT])
+            .output()?,
+    };
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let encoded = String::from_utf8_lossy(&output.stdout);
+    let secret = base64::decode(encoded.trim())
+        .map_err(|e| anyhow::anyhow!("The credentials in the keychain are corrupted: {}", e))?;
+    Ok(Some(String::from_utf8(secret)?))
+}
+
+/// Removes the secret from the keychain, if there is one
+pub fn delete() -> Result<()> {
+    let command = match Keychain::detect() {
+        Ok(Keychain::MacOs) => Command::new("security")
+            .args(&["delete-generic-password", "-s", SERVICE, "-a", ACCOUNT])
+            .output(),
+        Ok(Keychain::SecretService) => Command::new("secret-tool")
+            .args(&["clear", "service", SERVICE, "account", ACCOUNT])
+            .output(),
+        Err(_) => return Ok(()),
+    };
+    // both fail when there is nothing to delete, which is fine
+    command?;
+    Ok(())
+}
+
+fn run(command: &mut Command, input: &str) -> Result<Output> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    Ok(child.wait_with_output()?)
+}
@@ -1,8 +1,14 @@
 pub mod binding;
+pub mod encrypted_vars;
 mod environment;
 mod global_config;
 pub mod global_user;
+pub mod keychain;
 pub mod toml;
 
 pub use environment::{Environment, QueryEnvironment};
-pub use global_config::{get_global_config_path, get_wrangler_home_dir, DEFAULT_CONFIG_FILE_NAME};
+pub use global_config::{
+    get_global_config_path, get_proxy, get_timeouts, get_wrangler_home_dir, read_proxy,
+    read_settings, read_timeouts, read_update_check, update_check_enabled,
+    DEFAULT_CONFIG_FILE_NAME,
+};
@@ -1,11 +1,12 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use crate::upload::form::ModuleType;
+use crate::upload::Package;
 
 const WATCH_DIR: &str = "src";
 const UPLOAD_DIR: &str = "dist";
@@ -26,7 +27,10 @@ pub struct Builder {
 #[serde(deny_unknown_fields)]
 pub enum UploadFormat {
     #[serde(rename = "service-worker")]
-    ServiceWorker {},
+    ServiceWorker {
+        /// The script produced by the build command, instead of the `main` of package.json
+        main: Option<PathBuf>,
+    },
     #[serde(rename = "modules")]
     Modules {
         main: String, // String since this is a module name, not a path.
@@ -36,6 +40,16 @@ pub enum UploadFormat {
     },
 }
 
+/// The top level `format` of a project: whether its script is uploaded as a service worker
+/// or as ES modules, for projects that don't say so in a `[build]` table
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ScriptFormat {
+    #[serde(rename = "service-worker")]
+    ServiceWorker,
+    #[serde(rename = "modules")]
+    Modules,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct ModuleRule {
@@ -46,6 +60,16 @@ pub struct ModuleRule {
     pub fallthrough: bool,
 }
 
+impl ModuleRule {
+    fn new(glob: &str, module_type: ModuleType) -> Self {
+        ModuleRule {
+            globs: vec![glob.to_string()],
+            module_type,
+            fallthrough: false,
+        }
+    }
+}
+
 fn project_root() -> PathBuf {
     env::current_dir().unwrap()
 }
@@ -59,6 +83,54 @@ fn upload_dir() -> PathBuf {
 }
 
 impl Builder {
+    /// The build of a `type = "typescript"` project without a `[build]` table: `src/` is
+    /// compiled into `dist/`, which is uploaded as modules starting at `index.js`
+    pub(crate) fn typescript() -> Self {
+        Builder {
+            command: None,
+            cwd: project_root(),
+            watch_dir: watch_dir(),
+            upload: UploadFormat::Modules {
+                main: "index.js".to_string(),
+                dir: upload_dir(),
+                rules: None,
+            },
+        }
+    }
+
+    /// The build of a `format = "modules"` project without a `[build]` table: the `module` of
+    /// its package.json is uploaded as the main module, along with the modules next to it,
+    /// and `.wasm`, `.txt` and `.bin` files can be imported as well
+    pub(crate) fn modules(package_dir: &Path) -> Result<Self> {
+        let package = Package::new(package_dir)?;
+        let module = package_dir.join(package.module(package_dir)?);
+        let (dir, main) = match (module.parent(), module.file_name()) {
+            (Some(dir), Some(main)) => (dir.to_path_buf(), main.to_string_lossy().to_string()),
+            _ => anyhow::bail!("{} is not a module", module.display()),
+        };
+        if dir.canonicalize()? == project_root().canonicalize()? {
+            anyhow::bail!(
+                "format = \"modules\" uploads every module in the directory of {}, move it out of the project root, e.g. into src/",
+                main
+            )
+        }
+
+        Ok(Builder {
+            command: None,
+            cwd: project_root(),
+            watch_dir: dir.clone(),
+            upload: UploadFormat::Modules {
+                main,
+                dir,
+                rules: Some(vec![
+                    ModuleRule::new("**/*.wasm", ModuleType::CompiledWasm),
+                    ModuleRule::new("**/*.txt", ModuleType::Text),
+                    ModuleRule::new("**/*.bin", ModuleType::Data),
+                ]),
+            },
+        })
+    }
+
     pub fn verify_watch_dir(&self) -> Result<()> {
         let watch_canonical = match self.watch_dir.canonicalize() {
             Ok(path) => path,
@@ -88,7 +160,16 @@ impl Builder {
     pub fn verify_upload_dir(&self) -> Result<()> {
         let dir = match &self.upload {
             UploadFormat::Modules { dir, .. } => dir,
-            UploadFormat::ServiceWorker {} => return Ok(()),
+            UploadFormat::ServiceWorker { main: Some(main) } => {
+                if !main.is_file() {
+                    anyhow::bail!(
+                        "Your provided main {} does not exist. Make sure your build command writes it.",
+                        main.display()
+                    );
+                }
+                return Ok(());
+            }
+            UploadFormat::ServiceWorker { main: None } => return Ok(()),
         };
 
         let upload_canonical = match dir.canonicalize() {
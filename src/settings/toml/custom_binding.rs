@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+use crate::settings::binding::Binding;
+
+/// A `[[bindings]]` entry, for kinds of binding wrangler has no configuration of its own
+/// for yet. Everything besides `type` and `name` is passed on to the API as it is written.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct CustomBinding {
+    #[serde(rename = "type")]
+    pub binding_type: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl CustomBinding {
+    pub fn binding(&self) -> Binding {
+        Binding::new_custom(self.clone())
+    }
+}
@@ -0,0 +1,264 @@
+/// The zone of each route, that is the most specific zone its hostname is in, and the
+/// routes that aren't in any of `zones`
+fn zones_of<'a, 'r>(routes: &[&'r str], zones: &'a [Zone]) -> (Vec<&'a Zone>, Vec<&'r str>) {
+    let mut matched = Vec::new();
+    let mut unmatched = Vec::new();
+    for route in routes {
+        let zone = zones
+            .iter()
+            .filter(|zone| route::is_in_zone(route, &zone.name))
+            .max_by_key(|zone| zone.name.len());
+        match zone {
+            Some(zone) => matched.push(zone),
+            None => unmatched.push(*route),
+        }
+    }
+    matched.sort_by(|a, b| a.id.cmp(&b.id));
+    (matched, unmatched)
+}
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+
+use super::manifest::{confirm_write, LazyAccountId};
+use super::route;
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdOut};
+
+// The configuration file the manifest was read from, which chosen IDs are written back to
+static CONFIG_PATH: OnceCell<PathBuf> = OnceCell::new();
+
+const ZONES_PER_PAGE: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Zone {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct ZonesResponse {
+    result: Vec<Zone>,
+    result_info: ResultInfo,
+}
+
+#[derive(Deserialize)]
+struct ResultInfo {
+    total_pages: usize,
+}
+
+pub(super) fn set_config_path(config_path: PathBuf) {
+    let _ = CONFIG_PATH.set(config_path);
+}
+
+/// The configuration file the manifest was read from, once it was read
+pub(crate) fn config_path() -> Option<&'static Path> {
+    CONFIG_PATH.get().map(PathBuf::as_path)
+}
+
+/// Finds the zone `routes` belong to among the zones the credentials can access, asking
+/// which one to use when the routes don't tell. The zone is offered to be written to the
+/// table of `environment`, whose routes these are.
+#[cfg_attr(test, allow(unreachable_code, unused_variables))]
+pub(super) fn zone_id(
+    routes: &[&str],
+    account_id: &LazyAccountId,
+    environment: Option<&str>,
+) -> Result<String> {
+    #[cfg(test)]
+    // there are no credentials to list zones with
+    anyhow::bail!("Routes specified with no zone, specify `zone_id` in your wrangler.toml");
+
+    let user = GlobalUser::new()?;
+    let zones = fetch_zones(&user, account_id.maybe_load().as_deref())?;
+    let (mut matched, unmatched) = zones_of(routes, &zones);
+    matched.dedup_by(|a, b| a.id == b.id);
+    // a worker is published to a single zone, so the other routes would fail to be added
+    if !matched.is_empty() && !unmatched.is_empty() {
+        anyhow::bail!(
+            "These routes aren't in any of your zones: {}. Fix them, or specify `zone_id` in your wrangler.toml",
+            unmatched.join(", ")
+        )
+    }
+
+    let zone_id = match matched.as_slice() {
+        [zone] => {
+            StdOut::info(&format!(
+                "Publishing the routes to {} ({}), the zone they belong to",
+                zone.name, zone.id
+            ));
+            zone.id.clone()
+        }
+        [] if interactive::is_interactive() && !zones.is_empty() => {
+            StdOut::warn("None of your zones match the routes in your configuration file.");
+            let options = zones
+                .iter()
+                .map(|zone| (zone.id.as_str(), zone.name.as_str()))
+                .collect::<Vec<_>>();
+            choose("zone", &options)?
+        }
+        [] => anyhow::bail!(
+            "Routes specified with no zone, and none of your zones match them. Specify `zone_id` in your wrangler.toml"
+        ),
+        _multiple => anyhow::bail!(
+            "Routes specified with no zone belong to more than one of your zones, but a worker can only be published to one. Specify `zone_id` in your wrangler.toml"
+        ),
+    };
+    write_back("zone_id", &zone_id, environment);
+    Ok(zone_id)
+}
+
+/// Asks which of `options`, pairs of an ID and a name, to use
+pub(super) fn choose(kind: &str, options: &[(&str, &str)]) -> Result<String> {
+    StdOut::info(&format!("Which {} do you want to use?", kind));
+    for (i, (id, name)) in options.iter().enumerate() {
+        println!("  {}) {} ({})", i + 1, name, id);
+    }
+    loop {
+        let input = interactive::get_user_input(
+            &format!("Enter 1-{}:", options.len()),
+            &format!("set `{}_id` in the configuration file", kind),
+        )?;
+        match input.trim().parse::<usize>() {
+            Ok(n) if n >= 1 && n <= options.len() => return Ok(options[n - 1].0.to_string()),
+            _ => StdOut::user_error(&format!("{} isn't one of the options", input)),
+        }
+    }
+}
+
+/// Offers to set `key` in the table of `environment`, or at the top of the configuration file
+/// without one, so the next command doesn't have to look it up again
+pub(super) fn write_back(key: &str, id: &str, environment: Option<&str>) {
+    let config_path = match CONFIG_PATH.get() {
+        Some(config_path) if interactive::is_interactive() => config_path,
+        _ => return,
+    };
+    let written = fs::read_to_string(config_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| {
+            let toml = set_id(&contents, key, id, environment)?;
+            confirm_write(config_path, &toml)?;
+            fs::write(config_path, toml)?;
+            Ok(())
+        });
+    if let Err(e) = written {
+        log::info!("Didn't write {} to {}: {}", key, config_path.display(), e);
+    }
+}
+
+// Sets `key`, keeping the formatting and comments of the rest of the file
+fn set_id(contents: &str, key: &str, id: &str, environment: Option<&str>) -> Result<String> {
+    let mut doc = contents
+        .parse::<toml_edit::Document>()
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    // the top level of a workspace is shared by all of its workers
+    anyhow::ensure!(
+        doc["workers"].is_none(),
+        "the workers of a workspace have their own settings"
+    );
+    let section = match environment {
+        Some(env) if doc["env"][env].is_none() => {
+            anyhow::bail!("there is no environment {} in the configuration file", env)
+        }
+        Some(env) => &mut doc["env"][env],
+        None => &mut doc.root,
+    };
+    match section.as_table_mut() {
+        Some(table) => *table.entry(key) = toml_edit::value(id),
+        None => anyhow::bail!("expected a table to set {} in", key),
+    }
+    Ok(doc.to_string_in_original_order())
+}
+
+/// The zones the credentials of `user` can access, only those of `account_id` if given
+pub(crate) fn fetch_zones(user: &GlobalUser, account_id: Option<&str>) -> Result<Vec<Zone>> {
+    let client = http::legacy_auth_client(user);
+    let mut zones = Vec::new();
+    let mut page = 1;
+    loop {
+        let mut addr = http::api_url(&format!("zones?per_page={}&page={}", ZONES_PER_PAGE, page))?;
+        if let Some(account_id) = account_id {
+            addr.push_str(&format!("&account.id={}", account_id));
+        }
+        let res = client.get(&addr).send_logged()?;
+        let status = res.status();
+        if !status.is_success() {
+            anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+        }
+        let res: ZonesResponse = res.json()?;
+        zones.extend(res.result);
+        if page >= res.result_info.total_pages {
+            return Ok(zones);
+        }
+        page += 1;
+    }
+}
+
+/// The zone of each route, that is the most specific zone its hostname is in. Routes that
+/// aren't in any of `zones` have none.
+fn zones_of<'a>(routes: &[&str], zones: &'a [Zone]) -> Vec<&'a Zone> {
+    let mut matched = routes
+        .iter()
+        .filter_map(|route| {
+            zones
+                .iter()
+                .filter(|zone| route::is_in_zone(route, &zone.name))
+                .max_by_key(|zone| zone.name.len())
+        })
+        .collect::<Vec<_>>();
+    matched.sort_by(|a, b| a.id.cmp(&b.id));
+    matched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone(id: &str, name: &str) -> Zone {
+        Zone {
+            id: id.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn it_matches_routes_to_the_most_specific_zone() {
+        let zones = vec![
+            zone("1", "example.com"),
+            zone("2", "api.example.com"),
+            zone("3", "example.org"),
+        ];
+        let (matched, _) = zones_of(&["api.example.com/*", "v2.api.example.com/*"], &zones);
+        assert!(matched.iter().all(|zone| zone.id == "2"));
+
+        let (matched, unmatched) = zones_of(&["www.example.com/*", "notexample.com/*"], &zones);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].id, "1");
+        assert_eq!(unmatched, vec!["notexample.com/*"]);
+    }
+
+    #[test]
+    fn it_writes_ids_to_the_table_of_the_environment() {
+        let contents = "name = \"worker\"\n\n[env.staging]\nroute = \"staging.example.com/*\"\n";
+
+        let written = set_id(contents, "zone_id", "abc", Some("staging")).unwrap();
+        let doc = written.parse::<toml_edit::Document>().unwrap();
+        assert_eq!(doc["env"]["staging"]["zone_id"].as_str(), Some("abc"));
+        assert!(doc["zone_id"].is_none());
+
+        let written = set_id(contents, "account_id", "123", None).unwrap();
+        let doc = written.parse::<toml_edit::Document>().unwrap();
+        assert_eq!(doc["account_id"].as_str(), Some("123"));
+        assert!(doc["env"]["staging"]["account_id"].is_none());
+
+        assert!(set_id(contents, "zone_id", "abc", Some("production")).is_err());
+        assert!(set_id("[[workers]]\nname = \"api\"\n", "zone_id", "abc", None).is_err());
+    }
+}
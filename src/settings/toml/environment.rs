@@ -5,9 +5,13 @@ use serde::{Deserialize, Serialize};
 use serde_with::rust::string_empty_as_none;
 
 use crate::settings::toml::builder::Builder;
+use crate::settings::toml::custom_binding::CustomBinding;
 use crate::settings::toml::durable_objects::DurableObjects;
+use crate::settings::toml::hooks::Hooks;
 use crate::settings::toml::kv_namespace::ConfigKvNamespace;
-use crate::settings::toml::route::RouteConfig;
+use crate::settings::toml::limits::Limits;
+use crate::settings::toml::protection::Protection;
+use crate::settings::toml::route::{RouteConfig, RouteEntry};
 use crate::settings::toml::site::Site;
 use crate::settings::toml::triggers::Triggers;
 
@@ -16,22 +20,30 @@ pub struct Environment {
     pub name: Option<String>,
     #[serde(default, with = "string_empty_as_none")]
     pub account_id: Option<String>,
+    pub profile: Option<String>,
     pub workers_dev: Option<bool>,
     #[serde(default, with = "string_empty_as_none")]
     pub route: Option<String>,
-    pub routes: Option<Vec<String>>,
+    pub routes: Option<Vec<RouteEntry>>,
     #[serde(default, with = "string_empty_as_none")]
     pub zone_id: Option<String>,
     pub webpack_config: Option<String>,
     pub build: Option<Builder>,
     pub private: Option<bool>,
+    pub limits: Option<Limits>,
+    pub protection: Option<Protection>,
+    pub hooks: Option<Hooks>,
     pub site: Option<Site>,
     #[serde(alias = "kv-namespaces")]
     pub kv_namespaces: Option<Vec<ConfigKvNamespace>>,
     pub vars: Option<HashMap<String, String>>,
+    pub secrets: Option<Vec<String>>,
+    pub tail_consumers: Option<Vec<String>>,
+    pub queue_consumers: Option<Vec<String>>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
     pub triggers: Option<Triggers>,
     pub durable_objects: Option<DurableObjects>,
+    pub bindings: Option<Vec<CustomBinding>>,
 }
 
 impl Environment {
@@ -0,0 +1,134 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use super::interpolate;
+use super::overrides;
+use crate::commands::kv;
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::settings::global_user::GlobalUser;
+
+const KV_SCHEME: &str = "kv://";
+
+/// Where the `extends` key of a configuration file points: a shared base configuration
+/// stored in Workers KV, like `kv://<namespace-id>/wrangler-base.toml`
+#[derive(Debug, PartialEq)]
+pub struct Extends {
+    pub namespace_id: String,
+    pub key: String,
+}
+
+impl Extends {
+    pub fn parse(extends: &str) -> Result<Self> {
+        let parsed = extends
+            .strip_prefix(KV_SCHEME)
+            .and_then(|rest| rest.split_once('/'))
+            .filter(|(namespace_id, key)| !namespace_id.is_empty() && !key.is_empty());
+        match parsed {
+            Some((namespace_id, key)) => Ok(Extends {
+                namespace_id: namespace_id.to_string(),
+                key: key.to_string(),
+            }),
+            None => anyhow::bail!(
+                "extends = \"{}\" should look like \"kv://<namespace-id>/<key>\"",
+                extends
+            ),
+        }
+    }
+
+    // Kept apart per namespace and key, with anything that can't be in a file name replaced
+    fn cache_key(&self) -> String {
+        let key: String = self
+            .key
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("extends_{}_{}", self.namespace_id, key)
+    }
+}
+
+/// The base configuration the configuration file at `config_path` extends, if it has an
+/// `extends` key. It's read from KV of the account of `--account-id`, the file or else
+/// `CF_ACCOUNT_ID`, and cached for a few minutes since the configuration is loaded several
+/// times per command.
+pub(super) fn base_config(config_path: &Path) -> Result<Option<String>> {
+    let local: toml::Value = toml::from_str(&fs::read_to_string(config_path)?)?;
+    let extends = match local.get("extends") {
+        Some(toml::Value::String(extends)) => Extends::parse(extends)?,
+        Some(_) => anyhow::bail!("extends should be a string like \"kv://<namespace-id>/<key>\""),
+        None => return Ok(None),
+    };
+
+    let account_id = match (
+        overrides::get().account_id,
+        local.get("account_id").and_then(toml::Value::as_str),
+    ) {
+        (Some(account_id), _) => account_id,
+        (None, Some(account_id)) => interpolate::expand(account_id),
+        (None, None) => env::var("CF_ACCOUNT_ID").map_err(|_| {
+            anyhow!("Set account_id in your configuration file to read the base configuration it extends")
+        })?,
+    };
+    let user = GlobalUser::new().map_err(|e| {
+        anyhow!(
+            "Reading the base configuration your configuration file extends needs you to be logged in: {}",
+            e
+        )
+    })?;
+
+    log::info!(
+        "Reading the base configuration {} from namespace {}",
+        extends.key,
+        extends.namespace_id
+    );
+    let base = http::cache::cached(&user, &extends.cache_key(), || {
+        fetch(&user, &account_id, &extends)
+    })?;
+    Ok(Some(base))
+}
+
+fn fetch(user: &GlobalUser, account_id: &str, extends: &Extends) -> Result<String> {
+    let url = http::api_url(&format!(
+        "accounts/{}/storage/kv/namespaces/{}/values/{}",
+        account_id,
+        extends.namespace_id,
+        kv::url_encode_key(&extends.key)
+    ))?;
+    let res = http::legacy_auth_client(user).get(&url).send_logged()?;
+    let status = res.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        anyhow::bail!(
+            "There is no base configuration {} in namespace {}",
+            extends.key,
+            extends.namespace_id
+        )
+    }
+    if !status.is_success() {
+        anyhow::bail!(WranglerError::from_api_response(status, res.text()?))
+    }
+    Ok(res.text()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_kv_locations() {
+        let extends =
+            Extends::parse("kv://0f2ac74b498b48028cb68387c421e279/teams/base.toml").unwrap();
+        assert_eq!(extends.namespace_id, "0f2ac74b498b48028cb68387c421e279");
+        assert_eq!(extends.key, "teams/base.toml");
+        assert_eq!(
+            extends.cache_key(),
+            "extends_0f2ac74b498b48028cb68387c421e279_teams_base_toml"
+        );
+
+        assert!(Extends::parse("https://example.com/base.toml").is_err());
+        assert!(Extends::parse("kv://0f2ac74b498b48028cb68387c421e279").is_err());
+        assert!(Extends::parse("kv:///base.toml").is_err());
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// The `[hooks]` table of the top level or of an environment: shell commands run around
+/// `wrangler publish`
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    /// Run before anything is uploaded, a failing command stops the publish
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_publish: Vec<String>,
+    /// Run once the worker is live, a failing command is only warned about
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_publish: Vec<String>,
+}
@@ -0,0 +1,47 @@
+use std::env;
+
+use regex::{Captures, Regex};
+
+fn env_var_reference() -> Regex {
+    Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap()
+}
+
+/// Replaces every `${NAME}` in `value` with the value of the environment variable `NAME`.
+/// References to variables that aren't set are left as they are, so that they can be
+/// reported once we know whether the environment being used needs them.
+pub fn expand(value: &str) -> String {
+    expand_with(value, |name| env::var(name).ok())
+}
+
+fn expand_with(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    env_var_reference()
+        .replace_all(value, |captures: &Captures| {
+            lookup(&captures[1]).unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+/// The names of the variables `value` still refers to after expanding it
+pub fn unresolved(value: &str) -> Vec<String> {
+    env_var_reference()
+        .captures_iter(value)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_expands_the_variables_that_are_set() {
+        let lookup = |name: &str| match name {
+            "ZONE" => Some("example.com".to_string()),
+            _ => None,
+        };
+
+        let expanded = expand_with("${ZONE}/api/* ${MISSING} $ZONE {ZONE}", lookup);
+        assert_eq!(expanded, "example.com/api/* ${MISSING} $ZONE {ZONE}");
+        assert_eq!(unresolved(&expanded), vec!["MISSING"]);
+    }
+}
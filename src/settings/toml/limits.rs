@@ -0,0 +1,29 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+// The most CPU time the runtime lets a request of an unbound worker use
+const MAX_CPU_MS: u32 = 30_000;
+
+/// The `[limits]` table of the top level or of an environment, uploaded with the script so
+/// that publishing doesn't reset limits set on the dashboard
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limits {
+    /// Milliseconds of CPU time a request may use before it's cancelled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_ms: Option<u32>,
+}
+
+impl Limits {
+    pub(crate) fn validate(&self) -> Result<()> {
+        if let Some(cpu_ms) = self.cpu_ms {
+            anyhow::ensure!(
+                (1..=MAX_CPU_MS).contains(&cpu_ms),
+                "limits.cpu_ms must be between 1 and {}, not {}",
+                MAX_CPU_MS,
+                cpu_ms
+            );
+        }
+        Ok(())
+    }
+}
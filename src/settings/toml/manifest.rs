@@ -1,10 +1,11 @@
 use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use config::{Config, File};
+use config::{Config, File, FileFormat};
 
 use anyhow::{anyhow, Result};
 use chrono::Utc;
@@ -12,24 +13,36 @@ use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use serde_with::rust::string_empty_as_none;
 
+use super::discovery;
+use super::extends;
+use super::interpolate;
 use super::migrations::{MigrationConfig, MigrationTag, Migrations};
+use super::overrides::{self, Overrides};
+use super::unknown_keys;
 use super::UsageModel;
 use crate::commands::whoami::fetch_accounts;
 use crate::commands::{validate_worker_name, whoami, DEFAULT_CONFIG_PATH};
 use crate::deploy::{self, DeployTarget, DeploymentSet};
+use crate::error::WranglerError;
 use crate::settings::global_user::GlobalUser;
-use crate::settings::toml::builder::Builder;
+use crate::settings::toml::builder::{Builder, ModuleRule, ScriptFormat, UploadFormat};
+use crate::settings::toml::custom_binding::CustomBinding;
 use crate::settings::toml::dev::Dev;
 use crate::settings::toml::durable_objects::DurableObjects;
 use crate::settings::toml::environment::Environment;
+use crate::settings::toml::hooks::Hooks;
 use crate::settings::toml::kv_namespace::{ConfigKvNamespace, KvNamespace};
-use crate::settings::toml::route::RouteConfig;
+use crate::settings::toml::limits::Limits;
+use crate::settings::toml::protection::{DirtyTree, Freeze, Protection};
+use crate::settings::toml::resolved::ResolvedTarget;
+use crate::settings::toml::route::{RouteConfig, RouteEntry};
 use crate::settings::toml::site::Site;
 use crate::settings::toml::target_type::TargetType;
 use crate::settings::toml::triggers::Triggers;
+use crate::settings::toml::workspace::{self, WorkspaceWorker};
 use crate::settings::toml::Target;
 use crate::terminal::{
-    emoji,
+    diff, emoji, interactive,
     message::{Message, StdOut},
     styles,
 };
@@ -40,22 +53,34 @@ pub struct Manifest {
     pub name: String,
     #[serde(rename = "type")]
     pub target_type: TargetType,
+    pub format: Option<ScriptFormat>,
     #[serde(default)]
     pub account_id: LazyAccountId,
+    /// The profile of the global config file whose credentials are used, see `read_profile`
+    pub profile: Option<String>,
     pub workers_dev: Option<bool>,
     #[serde(default, with = "string_empty_as_none")]
     pub route: Option<String>,
-    pub routes: Option<Vec<String>>,
+    pub routes: Option<Vec<RouteEntry>>,
     #[serde(default, with = "string_empty_as_none")]
     pub zone_id: Option<String>,
     pub webpack_config: Option<String>,
     pub build: Option<Builder>,
+    /// How imports of non-JavaScript files are treated, by the bundler and in module uploads
+    pub rules: Option<Vec<ModuleRule>>,
     pub private: Option<bool>,
+    pub protection: Option<Protection>,
+    /// Environments that publishing to or deleting from needs to be confirmed for. `""`
+    /// stands for the top level
+    #[serde(default)]
+    pub protected_environments: Vec<String>,
+    pub hooks: Option<Hooks>,
     pub dev: Option<Dev>,
     pub triggers: Option<Triggers>,
     pub migrations: Option<Vec<MigrationConfig>>,
     #[serde(default, with = "string_empty_as_none")]
     pub usage_model: Option<UsageModel>,
+    pub limits: Option<Limits>,
     pub compatibility_date: Option<String>,
     #[serde(default)]
     pub compatibility_flags: Vec<String>,
@@ -67,36 +92,201 @@ pub struct Manifest {
     // as a TOML inline table (this would prevent confusion with environments too!)
     pub site: Option<Site>,
     pub vars: Option<HashMap<String, String>>,
+    /// The names of the secrets the worker expects, set with `wrangler secret put`. Only
+    /// `wrangler secret diff` reads them.
+    pub secrets: Option<Vec<String>>,
+    /// The workers the events of this one are sent to, and the queues it consumes. Only
+    /// `wrangler graph` reads them; they're set up in the dashboard.
+    pub tail_consumers: Option<Vec<String>>,
+    pub queue_consumers: Option<Vec<String>>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
     pub wasm_modules: Option<HashMap<String, PathBuf>>,
+    pub bindings: Option<Vec<CustomBinding>>,
+    pub workers: Option<Vec<WorkspaceWorker>>,
 }
 
 impl Manifest {
+    /// Looks for a configuration file named `file_name` in `dir` and then in each of its
+    /// parents, so that commands work from anywhere inside of a project.
+    pub fn find(dir: &Path, file_name: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|ancestor| ancestor.join(file_name))
+            .find(|config_path| config_path.is_file())
+    }
+
     pub fn new(config_path: &Path) -> Result<Self> {
         let file_name = config_path.file_name().unwrap().to_str().unwrap();
         let mut message = format!("{} not found", file_name);
         if config_path.to_str().unwrap() == DEFAULT_CONFIG_PATH {
             message.push_str("; run `wrangler init` to create one.");
         }
-        anyhow::ensure!(config_path.exists(), message);
-        let config = read_config(config_path)?;
+        anyhow::ensure!(config_path.exists(), WranglerError::Config(message));
+        let config_error = |e: anyhow::Error| WranglerError::tag(e, WranglerError::Config);
+        let base = extends::base_config(config_path).map_err(config_error)?;
+        let config = read_config(config_path, base.as_deref()).map_err(config_error)?;
+        unknown_keys::check(config_path, base.as_deref()).map_err(config_error)?;
 
-        let manifest: Manifest = match config.try_into() {
+        let mut manifest: Manifest = match config.try_into() {
             Ok(m) => m,
             Err(e) => {
                 if e.to_string().contains("unknown field `kv-namespaces`") {
-                    anyhow::bail!("kv-namespaces should not live under the [site] table in your configuration file; please move it above [site].")
+                    anyhow::bail!(WranglerError::Config("kv-namespaces should not live under the [site] table in your configuration file; please move it above [site].".to_string()))
                 } else {
-                    anyhow::bail!(e)
+                    anyhow::bail!(WranglerError::Config(e.to_string()))
                 }
             }
         };
 
-        check_for_duplicate_names(&manifest)?;
+        check_for_duplicate_names(&manifest).map_err(config_error)?;
+        manifest.expand_env_vars();
+        overrides::get().apply(&mut manifest);
+        discovery::set_config_path(config_path.to_path_buf());
 
         Ok(manifest)
     }
 
+    /// The `profile` of `environment`, or else of the top level, of the configuration file at
+    /// `config_path`. It's read on its own, since the credentials can be needed before the
+    /// file is loaded.
+    pub fn read_profile(config_path: &Path, environment: Option<&str>) -> Option<String> {
+        let config: toml::Value = toml::from_str(&fs::read_to_string(config_path).ok()?).ok()?;
+        environment
+            .and_then(|environment| config.get("env")?.get(environment)?.get("profile"))
+            .or_else(|| config.get("profile"))
+            .and_then(toml::Value::as_str)
+            .filter(|profile| !profile.is_empty())
+            .map(str::to_string)
+    }
+
+    /// The `profile` that every one of `environments`, `None` standing for the top level,
+    /// has, read like `read_profile`. A run of wrangler has the credentials of a single
+    /// profile, so environments of different profiles can't be worked on together.
+    pub fn read_shared_profile(
+        config_path: &Path,
+        environments: &[Option<String>],
+    ) -> Result<Option<String>> {
+        let profiles: Vec<Option<String>> = environments
+            .iter()
+            .map(|environment| Self::read_profile(config_path, environment.as_deref()))
+            .collect();
+        match profiles.split_first() {
+            Some((first, rest)) if rest.iter().any(|profile| profile != first) => {
+                let uses: Vec<String> = environments
+                    .iter()
+                    .zip(&profiles)
+                    .map(|(environment, profile)| {
+                        format!(
+                            "{} uses {}",
+                            environment.as_deref().unwrap_or("the top level"),
+                            profile.as_deref().map_or_else(
+                                || "the default credentials".to_string(),
+                                |profile| format!("the profile {}", profile)
+                            )
+                        )
+                    })
+                    .collect();
+                anyhow::bail!(WranglerError::Config(format!(
+                    "The environments don't share a profile ({}), publish to them one at a time so each gets its own credentials",
+                    uses.join(", ")
+                )))
+            }
+            Some((first, _)) => Ok(first.clone()),
+            None => Ok(None),
+        }
+    }
+
+    /// The names of the environments of the configuration file at `config_path`, sorted and
+    /// read on their own like `read_profile`
+    pub fn read_environment_names(config_path: &Path) -> Vec<String> {
+        let config: Option<toml::Value> = fs::read_to_string(config_path)
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok());
+        let mut names: Vec<String> = config
+            .as_ref()
+            .and_then(|config| config.get("env")?.as_table())
+            .map(|envs| envs.keys().cloned().collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    // Expands the `${NAME}` references in the settings that identify an account and where
+    // to publish to, so that they don't have to be committed along with the file
+    fn expand_env_vars(&mut self) {
+        if let Some(account_id) = self.account_id.if_present() {
+            self.account_id = Some(interpolate::expand(account_id)).into();
+        }
+        expand_route_settings(&mut self.zone_id, &mut self.route, &mut self.routes);
+        expand_kv_namespaces(&mut self.kv_namespaces);
+
+        for environment in self.env.iter_mut().flat_map(|envs| envs.values_mut()) {
+            if let Some(account_id) = &mut environment.account_id {
+                *account_id = interpolate::expand(account_id);
+            }
+            expand_route_settings(
+                &mut environment.zone_id,
+                &mut environment.route,
+                &mut environment.routes,
+            );
+            expand_kv_namespaces(&mut environment.kv_namespaces);
+        }
+    }
+
+    /// Fails with the names of the environment variables that the settings used by
+    /// `environment_name` refer to but that aren't set
+    pub fn check_env_vars(&self, environment_name: Option<&str>) -> Result<()> {
+        let environment = self.get_environment(environment_name)?;
+
+        let mut values: Vec<&String> = Vec::new();
+        values.extend(self.account_id.if_present());
+        values.extend(&self.zone_id);
+        let (route, routes, kv_namespaces) = match environment {
+            Some(environment) => {
+                values.extend(&environment.account_id);
+                values.extend(&environment.zone_id);
+                if environment.route.is_none() && environment.routes.is_none() {
+                    (&self.route, &self.routes, &environment.kv_namespaces)
+                } else {
+                    (
+                        &environment.route,
+                        &environment.routes,
+                        &environment.kv_namespaces,
+                    )
+                }
+            }
+            None => (&self.route, &self.routes, &self.kv_namespaces),
+        };
+        values.extend(route);
+        for entry in routes.iter().flatten() {
+            match entry {
+                RouteEntry::Pattern(pattern) => values.push(pattern),
+                RouteEntry::Table { pattern, zone_id } => {
+                    values.push(pattern);
+                    values.extend(zone_id);
+                }
+            }
+        }
+        for kv in kv_namespaces.iter().flatten() {
+            values.extend(&kv.id);
+            values.extend(&kv.preview_id);
+        }
+
+        let mut missing: Vec<String> = values
+            .into_iter()
+            .flat_map(|value| interpolate::unresolved(value))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        if !missing.is_empty() {
+            anyhow::bail!(WranglerError::Config(format!(
+                "{} Your configuration file refers to environment variables that are not set: {}",
+                emoji::WARN,
+                missing.join(", ")
+            )))
+        }
+        Ok(())
+    }
+
     pub fn generate(
         name: String,
         target_type: Option<TargetType>,
@@ -181,11 +371,37 @@ impl Manifest {
         let toml = config_template_doc.to_string_in_original_order();
         let manifest = toml::from_str::<Manifest>(&toml)?;
 
+        // generate and init write the configuration of a project they're just creating, so
+        // there's nothing of the user's in it to confirm changes to
         log::info!("Writing a wrangler.toml file at {}", config_file.display());
         fs::write(&config_file, &toml)?;
         Ok(manifest)
     }
 
+    /// The `secrets` of `environment_name`, or else of the top level: unlike vars, the names
+    /// of secrets are the same in every environment, only their values differ
+    pub fn secret_names(&self, environment_name: Option<&str>) -> Result<Vec<String>> {
+        let environment = self.get_environment(environment_name)?;
+        let mut secrets = environment
+            .and_then(|environment| environment.secrets.clone())
+            .or_else(|| self.secrets.clone())
+            .unwrap_or_default();
+        secrets.sort();
+        secrets.dedup();
+        Ok(secrets)
+    }
+
+    /// The names of the environments under [env], sorted
+    pub fn environment_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .env
+            .iter()
+            .flat_map(|envs| envs.keys().cloned())
+            .collect();
+        names.sort();
+        names
+    }
+
     pub fn worker_name(&self, env_arg: Option<&str>) -> String {
         if let Some(environment) = self.get_environment(env_arg).unwrap_or_default() {
             if let Some(name) = &environment.name {
@@ -199,6 +415,43 @@ impl Manifest {
         self.name.clone()
     }
 
+    /// Returns the manifests of the `[[workers]]` entries in this configuration file in the
+    /// order they should be published, each with the directory it is built from. If `only`
+    /// is not empty, just the named workers are returned.
+    pub fn workspace_members(&self, only: &[String]) -> Result<Vec<(Manifest, PathBuf)>> {
+        let workers = match &self.workers {
+            Some(workers) if !workers.is_empty() => workers,
+            _ => anyhow::bail!(
+                "{} There are no [[workers]] entries in your configuration file",
+                emoji::WARN
+            ),
+        };
+
+        Ok(workspace::publish_order(workers, only)?
+            .into_iter()
+            .map(|worker| {
+                let path = worker.path.clone().unwrap_or_else(|| PathBuf::from("."));
+                let mut manifest = worker.manifest(self);
+                // each worker keeps its own name
+                Overrides {
+                    name: None,
+                    ..overrides::get()
+                }
+                .apply(&mut manifest);
+                (manifest, path)
+            })
+            .collect())
+    }
+
+    /// The names of the `[[workers]]` entries built from one of the `changed` files, which
+    /// are relative to this configuration file
+    pub fn changed_workspace_members(&self, changed: &[PathBuf]) -> Vec<String> {
+        match &self.workers {
+            Some(workers) => workspace::changed_workers(workers, changed),
+            None => Vec::new(),
+        }
+    }
+
     fn route_config(&self) -> RouteConfig {
         RouteConfig {
             account_id: self.account_id.clone(),
@@ -209,28 +462,72 @@ impl Manifest {
         }
     }
 
+    /// The route patterns `env` is published to. Environments without routes of their own
+    /// use the ones of the top level.
+    pub fn route_patterns(&self, env: Option<&str>) -> Result<Vec<String>> {
+        let (route, routes) = match self.get_environment(env)? {
+            Some(environment) if environment.route.is_some() || environment.routes.is_some() => {
+                (&environment.route, &environment.routes)
+            }
+            _ => (&self.route, &self.routes),
+        };
+        Ok(route
+            .iter()
+            .map(String::as_str)
+            .chain(routes.iter().flatten().map(RouteEntry::pattern))
+            .map(str::to_string)
+            .collect())
+    }
+
     pub fn get_deployments(&self, env: Option<&str>) -> Result<DeploymentSet> {
+        self.check_env_vars(env)?;
         let script = self.worker_name(env);
         validate_worker_name(&script)?;
 
         let mut deployments = DeploymentSet::new();
 
+        let environment_name = env;
         let env = self.get_environment(env)?;
 
         let mut add_routed_deployments = |route_config: &RouteConfig| -> Result<()> {
+            // routes without a zone are published to the zone they belong to, if it can
+            // be found
+            let discovered;
+            let route_config = if !route_config.is_zoned()
+                && !route_config.is_zoneless()
+                && route_config.has_routes_defined()
+            {
+                let routes = route_config.unzoned_patterns();
+                discovered = RouteConfig {
+                    zone_id: Some(discovery::zone_id(
+                        &routes,
+                        &route_config.account_id,
+                        environment_name,
+                    )?),
+                    account_id: route_config.account_id.clone(),
+                    workers_dev: route_config.workers_dev,
+                    route: route_config.route.clone(),
+                    routes: route_config.routes.clone(),
+                };
+                &discovered
+            } else {
+                route_config
+            };
+
             if route_config.is_zoned() {
-                let zoned = deploy::ZonedTarget::build(&script, route_config)?;
+                // one target for each zone the routes are in
+                let zones = deploy::ZonedTarget::build(&script, route_config)?;
 
-                if zoned.routes.is_empty() {
+                if zones.is_empty() {
                     return Ok(());
                 }
 
                 // This checks all of the configured routes for the wildcard ending and warns
                 // the user that their site may not work as expected without it.
                 if self.site.is_some() {
-                    let no_star_routes = zoned
-                        .routes
+                    let no_star_routes = zones
                         .iter()
+                        .flat_map(|zoned| &zoned.routes)
                         .filter(|r| !r.pattern.ends_with('*'))
                         .map(|r| r.pattern.as_str())
                         .collect::<Vec<_>>();
@@ -242,7 +539,14 @@ impl Manifest {
                     }
                 }
 
-                deployments.push(DeployTarget::Zoned(zoned));
+                deployments.extend(zones.into_iter().map(DeployTarget::Zoned));
+
+                // publishing only ever turns workers.dev on, so a worker that was once on it
+                // would stay reachable there without being taken off
+                if route_config.workers_dev == Some(false) {
+                    let zoneless = deploy::ZonelessTarget::build(&script, route_config)?;
+                    deployments.push(DeployTarget::DisableZoneless(zoneless));
+                }
             }
 
             if route_config.is_zoneless() {
@@ -250,17 +554,6 @@ impl Manifest {
                 deployments.push(DeployTarget::Zoneless(zoneless));
             }
 
-            if !route_config.is_zoned()
-                && !route_config.is_zoneless()
-                && (route_config.route.is_some()
-                    || (route_config.routes.is_some()
-                        && !route_config.routes.as_ref().unwrap().is_empty()))
-            {
-                anyhow::bail!(
-                    "Routes specified with no zone, specify `zone_id` in your wrangler.toml"
-                )
-            }
-
             Ok(())
         };
 
@@ -331,7 +624,9 @@ impl Manifest {
         self.account_id.load().map(String::from)
     }
 
-    pub fn get_target(&self, environment_name: Option<&str>, preview: bool) -> Result<Target> {
+    // Workers Sites need a bundler, which Rust projects and JavaScript projects without a
+    // build command don't have
+    pub(crate) fn check_site(&self) -> Result<()> {
         if self.site.is_some() {
             match self.target_type {
                 TargetType::Rust => {
@@ -340,7 +635,7 @@ impl Manifest {
                         emoji::WARN
                     )
                 }
-                TargetType::JavaScript => {
+                TargetType::JavaScript | TargetType::TypeScript => {
                     let error_message = format!(
                         "{} Workers Sites requires using a bundler, and your configuration indicates that you aren't using one. You can fix this by:\n* setting your project type to \"webpack\" to use our automatically configured webpack bundler.\n* setting your project type to \"javascript\", and configuring a build command in the `[build]` section if you wish to use your choice of bundler.",
                         emoji::WARN
@@ -356,6 +651,12 @@ impl Manifest {
                 _ => {}
             }
         }
+        Ok(())
+    }
+
+    pub fn get_target(&self, environment_name: Option<&str>, preview: bool) -> Result<Target> {
+        self.check_env_vars(environment_name)?;
+        self.check_site()?;
 
         /*
         From https://developers.cloudflare.com/workers/cli-wrangler/configuration#keys
@@ -386,9 +687,12 @@ impl Manifest {
             vars: self.vars.clone(), // Not inherited
             text_blobs: self.text_blobs.clone(), // Inherited
             usage_model: self.usage_model, // Top level
+            limits: self.limits,     // Inherited
             wasm_modules: self.wasm_modules.clone(),
+            bindings: self.bindings.clone(), // Not inherited
             compatibility_date: self.compatibility_date.clone(),
             compatibility_flags: self.compatibility_flags.clone(),
+            rules: self.rules.clone().unwrap_or_default(), // Top level
         };
 
         let environment = self.get_environment(environment_name)?;
@@ -404,6 +708,9 @@ impl Manifest {
             if let Some(build) = &environment.build {
                 target.build = Some(build.clone());
             }
+            if let Some(limits) = environment.limits {
+                target.limits = Some(limits);
+            }
 
             // don't inherit kv namespaces because it is an anti-pattern to use the same namespaces across multiple environments
             target.kv_namespaces = get_namespaces(environment.kv_namespaces.clone(), preview)?;
@@ -418,11 +725,133 @@ impl Manifest {
 
             // don't inherit vars
             target.vars = environment.vars.clone();
+
+            // don't inherit custom bindings, for the same reason as kv namespaces
+            target.bindings = environment.bindings.clone();
+        }
+
+        if let Some(limits) = &target.limits {
+            limits.validate()?;
+        }
+        check_custom_binding_names(&target)?;
+
+        if target.target_type == TargetType::TypeScript && target.build.is_none() {
+            target.build = Some(Builder::typescript());
+        }
+
+        // the `format` of a project is only a shorthand for the upload of a `[build]` table
+        match (self.format, target.build.as_ref().map(|build| &build.upload)) {
+            (Some(ScriptFormat::Modules), None) if target.target_type == TargetType::JavaScript => {
+                target.build = Some(Builder::modules(&target.package_dir()?)?);
+            }
+            (Some(ScriptFormat::Modules), None) => anyhow::bail!(WranglerError::Config(format!(
+                "format = \"modules\" is only supported for javascript projects, configure [build.upload] for {} projects",
+                target.target_type
+            ))),
+            (Some(ScriptFormat::Modules), Some(UploadFormat::ServiceWorker { .. }))
+            | (Some(ScriptFormat::ServiceWorker), Some(UploadFormat::Modules { .. })) => {
+                anyhow::bail!(WranglerError::Config(
+                    "format does not match the format of [build.upload], remove one of them"
+                        .to_string()
+                ))
+            }
+            _ => {}
+        }
+
+        // the rules of `[build.upload]` are more specific, so they come first
+        if let Some(Builder {
+            upload: UploadFormat::Modules { rules, .. },
+            ..
+        }) = &mut target.build
+        {
+            if !target.rules.is_empty() {
+                let mut all = rules.take().unwrap_or_default();
+                all.extend(target.rules.iter().cloned());
+                *rules = Some(all);
+            }
         }
 
         Ok(target)
     }
 
+    /// The bindings of `environment_name` with every problem with them collected rather than
+    /// returned, see `ResolvedTarget`
+    pub fn resolve(&self, environment_name: Option<&str>) -> Result<ResolvedTarget> {
+        ResolvedTarget::new(self, environment_name)
+    }
+
+    /// What freezes publishing `environment_name`, if anything does: the `[protection]` table
+    /// of the environment, or of the top level when the environment has none, or else a
+    /// `.wrangler-freeze` file in the repository
+    pub fn get_freeze(&self, environment_name: Option<&str>) -> Result<Option<Freeze>> {
+        if let Some((protection, table)) = self.get_protection(environment_name)? {
+            let source = match discovery::config_path() {
+                Some(config_path) => format!("{} of {}", table, config_path.display()),
+                None => table,
+            };
+            if let Some(freeze) = Freeze::from_protection(protection, source) {
+                return Ok(Some(freeze));
+            }
+        }
+
+        Ok(Freeze::from_file(&env::current_dir()?))
+    }
+
+    /// What to do about publishing `environment_name` from a git checkout with uncommitted
+    /// changes, going by the same `[protection]` table as `get_freeze`
+    pub fn get_dirty_tree(&self, environment_name: Option<&str>) -> Result<DirtyTree> {
+        Ok(self
+            .get_protection(environment_name)?
+            .map(|(protection, _)| protection.dirty_tree)
+            .unwrap_or_default())
+    }
+
+    /// Whether publishing to or deleting from `environment_name` needs to be confirmed. The
+    /// top level is protected when `protected_environments` lists `""`, or `"production"`
+    /// without there being an `[env.production]`.
+    pub fn is_protected(&self, environment_name: Option<&str>) -> bool {
+        let has_production = self
+            .env
+            .as_ref()
+            .map_or(false, |env| env.contains_key("production"));
+        self.protected_environments
+            .iter()
+            .any(|protected| match environment_name {
+                Some(name) => protected == name,
+                None => protected.is_empty() || (protected == "production" && !has_production),
+            })
+    }
+
+    /// The commands to run around publishing `environment_name`: the `[hooks]` table of the
+    /// environment, or of the top level when the environment has none
+    pub fn get_hooks(&self, environment_name: Option<&str>) -> Result<Hooks> {
+        let environment = self.get_environment(environment_name)?;
+        Ok(environment
+            .and_then(|environment| environment.hooks.as_ref())
+            .or_else(|| self.hooks.as_ref())
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    // The `[protection]` table of `environment_name`, or of the top level when the environment
+    // has none, along with its name
+    fn get_protection(
+        &self,
+        environment_name: Option<&str>,
+    ) -> Result<Option<(&Protection, String)>> {
+        let environment = self.get_environment(environment_name)?;
+        let env_protection = environment.and_then(|environment| environment.protection.as_ref());
+        Ok(match (environment_name, env_protection) {
+            (Some(name), Some(protection)) => {
+                Some((protection, format!("[env.{}.protection]", name)))
+            }
+            _ => self
+                .protection
+                .as_ref()
+                .map(|protection| (protection, "[protection]".to_string())),
+        })
+    }
+
     pub fn get_environment(&self, environment_name: Option<&str>) -> Result<Option<&Environment>> {
         // check for user-specified environment name
         if let Some(environment_name) = environment_name {
@@ -607,7 +1036,7 @@ impl LazyAccountId {
     /// Return the `account_id` in `wrangler.toml`, if present.
     ///
     /// Use this with caution; prefer `maybe_load` instead where possible.
-    fn if_present(&self) -> Option<&String> {
+    pub(crate) fn if_present(&self) -> Option<&String> {
         self.0.get()
     }
 
@@ -648,6 +1077,16 @@ impl LazyAccountId {
                     anyhow::bail!("field `account_id` is required")
                 }
                 [single] => Ok(single.id.clone()),
+                multiple if interactive::is_interactive() => {
+                    StdOut::info("You have multiple accounts.");
+                    let options = multiple
+                        .iter()
+                        .map(|account| (account.id.as_str(), account.name.as_str()))
+                        .collect::<Vec<_>>();
+                    let account_id = discovery::choose("account", &options)?;
+                    discovery::write_back("account_id", &account_id, None);
+                    Ok(account_id)
+                }
                 _multiple => {
                     StdOut::user_error("You have multiple accounts.");
                     whoami::display_account_id_maybe();
@@ -666,12 +1105,38 @@ impl FromStr for Manifest {
     }
 }
 
-fn read_config(config_path: &Path) -> Result<Config> {
+fn expand_route_settings(
+    zone_id: &mut Option<String>,
+    route: &mut Option<String>,
+    routes: &mut Option<Vec<RouteEntry>>,
+) {
+    for value in zone_id
+        .iter_mut()
+        .chain(route)
+        .chain(routes.iter_mut().flatten().flat_map(RouteEntry::values_mut))
+    {
+        *value = interpolate::expand(value);
+    }
+}
+
+fn expand_kv_namespaces(kv_namespaces: &mut Option<Vec<ConfigKvNamespace>>) {
+    for kv in kv_namespaces.iter_mut().flatten() {
+        for id in kv.id.iter_mut().chain(&mut kv.preview_id) {
+            *id = interpolate::expand(id);
+        }
+    }
+}
+
+fn read_config(config_path: &Path, base: Option<&str>) -> Result<Config> {
     let mut config = Config::new();
 
     let config_str = config_path
         .to_str()
         .expect("project config path should be a string");
+    // the local file is merged over the base configuration it extends
+    if let Some(base) = base {
+        config.merge(File::from_str(base, FileFormat::Toml))?;
+    }
     config.merge(File::with_name(config_str))?;
 
     // Eg.. `CF_ACCOUNT_AUTH_KEY=farts` would set the `account_auth_key` key
@@ -680,6 +1145,30 @@ fn read_config(config_path: &Path) -> Result<Config> {
     Ok(config)
 }
 
+// Shows what writing `contents` to `config_file` changes, and asks before doing it unless
+// --yes was passed. Without anybody at the terminal to ask, it takes --yes to write it.
+// A file that doesn't exist yet is written without asking.
+pub(crate) fn confirm_write(config_file: &Path, contents: &str) -> Result<()> {
+    let current = match fs::read_to_string(config_file) {
+        Ok(current) => current,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    if current == contents {
+        return Ok(());
+    }
+
+    StdOut::info(&format!("Changes to {}:", config_file.display()));
+    diff::print(&current, contents);
+    if !interactive::confirm(&format!(
+        "Write these changes to {}?",
+        config_file.display()
+    ))? {
+        anyhow::bail!("Left {} unchanged", config_file.display())
+    }
+    Ok(())
+}
+
 fn check_for_duplicate_names(manifest: &Manifest) -> Result<()> {
     let mut names: HashSet<String> = HashSet::new();
     let mut duplicate_names: HashSet<String> = HashSet::new();
@@ -695,6 +1184,15 @@ fn check_for_duplicate_names(manifest: &Manifest) -> Result<()> {
             }
         }
     }
+    if let Some(workers) = &manifest.workers {
+        for worker in workers {
+            if names.contains(&worker.name) && !duplicate_names.contains(&worker.name) {
+                duplicate_names.insert(worker.name.to_string());
+            } else {
+                names.insert(worker.name.to_string());
+            }
+        }
+    }
     let duplicate_name_string = duplicate_names
         .clone()
         .into_iter()
@@ -716,6 +1214,39 @@ fn check_for_duplicate_names(manifest: &Manifest) -> Result<()> {
     Ok(())
 }
 
+// The API rejects an upload with two bindings of the same name, and [[bindings]] entries are
+// passed through as they are, so their names are checked against every other binding here
+fn check_custom_binding_names(target: &Target) -> Result<()> {
+    let mut names: HashSet<&str> = target
+        .kv_namespaces
+        .iter()
+        .map(|ns| ns.binding.as_str())
+        .collect();
+    if let Some(classes) = target
+        .durable_objects
+        .as_ref()
+        .and_then(|d| d.classes.as_ref())
+    {
+        names.extend(classes.iter().map(|class| class.binding.as_str()));
+    }
+    let named_after_keys = target
+        .vars
+        .iter()
+        .flat_map(|vars| vars.keys())
+        .chain(target.text_blobs.iter().flat_map(|blobs| blobs.keys()))
+        .chain(target.wasm_modules.iter().flat_map(|wasm| wasm.keys()));
+    names.extend(named_after_keys.map(String::as_str));
+    for binding in target.bindings.iter().flatten() {
+        if !names.insert(binding.name.as_str()) {
+            anyhow::bail!(WranglerError::Config(format!(
+                "More than one binding is named \"{}\", give the [[bindings]] entry a name of its own",
+                binding.name
+            )))
+        }
+    }
+    Ok(())
+}
+
 fn get_namespaces(
     kv_namespaces: Option<Vec<ConfigKvNamespace>>,
     preview: bool,
@@ -775,6 +1306,13 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn confirm_write_new_file() {
+        let dir = tempfile::tempdir().unwrap();
+        // nobody's at the terminal to answer, so this would fail if it asked
+        assert!(confirm_write(&dir.path().join("wrangler.toml"), "name = \"test\"\n").is_ok());
+    }
+
     #[test]
     fn serialize() {
         let manifest = Manifest {
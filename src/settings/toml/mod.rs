@@ -1,24 +1,43 @@
 mod builder;
+mod custom_binding;
 mod dev;
+mod discovery;
 mod durable_objects;
 mod environment;
+mod extends;
+mod hooks;
+mod interpolate;
 mod kv_namespace;
+mod limits;
 mod manifest;
 pub mod migrations;
-mod route;
+pub mod overrides;
+mod protection;
+mod resolved;
+pub(crate) mod route;
 mod site;
 pub(crate) mod target;
 mod target_type;
 mod triggers;
+mod unknown_keys;
+mod workspace;
 
-pub use builder::{ModuleRule, UploadFormat};
+pub use builder::{ModuleRule, ScriptFormat, UploadFormat};
+pub use custom_binding::CustomBinding;
+pub(crate) use discovery::{config_path, fetch_zones};
 pub use durable_objects::{DurableObjects, DurableObjectsClass};
+pub use hooks::Hooks;
 pub use kv_namespace::{ConfigKvNamespace, KvNamespace};
+pub use limits::Limits;
+pub(crate) use manifest::confirm_write;
 pub use manifest::Manifest;
-pub use route::{Route, RouteConfig};
-pub use site::Site;
+pub use protection::{DirtyTree, Freeze, Protection, FREEZE_FILE};
+pub use resolved::{ResolvedKvNamespace, ResolvedTarget};
+pub use route::{Route, RouteConfig, RouteEntry};
+pub use site::{ContentOptions, NotFoundHandling, Site, SiteCacheControl, SiteTtl};
 pub use target::Target;
 pub use target_type::TargetType;
+pub use workspace::WorkspaceWorker;
 
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
@@ -0,0 +1,81 @@
+use once_cell::sync::OnceCell;
+
+use super::Manifest;
+
+static OVERRIDES: OnceCell<Overrides> = OnceCell::new();
+
+/// Settings of the configuration file given on the command line. They take precedence over
+/// the file, its environments and the `CF_` environment variables, for every command that
+/// reads the file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overrides {
+    /// `--account-id`
+    pub account_id: Option<String>,
+    /// `--name` of `wrangler publish`, which names the worker whatever the environment
+    pub name: Option<String>,
+}
+
+/// Sets the overrides for the rest of the process. Only the first call has an effect.
+pub fn set(overrides: Overrides) {
+    let _ = OVERRIDES.set(overrides);
+}
+
+pub(super) fn get() -> Overrides {
+    OVERRIDES.get().cloned().unwrap_or_default()
+}
+
+impl Overrides {
+    pub(super) fn apply(&self, manifest: &mut Manifest) {
+        if let Some(account_id) = &self.account_id {
+            manifest.account_id = Some(account_id.clone()).into();
+        }
+        if let Some(name) = &self.name {
+            manifest.name = name.clone();
+        }
+        for environment in manifest.env.iter_mut().flat_map(|envs| envs.values_mut()) {
+            if self.account_id.is_some() {
+                environment.account_id = None;
+            }
+            if self.name.is_some() {
+                environment.name = self.name.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn it_overrides_the_file_and_its_environments() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("wrangler.toml");
+        fs::write(
+            &config_path,
+            r#"
+name = "worker"
+type = "javascript"
+account_id = "file"
+workers_dev = true
+
+[env.staging]
+account_id = "staging"
+"#,
+        )
+        .unwrap();
+        let mut manifest = Manifest::new(&config_path).unwrap();
+        Overrides {
+            account_id: Some("flag".to_string()),
+            name: Some("canary-worker".to_string()),
+        }
+        .apply(&mut manifest);
+
+        for env in &[None, Some("staging")] {
+            let target = manifest.get_target(*env, false).unwrap();
+            assert_eq!(target.account_id.if_present().unwrap(), "flag");
+            assert_eq!(target.name, "canary-worker");
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// The file that freezes publishing every environment of the projects below it, with the
+/// reason for the freeze as its contents
+pub const FREEZE_FILE: &str = ".wrangler-freeze";
+
+/// The `[protection]` table of the top level or of an environment
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Protection {
+    /// Refuse to publish unless the freeze is overridden
+    #[serde(default)]
+    pub frozen: bool,
+    /// Why publishing is frozen, printed when a publish is refused
+    pub reason: Option<String>,
+    /// What to do about publishing from a git checkout with uncommitted changes
+    #[serde(default)]
+    pub dirty_tree: DirtyTree,
+}
+
+/// What publishing from a git checkout with uncommitted changes to tracked files does
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DirtyTree {
+    Allow,
+    Warn,
+    /// Refuse to publish unless `--allow-dirty` is passed
+    Refuse,
+}
+
+impl Default for DirtyTree {
+    fn default() -> Self {
+        DirtyTree::Warn
+    }
+}
+
+/// A freeze on publishing an environment, and where it comes from
+#[derive(Clone, Debug, PartialEq)]
+pub struct Freeze {
+    pub reason: Option<String>,
+    pub source: String,
+}
+
+impl Freeze {
+    pub(crate) fn from_protection(protection: &Protection, source: String) -> Option<Self> {
+        if protection.frozen {
+            Some(Freeze {
+                reason: protection.reason.clone(),
+                source,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The freeze file of the repository `dir` is in, looked for in `dir` and each of its
+    /// parents up to the root of the repository
+    pub(crate) fn from_file(dir: &Path) -> Option<Self> {
+        let path = find_freeze_file(dir)?;
+        let reason = fs::read_to_string(&path).unwrap_or_default();
+        let reason = reason.trim();
+        Some(Freeze {
+            reason: if reason.is_empty() {
+                None
+            } else {
+                Some(reason.to_string())
+            },
+            source: path.display().to_string(),
+        })
+    }
+}
+
+fn find_freeze_file(dir: &Path) -> Option<PathBuf> {
+    for ancestor in dir.ancestors() {
+        let path = ancestor.join(FREEZE_FILE);
+        if path.is_file() {
+            return Some(path);
+        }
+        // a freeze file outside of the repository belongs to something else
+        if ancestor.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_freeze_file_of_the_repository() {
+        let repo = tempfile::tempdir().unwrap();
+        let project = repo.path().join("workers").join("api");
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir(repo.path().join(".git")).unwrap();
+        assert_eq!(Freeze::from_file(&project), None);
+
+        fs::write(
+            repo.path().join(FREEZE_FILE),
+            "Release 2.0 goes out Monday\n",
+        )
+        .unwrap();
+        let freeze = Freeze::from_file(&project).unwrap();
+        assert_eq!(
+            freeze.reason.as_deref(),
+            Some("Release 2.0 goes out Monday")
+        );
+
+        fs::write(project.join(FREEZE_FILE), "").unwrap();
+        assert_eq!(Freeze::from_file(&project).unwrap().reason, None);
+    }
+}
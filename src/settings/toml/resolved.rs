@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use cloudflare::endpoints::workers::ListSecrets;
+use cloudflare::framework::apiclient::ApiClient;
+use serde::Serialize;
+
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::custom_binding::CustomBinding;
+use crate::settings::toml::durable_objects::DurableObjectsClass;
+use crate::settings::toml::manifest::Manifest;
+
+/// The bindings a worker gets in an environment, with the inheritance rules of the
+/// configuration file applied. Unlike `Manifest::get_target`, which stops at the first
+/// problem, everything that can be worked out is, and the problems are collected in `errors`,
+/// so that tools embedding wrangler can show a whole environment at once.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ResolvedTarget {
+    /// The environment, `None` for the top level
+    pub environment: Option<String>,
+    /// The name the worker is published under in the environment
+    pub name: String,
+    /// The account ID, if the configuration file sets it
+    pub account_id: Option<String>,
+    pub kv_namespaces: Vec<ResolvedKvNamespace>,
+    pub durable_objects: Vec<DurableObjectsClass>,
+    pub vars: HashMap<String, String>,
+    pub text_blobs: HashMap<String, PathBuf>,
+    pub wasm_modules: HashMap<String, PathBuf>,
+    pub bindings: Vec<CustomBinding>,
+    /// The names of the secrets of the worker. They aren't part of the configuration file, so
+    /// this is empty until `with_secrets` lists them.
+    pub secrets: Vec<String>,
+    /// What keeps the configuration of the environment from being published
+    pub errors: Vec<String>,
+}
+
+/// A KV namespace binding with the namespaces it refers to when published and when previewed
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ResolvedKvNamespace {
+    pub binding: String,
+    pub id: Option<String>,
+    pub preview_id: Option<String>,
+}
+
+impl ResolvedTarget {
+    /// Resolves the bindings of `environment_name`. Only an environment that doesn't exist is
+    /// an error; everything else that is wrong ends up in `errors`.
+    pub fn new(manifest: &Manifest, environment_name: Option<&str>) -> Result<Self> {
+        let environment = manifest.get_environment(environment_name)?;
+        let mut errors = Vec::new();
+        for check in [
+            manifest.check_env_vars(environment_name),
+            manifest.check_site(),
+        ]
+        .iter()
+        {
+            if let Err(e) = check {
+                errors.push(e.to_string());
+            }
+        }
+
+        // kv namespaces, durable objects, vars and custom bindings aren't inherited; the
+        // account ID is
+        let (account_id, kv_namespaces, durable_objects, vars, bindings) = match environment {
+            Some(environment) => (
+                environment
+                    .account_id
+                    .clone()
+                    .or_else(|| manifest.account_id.if_present().cloned()),
+                environment.kv_namespaces.clone(),
+                environment.durable_objects.clone(),
+                environment.vars.clone(),
+                environment.bindings.clone(),
+            ),
+            None => (
+                manifest.account_id.if_present().cloned(),
+                manifest.kv_namespaces.clone(),
+                manifest.durable_objects.clone(),
+                manifest.vars.clone(),
+                manifest.bindings.clone(),
+            ),
+        };
+
+        let kv_namespaces = kv_namespaces
+            .unwrap_or_default()
+            .into_iter()
+            .map(|ns| {
+                if ns.id.is_none() {
+                    errors.push(format!(
+                        "The KV namespace with binding \"{}\" has no id",
+                        ns.binding
+                    ));
+                }
+                ResolvedKvNamespace {
+                    binding: ns.binding,
+                    id: ns.id,
+                    preview_id: ns.preview_id,
+                }
+            })
+            .collect();
+
+        let mut resolved = ResolvedTarget {
+            environment: environment_name.map(String::from),
+            name: manifest.worker_name(environment_name),
+            account_id,
+            kv_namespaces,
+            durable_objects: durable_objects
+                .and_then(|durable_objects| durable_objects.classes)
+                .unwrap_or_default(),
+            vars: vars.unwrap_or_default(),
+            text_blobs: manifest.text_blobs.clone().unwrap_or_default(),
+            wasm_modules: manifest.wasm_modules.clone().unwrap_or_default(),
+            bindings: bindings.unwrap_or_default(),
+            secrets: Vec::new(),
+            errors,
+        };
+        resolved.check_binding_names();
+        Ok(resolved)
+    }
+
+    /// Lists the names of the secrets of the worker. Failing to list them is added to
+    /// `errors` like any other problem.
+    pub fn with_secrets(mut self, user: &GlobalUser) -> Self {
+        let account_id = match &self.account_id {
+            Some(account_id) => account_id.clone(),
+            None => {
+                self.errors
+                    .push("Listing secrets requires the account_id to be set".to_string());
+                return self;
+            }
+        };
+        let secrets = http::cf_v4_client(user).and_then(|client| {
+            client
+                .request(&ListSecrets {
+                    account_identifier: &account_id,
+                    script_name: &self.name,
+                })
+                .map_err(|e| http::api_error(e, None))
+        });
+
+        match secrets {
+            Ok(success) => {
+                let mut secrets: Vec<String> = success.result.into_iter().map(|s| s.name).collect();
+                secrets.sort();
+                for secret in &secrets {
+                    if self.binding_names().any(|name| name == secret) {
+                        self.errors.push(format!(
+                            "The secret \"{}\" has the same name as a binding",
+                            secret
+                        ));
+                    }
+                }
+                self.secrets = secrets;
+            }
+            Err(e) => self.errors.push(format!(
+                "Could not list the secrets of {}: {}",
+                self.name, e
+            )),
+        }
+        self
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn binding_names(&self) -> impl Iterator<Item = &String> {
+        self.kv_namespaces
+            .iter()
+            .map(|ns| &ns.binding)
+            .chain(self.durable_objects.iter().map(|class| &class.binding))
+            .chain(self.vars.keys())
+            .chain(self.text_blobs.keys())
+            .chain(self.wasm_modules.keys())
+            .chain(self.bindings.iter().map(|binding| &binding.name))
+    }
+
+    // Every binding, whatever its kind, needs a name of its own
+    fn check_binding_names(&mut self) {
+        let mut seen = HashMap::new();
+        for name in self.binding_names() {
+            *seen.entry(name.as_str()).or_insert(0) += 1;
+        }
+        let mut duplicates: Vec<String> = seen
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(name, _)| name.to_string())
+            .collect();
+        duplicates.sort_unstable();
+        for name in duplicates {
+            self.errors
+                .push(format!("More than one binding is named \"{}\"", name));
+        }
+    }
+}
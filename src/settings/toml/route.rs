@@ -1,3 +1,4 @@
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use cloudflare::endpoints::workers::WorkersRoute;
@@ -21,11 +22,58 @@ impl From<&WorkersRoute> for Route {
     }
 }
 
+/// An entry of `routes`: a pattern published to the `zone_id` of the configuration, or a
+/// table of a pattern and the zone it's published to, like
+/// `[[routes]] pattern = "example.org/*" zone_id = "..."`
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum RouteEntry {
+    Pattern(String),
+    Table {
+        pattern: String,
+        zone_id: Option<String>,
+    },
+}
+
+impl RouteEntry {
+    pub fn pattern(&self) -> &str {
+        match self {
+            RouteEntry::Pattern(pattern) | RouteEntry::Table { pattern, .. } => pattern,
+        }
+    }
+
+    /// The zone of the route, when it isn't the one of the configuration
+    pub fn zone_id(&self) -> Option<&str> {
+        match self {
+            RouteEntry::Table {
+                zone_id: Some(zone_id),
+                ..
+            } if !zone_id.is_empty() => Some(zone_id),
+            _ => None,
+        }
+    }
+
+    /// The pattern and the zone of the route, for expanding the environment variables in
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        let (pattern, zone_id) = match self {
+            RouteEntry::Pattern(pattern) => (pattern, None),
+            RouteEntry::Table { pattern, zone_id } => (pattern, zone_id.as_mut()),
+        };
+        std::iter::once(pattern).chain(zone_id)
+    }
+}
+
+impl From<&str> for RouteEntry {
+    fn from(pattern: &str) -> Self {
+        RouteEntry::Pattern(pattern.to_string())
+    }
+}
+
 #[derive(Debug)]
 pub struct RouteConfig {
     pub workers_dev: Option<bool>,
     pub route: Option<String>,
-    pub routes: Option<Vec<String>>,
+    pub routes: Option<Vec<RouteEntry>>,
     pub zone_id: Option<String>,
     pub account_id: LazyAccountId,
 }
@@ -46,6 +94,81 @@ impl RouteConfig {
     }
 
     pub fn is_zoned(&self) -> bool {
-        self.has_routes_defined() && self.zone_id.is_some()
+        self.has_routes_defined() && (self.zone_id.is_some() || self.unzoned_patterns().is_empty())
     }
+
+    /// The patterns of the routes that don't name a zone of their own
+    pub fn unzoned_patterns(&self) -> Vec<&str> {
+        self.route
+            .iter()
+            .map(String::as_str)
+            .chain(
+                self.routes
+                    .iter()
+                    .flatten()
+                    .filter(|route| route.zone_id().is_none())
+                    .map(RouteEntry::pattern),
+            )
+            .collect()
+    }
+}
+
+/// Checks `pattern` the way the API does when creating a route, so a malformed one fails
+/// before anything is published. Patterns are a hostname and an optional path, without a
+/// scheme, port or query, and a `*` may only start the hostname or end the path.
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    let invalid = |reason: &str| anyhow::anyhow!("The route {} is invalid: {}", pattern, reason);
+    if pattern.contains("://") {
+        return Err(invalid(
+            "routes don't include a scheme, remove the http:// or https://",
+        ));
+    }
+    if pattern.chars().any(char::is_whitespace) {
+        return Err(invalid("it contains whitespace"));
+    }
+    if pattern.contains('?') || pattern.contains('#') {
+        return Err(invalid(
+            "routes match paths, without a query string or fragment",
+        ));
+    }
+
+    let (host, path) = match pattern.find('/') {
+        Some(i) => pattern.split_at(i),
+        None => (pattern, ""),
+    };
+    if host.contains(':') {
+        return Err(invalid("routes can't include a port"));
+    }
+    let host = host.strip_prefix('*').unwrap_or(host);
+    if host.trim_start_matches('.').is_empty() {
+        return Err(invalid("it has no hostname"));
+    }
+    if host.contains('*') {
+        return Err(invalid(
+            "a wildcard can only be at the start of the hostname, like *.example.com",
+        ));
+    }
+    let path = path.strip_suffix('*').unwrap_or(path);
+    if path.contains('*') {
+        return Err(invalid(
+            "a wildcard can only be at the end of the path, like example.com/api/*",
+        ));
+    }
+    Ok(())
+}
+
+/// The hostname a route pattern such as `*.example.com/api/*` matches, without its wildcard
+pub fn hostname(pattern: &str) -> String {
+    let pattern = pattern.splitn(2, "://").last().unwrap_or(pattern);
+    let host = pattern.split('/').next().unwrap_or_default();
+    host.trim_start_matches('*')
+        .trim_start_matches('.')
+        .to_ascii_lowercase()
+}
+
+/// Whether the hostname of `pattern` is within the zone named `zone_name`
+pub fn is_in_zone(pattern: &str, zone_name: &str) -> bool {
+    let host = hostname(pattern);
+    let zone_name = zone_name.to_ascii_lowercase();
+    host == zone_name || host.ends_with(&format!(".{}", zone_name))
 }
@@ -6,8 +6,11 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use crate::commands::generate::run_generate;
+use crate::sites::AssetManifest;
 
 const SITE_ENTRY_POINT: &str = "workers-site";
+const DEFAULT_INDEX_DOCUMENT: &str = "index.html";
+const NOT_FOUND_PAGE: &str = "404.html";
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
@@ -17,6 +20,61 @@ pub struct Site {
     pub entry_point: Option<PathBuf>,
     pub include: Option<Vec<String>>,
     pub exclude: Option<Vec<String>>,
+    /// Upload hidden files and folders as well; only `.well-known` is uploaded by default
+    #[serde(rename = "include-hidden")]
+    pub include_hidden: Option<bool>,
+    pub ttl: Option<Vec<SiteTtl>>,
+    pub cache_control: Option<Vec<SiteCacheControl>>,
+    /// Also upload gzipped variants of text assets, under their path and key with a `.gz`
+    /// suffix, for the worker to serve to clients accepting gzip
+    pub compress: Option<bool>,
+    /// Also upload the SHA-256 of every asset, signed with `WRANGLER_INTEGRITY_SECRET` if
+    /// it's set, for `wrangler verify-site` to check the published site against
+    pub integrity: Option<bool>,
+    pub not_found_handling: Option<NotFoundHandling>,
+    /// The document served for a directory, `index.html` by default
+    pub index_document: Option<String>,
+}
+
+/// What a site serves for paths that aren't in its bucket
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum NotFoundHandling {
+    /// The `404.html` of the bucket, with a 404 status
+    #[serde(rename = "404-page")]
+    NotFoundPage,
+    /// The index document, so the router of a single-page app can handle the path
+    #[serde(rename = "single-page-app")]
+    SinglePageApp,
+}
+
+/// How the worker of a site serves what it has no asset for. Publishing passes these to
+/// the worker as JSON in the `__STATIC_CONTENT_OPTIONS` binding, for it to hand to the
+/// asset handler alongside `__STATIC_CONTENT_MANIFEST`.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentOptions<'a> {
+    pub index_document: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_found_document: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_found_status: Option<u16>,
+}
+
+/// Makes the assets matching `pattern` expire from Workers KV `seconds` after each upload.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteTtl {
+    pub pattern: String,
+    pub seconds: u64,
+}
+
+/// Stores `value` as the Cache-Control hint in the KV metadata of the assets matching
+/// `pattern`, for the worker serving the site to send. Assets get it when they are uploaded.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteCacheControl {
+    pub pattern: String,
+    pub value: String,
 }
 
 impl Site {
@@ -39,6 +97,43 @@ impl Site {
         ))
     }
 
+    pub fn index_document(&self) -> &str {
+        self.index_document
+            .as_deref()
+            .unwrap_or(DEFAULT_INDEX_DOCUMENT)
+    }
+
+    /// The options for the worker of the site, if any were configured. Fails when the
+    /// documents they point at aren't among the assets of the site.
+    pub fn content_options(
+        &self,
+        asset_manifest: &AssetManifest,
+    ) -> Result<Option<ContentOptions>> {
+        if self.not_found_handling.is_none() && self.index_document.is_none() {
+            return Ok(None);
+        }
+        let (not_found_document, not_found_status) = match self.not_found_handling {
+            Some(NotFoundHandling::NotFoundPage) => (Some(NOT_FOUND_PAGE), Some(404)),
+            Some(NotFoundHandling::SinglePageApp) => (Some(self.index_document()), Some(200)),
+            None => (None, None),
+        };
+
+        for document in std::iter::once(self.index_document()).chain(not_found_document) {
+            if !asset_manifest.contains_key(document) {
+                anyhow::bail!(
+                    "{} isn't in your site's bucket {}",
+                    document,
+                    self.bucket.display()
+                )
+            }
+        }
+        Ok(Some(ContentOptions {
+            index_document: self.index_document(),
+            not_found_document,
+            not_found_status,
+        }))
+    }
+
     pub fn scaffold_worker(&self) -> Result<()> {
         let entry_point = &self.entry_point()?;
         let template = "https://github.com/cloudflare/worker-sites-init";
@@ -63,6 +158,13 @@ impl Default for Site {
             entry_point: Some(PathBuf::from(SITE_ENTRY_POINT)),
             include: None,
             exclude: None,
+            include_hidden: None,
+            ttl: None,
+            cache_control: None,
+            compress: None,
+            integrity: None,
+            not_found_handling: None,
+            index_document: None,
         }
     }
 }
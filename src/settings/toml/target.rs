@@ -1,10 +1,12 @@
+use super::custom_binding::CustomBinding;
 use super::durable_objects::DurableObjects;
 use super::kv_namespace::KvNamespace;
+use super::limits::Limits;
 pub(crate) use super::manifest::LazyAccountId;
 use super::site::Site;
 use super::target_type::TargetType;
 use super::UsageModel;
-use super::{builder::Builder, migrations::Migrations};
+use super::{builder::Builder, builder::ModuleRule, migrations::Migrations};
 
 use std::collections::HashMap;
 use std::env;
@@ -21,11 +23,14 @@ pub struct Target {
     pub target_type: TargetType,
     pub webpack_config: Option<String>,
     pub build: Option<Builder>,
+    pub rules: Vec<ModuleRule>,
     pub site: Option<Site>,
     pub vars: Option<HashMap<String, String>>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
     pub usage_model: Option<UsageModel>,
+    pub limits: Option<Limits>,
     pub wasm_modules: Option<HashMap<String, PathBuf>>,
+    pub bindings: Option<Vec<CustomBinding>>,
     pub compatibility_date: Option<String>,
     pub compatibility_flags: Vec<String>,
 }
@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 pub enum TargetType {
     JavaScript,
     Rust,
+    TypeScript,
     Webpack,
 }
 
@@ -23,6 +24,7 @@ impl fmt::Display for TargetType {
         let printable = match *self {
             TargetType::JavaScript => "javascript",
             TargetType::Rust => "rust",
+            TargetType::TypeScript => "typescript",
             TargetType::Webpack => "webpack",
         };
         write!(f, "{}", printable)
@@ -36,6 +38,7 @@ impl FromStr for TargetType {
         match s {
             "javascript" => Ok(TargetType::JavaScript),
             "rust" => Ok(TargetType::Rust),
+            "typescript" => Ok(TargetType::TypeScript),
             "webpack" => Ok(TargetType::Webpack),
             _ => Err(anyhow!("{} is not a valid wrangler build type!", s)),
         }
@@ -135,10 +135,16 @@ fn it_can_get_a_single_route_zoned_get_deployments_workers_dev_false() {
         pattern: PATTERN.to_string(),
         id: None,
     }];
-    let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
-        routes: expected_routes,
-    })];
+    let expected_deployments = vec![
+        DeployTarget::Zoned(ZonedTarget {
+            zone_id: ZONE_ID.to_string(),
+            routes: expected_routes,
+        }),
+        DeployTarget::DisableZoneless(ZonelessTarget {
+            account_id: Some(ACCOUNT_ID.to_string()).into(),
+            script_name: script_name.to_string(),
+        }),
+    ];
     let environment = None;
     let actual_deployments = manifest.get_deployments(environment).unwrap();
 
@@ -270,6 +276,19 @@ fn it_errors_on_single_route_get_deployments_missing_zone_id() {
     assert!(manifest.get_deployments(environment).is_err());
 }
 
+#[test]
+fn it_errors_on_single_route_get_deployments_invalid_pattern() {
+    let script_name = "single_route_invalid_pattern";
+
+    let test_toml =
+        WranglerToml::zoned_single_route(script_name, ZONE_ID, "https://hostname.tld/*");
+    let toml_string = toml::to_string(&test_toml).unwrap();
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    let error = manifest.get_deployments(None).unwrap_err();
+    assert!(error.to_string().contains("don't include a scheme"));
+}
+
 #[test]
 fn it_can_single_route_get_deployments_empty_route() {
     let script_name = "single_route_empty_route";
@@ -328,6 +347,51 @@ fn it_can_get_a_multi_route_zoned_get_deployments() {
     assert_eq!(actual_deployments, expected_deployments);
 }
 
+#[test]
+fn it_can_get_deployments_to_routes_in_several_zones() {
+    let script_name = "multi_zone";
+    let toml_string = format!(
+        r#"
+name = "{}"
+type = "webpack"
+zone_id = "{}"
+
+[[routes]]
+pattern = "{}"
+
+[[routes]]
+pattern = "example.org/*"
+zone_id = "otherzoneid"
+
+[[routes]]
+pattern = "blog.hostname.tld/*"
+"#,
+        script_name, ZONE_ID, PATTERN
+    );
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    let route = |pattern: &str| Route {
+        script: Some(script_name.to_string()),
+        pattern: pattern.to_string(),
+        id: None,
+    };
+    let expected_deployments = vec![
+        DeployTarget::Zoned(ZonedTarget {
+            zone_id: ZONE_ID.to_string(),
+            routes: vec![route(PATTERN), route("blog.hostname.tld/*")],
+        }),
+        DeployTarget::Zoned(ZonedTarget {
+            zone_id: "otherzoneid".to_string(),
+            routes: vec![route("example.org/*")],
+        }),
+    ];
+
+    let environment = None;
+    let actual_deployments = manifest.get_deployments(environment).unwrap();
+
+    assert_eq!(actual_deployments, expected_deployments);
+}
+
 #[test]
 fn it_can_get_a_multi_route_zoned_get_deployments_workers_dev_false() {
     let script_name = "multi_route_zoned_workers_dev_false";
@@ -348,10 +412,16 @@ fn it_can_get_a_multi_route_zoned_get_deployments_workers_dev_false() {
             id: None,
         })
         .collect();
-    let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
-        routes: expected_routes,
-    })];
+    let expected_deployments = vec![
+        DeployTarget::Zoned(ZonedTarget {
+            zone_id: ZONE_ID.to_string(),
+            routes: expected_routes,
+        }),
+        DeployTarget::DisableZoneless(ZonelessTarget {
+            account_id: Some(ACCOUNT_ID.to_string()).into(),
+            script_name: script_name.to_string(),
+        }),
+    ];
 
     let environment = None;
     let actual_deployments = manifest.get_deployments(environment).unwrap();
@@ -380,10 +450,16 @@ fn it_can_get_multi_route_with_route() {
             id: None,
         })
         .collect();
-    let expected_deployments = vec![DeployTarget::Zoned(ZonedTarget {
-        zone_id: ZONE_ID.to_string(),
-        routes: expected_routes,
-    })];
+    let expected_deployments = vec![
+        DeployTarget::Zoned(ZonedTarget {
+            zone_id: ZONE_ID.to_string(),
+            routes: expected_routes,
+        }),
+        DeployTarget::DisableZoneless(ZonelessTarget {
+            account_id: Some(ACCOUNT_ID.to_string()).into(),
+            script_name: script_name.to_string(),
+        }),
+    ];
 
     let environment = None;
     let actual_deployments = manifest.get_deployments(environment).unwrap();
@@ -764,6 +840,46 @@ fn when_top_level_empty_zoned_multi_route_env() {
     assert_eq!(actual_deployments, expected_deployments);
 }
 
+#[test]
+fn when_top_level_empty_zoned_multi_route_env_workers_dev_true() {
+    // when the environment enables workers.dev next to its routes, publish to both
+    let patterns = [PATTERN, "blog.hostname.tld/*"];
+    let mut env_config = EnvConfig::zoned_multi_route(ZONE_ID, patterns.to_vec());
+    env_config.workers_dev = Some(true);
+
+    let script_name = "top_level_empty_zoned_multi_route_env_workers_dev_true";
+    let mut test_toml = WranglerToml::with_env(script_name, env_config);
+    test_toml.account_id = Some(ACCOUNT_ID);
+    let toml_string = toml::to_string(&test_toml).unwrap();
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    let actual_deployments = manifest.get_deployments(Some(TEST_ENV_NAME)).unwrap();
+
+    let expected_name = manifest.worker_name(Some(TEST_ENV_NAME));
+
+    let expected_routes = patterns
+        .iter()
+        .map(|p| Route {
+            script: Some(expected_name.to_string()),
+            pattern: (*p).to_string(),
+            id: None,
+        })
+        .collect();
+
+    let expected_deployments = vec![
+        DeployTarget::Zoned(ZonedTarget {
+            zone_id: ZONE_ID.to_string(),
+            routes: expected_routes,
+        }),
+        DeployTarget::Zoneless(ZonelessTarget {
+            account_id: Some(ACCOUNT_ID.to_string()).into(),
+            script_name: expected_name,
+        }),
+    ];
+
+    assert_eq!(actual_deployments, expected_deployments);
+}
+
 #[test]
 fn when_top_level_zoneless_env_empty() {
     let script_name = "top_level_zoneless_env_empty";
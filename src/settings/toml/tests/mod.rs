@@ -1,6 +1,7 @@
 mod deployments;
 
 use super::*;
+use crate::upload::form::ModuleType;
 
 use std::env;
 use std::fs;
@@ -126,6 +127,612 @@ fn it_uses_env_name_when_provided() {
     assert_eq!(manifest.worker_name(Some(TEST_ENV_NAME)), custom_env_name);
 }
 
+#[test]
+fn it_reads_the_service_worker_entry_point_of_a_custom_build() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+
+        [build]
+        command = "npm run build"
+
+        [build.upload]
+        format = "service-worker"
+        main = "dist/worker.js"
+        "#,
+    )
+    .unwrap();
+
+    let target = manifest.get_target(None, false).unwrap();
+    let build = target.build.unwrap();
+    assert_eq!(build.command.as_deref(), Some("npm run build"));
+    assert_eq!(
+        build.upload,
+        UploadFormat::ServiceWorker {
+            main: Some(PathBuf::from("dist/worker.js"))
+        }
+    );
+}
+
+#[test]
+fn it_orders_workspace_members_by_dependency() {
+    let manifest = Manifest::from_str(
+        r#"
+        type = "javascript"
+        account_id = "account"
+
+        [[workers]]
+        name = "frontend"
+        path = "workers/frontend"
+        workers_dev = true
+        depends_on = ["api"]
+
+        [[workers]]
+        name = "api"
+        path = "workers/api"
+        depends_on = ["auth"]
+
+        [[workers]]
+        name = "auth"
+        type = "webpack"
+        "#,
+    )
+    .unwrap();
+
+    let members = manifest.workspace_members(&[]).unwrap();
+    let names: Vec<&str> = members.iter().map(|(m, _)| m.name.as_str()).collect();
+    assert_eq!(names, vec!["auth", "api", "frontend"]);
+
+    let (frontend, path) = &members[2];
+    assert_eq!(path, &PathBuf::from("workers/frontend"));
+    assert_eq!(frontend.target_type, TargetType::JavaScript);
+    assert_eq!(frontend.workers_dev, Some(true));
+    assert_eq!(members[0].0.target_type, TargetType::Webpack);
+
+    let only = manifest
+        .workspace_members(&["frontend".to_string(), "auth".to_string()])
+        .unwrap();
+    let names: Vec<&str> = only.iter().map(|(m, _)| m.name.as_str()).collect();
+    assert_eq!(names, vec!["auth", "frontend"]);
+
+    assert!(manifest.workspace_members(&["nope".to_string()]).is_err());
+}
+
+#[test]
+fn it_uploads_typescript_as_modules_by_default() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+        name = "worker"
+        type = "typescript"
+        workers_dev = true
+        "#,
+    )
+    .unwrap();
+
+    let target = manifest.get_target(None, false).unwrap();
+    match target.build.map(|build| build.upload) {
+        Some(UploadFormat::Modules { main, dir, .. }) => {
+            assert_eq!(main, "index.js");
+            assert!(dir.ends_with("dist"));
+        }
+        upload => panic!("unexpected upload format {:?}", upload),
+    }
+}
+
+#[test]
+fn it_adds_the_top_level_rules_to_module_uploads() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+        name = "worker"
+        type = "typescript"
+        workers_dev = true
+
+        [[rules]]
+        type = "Text"
+        globs = ["**/*.sql", "**/*.html"]
+        "#,
+    )
+    .unwrap();
+
+    let target = manifest.get_target(None, false).unwrap();
+    assert_eq!(target.rules.len(), 1);
+    match target.build.map(|build| build.upload) {
+        Some(UploadFormat::Modules {
+            rules: Some(rules), ..
+        }) => {
+            assert_eq!(rules, target.rules);
+            assert_eq!(rules[0].module_type, ModuleType::Text);
+        }
+        upload => panic!("unexpected upload format {:?}", upload),
+    }
+}
+
+#[test]
+fn it_checks_the_format_against_the_build() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+        name = "worker"
+        type = "typescript"
+        format = "service-worker"
+        workers_dev = true
+        "#,
+    )
+    .unwrap();
+    assert!(manifest.get_target(None, false).is_err());
+
+    let manifest: Manifest = toml::from_str(
+        r#"
+        name = "worker"
+        type = "typescript"
+        format = "modules"
+        workers_dev = true
+        "#,
+    )
+    .unwrap();
+    assert!(manifest.get_target(None, false).is_ok());
+
+    let manifest: Manifest = toml::from_str(
+        r#"
+        name = "worker"
+        type = "webpack"
+        format = "modules"
+        workers_dev = true
+        "#,
+    )
+    .unwrap();
+    assert!(manifest.get_target(None, false).is_err());
+}
+
+#[test]
+fn it_finds_the_workspace_members_a_change_touches() {
+    let manifest = Manifest::from_str(
+        r#"
+        type = "javascript"
+
+        [[workers]]
+        name = "frontend"
+        path = "./workers/frontend"
+
+        [[workers]]
+        name = "api"
+        path = "workers/api"
+        shared = ["lib/auth"]
+
+        [[workers]]
+        name = "admin"
+        path = "workers/admin"
+        shared = ["lib/auth", "lib/ui"]
+        "#,
+    )
+    .unwrap();
+    let changed = |files: &[&str]| {
+        let files: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+        manifest.changed_workspace_members(&files)
+    };
+
+    assert_eq!(changed(&["workers/frontend/index.js"]), vec!["frontend"]);
+    assert_eq!(changed(&["lib/auth/token.js"]), vec!["api", "admin"]);
+    assert_eq!(
+        changed(&["lib/ui/button.js", "workers/api/wrangler.toml"]),
+        vec!["api", "admin"]
+    );
+    assert!(changed(&["README.md", "workers/frontend-old/index.js"]).is_empty());
+}
+
+#[test]
+fn it_rejects_circular_workspace_dependencies() {
+    let manifest = Manifest::from_str(
+        r#"
+        type = "javascript"
+
+        [[workers]]
+        name = "a"
+        depends_on = ["b"]
+
+        [[workers]]
+        name = "b"
+        depends_on = ["a"]
+        "#,
+    )
+    .unwrap();
+
+    assert!(manifest.workspace_members(&[]).is_err());
+}
+
+#[test]
+fn it_finds_the_config_of_a_parent_directory() {
+    let project = tempfile::tempdir().unwrap();
+    let nested = project.path().join("src").join("handlers");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(
+        project.path().join("wrangler.toml"),
+        "type = \"javascript\"",
+    )
+    .unwrap();
+
+    let file_name = Path::new("wrangler.toml");
+    assert_eq!(
+        Manifest::find(&nested, file_name),
+        Some(project.path().join("wrangler.toml"))
+    );
+    assert_eq!(Manifest::find(&nested, Path::new("other.toml")), None);
+}
+
+#[test]
+fn it_expands_environment_variables_in_account_and_route_settings() {
+    let project = tempfile::tempdir().unwrap();
+    let config_path = project.path().join("wrangler.toml");
+    fs::write(
+        &config_path,
+        r#"
+name = "interpolated"
+type = "javascript"
+account_id = "${WRANGLER_TEST_ACCOUNT_ID}"
+zone_id = "${WRANGLER_TEST_ZONE_ID}"
+route = "${WRANGLER_TEST_HOST}/*"
+
+[env.staging]
+kv_namespaces = [{ binding = "KV", id = "${WRANGLER_TEST_STAGING_KV_ID}" }]
+"#,
+    )
+    .unwrap();
+    env::set_var("WRANGLER_TEST_ACCOUNT_ID", "abc123");
+    env::set_var("WRANGLER_TEST_ZONE_ID", "zone456");
+
+    let manifest = Manifest::new(&config_path).unwrap();
+    assert_eq!(manifest.zone_id.as_deref(), Some("zone456"));
+    assert_eq!(manifest.get_account_id(None).unwrap(), "abc123");
+
+    let error = manifest.get_deployments(None).unwrap_err();
+    assert!(error
+        .to_string()
+        .ends_with("environment variables that are not set: WRANGLER_TEST_HOST"));
+    let error = manifest.get_target(Some("staging"), false).unwrap_err();
+    assert!(error.to_string().ends_with(
+        "environment variables that are not set: WRANGLER_TEST_HOST, WRANGLER_TEST_STAGING_KV_ID"
+    ));
+}
+
+#[test]
+fn it_passes_custom_bindings_through_to_the_metadata() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+
+[[bindings]]
+type = "service"
+name = "AUTH"
+service = "auth-worker"
+
+[env.staging]
+bindings = [{ type = "service", name = "AUTH", service = "auth-worker-staging" }]
+"#,
+    )
+    .unwrap();
+
+    let target = manifest.get_target(None, false).unwrap();
+    let bindings = target.bindings.unwrap();
+    assert_eq!(
+        serde_json::to_value(bindings[0].binding()).unwrap(),
+        serde_json::json!({ "type": "service", "name": "AUTH", "service": "auth-worker" })
+    );
+
+    let target = manifest.get_target(Some("staging"), false).unwrap();
+    assert_eq!(
+        target.bindings.unwrap()[0].fields["service"],
+        "auth-worker-staging"
+    );
+}
+
+#[test]
+fn it_rejects_custom_bindings_named_like_another_binding() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+vars = { AUTH = "token" }
+bindings = [{ type = "service", name = "AUTH", service = "auth-worker" }]
+
+[env.staging]
+bindings = [
+    { type = "service", name = "AUTH", service = "auth-worker-staging" },
+    { type = "service", name = "AUTH", service = "auth-worker-canary" },
+]
+
+[env.production]
+vars = { MODE = "production" }
+bindings = [{ type = "service", name = "AUTH", service = "auth-worker" }]
+"#,
+    )
+    .unwrap();
+
+    let error = manifest.get_target(None, false).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("More than one binding is named \"AUTH\""));
+    assert!(manifest.get_target(Some("staging"), false).is_err());
+    assert!(manifest.get_target(Some("production"), false).is_ok());
+}
+
+#[test]
+fn it_encodes_not_found_handling_of_sites() {
+    let site: Site = toml::from_str(
+        r#"
+bucket = "./public"
+not_found_handling = "single-page-app"
+index_document = "app.html"
+"#,
+    )
+    .unwrap();
+    let mut asset_manifest = crate::sites::AssetManifest::new();
+    asset_manifest.insert("app.html".to_string(), "app.1a2b3c.html".to_string());
+
+    let options = site.content_options(&asset_manifest).unwrap().unwrap();
+    assert_eq!(
+        serde_json::to_value(options).unwrap(),
+        serde_json::json!({
+            "indexDocument": "app.html",
+            "notFoundDocument": "app.html",
+            "notFoundStatus": 200
+        })
+    );
+
+    let site: Site =
+        toml::from_str("bucket = \"./public\"\nnot_found_handling = \"404-page\"").unwrap();
+    assert!(site.content_options(&asset_manifest).is_err());
+    assert!(Site::default()
+        .content_options(&asset_manifest)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn it_validates_route_patterns() {
+    for pattern in &[
+        "example.com/*",
+        "*.example.com/api/*",
+        "*example.com",
+        "api.example.com/v1",
+    ] {
+        assert!(route::validate_pattern(pattern).is_ok(), "{}", pattern);
+    }
+    for pattern in &[
+        "https://example.com/*",
+        "example.com:8080/*",
+        "example.com/*/users",
+        "api.*.example.com/*",
+        "example.com/search?q=*",
+        "*/*",
+    ] {
+        assert!(route::validate_pattern(pattern).is_err(), "{}", pattern);
+    }
+}
+
+#[test]
+fn it_checks_routes_are_in_a_zone() {
+    assert_eq!(route::hostname("*.Example.com/api/*"), "example.com");
+    assert!(route::is_in_zone("example.com/*", "example.com"));
+    assert!(route::is_in_zone("*.api.example.com/*", "example.com"));
+    assert!(!route::is_in_zone("notexample.com/*", "example.com"));
+    assert!(!route::is_in_zone("example.org/*", "example.com"));
+}
+
+#[test]
+fn it_resolves_the_bindings_of_an_environment() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+vars = { MODE = "production" }
+kv_namespaces = [{ binding = "CACHE", id = "top", preview_id = "top-preview" }]
+
+[env.staging]
+vars = { MODE = "staging", CACHE = "off" }
+kv_namespaces = [{ binding = "CACHE", preview_id = "staging-preview" }]
+"#,
+    )
+    .unwrap();
+
+    let resolved = manifest.resolve(None).unwrap();
+    assert!(resolved.is_valid(), "{:?}", resolved.errors);
+    assert_eq!(resolved.name, "worker");
+    assert_eq!(resolved.account_id.as_deref(), Some("1234"));
+    assert_eq!(
+        resolved.kv_namespaces,
+        vec![ResolvedKvNamespace {
+            binding: "CACHE".to_string(),
+            id: Some("top".to_string()),
+            preview_id: Some("top-preview".to_string()),
+        }]
+    );
+
+    let resolved = manifest.resolve(Some("staging")).unwrap();
+    assert_eq!(resolved.name, "worker-staging");
+    assert_eq!(resolved.account_id.as_deref(), Some("1234"));
+    assert_eq!(resolved.vars["MODE"], "staging");
+    assert_eq!(resolved.kv_namespaces[0].id, None);
+    assert_eq!(
+        resolved.errors,
+        vec![
+            "The KV namespace with binding \"CACHE\" has no id",
+            "More than one binding is named \"CACHE\"",
+        ]
+    );
+
+    assert!(manifest.resolve(Some("production")).is_err());
+}
+
+#[test]
+fn it_freezes_environments_with_protection() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+
+[protection]
+frozen = true
+reason = "Release window"
+
+[env.staging]
+protection = { frozen = false }
+
+[env.production]
+route = "example.com/*"
+"#,
+    )
+    .unwrap();
+
+    let freeze = manifest.get_freeze(None).unwrap().unwrap();
+    assert_eq!(freeze.reason.as_deref(), Some("Release window"));
+    assert!(freeze.source.starts_with("[protection]"));
+    let freeze = manifest.get_freeze(Some("production")).unwrap().unwrap();
+    assert_eq!(freeze.reason.as_deref(), Some("Release window"));
+    assert_eq!(manifest.get_freeze(Some("staging")).unwrap(), None);
+}
+
+#[test]
+fn it_protects_the_listed_environments() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+protected_environments = ["production"]
+
+[env.staging]
+
+[env.production]
+"#,
+    )
+    .unwrap();
+
+    assert!(manifest.is_protected(Some("production")));
+    assert!(!manifest.is_protected(Some("staging")));
+    assert!(!manifest.is_protected(None));
+}
+
+#[test]
+fn it_protects_the_top_level() {
+    let config = |protected: &str, environments: &str| -> Manifest {
+        toml::from_str(&format!(
+            "name = \"worker\"\ntype = \"javascript\"\nprotected_environments = [{:?}]\n{}",
+            protected, environments
+        ))
+        .unwrap()
+    };
+
+    assert!(config("", "").is_protected(None));
+    assert!(config("production", "[env.staging]\n").is_protected(None));
+    // with an [env.production], "production" is that environment
+    let manifest = config("production", "[env.production]\n");
+    assert!(!manifest.is_protected(None));
+    assert!(manifest.is_protected(Some("production")));
+}
+
+#[test]
+fn it_takes_the_hooks_of_the_environment_over_the_top_level() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+
+[hooks]
+pre_publish = ["npm test"]
+
+[env.staging]
+
+[env.production]
+hooks = { post_publish = ["./notify-slack.sh"] }
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(manifest.get_hooks(None).unwrap().pre_publish, ["npm test"]);
+    assert_eq!(
+        manifest.get_hooks(Some("staging")).unwrap().pre_publish,
+        ["npm test"]
+    );
+    let production = manifest.get_hooks(Some("production")).unwrap();
+    assert!(production.pre_publish.is_empty());
+    assert_eq!(production.post_publish, ["./notify-slack.sh"]);
+}
+
+#[test]
+fn it_configures_publishing_from_a_dirty_tree() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+
+[env.staging]
+
+[env.production]
+protection = { dirty_tree = "refuse" }
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(manifest.get_dirty_tree(None).unwrap(), DirtyTree::Warn);
+    assert_eq!(
+        manifest.get_dirty_tree(Some("staging")).unwrap(),
+        DirtyTree::Warn
+    );
+    assert_eq!(
+        manifest.get_dirty_tree(Some("production")).unwrap(),
+        DirtyTree::Refuse
+    );
+}
+
+#[test]
+fn it_inherits_limits_and_the_usage_model() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+name = "worker"
+type = "javascript"
+account_id = "1234"
+workers_dev = true
+usage_model = "unbound"
+
+[limits]
+cpu_ms = 50
+
+[env.staging]
+
+[env.production]
+limits = { cpu_ms = 500 }
+
+[env.broken]
+limits = { cpu_ms = 0 }
+"#,
+    )
+    .unwrap();
+
+    let target = manifest.get_target(Some("staging"), false).unwrap();
+    assert_eq!(target.usage_model, Some(UsageModel::Unbound));
+    assert_eq!(target.limits, Some(Limits { cpu_ms: Some(50) }));
+    let target = manifest.get_target(Some("production"), false).unwrap();
+    assert_eq!(
+        serde_json::to_value(target.limits).unwrap(),
+        serde_json::json!({ "cpu_ms": 500 })
+    );
+    assert!(manifest.get_target(Some("broken"), false).is_err());
+}
+
 fn base_fixture_path() -> PathBuf {
     let current_dir = env::current_dir().unwrap();
 
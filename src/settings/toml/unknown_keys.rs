@@ -0,0 +1,172 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::Manifest;
+use crate::terminal::message::{Message, StdOut};
+
+// Keys that are read before the configuration is, rather than being settings of it
+const PREPROCESSED: &[&str] = &["extends"];
+
+/// Fails on the keys of the configuration file at `config_path` that no setting takes, like
+/// a misspelled setting, which would otherwise be ignored without a word. `base` is the
+/// configuration the file extends, if any.
+pub(super) fn check(config_path: &Path, base: Option<&str>) -> Result<()> {
+    let contents = fs::read_to_string(config_path)?;
+    let mut config: toml::Value = toml::from_str(&contents)?;
+    if let Some(base) = base {
+        let mut merged: toml::Value = toml::from_str(base)?;
+        merge(&mut merged, config);
+        config = merged;
+    }
+
+    let place = |key: &str| match line_of(&contents, key) {
+        Some(line) => format!("`{}` on line {}", key, line),
+        None => format!("`{}` in the configuration it extends", key),
+    };
+    let (ignored_keys, unknown): (Vec<String>, Vec<String>) = unknown_keys(config)
+        .into_iter()
+        .partition(|key| ignored(key).is_some());
+    for key in &ignored_keys {
+        StdOut::warn(&format!(
+            "{} is ignored, {}. Run `wrangler config migrate` to remove it.",
+            place(key),
+            ignored(key).unwrap_or_default()
+        ));
+    }
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    let keys: Vec<String> = unknown.iter().map(|key| place(key)).collect();
+    anyhow::bail!(
+        "{} has settings wrangler doesn't know: {}. Check them for typos.",
+        config_path.display(),
+        keys.join(", ")
+    )
+}
+
+/// The dotted paths of the keys of `config` that no setting takes
+fn unknown_keys(config: toml::Value) -> Vec<String> {
+    let mut unknown = Vec::new();
+    let manifest: Result<Manifest, _> = serde_ignored::deserialize(config, |path| {
+        let path = path.to_string();
+        if !PREPROCESSED.contains(&path.as_str()) {
+            unknown.push(path);
+        }
+    });
+    // a configuration that doesn't deserialize on its own, like one with numbers where the
+    // settings take strings, gets the errors of loading it instead
+    match manifest {
+        Ok(_) => unknown,
+        Err(_) => Vec::new(),
+    }
+}
+
+// Why the key at the dotted `path` is ignored, for the keys older versions of wrangler took
+// that no setting takes now, but that `wrangler config migrate` removes
+fn ignored(path: &str) -> Option<&'static str> {
+    match path.split('.').collect::<Vec<_>>().as_slice() {
+        ["env", _, "type"] => Some("environments take the type of the top level"),
+        _ => None,
+    }
+}
+
+// The settings of `local` replace those of `base`, table by table, like they do when the
+// configuration is loaded
+fn merge(base: &mut toml::Value, local: toml::Value) {
+    match (base, local) {
+        (toml::Value::Table(base), toml::Value::Table(local)) => {
+            for (key, value) in local {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, local) => *base = local,
+    }
+}
+
+/// The line of `contents` the key at the dotted `path` is on, like `env.staging.kv_namespaces.0.id`.
+/// A key in an inline table is on the line of the table.
+fn line_of(contents: &str, path: &str) -> Option<usize> {
+    let target: Vec<&str> = path
+        .split('.')
+        .filter(|part| part.parse::<usize>().is_err())
+        .collect();
+    let mut table: Vec<String> = Vec::new();
+    let mut containing = None;
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            let header = line.split('#').next().unwrap_or(line).trim();
+            table = split_key(header.trim_start_matches('[').trim_end_matches(']'));
+            continue;
+        }
+        let key = match line.split_once('=') {
+            Some((key, _)) if !line.starts_with('#') => key,
+            _ => continue,
+        };
+        let mut full = table.clone();
+        full.extend(split_key(key));
+        if full == target {
+            return Some(i + 1);
+        }
+        if containing.is_none() && full.len() < target.len() && target.starts_with(&full) {
+            containing = Some(i + 1);
+        }
+    }
+    containing
+}
+
+fn split_key(key: &str) -> Vec<String> {
+    key.split('.')
+        .map(|part| part.trim().trim_matches('"').to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_unknown_keys_and_their_lines() {
+        let config = r#"name = "worker"
+type = "javascript"
+workers-dev = true
+
+[env.staging]
+kv_namespaces = [{ binding = "CACHE", id = "1234", preview = "5678" }]
+
+[env.staging.triggers]
+crons = ["* * * * *"]
+"#;
+        let unknown = unknown_keys(toml::from_str(config).unwrap());
+        assert_eq!(
+            unknown,
+            vec!["env.staging.kv_namespaces.0.preview", "workers-dev"]
+        );
+        assert_eq!(line_of(config, &unknown[0]), Some(6));
+        assert_eq!(line_of(config, &unknown[1]), Some(3));
+        assert_eq!(line_of(config, "env.staging.triggers.crons"), Some(9));
+    }
+
+    #[test]
+    fn it_ignores_the_type_of_environments() {
+        let config =
+            "name = \"worker\"\ntype = \"webpack\"\n\n[env.production]\ntype = \"webpack\"\n";
+        let unknown = unknown_keys(toml::from_str(config).unwrap());
+        assert_eq!(unknown, vec!["env.production.type"]);
+        assert!(ignored(&unknown[0]).is_some());
+        assert!(ignored("env.production.kv_namespaces.0.type").is_none());
+    }
+
+    #[test]
+    fn it_allows_the_keys_of_extended_configurations() {
+        let config = "extends = \"kv://1234/wrangler-base.toml\"\nname = \"worker\"\ntype = \"javascript\"\n";
+        assert!(unknown_keys(toml::from_str(config).unwrap()).is_empty());
+    }
+}
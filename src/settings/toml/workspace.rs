@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_with::rust::string_empty_as_none;
+
+use crate::settings::toml::builder::{Builder, ModuleRule, ScriptFormat};
+use crate::settings::toml::custom_binding::CustomBinding;
+use crate::settings::toml::durable_objects::DurableObjects;
+use crate::settings::toml::environment::Environment;
+use crate::settings::toml::hooks::Hooks;
+use crate::settings::toml::kv_namespace::ConfigKvNamespace;
+use crate::settings::toml::limits::Limits;
+use crate::settings::toml::manifest::Manifest;
+use crate::settings::toml::route::RouteEntry;
+use crate::settings::toml::site::Site;
+use crate::settings::toml::target_type::TargetType;
+use crate::settings::toml::triggers::Triggers;
+use crate::settings::toml::UsageModel;
+
+/// A `[[workers]]` entry of a workspace configuration file: one script living in its own
+/// directory, deployed alongside the other entries.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct WorkspaceWorker {
+    pub name: String,
+    /// Directory the worker is built from, relative to the configuration file
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    #[serde(rename = "type")]
+    pub target_type: Option<TargetType>,
+    pub format: Option<ScriptFormat>,
+    #[serde(default, with = "string_empty_as_none")]
+    pub account_id: Option<String>,
+    pub workers_dev: Option<bool>,
+    #[serde(default, with = "string_empty_as_none")]
+    pub route: Option<String>,
+    pub routes: Option<Vec<RouteEntry>>,
+    #[serde(default, with = "string_empty_as_none")]
+    pub zone_id: Option<String>,
+    pub webpack_config: Option<String>,
+    pub build: Option<Builder>,
+    pub rules: Option<Vec<ModuleRule>>,
+    pub triggers: Option<Triggers>,
+    #[serde(default, with = "string_empty_as_none")]
+    pub usage_model: Option<UsageModel>,
+    pub limits: Option<Limits>,
+    pub compatibility_date: Option<String>,
+    pub compatibility_flags: Option<Vec<String>>,
+    pub durable_objects: Option<DurableObjects>,
+    pub env: Option<HashMap<String, Environment>>,
+    #[serde(alias = "kv-namespaces")]
+    pub kv_namespaces: Option<Vec<ConfigKvNamespace>>,
+    pub site: Option<Site>,
+    pub vars: Option<HashMap<String, String>>,
+    pub secrets: Option<Vec<String>>,
+    pub tail_consumers: Option<Vec<String>>,
+    pub queue_consumers: Option<Vec<String>>,
+    pub text_blobs: Option<HashMap<String, PathBuf>>,
+    pub wasm_modules: Option<HashMap<String, PathBuf>>,
+    pub bindings: Option<Vec<CustomBinding>>,
+    pub hooks: Option<Hooks>,
+    /// Workers that have to be published before this one, e.g. because it binds to them
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Directories outside `path` the worker is built from too, like libraries shared by
+    /// several workers, relative to the configuration file
+    #[serde(default)]
+    pub shared: Vec<PathBuf>,
+}
+
+impl WorkspaceWorker {
+    /// Builds the manifest of this worker on its own. Settings shared by a whole account
+    /// (`type`, `account_id`, `zone_id`, `usage_model`, `limits` and compatibility settings) fall back
+    /// to the top level of the workspace, and so do `format` and `[[rules]]`. Its `[protection]`,
+    /// `protected_environments` and `profile` apply to every worker. So do its `[hooks]`, unless
+    /// the worker has hooks of its own.
+    pub fn manifest(&self, workspace: &Manifest) -> Manifest {
+        Manifest {
+            name: self.name.clone(),
+            target_type: self
+                .target_type
+                .clone()
+                .unwrap_or_else(|| workspace.target_type.clone()),
+            format: self.format.or(workspace.format),
+            account_id: match &self.account_id {
+                Some(id) => Some(id.clone()).into(),
+                None => workspace.account_id.clone(),
+            },
+            profile: workspace.profile.clone(),
+            workers_dev: self.workers_dev,
+            route: self.route.clone(),
+            routes: self.routes.clone(),
+            zone_id: self.zone_id.clone().or_else(|| workspace.zone_id.clone()),
+            webpack_config: self.webpack_config.clone(),
+            build: self.build.clone(),
+            rules: self.rules.clone().or_else(|| workspace.rules.clone()),
+            private: None,
+            protection: workspace.protection.clone(),
+            protected_environments: workspace.protected_environments.clone(),
+            hooks: self.hooks.clone().or_else(|| workspace.hooks.clone()),
+            dev: None,
+            triggers: self.triggers.clone(),
+            migrations: None,
+            usage_model: self.usage_model.or(workspace.usage_model),
+            limits: self.limits.or(workspace.limits),
+            compatibility_date: self
+                .compatibility_date
+                .clone()
+                .or_else(|| workspace.compatibility_date.clone()),
+            compatibility_flags: self
+                .compatibility_flags
+                .clone()
+                .unwrap_or_else(|| workspace.compatibility_flags.clone()),
+            durable_objects: self.durable_objects.clone(),
+            env: self.env.clone(),
+            kv_namespaces: self.kv_namespaces.clone(),
+            site: self.site.clone(),
+            vars: self.vars.clone(),
+            secrets: self.secrets.clone(),
+            tail_consumers: self.tail_consumers.clone(),
+            queue_consumers: self.queue_consumers.clone(),
+            text_blobs: self.text_blobs.clone(),
+            wasm_modules: self.wasm_modules.clone(),
+            bindings: self.bindings.clone(),
+            workers: None,
+        }
+    }
+}
+
+/// The names of the `workers` built from one of the `changed` files, which are relative to
+/// the configuration file: a file changed a worker if it's in its `path` or one of its
+/// `shared` directories.
+pub fn changed_workers(workers: &[WorkspaceWorker], changed: &[PathBuf]) -> Vec<String> {
+    let changed: Vec<PathBuf> = changed.iter().map(|file| normalize(file)).collect();
+    workers
+        .iter()
+        .filter(|worker| {
+            let path = worker.path.clone().unwrap_or_default();
+            std::iter::once(&path)
+                .chain(&worker.shared)
+                .map(|dir| normalize(dir))
+                .any(|dir| changed.iter().any(|file| file.starts_with(&dir)))
+        })
+        .map(|worker| worker.name.clone())
+        .collect()
+}
+
+// Without `.` components, so `./workers/api` and `workers/api` are the same directory
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// Orders `workers` so that each comes after everything it `depends_on`. If `only` is not
+/// empty, just those workers are returned, still in dependency order.
+pub fn publish_order<'a>(
+    workers: &'a [WorkspaceWorker],
+    only: &[String],
+) -> Result<Vec<&'a WorkspaceWorker>> {
+    let by_name: HashMap<&str, &WorkspaceWorker> =
+        workers.iter().map(|w| (w.name.as_str(), w)).collect();
+
+    for name in only {
+        if !by_name.contains_key(name.as_str()) {
+            anyhow::bail!("There is no [[workers]] entry named \"{}\"", name)
+        }
+    }
+
+    let mut ordered = Vec::new();
+    let mut done = HashSet::new();
+    let mut visiting = Vec::new();
+    for worker in workers {
+        visit(worker, &by_name, &mut done, &mut visiting, &mut ordered)?;
+    }
+
+    if !only.is_empty() {
+        ordered.retain(|w| only.contains(&w.name));
+    }
+    Ok(ordered)
+}
+
+fn visit<'a>(
+    worker: &'a WorkspaceWorker,
+    by_name: &HashMap<&str, &'a WorkspaceWorker>,
+    done: &mut HashSet<&'a str>,
+    visiting: &mut Vec<&'a str>,
+    ordered: &mut Vec<&'a WorkspaceWorker>,
+) -> Result<()> {
+    if done.contains(worker.name.as_str()) {
+        return Ok(());
+    }
+    if visiting.contains(&worker.name.as_str()) {
+        visiting.push(&worker.name);
+        anyhow::bail!(
+            "[[workers]] depend on each other: {}",
+            visiting.join(" -> ")
+        )
+    }
+
+    visiting.push(&worker.name);
+    for dependency in &worker.depends_on {
+        match by_name.get(dependency.as_str()) {
+            Some(dependency) => visit(dependency, by_name, done, visiting, ordered)?,
+            None => anyhow::bail!(
+                "\"{}\" depends on \"{}\", but there is no [[workers]] entry with that name",
+                worker.name,
+                dependency
+            ),
+        }
+    }
+    visiting.pop();
+
+    done.insert(&worker.name);
+    ordered.push(worker);
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use std::collections::{HashMap, HashSet};
+
+use super::manifest::AssetManifest;
+use super::report::value_size;
+use crate::kv::bulk::KeyValuePair;
+use crate::terminal::message::{Message, StdOut};
+use crate::upload::form::format_bytes;
+
+/// How the files of a bucket differ from what its namespace has, by the path the site
+/// serves them under
+#[derive(Debug, Default, PartialEq)]
+pub struct BucketDiff {
+    /// Files the namespace has no version of, with their size in bytes
+    pub added: Vec<(String, u64)>,
+    /// Files the namespace has another version of, with the size of the new one
+    pub updated: Vec<(String, u64)>,
+    /// Files the namespace has that the bucket doesn't anymore. A sync leaves them in the
+    /// namespace for the version of the worker that's live, but stops serving them.
+    pub deleted: Vec<String>,
+}
+
+impl BucketDiff {
+    /// Compares every asset of the bucket and its manifest with the keys of the namespace
+    pub fn new(
+        assets: &[KeyValuePair],
+        manifest: &AssetManifest,
+        remote_keys: &HashSet<String>,
+    ) -> BucketDiff {
+        let paths: HashMap<&str, &str> = manifest
+            .iter()
+            .map(|(path, key)| (key.as_str(), path.as_str()))
+            .collect();
+        let remote_paths: HashSet<String> = remote_keys
+            .iter()
+            .filter_map(|key| unhashed_path(key))
+            .collect();
+
+        let mut diff = BucketDiff::default();
+        for asset in assets {
+            if remote_keys.contains(&asset.key) {
+                continue;
+            }
+            let path = paths
+                .get(asset.key.as_str())
+                .map_or_else(|| asset.key.clone(), |path| path.to_string());
+            if remote_paths.contains(&path) {
+                diff.updated.push((path, value_size(asset)));
+            } else {
+                diff.added.push((path, value_size(asset)));
+            }
+        }
+        diff.deleted = remote_paths
+            .into_iter()
+            .filter(|path| !manifest.contains_key(path))
+            .collect();
+
+        diff.added.sort();
+        diff.updated.sort();
+        diff.deleted.sort();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
+
+    /// Bytes a sync would upload
+    pub fn upload_size(&self) -> u64 {
+        self.added
+            .iter()
+            .chain(&self.updated)
+            .map(|(_, size)| size)
+            .sum()
+    }
+
+    pub fn print(&self) {
+        if self.is_empty() {
+            StdOut::info("The namespace is up to date with the bucket");
+            return;
+        }
+        for (path, size) in &self.added {
+            StdOut::message(&format!("+ {:>10}  {}", format_bytes(*size), path));
+        }
+        for (path, size) in &self.updated {
+            StdOut::message(&format!("~ {:>10}  {}", format_bytes(*size), path));
+        }
+        for path in &self.deleted {
+            StdOut::message(&format!("- {:>10}  {}", "", path));
+        }
+        StdOut::info(&format!(
+            "{} added, {} updated and {} deleted, {} to upload",
+            self.added.len(),
+            self.updated.len(),
+            self.deleted.len(),
+            format_bytes(self.upload_size())
+        ));
+    }
+}
+
+// The path of an asset from its key, which has the digest of the file before its extension,
+// e.g. sitemap.xml for sitemap.ec717eb213.xml. Keys without one aren't assets.
+fn unhashed_path(key: &str) -> Option<String> {
+    let name_start = key.rfind('/').map_or(0, |slash| slash + 1);
+    let name = &key[name_start..];
+    let digest_start = name
+        .match_indices('.')
+        .map(|(dot, _)| dot)
+        .filter(|dot| {
+            let digest = &name[dot + 1..];
+            let digest = digest.split('.').next().unwrap_or_default();
+            digest.len() == super::KEY_DIGEST_LENGTH
+                && digest.chars().all(|c| c.is_ascii_hexdigit())
+        })
+        .last()?;
+    let digest_end = digest_start + 1 + super::KEY_DIGEST_LENGTH;
+    Some(format!(
+        "{}{}{}",
+        &key[..name_start],
+        &name[..digest_start],
+        &name[digest_end..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sites::INTEGRITY_KEY;
+
+    fn asset(key: &str, size: usize) -> KeyValuePair {
+        KeyValuePair {
+            key: key.to_string(),
+            value: base64::encode(&vec![0u8; size]),
+            expiration: None,
+            expiration_ttl: None,
+            base64: Some(true),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn it_finds_the_path_of_a_key() {
+        assert_eq!(
+            unhashed_path("sitemap.ec717eb213.xml").as_deref(),
+            Some("sitemap.xml")
+        );
+        assert_eq!(
+            unhashed_path("js/app.0123456789.ec717eb213.js.gz").as_deref(),
+            Some("js/app.0123456789.js.gz")
+        );
+        assert_eq!(
+            unhashed_path("LICENSE.ec717eb213").as_deref(),
+            Some("LICENSE")
+        );
+        assert_eq!(unhashed_path(INTEGRITY_KEY), None);
+    }
+
+    #[test]
+    fn it_tells_added_updated_and_deleted_files_apart() {
+        let assets = vec![
+            asset("index.aaaaaaaaaa.html", 10),
+            asset("app.bbbbbbbbbb.js", 2048),
+            asset("new.cccccccccc.css", 5),
+        ];
+        let manifest: AssetManifest = vec![
+            ("index.html", "index.aaaaaaaaaa.html"),
+            ("app.js", "app.bbbbbbbbbb.js"),
+            ("new.css", "new.cccccccccc.css"),
+        ]
+        .into_iter()
+        .map(|(path, key)| (path.to_string(), key.to_string()))
+        .collect();
+        let remote_keys: HashSet<String> = vec![
+            "index.aaaaaaaaaa.html",
+            "app.0000000000.js",
+            "old.dddddddddd.png",
+            INTEGRITY_KEY,
+        ]
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+        let diff = BucketDiff::new(&assets, &manifest, &remote_keys);
+        assert_eq!(diff.added, vec![("new.css".to_string(), 5)]);
+        assert_eq!(diff.updated, vec![("app.js".to_string(), 2048)]);
+        assert_eq!(diff.deleted, vec!["old.png".to_string()]);
+        assert_eq!(diff.upload_size(), 2053);
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::AssetManifest;
+use crate::kv::bulk::KeyValuePair;
+
+/// The key of the integrity manifest in the Workers Sites namespace
+pub const INTEGRITY_KEY: &str = "__wrangler_integrity.json";
+/// The environment variable holding the secret integrity manifests are signed with
+pub const INTEGRITY_SECRET_VAR: &str = "WRANGLER_INTEGRITY_SECRET";
+
+/// The SHA-256 of every asset of a site, which `[site] integrity` uploads next to the
+/// assets so `wrangler verify-site` can check what the site serves.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityManifest {
+    /// Hex encoded SHA-256 of each asset, by the path it's served at
+    pub assets: BTreeMap<String, String>,
+    /// Hex encoded HMAC-SHA256 of `assets`, when a secret was set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl IntegrityManifest {
+    /// Hashes the assets in `asset_manifest`, reading them from `bucket`
+    pub fn new(
+        bucket: &Path,
+        asset_manifest: &AssetManifest,
+        secret: Option<&str>,
+    ) -> Result<Self> {
+        let mut assets = BTreeMap::new();
+        for path in asset_manifest.keys() {
+            let file = bucket.join(path);
+            // the gzipped variants of assets have no file of their own
+            if file.is_file() {
+                assets.insert(path.clone(), sha256(&fs::read(file)?));
+            }
+        }
+        let signature = match secret {
+            Some(secret) => Some(sign(secret, &assets)?),
+            None => None,
+        };
+        Ok(IntegrityManifest { assets, signature })
+    }
+
+    /// Fails unless the manifest was signed with `secret`
+    pub fn verify(&self, secret: &str) -> Result<()> {
+        let signature = match &self.signature {
+            Some(signature) => hex::decode(signature).unwrap_or_default(),
+            None => anyhow::bail!("The integrity manifest isn't signed"),
+        };
+        // compared in constant time, so the signature can't be guessed byte by byte
+        let message = serde_json::to_vec(&self.assets)?;
+        match hmac::verify(&key(secret), &message, &signature) {
+            Ok(()) => Ok(()),
+            Err(_) => anyhow::bail!("The signature of the integrity manifest doesn't match"),
+        }
+    }
+
+    pub fn to_pair(&self) -> Result<KeyValuePair> {
+        Ok(KeyValuePair {
+            key: INTEGRITY_KEY.to_string(),
+            value: serde_json::to_string(self)?,
+            ..Default::default()
+        })
+    }
+}
+
+pub fn sha256(contents: &[u8]) -> String {
+    hex::encode(Sha256::digest(contents))
+}
+
+fn sign(secret: &str, assets: &BTreeMap<String, String>) -> Result<String> {
+    let message = serde_json::to_vec(assets)?;
+    Ok(hex::encode(hmac::sign(&key(secret), &message)))
+}
+
+fn key(secret: &str) -> hmac::Key {
+    hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_signs_and_verifies_manifests() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        fs::write(tmpdir.path().join("index.html"), "hello").unwrap();
+        let mut asset_manifest = AssetManifest::new();
+        asset_manifest.insert(
+            "index.html".to_string(),
+            "index.5d41402abc.html".to_string(),
+        );
+        asset_manifest.insert(
+            "index.html.gz".to_string(),
+            "index.5d41402abc.html.gz".to_string(),
+        );
+
+        let manifest =
+            IntegrityManifest::new(tmpdir.path(), &asset_manifest, Some("secret")).unwrap();
+        assert_eq!(
+            manifest.assets.get("index.html").unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(manifest.assets.len(), 1);
+        assert!(manifest.verify("secret").is_ok());
+        assert!(manifest.verify("another secret").is_err());
+
+        let tampered = IntegrityManifest {
+            assets: manifest.assets.clone(),
+            signature: Some("not hex".to_string()),
+        };
+        assert!(tampered.verify("secret").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::kv::bulk::KeyValuePair;
+
+/// The keys a site upload into a namespace wrote so far, kept in `.wrangler/sites/` until the
+/// upload completes. Listing the keys of a namespace can lag behind writes, so an upload
+/// that was interrupted and run again also skips the keys of the journal it finds in the
+/// namespace when looking them up one by one.
+pub struct UploadJournal {
+    path: PathBuf,
+}
+
+impl UploadJournal {
+    pub fn open(namespace_id: &str) -> Self {
+        Self::open_in(&Path::new(".wrangler").join("sites"), namespace_id)
+    }
+
+    fn open_in(dir: &Path, namespace_id: &str) -> Self {
+        UploadJournal {
+            path: dir.join(format!("{}.uploaded", namespace_id)),
+        }
+    }
+
+    /// The keys an interrupted upload already wrote
+    pub fn keys(&self) -> Result<HashSet<String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(contents
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashSet::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Notes that `pairs` were written. Keys with a TTL are left out, they're uploaded again
+    /// either way.
+    pub fn record(&self, pairs: &[KeyValuePair]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut lines = String::new();
+        for pair in pairs.iter().filter(|pair| pair.expiration_ttl.is_none()) {
+            lines.push_str(&pair.key);
+            lines.push('\n');
+        }
+        file.write_all(lines.as_bytes())?;
+        Ok(())
+    }
+
+    /// Forgets the keys once the upload completed
+    pub fn finish(self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(key: &str) -> KeyValuePair {
+        KeyValuePair {
+            key: key.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn it_remembers_written_keys_until_finished() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let journal = UploadJournal::open_in(tmpdir.path(), "abc");
+        assert!(journal.keys().unwrap().is_empty());
+
+        journal
+            .record(&[pair("a.1234.html"), pair("b.5678.css")])
+            .unwrap();
+        journal.record(&[pair("c.9abc.js")]).unwrap();
+        let reopened = UploadJournal::open_in(tmpdir.path(), "abc");
+        assert_eq!(reopened.keys().unwrap().len(), 3);
+        assert!(UploadJournal::open_in(tmpdir.path(), "other")
+            .keys()
+            .unwrap()
+            .is_empty());
+
+        reopened.finish().unwrap();
+        assert!(journal.keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_leaves_keys_with_a_ttl_out() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let journal = UploadJournal::open_in(tmpdir.path(), "abc");
+        let expiring = KeyValuePair {
+            expiration_ttl: Some(300),
+            ..pair("index.1234.html")
+        };
+        journal.record(&[expiring, pair("b.5678.css")]).unwrap();
+
+        let keys = journal.keys().unwrap();
+        assert_eq!(keys.len(), 1);
+        assert!(keys.contains("b.5678.css"));
+    }
+}
@@ -1,42 +1,106 @@
 extern crate base64;
 
+mod diff;
+mod integrity;
+mod journal;
 mod manifest;
+mod report;
 mod sync;
 
+pub use diff::BucketDiff;
+pub use integrity::{sha256, IntegrityManifest, INTEGRITY_KEY, INTEGRITY_SECRET_VAR};
+pub use journal::UploadJournal;
 pub use manifest::AssetManifest;
-pub use sync::sync;
+pub use report::UploadReport;
+pub use sync::{remote_keys, sync};
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::ffi::OsString;
 use std::fmt;
 use std::fs;
 use std::hash::Hasher;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use globset::{Glob, GlobMatcher};
 use ignore::overrides::{Override, OverrideBuilder};
 use ignore::{Walk, WalkBuilder};
-use indicatif::{ProgressBar, ProgressStyle};
+use mime_guess::mime;
+use rayon::prelude::*;
+use serde::Serialize;
 use twox_hash::XxHash64;
 
-use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
-
+use crate::error::WranglerError;
+use crate::kv::bulk::KeyValuePair;
 use crate::kv::namespace::{upsert, UpsertedNamespace};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::{KvNamespace, Target};
 use crate::terminal::message::{Message, StdErr};
+use crate::terminal::ui;
+use crate::upload::form::format_bytes;
 pub const KEY_MAX_SIZE: usize = 512;
 // Oddly enough, metadata.len() returns a u64, not usize.
 pub const VALUE_MAX_SIZE: u64 = 25 * 1024 * 1024;
+/// Appended to the path and key of the gzipped variant of an asset, which `[site] compress`
+/// uploads next to the asset
+pub const GZIP_SUFFIX: &str = ".gz";
+
+/// What the worker serving a site needs to know about an asset, stored as its KV metadata
+/// so the worker doesn't need a table of extensions to set its headers
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetMetadata {
+    pub content_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+    // text compresses well, images and the like are compressed already
+    #[serde(skip)]
+    compressible: bool,
+}
 
-// Updates given Target with kv_namespace binding for a static site assets KV namespace.
-pub fn add_namespace(user: &GlobalUser, target: &mut Target, preview: bool) -> Result<KvNamespace> {
-    let title = if preview {
+impl AssetMetadata {
+    fn new(url_safe_path: &str, cache_controls: &[(GlobMatcher, String)]) -> Self {
+        let content_type = mime_guess::from_path(url_safe_path).first_or_octet_stream();
+        let is_text = content_type.type_() == mime::TEXT
+            || content_type.subtype() == mime::JAVASCRIPT
+            || content_type.subtype() == mime::JSON;
+        let is_svg = content_type.subtype() == mime::SVG;
+        let content_type = if is_text && content_type.get_param(mime::CHARSET).is_none() {
+            format!("{}; charset=utf-8", content_type)
+        } else {
+            content_type.to_string()
+        };
+
+        AssetMetadata {
+            content_type,
+            cache_control: cache_controls
+                .iter()
+                .find(|(glob, _)| glob.is_match(url_safe_path))
+                .map(|(_, value)| value.clone()),
+            content_encoding: None,
+            compressible: is_text || is_svg,
+        }
+    }
+}
+
+// The title of the static site assets KV namespace of the given Target.
+pub fn namespace_title(target: &Target, preview: bool) -> String {
+    if preview {
         format!("__{}-{}", target.name, "workers_sites_assets_preview")
     } else {
         format!("__{}-{}", target.name, "workers_sites_assets")
-    };
+    }
+}
+
+// Updates given Target with kv_namespace binding for a static site assets KV namespace.
+pub fn add_namespace(user: &GlobalUser, target: &mut Target, preview: bool) -> Result<KvNamespace> {
+    let title = namespace_title(target, preview);
 
     let site_namespace = match upsert(target, user, title)? {
         UpsertedNamespace::Created(namespace) => {
@@ -85,47 +149,103 @@ pub fn directory_keys_values(
             let mut upload_vec: Vec<KeyValuePair> = Vec::new();
             let mut asset_manifest = AssetManifest::new();
             let mut file_list: Vec<String> = Vec::new();
-            let dir_walker = get_dir_iterator(target, directory)?;
-            let spinner_style =
-                ProgressStyle::default_spinner().template("{spinner}   Preparing {msg}...");
-            let spinner = ProgressBar::new_spinner().with_style(spinner_style);
-
+            let mut seen_paths: HashMap<String, PathBuf> = HashMap::new();
+            // on Windows, walk from an extended-length root so that deeply nested assets
+            // don't run into MAX_PATH; everywhere else this is the directory as given.
+            let walk_root = extended_length_path(directory)?;
+            let dir_walker = get_dir_iterator(target, &walk_root)?;
+            let ttls = expiration_ttls(target)?;
+            let cache_controls = cache_controls(target)?;
+            let compress = target
+                .site
+                .as_ref()
+                .and_then(|site| site.compress)
+                .unwrap_or_default();
+            let spinner = ui::spinner("Preparing files...");
+
+            let mut paths = Vec::new();
             for entry in dir_walker {
-                spinner.tick();
-                let entry = entry.unwrap();
-                let path = entry.path();
-                if path.is_file() {
-                    spinner.set_message(&format!("{}", path.display()));
-
-                    file_list.push(path.to_str().unwrap().to_string());
-                    validate_file_size(path)?;
-
-                    let value = std::fs::read(path)?;
-
-                    // Need to base64 encode value
-                    let b64_value = base64::encode(&value);
-
-                    let (url_safe_path, key) =
-                        generate_path_and_key(path, directory, Some(b64_value.clone()))?;
+                let entry = entry?;
+                if entry.path().is_file() {
+                    paths.push(entry.into_path());
+                }
+            }
+            spinner.set_message("Checking file sizes...");
+            validate_sizes(&paths, directory, &walk_root)?;
+
+            // reading, encoding and hashing every file is what takes time for large sites,
+            // so files are prepared concurrently and then collected in the order of the walk
+            let prepared = paths
+                .par_iter()
+                .map(|path| {
+                    spinner.tick();
+                    let asset =
+                        PreparedAsset::new(path, directory, &walk_root, &cache_controls, compress)?;
+                    spinner.set_message(&format!("Preparing {}...", asset.display_path.display()));
+                    Ok(asset)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            for asset in prepared {
+                let PreparedAsset {
+                    display_path,
+                    url_safe_path,
+                    key,
+                    b64_value,
+                    metadata,
+                    compressed,
+                } = asset;
+                match display_path.to_str() {
+                    Some(display_path) => file_list.push(display_path.to_string()),
+                    None => anyhow::bail!(
+                        "File `{}` has a name that isn't valid UTF-8. Rename it before uploading your site.",
+                        display_path.display()
+                    ),
+                }
 
-                    validate_key_size(&key)?;
+                if cfg!(windows) {
+                    validate_unique_path(&mut seen_paths, &url_safe_path, &display_path)?;
+                }
 
-                    // asset manifest should always contain all files
-                    asset_manifest.insert(url_safe_path, key.clone());
+                // asset manifest should always contain all files
+                asset_manifest.insert(url_safe_path.clone(), key.clone());
+
+                let expiration_ttl = ttls
+                    .iter()
+                    .find(|(glob, _)| glob.is_match(&url_safe_path))
+                    .map(|(_, seconds)| *seconds as i64);
+
+                let mut variants = vec![(key.clone(), b64_value, metadata.clone())];
+                if let Some(compressed) = compressed {
+                    let gzip_key = format!("{}{}", key, GZIP_SUFFIX);
+                    validate_key_size(&gzip_key)?;
+                    asset_manifest.insert(
+                        format!("{}{}", url_safe_path, GZIP_SUFFIX),
+                        gzip_key.clone(),
+                    );
+                    let metadata = AssetMetadata {
+                        content_encoding: Some("gzip".to_string()),
+                        ..metadata
+                    };
+                    variants.push((gzip_key, compressed, metadata));
+                }
 
-                    // skip uploading existing keys, if configured to do so
-                    if let Some(remote_keys) = exclude {
-                        if remote_keys.contains(&key) {
-                            continue;
-                        }
+                for (key, value, metadata) in variants {
+                    // skip uploading existing keys, if configured to do so. Keys with a TTL
+                    // are uploaded on every publish, or the site would 404 once they expire
+                    if expiration_ttl.is_none()
+                        && exclude.map_or(false, |remote_keys| remote_keys.contains(&key))
+                    {
+                        continue;
                     }
 
                     upload_vec.push(KeyValuePair {
-                        key: key.clone(),
-                        value: b64_value,
+                        key,
+                        value,
                         expiration: None,
-                        expiration_ttl: None,
+                        expiration_ttl,
                         base64: Some(true),
+                        metadata: Some(serde_json::to_value(metadata)?),
                     });
                 }
             }
@@ -139,24 +259,169 @@ pub fn directory_keys_values(
     }
 }
 
-// Ensure that all files in upload directory do not exceed the MAX_VALUE_SIZE (this ensures that
-// no partial uploads happen). I don't like this functionality (and the similar key length checking
-// logic in validate_key_size()) because it duplicates the size checking the API already does--but
-// doing a preemptive check like this (before calling the API) will prevent partial bucket uploads
-// from happening.
-fn validate_file_size(path: &Path) -> Result<()> {
-    let metadata = fs::metadata(path)?;
-    let file_len = metadata.len();
+// A file of the bucket read, encoded and hashed into the key it's uploaded under
+struct PreparedAsset {
+    // the path as the user wrote it, not with the `\\?\` prefix
+    display_path: PathBuf,
+    url_safe_path: String,
+    key: String,
+    b64_value: String,
+    metadata: AssetMetadata,
+    // the base64 of the gzipped file, when `[site] compress` is set and it compresses
+    compressed: Option<String>,
+}
+
+impl PreparedAsset {
+    fn new(
+        path: &Path,
+        directory: &Path,
+        walk_root: &Path,
+        cache_controls: &[(GlobMatcher, String)],
+        compress: bool,
+    ) -> Result<Self> {
+        let display_path = directory.join(path.strip_prefix(walk_root)?);
 
-    if file_len > VALUE_MAX_SIZE {
-        anyhow::bail!(
-            "File `{}` of {} bytes exceeds the maximum value size limit of {} bytes",
+        if cfg!(windows) {
+            validate_file_name(&display_path)?;
+        }
+
+        let value = std::fs::read(path)?;
+
+        // Need to base64 encode value
+        let b64_value = base64::encode(&value);
+
+        let (url_safe_path, key) = generate_path_and_key(path, walk_root, Some(b64_value.clone()))?;
+
+        let metadata = AssetMetadata::new(&url_safe_path, cache_controls);
+        let compressed = if compress && metadata.compressible {
+            gzip(&value)?.map(|compressed| base64::encode(&compressed))
+        } else {
+            None
+        };
+
+        Ok(PreparedAsset {
+            display_path,
+            url_safe_path,
+            key,
+            b64_value,
+            metadata,
+            compressed,
+        })
+    }
+}
+
+// Workers KV refuses expiration TTLs shorter than a minute
+const MIN_EXPIRATION_TTL: u64 = 60;
+
+// The [[site.ttl]] rules of the target, the first matching rule wins.
+fn expiration_ttls(target: &Target) -> Result<Vec<(GlobMatcher, u64)>> {
+    let rules = match target.site.as_ref().and_then(|site| site.ttl.as_ref()) {
+        Some(rules) => rules,
+        None => return Ok(Vec::new()),
+    };
+
+    rules
+        .iter()
+        .map(|rule| {
+            if rule.seconds < MIN_EXPIRATION_TTL {
+                anyhow::bail!(
+                    "The ttl for \"{}\" in [site] must be at least {} seconds",
+                    rule.pattern,
+                    MIN_EXPIRATION_TTL
+                )
+            }
+            let glob = Glob::new(&rule.pattern).map_err(|e| {
+                anyhow!("Invalid ttl pattern \"{}\" in [site]: {}", rule.pattern, e)
+            })?;
+            Ok((glob.compile_matcher(), rule.seconds))
+        })
+        .collect()
+}
+
+// The gzipped value, if that is any smaller
+fn gzip(value: &[u8]) -> Result<Option<Vec<u8>>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(value)?;
+    let compressed = encoder.finish()?;
+    if compressed.len() < value.len() {
+        Ok(Some(compressed))
+    } else {
+        Ok(None)
+    }
+}
+
+// The [[site.cache_control]] rules of the target, the first matching rule wins.
+fn cache_controls(target: &Target) -> Result<Vec<(GlobMatcher, String)>> {
+    let rules = match target
+        .site
+        .as_ref()
+        .and_then(|site| site.cache_control.as_ref())
+    {
+        Some(rules) => rules,
+        None => return Ok(Vec::new()),
+    };
+
+    rules
+        .iter()
+        .map(|rule| {
+            let glob = Glob::new(&rule.pattern).map_err(|e| {
+                anyhow!(
+                    "Invalid cache_control pattern \"{}\" in [site]: {}",
+                    rule.pattern,
+                    e
+                )
+            })?;
+            Ok((glob.compile_matcher(), rule.value.clone()))
+        })
+        .collect()
+}
+
+// Ensure that no file in the upload directory exceeds VALUE_MAX_SIZE and that no key exceeds
+// KEY_MAX_SIZE before anything is read or uploaded. The API does the same checks, but only
+// once the sync is partway through; checking up front reports every offender at once and
+// prevents partial bucket uploads.
+fn validate_sizes(paths: &[PathBuf], directory: &Path, walk_root: &Path) -> Result<()> {
+    let mut too_large = Vec::new();
+    let mut too_long = Vec::new();
+
+    for path in paths {
+        let display_path = directory.join(path.strip_prefix(walk_root)?);
+        let file_len = fs::metadata(path)?.len();
+        if file_len > VALUE_MAX_SIZE {
+            too_large.push((display_path.clone(), file_len));
+        }
+
+        // the digest is always the same length, so a placeholder gives the length of the key
+        let relative_path = path.strip_prefix(walk_root)?;
+        let key = generate_path_with_hash(relative_path, "0".repeat(KEY_DIGEST_LENGTH))?;
+        if key.len() > KEY_MAX_SIZE {
+            too_long.push((display_path, key.len()));
+        }
+    }
+
+    if too_large.is_empty() && too_long.is_empty() {
+        return Ok(());
+    }
+
+    let mut msg = String::from("Some files of your site can't be uploaded to Workers KV:");
+    for (path, file_len) in &too_large {
+        msg.push_str(&format!(
+            "\n {:>10}  {} (files can be at most {})",
+            format_bytes(*file_len),
             path.display(),
-            file_len,
-            VALUE_MAX_SIZE
-        );
+            format_bytes(VALUE_MAX_SIZE)
+        ));
     }
-    Ok(())
+    for (path, key_len) in &too_long {
+        msg.push_str(&format!(
+            "\n {:>10}  {} (its key of {} bytes exceeds the limit of {} bytes, move it to a shallower directory)",
+            "key",
+            path.display(),
+            key_len,
+            KEY_MAX_SIZE
+        ));
+    }
+    anyhow::bail!(WranglerError::Validation(msg))
 }
 
 fn validate_key_size(key: &str) -> Result<()> {
@@ -171,6 +436,108 @@ fn validate_key_size(key: &str) -> Result<()> {
     Ok(())
 }
 
+// Device names that Windows reserves in every directory, regardless of extension
+// (`aux.html` is just as unusable as `aux`).
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+fn validate_file_name(path: &Path) -> Result<()> {
+    let file_name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(file_name) => file_name,
+        None => return Ok(()),
+    };
+    // Windows ignores everything after the first dot and any trailing spaces when it
+    // decides whether a name refers to a device.
+    let device_name = file_name
+        .split('.')
+        .next()
+        .unwrap_or(file_name)
+        .trim_end()
+        .to_lowercase();
+
+    if WINDOWS_RESERVED_NAMES.contains(&device_name.as_str()) {
+        anyhow::bail!(
+            "File `{}` uses the reserved Windows device name `{}`. Rename it before uploading your site.",
+            path.display(),
+            device_name
+        );
+    }
+    Ok(())
+}
+
+// Two files that differ only by case can't coexist on a case-insensitive filesystem, so one of
+// them would silently replace the other in the asset manifest. Fail with both names instead.
+fn validate_unique_path(
+    seen: &mut HashMap<String, PathBuf>,
+    url_safe_path: &str,
+    path: &Path,
+) -> Result<()> {
+    if let Some(existing) = seen.insert(url_safe_path.to_lowercase(), path.to_path_buf()) {
+        anyhow::bail!(
+            "Files `{}` and `{}` differ only by case, so their keys would collide. Rename one of them before uploading your site.",
+            existing.display(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+// Returns `path` in its `\\?\`-prefixed form on Windows, so that no file under it runs into
+// MAX_PATH however short the directory itself is. On every other platform the path is
+// returned untouched.
+fn extended_length_path(path: &Path) -> Result<PathBuf> {
+    if !cfg!(windows) {
+        return Ok(path.to_path_buf());
+    }
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    Ok(to_verbatim_path(&absolute))
+}
+
+// Verbatim paths are passed to the filesystem as-is, so they must be absolute, use `\`
+// separators and contain no `.` or `..` components.
+fn to_verbatim_path(path: &Path) -> PathBuf {
+    use std::path::{Component, Prefix};
+
+    let mut verbatim = OsString::new();
+    let mut parts: Vec<&std::ffi::OsStr> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::Verbatim(_)
+                | Prefix::VerbatimUNC(..)
+                | Prefix::VerbatimDisk(_)
+                | Prefix::DeviceNS(_) => return path.to_path_buf(),
+                Prefix::UNC(server, share) => {
+                    verbatim.push(r"\\?\UNC\");
+                    verbatim.push(server);
+                    verbatim.push(r"\");
+                    verbatim.push(share);
+                }
+                Prefix::Disk(_) => {
+                    verbatim.push(r"\\?\");
+                    verbatim.push(prefix.as_os_str());
+                }
+            },
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => {
+                parts.pop();
+            }
+            Component::Normal(part) => parts.push(part),
+        }
+    }
+    for part in parts {
+        verbatim.push(r"\");
+        verbatim.push(part);
+    }
+    PathBuf::from(verbatim)
+}
+
 const REQUIRED_IGNORE_FILES: &[&str] = &[NODE_MODULES];
 const NODE_MODULES: &str = "node_modules";
 
@@ -211,10 +578,12 @@ fn build_ignore(target: &Target, directory: &Path) -> Result<Override> {
             // allow all files. This is required since without this the `.well-known`
             // override would act as a allowlist
             required_override.add("*")?;
-            // ignore hidden files and folders
-            required_override.add("!.*")?;
-            // but allow .well-known, this has precedence over hidden files since it's later
-            required_override.add(".well-known")?;
+            if !site.include_hidden.unwrap_or(false) {
+                // ignore hidden files and folders
+                required_override.add("!.*")?;
+                // but allow .well-known, this has precedence over hidden files since it's later
+                required_override.add(".well-known")?;
+            }
             // add this AFTER since the `*` override would have precedence over this,
             // making it useless
             required_ignore(&mut required_override)?;
@@ -249,14 +618,21 @@ fn generate_url_safe_path(path: &Path) -> Result<String> {
         path_with_forward_slash.push(component);
     }
 
-    // if we have a non-utf8 path here, it will fail, but that's not realistically going to happen
-    let path = path_with_forward_slash
-        .to_str()
-        .unwrap_or_else(|| panic!("found a non-UTF-8 path, {:?}", path_with_forward_slash));
+    // keys and URLs are strings, so there's no uploading a file whose name isn't UTF-8
+    let path = match path_with_forward_slash.to_str() {
+        Some(path) => path,
+        None => anyhow::bail!(
+            "File `{}` has a name that isn't valid UTF-8. Rename it before uploading your site.",
+            path.display()
+        ),
+    };
 
     Ok(path.to_string())
 }
 
+// How many characters of the content digest go into the key of an asset
+const KEY_DIGEST_LENGTH: usize = 10;
+
 // Adds the XXhash hash of the path's file contents to the url-safe path of a file to
 // generate a versioned key for the file and its contents. Returns the url-safe path prefix
 // for the key, as well as the key with hash appended.
@@ -278,7 +654,7 @@ pub fn generate_path_and_key(
         // the most important thing here is to detect changes
         // of a single file to invalidate the cache and
         // it's impossible to serve two different files with the same name
-        let digest = digest[0..10].to_string();
+        let digest = digest[0..KEY_DIGEST_LENGTH].to_string();
         generate_path_with_hash(relative_path, digest)?
     } else {
         url_safe_path.to_owned()
@@ -327,7 +703,7 @@ mod tests {
     use std::path::{Path, PathBuf};
     use tempfile::TempDir;
 
-    use crate::settings::toml::{Site, Target, TargetType};
+    use crate::settings::toml::{Site, SiteCacheControl, SiteTtl, Target, TargetType};
 
     fn make_target(site: Site) -> Target {
         Target {
@@ -340,10 +716,13 @@ mod tests {
             webpack_config: None,
             site: Some(site),
             build: None,
+            rules: Vec::new(),
             vars: None,
             text_blobs: None,
             usage_model: None,
+            limits: None,
             wasm_modules: None,
+            bindings: None,
             compatibility_date: None,
             compatibility_flags: Vec::new(),
         }
@@ -414,6 +793,137 @@ mod tests {
         assert_eq!(to_upload.len(), all_files.len() - exclude.len());
     }
 
+    #[test]
+    fn it_applies_the_first_matching_ttl() {
+        let (tmpdir, _) = tmpdir_with_default_files();
+        std::fs::File::create(tmpdir.join("index.html")).unwrap();
+
+        let mut site = Site::default();
+        site.ttl = Some(vec![
+            SiteTtl {
+                pattern: "*.html".to_string(),
+                seconds: 300,
+            },
+            SiteTtl {
+                pattern: "file_a.*".to_string(),
+                seconds: 86400,
+            },
+            SiteTtl {
+                pattern: "*".to_string(),
+                seconds: 3600,
+            },
+        ]);
+
+        let (to_upload, _, _) = directory_keys_values(&make_target(site), &tmpdir, None).unwrap();
+        let ttl_of = |prefix: &str| {
+            to_upload
+                .iter()
+                .find(|kv| kv.key.starts_with(prefix))
+                .unwrap()
+                .expiration_ttl
+        };
+        assert_eq!(ttl_of("index"), Some(300));
+        assert_eq!(ttl_of("file_a"), Some(86400));
+        assert_eq!(ttl_of("file_b"), Some(3600));
+    }
+
+    #[test]
+    fn it_uploads_assets_with_a_ttl_again_on_every_publish() {
+        let (tmpdir, _) = tmpdir_with_default_files();
+        std::fs::write(tmpdir.join("index.html"), "<p>hello</p>").unwrap();
+
+        let mut site = Site::default();
+        site.ttl = Some(vec![SiteTtl {
+            pattern: "*.html".to_string(),
+            seconds: 300,
+        }]);
+        let target = make_target(site);
+
+        // the first publish uploads every file, and the next one finds them all in KV
+        let (first, _, _) = directory_keys_values(&target, &tmpdir, None).unwrap();
+        let remote_keys: HashSet<String> = first.iter().map(|kv| kv.key.clone()).collect();
+        let (republished, _, _) =
+            directory_keys_values(&target, &tmpdir, Some(&remote_keys)).unwrap();
+
+        assert_eq!(republished.len(), 1);
+        assert!(republished[0].key.starts_with("index"));
+        assert_eq!(republished[0].expiration_ttl, Some(300));
+    }
+
+    #[test]
+    fn it_stores_content_type_and_cache_control_as_metadata() {
+        let (tmpdir, _) = tmpdir_with_default_files();
+        std::fs::File::create(tmpdir.join("index.html")).unwrap();
+        std::fs::File::create(tmpdir.join("logo.png")).unwrap();
+
+        let mut site = Site::default();
+        site.cache_control = Some(vec![SiteCacheControl {
+            pattern: "*.png".to_string(),
+            value: "public, max-age=31536000, immutable".to_string(),
+        }]);
+
+        let (to_upload, _, _) = directory_keys_values(&make_target(site), &tmpdir, None).unwrap();
+        let metadata_of = |prefix: &str| {
+            to_upload
+                .iter()
+                .find(|kv| kv.key.starts_with(prefix))
+                .unwrap()
+                .metadata
+                .clone()
+                .unwrap()
+        };
+        assert_eq!(
+            metadata_of("index"),
+            serde_json::json!({ "contentType": "text/html; charset=utf-8" })
+        );
+        assert_eq!(
+            metadata_of("logo"),
+            serde_json::json!({
+                "contentType": "image/png",
+                "cacheControl": "public, max-age=31536000, immutable"
+            })
+        );
+    }
+
+    #[test]
+    fn it_uploads_gzipped_variants_of_text_assets() {
+        let (tmpdir, _) = tmpdir_with_default_files();
+        std::fs::write(tmpdir.join("index.html"), "<p>hello</p>".repeat(100)).unwrap();
+        std::fs::write(tmpdir.join("logo.png"), vec![0u8; 1000]).unwrap();
+
+        let mut site = Site::default();
+        site.compress = Some(true);
+
+        let (to_upload, asset_manifest, _) =
+            directory_keys_values(&make_target(site), &tmpdir, None).unwrap();
+        let gzip_key = asset_manifest.get("index.html.gz").unwrap();
+        assert_eq!(
+            gzip_key,
+            &format!("{}{}", asset_manifest["index.html"], GZIP_SUFFIX)
+        );
+        assert!(!asset_manifest.contains_key("logo.png.gz"));
+
+        let gzipped = to_upload.iter().find(|kv| &kv.key == gzip_key).unwrap();
+        assert_eq!(
+            gzipped.metadata,
+            Some(serde_json::json!({
+                "contentType": "text/html; charset=utf-8",
+                "contentEncoding": "gzip"
+            }))
+        );
+        assert!(base64::decode(&gzipped.value).unwrap().len() < 1200);
+    }
+
+    #[test]
+    fn it_rejects_ttls_below_a_minute() {
+        let mut site = Site::default();
+        site.ttl = Some(vec![SiteTtl {
+            pattern: "*.html".to_string(),
+            seconds: 30,
+        }]);
+        assert!(expiration_ttls(&make_target(site)).is_err());
+    }
+
     #[test]
     fn it_can_ignore_node_modules() {
         let mut site = Site::default();
@@ -473,6 +983,25 @@ mod tests {
         fs::remove_dir_all(test_dir).unwrap();
     }
 
+    #[test]
+    fn it_can_include_hidden_files() {
+        let mut site = Site::default();
+        site.bucket = PathBuf::from("fake");
+        site.include_hidden = Some(true);
+        let target = make_target(site);
+
+        let tmpdir = TempDir::new().unwrap();
+        let test_dir = tmpdir.path();
+        fs::create_dir(test_dir.join(".well-known")).unwrap();
+        fs::File::create(test_dir.join(".env.example")).unwrap();
+        fs::File::create(test_dir.join(".well-known").join("security.txt")).unwrap();
+
+        let (_, _, file_list) = directory_keys_values(&target, test_dir, None).unwrap();
+        let listed = |path: PathBuf| file_list.contains(&path.to_str().unwrap().to_string());
+        assert!(listed(test_dir.join(".env.example")));
+        assert!(listed(test_dir.join(".well-known").join("security.txt")));
+    }
+
     #[test]
     fn it_can_allow_unfiltered_files() {
         let mut site = Site::default();
@@ -628,6 +1157,81 @@ mod tests {
         fs::remove_dir_all(test_dir).unwrap();
     }
 
+    #[test]
+    fn it_reports_every_file_over_the_kv_limits() {
+        let tmpdir = TempDir::new().unwrap();
+        let nested = tmpdir.path().join("a".repeat(200)).join("b".repeat(200));
+        fs::create_dir_all(&nested).unwrap();
+        fs::File::create(nested.join(format!("{}.html", "c".repeat(150)))).unwrap();
+        fs::File::create(tmpdir.path().join("small.txt")).unwrap();
+        fs::File::create(tmpdir.path().join("video.mp4"))
+            .unwrap()
+            .set_len(VALUE_MAX_SIZE + 1)
+            .unwrap();
+
+        let err = directory_keys_values(&make_target(Site::default()), tmpdir.path(), None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("video.mp4"));
+        assert!(err.contains(&"c".repeat(150)));
+        assert!(!err.contains("small.txt"));
+    }
+
+    #[test]
+    fn it_rejects_windows_reserved_names() {
+        for name in &["aux", "CON.html", "nul.tar.gz", "com1 .txt", "Lpt9"] {
+            assert!(validate_file_name(&Path::new("public").join(name)).is_err());
+        }
+        for name in &["auxiliary.html", "console.js", "index.html", "com10.txt"] {
+            assert!(validate_file_name(&Path::new("public").join(name)).is_ok());
+        }
+    }
+
+    #[test]
+    fn it_rejects_paths_differing_only_by_case() {
+        let mut seen = HashMap::new();
+        validate_unique_path(&mut seen, "img/Logo.png", Path::new("public/img/Logo.png")).unwrap();
+        validate_unique_path(&mut seen, "img/logo.svg", Path::new("public/img/logo.svg")).unwrap();
+
+        let err = validate_unique_path(&mut seen, "img/logo.png", Path::new("public/img/logo.png"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Logo.png"));
+        assert!(err.contains("logo.png"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn it_rejects_file_names_that_arent_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let tmpdir = TempDir::new().unwrap();
+        let name = OsStr::from_bytes(b"logo\xff.png");
+        std::fs::File::create(tmpdir.path().join(name)).unwrap();
+
+        let err = directory_keys_values(&make_target(Site::default()), tmpdir.path(), None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("isn't valid UTF-8"));
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn it_prefixes_long_windows_paths() {
+        let path = PathBuf::from(r"C:\sites\public\.\assets\..\img\logo.png");
+        assert_eq!(
+            to_verbatim_path(&path),
+            PathBuf::from(r"\\?\C:\sites\public\img\logo.png")
+        );
+
+        let unc = PathBuf::from(r"\\server\share\public\logo.png");
+        assert_eq!(
+            to_verbatim_path(&unc),
+            PathBuf::from(r"\\?\UNC\server\share\public\logo.png")
+        );
+    }
+
     #[test]
     fn it_inserts_hash_before_extension() {
         let value = "<h1>Hello World!</h1>";
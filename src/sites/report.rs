@@ -0,0 +1,120 @@
+use crate::kv::bulk::KeyValuePair;
+use crate::terminal::message::{Message, StdErr};
+use crate::upload::form::format_bytes;
+
+// How many of the biggest assets are listed after an upload
+const LARGEST_ASSETS_SHOWN: usize = 5;
+// Assets at least this big are called out, they are rarely meant to be part of a site
+const LARGE_ASSET_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Sizes of the assets a sync uploads to the Workers Sites namespace.
+#[derive(Debug, PartialEq)]
+pub struct UploadReport {
+    // (key, decoded size in bytes), biggest first
+    assets: Vec<(String, u64)>,
+}
+
+impl UploadReport {
+    pub fn new(to_upload: &[KeyValuePair]) -> UploadReport {
+        let mut assets: Vec<(String, u64)> = to_upload
+            .iter()
+            .map(|pair| (pair.key.clone(), value_size(pair)))
+            .collect();
+        assets.sort_by(|(a_key, a_size), (b_key, b_size)| {
+            b_size.cmp(a_size).then_with(|| a_key.cmp(b_key))
+        });
+        UploadReport { assets }
+    }
+
+    /// Bytes the namespace grows by once every asset is uploaded
+    pub fn total_size(&self) -> u64 {
+        self.assets.iter().map(|(_, size)| size).sum()
+    }
+
+    pub fn largest(&self) -> &[(String, u64)] {
+        &self.assets[..self.assets.len().min(LARGEST_ASSETS_SHOWN)]
+    }
+
+    pub fn oversized(&self) -> impl Iterator<Item = &(String, u64)> {
+        self.assets
+            .iter()
+            .take_while(|(_, size)| *size >= LARGE_ASSET_SIZE)
+    }
+
+    pub fn print(&self) {
+        if self.assets.is_empty() {
+            return;
+        }
+
+        let mut msg = format!(
+            "Uploaded {} asset(s), growing your site namespace by {}. Largest uploads:",
+            self.assets.len(),
+            format_bytes(self.total_size())
+        );
+        for (key, size) in self.largest() {
+            msg.push_str(&format!("\n {:>10}  {}", format_bytes(*size), key));
+        }
+        StdErr::info(&msg);
+
+        for (key, size) in self.oversized() {
+            StdErr::warn(&format!(
+                "{} is {}. Add it to `exclude` in [site] if it isn't meant to be served by your site.",
+                key,
+                format_bytes(*size)
+            ));
+        }
+    }
+}
+
+// Values are base64 encoded for the bulk API, report the size of the file itself
+pub(super) fn value_size(pair: &KeyValuePair) -> u64 {
+    if pair.base64 != Some(true) {
+        return pair.value.len() as u64;
+    }
+    let padding = pair.value.bytes().rev().take_while(|b| *b == b'=').count();
+    (pair.value.len() / 4 * 3 - padding) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(key: &str, size: usize) -> KeyValuePair {
+        KeyValuePair {
+            key: key.to_string(),
+            value: base64::encode(&vec![0u8; size]),
+            expiration: None,
+            expiration_ttl: None,
+            base64: Some(true),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn it_reports_the_largest_assets_first() {
+        let to_upload: Vec<KeyValuePair> = (1..=7)
+            .map(|i| asset(&format!("file_{}.abc.txt", i), i * 100 + 1))
+            .chain(std::iter::once(asset("video.123.mp4", 200 * 1024 * 1024)))
+            .collect();
+        let report = UploadReport::new(&to_upload);
+
+        assert_eq!(
+            report.total_size(),
+            200 * 1024 * 1024 + (1..=7).map(|i| i * 100 + 1).sum::<usize>() as u64
+        );
+        let largest: Vec<&str> = report.largest().iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(
+            largest,
+            vec![
+                "video.123.mp4",
+                "file_7.abc.txt",
+                "file_6.abc.txt",
+                "file_5.abc.txt",
+                "file_4.abc.txt"
+            ]
+        );
+        assert_eq!(report.largest()[1].1, 701);
+        let oversized: Vec<&str> = report.oversized().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(oversized, vec!["video.123.mp4"]);
+    }
+}
@@ -2,16 +2,21 @@ use std::collections::HashSet;
 use std::path::Path;
 
 use anyhow::Result;
-use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
 
 use super::directory_keys_values;
 use super::manifest::AssetManifest;
+use super::UploadJournal;
 use crate::commands::kv;
-use crate::http;
+use crate::error::WranglerError;
+use crate::http::{self, SendLogged};
+use crate::kv::bulk::KeyValuePair;
 use crate::kv::key::KeyList;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::message::{Message, StdErr};
+use crate::terminal::progress::{self, Phase, ProgressEvent};
 
 pub fn sync(
     target: &Target,
@@ -23,24 +28,72 @@ pub fn sync(
     // in Workers KV).
 
     // Get remote keys, which contain the hash of the file (value) as the suffix.
-    // Turn it into a HashSet. This will be used by upload() to figure out which
-    // files to exclude from upload (because their current version already exists in
-    // the Workers KV remote).
-    let client = http::cf_v4_client(user)?;
-    let remote_keys_iter = KeyList::new(target, client, namespace_id, None)?;
-    let mut remote_keys: HashSet<String> = HashSet::new();
-    for remote_key in remote_keys_iter {
-        match remote_key {
-            Ok(remote_key) => {
-                remote_keys.insert(remote_key.name);
+    // This will be used by upload() to figure out which files to exclude from upload
+    // (because their current version already exists in the Workers KV remote).
+    let mut remote_keys = remote_keys(target, user, namespace_id)?;
+
+    // keys an interrupted upload wrote may not be listed yet, so the ones the listing is
+    // missing are looked up one by one; the journal alone could be stale
+    let written = UploadJournal::open(namespace_id).keys()?;
+    if !written.is_empty() {
+        let client = http::legacy_auth_client(user);
+        let mut uploaded = 0;
+        for key in written {
+            if remote_keys.contains(&key) || key_exists(&client, target, namespace_id, &key)? {
+                remote_keys.insert(key);
+                uploaded += 1;
             }
-            Err(e) => anyhow::bail!(kv::format_error(e)),
         }
+        StdErr::info(&format!(
+            "Resuming an interrupted upload, {} file(s) were uploaded already",
+            uploaded
+        ));
     }
 
     let (diff_files_to_upload, asset_manifest, _): (Vec<KeyValuePair>, AssetManifest, _) =
         directory_keys_values(target, path, Some(&remote_keys))?;
 
+    progress::emit(ProgressEvent::Files {
+        phase: Phase::Sync,
+        total: asset_manifest.len(),
+        changed: diff_files_to_upload.len(),
+    });
     StdErr::success("Success");
     Ok((diff_files_to_upload, asset_manifest))
 }
+
+/// The keys of the namespace of a site
+pub fn remote_keys(
+    target: &Target,
+    user: &GlobalUser,
+    namespace_id: &str,
+) -> Result<HashSet<String>> {
+    let client = http::cf_v4_client(user)?;
+    let mut remote_keys: HashSet<String> = HashSet::new();
+    for remote_key in KeyList::new(target, client, namespace_id, None)? {
+        match remote_key {
+            Ok(remote_key) => {
+                remote_keys.insert(remote_key.name);
+            }
+            Err(e) => return Err(kv::api_error(e)),
+        }
+    }
+    Ok(remote_keys)
+}
+
+// Whether `key` is in the namespace, which reading its metadata tells even before listing the
+// namespace does
+fn key_exists(client: &Client, target: &Target, namespace_id: &str, key: &str) -> Result<bool> {
+    let addr = http::api_url(&format!(
+        "accounts/{}/storage/kv/namespaces/{}/metadata/{}",
+        target.account_id.load()?,
+        namespace_id,
+        kv::url_encode_key(key)
+    ))?;
+    let res = client.get(&addr).send_logged()?;
+    match res.status() {
+        StatusCode::NOT_FOUND => Ok(false),
+        status if status.is_success() => Ok(true),
+        status => anyhow::bail!(WranglerError::from_api_response(status, res.text()?)),
+    }
+}
@@ -0,0 +1,180 @@
+use std::convert::TryFrom;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A position in one of the original sources of a source map. Lines and columns are one
+/// based, like the ones of stack traces.
+#[derive(Debug, PartialEq)]
+pub struct Location<'a> {
+    pub source: &'a str,
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    version: u32,
+    source_root: Option<String>,
+    sources: Vec<Option<String>>,
+    mappings: String,
+}
+
+/// A version 3 source map, decoded as far as looking up where generated code came from
+#[derive(Debug)]
+pub struct SourceMap {
+    sources: Vec<String>,
+    /// The segments of each generated line, by generated column
+    lines: Vec<Vec<Segment>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Segment {
+    column: u32,
+    source: u32,
+    line: u32,
+    source_column: u32,
+}
+
+impl SourceMap {
+    pub fn parse(json: &[u8]) -> Result<Self> {
+        let raw: RawSourceMap = serde_json::from_slice(json)?;
+        anyhow::ensure!(
+            raw.version == 3,
+            "Only version 3 source maps are supported, not version {}",
+            raw.version
+        );
+        let root = raw.source_root.unwrap_or_default();
+        let sources = raw
+            .sources
+            .into_iter()
+            .map(|source| {
+                let source = source.unwrap_or_default();
+                if root.is_empty() || root.ends_with('/') {
+                    format!("{}{}", root, source)
+                } else {
+                    format!("{}/{}", root, source)
+                }
+            })
+            .collect();
+        Ok(SourceMap {
+            sources,
+            lines: decode_mappings(&raw.mappings)?,
+        })
+    }
+
+    /// Where the generated code at `line` and `column`, both one based, came from
+    pub fn lookup(&self, line: u32, column: u32) -> Option<Location> {
+        let segments = self.lines.get(line.checked_sub(1)? as usize)?;
+        let column = column.saturating_sub(1);
+        let index = segments.partition_point(|segment| segment.column <= column);
+        let segment = segments.get(index.checked_sub(1)?)?;
+        Some(Location {
+            source: self.sources.get(segment.source as usize)?,
+            line: segment.line + 1,
+            column: segment.source_column + 1,
+        })
+    }
+}
+
+fn decode_mappings(mappings: &str) -> Result<Vec<Vec<Segment>>> {
+    let invalid = || anyhow!("The mappings of the source map are invalid");
+    let mut lines = Vec::new();
+    // the generated column starts over on every line, the other fields carry over
+    let (mut source, mut line, mut source_column) = (0i64, 0i64, 0i64);
+    for encoded_line in mappings.split(';') {
+        let mut segments = Vec::new();
+        let mut column = 0i64;
+        for encoded in encoded_line.split(',').filter(|s| !s.is_empty()) {
+            let fields = decode_vlq(encoded)?;
+            column += fields[0];
+            // a segment of just a column maps to nothing
+            if fields.len() < 4 {
+                continue;
+            }
+            source += fields[1];
+            line += fields[2];
+            source_column += fields[3];
+            let field = |value: i64| u32::try_from(value).map_err(|_| invalid());
+            segments.push(Segment {
+                column: field(column)?,
+                source: field(source)?,
+                line: field(line)?,
+                source_column: field(source_column)?,
+            });
+        }
+        segments.sort_by_key(|segment| segment.column);
+        lines.push(segments);
+    }
+    Ok(lines)
+}
+
+// Base64 VLQ: five bits of value per digit, the sixth saying whether more digits follow, and
+// the lowest bit of the value its sign
+fn decode_vlq(encoded: &str) -> Result<Vec<i64>> {
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0i64, 0u32);
+    for byte in encoded.bytes() {
+        let digit = BASE64
+            .iter()
+            .position(|&c| c == byte)
+            .ok_or_else(|| anyhow!("Invalid character {:?} in a source map", byte as char))?
+            as i64;
+        value += (digit & 31) << shift;
+        if digit & 32 == 0 {
+            let magnitude = value >> 1;
+            values.push(if value & 1 == 1 {
+                -magnitude
+            } else {
+                magnitude
+            });
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 5;
+            anyhow::ensure!(shift < 60, "A value of the source map is too large");
+        }
+    }
+    anyhow::ensure!(
+        shift == 0 && !values.is_empty(),
+        "A value of the source map is cut off"
+    );
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_decodes_vlq_values() {
+        assert_eq!(decode_vlq("AAAA").unwrap(), vec![0, 0, 0, 0]);
+        assert_eq!(decode_vlq("MADgB").unwrap(), vec![6, 0, -1, 16]);
+        assert!(decode_vlq("g").is_err());
+        assert!(decode_vlq("A!").is_err());
+    }
+
+    #[test]
+    fn it_looks_up_original_locations() {
+        let map = SourceMap::parse(
+            br#"{
+                "version": 3,
+                "sourceRoot": "webpack:///",
+                "sources": ["src/index.ts"],
+                "names": [],
+                "mappings": "AAAA;AACA,MAAM;;"
+            }"#,
+        )
+        .unwrap();
+
+        let location = map.lookup(2, 8).unwrap();
+        assert_eq!(location.source, "webpack:///src/index.ts");
+        assert_eq!((location.line, location.column), (2, 7));
+        assert_eq!(map.lookup(2, 1).unwrap().column, 1);
+        assert_eq!(map.lookup(3, 1), None);
+        assert_eq!(map.lookup(9, 1), None);
+    }
+}
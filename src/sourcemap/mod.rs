@@ -0,0 +1,210 @@
+mod mappings;
+
+pub use mappings::{Location, SourceMap};
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::settings::toml::{Target, UploadFormat};
+use crate::terminal::styles;
+use crate::upload::form;
+
+// The runtime names the script of a service worker this in stack traces, whatever its file
+// was called
+const SERVICE_WORKER_SCRIPT: &str = "worker.js";
+const LATEST: &str = "latest";
+
+// `file.js:line:column`, the way stack traces point at code
+static STACK_LOCATION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"([\w@.\-/]+\.m?js):(\d+):(\d+)").unwrap());
+
+fn sourcemaps_dir(script_name: &str) -> PathBuf {
+    Path::new(".wrangler").join("sourcemaps").join(script_name)
+}
+
+/// Keeps the source maps of the scripts just uploaded for `target` in
+/// `.wrangler/sourcemaps/<name>/<version>/`, the version being a hash of the scripts, for
+/// `wrangler tail --symbolicate`. Scripts without a source map next to them are left out.
+pub fn store(target: &Target) -> Result<()> {
+    let service_worker = !matches!(
+        target.build.as_ref().map(|build| &build.upload),
+        Some(UploadFormat::Modules { .. })
+    );
+
+    let mut hasher = Sha256::new();
+    let mut maps = Vec::new();
+    for script_path in form::script_paths(target)? {
+        let script = fs::read(&script_path)?;
+        hasher.update(&script);
+        if let Some(map_path) = map_path(&script_path, &String::from_utf8_lossy(&script)) {
+            let name = match script_path.file_name() {
+                _ if service_worker => SERVICE_WORKER_SCRIPT.to_string(),
+                Some(name) => name.to_string_lossy().to_string(),
+                None => continue,
+            };
+            maps.push((name, map_path));
+        }
+    }
+    if maps.is_empty() {
+        return Ok(());
+    }
+
+    let version = &hex::encode(hasher.finalize())[..12];
+    let root = sourcemaps_dir(&target.name);
+    let dir = root.join(version);
+    fs::create_dir_all(&dir)?;
+    for (name, map_path) in maps {
+        fs::copy(map_path, dir.join(format!("{}.map", name)))?;
+    }
+    fs::write(root.join(LATEST), version)?;
+    log::info!(
+        "Stored the source maps of {} in {}",
+        target.name,
+        dir.display()
+    );
+    Ok(())
+}
+
+// The file the `sourceMappingURL` comment of the script points at, or else the file next to
+// the script named like it with `.map` at the end
+fn map_path(script_path: &Path, script: &str) -> Option<PathBuf> {
+    let dir = script_path.parent().unwrap_or_else(|| Path::new(""));
+    let from_comment = script
+        .rfind("//# sourceMappingURL=")
+        .map(|start| &script[start + "//# sourceMappingURL=".len()..])
+        .and_then(|rest| rest.lines().next())
+        .map(str::trim)
+        .filter(|url| !url.is_empty() && !url.contains(':'))
+        .map(|url| dir.join(url));
+
+    let mut next_to_script = script_path.as_os_str().to_owned();
+    next_to_script.push(".map");
+    from_comment
+        .into_iter()
+        .chain(Some(PathBuf::from(next_to_script)))
+        .find(|path| path.is_file())
+}
+
+/// The source maps of the last script published from this project, by the name the runtime
+/// gives the script they map in stack traces
+pub struct SourceMaps {
+    maps: HashMap<String, SourceMap>,
+}
+
+impl SourceMaps {
+    pub fn load(script_name: &str) -> Result<Self> {
+        let root = sourcemaps_dir(script_name);
+        let version = match fs::read_to_string(root.join(LATEST)) {
+            Ok(version) => version,
+            Err(_) => anyhow::bail!(
+                "No source maps of {} were kept. They're kept when {} builds them, so publish {} from this project first",
+                script_name,
+                styles::highlight("`wrangler publish`"),
+                script_name
+            ),
+        };
+        Self::load_from(&root.join(version.trim()))
+    }
+
+    fn load_from(dir: &Path) -> Result<Self> {
+        let mut maps = HashMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name.trim_end_matches(".map").to_string(),
+                None => continue,
+            };
+            maps.insert(name, SourceMap::parse(&fs::read(&path)?)?);
+        }
+        Ok(SourceMaps { maps })
+    }
+
+    /// `text` with the locations of the generated scripts replaced by the ones in their
+    /// original sources. Locations the source maps don't cover are left as they are.
+    pub fn symbolicate<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        STACK_LOCATION.replace_all(text, |caps: &Captures| {
+            let file = caps[1].rsplit('/').next().unwrap_or_default();
+            let location = match (caps[2].parse(), caps[3].parse()) {
+                (Ok(line), Ok(column)) => {
+                    self.maps.get(file).and_then(|map| map.lookup(line, column))
+                }
+                _ => None,
+            };
+            match location {
+                Some(location) => {
+                    format!("{}:{}:{}", location.source, location.line, location.column)
+                }
+                None => caps[0].to_string(),
+            }
+        })
+    }
+
+    /// Symbolicates every string in `value`
+    pub fn symbolicate_json(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => {
+                if let Cow::Owned(symbolicated) = self.symbolicate(text) {
+                    *text = symbolicated;
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|v| self.symbolicate_json(v)),
+            Value::Object(map) => map.values_mut().for_each(|v| self.symbolicate_json(v)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_finds_the_map_of_a_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let script_path = dir.path().join("index.js");
+        fs::write(dir.path().join("index.js.map"), "{}").unwrap();
+        assert_eq!(
+            map_path(&script_path, "export default {}"),
+            Some(dir.path().join("index.js.map"))
+        );
+
+        fs::write(dir.path().join("out.map"), "{}").unwrap();
+        let script = "export default {}\n//# sourceMappingURL=out.map\n";
+        assert_eq!(
+            map_path(&script_path, script),
+            Some(dir.path().join("out.map"))
+        );
+
+        fs::remove_file(dir.path().join("index.js.map")).unwrap();
+        assert_eq!(map_path(&script_path, "export default {}"), None);
+    }
+
+    #[test]
+    fn it_symbolicates_stack_traces() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("worker.js.map"),
+            r#"{"version":3,"sources":["src/index.ts"],"names":[],"mappings":"AAAA;AACA,MAAM"}"#,
+        )
+        .unwrap();
+        let maps = SourceMaps::load_from(dir.path()).unwrap();
+
+        assert_eq!(
+            maps.symbolicate("Error: oops\n    at handle (worker.js:2:8)\n    at other.js:1:1"),
+            "Error: oops\n    at handle (src/index.ts:2:7)\n    at other.js:1:1"
+        );
+
+        let mut event = json!({ "exceptions": [{ "stack": "at worker.js:1:1" }], "outcome": "ok" });
+        maps.symbolicate_json(&mut event);
+        assert_eq!(event["exceptions"][0]["stack"], "at src/index.ts:1:1");
+    }
+}
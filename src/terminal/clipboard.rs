@@ -0,0 +1,48 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+
+// The first of these that runs takes the text on stdin
+const LINUX_COMMANDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let commands: &[&[&str]] = if cfg!(target_os = "windows") {
+        &[&["clip"]]
+    } else if cfg!(target_os = "linux") {
+        LINUX_COMMANDS
+    } else {
+        &[&["pbcopy"]]
+    };
+
+    for command in commands {
+        let child = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            // not installed, try the next one
+            Err(_) => continue,
+        };
+        // dropped once written, so the command sees the end of the text
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+
+    let names: Vec<&str> = commands.iter().map(|command| command[0]).collect();
+    anyhow::bail!(
+        "Could not copy to the clipboard, none of {} worked",
+        names.join(", ")
+    )
+}
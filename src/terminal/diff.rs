@@ -0,0 +1,100 @@
+use crate::terminal::styles;
+
+// How many unchanged lines are shown around each change
+const CONTEXT_LINES: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// The lines that turn `old` into `new`, keeping as many of them the same as possible.
+/// Configuration files are short, so the quadratic table this builds is fine.
+pub fn lines<'a>(old: &'a str, new: &'a str) -> Vec<Line<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            diff.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            diff.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+/// Prints the changed lines between `old` and `new` in red and green, with a few of the
+/// unchanged lines around them
+pub fn print(old: &str, new: &str) {
+    let diff = lines(old, new);
+    let near_change = |index: usize| {
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(diff.len());
+        diff[start..end]
+            .iter()
+            .any(|line| !matches!(line, Line::Same(_)))
+    };
+
+    let mut skipped = false;
+    for (index, line) in diff.iter().enumerate() {
+        match line {
+            Line::Same(text) if near_change(index) => println!("  {}", text),
+            Line::Same(_) => {
+                if !skipped {
+                    println!("  {}", styles::bold("..."));
+                }
+                skipped = true;
+                continue;
+            }
+            Line::Removed(text) => println!("{}", styles::warning(format!("- {}", text))),
+            Line::Added(text) => println!("{}", styles::success(format!("+ {}", text))),
+        }
+        skipped = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_lines_both_sides_share() {
+        let old = "name = \"template\"\ntype = \"webpack\"\nroute = \"\"\n";
+        let new = "name = \"my-worker\"\ntype = \"webpack\"\nworkers_dev = true\nroute = \"\"\n";
+
+        assert_eq!(
+            lines(old, new),
+            vec![
+                Line::Removed("name = \"template\""),
+                Line::Added("name = \"my-worker\""),
+                Line::Same("type = \"webpack\""),
+                Line::Added("workers_dev = true"),
+                Line::Same("route = \"\""),
+            ]
+        );
+        assert_eq!(lines("", "a"), vec![Line::Added("a")]);
+    }
+}
@@ -1,28 +1,60 @@
 use anyhow::Result;
 use atty::Stream;
+use once_cell::sync::OnceCell;
+use std::env;
 use std::io::{self, Read};
 
-// For interactively handling reading in a string
-pub fn get_user_input(prompt_string: &str) -> String {
+use crate::terminal::message;
+
+static NON_INTERACTIVE: OnceCell<()> = OnceCell::new();
+static ASSUME_YES: OnceCell<()> = OnceCell::new();
+
+/// Never prompts for the rest of the process, as if nobody was at the terminal: prompts
+/// take their safe answer or fail. Only the first call has an effect.
+pub fn set_non_interactive() {
+    let _ = NON_INTERACTIVE.set(());
+}
+
+/// Answers yes to every confirmation for the rest of the process, without asking. Only the
+/// first call has an effect.
+pub fn set_assume_yes() {
+    let _ = ASSUME_YES.set(());
+}
+
+pub fn assume_yes() -> bool {
+    ASSUME_YES.get().is_some()
+}
+
+// For interactively handling reading in a string. Without anybody at the terminal, it fails
+// telling what to do `instead`, e.g. "pass --name"
+pub fn get_user_input(prompt_string: &str, instead: &str) -> Result<String> {
+    if !is_interactive() {
+        anyhow::bail!(unanswered(prompt_string, instead))
+    }
     println!("{}", prompt_string);
     let mut input: String = read!("{}\n");
     input = strip_trailing_whitespace(input);
-    input
+    Ok(input)
 }
 
-pub fn get_user_input_multi_line(prompt_string: &str) -> String {
-    println!("{}", prompt_string);
+// Like `get_user_input`, but the input can also be piped in, newlines and all
+pub fn get_user_input_multi_line(prompt_string: &str, instead: &str) -> Result<String> {
     // are we reading from user input?
     let mut input = if atty::is(Stream::Stdin) {
+        if !is_interactive() {
+            anyhow::bail!(unanswered(prompt_string, instead))
+        }
+        println!("{}", prompt_string);
         read!("{}\n")
     } else {
         // or is this data from a pipe? (support newlines)
+        println!("{}", prompt_string);
         let mut tmp = String::new();
         let _ = io::stdin().read_to_string(&mut tmp);
         tmp
     };
     input = strip_trailing_whitespace(input);
-    input
+    Ok(input)
 }
 
 fn strip_trailing_whitespace(mut input: String) -> String {
@@ -34,10 +66,54 @@ fn strip_trailing_whitespace(mut input: String) -> String {
 const INTERACTIVE_RESPONSE_LEN: usize = 1;
 const YES: &str = "y";
 const NO: &str = "n";
+
+/// Whether there is somebody at the terminal to answer prompts: not with `--non-interactive`,
+/// nor in CI, where `CI` is set to `true`.
+pub fn is_interactive() -> bool {
+    NON_INTERACTIVE.get().is_none()
+        && !is_ci()
+        && atty::is(Stream::Stdin)
+        && atty::is(Stream::Stdout)
+}
+
+fn is_ci() -> bool {
+    env::var("CI").map_or(false, |ci| ci == "true" || ci == "1")
+}
+
 // For interactively handling destructive commands (and discouraging accidental deletes).
 // Input like "yes", "Yes", "no", "No" will be accepted, thanks to the whitespace-stripping
-// and lowercasing logic below.
+// and lowercasing logic below. With `--yes` it's answered yes without asking, and without
+// anybody at the terminal it fails, telling to pass `--yes`.
 pub fn confirm(prompt_string: &str) -> Result<bool> {
+    confirm_or_fail(prompt_string, "pass --yes to answer yes")
+}
+
+// Like `confirm`, but without anybody at the terminal it fails telling what to do `instead`,
+// e.g. "pass --force"
+pub fn confirm_or_fail(prompt_string: &str, instead: &str) -> Result<bool> {
+    if !assume_yes() && !is_interactive() {
+        anyhow::bail!(unanswered(prompt_string, instead))
+    }
+    ask(prompt_string)
+}
+
+// Like `confirm`, but without anybody at the terminal the answer is `unattended`, for the
+// prompts that have a safe one
+pub fn confirm_or(prompt_string: &str, unattended: bool) -> Result<bool> {
+    if !assume_yes() && !is_interactive() {
+        return Ok(unattended);
+    }
+    ask(prompt_string)
+}
+
+fn ask(prompt_string: &str) -> Result<bool> {
+    if assume_yes() {
+        // the answer is only for the record, so it stays out of the way of what's on stdout
+        if !message::is_quiet() {
+            eprintln!("{} [y/n] y (--yes)", prompt_string);
+        }
+        return Ok(true);
+    }
     println!("{} [y/n]", prompt_string);
     loop {
         let mut response: String = read!("{}\n");
@@ -52,6 +128,14 @@ pub fn confirm(prompt_string: &str) -> Result<bool> {
     }
 }
 
+fn unanswered(prompt_string: &str, instead: &str) -> String {
+    format!(
+        "\"{}\" needs an answer, but wrangler isn't running interactively: there's no terminal, --non-interactive was passed or CI is set. Instead, {}",
+        prompt_string.trim_end_matches(|c: char| c == ':' || c.is_whitespace()),
+        instead
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,4 +147,12 @@ mod tests {
         let truncated_str = strip_trailing_whitespace(test_str);
         assert_eq!(truncated_str, "mysecret")
     }
+
+    #[test]
+    fn it_tells_what_to_do_instead_of_answering() {
+        assert_eq!(
+            unanswered("Enter API Token: ", "set CF_API_TOKEN"),
+            "\"Enter API Token\" needs an answer, but wrangler isn't running interactively: there's no terminal, --non-interactive was passed or CI is set. Instead, set CF_API_TOKEN"
+        );
+    }
 }
@@ -1,8 +1,71 @@
 use super::emoji;
+use super::progress::{self, ProgressEvent};
 
 use billboard::{Billboard, BorderColor, BorderStyle};
+use once_cell::sync::OnceCell;
 use serde::Serialize;
 
+static VERBOSITY: OnceCell<Verbosity> = OnceCell::new();
+static SINK: OnceCell<Box<dyn Sink>> = OnceCell::new();
+
+/// How much wrangler tells you about what it's doing
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Verbosity {
+    /// Only errors and the results of a command, like the URLs a worker was published to
+    Quiet,
+    Normal,
+    /// Everything, including what is logged about each request to the API
+    Verbose,
+}
+
+/// Sets the verbosity for the rest of the process. Only the first call has an effect.
+pub fn set_verbosity(verbosity: Verbosity) {
+    let _ = VERBOSITY.set(verbosity);
+}
+
+pub fn verbosity() -> Verbosity {
+    VERBOSITY.get().copied().unwrap_or(Verbosity::Normal)
+}
+
+pub fn is_quiet() -> bool {
+    verbosity() == Verbosity::Quiet
+}
+
+/// Which of `StdOut` and `StdErr` a message was for
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stream {
+    Out,
+    Err,
+}
+
+/// Receives the messages wrangler would print when it's embedded in another program, see
+/// `wrangler::api`
+pub trait Sink: Send + Sync {
+    fn message(&self, stream: Stream, msg: &str);
+}
+
+/// Sends every message of `StdOut` and `StdErr` to `sink` instead of the terminal for the
+/// rest of the process, and hides spinners and progress bars. Only the first call has an
+/// effect.
+pub fn set_sink(sink: Box<dyn Sink>) {
+    let _ = SINK.set(sink);
+}
+
+pub fn has_sink() -> bool {
+    SINK.get().is_some()
+}
+
+// Hands `msg` to the sink if there is one, or else gives it back to be printed
+fn to_sink(stream: Stream, msg: &str) -> Option<&str> {
+    match SINK.get() {
+        Some(sink) => {
+            sink.message(stream, msg);
+            None
+        }
+        None => Some(msg),
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Output {
     Json,
@@ -12,16 +75,25 @@ pub trait Message {
     fn message(msg: &str);
 
     fn info(msg: &str) {
+        if is_quiet() {
+            return;
+        }
         let msg = format!("{} {}", emoji::INFO, msg);
         Self::message(&msg);
     }
 
     fn warn(msg: &str) {
+        if is_quiet() {
+            return;
+        }
         let msg = format!("{} {}", emoji::WARN, msg);
         Self::message(&msg);
     }
 
     fn success(msg: &str) {
+        if is_quiet() {
+            return;
+        }
         let msg = format!("{} {}", emoji::SPARKLES, msg);
         Self::message(&msg);
     }
@@ -32,16 +104,25 @@ pub trait Message {
     }
 
     fn working(msg: &str) {
+        if is_quiet() {
+            return;
+        }
         let msg = format!("{} {}", emoji::SWIRL, msg);
         Self::message(&msg);
     }
 
     fn preview(msg: &str) {
+        if is_quiet() {
+            return;
+        }
         let msg = format!("{} {}", emoji::WORKER, msg);
         Self::message(&msg);
     }
 
     fn help(msg: &str) {
+        if is_quiet() {
+            return;
+        }
         let msg = format!("{} {}", emoji::SLEUTH, msg);
         Self::message(&msg);
     }
@@ -58,10 +139,15 @@ pub struct StdOut;
 
 impl Message for StdOut {
     fn message(msg: &str) {
-        println!("{}", msg);
+        if let Some(msg) = to_sink(Stream::Out, msg) {
+            println!("{}", msg);
+        }
     }
 
     fn billboard(msg: &str) {
+        if is_quiet() || to_sink(Stream::Out, msg).is_none() {
+            return;
+        }
         let billboard = Billboard::builder()
             .border_style(BorderStyle::Round)
             .border_color(BorderColor::Cyan)
@@ -71,6 +157,9 @@ impl Message for StdOut {
     }
 
     fn deprecation_warning(msg: &str) {
+        if to_sink(Stream::Out, msg).is_none() {
+            return;
+        }
         let bb = Billboard::builder()
             .border_style(BorderStyle::Round)
             .border_color(BorderColor::Red)
@@ -83,7 +172,10 @@ impl Message for StdOut {
     where
         T: ?Sized + Serialize,
     {
-        println!("{}", &serde_json::to_string(value).unwrap());
+        let json = serde_json::to_string(value).unwrap();
+        if let Some(json) = to_sink(Stream::Out, &json) {
+            println!("{}", json);
+        }
     }
 }
 
@@ -91,7 +183,18 @@ pub struct StdErr;
 
 impl Message for StdErr {
     fn message(msg: &str) {
-        eprintln!("{}", msg);
+        let msg = match to_sink(Stream::Err, msg) {
+            Some(msg) => msg,
+            None => return,
+        };
+        // stderr only carries progress events then, so tools can parse every line of it
+        if progress::is_json() {
+            progress::emit(ProgressEvent::Message {
+                text: msg.to_string(),
+            });
+        } else {
+            eprintln!("{}", msg);
+        }
     }
 
     fn billboard(_msg: &str) {
@@ -108,3 +211,31 @@ impl Message for StdErr {
         panic!("Json output intended for stdout, not stderr.")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::sync::Mutex;
+
+    static RECORDED: Lazy<Mutex<Vec<(Stream, String)>>> = Lazy::new(Default::default);
+
+    struct Recorder;
+
+    impl Sink for Recorder {
+        fn message(&self, stream: Stream, msg: &str) {
+            RECORDED.lock().unwrap().push((stream, msg.to_string()));
+        }
+    }
+
+    #[test]
+    fn it_sends_messages_to_the_sink() {
+        set_sink(Box::new(Recorder));
+        StdErr::message("to stderr");
+        StdOut::as_json(&[1, 2]);
+
+        let recorded = RECORDED.lock().unwrap();
+        assert!(recorded.contains(&(Stream::Err, "to stderr".to_string())));
+        assert!(recorded.contains(&(Stream::Out, "[1,2]".to_string())));
+    }
+}
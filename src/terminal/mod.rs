@@ -1,8 +1,13 @@
 mod browser;
+mod clipboard;
+pub mod diff;
 pub mod emoji;
 pub mod interactive;
 mod json;
 pub mod message;
+pub mod progress;
 pub mod styles;
+pub mod ui;
 pub use browser::open_browser;
+pub use clipboard::copy_to_clipboard;
 pub use json::colored_json_string;
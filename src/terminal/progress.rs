@@ -0,0 +1,161 @@
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use anyhow::Result;
+use indicatif::ProgressBar;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+use crate::terminal::message;
+
+static FORMAT: OnceCell<ProgressFormat> = OnceCell::new();
+static RECORDED: OnceCell<Mutex<Vec<ProgressEvent>>> = OnceCell::new();
+
+/// How progress is reported during long-running commands like publish.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProgressFormat {
+    /// Spinners and progress bars for humans
+    Plain,
+    /// One JSON event per line on stderr, for tools wrapping wrangler
+    Json,
+}
+
+impl FromStr for ProgressFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(ProgressFormat::Plain),
+            "json" => Ok(ProgressFormat::Json),
+            _ => anyhow::bail!("Invalid progress format; must be either \"plain\" or \"json\""),
+        }
+    }
+}
+
+/// Sets the progress format for the rest of the process. Only the first call has an effect.
+pub fn set_format(format: ProgressFormat) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn is_json() -> bool {
+    FORMAT.get() == Some(&ProgressFormat::Json)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Build,
+    Sync,
+    UploadAssets,
+    UploadScript,
+    Deploy,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    PhaseStarted {
+        phase: Phase,
+    },
+    PhaseCompleted {
+        phase: Phase,
+        success: bool,
+        elapsed_ms: u128,
+    },
+    /// The files found for a phase, and how many of them have to be uploaded
+    Files {
+        phase: Phase,
+        total: usize,
+        changed: usize,
+    },
+    /// Cumulative progress of an upload
+    Uploaded {
+        phase: Phase,
+        files: usize,
+        total_files: usize,
+        bytes: u64,
+        total_bytes: u64,
+    },
+    /// A status line that would otherwise have been printed on stderr between the events
+    Message {
+        text: String,
+    },
+}
+
+/// Keeps every event emitted from now on, whatever the format, for `take_recorded`
+pub fn record() {
+    let _ = RECORDED.set(Mutex::new(Vec::new()));
+}
+
+/// The events emitted since the last call, once `record` was called
+pub fn take_recorded() -> Vec<ProgressEvent> {
+    match RECORDED.get() {
+        Some(recorded) => std::mem::take(&mut *recorded.lock().unwrap()),
+        None => Vec::new(),
+    }
+}
+
+/// Writes `event` to stderr when progress is reported as JSON, and does nothing otherwise.
+pub fn emit(event: ProgressEvent) {
+    if let Some(recorded) = RECORDED.get() {
+        recorded.lock().unwrap().push(event.clone());
+    }
+    if is_json() {
+        match serde_json::to_string(&event) {
+            Ok(line) => eprintln!("{}", line),
+            Err(e) => log::info!("Could not serialize progress event: {}", e),
+        }
+    }
+}
+
+/// Runs `f` as `phase`, bracketed by started and completed events.
+pub fn phase<T>(phase: Phase, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let start = Instant::now();
+    emit(ProgressEvent::PhaseStarted { phase });
+    let result = f();
+    emit(ProgressEvent::PhaseCompleted {
+        phase,
+        success: result.is_ok(),
+        elapsed_ms: start.elapsed().as_millis(),
+    });
+    result
+}
+
+/// Hides `bar` when progress is reported as JSON, so it doesn't get in the way of events,
+/// and with `--quiet`.
+pub fn unless_json(bar: ProgressBar) -> ProgressBar {
+    if is_json() || message::is_quiet() || message::has_sink() {
+        ProgressBar::hidden()
+    } else {
+        bar
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_serializes_events_as_tagged_objects() {
+        let event = ProgressEvent::Uploaded {
+            phase: Phase::UploadAssets,
+            files: 10,
+            total_files: 20,
+            bytes: 1024,
+            total_bytes: 4096,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"uploaded","phase":"upload_assets","files":10,"total_files":20,"bytes":1024,"total_bytes":4096}"#
+        );
+
+        let event = ProgressEvent::Message {
+            text: "Uploading 2 files".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"message","text":"Uploading 2 files"}"#
+        );
+    }
+}
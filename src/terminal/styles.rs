@@ -19,3 +19,7 @@ pub fn cyan<D>(msg: D) -> StyledObject<D> {
 pub fn bold<D>(msg: D) -> StyledObject<D> {
     style(msg).bold()
 }
+
+pub fn success<D>(msg: D) -> StyledObject<D> {
+    style(msg).green().bold()
+}
@@ -0,0 +1,134 @@
+//! Spinners, progress bars and tables for commands to report with, which fall back to plain
+//! output when they aren't attached to a terminal: no animations in CI logs, and rows of
+//! tab separated values for other programs to read.
+
+use atty::Stream;
+use indicatif::{ProgressBar, ProgressStyle};
+use prettytable::{Cell, Row};
+
+use crate::terminal::progress;
+
+// How often spinners move, in milliseconds
+const TICK: u64 = 20;
+
+/// Whether stdout is a terminal, rather than a pipe or a file
+pub fn stdout_is_tty() -> bool {
+    atty::is(Stream::Stdout)
+}
+
+/// Whether stderr, where spinners and progress bars go, is a terminal
+pub fn stderr_is_tty() -> bool {
+    atty::is(Stream::Stderr)
+}
+
+/// A spinner showing `message` on stderr for an operation of unknown length. Change the
+/// message as it goes, and clear it with `finish_and_clear` when it's done.
+pub fn spinner(message: &str) -> ProgressBar {
+    let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
+    let spinner = visible(ProgressBar::new_spinner().with_style(style));
+    spinner.set_message(message);
+    spinner.enable_steady_tick(TICK);
+    spinner
+}
+
+/// A progress bar on stderr counting up to `len`, with a message under it
+pub fn bar(len: u64) -> ProgressBar {
+    let style = ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}");
+    visible(ProgressBar::new(len).with_style(style))
+}
+
+/// A progress bar on stderr for an upload of `len` bytes, showing how fast it goes, with a
+/// message beside it. The length can be set later, before anything is counted.
+pub fn upload_bar(len: u64) -> ProgressBar {
+    let style = ProgressStyle::default_bar()
+        .template("{msg} {wide_bar} {bytes}/{total_bytes} ({bytes_per_sec}, {eta})");
+    visible(ProgressBar::new(len).with_style(style))
+}
+
+// Progress is only drawn for somebody at a terminal, and not in the way of JSON events or
+// of --quiet
+fn visible(bar: ProgressBar) -> ProgressBar {
+    if stderr_is_tty() {
+        progress::unless_json(bar)
+    } else {
+        ProgressBar::hidden()
+    }
+}
+
+/// Rows of values printed to stdout, as a table at a terminal and as tab separated lines,
+/// header first, otherwise
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(header: &[&str]) -> Self {
+        Table {
+            header: header.iter().map(|column| column.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds a row of values. A value of several lines stays in its cell at a terminal, and
+    /// is joined with commas otherwise
+    pub fn add_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn print(&self) {
+        print!("{}", self.render(stdout_is_tty()));
+    }
+
+    fn render(&self, tty: bool) -> String {
+        if tty {
+            let mut table = prettytable::Table::new();
+            for row in std::iter::once(&self.header).chain(&self.rows) {
+                table.add_row(Row::new(row.iter().map(|value| Cell::new(value)).collect()));
+            }
+            return table.to_string();
+        }
+        let mut lines = String::new();
+        for row in std::iter::once(&self.header).chain(&self.rows) {
+            let values: Vec<String> = row
+                .iter()
+                .map(|value| {
+                    value
+                        .replace('\t', " ")
+                        .lines()
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .collect();
+            lines.push_str(&values.join("\t"));
+            lines.push('\n');
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_tables_for_terminals_and_for_programs() {
+        let mut table = Table::new(&["Name", "Routes"]);
+        table.add_row(vec![
+            "worker".to_string(),
+            "example.com/*\nexample.org/*".to_string(),
+        ]);
+
+        assert_eq!(
+            table.render(false),
+            "Name\tRoutes\nworker\texample.com/*,example.org/*\n"
+        );
+        let rendered = table.render(true);
+        assert!(rendered.contains("| worker |"));
+        assert!(rendered.lines().any(|line| line.contains("example.org/*")));
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use prettytable::{Cell, Row, Table};
+use sha2::{Digest, Sha256};
+
+use super::project_assets::{ModuleType, ModulesAssets, ServiceWorkerAssets};
+use super::source_map;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::styles;
+
+/// The API rejects scripts whose compressed upload is larger than this
+pub const SCRIPT_SIZE_LIMIT: u64 = 1024 * 1024;
+
+/// Warn once the compressed upload reaches this share of the limit
+const WARN_THRESHOLD: f64 = 0.8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundlePart {
+    pub name: String,
+    pub kind: String,
+    pub size: u64,
+    pub compressed_size: u64,
+    /// A hash of the contents, to tell whether they changed since they were last uploaded
+    pub digest: String,
+    /// The JavaScript file the part was read from, which `--analyze` breaks down into the
+    /// modules it was bundled from
+    pub script_path: Option<PathBuf>,
+}
+
+impl BundlePart {
+    fn new(name: String, kind: &str, contents: &[u8]) -> Result<Self> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents)?;
+        let compressed = encoder.finish()?;
+
+        Ok(BundlePart {
+            name,
+            kind: kind.to_string(),
+            size: contents.len() as u64,
+            compressed_size: compressed.len() as u64,
+            digest: hex::encode(Sha256::digest(contents)),
+            script_path: None,
+        })
+    }
+
+    // Like `new`, reading the file at `path` a bit at a time rather than all at once, since
+    // wasm modules can be large
+    fn from_file(name: String, kind: &str, path: &Path) -> Result<Self> {
+        let mut measure = Measure {
+            size: 0,
+            encoder: GzEncoder::new(Counter(0), Compression::default()),
+            hasher: Sha256::new(),
+        };
+        io::copy(&mut File::open(path)?, &mut measure)?;
+        let compressed = measure.encoder.finish()?;
+
+        Ok(BundlePart {
+            name,
+            kind: kind.to_string(),
+            size: measure.size,
+            compressed_size: compressed.0,
+            digest: hex::encode(measure.hasher.finalize()),
+            script_path: None,
+        })
+    }
+}
+
+// What `BundlePart::from_file` works out as it reads a file
+struct Measure {
+    size: u64,
+    encoder: GzEncoder<Counter>,
+    hasher: Sha256,
+}
+
+impl Write for Measure {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.encoder.write_all(buf)?;
+        self.hasher.update(buf);
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.encoder.flush()
+    }
+}
+
+// Counts the bytes written to it and drops them
+struct Counter(u64);
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The size of every file that goes into a script upload.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BundleSize {
+    pub parts: Vec<BundlePart>,
+}
+
+impl BundleSize {
+    pub(super) fn from_service_worker(assets: &ServiceWorkerAssets) -> Result<Self> {
+        let mut script =
+            BundlePart::from_file(assets.script_name()?, "script", &assets.script_path())?;
+        script.script_path = Some(assets.script_path());
+        let mut parts = vec![script];
+        for wasm_module in &assets.wasm_modules {
+            parts.push(BundlePart::from_file(
+                wasm_module.filename(),
+                "wasm",
+                &wasm_module.path(),
+            )?);
+        }
+        for text_blob in &assets.text_blobs {
+            parts.push(BundlePart::new(
+                text_blob.binding.clone(),
+                "text",
+                text_blob.data.as_bytes(),
+            )?);
+        }
+
+        Ok(BundleSize { parts })
+    }
+
+    pub(super) fn from_modules(assets: &ModulesAssets) -> Result<Self> {
+        let mut parts = Vec::new();
+        for (name, module) in &assets.manifest.modules {
+            let mut part =
+                BundlePart::from_file(name.clone(), module.module_type.name(), &module.path)?;
+            if let ModuleType::ESModule | ModuleType::CommonJS = module.module_type {
+                part.script_path = Some(module.path.clone());
+            }
+            parts.push(part);
+        }
+        for text_blob in &assets.text_blobs {
+            parts.push(BundlePart::new(
+                text_blob.binding.clone(),
+                "text",
+                text_blob.data.as_bytes(),
+            )?);
+        }
+
+        Ok(BundleSize { parts })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.parts.iter().map(|part| part.size).sum()
+    }
+
+    pub fn compressed_size(&self) -> u64 {
+        self.parts.iter().map(|part| part.compressed_size).sum()
+    }
+
+    pub fn exceeds_limit(&self) -> bool {
+        self.compressed_size() > SCRIPT_SIZE_LIMIT
+    }
+
+    pub fn approaches_limit(&self) -> bool {
+        self.compressed_size() as f64 >= SCRIPT_SIZE_LIMIT as f64 * WARN_THRESHOLD
+    }
+
+    /// Prints the total size, and a warning when it gets close to the limit.
+    pub fn report(&self) {
+        let msg = format!(
+            "Script size is {} ({} compressed)",
+            format_bytes(self.size()),
+            format_bytes(self.compressed_size())
+        );
+        let analyze = styles::highlight("`wrangler publish --analyze`");
+
+        if self.exceeds_limit() {
+            StdErr::warn(&format!(
+                "{}, above the {} limit, so the upload will likely be rejected. Run {} to see what takes up the most space.",
+                msg,
+                format_bytes(SCRIPT_SIZE_LIMIT),
+                analyze
+            ));
+        } else if self.approaches_limit() {
+            StdErr::warn(&format!(
+                "{}, close to the {} limit. Run {} to see what takes up the most space.",
+                msg,
+                format_bytes(SCRIPT_SIZE_LIMIT),
+                analyze
+            ));
+        } else {
+            StdErr::info(&msg);
+        }
+    }
+
+    /// Prints what takes up the upload, largest first: the modules bundled into each script
+    /// that has a source map, and every other part as a whole.
+    pub fn print_breakdown(&self) {
+        let mut rows = Vec::new();
+        for part in &self.parts {
+            let modules = part
+                .script_path
+                .as_deref()
+                .and_then(source_map::module_sizes);
+            match modules {
+                Some(modules) => rows.extend(modules_of(part, modules)),
+                None => {
+                    if part.script_path.is_some() {
+                        StdErr::info(&format!(
+                            "{} has no source map, so it can't be broken down into the modules it was bundled from",
+                            part.name
+                        ));
+                    }
+                    rows.push(BreakdownRow {
+                        name: part.name.clone(),
+                        kind: part.kind.clone(),
+                        size: part.size,
+                        compressed_size: part.compressed_size,
+                        estimated: false,
+                    });
+                }
+            }
+        }
+        rows.sort_by(|a, b| {
+            b.compressed_size
+                .cmp(&a.compressed_size)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        let total = self.compressed_size().max(1) as f64;
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("Name"),
+            Cell::new("Type"),
+            Cell::new("Size"),
+            Cell::new("Compressed"),
+            Cell::new("Share"),
+        ]));
+        for row in rows {
+            let compressed = format_bytes(row.compressed_size);
+            table.add_row(Row::new(vec![
+                Cell::new(&row.name),
+                Cell::new(&row.kind),
+                Cell::new(&format_bytes(row.size)),
+                Cell::new(&if row.estimated {
+                    format!("~{}", compressed)
+                } else {
+                    compressed
+                }),
+                Cell::new(&format!(
+                    "{:.1}%",
+                    row.compressed_size as f64 / total * 100.0
+                )),
+            ]));
+        }
+        eprint!("{}", table);
+    }
+}
+
+// A line of `print_breakdown`
+#[derive(Debug, PartialEq)]
+struct BreakdownRow {
+    name: String,
+    kind: String,
+    size: u64,
+    compressed_size: u64,
+    /// Modules are compressed together, so each is credited with its share of the part
+    estimated: bool,
+}
+
+fn modules_of(part: &BundlePart, modules: BTreeMap<String, u64>) -> Vec<BreakdownRow> {
+    let ratio = part.compressed_size as f64 / part.size.max(1) as f64;
+    modules
+        .into_iter()
+        .map(|(name, size)| BreakdownRow {
+            name,
+            kind: format!("module of {}", part.name),
+            size,
+            compressed_size: (size as f64 * ratio).round() as u64,
+            estimated: true,
+        })
+        .collect()
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(name: &str, compressed_size: u64) -> BundlePart {
+        BundlePart {
+            name: name.to_string(),
+            kind: "script".to_string(),
+            size: compressed_size * 3,
+            compressed_size,
+            digest: String::new(),
+            script_path: None,
+        }
+    }
+
+    #[test]
+    fn it_compresses_parts() {
+        let contents = "addEventListener('fetch', () => {});\n".repeat(100);
+        let part = BundlePart::new("worker".to_string(), "script", contents.as_bytes()).unwrap();
+        assert_eq!(part.size, contents.len() as u64);
+        assert!(part.compressed_size < part.size);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("worker.js");
+        std::fs::write(&path, &contents).unwrap();
+        let read = BundlePart::from_file("worker".to_string(), "script", &path).unwrap();
+        assert_eq!(read, part);
+    }
+
+    #[test]
+    fn it_compares_the_compressed_size_to_the_limit() {
+        let small = BundleSize {
+            parts: vec![part("worker", 100 * 1024)],
+        };
+        assert!(!small.approaches_limit());
+        assert!(!small.exceeds_limit());
+
+        let close = BundleSize {
+            parts: vec![part("worker", 600 * 1024), part("wasm", 300 * 1024)],
+        };
+        assert_eq!(close.compressed_size(), 900 * 1024);
+        assert!(close.approaches_limit());
+        assert!(!close.exceeds_limit());
+
+        let large = BundleSize {
+            parts: vec![part("worker", SCRIPT_SIZE_LIMIT + 1)],
+        };
+        assert!(large.exceeds_limit());
+    }
+
+    #[test]
+    fn it_credits_modules_with_their_share_of_the_compressed_script() {
+        let script = part("worker", 300);
+        let mut modules = BTreeMap::new();
+        modules.insert("lodash".to_string(), 600);
+        modules.insert("src/index.js".to_string(), 300);
+
+        let rows = modules_of(&script, modules);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].name, "lodash");
+        assert_eq!(rows[0].kind, "module of worker");
+        assert_eq!(rows[0].compressed_size, 200);
+        assert_eq!(rows[1].compressed_size, 100);
+        assert!(rows.iter().all(|row| row.estimated));
+    }
+
+    #[test]
+    fn it_formats_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.00 MiB");
+    }
+}
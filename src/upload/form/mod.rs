@@ -1,35 +1,86 @@
+mod bundle_size;
 mod modules_worker;
 mod plain_text;
 mod project_assets;
 mod service_worker;
+mod settings;
+mod source_map;
+mod streamed;
 mod text_blob;
 mod wasm_module;
 
 use anyhow::Result;
+use indicatif::ProgressBar;
 use reqwest::blocking::multipart::Form;
 use std::fs;
-use std::path::Path;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use crate::commands::build::RustArtifacts;
 use crate::settings::binding;
-use crate::settings::toml::{Target, TargetType, UploadFormat, UsageModel};
+use crate::settings::toml::{Limits, Target, TargetType, UploadFormat, UsageModel};
 use crate::sites::AssetManifest;
 use crate::wranglerjs;
 
+pub(crate) use bundle_size::format_bytes;
+pub use bundle_size::{BundlePart, BundleSize};
 use plain_text::PlainText;
 pub use project_assets::{ModuleConfig, ModuleType};
 use project_assets::{ModulesAssets, ServiceWorkerAssets};
+pub use settings::build_settings_form;
 use text_blob::TextBlob;
 use wasm_module::WasmModule;
 
 // TODO: https://github.com/cloudflare/wrangler/issues/1083
-use super::{krate, Package};
+use super::Package;
 
 pub fn build(
     target: &Target,
     asset_manifest: Option<AssetManifest>,
     session_config: Option<serde_json::Value>,
 ) -> Result<Form> {
+    prepare(target, asset_manifest)?
+        .form(session_config, &ProgressBar::hidden())
+        .map(|(form, _)| form)
+}
+
+/// What goes into the upload of a script, gathered before any of it is read so that it can
+/// be measured first
+pub struct Prepared {
+    assets: Assets,
+}
+
+enum Assets {
+    ServiceWorker(ServiceWorkerAssets),
+    Modules(ModulesAssets),
+}
+
+impl Prepared {
+    /// The size of every file that goes into the upload
+    pub fn size(&self) -> Result<BundleSize> {
+        match &self.assets {
+            Assets::ServiceWorker(assets) => BundleSize::from_service_worker(assets),
+            Assets::Modules(assets) => BundleSize::from_modules(assets),
+        }
+    }
+
+    /// The form of the upload, with every part of it counted on `progress` as it's sent, and
+    /// the number of bytes the parts come to
+    pub fn form(
+        &self,
+        session_config: Option<serde_json::Value>,
+        progress: &ProgressBar,
+    ) -> Result<(Form, u64)> {
+        match &self.assets {
+            Assets::ServiceWorker(assets) => {
+                service_worker::build_form(assets, session_config, progress)
+            }
+            Assets::Modules(assets) => modules_worker::build_form(assets, session_config, progress),
+        }
+    }
+}
+
+/// Like `build`, but leaves building the form for after the upload is measured
+pub fn prepare(target: &Target, asset_manifest: Option<AssetManifest>) -> Result<Prepared> {
     let target_type = &target.target_type;
     let compatibility_date = target.compatibility_date.clone();
     let compatibility_flags = target.compatibility_flags.clone();
@@ -43,6 +94,8 @@ pub fn build(
     let mut plain_texts: Vec<PlainText> = Vec::new();
     let mut wasm_modules: Vec<WasmModule> = Vec::new();
     let usage_model = target.usage_model;
+    let limits = target.limits;
+    let custom_bindings = target.bindings.clone().unwrap_or_default();
 
     if let Some(blobs) = &target.text_blobs {
         for (key, blob_path) in blobs.iter() {
@@ -59,11 +112,19 @@ pub fn build(
 
     if let Some(vars) = &target.vars {
         for (key, value) in vars.iter() {
-            plain_texts.push(PlainText::new(key.clone(), value.clone())?)
+            plain_texts.push(PlainText::from_var(key.clone(), value)?)
         }
     }
 
     if let Some(asset_manifest) = asset_manifest {
+        if let Some(site) = &target.site {
+            if let Some(options) = site.content_options(&asset_manifest)? {
+                log::info!("adding __STATIC_CONTENT_OPTIONS");
+                let binding = "__STATIC_CONTENT_OPTIONS".to_string();
+                plain_texts.push(PlainText::new(binding, serde_json::to_string(&options)?)?);
+            }
+        }
+
         log::info!("adding __STATIC_CONTENT_MANIFEST");
         let binding = "__STATIC_CONTENT_MANIFEST".to_string();
         let asset_manifest_blob = get_asset_manifest_blob(asset_manifest)?;
@@ -74,16 +135,13 @@ pub fn build(
     match target_type {
         TargetType::Rust => {
             log::info!("Rust project detected. Publishing...");
-            let name = krate::Krate::new("./")?.name.replace("-", "_");
-            // TODO: move into build?
-            build_generated_dir()?;
-            concat_js(&name)?;
+            let artifacts = RustArtifacts::new("./")?;
+            artifacts.verify()?;
 
-            let path = PathBuf::from(format!("./pkg/{}_bg.wasm", name));
             let binding = "wasm".to_string();
-            let wasm_module = WasmModule::new(path, binding)?;
+            let wasm_module = WasmModule::new(artifacts.wasm_path, binding)?;
             wasm_modules.push(wasm_module);
-            let script_path = PathBuf::from("./worker/generated/script.js");
+            let script_path = artifacts.script_path;
 
             let assets = ServiceWorkerAssets {
                 script_path,
@@ -94,18 +152,27 @@ pub fn build(
                 durable_object_classes,
                 text_blobs,
                 plain_texts,
+                custom_bindings,
                 usage_model,
+                limits,
             };
 
-            service_worker::build_form(&assets, session_config)
+            Ok(Prepared {
+                assets: Assets::ServiceWorker(assets),
+            })
         }
-        TargetType::JavaScript => match &target.build {
+        TargetType::JavaScript | TargetType::TypeScript => match &target.build {
             Some(config) => match &config.upload {
-                UploadFormat::ServiceWorker {} => {
+                UploadFormat::ServiceWorker { main } => {
                     log::info!("Plain JavaScript project detected. Publishing...");
-                    let package_dir = target.package_dir()?;
-                    let package = Package::new(&package_dir)?;
-                    let script_path = package_dir.join(package.main(&package_dir)?);
+                    let script_path = match main {
+                        Some(main) => main.clone(),
+                        None => {
+                            let package_dir = target.package_dir()?;
+                            let package = Package::new(&package_dir)?;
+                            package_dir.join(package.main(&package_dir)?)
+                        }
+                    };
 
                     let assets = ServiceWorkerAssets {
                         script_path,
@@ -116,10 +183,14 @@ pub fn build(
                         durable_object_classes,
                         text_blobs,
                         plain_texts,
+                        custom_bindings,
                         usage_model,
+                        limits,
                     };
 
-                    service_worker::build_form(&assets, session_config)
+                    Ok(Prepared {
+                        assets: Assets::ServiceWorker(assets),
+                    })
                 }
                 UploadFormat::Modules { main, dir, rules } => {
                     let migration = match &target.migrations {
@@ -137,10 +208,14 @@ pub fn build(
                         migration,
                         text_blobs,
                         plain_texts,
+                        custom_bindings,
                         usage_model,
+                        limits,
                     )?;
 
-                    modules_worker::build_form(&assets, session_config)
+                    Ok(Prepared {
+                        assets: Assets::Modules(assets),
+                    })
                 }
             },
             None => {
@@ -158,10 +233,14 @@ pub fn build(
                     durable_object_classes,
                     text_blobs,
                     plain_texts,
+                    custom_bindings,
                     usage_model,
+                    limits,
                 };
 
-                service_worker::build_form(&assets, session_config)
+                Ok(Prepared {
+                    assets: Assets::ServiceWorker(assets),
+                })
             }
         },
         TargetType::Webpack => {
@@ -188,10 +267,51 @@ pub fn build(
                 durable_object_classes,
                 text_blobs,
                 plain_texts,
+                custom_bindings,
                 usage_model,
+                limits,
             };
 
-            service_worker::build_form(&assets, session_config)
+            Ok(Prepared {
+                assets: Assets::ServiceWorker(assets),
+            })
+        }
+    }
+}
+
+/// The scripts that go into the upload of `target`, leaving out its other modules
+pub fn script_paths(target: &Target) -> Result<Vec<PathBuf>> {
+    match &target.target_type {
+        TargetType::Rust => Ok(vec![RustArtifacts::new("./")?.script_path]),
+        TargetType::JavaScript | TargetType::TypeScript => {
+            match target.build.as_ref().map(|config| &config.upload) {
+                Some(UploadFormat::ServiceWorker { main: Some(main) }) => Ok(vec![main.clone()]),
+                Some(UploadFormat::Modules { main, dir, rules }) => {
+                    let manifest = ModuleConfig::new(main, dir, rules).get_modules()?;
+                    let mut paths: Vec<PathBuf> = manifest
+                        .modules
+                        .into_iter()
+                        .filter(|(_, module)| {
+                            matches!(
+                                module.module_type,
+                                ModuleType::ESModule | ModuleType::CommonJS
+                            )
+                        })
+                        .map(|(_, module)| module.path)
+                        .collect();
+                    paths.sort();
+                    Ok(paths)
+                }
+                _ => {
+                    let package_dir = target.package_dir()?;
+                    let package = Package::new(&package_dir)?;
+                    Ok(vec![package_dir.join(package.main(&package_dir)?)])
+                }
+            }
+        }
+        TargetType::Webpack => {
+            let bundle = wranglerjs::Bundle::new(&target.package_dir()?);
+            Ok(vec![bundle.script_path()])
         }
     }
 }
@@ -204,23 +324,3 @@ fn get_asset_manifest_blob(asset_manifest: AssetManifest) -> Result<String> {
 fn filestem_from_path(path: &Path) -> Option<String> {
     path.file_stem()?.to_str().map(|s| s.to_string())
 }
-
-fn build_generated_dir() -> Result<()> {
-    let dir = "./worker/generated";
-    if !Path::new(dir).is_dir() {
-        fs::create_dir("./worker/generated")?;
-    }
-
-    Ok(())
-}
-
-fn concat_js(name: &str) -> Result<()> {
-    let bindgen_js_path = format!("./pkg/{}.js", name);
-    let bindgen_js: String = fs::read_to_string(bindgen_js_path)?.parse()?;
-
-    let worker_js: String = fs::read_to_string("./worker/worker.js")?.parse()?;
-    let js = format!("{} {}", bindgen_js, worker_js);
-
-    fs::write("./worker/generated/script.js", js.as_bytes())?;
-    Ok(())
-}
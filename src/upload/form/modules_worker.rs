@@ -1,13 +1,12 @@
-use std::fs::File;
-
 use anyhow::Result;
-use reqwest::blocking::multipart::{Form, Part};
+use indicatif::ProgressBar;
+use reqwest::blocking::multipart::Form;
 use serde::Serialize;
 
 use crate::settings::binding::Binding;
 use crate::settings::toml::migrations::ApiMigration;
 
-use super::{ModulesAssets, UsageModel};
+use super::{streamed, Limits, ModulesAssets, UsageModel};
 
 #[derive(Serialize, Debug)]
 struct Metadata {
@@ -16,41 +15,49 @@ struct Metadata {
     pub migrations: Option<ApiMigration>,
     pub usage_model: Option<UsageModel>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub limits: Option<Limits>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub compatibility_date: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub compatibility_flags: Vec<String>,
 }
 
+/// Builds the form of the upload of `assets`, whose parts are read as it's sent and counted
+/// on `progress`, and returns it with the number of bytes the parts come to
 pub fn build_form(
     assets: &ModulesAssets,
     session_config: Option<serde_json::Value>,
-) -> Result<Form> {
+    progress: &ProgressBar,
+) -> Result<(Form, u64)> {
     let mut form = Form::new();
+    let mut parts = streamed::Parts::new(progress);
 
     // The preview service in particular streams the request form, and requires that the
     // "metadata" part be set first, so this order is important.
-    form = add_metadata(form, assets)?;
-    form = add_files(form, assets)?;
+    form = add_metadata(form, assets, &mut parts)?;
+    form = add_files(form, assets, &mut parts)?;
     if let Some(session_config) = session_config {
-        form = add_session_config(form, session_config)?
+        form = add_session_config(form, session_config, &mut parts)?
     }
 
     log::info!("building form");
     log::info!("{:#?}", &form);
 
-    Ok(form)
+    Ok((form, parts.length))
 }
 
-fn add_files(mut form: Form, assets: &ModulesAssets) -> Result<Form> {
+fn add_files(mut form: Form, assets: &ModulesAssets, parts: &mut streamed::Parts) -> Result<Form> {
     for (name, module) in &assets.manifest.modules {
-        let part = Part::reader(File::open(module.path.clone())?)
+        let part = parts
+            .file(&module.path)?
             .mime_str(module.module_type.content_type())?
             .file_name(name.clone());
         form = form.part(name.clone(), part);
     }
 
     for text_blob in &assets.text_blobs {
-        let part = Part::text(text_blob.data.clone())
+        let part = parts
+            .text(text_blob.data.clone())
             .file_name(text_blob.binding.clone())
             .mime_str("text/plain")?;
 
@@ -60,17 +67,23 @@ fn add_files(mut form: Form, assets: &ModulesAssets) -> Result<Form> {
     Ok(form)
 }
 
-fn add_metadata(mut form: Form, assets: &ModulesAssets) -> Result<Form> {
+fn add_metadata(
+    mut form: Form,
+    assets: &ModulesAssets,
+    parts: &mut streamed::Parts,
+) -> Result<Form> {
     let metadata_json = serde_json::json!(&Metadata {
         main_module: assets.manifest.main.clone(),
         bindings: assets.bindings(),
         migrations: assets.migration.clone(),
         usage_model: assets.usage_model,
+        limits: assets.limits,
         compatibility_date: assets.compatibility_date.clone(),
         compatibility_flags: assets.compatibility_flags.clone(),
     });
 
-    let metadata = Part::text(metadata_json.to_string())
+    let metadata = parts
+        .text(metadata_json.to_string())
         .file_name("metadata.json")
         .mime_str("application/json")?;
 
@@ -79,8 +92,13 @@ fn add_metadata(mut form: Form, assets: &ModulesAssets) -> Result<Form> {
     Ok(form)
 }
 
-fn add_session_config(mut form: Form, session_config: serde_json::Value) -> Result<Form> {
-    let wrangler_session_config = Part::text(session_config.to_string())
+fn add_session_config(
+    mut form: Form,
+    session_config: serde_json::Value,
+    parts: &mut streamed::Parts,
+) -> Result<Form> {
+    let wrangler_session_config = parts
+        .text(session_config.to_string())
         .file_name("")
         .mime_str("application/json")?;
 
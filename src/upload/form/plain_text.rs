@@ -1,4 +1,5 @@
 use super::binding::Binding;
+use crate::settings::encrypted_vars;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
@@ -6,14 +7,35 @@ use serde::{Deserialize, Serialize};
 pub struct PlainText {
     pub name: String,
     pub value: String,
+    /// Bound as a secret, so the value isn't shown in the dashboard
+    #[serde(default)]
+    pub secret: bool,
 }
 
 impl PlainText {
     pub fn new(name: String, value: String) -> Result<Self> {
-        Ok(Self { name, value })
+        Ok(Self {
+            name,
+            value,
+            secret: false,
+        })
+    }
+
+    /// A var of the configuration file, decrypted and bound as a secret if it was encrypted
+    /// with `wrangler vars encrypt`
+    pub fn from_var(name: String, value: &str) -> Result<Self> {
+        Ok(Self {
+            secret: encrypted_vars::is_encrypted(value),
+            value: encrypted_vars::decrypt(&name, value)?,
+            name,
+        })
     }
 
     pub fn binding(&self) -> Binding {
-        Binding::new_plain_text(self.name.clone(), self.value.clone())
+        if self.secret {
+            Binding::new_secret_text(self.name.clone(), self.value.clone())
+        } else {
+            Binding::new_plain_text(self.name.clone(), self.value.clone())
+        }
     }
 }
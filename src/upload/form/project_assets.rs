@@ -11,10 +11,10 @@ use super::filestem_from_path;
 use super::plain_text::PlainText;
 use super::text_blob::TextBlob;
 use super::wasm_module::WasmModule;
-use super::UsageModel;
+use super::{Limits, UsageModel};
 
 use crate::settings::toml::{
-    migrations::ApiMigration, DurableObjectsClass, KvNamespace, ModuleRule,
+    migrations::ApiMigration, CustomBinding, DurableObjectsClass, KvNamespace, ModuleRule,
 };
 use std::collections::{HashMap, HashSet};
 
@@ -28,7 +28,9 @@ pub struct ServiceWorkerAssets {
     pub durable_object_classes: Vec<DurableObjectsClass>,
     pub text_blobs: Vec<TextBlob>,
     pub plain_texts: Vec<PlainText>,
+    pub custom_bindings: Vec<CustomBinding>,
     pub usage_model: Option<UsageModel>,
+    pub limits: Option<Limits>,
 }
 
 impl ServiceWorkerAssets {
@@ -55,6 +57,10 @@ impl ServiceWorkerAssets {
             let binding = plain_text.binding();
             bindings.push(binding);
         }
+        for custom in &self.custom_bindings {
+            let binding = custom.binding();
+            bindings.push(binding);
+        }
 
         bindings
     }
@@ -320,7 +326,9 @@ pub struct ModulesAssets {
     pub migration: Option<ApiMigration>,
     pub text_blobs: Vec<TextBlob>,
     pub plain_texts: Vec<PlainText>,
+    pub custom_bindings: Vec<CustomBinding>,
     pub usage_model: Option<UsageModel>,
+    pub limits: Option<Limits>,
 }
 
 impl ModulesAssets {
@@ -334,7 +342,9 @@ impl ModulesAssets {
         migration: Option<ApiMigration>,
         text_blobs: Vec<TextBlob>,
         plain_texts: Vec<PlainText>,
+        custom_bindings: Vec<CustomBinding>,
         usage_model: Option<UsageModel>,
+        limits: Option<Limits>,
     ) -> Result<Self> {
         Ok(Self {
             compatibility_date,
@@ -345,7 +355,9 @@ impl ModulesAssets {
             migration,
             text_blobs,
             plain_texts,
+            custom_bindings,
             usage_model,
+            limits,
         })
     }
 
@@ -367,6 +379,10 @@ impl ModulesAssets {
             let binding = plain_text.binding();
             bindings.push(binding);
         }
+        for custom in &self.custom_bindings {
+            let binding = custom.binding();
+            bindings.push(binding);
+        }
 
         bindings
     }
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use reqwest::blocking::multipart::{Form, Part};
+use serde::Serialize;
+
+use crate::settings::binding::Binding;
+use crate::settings::toml::{Target, TargetType};
+
+use super::{Limits, PlainText, UsageModel};
+
+#[derive(Serialize, Debug)]
+struct Settings {
+    pub bindings: Vec<Binding>,
+    pub usage_model: Option<UsageModel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limits: Option<Limits>,
+}
+
+/// The form that updates the settings of the script of `target` without uploading it again.
+/// Bindings to files of the upload, like text blobs, Wasm modules and the asset manifest of a
+/// site, can only change along with the script, so there's no such form for a target with any.
+pub fn build_settings_form(target: &Target) -> Result<Form> {
+    if let Some(bound) = uploaded_bindings(target) {
+        anyhow::bail!(
+            "{} binds {}, which is uploaded with the script, so its configuration can't be published without it",
+            target.name,
+            bound
+        )
+    }
+    let settings = serde_json::json!(&Settings {
+        bindings: bindings(target)?,
+        usage_model: target.usage_model,
+        limits: target.limits,
+    });
+    let part = Part::text(settings.to_string())
+        .file_name("settings.json")
+        .mime_str("application/json")?;
+    Ok(Form::new().part("settings", part))
+}
+
+// What of `target` is bound to a file of the upload, if anything is
+fn uploaded_bindings(target: &Target) -> Option<&'static str> {
+    if target.target_type == TargetType::Rust {
+        Some("the Wasm module of its Rust build")
+    } else if target.site.is_some() {
+        Some("the asset manifest of its site")
+    } else if target.text_blobs.as_ref().map_or(false, |b| !b.is_empty()) {
+        Some("text blobs")
+    } else if target
+        .wasm_modules
+        .as_ref()
+        .map_or(false, |m| !m.is_empty())
+    {
+        Some("Wasm modules")
+    } else {
+        None
+    }
+}
+
+fn bindings(target: &Target) -> Result<Vec<Binding>> {
+    let mut bindings: Vec<Binding> = target.kv_namespaces.iter().map(|kv| kv.binding()).collect();
+    if let Some(classes) = target
+        .durable_objects
+        .as_ref()
+        .and_then(|d| d.classes.as_ref())
+    {
+        bindings.extend(classes.iter().map(|class| class.binding()));
+    }
+    for (name, value) in target.vars.iter().flatten() {
+        bindings.push(PlainText::from_var(name.clone(), value)?.binding());
+    }
+    if let Some(custom_bindings) = &target.bindings {
+        bindings.extend(custom_bindings.iter().map(|custom| custom.binding()));
+    }
+    Ok(bindings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::{KvNamespace, Site};
+
+    #[test]
+    fn it_only_updates_bindings_that_arent_uploaded() {
+        let mut target = Target {
+            name: "worker".to_string(),
+            kv_namespaces: vec![KvNamespace {
+                id: "1234".to_string(),
+                binding: "CACHE".to_string(),
+            }],
+            vars: Some(
+                vec![("MODE".to_string(), "incident".to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(bindings(&target).unwrap()).unwrap(),
+            serde_json::json!([
+                { "type": "kv_namespace", "name": "CACHE", "namespace_id": "1234" },
+                { "type": "plain_text", "name": "MODE", "text": "incident" },
+            ])
+        );
+        assert!(build_settings_form(&target).is_ok());
+
+        target.site = Some(Site::default());
+        assert!(build_settings_form(&target).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+const BASE64_DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Groups the bytes of a bundle that aren't mapped to any source
+const UNMAPPED: &str = "(unmapped)";
+
+#[derive(Deserialize)]
+struct SourceMap {
+    sources: Vec<String>,
+    mappings: String,
+}
+
+/// How many bytes of the bundled script at `script_path` each module it was built from takes
+/// up, going by its source map. The files of a package in node_modules count as one module.
+/// `None` when the script has no source map wrangler can read.
+pub(super) fn module_sizes(script_path: &Path) -> Option<BTreeMap<String, u64>> {
+    let sizes = fs::read_to_string(script_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|script| match read_map(&script, script_path)? {
+            Some(map) => Ok(Some(sizes(&script, &map)?)),
+            None => Ok(None),
+        });
+    match sizes {
+        Ok(sizes) => sizes,
+        Err(e) => {
+            log::info!(
+                "Could not read the source map of {}: {}",
+                script_path.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+// The map a `sourceMappingURL` comment points to, inline or next to the script, or else
+// `<script>.map`
+fn read_map(script: &str, script_path: &Path) -> Result<Option<SourceMap>> {
+    let url = script
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix("//# sourceMappingURL="));
+    let contents = match url {
+        Some(url) if url.starts_with("data:") => match url.split_once(";base64,") {
+            Some((_, data)) => base64::decode(data)?,
+            None => url
+                .split_once(',')
+                .unwrap_or_default()
+                .1
+                .as_bytes()
+                .to_vec(),
+        },
+        Some(url) => fs::read(script_path.with_file_name(url))?,
+        None => {
+            let mut path = script_path.as_os_str().to_owned();
+            path.push(".map");
+            match fs::read(path) {
+                Ok(contents) => contents,
+                Err(_) => return Ok(None),
+            }
+        }
+    };
+    Ok(Some(serde_json::from_slice(&contents)?))
+}
+
+// Every segment of the mappings covers the generated code up to the next one on its line
+fn sizes(script: &str, map: &SourceMap) -> Result<BTreeMap<String, u64>> {
+    let mut sizes = BTreeMap::new();
+    let mut mapped = 0;
+    let mut source = 0;
+    for (line, mappings) in script.split('\n').zip(map.mappings.split(';')) {
+        // columns count UTF-16 code units
+        let offsets: Option<Vec<usize>> = if line.is_ascii() {
+            None
+        } else {
+            Some(
+                line.char_indices()
+                    .flat_map(|(offset, c)| std::iter::repeat(offset).take(c.len_utf16()))
+                    .collect(),
+            )
+        };
+        let offset = |column: i64| match &offsets {
+            None => (column as usize).min(line.len()),
+            Some(offsets) => offsets.get(column as usize).copied().unwrap_or(line.len()),
+        };
+
+        let mut column = 0;
+        let mut segments = Vec::new();
+        for segment in mappings.split(',').filter(|segment| !segment.is_empty()) {
+            let fields = decode_vlq(segment)?;
+            column += fields[0];
+            let segment_source = match fields.len() {
+                1 => None,
+                _ => {
+                    source += fields[1];
+                    Some(source)
+                }
+            };
+            segments.push((offset(column), segment_source));
+        }
+
+        for (i, (start, segment_source)) in segments.iter().enumerate() {
+            let end = segments.get(i + 1).map_or(line.len(), |next| next.0);
+            let file = match segment_source.and_then(|s| map.sources.get(s as usize)) {
+                Some(file) => file,
+                None => continue,
+            };
+            let size = end.saturating_sub(*start) as u64;
+            *sizes.entry(module_name(file)).or_insert(0) += size;
+            mapped += size;
+        }
+    }
+
+    let unmapped = script.len() as u64 - mapped;
+    if unmapped > 0 {
+        sizes.insert(UNMAPPED.to_string(), unmapped);
+    }
+    Ok(sizes)
+}
+
+fn decode_vlq(segment: &str) -> Result<Vec<i64>> {
+    let mut fields = Vec::new();
+    let (mut value, mut shift) = (0i64, 0);
+    for c in segment.bytes() {
+        let digit = BASE64_DIGITS
+            .iter()
+            .position(|&d| d == c)
+            .ok_or_else(|| anyhow!("Invalid mapping \"{}\"", segment))? as i64;
+        if shift > 60 {
+            anyhow::bail!("Invalid mapping \"{}\"", segment);
+        }
+        value += (digit & 31) << shift;
+        if digit & 32 == 0 {
+            fields.push(if value & 1 == 1 {
+                -(value >> 1)
+            } else {
+                value >> 1
+            });
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 5;
+        }
+    }
+    if fields.is_empty() || shift != 0 {
+        anyhow::bail!("Invalid mapping \"{}\"", segment);
+    }
+    Ok(fields)
+}
+
+// Like "lodash" or "@cloudflare/kv-asset-handler" for a file of a package, and like
+// "src/index.js" for the project's own files
+fn module_name(source: &str) -> String {
+    let path = source.split("://").last().unwrap_or(source);
+    if let Some(index) = path.rfind("node_modules/") {
+        let mut components = path[index + "node_modules/".len()..].split('/');
+        let package = components.next().unwrap_or_default();
+        return match (package.starts_with('@'), components.next()) {
+            (true, Some(name)) => format!("{}/{}", package, name),
+            _ => package.to_string(),
+        };
+    }
+    // webpack names sources like webpack://<project>/./src/index.js
+    let path = path.rsplit("/./").next().unwrap_or(path);
+    path.trim_start_matches('/')
+        .trim_start_matches("./")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_decodes_vlq_fields() {
+        assert_eq!(decode_vlq("AAAA").unwrap(), vec![0, 0, 0, 0]);
+        assert_eq!(decode_vlq("SAAS").unwrap(), vec![9, 0, 0, 9]);
+        assert_eq!(decode_vlq("D").unwrap(), vec![-1]);
+        assert_eq!(decode_vlq("gB").unwrap(), vec![16]);
+        assert!(decode_vlq("g").is_err());
+        assert!(decode_vlq("A!").is_err());
+    }
+
+    #[test]
+    fn it_names_modules_after_their_package() {
+        assert_eq!(
+            module_name("webpack:///./node_modules/lodash/lodash.js"),
+            "lodash"
+        );
+        assert_eq!(
+            module_name("../node_modules/@cloudflare/kv-asset-handler/dist/index.js"),
+            "@cloudflare/kv-asset-handler"
+        );
+        assert_eq!(
+            module_name("webpack://worker/./src/index.js"),
+            "src/index.js"
+        );
+        assert_eq!(module_name("src/index.js"), "src/index.js");
+    }
+
+    #[test]
+    fn it_attributes_the_bundle_to_its_modules() {
+        // "var a=1;" comes from src/index.js, "var b=2;" from the lodash package
+        let script = "var a=1;var b=2;\n//# sourceMappingURL=worker.js.map";
+        let map = SourceMap {
+            sources: vec![
+                "src/index.js".to_string(),
+                "node_modules/lodash/lodash.js".to_string(),
+            ],
+            mappings: "AAAA,QCAA".to_string(),
+        };
+
+        let sizes = sizes(script, &map).unwrap();
+        assert_eq!(sizes["src/index.js"], 8);
+        assert_eq!(sizes["lodash"], 8);
+        assert_eq!(sizes[UNMAPPED], script.len() as u64 - 16);
+    }
+
+    #[test]
+    fn it_finds_the_source_map_of_a_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let script_path = dir.path().join("worker.js");
+        std::fs::write(&script_path, "var a=1;").unwrap();
+        assert!(module_sizes(&script_path).is_none());
+
+        std::fs::write(
+            dir.path().join("worker.js.map"),
+            r#"{"version":3,"sources":["src/index.js"],"mappings":"AAAA"}"#,
+        )
+        .unwrap();
+        let sizes = module_sizes(&script_path).unwrap();
+        assert_eq!(sizes["src/index.js"], 8);
+    }
+}
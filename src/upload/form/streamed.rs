@@ -0,0 +1,95 @@
+use std::fs::File;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+
+use anyhow::Result;
+use indicatif::ProgressBar;
+use reqwest::blocking::multipart::Part;
+
+/// Makes the parts of an upload that count the bytes sent on `progress`, and adds up how many
+/// bytes they come to
+pub(super) struct Parts<'a> {
+    progress: &'a ProgressBar,
+    pub(super) length: u64,
+}
+
+impl<'a> Parts<'a> {
+    pub(super) fn new(progress: &'a ProgressBar) -> Self {
+        Parts {
+            progress,
+            length: 0,
+        }
+    }
+
+    /// A part of the upload read from the file at `path` as the request goes out, rather
+    /// than loaded beforehand. Like `Form::file`, it's named after the file and typed by its
+    /// extension.
+    pub(super) fn file(&mut self, path: &Path) -> Result<Part> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        self.length += len;
+        let mut part = Part::reader_with_length(Counted::new(file, self.progress), len)
+            .mime_str(mime_guess::from_path(path).first_or_octet_stream().as_ref())?;
+        if let Some(file_name) = path.file_name() {
+            part = part.file_name(file_name.to_string_lossy().into_owned());
+        }
+        Ok(part)
+    }
+
+    /// A part of the upload from `data`, counted like the files are
+    pub(super) fn text(&mut self, data: String) -> Part {
+        let len = data.len() as u64;
+        self.length += len;
+        Part::reader_with_length(
+            Counted::new(Cursor::new(data.into_bytes()), self.progress),
+            len,
+        )
+    }
+}
+
+// Moves a progress bar along by what is read through it
+struct Counted<R> {
+    inner: R,
+    progress: ProgressBar,
+}
+
+impl<R> Counted<R> {
+    fn new(inner: R, progress: &ProgressBar) -> Self {
+        Counted {
+            inner,
+            progress: progress.clone(),
+        }
+    }
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.progress.inc(read as u64);
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_counts_what_is_read() {
+        let progress = ProgressBar::hidden();
+        let mut counted = Counted::new(Cursor::new(vec![7u8; 5000]), &progress);
+        let mut read = Vec::new();
+        counted.read_to_end(&mut read).unwrap();
+        assert_eq!(read.len(), 5000);
+        assert_eq!(progress.position(), 5000);
+    }
+
+    #[test]
+    fn it_adds_up_the_length_of_the_parts() {
+        let progress = ProgressBar::hidden();
+        let mut parts = Parts::new(&progress);
+        parts.text("{\"bindings\":[]}".to_string());
+        parts.text("hello".to_string());
+        assert_eq!(parts.length, 20);
+    }
+}
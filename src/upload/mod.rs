@@ -1,58 +1,168 @@
 pub mod form;
-mod krate;
+pub(crate) mod krate;
 pub mod package;
 
-use indicatif::{ProgressBar, ProgressStyle};
+use std::fs;
+use std::path::{Path, PathBuf};
+
 pub use package::Package;
 
 use anyhow::Result;
-use reqwest::blocking::Client;
+use sha2::{Digest, Sha256};
 
-use crate::settings::toml::Target;
+use crate::build::cache::CACHE_DIR;
+use crate::error::{api_guidance, WranglerError};
+use crate::http::CloudflareApi;
+use crate::settings::toml::migrations::Migrations;
+use crate::settings::toml::{self, Target};
 use crate::sites::AssetManifest;
+use crate::sourcemap;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::progress::{self, Phase, ProgressEvent};
+use crate::terminal::ui;
+use form::BundleSize;
 
+/// Uploads the script of `target`. With `if_changed`, the upload is skipped when it is the
+/// same as the last one from this project.
 pub fn script(
-    client: &Client,
+    api: &impl CloudflareApi,
     target: &Target,
     asset_manifest: Option<AssetManifest>,
+    analyze: bool,
+    if_changed: bool,
 ) -> Result<()> {
-    let worker_addr = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
-        target.account_id.load()?,
-        target.name,
-    );
+    let prepared = form::prepare(target, asset_manifest)?;
+    let bundle_size = prepared.size()?;
+    bundle_size.report();
+    if analyze {
+        bundle_size.print_breakdown();
+    }
+
+    let fingerprint = fingerprint(target, &bundle_size)?;
+    let last_upload = last_upload_path(target);
+    if let (true, Some(fingerprint)) = (if_changed, &fingerprint) {
+        if fs::read_to_string(&last_upload).ok().as_ref() == Some(fingerprint) {
+            StdErr::info("The script is the same as the last one uploaded, skipping the upload");
+            return Ok(());
+        }
+    }
+
+    // the form is read from the files as it's sent, so it counts on the bar as it goes
+    let bar = ui::upload_bar(0);
+    let (script_upload_form, length) = prepared.form(None, &bar)?;
+    bar.set_length(length);
+    bar.set_message("Uploading script");
+
+    let uploaded = api.upload_script(target.account_id.load()?, &target.name, script_upload_form);
+    bar.finish_and_clear();
+    uploaded.map_err(upload_error)?;
 
-    let script_upload_form = form::build(target, asset_manifest, None)?;
+    // failing to remember the upload only means the next one won't be skipped
+    if let Some(fingerprint) = fingerprint {
+        if let Err(e) = remember_upload(&last_upload, &fingerprint) {
+            log::info!("failed to write {}: {}", last_upload.display(), e);
+        }
+    }
+    // without them `wrangler tail --symbolicate` only has older ones to go by
+    if let Err(e) = sourcemap::store(target) {
+        log::info!("failed to store the source maps of {}: {}", target.name, e);
+    }
+
+    let files = bundle_size.parts.len();
+    progress::emit(ProgressEvent::Uploaded {
+        phase: Phase::UploadScript,
+        files,
+        total_files: files,
+        bytes: bundle_size.size(),
+        total_bytes: bundle_size.size(),
+    });
+
+    Ok(())
+}
 
-    let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
-    let spinner = ProgressBar::new_spinner().with_style(style);
-    spinner.set_message("Uploading script...");
-    spinner.enable_steady_tick(20);
+/// Updates the bindings of the script of `target`, which has to be published already,
+/// without uploading the script again
+pub fn settings(api: &impl CloudflareApi, target: &Target) -> Result<()> {
+    let settings_form = form::build_settings_form(target)?;
+    api.update_settings(target.account_id.load()?, &target.name, settings_form)
+        .map_err(upload_error)
+}
 
-    let res = client
-        .put(&worker_addr)
-        .multipart(script_upload_form)
-        .send()?;
+// A hash of everything that goes into the upload of `target`, or `None` when there's more to
+// it than the configuration file and the files of the bundle
+fn fingerprint(target: &Target, bundle_size: &BundleSize) -> Result<Option<String>> {
+    // migrations passed on the command line aren't in the configuration file
+    if let Some(Migrations::Adhoc { .. }) = target.migrations {
+        return Ok(None);
+    }
+    let config_path = match toml::config_path() {
+        Some(config_path) => config_path,
+        None => return Ok(None),
+    };
 
-    spinner.finish_and_clear();
+    let mut hasher = Sha256::new();
+    hasher.update(target.account_id.load()?.as_bytes());
+    hasher.update(target.name.as_bytes());
+    hasher.update(fs::read(config_path)?);
+    // not every var comes from the configuration file, like the commit in WRANGLER_GIT_SHA
+    let mut vars: Vec<_> = target.vars.iter().flatten().collect();
+    vars.sort();
+    for (name, value) in vars {
+        hasher.update(name.as_bytes());
+        hasher.update(value.as_bytes());
+    }
+    for part in &bundle_size.parts {
+        hasher.update(part.name.as_bytes());
+        hasher.update(part.digest.as_bytes());
+    }
+    Ok(Some(hex::encode(hasher.finalize())))
+}
 
-    if !res.status().is_success() {
-        anyhow::bail!(error_msg(res.text()?))
+/// A hash of the files of the last build of `target`, which tells one build from another
+/// whatever worker it's published as
+pub fn build_digest(target: &Target) -> Result<String> {
+    let bundle_size = form::prepare(target, None)?.size()?;
+    let mut hasher = Sha256::new();
+    for part in &bundle_size.parts {
+        hasher.update(part.name.as_bytes());
+        hasher.update(part.digest.as_bytes());
     }
+    Ok(hex::encode(hasher.finalize()))
+}
 
+fn last_upload_path(target: &Target) -> PathBuf {
+    Path::new(CACHE_DIR).join("uploads").join(&target.name)
+}
+
+fn remember_upload(path: &Path, fingerprint: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, fingerprint)?;
     Ok(())
 }
 
-fn error_msg(text: String) -> String {
-    if text.contains("\"code\": 10034,") {
-        "You need to verify your account's email address before you can publish. You can do this by checking your email or logging in to https://dash.cloudflare.com.".into()
-    } else if text.contains("\"code\": 10000,") {
-        "Your user configuration is invalid, please run wrangler login or wrangler config and enter a new set of credentials.".into()
-    } else if text.contains("\"code\": 10075,") {
-        "Setting a Usage Model requires a Paid plan with Unbound enabled. You can do this in the dash by logging in to https://dash.cloudflare.com/?account=workers/plans".into()
-    } else {
-        crate::format_api_errors(text)
+// Replaces the API errors a user can act upon with an explanation of what to do
+fn upload_error(error: anyhow::Error) -> anyhow::Error {
+    match error.downcast::<WranglerError>() {
+        Ok(error) => actionable_error(error).into(),
+        Err(error) => error,
+    }
+}
+
+fn actionable_error(error: WranglerError) -> WranglerError {
+    let actionable: [(u16, fn(String) -> WranglerError); 4] = [
+        (10034, WranglerError::Auth),
+        (10000, WranglerError::Auth),
+        (10027, WranglerError::Build),
+        (10075, WranglerError::Config),
+    ];
+    for (code, kind) in &actionable {
+        if let (true, Some(guidance)) = (error.has_api_code(*code), api_guidance(*code)) {
+            return kind(guidance.to_string());
+        }
     }
+    error
 }
 
 #[test]
@@ -69,6 +179,10 @@ fn fails_with_good_error_msg_on_verify_email_err() {
   "messages": []
 }"#
     .to_string();
-    let result = error_msg(text);
-    assert!(result.contains("https://dash.cloudflare.com"));
+    let result = actionable_error(WranglerError::from_api_response(
+        reqwest::StatusCode::FORBIDDEN,
+        text,
+    ));
+    assert!(matches!(result, WranglerError::Auth(_)));
+    assert!(result.to_string().contains("https://dash.cloudflare.com"));
 }
@@ -11,6 +11,8 @@ const PACKAGE_JSON_KEY_ERROR_MODULE: &str = "The `module` key in your `package.j
 pub struct Package {
     #[serde(default)]
     main: PathBuf,
+    #[serde(default)]
+    module: PathBuf,
 }
 impl Package {
     pub fn main(&self, package_dir: &Path) -> Result<PathBuf> {
@@ -25,6 +27,20 @@ impl Package {
             Ok(self.main.clone())
         }
     }
+
+    /// The entry point of a worker uploaded as ES modules
+    pub fn module(&self, package_dir: &Path) -> Result<PathBuf> {
+        if self.module == PathBuf::from("") {
+            anyhow::bail!(PACKAGE_JSON_KEY_ERROR_MODULE)
+        } else if !package_dir.join(&self.module).exists() {
+            anyhow::bail!(
+                "The entrypoint of your Worker ({}) could not be found.",
+                self.module.display()
+            )
+        } else {
+            Ok(self.module.clone())
+        }
+    }
 }
 
 impl Package {
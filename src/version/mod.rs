@@ -3,7 +3,8 @@ use std::path::Path;
 use std::str::FromStr;
 use std::time::SystemTime;
 
-use crate::settings::get_wrangler_home_dir;
+use crate::http::SendLogged;
+use crate::settings::{get_wrangler_home_dir, update_check_enabled};
 use crate::terminal::message::{Message, StdOut};
 use crate::terminal::styles;
 
@@ -14,7 +15,13 @@ use serde::{Deserialize, Serialize};
 
 const ONE_DAY: u64 = 60 * 60 * 24;
 
+/// Tells about a newer version of wrangler on crates.io, asking it at most once a day. The
+/// global configuration file turns this off with `update_check = false`.
 pub fn check_for_updates() {
+    if !update_check_enabled() {
+        log::debug!("update checks are turned off in the global configuration file");
+        return;
+    }
     match check_wrangler_versions() {
         Ok(wrangler_versions) => {
             // If the wrangler version has not been checked within the last day and the versions
@@ -54,7 +61,7 @@ struct WranglerVersion {
 
 impl WranglerVersion {
     pub fn is_outdated(&self) -> bool {
-        !self.checked && (self.current != self.latest)
+        !self.checked && self.current < self.latest
     }
 }
 
@@ -129,6 +136,9 @@ fn get_latest_version(
     current_time: SystemTime,
 ) -> Result<Version> {
     let latest_version = get_latest_version_from_api(installed_version)?;
+    if let Some(config_dir) = version_file.parent() {
+        fs::create_dir_all(config_dir)?;
+    }
     let updated_file_contents = toml::to_string(&LastCheckedVersion {
         latest_version: latest_version.to_string(),
         last_checked: current_time,
@@ -148,11 +158,17 @@ fn get_latest_version_from_api(installed_version: &str) -> Result<Version> {
     let response = client
         .get(url)
         .header(USER_AGENT, user_agent)
-        .send()?
+        .send_logged()?
         .error_for_status()?;
     let text = response.text()?;
     let crt: ApiResponse = serde_json::from_str(&text)?;
-    let version = Version::parse(&crt.info.max_version)?;
+    // pre-releases are only for those who went looking for them
+    let version = Version::parse(
+        crt.info
+            .max_stable_version
+            .as_ref()
+            .unwrap_or(&crt.info.max_version),
+    )?;
     Ok(version)
 }
 
@@ -165,4 +181,27 @@ struct ApiResponse {
 #[derive(Deserialize, Debug)]
 struct CrateInformation {
     max_version: String,
+    max_stable_version: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_is_outdated_only_behind_the_latest_version() {
+        let version = |current: &str, latest: &str| WranglerVersion {
+            current: Version::parse(current).unwrap(),
+            latest: Version::parse(latest).unwrap(),
+            checked: false,
+        };
+        assert!(version("1.19.0", "1.19.1").is_outdated());
+        assert!(!version("1.19.1", "1.19.1").is_outdated());
+        assert!(!version("1.20.0-rc.1", "1.19.1").is_outdated());
+        assert!(!WranglerVersion {
+            checked: true,
+            ..version("1.19.0", "1.19.1")
+        }
+        .is_outdated());
+    }
 }
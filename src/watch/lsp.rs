@@ -0,0 +1,117 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+use serde_json::json;
+use url::Url;
+
+// The severities of the Language Server Protocol
+const ERROR: u8 = 1;
+const WARNING: u8 = 2;
+
+/// A problem found while watching, in the shape of an LSP diagnostic so editor plugins can
+/// show it inline. Lines and characters are zero based.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub range: Range,
+    pub severity: u8,
+    pub source: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub character: usize,
+}
+
+impl Diagnostic {
+    /// An error at `line` and `character`, or at the start of the file
+    pub fn error(message: String, position: Option<(usize, usize)>) -> Self {
+        Diagnostic::new(ERROR, message, position)
+    }
+
+    /// A warning about the build, at the start of the file
+    pub fn warning(message: String) -> Self {
+        Diagnostic::new(WARNING, message, None)
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == ERROR
+    }
+
+    fn new(severity: u8, message: String, position: Option<(usize, usize)>) -> Self {
+        let (line, character) = position.unwrap_or_default();
+        Diagnostic {
+            range: Range {
+                start: Position { line, character },
+                end: Position {
+                    line,
+                    character: character + 1,
+                },
+            },
+            severity,
+            source: "wrangler",
+            message,
+        }
+    }
+
+    /// The error of a configuration file that failed to load. Syntax errors point at where
+    /// the configuration file stops being valid TOML.
+    pub fn config_error(contents: &str, error: &anyhow::Error) -> Self {
+        let position = toml::from_str::<toml::Value>(contents)
+            .err()
+            .and_then(|e| e.line_col());
+        Diagnostic::error(error.to_string(), position)
+    }
+}
+
+/// Writes a `textDocument/publishDiagnostics` notification for `uri` to stdout, with the
+/// Content-Length header that frames messages of the Language Server Protocol. Publishing
+/// no diagnostics clears the ones published before.
+pub fn publish(uri: &Url, diagnostics: &[Diagnostic]) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(frame(uri, diagnostics).as_bytes())?;
+    stdout.flush()
+}
+
+fn frame(uri: &Url, diagnostics: &[Diagnostic]) -> String {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri.as_str(), "diagnostics": diagnostics },
+    })
+    .to_string();
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_points_at_toml_syntax_errors() {
+        let contents = "name = \"worker\"\ntype = javascript\n";
+        let diagnostic = Diagnostic::config_error(contents, &anyhow::anyhow!("invalid TOML"));
+        assert_eq!(diagnostic.range.start.line, 1);
+        assert_eq!(diagnostic.severity, ERROR);
+    }
+
+    #[test]
+    fn it_frames_notifications() {
+        let uri = Url::parse("file:///project/wrangler.toml").unwrap();
+        let framed = frame(&uri, &[]);
+        let (header, body) = framed.split_once("\r\n\r\n").unwrap();
+        assert_eq!(header, format!("Content-Length: {}", body.len()));
+
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["method"], "textDocument/publishDiagnostics");
+        assert_eq!(body["params"]["uri"], "file:///project/wrangler.toml");
+        assert_eq!(body["params"]["diagnostics"], json!([]));
+    }
+}
@@ -1,19 +1,26 @@
+pub mod lsp;
 mod watcher;
 use ignore::overrides::OverrideBuilder;
 use ignore::WalkBuilder;
 pub use watcher::wait_for_changes;
 
-use crate::settings::toml::{Target, TargetType};
-use crate::terminal::message::{Message, StdOut};
+use crate::build::{Build, BuildEvent};
+use crate::build_target;
+use crate::settings::toml::{Manifest, Target, TargetType};
+use crate::terminal::message::{Message, StdErr, StdOut};
 use crate::wranglerjs;
-use crate::{build::command, build_target};
 use crate::{commands, install};
 
 use anyhow::Result;
-use notify::{self, RecursiveMode, Watcher};
-use std::sync::mpsc::{self, SendError, Sender};
+use lsp::Diagnostic;
+use notify::{self, DebouncedEvent, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Component, Path};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
 use std::thread;
 use std::time::Duration;
+use url::Url;
 
 pub const COOLDOWN_PERIOD: Duration = Duration::from_millis(2000);
 const JAVASCRIPT_PATH: &str = "./";
@@ -21,6 +28,124 @@ const RUST_PATH: &str = "./";
 
 // Paths to ignore live watching in Rust Workers
 const RUST_IGNORE: &[&str] = &["pkg", "target", "worker/generated"];
+// Directories builds write to or that only change with them, which would otherwise
+// trigger another build after every build
+const BUILD_OUTPUT_DIRS: &[&str] = &[".git", "node_modules", "dist", "pkg", "target", "worker"];
+
+/// How `wrangler build --watch` reports the result of each build.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WatchOutput {
+    /// Messages for humans
+    Plain,
+    /// `textDocument/publishDiagnostics` notifications of the Language Server Protocol on
+    /// stdout, for editor plugins
+    Lsp,
+}
+
+impl FromStr for WatchOutput {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(WatchOutput::Plain),
+            "lsp" => Ok(WatchOutput::Lsp),
+            _ => anyhow::bail!("Invalid output; must be either \"plain\" or \"lsp\""),
+        }
+    }
+}
+
+/// Loads the configuration file and builds the worker every time the project changes,
+/// reporting problems with either as `output`. Webpack and Rust builds print their own
+/// output, which `WatchOutput::Lsp` can't capture.
+pub fn watch_and_check(
+    config_path: &Path,
+    environment: Option<&str>,
+    output: WatchOutput,
+) -> Result<()> {
+    let (watcher_tx, watcher_rx) = mpsc::channel();
+    let mut watcher = notify::watcher(watcher_tx, Duration::from_secs(1))?;
+    watcher.watch(".", RecursiveMode::Recursive)?;
+    let uri = Url::from_file_path(config_path.canonicalize()?)
+        .map_err(|_| anyhow::anyhow!("{} can't be a file URL", config_path.display()))?;
+
+    loop {
+        let diagnostics = check(config_path, environment);
+        match output {
+            WatchOutput::Lsp => lsp::publish(&uri, &diagnostics)?,
+            WatchOutput::Plain => {
+                for diagnostic in &diagnostics {
+                    if diagnostic.is_error() {
+                        StdOut::user_error(&diagnostic.message);
+                    } else {
+                        StdErr::warn(&diagnostic.message);
+                    }
+                }
+            }
+        }
+        wait_for_source_changes(&watcher_rx)?;
+    }
+}
+
+fn check(config_path: &Path, environment: Option<&str>) -> Vec<Diagnostic> {
+    let contents = match fs::read_to_string(config_path) {
+        Ok(contents) => contents,
+        Err(e) => return vec![Diagnostic::error(e.to_string(), None)],
+    };
+    let loaded = Manifest::new(config_path).and_then(|manifest| {
+        let target = manifest.get_target(environment, false)?;
+        Ok((manifest, target))
+    });
+    let (manifest, target) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => return vec![Diagnostic::config_error(&contents, &e)],
+    };
+
+    let routes = manifest.route_patterns(environment).unwrap_or_default();
+    let (events_tx, events_rx) = mpsc::channel();
+    let result = Build::new(&target)
+        .with_routes(&routes)
+        .with_events(events_tx)
+        .run();
+    let mut diagnostics: Vec<Diagnostic> = events_rx
+        .try_iter()
+        .filter_map(|event| match event {
+            BuildEvent::Warning(warning) => Some(Diagnostic::warning(warning)),
+            _ => None,
+        })
+        .collect();
+    match result {
+        Ok(output) => StdOut::success(&output),
+        Err(e) => diagnostics.push(Diagnostic::error(e.to_string(), None)),
+    }
+    diagnostics
+}
+
+fn wait_for_source_changes(rx: &Receiver<DebouncedEvent>) -> Result<()> {
+    loop {
+        let path = match rx.recv()? {
+            DebouncedEvent::Create(path)
+            | DebouncedEvent::Write(path)
+            | DebouncedEvent::Remove(path)
+            | DebouncedEvent::Rename(_, path) => path,
+            _ => continue,
+        };
+        if !is_build_output(&path) {
+            while rx.recv_timeout(COOLDOWN_PERIOD).is_ok() {}
+            return Ok(());
+        }
+    }
+}
+
+fn is_build_output(path: &Path) -> bool {
+    let root = std::env::current_dir()
+        .and_then(|dir| dir.canonicalize())
+        .unwrap_or_default();
+    let path = path.strip_prefix(&root).unwrap_or(path);
+    path.components().any(|component| match component {
+        Component::Normal(name) => BUILD_OUTPUT_DIRS.iter().any(|dir| name == *dir),
+        _ => false,
+    })
+}
 
 // watch a project for changes and re-build it when necessary,
 // outputting a build event to tx.
@@ -32,7 +157,7 @@ pub fn watch_and_build(
     let target_type = &target.target_type;
     let build = target.build.clone();
     match target_type {
-        TargetType::JavaScript => {
+        TargetType::JavaScript | TargetType::TypeScript => {
             let target = target.clone();
             thread::spawn::<_, Result<()>>(move || {
                 let (watcher_tx, watcher_rx) = mpsc::channel();
@@ -96,7 +221,6 @@ pub fn watch_and_build(
         }
         TargetType::Rust => {
             let binary_path = install::install_wasm_pack()?;
-            let args = ["build", "--target", "no-modules"];
 
             thread::spawn::<_, Result<()>>(move || {
                 let (watcher_tx, watcher_rx) = mpsc::channel();
@@ -132,9 +256,7 @@ pub fn watch_and_build(
                         COOLDOWN_PERIOD,
                     ) {
                         Ok(_path) => {
-                            let command = command(&args, &binary_path);
-                            let command_name = format!("{:?}", command);
-                            if commands::run(command, &command_name).is_ok() {
+                            if commands::build::rust_with_wasm_pack(&binary_path).is_ok() {
                                 if let Some(tx) = tx.clone() {
                                     tx.send(())?;
                                 }
@@ -44,10 +44,9 @@ impl Bundle {
             wasm_file.write_all(&wasm)?;
         }
 
-        if self.has_wasm() {
-            script_file.write_all(
-                format!(
-                    r#"
+        let prelude = if self.has_wasm() {
+            format!(
+                r#"
                         WebAssembly.instantiateStreaming =
                             async function instantiateStreaming(req, importObject) {{
                           const module = {};
@@ -57,13 +56,30 @@ impl Bundle {
                           }}
                         }};
                     "#,
-                    self.get_wasm_binding()
-                )
-                .as_bytes(),
-            )?;
-        }
+                self.get_wasm_binding()
+            )
+        } else {
+            String::new()
+        };
+        script_file.write_all(prelude.as_bytes())?;
         script_file.write_all(wranglerjs_output.script.as_bytes())?;
 
+        match &wranglerjs_output.sourcemap {
+            Some(sourcemap) => self.write_sourcemap(sourcemap, &prelude)?,
+            None if self.sourcemap_path().exists() => fs::remove_file(self.sourcemap_path())?,
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    // The prelude pushes the script down, so the map skips a generated line for each of its
+    fn write_sourcemap(&self, sourcemap: &str, prelude: &str) -> Result<()> {
+        let mut sourcemap: serde_json::Value = serde_json::from_str(sourcemap)?;
+        if let Some(serde_json::Value::String(mappings)) = sourcemap.get_mut("mappings") {
+            mappings.insert_str(0, &";".repeat(prelude.matches('\n').count()));
+        }
+        fs::write(self.sourcemap_path(), serde_json::to_vec(&sourcemap)?)?;
         Ok(())
     }
 
@@ -82,6 +98,10 @@ impl Bundle {
     pub fn script_path(&self) -> PathBuf {
         PathBuf::from(&self.out).join("script.js")
     }
+
+    pub fn sourcemap_path(&self) -> PathBuf {
+        PathBuf::from(&self.out).join("script.js.map")
+    }
 }
 
 #[cfg(test)]
@@ -107,6 +127,7 @@ mod tests {
             errors: vec![],
             script: "foo".to_string(),
             wasm: None,
+            sourcemap: None,
         };
         let bundle = Bundle::new_at(out.clone());
 
@@ -124,6 +145,7 @@ mod tests {
             errors: vec![],
             script: "".to_string(),
             wasm: Some("abc".to_string()),
+            sourcemap: None,
         };
         let bundle = Bundle::new_at(out.clone());
 
@@ -134,12 +156,37 @@ mod tests {
         cleanup(out);
     }
 
+    #[test]
+    fn it_writes_the_bundle_sourcemap_past_the_wasm_prelude() {
+        let out = create_temp_dir("it_writes_the_bundle_sourcemap_past_the_wasm_prelude");
+        let wranglerjs_output = WranglerjsOutput {
+            errors: vec![],
+            script: "foo".to_string(),
+            wasm: Some("abc".to_string()),
+            sourcemap: Some(r#"{"version":3,"mappings":"AAAA"}"#.to_string()),
+        };
+        let bundle = Bundle::new_at(out.clone());
+
+        bundle.write(&wranglerjs_output).unwrap();
+        let script = fs::read_to_string(bundle.script_path()).unwrap();
+        let sourcemap: serde_json::Value =
+            serde_json::from_slice(&fs::read(bundle.sourcemap_path()).unwrap()).unwrap();
+        let prelude_lines = script.matches('\n').count();
+        assert_eq!(
+            sourcemap["mappings"],
+            format!("{}AAAA", ";".repeat(prelude_lines))
+        );
+
+        cleanup(out);
+    }
+
     #[test]
     fn it_has_errors() {
         let wranglerjs_output = WranglerjsOutput {
             errors: vec!["a".to_string(), "b".to_string()],
             script: "".to_string(),
             wasm: None,
+            sourcemap: None,
         };
         assert!(wranglerjs_output.has_errors());
         assert!(wranglerjs_output.get_errors() == "a\nb");
@@ -187,8 +187,23 @@ fn setup_build(target: &Target) -> Result<(Command, PathBuf, Bundle)> {
 
     command.arg(format!("--wasm-binding={}", bundle.get_wasm_binding()));
 
+    if !target.rules.is_empty() {
+        command.arg(format!(
+            "--module-rules={}",
+            serde_json::to_string(&target.rules)?
+        ));
+    }
+
     let custom_webpack_config_path = match &target.webpack_config {
-        Some(webpack_config) => Some(PathBuf::from(&webpack_config)),
+        Some(webpack_config) => {
+            let config_path = PathBuf::from(&webpack_config);
+            anyhow::ensure!(
+                config_path.exists(),
+                "The webpack_config {} of your configuration file doesn't exist",
+                webpack_config
+            );
+            Some(config_path)
+        }
         None => {
             let config_path = PathBuf::from("webpack.config.js".to_string());
             if config_path.exists() {
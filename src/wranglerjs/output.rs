@@ -14,6 +14,9 @@ pub struct WranglerjsOutput {
     pub script: String,
     // Errors emitted by {wranglerjs}, if any
     pub errors: Vec<String>,
+    // The source map of the script, when webpack made a single one
+    #[serde(default)]
+    pub sourcemap: Option<String>,
 }
 
 impl WranglerjsOutput {
@@ -94,6 +97,7 @@ mod tests {
             errors: vec![],
             script: "abcdefg".to_string(),
             wasm: Some("123456".to_string()),
+            sourcemap: None,
         };
 
         assert_eq!(wranglerjs_output.project_size_bytes(), 21);
@@ -105,6 +109,7 @@ mod tests {
             errors: vec![],
             script: "abcdefg".to_string(),
             wasm: None,
+            sourcemap: None,
         };
 
         assert_eq!(wranglerjs_output.project_size_bytes(), 15);
@@ -172,16 +172,18 @@ fn it_builds_with_webpack_single_js_missing_package_main() {
 }
 
 #[test]
-fn it_fails_with_multiple_webpack_configs() {
+fn it_builds_with_multiple_webpack_configs() {
     let fixture = Fixture::new();
     fixture.scaffold_webpack();
+    fixture.create_file("a.js", "addEventListener('fetch', () => {});");
+    fixture.create_file("b.js", "addEventListener('scheduled', () => {});");
 
     fixture.create_file(
         "webpack.config.js",
         r#"
         module.exports = [
             { entry: "./a.js" },
-            { entry: "./b.js" }
+            async () => ({ entry: "./b.js", target: "webworker" })
         ]
     "#,
     );
@@ -189,7 +191,70 @@ fn it_fails_with_multiple_webpack_configs() {
     let wrangler_toml = WranglerToml::webpack_std_config("test-build-multiple-webpack-configs");
     fixture.create_wrangler_toml(wrangler_toml);
 
-    build_fails_with(&fixture, "Multiple webpack configurations are not supported. You can specify a different path for your webpack configuration file in wrangler.toml with the `webpack_config` field");
+    build_creates_assets(&fixture, vec!["script.js"]);
+
+    let out = fs::read_to_string(fixture.get_output_path().join("script.js")).unwrap();
+    assert!(out.contains("'fetch'") && out.contains("'scheduled'"));
+}
+
+#[test]
+fn it_builds_with_webpack_multiple_entries() {
+    let fixture = Fixture::new();
+    fixture.scaffold_webpack();
+    fixture.create_file("a.js", "addEventListener('fetch', () => {});");
+    fixture.create_file("b.js", "addEventListener('scheduled', () => {});");
+
+    fixture.create_file(
+        "webpack.config.js",
+        r#"
+        module.exports = { entry: { fetch: "./a.js", scheduled: "./b.js" } }
+    "#,
+    );
+
+    let wrangler_toml = WranglerToml::webpack_std_config("test-build-webpack-multiple-entries");
+    fixture.create_wrangler_toml(wrangler_toml);
+
+    build_creates_assets(&fixture, vec!["script.js"]);
+}
+
+#[test]
+fn it_fails_with_webpack_target_node_in_multiple_configs() {
+    let fixture = Fixture::new();
+    fixture.scaffold_webpack();
+
+    fixture.create_file(
+        "webpack.config.js",
+        r#"
+        module.exports = [
+            { entry: "./index.js" },
+            { entry: "./index.js", target: "node" }
+        ]
+    "#,
+    );
+
+    let wrangler_toml =
+        WranglerToml::webpack_std_config("test-build-fails-webpack-multiple-target-node");
+    fixture.create_wrangler_toml(wrangler_toml);
+
+    build_fails_with(
+        &fixture,
+        "Building a Cloudflare Worker with target \"node\" is not supported (configuration 1)",
+    );
+}
+
+#[test]
+fn it_fails_with_missing_webpack_config() {
+    let fixture = Fixture::new();
+    fixture.scaffold_webpack();
+
+    let mut wrangler_toml = WranglerToml::webpack_build("test-build-missing-webpack-config");
+    wrangler_toml.webpack_config = Some("custom.webpack.js");
+    fixture.create_wrangler_toml(wrangler_toml);
+
+    build_fails_with(
+        &fixture,
+        "The webpack_config custom.webpack.js of your configuration file doesn't exist",
+    );
 }
 
 #[test]
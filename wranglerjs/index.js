@@ -18,47 +18,90 @@ function filterByExtension(ext) {
   return (v) => new RegExp(`\\.${ext}$`).test(v);
 }
 
-(async function () {
-  const rawArgs = process.argv.slice(2);
-  const args = rawArgs.reduce((obj, e) => {
-    if (e.indexOf("--") === -1 && e.indexOf("=") === -1) {
-      throw error("malformed arguments");
+// The [[rules]] of wrangler.toml, as webpack rules. Text and Data files are
+// turned into modules exporting their content, as a String and an ArrayBuffer.
+function moduleRules(rules) {
+  const loader = join(__dirname, "module-loader.js");
+  return rules.map((rule) => {
+    const test = rule.globs.map(globToRegExp);
+    switch (rule.type) {
+      case "Text":
+        return {
+          test,
+          type: "javascript/auto",
+          use: [{ loader, options: { as: "text" } }],
+        };
+      case "Data":
+        return {
+          test,
+          type: "javascript/auto",
+          use: [{ loader, options: { as: "data" } }],
+        };
+      case "CompiledWasm":
+        return { test, type: "webassembly/experimental" };
+      case "ESModule":
+        return { test, type: "javascript/esm" };
+      default:
+        return { test, type: "javascript/auto" };
     }
+  });
+}
 
-    const [name, value] = e.split("=");
-    const normalizedName = name.replace("--", "");
-    obj[normalizedName] = value;
-    return obj;
-  }, {});
-
-  let config;
-  if (args["no-webpack-config"] === "1") {
-    config = { entry: args["use-entry"] };
-  } else {
-    config = require(join(process.cwd(), args["webpack-config"]));
+// Globs match the whole path, with `**/` matching any number of directories
+// and `*` anything but a separator
+function globToRegExp(glob) {
+  let source = "";
+  for (let i = 0; i < glob.length; i++) {
+    const c = glob[i];
+    if (glob.startsWith("**/", i)) {
+      source += "(?:.*/)?";
+      i += 2;
+    } else if (c === "*") {
+      source += "[^/]*";
+    } else if (c === "?") {
+      source += "[^/]";
+    } else {
+      source += c.replace(/[.+^${}()|[\]\\]/g, "\\$&");
+    }
   }
+  return new RegExp("(?:^|/)" + source + "$");
+}
 
-  // Check if the config is a function and await it either way in
-  // case the result is a Promise
-  config = await (typeof config === "function" ? config({}) : config);
-
-  if (Array.isArray(config)) {
-    throw error(
-      "Multiple webpack configurations are not supported. You can specify a different path for your webpack configuration file in wrangler.toml with the `webpack_config` field\n" +
-        "Please make sure that your webpack configuration exports an Object, Promise of an Object, Function that returns an object, or a Function that returns a Promise of an Object"
-    );
+function entryCount(config) {
+  const entry = config.entry;
+  if (typeof entry === "object" && !Array.isArray(entry)) {
+    return Object.keys(entry).length;
   }
+  return 1;
+}
 
+// Targets the worker runtime and names the output the way it expects. When
+// there is more than one entry, each is named after its entry, with the index
+// of its configuration in front when the configuration exports an Array.
+function prepareConfig(config, index, count) {
+  const which = count === 1 ? "" : " (configuration " + index + ")";
   if (config.target !== undefined && config.target !== "webworker") {
     throw error(
       "Building a Cloudflare Worker with target " +
         JSON.stringify(config.target) +
-        " is not supported. Wrangler will set webworker by default, please remove " +
+        " is not supported" +
+        which +
+        ". Wrangler will set webworker by default, please remove " +
         "the `target` key in your webpack configuration."
     );
   }
   config.target = "webworker";
 
+  let filename = WEBPACK_OUTPUT_FILENAME;
+  if (count > 1 || entryCount(config) > 1) {
+    filename =
+      (count > 1 ? index + "." : "") + "[name]." + WEBPACK_OUTPUT_FILENAME;
+  }
+  const sourceMapFilename =
+    filename === WEBPACK_OUTPUT_FILENAME
+      ? WEBPACK_OUTPUT_SOURCEMAPFILENAME
+      : "[file].map";
+
   // The worker runtime will set the name of the script to `worker.js`,
   // regardless of what's specified in the sourcemap.
   // We can tell webpack to name the generated worker by configuring the output.
@@ -70,60 +113,137 @@ function filterByExtension(ext) {
   }
   if (
     config.output.filename !== undefined &&
-    config.output.filename !== WEBPACK_OUTPUT_FILENAME
+    config.output.filename !== filename
   ) {
     warn(
       "webpack's output filename is being renamed to",
-      WEBPACK_OUTPUT_FILENAME,
+      filename,
       "because of requirements from the Workers runtime"
     );
   }
   if (
     config.output.sourceMapFilename !== undefined &&
-    config.output.sourceMapFilename !== WEBPACK_OUTPUT_SOURCEMAPFILENAME
+    config.output.sourceMapFilename !== sourceMapFilename
   ) {
     warn(
       "webpack's output sourcemap filename is being renamed to",
-      WEBPACK_OUTPUT_SOURCEMAPFILENAME,
+      sourceMapFilename,
       "because of requirements from the Workers runtime"
     );
   }
-  config.output.filename = WEBPACK_OUTPUT_FILENAME;
-  config.output.sourceMapFilename = WEBPACK_OUTPUT_SOURCEMAPFILENAME;
+  config.output.filename = filename;
+  config.output.sourceMapFilename = sourceMapFilename;
+}
+
+(async function () {
+  const rawArgs = process.argv.slice(2);
+  const args = rawArgs.reduce((obj, e) => {
+    if (e.indexOf("--") === -1 && e.indexOf("=") === -1) {
+      throw error("malformed arguments");
+    }
+
+    // values like --module-rules can contain = themselves
+    const split = e.indexOf("=");
+    const name = split === -1 ? e : e.slice(0, split);
+    const value = split === -1 ? undefined : e.slice(split + 1);
+    const normalizedName = name.replace("--", "");
+    obj[normalizedName] = value;
+    return obj;
+  }, {});
+
+  let config;
+  if (args["no-webpack-config"] === "1") {
+    // source maps let `wrangler tail --symbolicate` point at the sources
+    config = { entry: args["use-entry"], devtool: "source-map" };
+  } else {
+    try {
+      config = require(join(process.cwd(), args["webpack-config"]));
+    } catch (e) {
+      throw error(
+        "could not load the webpack configuration " +
+          args["webpack-config"] +
+          ":\n" +
+          (e.stack || e)
+      );
+    }
+  }
+
+  // The configuration can be an Object, a Function or a Promise of one, or an
+  // Array of them, to build several entries into the same worker
+  config = await (typeof config === "function" ? config({}) : config);
+  const configs = await Promise.all(
+    (Array.isArray(config) ? config : [config]).map((c) =>
+      typeof c === "function" ? c({}) : c
+    )
+  );
+  if (configs.length === 0) {
+    throw error("your webpack configuration exports an empty Array");
+  }
+  configs.forEach((c, index) => prepareConfig(c, index, configs.length));
+  if (args["module-rules"] !== undefined) {
+    const rules = moduleRules(JSON.parse(args["module-rules"]));
+    for (const c of configs) {
+      c.module = c.module || {};
+      c.module.rules = rules.concat(c.module.rules || []);
+    }
+  }
 
-  const compiler = webpack(config);
-  const fullConfig = compiler.options;
+  const compiler = webpack(configs.length === 1 ? configs[0] : configs);
+  const fullConfig = Array.isArray(compiler.compilers)
+    ? compiler.compilers[0].options
+    : compiler.options;
 
   let lastHash = "";
   const compilerCallback = (err, stats) => {
+    // webpack's own errors, like an invalid configuration, stop the build
     if (err) {
-      throw err;
+      console.error(err.stack || err);
+      if (err.details) {
+        console.error(err.details);
+      }
+      process.exit(1);
     }
 
     if (stats.hash !== lastHash) {
-      const assets = stats.compilation.assets;
-      const jsonStats = stats.toJson();
+      const compilations = (stats.stats || [stats]).map((s) => s.compilation);
       const bundle = {
         wasm: null,
         script: "",
-        errors: jsonStats.errors,
+        errors: stats.toJson().errors,
       };
 
-      const wasmModuleAsset = Object.keys(assets).find(
-        filterByExtension("wasm")
-      );
-      const jsAssets = Object.keys(assets).filter(filterByExtension("js"));
-      const hasWasmModule = wasmModuleAsset !== undefined;
-
-      bundle.script = jsAssets.reduce((acc, k) => {
-        const asset = assets[k];
-        return acc + asset.source();
-      }, "");
+      const wasmModules = [];
+      const scripts = [];
+      const sourceMaps = [];
+      for (const compilation of compilations) {
+        const assets = compilation.assets;
+        const names = Object.keys(assets);
+        for (const name of names.filter(filterByExtension("js"))) {
+          bundle.script += assets[name].source();
+          scripts.push(name);
+        }
+        for (const name of names.filter(filterByExtension("map"))) {
+          sourceMaps.push(assets[name].source().toString());
+        }
+        for (const name of names.filter(filterByExtension("wasm"))) {
+          wasmModules.push(assets[name]);
+        }
+      }
 
-      if (hasWasmModule === true) {
-        bundle.wasm = Buffer.from(assets[wasmModuleAsset].source()).toString(
-          "base64"
+      if (wasmModules.length > 1) {
+        bundle.errors.push(
+          "A worker can only have one WebAssembly module, but your webpack " +
+            "configuration builds " +
+            wasmModules.length
         );
+      } else if (wasmModules.length === 1) {
+        bundle.wasm = Buffer.from(wasmModules[0].source()).toString("base64");
+      }
+
+      // the scripts are concatenated, so a single map only holds for a single
+      // script
+      if (scripts.length === 1 && sourceMaps.length === 1) {
+        bundle.sourcemap = sourceMaps[0];
       }
 
       fs.writeFileSync(args["output-file"], JSON.stringify(bundle));
@@ -0,0 +1,20 @@
+// Turns a file matched by a Text or Data rule of wrangler.toml into a module
+// exporting its content.
+module.exports = function (content) {
+  const as = (this.query && this.query.as) || "text";
+  if (as === "data") {
+    const base64 = JSON.stringify(content.toString("base64"));
+    return (
+      "const binary = atob(" +
+      base64 +
+      ");\n" +
+      "const bytes = new Uint8Array(binary.length);\n" +
+      "for (let i = 0; i < binary.length; i++) bytes[i] = binary.charCodeAt(i);\n" +
+      "module.exports = bytes.buffer;\n"
+    );
+  }
+  return "module.exports = " + JSON.stringify(content.toString("utf8")) + ";\n";
+};
+
+// the content is a Buffer, so that binary files survive
+module.exports.raw = true;